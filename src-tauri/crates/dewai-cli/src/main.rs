@@ -0,0 +1,346 @@
+// DewAI のコマンドラインツール
+// GUI を起動せずに議論を進めて Markdown / JSON で出力する（CI・サーバーでの一括実行や、プロンプトの変更の比較用）
+//
+//   dewai-cli run --topic "テーマ" --rounds 3 [--model gemma3:4b] [--participants profiles.json]
+//                 [--repetitions N] [--format md|json] [--output 出力先] [--no-summary] [--host 127.0.0.1:11434]
+//                 [--settings 設定.json] [--facilitator]
+//   dewai-cli models [--host ...]
+//
+// 手番の進め方（生成に失敗した手番のやり直しと飛ばし・司会役の介入・後処理・安全ポリシー）はアプリの自動進行と共通
+// JSON 出力はアプリの JSON エクスポートと同じ形式（dewai-session）で、そのままアプリに取り込める
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use dewai_core::{
+    engine::{self, DiscussionConfig, RunEvent, RunSettings, DEFAULT_GENERATED_PARTICIPANTS, MAX_TURN_ATTEMPTS},
+    ollama::Client,
+    prompts::PromptStyle,
+    session::AiParticipant,
+};
+
+const DEFAULT_MODEL: &str = "gemma3:4b";
+const DEFAULT_ROUNDS: u32 = 3;
+// アプリの自動進行・シミュレーションと同じ上限
+const MAX_ROUNDS: u32 = 50;
+const MAX_REPETITIONS: u32 = 200;
+
+const USAGE: &str = "使い方:
+  dewai-cli run --topic <テーマ> [--rounds <N>] [--model <モデル>] [--participants <JSONファイル>]
+                [--repetitions <N>] [--format md|json] [--output <ファイル>] [--no-summary] [--host <接続先>]
+                [--settings <JSONファイル>] [--facilitator]
+  dewai-cli models [--host <接続先>]
+
+  --participants  AI参加者の配列（[{\"name\": ..., \"role\": ..., \"description\": ...}]）。省略時はテーマから生成する
+  --repetitions   同じ条件で繰り返す回数。2回以上なら --output に連番を付けて保存する
+  --host          Ollama の接続先（省略時は環境変数 OLLAMA_HOST、なければ http://localhost:11434）
+  --settings      アプリ設定と同じ形式の JSON。facilitator・postprocess・safety を使う（省略時は既定値）
+  --facilitator   司会役を有効にする（議論の堂々巡り・独占に介入する）";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Markdown,
+    Json,
+}
+
+impl Format {
+    fn extension(self) -> &'static str {
+        match self {
+            Format::Markdown => "md",
+            Format::Json => "json",
+        }
+    }
+}
+
+/// run の引数
+#[derive(Debug)]
+struct RunArgs {
+    topic: String,
+    rounds: u32,
+    model: String,
+    participants: Option<PathBuf>,
+    repetitions: u32,
+    format: Format,
+    output: Option<PathBuf>,
+    summarize: bool,
+    host: Option<String>,
+    settings: Option<PathBuf>,
+    facilitator: bool,
+}
+
+#[derive(Debug)]
+enum Command {
+    Run(RunArgs),
+    Models { host: Option<String> },
+    Help,
+}
+
+fn parse_number(flag: &str, value: &str, max: u32) -> Result<u32, String> {
+    match value.parse::<u32>() {
+        Ok(n) if (1..=max).contains(&n) => Ok(n),
+        _ => Err(format!("{} は1〜{}で指定してください: {}", flag, max, value)),
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Command, String> {
+    let Some((command, rest)) = args.split_first() else {
+        return Ok(Command::Help);
+    };
+    let mut run = RunArgs {
+        topic: String::new(),
+        rounds: DEFAULT_ROUNDS,
+        model: DEFAULT_MODEL.to_string(),
+        participants: None,
+        repetitions: 1,
+        format: Format::Markdown,
+        output: None,
+        summarize: true,
+        host: None,
+        settings: None,
+        facilitator: false,
+    };
+    let mut iter = rest.iter();
+    while let Some(flag) = iter.next() {
+        if matches!(flag.as_str(), "-h" | "--help") {
+            return Ok(Command::Help);
+        }
+        if flag == "--no-summary" {
+            run.summarize = false;
+            continue;
+        }
+        if flag == "--facilitator" {
+            run.facilitator = true;
+            continue;
+        }
+        let value = iter.next().ok_or_else(|| format!("{} の値がありません", flag))?;
+        match flag.as_str() {
+            "--topic" => run.topic = value.trim().to_string(),
+            "--rounds" => run.rounds = parse_number(flag, value, MAX_ROUNDS)?,
+            "--model" => run.model = value.clone(),
+            "--participants" => run.participants = Some(PathBuf::from(value)),
+            "--repetitions" => run.repetitions = parse_number(flag, value, MAX_REPETITIONS)?,
+            "--format" => {
+                run.format = match value.as_str() {
+                    "md" | "markdown" => Format::Markdown,
+                    "json" => Format::Json,
+                    _ => return Err(format!("--format は md か json で指定してください: {}", value)),
+                }
+            }
+            "--output" => run.output = Some(PathBuf::from(value)),
+            "--host" => run.host = Some(value.clone()),
+            "--settings" => run.settings = Some(PathBuf::from(value)),
+            _ => return Err(format!("不明なオプション: {}", flag)),
+        }
+    }
+    match command.as_str() {
+        "run" if run.topic.is_empty() => Err("--topic を指定してください".into()),
+        "run" => Ok(Command::Run(run)),
+        "models" => Ok(Command::Models { host: run.host }),
+        "help" | "-h" | "--help" => Ok(Command::Help),
+        _ => Err(format!("不明なコマンド: {}", command)),
+    }
+}
+
+fn client_for(host: Option<&str>) -> Client {
+    host.map(Client::new).unwrap_or_else(Client::from_env)
+}
+
+/// 繰り返し実行時の出力先（"out.md" → "out-2.md"）
+fn numbered_path(path: &std::path::Path, index: u32, format: Format) -> PathBuf {
+    let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_else(|| "dewai".into());
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().into_owned())
+        .unwrap_or_else(|| format.extension().to_string());
+    path.with_file_name(format!("{}-{}.{}", stem, index, extension))
+}
+
+/// 司会役・後処理・安全ポリシーの設定（アプリ設定の JSON のうち使う項目だけ読む）
+fn load_settings(path: Option<&std::path::Path>, facilitator: bool) -> Result<RunSettings, String> {
+    let mut settings: RunSettings = match path {
+        Some(path) => {
+            let text = std::fs::read_to_string(path)
+                .map_err(|e| format!("設定ファイルの読み込み失敗 ({}): {}", path.display(), e))?;
+            serde_json::from_str(&text).map_err(|e| format!("設定ファイルの形式が正しくありません: {}", e))?
+        }
+        None => RunSettings::default(),
+    };
+    if facilitator {
+        settings.facilitator.enabled = true;
+    }
+    settings.validated()
+}
+
+/// 進み具合を標準エラーに出す
+fn report(event: RunEvent<'_>) {
+    match event {
+        RunEvent::Message(m) => eprintln!("{}: {}", m.speaker, m.message),
+        RunEvent::Intervention(i) => eprintln!("{}: {}", i.message.speaker, i.message.message),
+        RunEvent::TurnFailed(f) if f.skipped => {
+            eprintln!("{} の発言を飛ばします（{}回失敗）: {}", f.speaker, f.attempt, f.error)
+        }
+        RunEvent::TurnFailed(f) => {
+            eprintln!("{} の発言の生成に失敗（{}/{}回目）: {}", f.speaker, f.attempt, MAX_TURN_ATTEMPTS, f.error)
+        }
+        RunEvent::FacilitatorFailed(e) => eprintln!("司会者の介入に失敗: {}", e),
+    }
+}
+
+async fn run(args: RunArgs) -> Result<(), String> {
+    let settings = load_settings(args.settings.as_deref(), args.facilitator)?;
+    let client = client_for(args.host.as_deref());
+    let participants: Vec<AiParticipant> = match &args.participants {
+        Some(path) => {
+            let text = std::fs::read_to_string(path)
+                .map_err(|e| format!("参加者ファイルの読み込み失敗 ({}): {}", path.display(), e))?;
+            serde_json::from_str(&text).map_err(|e| format!("参加者ファイルの形式が正しくありません: {}", e))?
+        }
+        None => {
+            eprintln!("参加者を生成中: {}", args.topic);
+            engine::generate_participants(&client, &args.topic, &args.model, DEFAULT_GENERATED_PARTICIPANTS).await?
+        }
+    };
+    let participants: Vec<AiParticipant> = participants.into_iter().filter(|p| !p.name.trim().is_empty()).collect();
+    let config = DiscussionConfig {
+        topic: args.topic.clone(),
+        model: args.model.clone(),
+        participants,
+        rounds: args.rounds,
+        summarize: args.summarize,
+        style: PromptStyle::default(),
+        settings,
+    };
+
+    for repetition in 1..=args.repetitions {
+        if args.repetitions > 1 {
+            eprintln!("{}/{}回目の議論", repetition, args.repetitions);
+        }
+        // 進み具合は標準エラーに出し、標準出力は結果だけにする
+        let archive = engine::run(&client, &config, report).await?;
+        let text = match args.format {
+            Format::Markdown => archive.to_markdown(),
+            Format::Json => serde_json::to_string_pretty(&archive).map_err(|e| format!("JSON への変換失敗: {}", e))?,
+        };
+        match &args.output {
+            Some(path) => {
+                let path =
+                    if args.repetitions > 1 { numbered_path(path, repetition, args.format) } else { path.clone() };
+                std::fs::write(&path, text).map_err(|e| format!("書き込み失敗 ({}): {}", path.display(), e))?;
+                eprintln!("保存しました: {}", path.display());
+            }
+            None => println!("{}", text),
+        }
+    }
+    Ok(())
+}
+
+async fn models(host: Option<String>) -> Result<(), String> {
+    for model in client_for(host.as_deref()).list_models().await? {
+        println!("{}", model);
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match parse_args(&args) {
+        Ok(Command::Run(args)) => run(args).await,
+        Ok(Command::Models { host }) => models(host).await,
+        Ok(Command::Help) => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        Err(e) => Err(format!("{}\n\n{}", e, USAGE)),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("エラー: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn run_flags_are_parsed() {
+        let parsed = parse_args(&args(&[
+            "run", "--topic", " 週休3日制 ", "--rounds", "5", "--model", "gemma3:1b", "--participants", "p.json",
+            "--repetitions", "2", "--format", "json", "--output", "out.json", "--no-summary", "--host", "127.0.0.1:11434",
+            "--settings", "settings.json", "--facilitator",
+        ]));
+        let Ok(Command::Run(run)) = parsed else { panic!("run として解釈されませんでした: {:?}", parsed) };
+        assert_eq!(run.topic, "週休3日制");
+        assert_eq!(run.rounds, 5);
+        assert_eq!(run.model, "gemma3:1b");
+        assert_eq!(run.participants, Some(PathBuf::from("p.json")));
+        assert_eq!(run.repetitions, 2);
+        assert_eq!(run.format, Format::Json);
+        assert_eq!(run.output, Some(PathBuf::from("out.json")));
+        assert!(!run.summarize);
+        assert_eq!(run.host.as_deref(), Some("127.0.0.1:11434"));
+        assert_eq!(run.settings, Some(PathBuf::from("settings.json")));
+        assert!(run.facilitator);
+    }
+
+    #[test]
+    fn run_defaults_apply_when_only_the_topic_is_given() {
+        let Ok(Command::Run(run)) = parse_args(&args(&["run", "--topic", "テーマ"])) else { panic!() };
+        assert_eq!(run.rounds, DEFAULT_ROUNDS);
+        assert_eq!(run.model, DEFAULT_MODEL);
+        assert_eq!(run.repetitions, 1);
+        assert_eq!(run.format, Format::Markdown);
+        assert!(run.summarize);
+        assert!(run.settings.is_none() && !run.facilitator);
+    }
+
+    #[test]
+    fn run_without_topic_is_rejected() {
+        assert_eq!(parse_args(&args(&["run", "--rounds", "2"])).unwrap_err(), "--topic を指定してください");
+        // 空白だけのテーマも未指定とみなす
+        assert!(parse_args(&args(&["run", "--topic", "  "])).is_err());
+    }
+
+    #[test]
+    fn rounds_must_be_a_number_in_range() {
+        for rounds in ["three", "0", "-1", "51"] {
+            let err = parse_args(&args(&["run", "--topic", "テーマ", "--rounds", rounds])).unwrap_err();
+            assert!(err.starts_with("--rounds は1〜50で指定してください"), "{}", err);
+        }
+        assert_eq!(parse_args(&args(&["run", "--topic", "テーマ", "--rounds"])).unwrap_err(), "--rounds の値がありません");
+    }
+
+    #[test]
+    fn unknown_flags_and_commands_are_rejected() {
+        assert!(parse_args(&args(&["run", "--topic", "テーマ", "--verbose", "1"])).is_err());
+        assert!(parse_args(&args(&["serve"])).is_err());
+        assert!(matches!(parse_args(&[]), Ok(Command::Help)));
+    }
+
+    #[test]
+    fn settings_are_read_from_the_app_settings_json() {
+        // アプリ設定の JSON には CLI が使わない項目も含まれる
+        let path = std::env::temp_dir().join(format!("dewai-cli-settings-{}.json", std::process::id()));
+        let json = r#"{"autoSummary": false, "postprocess": {"maxChars": 5}, "facilitator": {"name": "  進行役 "}}"#;
+        std::fs::write(&path, json).unwrap();
+        let settings = load_settings(Some(&path), true);
+        std::fs::remove_file(&path).unwrap();
+        let settings = settings.unwrap();
+        assert!(settings.facilitator.enabled);
+        assert_eq!(settings.facilitator.name, "進行役");
+        // 範囲外の値はアプリと同じく補正する
+        assert_eq!(settings.postprocess.max_chars, Some(20));
+        assert!(settings.safety.enabled);
+
+        let defaults = load_settings(None, false).unwrap();
+        assert!(!defaults.facilitator.enabled);
+        assert!(load_settings(Some(std::path::Path::new("存在しない.json")), false).is_err());
+    }
+}
@@ -0,0 +1,112 @@
+// 議論の JSON アーカイブ（アプリの JSON エクスポート・import_session と CLI の出力で共通の形式）
+// 別の環境へ議論を移すための形式で、識別子と版を持ち、読めない版のファイルは取り込まない
+use serde::{Deserialize, Serialize};
+
+use crate::session::{ParticipantsData, StoredMessage};
+
+// JSON エクスポートの識別子と版
+pub const ARCHIVE_FORMAT: &str = "dewai-session";
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// JSON エクスポートの中身（import_session で読み込む）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionArchive {
+    /// 常に "dewai-session"
+    pub format: String,
+    pub version: u32,
+    /// 書き出し元でのセッションID（取り込み時は新しいIDを振る）
+    #[serde(default)]
+    pub session_id: Option<i64>,
+    pub topic: String,
+    #[serde(default)]
+    pub model: String,
+    #[serde(default)]
+    pub participants: ParticipantsData,
+    pub messages: Vec<StoredMessage>,
+    #[serde(default)]
+    pub summary: Option<String>,
+    #[serde(default)]
+    pub created_at: Option<String>,
+    #[serde(default)]
+    pub exported_at: Option<String>,
+}
+
+impl SessionArchive {
+    /// 書き出す内容からアーカイブを作る（exported_at は呼び出し側で付ける）
+    pub fn new(
+        session_id: Option<i64>,
+        topic: String,
+        model: String,
+        participants: ParticipantsData,
+        messages: Vec<StoredMessage>,
+        summary: Option<String>,
+        created_at: Option<String>,
+    ) -> Self {
+        Self {
+            format: ARCHIVE_FORMAT.to_string(),
+            version: ARCHIVE_VERSION,
+            session_id,
+            topic,
+            model,
+            participants,
+            messages,
+            summary,
+            created_at,
+            exported_at: None,
+        }
+    }
+
+    /// 取り込める内容か確認
+    pub fn validate(&self) -> Result<(), String> {
+        if self.format != ARCHIVE_FORMAT {
+            return Err(format!("DewAI のセッションファイルではありません（format='{}'）", self.format));
+        }
+        if self.version == 0 || self.version > ARCHIVE_VERSION {
+            return Err(format!("対応していない版です: version={}（対応: {}）", self.version, ARCHIVE_VERSION));
+        }
+        if self.topic.trim().is_empty() {
+            return Err("テーマが空です".into());
+        }
+        if let Some(i) = self.messages.iter().position(|m| m.speaker.trim().is_empty()) {
+            return Err(format!("{}件目の発言に発言者がありません", i + 1));
+        }
+        if self.participants.ai_data.iter().any(|p| p.name.trim().is_empty()) {
+            return Err("名前のないAI参加者があります".into());
+        }
+        Ok(())
+    }
+
+    /// 読むための Markdown（参加者・要約・発言録。CLI の --format md で使う）
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!("# {}\n\n", self.topic);
+        if !self.model.is_empty() {
+            out.push_str(&format!("- モデル: {}\n", self.model));
+        }
+        if let Some(created_at) = &self.created_at {
+            out.push_str(&format!("- 開始日時: {}\n", created_at));
+        }
+
+        out.push_str("\n## 参加者\n\n");
+        for participant in &self.participants.ai_data {
+            if participant.role.is_empty() {
+                out.push_str(&format!("- {}\n", participant.name));
+            } else {
+                out.push_str(&format!("- {}（{}）\n", participant.name, participant.role));
+            }
+        }
+
+        if let Some(summary) = &self.summary {
+            out.push_str(&format!("\n## 要約\n\n{}\n", summary.trim()));
+        }
+
+        out.push_str("\n## 発言録\n");
+        for m in &self.messages {
+            // 改行を含む発言もリスト項目内に収める
+            let body = m.message.trim().lines().collect::<Vec<_>>().join("  \n  ");
+            out.push_str(&format!("\n- **{}**: {}", m.speaker, body));
+        }
+        out.push('\n');
+        out
+    }
+}
@@ -0,0 +1,702 @@
+// 議論の進行（アプリの自動進行と dewai-cli で共通）
+// drive が手番の進め方（話者の選択 → 司会者の介入 → 生成 → 失敗時のやり直しと飛ばし → 後処理 → 保存）を持ち、
+// 保存先・通知・一時停止や持ち時間など、進行する側ごとの処理は TurnHooks で差し込む
+// run は DB やイベントを使わない進行（dewai-cli 用）で、全員が1回ずつ発言するラウンドを指定回数くり返し、
+// 最後に要約して SessionArchive にまとめる
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{
+    archive::SessionArchive,
+    facilitator::{self, Discussion, FacilitatorSettings, Intervention},
+    generation::{GenerationOptions, GenerationResult, OutputFormat},
+    llm_json,
+    ollama::Client,
+    postprocess::{self, PostprocessSettings},
+    prompts::{self, PromptStyle},
+    safety::{SafetyPolicy, SafetyRules},
+    session::{format_history, AiParticipant, ParticipantsData, StoredMessage},
+};
+
+/// 議論の進行に使う生成（通常は Ollama の Client。テストでは決まった応答を返すものに差し替える）
+#[async_trait]
+pub trait Generator: Sync {
+    async fn generate(&self, model: &str, prompt: &str, options: &GenerationOptions) -> Result<GenerationResult, String>;
+}
+
+#[async_trait]
+impl Generator for Client {
+    async fn generate(&self, model: &str, prompt: &str, options: &GenerationOptions) -> Result<GenerationResult, String> {
+        Client::generate(self, model, prompt, options).await
+    }
+}
+
+// 参加者未指定時に自動生成する人数
+pub const DEFAULT_GENERATED_PARTICIPANTS: usize = 3;
+
+// 1つの手番の生成を試す回数（これだけ続けて失敗したらその参加者を飛ばす）
+pub const MAX_TURN_ATTEMPTS: u32 = 3;
+
+// run で生成に失敗した手番をやり直すまでの間隔（失敗の回数に比例して延ばす）
+const RETRY_BACKOFF: Duration = Duration::from_secs(2);
+
+/// 進行の位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TurnPosition {
+    pub total_rounds: u32,
+    /// 1始まりの現在ラウンド
+    pub current_round: u32,
+    /// 現在ラウンドの発言順で次に話す位置
+    pub next_speaker: usize,
+}
+
+impl TurnPosition {
+    pub fn new(total_rounds: u32) -> Self {
+        Self { total_rounds, current_round: 1, next_speaker: 0 }
+    }
+
+    /// 次のラウンドの最初の手番へ進める
+    pub fn next_round(&mut self) {
+        self.current_round += 1;
+        self.next_speaker = 0;
+    }
+}
+
+/// 進行の集計
+#[derive(Debug, Clone, Default)]
+pub struct RoundsStats {
+    pub generated: usize,
+    pub total_chars: usize,
+    /// LLM 呼び出しに要した時間の合計
+    pub generation_ms: u128,
+    /// 停止の指示で途中終了したか
+    pub stopped: bool,
+}
+
+/// 手番の前の確認の結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Checkpoint {
+    Continue,
+    Stop,
+    /// ラウンドの持ち時間を使い切った
+    TimeUp,
+}
+
+/// ラウンドの終わり方
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoundEnd {
+    /// 全員が発言した
+    Completed,
+    /// 持ち時間を使い切った
+    TimeUp,
+}
+
+/// 手番の生成の結果
+pub enum Generation {
+    Done(Result<GenerationResult, String>),
+    /// 停止の指示で打ち切った
+    Stopped,
+    /// 持ち時間切れで打ち切った
+    TimeUp,
+}
+
+/// 参加者より先に司会者が発言するか
+pub enum Facilitation {
+    Continue,
+    /// 司会者の判定中に停止の指示があった
+    Stop,
+    Intervened(Box<Intervention>),
+}
+
+/// 生成に失敗した手番
+#[derive(Debug, Clone, Copy)]
+pub struct TurnFailure<'a> {
+    pub round: u32,
+    pub speaker: &'a str,
+    /// 何回目の失敗か
+    pub attempt: u32,
+    pub error: &'a str,
+    /// やり直さずにこの参加者を飛ばしたか
+    pub skipped: bool,
+}
+
+/// 保存する参加者の発言（プロンプト・生成結果は生成の記録用）
+pub struct Reply<'a> {
+    pub participant: &'a AiParticipant,
+    pub prompt: &'a str,
+    pub options: &'a GenerationOptions,
+    pub generated: &'a GenerationResult,
+    pub message: StoredMessage,
+}
+
+/// 進行する側ごとの処理（アプリでは DB への保存・イベント通知・一時停止と持ち時間、run ではメモリへの記録だけ）
+#[async_trait]
+pub trait TurnHooks: Send {
+    /// 進行の位置（drive が手番ごとに進める）
+    fn position(&mut self) -> &mut TurnPosition;
+
+    /// 手番の前に呼ぶ（一時停止中ならここで再開・停止まで待つ）
+    async fn checkpoint(&mut self) -> Result<Checkpoint, String> {
+        Ok(Checkpoint::Continue)
+    }
+
+    /// 現在ラウンドの発言順
+    async fn speaking_order(&mut self) -> Result<Vec<AiParticipant>, String>;
+
+    /// ラウンドの最初の手番の前に呼ぶ
+    async fn round_started(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// ラウンドを終えた時に呼ぶ（この後 drive が次のラウンドへ進める）
+    async fn round_ended(&mut self, _end: RoundEnd) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// 発言順と別の話者を選ぶ（None なら発言順どおり）
+    async fn pick_speaker(&mut self, _order: &[AiParticipant]) -> Result<Option<AiParticipant>, String> {
+        Ok(None)
+    }
+
+    /// 手番の生成オプション（シード未指定なら drive が採番する）
+    fn options(&self) -> GenerationOptions;
+
+    async fn facilitate(&mut self, options: &GenerationOptions) -> Result<Facilitation, String>;
+
+    async fn prompt(&mut self, participant: &AiParticipant, options: &GenerationOptions) -> Result<String, String>;
+
+    async fn generate(&mut self, prompt: &str, options: &GenerationOptions) -> Generation;
+
+    /// 生成した発言を整える（後処理）
+    async fn finish_reply(&mut self, participant: &AiParticipant, text: &str) -> String;
+
+    async fn save_intervention(&mut self, intervention: Intervention) -> Result<(), String>;
+
+    /// 参加者の発言を保存する（位置はこの発言の次まで進めてある）
+    async fn save_reply(&mut self, reply: Reply<'_>) -> Result<(), String>;
+
+    /// 発言を保存せずに進めた位置を保存する（参加者を飛ばした時・持ち時間切れでラウンドを進めた時）
+    async fn save_position(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn turn_failed(&mut self, failure: &TurnFailure<'_>);
+
+    /// 同じ手番をやり直す前に待つ（待つ間に停止の指示があれば false）
+    async fn backoff(&mut self, attempt: u32) -> bool;
+}
+
+/// 生成失敗の数
+#[derive(Debug, Default)]
+struct TurnFailures {
+    /// 今の手番で続けて失敗した回数
+    attempts: u32,
+    /// 続けて飛ばした参加者の数
+    skipped_in_a_row: usize,
+}
+
+/// 持ち時間切れでラウンドを終え、次のラウンドへ進める
+async fn end_round_on_time_up(hooks: &mut impl TurnHooks) -> Result<(), String> {
+    hooks.round_ended(RoundEnd::TimeUp).await?;
+    hooks.position().next_round();
+    hooks.save_position().await
+}
+
+/// 全ラウンドを終えるか停止されるまで参加者に発言させ、集計を返す
+/// 1回の生成の失敗では進行全体を止めず、間を空けて同じ手番をやり直す。続けて失敗したらその参加者を飛ばす
+pub async fn drive(hooks: &mut impl TurnHooks) -> Result<RoundsStats, String> {
+    let mut stats = RoundsStats::default();
+    let mut failures = TurnFailures::default();
+    while hooks.position().current_round <= hooks.position().total_rounds {
+        match hooks.checkpoint().await? {
+            Checkpoint::Continue => {}
+            Checkpoint::Stop => {
+                stats.stopped = true;
+                break;
+            }
+            Checkpoint::TimeUp => {
+                end_round_on_time_up(hooks).await?;
+                continue;
+            }
+        }
+        let order = hooks.speaking_order().await?;
+        let next = hooks.position().next_speaker;
+        if next == 0 {
+            hooks.round_started().await?;
+        }
+        let suggested = if next < order.len() { hooks.pick_speaker(&order).await? } else { None };
+        let Some(participant) = suggested.or_else(|| order.get(next).cloned()) else {
+            // ラウンド終了（再開時に参加者が減っていた場合もここで次へ進む）
+            hooks.round_ended(RoundEnd::Completed).await?;
+            hooks.position().next_round();
+            continue;
+        };
+
+        let options = hooks.options().with_seed_assigned();
+        // 議論が堂々巡り・独占・脱線していれば、次の参加者より先に司会者が発言する
+        match hooks.facilitate(&options).await? {
+            Facilitation::Continue => {}
+            Facilitation::Stop => {
+                stats.stopped = true;
+                break;
+            }
+            Facilitation::Intervened(intervention) => {
+                hooks.save_intervention(*intervention).await?;
+                continue;
+            }
+        }
+        let prompt = hooks.prompt(&participant, &options).await?;
+        let started = Instant::now();
+        let generated = match hooks.generate(&prompt, &options).await {
+            Generation::Done(Ok(generated)) => generated,
+            Generation::Done(Err(error)) => {
+                failures.attempts += 1;
+                let skipped = failures.attempts >= MAX_TURN_ATTEMPTS;
+                let failure = TurnFailure {
+                    round: hooks.position().current_round,
+                    speaker: &participant.name,
+                    attempt: failures.attempts,
+                    error: &error,
+                    skipped,
+                };
+                hooks.turn_failed(&failure);
+                if !skipped {
+                    if !hooks.backoff(failures.attempts).await {
+                        stats.stopped = true;
+                        break;
+                    }
+                    continue;
+                }
+                failures.attempts = 0;
+                failures.skipped_in_a_row += 1;
+                // ラウンドの全員が続けて飛ばされたら、生成できない状態とみなして止める
+                if failures.skipped_in_a_row >= order.len() {
+                    return Err(error);
+                }
+                hooks.position().next_speaker += 1;
+                hooks.save_position().await?;
+                continue;
+            }
+            Generation::Stopped => {
+                stats.stopped = true;
+                break;
+            }
+            Generation::TimeUp => {
+                end_round_on_time_up(hooks).await?;
+                continue;
+            }
+        };
+        failures = TurnFailures::default();
+        stats.generation_ms += started.elapsed().as_millis();
+        let reply = hooks.finish_reply(&participant, &generated.text).await;
+        let message = StoredMessage {
+            speaker: participant.name.clone(),
+            message: reply.trim().to_string(),
+            is_user: false,
+            timestamp: now_timestamp(),
+            seed: options.seed,
+            generation: Some(generated.meta.clone()),
+            edited_at: None,
+            regenerated_at: None,
+        };
+        stats.total_chars += message.message.chars().count();
+        hooks.position().next_speaker += 1;
+        let reply =
+            Reply { participant: &participant, prompt: &prompt, options: &options, generated: &generated, message };
+        hooks.save_reply(reply).await?;
+        stats.generated += 1;
+    }
+    Ok(stats)
+}
+
+/// アプリ設定のうち GUI なしの進行でも使うもの（アプリ設定の JSON からそのまま読める）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RunSettings {
+    /// 司会役（既定では介入しない）
+    pub facilitator: FacilitatorSettings,
+    pub postprocess: PostprocessSettings,
+    pub safety: SafetyPolicy,
+}
+
+impl RunSettings {
+    /// 範囲外の値を補正し、安全ルールを検証する
+    pub fn validated(mut self) -> Result<Self, String> {
+        self.facilitator = self.facilitator.sanitized();
+        self.postprocess = self.postprocess.sanitized();
+        self.safety.validate()?;
+        Ok(self)
+    }
+}
+
+/// 議論の実行条件
+#[derive(Debug, Clone)]
+pub struct DiscussionConfig {
+    pub topic: String,
+    pub model: String,
+    pub participants: Vec<AiParticipant>,
+    /// 全員が1回ずつ発言するのを1ラウンドとした回数
+    pub rounds: u32,
+    /// 最後に要約するか
+    pub summarize: bool,
+    pub style: PromptStyle,
+    /// 司会役・後処理・安全ポリシー
+    pub settings: RunSettings,
+}
+
+/// 参加者プロフィール生成の出力形式（名前・役職・説明の配列）
+pub fn profiles_format() -> OutputFormat {
+    OutputFormat::Schema(json!({
+        "type": "array",
+        "items": {
+            "type": "object",
+            "properties": {
+                "name": { "type": "string" },
+                "role": { "type": "string" },
+                "description": { "type": "string" }
+            },
+            "required": ["name", "role", "description"]
+        }
+    }))
+}
+
+/// テーマから参加者プロフィールを生成
+pub async fn generate_participants(
+    client: &impl Generator,
+    topic: &str,
+    model: &str,
+    count: usize,
+) -> Result<Vec<AiParticipant>, String> {
+    let prompt = prompts::build_ai_profiles_prompt(topic, count, "", &Default::default(), prompts::default_language());
+    let options = GenerationOptions::default().with_format(profiles_format());
+    let raw = client.generate(model, &prompt, &options).await?.text;
+    let profiles: Vec<AiParticipant> = llm_json::parse_llm_json(&raw)?;
+    let profiles: Vec<AiParticipant> = profiles.into_iter().filter(|p| !p.name.trim().is_empty()).collect();
+    if profiles.is_empty() {
+        return Err("参加者プロフィールを生成できませんでした".into());
+    }
+    Ok(profiles)
+}
+
+/// 発言時刻（アプリの発言と同じ RFC 3339 形式）
+pub fn now_timestamp() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
+
+/// 安全ポリシーを入力と出力に適用する生成（アプリの call_ollama_generate_full と同じ）
+struct Guarded<'a, G> {
+    inner: &'a G,
+    rules: SafetyRules,
+}
+
+#[async_trait]
+impl<G: Generator> Generator for Guarded<'_, G> {
+    async fn generate(&self, model: &str, prompt: &str, options: &GenerationOptions) -> Result<GenerationResult, String> {
+        let prompt = self.rules.enforce(prompt, |_, _| {})?;
+        let mut result = self.inner.generate(model, &prompt, options).await?;
+        result.text = self.rules.enforce(&result.text, |_, _| {})?;
+        Ok(result)
+    }
+}
+
+/// run の進行中の出来事
+pub enum RunEvent<'a> {
+    /// 参加者の発言
+    Message(&'a StoredMessage),
+    /// 司会者の発言
+    Intervention(&'a Intervention),
+    TurnFailed(&'a TurnFailure<'a>),
+    /// 司会者の判定・生成に失敗した（進行は続ける）
+    FacilitatorFailed(&'a str),
+}
+
+/// run の進行（発言はメモリに積み、出来事を on_event に渡す）
+struct Transcript<'a, G, F> {
+    generator: Guarded<'a, G>,
+    config: &'a DiscussionConfig,
+    participants: ParticipantsData,
+    messages: Vec<StoredMessage>,
+    position: TurnPosition,
+    on_event: F,
+}
+
+#[async_trait]
+impl<G, F> TurnHooks for Transcript<'_, G, F>
+where
+    G: Generator,
+    F: FnMut(RunEvent<'_>) + Send,
+{
+    fn position(&mut self) -> &mut TurnPosition {
+        &mut self.position
+    }
+
+    async fn speaking_order(&mut self) -> Result<Vec<AiParticipant>, String> {
+        Ok(self.config.participants.clone())
+    }
+
+    fn options(&self) -> GenerationOptions {
+        GenerationOptions::default()
+    }
+
+    async fn facilitate(&mut self, options: &GenerationOptions) -> Result<Facilitation, String> {
+        let settings = &self.config.settings;
+        if !settings.facilitator.enabled {
+            return Ok(Facilitation::Continue);
+        }
+        let discussion = Discussion {
+            topic: &self.config.topic,
+            model: &self.config.model,
+            participants: &self.participants,
+            messages: &self.messages,
+        };
+        let intervening = facilitator::intervene(
+            &self.generator,
+            &settings.facilitator,
+            &settings.postprocess,
+            &discussion,
+            None,
+            &self.config.style,
+            options,
+        );
+        match intervening.await {
+            Ok(Some(intervention)) => Ok(Facilitation::Intervened(Box::new(intervention))),
+            Ok(None) => Ok(Facilitation::Continue),
+            Err(e) => {
+                (self.on_event)(RunEvent::FacilitatorFailed(&e));
+                Ok(Facilitation::Continue)
+            }
+        }
+    }
+
+    async fn prompt(&mut self, participant: &AiParticipant, _options: &GenerationOptions) -> Result<String, String> {
+        Ok(prompts::build_ai_response_prompt(
+            &participant.name,
+            &participant.role,
+            &participant.description,
+            &format_history(&self.messages),
+            &self.config.topic,
+            &self.config.style,
+        ))
+    }
+
+    async fn generate(&mut self, prompt: &str, options: &GenerationOptions) -> Generation {
+        Generation::Done(self.generator.generate(&self.config.model, prompt, options).await)
+    }
+
+    async fn finish_reply(&mut self, participant: &AiParticipant, text: &str) -> String {
+        postprocess::process_reply(&self.config.settings.postprocess, text, &participant.name)
+    }
+
+    async fn save_intervention(&mut self, intervention: Intervention) -> Result<(), String> {
+        (self.on_event)(RunEvent::Intervention(&intervention));
+        self.messages.push(intervention.message);
+        Ok(())
+    }
+
+    async fn save_reply(&mut self, reply: Reply<'_>) -> Result<(), String> {
+        (self.on_event)(RunEvent::Message(&reply.message));
+        self.messages.push(reply.message);
+        Ok(())
+    }
+
+    fn turn_failed(&mut self, failure: &TurnFailure<'_>) {
+        (self.on_event)(RunEvent::TurnFailed(failure));
+    }
+
+    async fn backoff(&mut self, attempt: u32) -> bool {
+        tokio::time::sleep(RETRY_BACKOFF * attempt).await;
+        true
+    }
+}
+
+/// 議論を最後まで進めて SessionArchive にまとめる（on_event は発言・失敗のたびに呼ぶ）
+pub async fn run(
+    client: &impl Generator,
+    config: &DiscussionConfig,
+    on_event: impl FnMut(RunEvent<'_>) + Send,
+) -> Result<SessionArchive, String> {
+    if config.topic.trim().is_empty() {
+        return Err("テーマを入力してください".into());
+    }
+    if config.participants.is_empty() {
+        return Err("参加者がいません".into());
+    }
+    let created_at = chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let mut transcript = Transcript {
+        generator: Guarded { inner: client, rules: SafetyRules::compile(&config.settings.safety) },
+        config,
+        participants: ParticipantsData { user_participates: false, ai_data: config.participants.clone() },
+        messages: Vec::new(),
+        position: TurnPosition::new(config.rounds),
+        on_event,
+    };
+    drive(&mut transcript).await?;
+
+    let Transcript { generator, participants, messages, .. } = transcript;
+    let summary = if config.summarize && !messages.is_empty() {
+        let prompt = prompts::build_discussion_summary_prompt(
+            &config.topic,
+            &format_history(&messages),
+            &participants.names(),
+            &config.style,
+        );
+        let generated = generator.generate(&config.model, &prompt, &GenerationOptions::default()).await?;
+        Some(generated.text.trim().to_string())
+    } else {
+        None
+    };
+    Ok(SessionArchive::new(
+        None,
+        config.topic.clone(),
+        config.model.clone(),
+        participants,
+        messages,
+        summary,
+        Some(created_at),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::safety::{SafetyAction, SafetyRule};
+
+    /// 呼ばれた順にプロンプトを記録し、決まった応答を返す生成（最初の failures 回は失敗する）
+    #[derive(Default)]
+    struct ScriptedGenerator {
+        prompts: Mutex<Vec<String>>,
+        failures: usize,
+    }
+
+    #[async_trait]
+    impl Generator for ScriptedGenerator {
+        async fn generate(&self, model: &str, prompt: &str, options: &GenerationOptions) -> Result<GenerationResult, String> {
+            let mut prompts = self.prompts.lock().unwrap();
+            prompts.push(prompt.to_string());
+            if prompts.len() <= self.failures {
+                return Err(format!("失敗{}", prompts.len()));
+            }
+            let text = if prompts.len() == 1 { "A: 最初の意見です".to_string() } else { format!("発言{}", prompts.len()) };
+            let mut result = GenerationResult::from_ollama(model, text, &json!({ "eval_count": 5 }));
+            result.seed = options.seed;
+            Ok(result)
+        }
+    }
+
+    fn participant(name: &str) -> AiParticipant {
+        AiParticipant { name: name.into(), role: "役".into(), description: "説明".into() }
+    }
+
+    fn config(rounds: u32, summarize: bool) -> DiscussionConfig {
+        DiscussionConfig {
+            topic: "テーマ".into(),
+            model: "gemma3:4b".into(),
+            participants: vec![participant("A"), participant("B")],
+            rounds,
+            summarize,
+            style: PromptStyle::default(),
+            settings: RunSettings::default(),
+        }
+    }
+
+    /// 発言者の名前・司会者の介入の理由・失敗を順に記録する
+    fn record(seen: &mut Vec<String>) -> impl FnMut(RunEvent<'_>) + Send + '_ {
+        move |event| match event {
+            RunEvent::Message(m) => seen.push(m.speaker.clone()),
+            RunEvent::Intervention(i) => seen.push(format!("{}:{:?}", i.message.speaker, i.finding.reason)),
+            RunEvent::TurnFailed(f) => seen.push(format!("失敗:{}:{}:{}", f.speaker, f.attempt, f.skipped)),
+            RunEvent::FacilitatorFailed(e) => seen.push(format!("司会者の失敗:{}", e)),
+        }
+    }
+
+    #[tokio::test]
+    async fn each_round_lets_every_participant_speak_in_order() {
+        let generator = ScriptedGenerator::default();
+        let mut seen = Vec::new();
+        let archive = run(&generator, &config(2, false), record(&mut seen)).await.unwrap();
+        assert_eq!(seen, ["A", "B", "A", "B"]);
+        let messages = &archive.messages;
+        assert_eq!(messages.len(), 4);
+        // 先頭の「A:」は取り除き、生成のメタデータとシードを発言に残す
+        assert_eq!(messages[0].message, "最初の意見です");
+        assert_eq!(messages[3].message, "発言4");
+        assert!(messages.iter().all(|m| !m.is_user && m.seed.is_some()));
+        assert_eq!(messages[0].generation.as_ref().and_then(|g| g.completion_tokens), Some(5));
+        // 後の発言のプロンプトにはそれまでの発言が入る
+        let prompts = generator.prompts.lock().unwrap();
+        assert!(prompts[1].contains("最初の意見です"));
+        assert!(archive.summary.is_none());
+    }
+
+    #[tokio::test]
+    async fn summary_is_generated_after_the_last_round() {
+        let generator = ScriptedGenerator::default();
+        let archive = run(&generator, &config(1, true), |_| {}).await.unwrap();
+        assert_eq!(archive.messages.len(), 2);
+        assert_eq!(archive.summary.as_deref(), Some("発言3"));
+        assert_eq!(generator.prompts.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn missing_topic_or_participants_fails_before_generating() {
+        let generator = ScriptedGenerator::default();
+        let mut no_topic = config(1, false);
+        no_topic.topic = " ".into();
+        assert!(run(&generator, &no_topic, |_| {}).await.is_err());
+        let mut nobody = config(1, false);
+        nobody.participants.clear();
+        assert!(run(&generator, &nobody, |_| {}).await.is_err());
+        assert!(generator.prompts.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn failed_turn_is_retried_then_skipped() {
+        let generator = ScriptedGenerator { failures: MAX_TURN_ATTEMPTS as usize, ..Default::default() };
+        let mut seen = Vec::new();
+        let archive = run(&generator, &config(1, false), record(&mut seen)).await.unwrap();
+        assert_eq!(seen, ["失敗:A:1:false", "失敗:A:2:false", "失敗:A:3:true", "B"]);
+        assert_eq!(archive.messages.len(), 1);
+        assert_eq!(archive.messages[0].message, "発言4");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn run_fails_when_every_participant_is_skipped() {
+        let generator = ScriptedGenerator { failures: usize::MAX, ..Default::default() };
+        let err = run(&generator, &config(1, false), |_| {}).await.unwrap_err();
+        assert_eq!(err, "失敗6");
+        assert_eq!(generator.prompts.lock().unwrap().len(), 2 * MAX_TURN_ATTEMPTS as usize);
+    }
+
+    #[tokio::test]
+    async fn facilitator_steps_in_when_one_participant_dominates() {
+        let generator = ScriptedGenerator::default();
+        let mut config = config(2, false);
+        config.settings.facilitator = FacilitatorSettings { enabled: true, window: 3, ..Default::default() };
+        let mut seen = Vec::new();
+        let archive = run(&generator, &config, record(&mut seen)).await.unwrap();
+        // A・B・A の3件で A が過半数になり、4人目の手番の前に司会者が発言する
+        assert_eq!(seen, ["A", "B", "A", "司会:Dominating", "B"]);
+        assert_eq!(archive.messages[3].message, "発言4");
+        let prompts = generator.prompts.lock().unwrap();
+        assert!(prompts[3].contains("直近3件の発言のうち2件がAさんのものです"));
+    }
+
+    #[tokio::test]
+    async fn safety_policy_applies_to_generated_replies() {
+        let generator = ScriptedGenerator::default();
+        let mut config = config(1, false);
+        config.settings.safety.rules = vec![SafetyRule {
+            category: "test".into(),
+            pattern: "発言".into(),
+            severity: 1,
+            action: SafetyAction::Redact,
+        }];
+        let archive = run(&generator, &config, |_| {}).await.unwrap();
+        assert_eq!(archive.messages[1].message, "＊＊＊2");
+    }
+}
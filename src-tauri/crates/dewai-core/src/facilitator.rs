@@ -0,0 +1,256 @@
+// 司会役（ファシリテーター。アプリと dewai-cli で共通）
+// 発言の合間に直近の発言を調べ、堂々巡り・1人の参加者による独占・テーマからの脱線を見つけたら、
+// テンプレート moderator_intervention で司会者の発言を生成する（議論への差し込みは進行側で行う）
+// 堂々巡り・独占はモデルを使わない軽い比較（2文字単位の重なり・発言数の割合）で判定する
+// 脱線はモデルによる判定（アプリの topic_drift）を渡された時だけ使う
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    engine::{now_timestamp, Generator},
+    generation::GenerationOptions,
+    postprocess::{self, PostprocessSettings},
+    prompts::{self, PromptStyle},
+    session::{format_history, ParticipantsData, StoredMessage},
+    similarity,
+};
+
+// 司会者名の最大文字数
+const MAX_NAME_CHARS: usize = 30;
+// 堂々巡りの判定で、直近の発言と比べる過去の発言数（判定の窓の何倍か）
+const LOOKBACK_FACTOR: usize = 2;
+
+/// 司会役の設定（アプリ設定の facilitator）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FacilitatorSettings {
+    /// 自動進行で司会者に介入させるか
+    pub enabled: bool,
+    /// 司会者の名前（会話履歴の発言者名になる）
+    pub name: String,
+    /// 判定に使う直近の発言数
+    pub window: usize,
+    /// 1人の発言がこの割合を超えたら独占とみなす（0〜1）
+    pub dominance_share: f32,
+    /// 直近の発言と過去の発言の重なりの平均がこれを超えたら堂々巡りとみなす（0〜1）
+    pub repetition_threshold: f32,
+    /// 介入の後、この件数の発言が増えるまで次の介入をしない
+    pub cooldown_messages: usize,
+}
+
+impl Default for FacilitatorSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            name: "司会".into(),
+            window: 8,
+            dominance_share: 0.5,
+            repetition_threshold: 0.45,
+            cooldown_messages: 6,
+        }
+    }
+}
+
+impl FacilitatorSettings {
+    /// 範囲外の値を補正
+    pub fn sanitized(mut self) -> Self {
+        self.name = self.name.trim().chars().take(MAX_NAME_CHARS).collect();
+        if self.name.is_empty() {
+            self.name = Self::default().name;
+        }
+        self.window = self.window.max(3);
+        self.dominance_share = self.dominance_share.clamp(0.0, 1.0);
+        self.repetition_threshold = self.repetition_threshold.clamp(0.0, 1.0);
+        self.cooldown_messages = self.cooldown_messages.max(1);
+        self
+    }
+}
+
+/// 介入の理由
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum InterventionReason {
+    /// 同じ主張の繰り返し
+    Circular,
+    /// 1人の参加者が発言の多くを占めている
+    Dominating,
+    /// テーマからの脱線
+    TopicDrift,
+}
+
+/// 見つかった議論の問題
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub reason: InterventionReason,
+    /// 司会者のプロンプトに渡す状況の説明
+    pub situation: String,
+    /// 判定に使った値（しきい値と比べた値）
+    pub score: f32,
+}
+
+/// 司会者の発言
+#[derive(Debug, Clone)]
+pub struct Intervention {
+    pub finding: Finding,
+    pub message: StoredMessage,
+}
+
+/// 判定の対象になる議論
+pub struct Discussion<'a> {
+    pub topic: &'a str,
+    pub model: &'a str,
+    pub participants: &'a ParticipantsData,
+    pub messages: &'a [StoredMessage],
+}
+
+/// モデルによる脱線の判定
+#[derive(Debug, Clone, Copy)]
+pub struct DetectedDrift<'a> {
+    /// テーマからの離れ具合（0〜1）
+    pub score: f32,
+    /// 逸れていった話題（テーマどおりなら空）
+    pub tangent: &'a str,
+    /// 判定時点の発言数
+    pub message_count: usize,
+    /// 脱線とみなすしきい値（アプリ設定の topicDriftThreshold）
+    pub threshold: f32,
+}
+
+fn mean(values: impl Iterator<Item = f32>) -> Option<f32> {
+    let (sum, count) = values.fold((0.0, 0), |(sum, count), v| (sum + v, count + 1));
+    (count > 0).then(|| sum / count as f32)
+}
+
+/// 直近の発言が、それより前の発言のどれかとどれだけ重なるかの平均
+fn repetition(recent: &[&StoredMessage], earlier: &[&StoredMessage]) -> Option<f32> {
+    let earlier: Vec<HashSet<String>> = earlier.iter().map(|m| similarity::topic_grams(&m.message)).collect();
+    mean(recent.iter().filter_map(|m| {
+        let grams = similarity::topic_grams(&m.message);
+        earlier.iter().map(|e| similarity::jaccard(&grams, e)).max_by(f32::total_cmp)
+    }))
+}
+
+/// 直近の発言を調べ、司会者が介入すべき問題があれば返す（脱線・堂々巡り・独占の順に判定）
+pub fn assess(
+    settings: &FacilitatorSettings,
+    discussion: &Discussion<'_>,
+    detected: Option<DetectedDrift<'_>>,
+) -> Option<Finding> {
+    let messages = discussion.messages;
+    // 前回の介入から間がなければ様子を見る
+    let since = messages.iter().rev().position(|m| !m.is_user && m.speaker == settings.name);
+    if since.is_some_and(|n| n < settings.cooldown_messages) {
+        return None;
+    }
+    // 前回の介入より後の発言を含み、直近の発言まで届いている判定だけを使う
+    let intervened_at = since.map(|n| messages.len() - n);
+    let detected = detected.filter(|drift| {
+        let after_intervention = intervened_at.is_none_or(|at| drift.message_count > at);
+        after_intervention && drift.message_count + settings.window > messages.len()
+    });
+    let spoken: Vec<&StoredMessage> =
+        messages.iter().filter(|m| m.speaker != settings.name && !m.message.trim().is_empty()).collect();
+    if spoken.len() < settings.window {
+        return None;
+    }
+    let (earlier, recent) = spoken.split_at(spoken.len() - settings.window);
+    let earlier = &earlier[earlier.len().saturating_sub(settings.window * LOOKBACK_FACTOR)..];
+
+    if let Some(drift) = detected.filter(|drift| drift.score > drift.threshold) {
+        let tangent = if drift.tangent.is_empty() { String::new() } else { format!("（{}）", drift.tangent) };
+        return Some(Finding {
+            reason: InterventionReason::TopicDrift,
+            situation: format!(
+                "議論がテーマから逸れた話題{}に移っています。テーマに沿った論点へ議論を戻してください。",
+                tangent
+            ),
+            score: drift.score,
+        });
+    }
+    if let Some(score) = repetition(recent, earlier).filter(|s| *s > settings.repetition_threshold) {
+        return Some(Finding {
+            reason: InterventionReason::Circular,
+            situation: format!(
+                "直近{}件の発言が以前の主張の繰り返しになり、議論が堂々巡りしています。\
+                 これまでの論点を整理し、まだ話し合っていない観点を示してください。",
+                recent.len()
+            ),
+            score,
+        });
+    }
+    // 独占は複数の参加者がいる場合だけ判定する
+    let names = discussion.participants.names();
+    if names.len() >= 2 {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for m in recent {
+            *counts.entry(m.speaker.as_str()).or_default() += 1;
+        }
+        let (speaker, count) = counts.into_iter().max_by_key(|(speaker, count)| (*count, *speaker))?;
+        let score = count as f32 / recent.len() as f32;
+        if score > settings.dominance_share {
+            let quiet: Vec<&str> =
+                names.iter().filter(|name| !recent.iter().any(|m| &m.speaker == *name)).map(String::as_str).collect();
+            let invite = if quiet.is_empty() {
+                "ほかの参加者にも意見を求めてください。".to_string()
+            } else {
+                format!("まだ発言していない{}さんに意見を求めてください。", quiet.join("さん、"))
+            };
+            return Some(Finding {
+                reason: InterventionReason::Dominating,
+                situation: format!(
+                    "直近{}件の発言のうち{}件が{}さんのものです。{}",
+                    recent.len(),
+                    count,
+                    speaker,
+                    invite
+                ),
+                score,
+            });
+        }
+    }
+    None
+}
+
+/// 問題があれば司会者の発言を生成する（問題がない・後処理で空になれば None。保存・通知は呼び出し側で行う）
+pub async fn intervene(
+    generator: &impl Generator,
+    settings: &FacilitatorSettings,
+    postprocess: &PostprocessSettings,
+    discussion: &Discussion<'_>,
+    detected: Option<DetectedDrift<'_>>,
+    style: &PromptStyle,
+    options: &GenerationOptions,
+) -> Result<Option<Intervention>, String> {
+    // 参加者と同名だと発言の区別がつかないため介入しない
+    if discussion.participants.ai_data.iter().any(|p| p.name == settings.name) {
+        return Ok(None);
+    }
+    let Some(finding) = assess(settings, discussion, detected) else {
+        return Ok(None);
+    };
+    let prompt = prompts::build_moderator_intervention_prompt(
+        &settings.name,
+        discussion.topic,
+        &discussion.participants.names(),
+        &finding.situation,
+        &format_history(discussion.messages),
+        style,
+    );
+    let generated = generator.generate(discussion.model, &prompt, options).await?;
+    let reply = postprocess::process_reply(postprocess, &generated.text, &settings.name);
+    if reply.trim().is_empty() {
+        return Ok(None);
+    }
+    let message = StoredMessage {
+        speaker: settings.name.clone(),
+        message: reply.trim().to_string(),
+        is_user: false,
+        timestamp: now_timestamp(),
+        seed: options.seed,
+        generation: Some(generated.meta),
+        edited_at: None,
+        regenerated_at: None,
+    };
+    Ok(Some(Intervention { finding, message }))
+}
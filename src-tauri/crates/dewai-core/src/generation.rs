@@ -0,0 +1,178 @@
+// 生成オプションと生成結果
+// Ollama の options・format に対応するオプションと、応答から取り出すメタデータ（トークン数・生成時間）
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+use serde::{Deserialize, Serialize};
+
+/// Ollama の options フィールドに対応する生成オプション（未指定はモデル既定値）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerationOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_predict: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_ctx: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repeat_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+    /// 出力形式の制約（未指定は通常のテキスト）。options ではなくリクエスト本体の format に入る
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<OutputFormat>,
+    /// 添付画像（base64）。リクエスト本体の images に入る。大きいため生成ログには保存しない
+    #[serde(skip)]
+    pub images: Vec<String>,
+}
+
+/// Ollama の format パラメータ（JSON モード）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OutputFormat {
+    /// 任意の JSON オブジェクト（"format": "json"）
+    Json,
+    /// JSON スキーマで形を指定（配列を返させたい場合もこちら）
+    Schema(serde_json::Value),
+}
+
+impl GenerationOptions {
+    /// シードのみ指定したオプション（コマンド引数の seed から作る）
+    pub fn seeded(seed: Option<i64>) -> Self {
+        Self { seed, ..Default::default() }
+    }
+
+    /// コマンド引数の options / seed から組み立てる（seed 引数があれば options.seed より優先）
+    /// 値は Ollama が受け付ける範囲に丸める
+    pub fn from_request(options: Option<GenerationOptions>, seed: Option<i64>) -> Self {
+        let mut o = options.unwrap_or_default();
+        if seed.is_some() {
+            o.seed = seed;
+        }
+        o.temperature = o.temperature.map(|t| t.clamp(0.0, 2.0));
+        o.top_p = o.top_p.map(|p| p.clamp(0.0, 1.0));
+        o.num_ctx = o.num_ctx.map(|n| n.clamp(256, 131_072));
+        o.repeat_penalty = o.repeat_penalty.map(|r| r.clamp(0.0, 2.0));
+        o
+    }
+
+    /// 出力形式を指定したオプション
+    pub fn with_format(mut self, format: OutputFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// Ollama API へ渡す format の値（テキスト出力なら None）
+    pub fn to_ollama_format(&self) -> Option<serde_json::Value> {
+        match self.format.as_ref()? {
+            OutputFormat::Json => Some(serde_json::Value::String("json".into())),
+            OutputFormat::Schema(schema) => Some(schema.clone()),
+        }
+    }
+
+    /// Ollama API へ渡す options オブジェクト（キーは Ollama の snake_case）
+    pub fn to_ollama_options(&self) -> serde_json::Value {
+        let mut map = serde_json::Map::new();
+        let mut put = |key: &str, value: Option<serde_json::Value>| {
+            if let Some(v) = value {
+                map.insert(key.to_string(), v);
+            }
+        };
+        put("temperature", self.temperature.map(Into::into));
+        put("top_p", self.top_p.map(Into::into));
+        put("top_k", self.top_k.map(Into::into));
+        put("num_predict", self.num_predict.map(Into::into));
+        put("num_ctx", self.num_ctx.map(Into::into));
+        put("repeat_penalty", self.repeat_penalty.map(Into::into));
+        put("seed", self.seed.map(Into::into));
+        serde_json::Value::Object(map)
+    }
+
+    /// シード未指定なら新しく採番したオプションを返す
+    pub fn with_seed_assigned(mut self) -> Self {
+        if self.seed.is_none() {
+            self.seed = Some(generate_seed());
+        }
+        self
+    }
+}
+
+/// 生成のメタデータ（Ollama の prompt_eval_count / eval_count / total_duration）
+/// 発言ごとに messages の JSON に保存され、ターンごとのトークン数・待ち時間の表示に使う
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerationMeta {
+    pub model: String,
+    /// 入力トークン数（バックエンドが返さない場合は None）
+    pub prompt_tokens: Option<u32>,
+    /// 出力トークン数
+    pub completion_tokens: Option<u32>,
+    /// 生成にかかった時間（Ollama の total_duration。無ければ計測値）
+    pub duration_ms: Option<u64>,
+    /// 出力の生成速度（トークン/秒。Ollama は eval_duration から、それ以外は duration_ms から求める）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tokens_per_sec: Option<f64>,
+    /// 失敗して再送した回数（一括生成のみ。ストリーミングは再送しないので 0）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retries: Option<u32>,
+    /// 生成キューの待ち時間と再送を含む実時間
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wall_ms: Option<u64>,
+}
+
+/// 生成系コマンドの戻り値（本文 + メタデータ）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GenerationResult {
+    pub text: String,
+    /// 生成時のシード（発言の再現用。シードを指定・採番した場合のみ）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+    #[serde(flatten)]
+    pub meta: GenerationMeta,
+}
+
+impl GenerationResult {
+    /// メタデータの無い生成結果（モック・フィクスチャ再生など）
+    pub fn text_only(model: &str, text: String) -> Self {
+        Self { text, seed: None, meta: GenerationMeta { model: model.to_string(), ..Default::default() } }
+    }
+
+    /// Ollama の応答（一括 or ストリームの最終行）からメタデータを取り出す
+    pub fn from_ollama(model: &str, text: String, response: &serde_json::Value) -> Self {
+        let count = |key: &str| response[key].as_u64().map(|n| n.min(u32::MAX as u64) as u32);
+        Self {
+            text,
+            seed: None,
+            meta: GenerationMeta {
+                model: model.to_string(),
+                prompt_tokens: count("prompt_eval_count"),
+                completion_tokens: count("eval_count"),
+                duration_ms: response["total_duration"].as_u64().map(|ns| ns / 1_000_000),
+                tokens_per_sec: match (response["eval_count"].as_u64(), response["eval_duration"].as_u64()) {
+                    (Some(count), Some(ns)) if ns > 0 => Some(count as f64 / (ns as f64 / 1e9)),
+                    _ => None,
+                },
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// 新しいシード値（Ollama が受け付ける正の32bit整数の範囲）
+pub fn generate_seed() -> i64 {
+    // RandomState はプロセスごとにランダムな鍵を持つため、乱数源として利用する
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default(),
+    );
+    (hasher.finish() % (i32::MAX as u64)) as i64 + 1
+}
@@ -0,0 +1,15 @@
+// DewAI の共通部分（GUI なしで使えるもの）
+// プロンプトテンプレート・生成オプション・セッションの型・JSON アーカイブ・Ollama クライアント・発言の後処理・
+// 安全ポリシー・司会役の判定と、手番の進め方（engine）を持つ。Tauri アプリと dewai-cli の両方から使う
+pub mod archive;
+pub mod engine;
+pub mod facilitator;
+pub mod generation;
+pub mod llm_json;
+pub mod ollama;
+pub mod postprocess;
+pub mod prompts;
+pub mod safety;
+pub mod session;
+pub mod similarity;
+pub mod tokens;
@@ -0,0 +1,70 @@
+// LLM出力のJSON抽出ユーティリティ
+// モデルはコードフェンスや前置きを付けることがあるため、JSON部分だけを取り出してパースする
+use serde::de::DeserializeOwned;
+
+/// コードフェンス・前後の説明文を除去し、最初のJSONオブジェクト/配列を切り出す
+pub fn extract_json_block(raw: &str) -> &str {
+    let trimmed = raw.trim();
+    let start = trimmed.find(['{', '[']);
+    let end = trimmed.rfind(['}', ']']);
+    match (start, end) {
+        (Some(s), Some(e)) if s < e => &trimmed[s..=e],
+        _ => trimmed,
+    }
+}
+
+/// よくある崩れ（末尾カンマ・途中で切れた出力）を修復する
+/// 文字列リテラルの中身には手を付けない
+pub fn repair_json(block: &str) -> String {
+    let chars: Vec<char> = block.chars().collect();
+    let mut out = String::with_capacity(block.len());
+    let mut closers: Vec<char> = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, &c) in chars.iter().enumerate() {
+        if in_string {
+            out.push(c);
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' => closers.push('}'),
+            '[' => closers.push(']'),
+            '}' | ']' => {
+                closers.pop();
+            }
+            ',' => {
+                // 直後（空白を除く）が閉じ括弧か末尾なら末尾カンマとして捨てる
+                let next = chars[i + 1..].iter().find(|c| !c.is_whitespace());
+                if matches!(next, None | Some('}') | Some(']')) {
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        out.push(c);
+    }
+    // 途中で切れていたら文字列と括弧を閉じる
+    if in_string {
+        out.push('"');
+    }
+    while let Some(c) = closers.pop() {
+        out.push(c);
+    }
+    out
+}
+
+/// LLM出力を指定の型としてパース（そのままで読めなければ修復して再試行）
+pub fn parse_llm_json<T: DeserializeOwned>(raw: &str) -> Result<T, String> {
+    let block = extract_json_block(raw);
+    serde_json::from_str(block).or_else(|e| {
+        serde_json::from_str(&repair_json(block)).map_err(|_| format!("JSON解析失敗: {}", e))
+    })
+}
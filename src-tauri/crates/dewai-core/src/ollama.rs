@@ -0,0 +1,178 @@
+// Ollama の HTTP API クライアント（GUI なしで使う最小限のもの）
+// アプリは接続設定・再試行・サーキットブレーカーを持つ backend.rs の OllamaBackend を使い、
+// リクエスト本文の組み立て・応答の読み取り（request_body・model_names・StreamDecoder）をここと共通にする
+use std::time::Duration;
+
+use serde_json::json;
+
+use crate::generation::{GenerationOptions, GenerationResult};
+
+pub const DEFAULT_BASE_URL: &str = "http://localhost:11434";
+
+// 1回の生成を待つ上限（CPU だけの環境での長い発言も待てるように長めにする）
+const GENERATE_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// /api/generate のリクエスト本文（keep_alive は呼び出し側で付ける）
+pub fn request_body(model: &str, prompt: &str, stream: bool, options: &GenerationOptions) -> serde_json::Value {
+    let mut body = json!({
+        "model": model,
+        "prompt": prompt,
+        "stream": stream,
+        "options": options.to_ollama_options(),
+    });
+    if let Some(format) = options.to_ollama_format() {
+        body["format"] = format;
+    }
+    if !options.images.is_empty() {
+        body["images"] = json!(options.images);
+    }
+    body
+}
+
+/// /api/tags の応答からインストール済みのモデル名を取り出す
+pub fn model_names(tags: &serde_json::Value) -> Vec<String> {
+    let models = tags["models"].as_array().map(Vec::as_slice).unwrap_or_default();
+    models.iter().filter_map(|m| m["name"].as_str().map(str::to_string)).collect()
+}
+
+/// ストリーミング応答（1行1JSON の NDJSON）の読み取り
+/// 行の途中で区切られて届くことがあるので、改行までバッファしてから読む
+#[derive(Debug, Default)]
+pub struct StreamDecoder {
+    buffer: Vec<u8>,
+    text: String,
+    // 最終行（done: true）にトークン数・所要時間が入る
+    last: serde_json::Value,
+}
+
+impl StreamDecoder {
+    /// 受け取ったバイト列を読み進め、本文の断片ごとに on_piece を呼ぶ（応答に error があれば失敗）
+    pub fn push(&mut self, bytes: &[u8], mut on_piece: impl FnMut(&str)) -> Result<(), String> {
+        self.buffer.extend_from_slice(bytes);
+        while let Some(pos) = self.buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=pos).collect();
+            let Ok(json) = serde_json::from_slice::<serde_json::Value>(&line) else { continue };
+            if let Some(err) = json["error"].as_str() {
+                return Err(format!("生成失敗: {}", err));
+            }
+            if let Some(piece) = json["response"].as_str().filter(|p| !p.is_empty()) {
+                on_piece(piece);
+                self.text.push_str(piece);
+            }
+            if json["done"].as_bool() == Some(true) {
+                self.last = json;
+            }
+        }
+        Ok(())
+    }
+
+    /// 受け取った本文と最終行のメタデータから生成結果を作る
+    pub fn finish(self, model: &str) -> GenerationResult {
+        GenerationResult::from_ollama(model, self.text, &self.last)
+    }
+}
+
+/// Ollama のクライアント
+#[derive(Debug, Clone)]
+pub struct Client {
+    base_url: String,
+    http: reqwest::Client,
+}
+
+impl Client {
+    /// 接続先を指定して作る（"127.0.0.1:11434" のようにスキームがなければ http:// を補う）
+    pub fn new(host: &str) -> Self {
+        let host = host.trim().trim_end_matches('/');
+        let base_url = if host.contains("://") { host.to_string() } else { format!("http://{}", host) };
+        Self { base_url, http: reqwest::Client::new() }
+    }
+
+    /// 環境変数 OLLAMA_HOST の接続先（未設定なら http://localhost:11434）
+    pub fn from_env() -> Self {
+        match std::env::var("OLLAMA_HOST") {
+            Ok(host) if !host.trim().is_empty() => Self::new(&host),
+            _ => Self::new(DEFAULT_BASE_URL),
+        }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// 一括で生成する（ストリーミングなし）
+    pub async fn generate(
+        &self,
+        model: &str,
+        prompt: &str,
+        options: &GenerationOptions,
+    ) -> Result<GenerationResult, String> {
+        let response = self
+            .http
+            .post(format!("{}/api/generate", self.base_url))
+            .json(&request_body(model, prompt, false, options))
+            .timeout(GENERATE_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| format!("Ollama への接続失敗 ({}): {}", self.base_url, e))?;
+        let status = response.status();
+        let body: serde_json::Value =
+            response.json().await.map_err(|e| format!("Ollama の応答の読み取り失敗: {}", e))?;
+        if !status.is_success() {
+            let message = body["error"].as_str().unwrap_or("不明なエラー");
+            return Err(format!("Ollama エラー ({}): {}", status, message));
+        }
+        let text = body["response"].as_str().ok_or("Ollama の応答に本文がありません")?.to_string();
+        let mut result = GenerationResult::from_ollama(model, text, &body);
+        result.seed = options.seed;
+        Ok(result)
+    }
+
+    /// インストール済みのモデル名
+    pub async fn list_models(&self) -> Result<Vec<String>, String> {
+        let body: serde_json::Value = self
+            .http
+            .get(format!("{}/api/tags", self.base_url))
+            .send()
+            .await
+            .map_err(|e| format!("Ollama への接続失敗 ({}): {}", self.base_url, e))?
+            .json()
+            .await
+            .map_err(|e| format!("Ollama の応答の読み取り失敗: {}", e))?;
+        Ok(model_names(&body))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stream_lines_split_across_chunks_are_joined() {
+        let stream = "{\"response\":\"こん\"}\n{\"response\":\"にちは\"}\n{\"response\":\"\",\"done\":true,\"eval_count\":3}\n";
+        // 2行目の途中（マルチバイト文字の途中）で区切って届ける
+        let split = stream.find("にち").unwrap() + 1;
+        let mut decoder = StreamDecoder::default();
+        let mut pieces = Vec::new();
+        decoder.push(&stream.as_bytes()[..split], |p| pieces.push(p.to_string())).unwrap();
+        assert_eq!(pieces, ["こん"]);
+        decoder.push(&stream.as_bytes()[split..], |p| pieces.push(p.to_string())).unwrap();
+        assert_eq!(pieces, ["こん", "にちは"]);
+        let result = decoder.finish("gemma3:4b");
+        assert_eq!(result.text, "こんにちは");
+        assert_eq!(result.meta.completion_tokens, Some(3));
+    }
+
+    #[test]
+    fn stream_error_line_fails() {
+        let mut decoder = StreamDecoder::default();
+        let err = decoder.push(b"{\"error\":\"model not found\"}\n", |_| {}).unwrap_err();
+        assert_eq!(err, "生成失敗: model not found");
+    }
+
+    #[test]
+    fn model_names_skip_entries_without_a_name() {
+        let tags = json!({ "models": [{ "name": "gemma3:4b" }, { "size": 1 }, { "name": "llama3.2" }] });
+        assert_eq!(model_names(&tags), ["gemma3:4b", "llama3.2"]);
+        assert!(model_names(&json!({})).is_empty());
+    }
+}
@@ -0,0 +1,185 @@
+// 生成結果の後処理（アプリと dewai-cli で共通）
+// モデルが指示に反して付ける「〇〇:」の話者名・コードフェンス・全体を囲む引用符・前置きや注釈を取り除き、
+// 設定（PostprocessSettings）があれば文の区切りで最大文字数に収める
+use std::sync::OnceLock;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+// maxChars に指定できる最小値（極端に短い値で発言が成り立たなくなるのを防ぐ）
+const MIN_MAX_CHARS: usize = 20;
+// 切り詰めで文の区切りを探す範囲（上限のこの割合より前では区切らない）
+const MIN_KEEP_RATIO: usize = 3;
+const ELLIPSIS: char = '…';
+
+struct Patterns {
+    /// 「以下は〇〇の発言です：」「〇〇として発言します。」などの前置き
+    leading_meta: Regex,
+    /// 「※」「（注）」「Note:」などで始まる末尾の注釈
+    trailing_meta: Regex,
+}
+
+fn patterns() -> &'static Patterns {
+    static PATTERNS: OnceLock<Patterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| Patterns {
+        leading_meta: Regex::new(
+            r"(?i)^(?:(?:以下|これ|次)は.{0,40}(?:発言|回答|返答|応答).{0,10}|.{0,30}として(?:発言|回答|返答)(?:します|いたします|しました)|(?:here is|here's|sure)[^\n]{0,60})[。.!！:：]?$",
+        )
+        .expect("前置きの正規表現"),
+        trailing_meta: Regex::new(r"(?i)^(?:※|[（(]注|[（(]補足|[（(]説明|注[:：]|補足[:：]|note:)").expect("注釈の正規表現"),
+    })
+}
+
+/// 行頭の「〇〇:」「【〇〇】」「**〇〇（役職）**：」などの話者名を取り除く
+pub fn strip_speaker<'a>(text: &'a str, speaker: &str) -> &'a str {
+    let speaker = speaker.trim();
+    if speaker.is_empty() {
+        return text;
+    }
+    let prefix = Regex::new(&format!(
+        r"^(?:\*\*|__)?[【\[]?{}(?:さん)?(?:\s*[（(][^）)\n]{{0,30}}[）)])?[】\]]?(?:\*\*|__)?\s*(?:[:：]\s*)?",
+        regex::escape(speaker)
+    ));
+    let Ok(prefix) = prefix else { return text };
+    let Some(m) = prefix.find(text) else { return text };
+    let matched = m.as_str();
+    let rest = &text[m.end()..];
+    // 区切り（コロン・【】）がなければ、直後が発言の「」で始まる場合だけ話者名とみなす
+    let delimited = matched.contains([':', '：', '】', ']']);
+    if delimited || rest.starts_with('「') {
+        rest
+    } else {
+        text
+    }
+}
+
+/// 全体を囲む引用符を外す（中に同じ引用符があれば発言の一部なので残す）
+fn strip_wrapping_quotes(text: &str) -> &str {
+    for (open, close) in [('「', '」'), ('『', '』'), ('"', '"'), ('“', '”'), ('\'', '\'')] {
+        if let Some(inner) = text.strip_prefix(open).and_then(|t| t.strip_suffix(close)) {
+            if !inner.contains(open) && !inner.contains(close) {
+                return inner.trim();
+            }
+        }
+    }
+    text
+}
+
+/// 文の区切りで max_chars 以内に切り詰める（区切りが見つからなければ途中で切って … を付ける）
+pub fn truncate_at_sentence(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let head: String = text.chars().take(max_chars).collect();
+    let boundary = head
+        .char_indices()
+        .filter(|(_, c)| matches!(c, '。' | '！' | '？' | '!' | '?' | '.' | '\n'))
+        .map(|(i, c)| i + c.len_utf8())
+        .rfind(|end| head[..*end].chars().count() >= max_chars / MIN_KEEP_RATIO);
+    match boundary {
+        Some(end) => head[..end].trim_end().to_string(),
+        None => {
+            let mut cut: String = head.chars().take(max_chars.saturating_sub(1)).collect();
+            cut.push(ELLIPSIS);
+            cut
+        }
+    }
+}
+
+/// 生成結果から発言本文以外の部分を取り除く
+pub fn clean_reply(text: &str, speaker: &str) -> String {
+    let p = patterns();
+    // コードフェンスの行を除く
+    let mut lines: Vec<&str> = text.lines().filter(|l| !l.trim_start().starts_with("```")).collect();
+    while lines.first().is_some_and(|l| l.trim().is_empty()) {
+        lines.remove(0);
+    }
+    while lines.last().is_some_and(|l| l.trim().is_empty()) {
+        lines.pop();
+    }
+    // 本文が残る場合だけ前置き・末尾の注釈を除く
+    if lines.len() > 1 && p.leading_meta.is_match(lines[0].trim()) {
+        lines.remove(0);
+    }
+    while lines.len() > 1 && lines.last().is_some_and(|l| p.trailing_meta.is_match(l.trim())) {
+        lines.pop();
+        while lines.last().is_some_and(|l| l.trim().is_empty()) {
+            lines.pop();
+        }
+    }
+    let joined = lines.join("\n");
+    let body = strip_speaker(joined.trim(), speaker).trim();
+    strip_wrapping_quotes(body).to_string()
+}
+
+/// 後処理の設定（アプリ設定の postprocess）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PostprocessSettings {
+    /// 話者名・コードフェンス・引用符・前置きなどを取り除くか
+    pub enabled: bool,
+    /// 発言の最大文字数（超えたら文の区切りで切り詰める。未指定なら切り詰めない）
+    pub max_chars: Option<usize>,
+}
+
+impl Default for PostprocessSettings {
+    fn default() -> Self {
+        Self { enabled: true, max_chars: None }
+    }
+}
+
+impl PostprocessSettings {
+    pub fn sanitized(mut self) -> Self {
+        self.max_chars = self.max_chars.map(|n| n.max(MIN_MAX_CHARS));
+        self
+    }
+}
+
+/// 設定に従って発言を後処理する（後処理で空になる場合は元の文を返す）
+pub fn process_reply(settings: &PostprocessSettings, text: &str, speaker: &str) -> String {
+    let mut out = if settings.enabled { clean_reply(text, speaker) } else { text.trim().to_string() };
+    if out.is_empty() {
+        out = text.trim().to_string();
+    }
+    match settings.max_chars {
+        Some(max) => truncate_at_sentence(&out, max),
+        None => out,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strip_speaker_removes_name_prefixes() {
+        assert_eq!(strip_speaker("田中: 賛成です", "田中"), "賛成です");
+        assert_eq!(strip_speaker("田中さん：賛成です", "田中"), "賛成です");
+        assert_eq!(strip_speaker("【田中】賛成です", "田中"), "賛成です");
+        assert_eq!(strip_speaker("**田中（エンジニア）**: 賛成です", "田中"), "賛成です");
+        assert_eq!(strip_speaker("田中「賛成です」", "田中"), "「賛成です」");
+    }
+
+    #[test]
+    fn strip_speaker_keeps_text_that_only_starts_with_the_name() {
+        // 区切りがなければ本文の一部とみなす
+        assert_eq!(strip_speaker("田中の案に賛成です", "田中"), "田中の案に賛成です");
+        assert_eq!(strip_speaker("佐藤: 賛成です", "田中"), "佐藤: 賛成です");
+        assert_eq!(strip_speaker("田中: 賛成です", " "), "田中: 賛成です");
+    }
+
+    #[test]
+    fn clean_reply_removes_fences_preambles_and_notes() {
+        let raw = "以下は田中の発言です：\n```\n田中: 「コストを先に見積もるべきです」\n```\n\n※ 役割に沿って回答しました";
+        assert_eq!(clean_reply(raw, "田中"), "コストを先に見積もるべきです");
+        // 本文が前置きだけなら消さない
+        assert_eq!(clean_reply("以下は田中の発言です：", "田中"), "以下は田中の発言です：");
+    }
+
+    #[test]
+    fn truncation_prefers_sentence_boundaries() {
+        assert_eq!(truncate_at_sentence("短い文。", 20), "短い文。");
+        assert_eq!(truncate_at_sentence("一つ目の文です。二つ目の文はとても長く続きます", 12), "一つ目の文です。");
+        assert_eq!(truncate_at_sentence("区切りのないとても長い文章が続きます", 6), "区切りのな…");
+    }
+}
@@ -0,0 +1,2181 @@
+// プロンプト管理モジュール
+// 各種AI操作用のプロンプトテンプレートを一元管理
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+
+use crate::tokens;
+
+const TPL_DISCUSSION_ANALYSIS: &str = r#"<discussion_analysis>
+<topic>{discussion_topic}</topic>
+<participants>{participants_list}</participants>
+
+<current_conversation>
+{conversation_history}
+</current_conversation>
+
+<instructions>
+この議論を分析し、以下の要素を抽出してください：
+
+1. **主要論点** - 議論の中心となっている具体的な争点
+2. **各参加者の立場** - 参加者ごとの現在の見解や主張
+3. **対立点** - 参加者間で意見が分かれている具体的なポイント
+4. **共通認識** - 参加者が共有している認識や合意点
+5. **未探索領域** - まだ十分に議論されていない関連トピック
+
+JSON形式で以下の構造で出力してください：
+
+{
+  "mainPoints": [
+    {
+      "point": "論点の具体的な内容",
+      "description": "論点の詳細説明"
+    }
+  ],
+  "participantStances": [
+    {
+      "participant": "参加者名",
+      "label": "立場の短いラベル（例: 賛成 / 条件付き賛成 / 中立 / 反対）",
+      "confidence": 0.8,
+      "stance": "その参加者の立場・主張",
+      "keyArguments": ["主要な論拠1", "主要な論拠2"]
+    }
+  ],
+  "conflicts": [
+    {
+      "issue": "対立している具体的な問題",
+      "sides": ["立場A", "立場B"],
+      "description": "対立の詳細"
+    }
+  ],
+  "commonGround": [
+    "共通認識1",
+    "共通認識2"
+  ],
+  "unexploredAreas": [
+    "未探索トピック1",
+    "未探索トピック2"
+  ]
+}
+
+重要：
+- 必ず有効なJSON形式で応答すること
+- confidence はその参加者が立場をどれだけはっきり示しているか（0.0〜1.0の数値）
+</instructions>
+</discussion_analysis>"#;
+
+const TPL_INCREMENTAL_ANALYSIS: &str = r#"<incremental_discussion_analysis>
+<topic>{discussion_topic}</topic>
+<participants>{participants_list}</participants>
+
+<previous_analysis>
+{previous_analysis}
+</previous_analysis>
+
+<new_messages>
+{new_messages}
+</new_messages>
+
+<instructions>
+previous_analysis はこれまでの議論の分析結果（JSON）です。new_messages は前回の分析以降に追加された発言のみです。
+これらを統合し、previous_analysis と同じJSON構造（mainPoints / participantStances / conflicts / commonGround / unexploredAreas）で最新の分析を出力してください。
+
+要件：
+- 既存の論点・立場・対立点は維持し、新しい発言で追加・変化・解決したものを反映する
+- 立場が変わった参加者は participantStances の label / confidence / stance を更新し、新たに発言した参加者は追加する
+- 新しい発言で合意に至った対立点は conflicts から commonGround へ移す
+- 新しい発言で扱われた未探索領域は unexploredAreas から外す
+- 重複は統合し簡潔にする
+- 出力は完全な最新の分析のみ（差分や説明文を含めない）
+
+重要：
+- 必ず有効なJSON形式で応答すること
+</instructions>
+</incremental_discussion_analysis>"#;
+
+const TPL_AI_PROFILES: &str = r#"<ai_profiles_generation>
+<topic>{discussion_topic}</topic>
+<count>{count}</count>
+<hints>{hint_line}</hints>
+
+<constraints>
+{constraints}
+</constraints>
+
+<instructions>
+次の議論テーマに適したAI参加者プロフィールを{count}名分、JSON配列のみで生成してください。
+constraints に条件がある場合は、下の要件より優先して必ず満たしてください。
+各要素は必ず次のキーを含めてください： name, role, description。
+
+要件：
+- name: 参加者の短い日本語の名前（一般的な人名）。　テーマにそぐわなくてよいから、多様な名前を使用してください。
+- role: テーマに関連する役職/立場/専門領域。
+- description: 100文字前後で、その人物の視点・価値観・発言スタイルを簡潔に説明。　テーマにそぐわなくてよいから、個性的な視点を持たせてください。
+- 視点がバラけるように、賛成・反対・懐疑・中立・実務など多様性を持たせる。テーマにそぐわなくてよいから、個性的な視点を持たせてください。
+- 参加者同士で名前・役割の重複は避ける。
+
+出力フォーマット（必ず純粋なJSONのみにしてください。）：
+
+[
+  { "name": "", "role": "", "description": "" }
+]
+</instructions>
+</ai_profiles_generation>"#;
+
+const TPL_AI_RESPONSE: &str = r#"<discussion_context>
+<discussion_topic>{discussion_topic}</discussion_topic>
+
+<participant>
+<name>{participant_name}</name>
+<role>{role}</role>
+<description>{description}</description>
+</participant>
+
+<user_profile>
+{user_profile}
+</user_profile>
+
+<conversation_history>
+{conversation_history}
+</conversation_history>
+
+<discussion_guidelines>
+議論を深めるために、以下のいずれかの要素を含めてください：
+
+1. 深掘りの要素
+2. 新しい視点の提供
+3. 建設的な対話
+
+</discussion_guidelines>
+
+<instructions>
+あなたは{participant_name}で、役職または職業が{role}です。{description}
+
+議論のテーマは「{discussion_topic}」です。
+上記のdiscussion_guidelinesに従い、議論を深める発言をしてください。
+
+重要：会話履歴で「ユーザー」と表示されているのは人間の参加者の一人です。user_profile に名前があればその名前で呼びかけ、役職・専門と立場を踏まえて応答してください。そして、あなたはあくまで{participant_name}であり、{participant_name}として発言してください。
+
+必須要件：
+- 前の発言者に具体的に反応する（質問に対しては意見を、意見に対しては反応を）
+- 「ユーザー」が質問をしている場合は、質問に対する自分の立場を明確に表明する
+- 「ユーザー」が意見を述べている場合は、その意見に対して賛成・反対・補足などの反応をする
+- 具体例、疑問、仮定、検証のいずれかを含める
+- {participant_name}らしい視点と口調を維持
+- 議論を前進させる内容にする
+- 人間の参加者（ユーザー）の意見を尊重し、適切に応答する
+- 発言は一言二言程度で、短くすることを心がけてください
+
+
+回答は{participant_name}の発言内容のみを返してください。説明や注釈は不要です。
+日本語で口語の文章で発言してください。
+{style_guidelines}</instructions>
+</discussion_context>"#;
+
+const TPL_DEVILS_ADVOCATE: &str = r#"<devils_advocate>
+<discussion_topic>{discussion_topic}</discussion_topic>
+
+<participant>
+<name>{participant_name}</name>
+<role>{role}</role>
+<description>{description}</description>
+</participant>
+
+<current_consensus>
+{consensus}
+</current_consensus>
+
+<conversation_history>
+{conversation_history}
+</conversation_history>
+
+<instructions>
+あなたは{participant_name}で、役職または職業が{role}です。{description}
+この議論であなたは「悪魔の代弁者（反論役）」を務めます。自分の本心とは関係なく、current_consensus のうち最も強い合意を1つ選び、それに体系的に異議を唱えてください。
+
+進め方：
+- 合意が前提としている仮定を1つ特定し、それが崩れる具体的な状況や反例を示す
+- 見落とされているリスク・コスト・利害関係者を指摘する
+- 必要なら代替案を提示し、合意側に答えてほしい問いを1つ投げかける
+
+必須要件：
+- 人格攻撃や揚げ足取りではなく、論拠に基づいて反論する
+- 直前の発言にも触れ、議論の流れから浮かないようにする
+- {participant_name}らしい口調を保ち、発言は一言二言程度で短くする
+
+回答は{participant_name}の発言内容のみを返してください。説明や注釈は不要です。
+日本語で口語の文章で発言してください。
+{style_guidelines}</instructions>
+</devils_advocate>"#;
+
+const TPL_DISCUSSION_START: &str = r#"<discussion_start>
+<topic>{discussion_topic}</topic>
+<participants>{participants_list}</participants>
+
+<instructions>
+議論のテーマは「{discussion_topic}」です。
+参加者は{participants_list}です。
+
+議論を開始するための導入的な発言をしてください。以下の要素を含めてください：
+- 主張の提示
+- 主張の根拠
+- 参加者への問いかけ
+
+自然で建設的な議論の開始を促すような発言をお願いします。
+{style_guidelines}</instructions>
+</discussion_start>"#;
+
+const TPL_DISCUSSION_SUMMARY: &str = r#"<discussion_summary>
+<topic>{discussion_topic}</topic>
+<participants>{participants_list}</participants>
+
+<conversation_to_summarize>
+{conversation_history}
+</conversation_to_summarize>
+
+<instructions>
+以下の議論を要約してください。テーマは「{discussion_topic}」です。
+
+重要：各参加者の「立場」を固定化せず、「議論の争点」を中心に要約してください。
+
+要約に含めるべき要素：
+1. 議論で浮上した主要な争点・論点
+2. 提起された具体例や事例
+3. 検証が必要な仮定や課題
+4. 参加者間で生まれた疑問や質問
+5. 未解決の問題や深掘りが必要な点
+
+要約は以下の形式で出力してください：
+
+【議論の争点】
+- 争点1: [具体的な論点]
+- 争点2: [具体的な論点]
+
+【提起された具体例・事例】
+- [具体例1]
+- [具体例2]
+
+【検証が必要な仮定】
+- [仮定1]: [検証ポイント]
+- [仮定2]: [検証ポイント]
+
+【未解決の課題】
+- [課題1]: [深掘りの必要性]
+- [課題2]: [検討が必要な理由]
+
+【次の議論の方向性】
+- [継続すべき論点]
+- [新たに検討すべき視点]
+
+この要約により、議論が深化し続けるようにしてください。
+{style_guidelines}</instructions>
+</discussion_summary>"#;
+
+const TPL_INCREMENTAL_SUMMARY: &str = r#"<incremental_discussion_summary>
+<topic>{discussion_topic}</topic>
+<participants>{participants_list}</participants>
+
+<previous_summary>
+{previous_summary}
+</previous_summary>
+
+<new_messages>
+{new_messages}
+</new_messages>
+
+<instructions>
+上記の previous_summary はこれまでの議論の要約です。new_messages は今回新たに追加された発言のみです。
+これらを統合し、同じフォーマット/粒度で最新の包括的要約を再生成してください。
+
+要件:
+- 既存の重要論点/未解決事項を維持しつつ、新規発言で追加/修正/解決された点を反映
+- 重複は統合し簡潔化
+- 以前の要約から削除すべき内容が明確な場合のみ削除（根拠のなく失われた情報は削除しない）
+- 形式は従来の【議論の争点】【提起された具体例・事例】... 等の見出し構造をそのまま踏襲
+- 追加された具体例/仮定/未解決課題を適切なセクションに組み込む
+- 出力は完全な最新要約のみ（差分表示や説明文を含めない）
+{style_guidelines}</instructions>
+</incremental_discussion_summary>"#;
+
+const TPL_SUMMARY_MERGE: &str = r#"<summary_merge>
+<topic>{discussion_topic}</topic>
+<participants>{participants_list}</participants>
+
+<partial_summaries>
+{partial_summaries}
+</partial_summaries>
+
+<instructions>
+partial_summaries は、長い議論を前から順に区切って部分ごとに要約したものです（part の番号が時系列順）。
+これらを統合し、議論全体の要約を1つ作成してください。
+
+要件:
+- 各部分の重要な争点・具体例・仮定・未解決の課題を漏らさずに統合
+- 後の部分で解決・修正された点は最新の状態に合わせる
+- 部分をまたいで重複する内容は1つにまとめる
+- 形式は【議論の争点】【提起された具体例・事例】【検証が必要な仮定】【未解決の課題】【次の議論の方向性】の見出し構造にする
+- 出力は統合した要約のみ（部分ごとの区切りや説明文を含めない）
+{style_guidelines}</instructions>
+</summary_merge>"#;
+
+const TPL_NEXT_SPEAKER: &str = r#"<next_speaker_selection>
+<topic>{discussion_topic}</topic>
+
+<candidates>
+{participants_list}
+</candidates>
+
+<unresolved_conflicts>
+{conflicts}
+</unresolved_conflicts>
+
+<recent_conversation>
+{conversation_history}
+</recent_conversation>
+
+<instructions>
+あなたは議論の司会者です。candidates の中から、次に発言すると議論が最も深まる参加者を1人選んでください。
+
+判断の観点：
+- 直前の発言で名指しされた・質問された参加者は優先して答えさせる
+- まだ発言していない参加者、しばらく発言していない参加者に機会を与える
+- unresolved_conflicts の当事者に発言させ、対立点を掘り下げる
+- 直前の発言者を続けて選ばない
+
+JSON形式で以下の構造のみを出力してください：
+
+{
+  "speaker": "candidates の name のいずれか（表記はそのまま）",
+  "reason": "選んだ理由（1文）"
+}
+
+重要：
+- speaker は candidates の name と完全に一致させること
+- 必ず有効なJSON形式で応答すること
+</instructions>
+</next_speaker_selection>"#;
+
+const TPL_SESSION_QA: &str = r#"<session_question_answering>
+<topic>{discussion_topic}</topic>
+
+<summary>
+{summary}
+</summary>
+
+<excerpts>
+{excerpts}
+</excerpts>
+
+<question>{question}</question>
+
+<instructions>
+保存された議論について question に答えてください。summary は議論全体の要約、excerpts は質問に関係しそうな発言の抜粋です（各発言の先頭の [#番号] が発言番号）。
+
+要件：
+- summary と excerpts に書かれていることだけを根拠に答える。推測で補わない
+- 根拠にした発言は本文中で [#番号] の形で示し、references にその番号を並べる
+- 記録から答えが分からない場合は、分からないことをはっきり述べる
+- 簡潔に答える（長くても数段落）
+
+JSON形式で以下の構造のみを出力してください：
+
+{
+  "answer": "質問への回答（根拠の発言を [#番号] で示す）",
+  "references": [1, 2]
+}
+
+重要：
+- references には excerpts にある発言番号だけを含めること
+- 必ず有効なJSON形式で応答すること
+</instructions>
+</session_question_answering>"#;
+
+const TPL_SESSION_TAGS: &str = r#"<session_tagging>
+<topic>{discussion_topic}</topic>
+
+<summary>
+{summary}
+</summary>
+
+<instructions>
+議論を後から探しやすくするためのキーワード（タグ）を3〜5個挙げてください。
+
+要件：
+- 議論の主題・分野・主要な論点を表す短い語句（15文字以内）
+- 参加者名や「議論」「意見」「AI」など一般的すぎる語は避ける
+- 同じ意味の語を重複させない
+
+JSON形式で以下の構造のみを出力してください：
+
+{
+  "tags": ["キーワード1", "キーワード2", "キーワード3"]
+}
+
+重要：
+- tags は3〜5個にすること
+- 必ず有効なJSON形式で応答すること
+</instructions>
+</session_tagging>"#;
+
+const TPL_PARTICIPANT_MEMORY: &str = r#"<participant_memory_update>
+<participant name="{participant_name}" role="{role}"/>
+<topic>{discussion_topic}</topic>
+
+<previous_memory>
+{previous_memory}
+</previous_memory>
+
+<statements>
+{statements}
+</statements>
+
+<instructions>
+statements はこの参加者が今回の議論で述べた発言です。previous_memory（これまでの議論での記憶）に今回の内容を反映し、
+次の議論でもこの参加者が一貫した人物として振る舞えるよう、記憶を書き直してください。
+
+要件:
+- 取った立場・主張（どのテーマでどんな意見か）と、議論を通じて知った事実・考えを改めた点を残す
+- 「- 」で始まる箇条書きで最大10項目、全体で{max_chars}文字以内
+- 以前の記憶と食い違う点は今回の内容に更新し、重複する項目はまとめる
+- 他の参加者の名前や、やり取りの細部は残さない
+- 出力は記憶の箇条書きのみ（説明文を含めない）
+</instructions>
+</participant_memory_update>"#;
+
+const TPL_PERSONA_STATE: &str = r#"<persona_state_update>
+<topic>{discussion_topic}</topic>
+
+<participants>
+{participants}
+</participants>
+
+<new_messages>
+{new_messages}
+</new_messages>
+
+<instructions>
+participants は各参加者と前回時点の状態です。new_messages（その後の発言）を踏まえて、各参加者の今の状態を見積もってください。
+
+- agreement: 議論の大勢や他の参加者の意見にどれだけ同意しているか（0.0=強く反対〜1.0=完全に同意）
+- frustration: 苛立ち・もどかしさの強さ（0.0=落ち着いている〜1.0=強く苛立っている）。繰り返し反論されたり、主張を無視されたりすると高まり、受け入れられると下がる
+- note: 状態の理由を30文字以内で（例: 「二度反論されて苛立ち始めている」）
+
+要件：
+- participants に挙がっている全員について、name を変えずに答える
+- 急激に変えず、前回の状態から発言に応じて少しずつ動かす
+
+JSON形式で以下の構造のみを出力してください：
+
+{
+  "states": [
+    { "name": "参加者名", "agreement": 0.5, "frustration": 0.2, "note": "状態の理由" }
+  ]
+}
+</instructions>
+</persona_state_update>"#;
+
+const TPL_MODERATOR_INTERVENTION: &str = r#"<moderator_intervention>
+<topic>{discussion_topic}</topic>
+<moderator>{moderator_name}</moderator>
+<participants>{participants_list}</participants>
+
+<conversation_history>
+{conversation_history}
+</conversation_history>
+
+<situation>
+{situation}
+</situation>
+
+<instructions>
+あなたはこの議論の司会者（{moderator_name}）です。situation に書かれた議論の状況に対処するため、短く介入してください。
+
+要件:
+- 2〜4文で、どの参加者の立場にも肩入れしない
+- 状況を穏やかに指摘し、次に何を話し合うかを具体的に示す（発言の少ない参加者がいれば名前を挙げて意見を促す）
+- 自分の意見や議論の結論を述べない
+- 出力は発言内容のみ（名前や「司会:」などの前置きを付けない）
+{style_guidelines}</instructions>
+</moderator_intervention>"#;
+
+const TPL_TOPIC_DRIFT: &str = r#"<topic_drift_check>
+<topic>{discussion_topic}</topic>
+
+<recent_messages>
+{recent_messages}
+</recent_messages>
+
+<instructions>
+直近の発言が、議論のテーマからどれだけ逸れているかを判定してください。
+
+要件：
+- drift はテーマからの離れ具合（0.0 = テーマどおり、0.5 = 関連はあるが周辺の話題が中心、1.0 = 完全に別の話題）
+- テーマを掘り下げるための具体例や関連する論点は脱線とみなさない
+- tangent は逸れていった話題を30文字以内で書く（テーマどおりなら空文字）
+
+JSON形式で以下の構造のみを出力してください：
+
+{
+  "drift": 0.0,
+  "tangent": ""
+}
+
+重要：
+- 必ず有効なJSON形式で応答すること
+</instructions>
+</topic_drift_check>"#;
+
+const TPL_ACTION_ITEMS: &str = r#"<action_item_extraction>
+<topic>{discussion_topic}</topic>
+<participants>{participants_list}</participants>
+
+<conversation_history>
+{conversation_history}
+</conversation_history>
+
+<instructions>
+この議論で決まった、または実行が提案された具体的な行動（アクションアイテム）を抜き出してください。
+
+要件：
+- description は「誰かが実際に行うこと」を動詞で終わる1文で書く（80文字以内）
+- owner は担当として名前が挙がった参加者（明示されていなければ空文字）
+- due_hint は期限・時期の手がかり（「来週まで」「次回の会議前」など。なければ空文字）
+- 意見・感想・結論だけのものは含めない。同じ内容を重複させない
+- 該当がなければ items を空の配列にする
+
+JSON形式で以下の構造のみを出力してください：
+
+{
+  "items": [
+    { "description": "行うこと", "owner": "担当者", "due_hint": "期限の手がかり" }
+  ]
+}
+
+重要：
+- 必ず有効なJSON形式で応答すること
+</instructions>
+</action_item_extraction>"#;
+
+const TPL_AI_PROFILES_REPAIR: &str = r#"<ai_profiles_repair>
+<topic>{discussion_topic}</topic>
+<count>{count}</count>
+
+<previous_output>
+{previous_output}
+</previous_output>
+
+<problems>
+{problems}
+</problems>
+
+<instructions>
+previous_output は、議論テーマに適したAI参加者プロフィールを{count}名分生成しようとした出力ですが、problems の問題があり使えませんでした。
+問題を直したプロフィールを{count}名分、JSON配列のみで出力してください。
+
+要件：
+- 各要素は name, role, description のキーを持ち、どれも空にしない
+- name は参加者ごとに異なる短い日本語の名前にする
+- description は100文字前後で、その人物の視点・価値観・発言スタイルを説明する
+- 問題のない参加者はできるだけそのまま残す
+
+出力フォーマット（必ず純粋なJSONのみにしてください。）：
+
+[
+  { "name": "", "role": "", "description": "" }
+]
+</instructions>
+</ai_profiles_repair>"#;
+
+/// 利用者が編集できるプロンプトテンプレートの種類（DB の prompt_templates.kind）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TemplateKind {
+    AiResponse,
+    DevilsAdvocate,
+    DiscussionStart,
+    DiscussionAnalysis,
+    IncrementalAnalysis,
+    DiscussionSummary,
+    IncrementalSummary,
+    SummaryMerge,
+    AiProfiles,
+    NextSpeaker,
+    SessionQa,
+    SessionTags,
+    ParticipantMemory,
+    PersonaState,
+    ModeratorIntervention,
+    TopicDrift,
+    ActionItems,
+    AiProfilesRepair,
+}
+
+impl TemplateKind {
+    pub const ALL: [TemplateKind; 18] = [
+        TemplateKind::AiResponse,
+        TemplateKind::DevilsAdvocate,
+        TemplateKind::DiscussionStart,
+        TemplateKind::DiscussionAnalysis,
+        TemplateKind::IncrementalAnalysis,
+        TemplateKind::DiscussionSummary,
+        TemplateKind::IncrementalSummary,
+        TemplateKind::SummaryMerge,
+        TemplateKind::AiProfiles,
+        TemplateKind::NextSpeaker,
+        TemplateKind::SessionQa,
+        TemplateKind::SessionTags,
+        TemplateKind::ParticipantMemory,
+        TemplateKind::PersonaState,
+        TemplateKind::ModeratorIntervention,
+        TemplateKind::TopicDrift,
+        TemplateKind::ActionItems,
+        TemplateKind::AiProfilesRepair,
+    ];
+
+    /// DB・コマンドで使う名前
+    pub fn key(self) -> &'static str {
+        match self {
+            TemplateKind::AiResponse => "ai_response",
+            TemplateKind::DevilsAdvocate => "devils_advocate",
+            TemplateKind::DiscussionStart => "discussion_start",
+            TemplateKind::DiscussionAnalysis => "discussion_analysis",
+            TemplateKind::IncrementalAnalysis => "incremental_analysis",
+            TemplateKind::DiscussionSummary => "discussion_summary",
+            TemplateKind::IncrementalSummary => "incremental_summary",
+            TemplateKind::SummaryMerge => "summary_merge",
+            TemplateKind::AiProfiles => "ai_profiles",
+            TemplateKind::NextSpeaker => "next_speaker",
+            TemplateKind::SessionQa => "session_qa",
+            TemplateKind::SessionTags => "session_tags",
+            TemplateKind::ParticipantMemory => "participant_memory",
+            TemplateKind::PersonaState => "persona_state",
+            TemplateKind::ModeratorIntervention => "moderator_intervention",
+            TemplateKind::TopicDrift => "topic_drift",
+            TemplateKind::ActionItems => "action_items",
+            TemplateKind::AiProfilesRepair => "ai_profiles_repair",
+        }
+    }
+
+    pub fn parse(key: &str) -> Result<Self, String> {
+        Self::ALL
+            .into_iter()
+            .find(|k| k.key() == key.trim())
+            .ok_or_else(|| format!("テンプレートの種類が不正です: '{}'", key))
+    }
+
+    /// 組み込みのテンプレート
+    pub fn default_body(self) -> &'static str {
+        match self {
+            TemplateKind::AiResponse => TPL_AI_RESPONSE,
+            TemplateKind::DevilsAdvocate => TPL_DEVILS_ADVOCATE,
+            TemplateKind::DiscussionStart => TPL_DISCUSSION_START,
+            TemplateKind::DiscussionAnalysis => TPL_DISCUSSION_ANALYSIS,
+            TemplateKind::IncrementalAnalysis => TPL_INCREMENTAL_ANALYSIS,
+            TemplateKind::DiscussionSummary => TPL_DISCUSSION_SUMMARY,
+            TemplateKind::IncrementalSummary => TPL_INCREMENTAL_SUMMARY,
+            TemplateKind::SummaryMerge => TPL_SUMMARY_MERGE,
+            TemplateKind::AiProfiles => TPL_AI_PROFILES,
+            TemplateKind::NextSpeaker => TPL_NEXT_SPEAKER,
+            TemplateKind::SessionQa => TPL_SESSION_QA,
+            TemplateKind::SessionTags => TPL_SESSION_TAGS,
+            TemplateKind::ParticipantMemory => TPL_PARTICIPANT_MEMORY,
+            TemplateKind::PersonaState => TPL_PERSONA_STATE,
+            TemplateKind::ModeratorIntervention => TPL_MODERATOR_INTERVENTION,
+            TemplateKind::TopicDrift => TPL_TOPIC_DRIFT,
+            TemplateKind::ActionItems => TPL_ACTION_ITEMS,
+            TemplateKind::AiProfilesRepair => TPL_AI_PROFILES_REPAIR,
+        }
+    }
+
+    /// 使えるプレースホルダー
+    pub fn placeholders(self) -> &'static [&'static str] {
+        match self {
+            TemplateKind::AiResponse => &[
+                "discussion_topic", "participant_name", "role", "description", "user_profile", "conversation_history",
+                "style_guidelines",
+            ],
+            TemplateKind::DevilsAdvocate => &[
+                "discussion_topic", "participant_name", "role", "description", "consensus", "conversation_history",
+                "style_guidelines",
+            ],
+            TemplateKind::DiscussionStart => &["discussion_topic", "participants_list", "style_guidelines"],
+            TemplateKind::DiscussionAnalysis => &["discussion_topic", "participants_list", "conversation_history"],
+            TemplateKind::IncrementalAnalysis => {
+                &["discussion_topic", "participants_list", "previous_analysis", "new_messages"]
+            }
+            TemplateKind::DiscussionSummary => {
+                &["discussion_topic", "participants_list", "conversation_history", "style_guidelines"]
+            }
+            TemplateKind::IncrementalSummary => {
+                &["discussion_topic", "participants_list", "previous_summary", "new_messages", "style_guidelines"]
+            }
+            TemplateKind::SummaryMerge => {
+                &["discussion_topic", "participants_list", "partial_summaries", "style_guidelines"]
+            }
+            TemplateKind::AiProfiles => &["discussion_topic", "count", "hint_line", "constraints"],
+            TemplateKind::NextSpeaker => &["discussion_topic", "participants_list", "conflicts", "conversation_history"],
+            TemplateKind::SessionQa => &["discussion_topic", "summary", "excerpts", "question"],
+            TemplateKind::SessionTags => &["discussion_topic", "summary"],
+            TemplateKind::ParticipantMemory => {
+                &["participant_name", "role", "discussion_topic", "previous_memory", "statements", "max_chars"]
+            }
+            TemplateKind::PersonaState => &["discussion_topic", "participants", "new_messages"],
+            TemplateKind::ModeratorIntervention => &[
+                "discussion_topic",
+                "moderator_name",
+                "participants_list",
+                "conversation_history",
+                "situation",
+                "style_guidelines",
+            ],
+            TemplateKind::TopicDrift => &["discussion_topic", "recent_messages"],
+            TemplateKind::ActionItems => &["discussion_topic", "participants_list", "conversation_history"],
+            TemplateKind::AiProfilesRepair => &["discussion_topic", "count", "previous_output", "problems"],
+        }
+    }
+
+    /// 省くとプロンプトが成り立たないプレースホルダー
+    pub fn required_placeholders(self) -> &'static [&'static str] {
+        match self {
+            TemplateKind::AiResponse => &["discussion_topic", "participant_name", "conversation_history"],
+            TemplateKind::DevilsAdvocate => &["discussion_topic", "participant_name", "consensus", "conversation_history"],
+            TemplateKind::DiscussionStart => &["discussion_topic", "participants_list"],
+            TemplateKind::DiscussionAnalysis => &["discussion_topic", "conversation_history"],
+            TemplateKind::IncrementalAnalysis => &["discussion_topic", "previous_analysis", "new_messages"],
+            TemplateKind::DiscussionSummary => &["discussion_topic", "conversation_history"],
+            TemplateKind::IncrementalSummary => &["discussion_topic", "previous_summary", "new_messages"],
+            TemplateKind::SummaryMerge => &["partial_summaries"],
+            TemplateKind::AiProfiles => &["discussion_topic", "count"],
+            TemplateKind::NextSpeaker => &["participants_list", "conversation_history"],
+            TemplateKind::SessionQa => &["excerpts", "question"],
+            TemplateKind::SessionTags => &["summary"],
+            TemplateKind::ParticipantMemory => &["statements"],
+            TemplateKind::PersonaState => &["participants", "new_messages"],
+            TemplateKind::ModeratorIntervention => &["situation", "conversation_history"],
+            TemplateKind::TopicDrift => &["discussion_topic", "recent_messages"],
+            TemplateKind::ActionItems => &["conversation_history"],
+            TemplateKind::AiProfilesRepair => &["count", "previous_output", "problems"],
+        }
+    }
+}
+
+/// 編集されたテンプレート（DB から読み込んで反映する。無ければ組み込みを使う）
+fn template_slot() -> &'static RwLock<HashMap<TemplateKind, String>> {
+    static SLOT: OnceLock<RwLock<HashMap<TemplateKind, String>>> = OnceLock::new();
+    SLOT.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+/// 編集されたテンプレートを反映する（None で組み込みに戻す）
+pub fn set_template_override(kind: TemplateKind, body: Option<String>) {
+    let mut slot = template_slot().write().unwrap_or_else(|e| e.into_inner());
+    match body {
+        Some(body) => slot.insert(kind, body),
+        None => slot.remove(&kind),
+    };
+}
+
+/// 現在使うテンプレート
+pub fn template(kind: TemplateKind) -> String {
+    let slot = template_slot().read().unwrap_or_else(|e| e.into_inner());
+    slot.get(&kind).cloned().unwrap_or_else(|| kind.default_body().to_string())
+}
+
+/// テンプレート中の {名前} を列挙する（JSON の波括弧などは対象外）
+fn placeholders_in(body: &str) -> Vec<&str> {
+    let mut found = Vec::new();
+    let mut rest = body;
+    while let Some(start) = rest.find('{') {
+        rest = &rest[start + 1..];
+        let len = rest.find(|c: char| !(c.is_ascii_lowercase() || c == '_')).unwrap_or(rest.len());
+        if len > 0 && rest[len..].starts_with('}') && !found.contains(&&rest[..len]) {
+            found.push(&rest[..len]);
+        }
+    }
+    found
+}
+
+/// 編集されたテンプレートを検証（必須のプレースホルダーの欠落と未知のプレースホルダーを拒否）
+pub fn validate_template(kind: TemplateKind, body: &str) -> Result<(), String> {
+    if body.trim().is_empty() {
+        return Err("テンプレートが空です".into());
+    }
+    let used = placeholders_in(body);
+    let missing: Vec<String> =
+        kind.required_placeholders().iter().filter(|p| !used.contains(p)).map(|p| format!("{{{}}}", p)).collect();
+    if !missing.is_empty() {
+        return Err(format!("必須のプレースホルダーがありません: {}", missing.join(", ")));
+    }
+    let unknown: Vec<String> =
+        used.iter().filter(|p| !kind.placeholders().contains(p)).map(|p| format!("{{{}}}", p)).collect();
+    if !unknown.is_empty() {
+        return Err(format!(
+            "使えないプレースホルダーです: {}（使えるもの: {}）",
+            unknown.join(", "),
+            kind.placeholders().iter().map(|p| format!("{{{}}}", p)).collect::<Vec<_>>().join(", ")
+        ));
+    }
+    Ok(())
+}
+
+/// プレースホルダーを1回の走査で置換（差し込んだ値の中の {..} は置換しない）
+fn render(template: &str, values: &[(&str, &str)]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let value = rest[1..]
+            .find('}')
+            .and_then(|end| values.iter().find(|(name, _)| *name == &rest[1..end + 1]).map(|(_, v)| (end, *v)));
+        match value {
+            Some((end, v)) => {
+                out.push_str(v);
+                rest = &rest[end + 2..];
+            }
+            None => {
+                out.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+// XMLエスケープ（最低限）
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+     .replace('<', "&lt;")
+     .replace('>', "&gt;")
+     .replace('"', "&quot;")
+     .replace('\'', "&apos;")
+}
+
+const PLAIN_LANGUAGE_GUIDE: &str = "- やさしい日本語で書く：一文は短く（目安30文字以内）、一文に一つの内容だけを書く
+- 専門用語・カタカナ語・難しい漢語は避け、使う場合はすぐ後に簡単な言葉で言い換える
+- 小学校高学年で習う程度の漢字と語彙を中心にし、ふりがなを振りやすい言葉を選ぶ
+- 二重否定や遠回しな表現を使わない";
+
+const PLAIN_ENGLISH_GUIDE: &str = "- Write in plain English: keep sentences short and put one idea in each sentence
+- Avoid jargon and idioms; if a technical term is needed, explain it right away in simple words
+- Prefer common, everyday words that a young or non-native reader knows
+- Avoid double negatives and indirect phrasing";
+
+/// 出力言語（議論・要約・分析 JSON の値をこの言語で書かせる）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    #[default]
+    Ja,
+    En,
+}
+
+impl Language {
+    /// やさしい言葉モードの指示
+    fn plain_language_guide(self) -> &'static str {
+        match self {
+            Language::Ja => PLAIN_LANGUAGE_GUIDE,
+            Language::En => PLAIN_ENGLISH_GUIDE,
+        }
+    }
+
+    /// テンプレートの日本語出力の指示を上書きする指示（日本語なら不要）
+    fn output_directive(self) -> Option<&'static str> {
+        match self {
+            Language::Ja => None,
+            Language::En => Some(
+                "Write your entire response in English, including every JSON string value (keep JSON keys exactly as specified). \
+This overrides any instruction above that asks for Japanese output. Quote names and text from the conversation as they appear.",
+            ),
+        }
+    }
+}
+
+/// 出力言語の既定値（アプリ設定の language。セッション設定で上書きできる）
+fn language_slot() -> &'static RwLock<Language> {
+    static SLOT: OnceLock<RwLock<Language>> = OnceLock::new();
+    SLOT.get_or_init(|| RwLock::new(Language::default()))
+}
+
+/// 出力言語の既定値を反映（起動時と設定保存時）
+pub fn set_default_language(language: Language) {
+    *language_slot().write().unwrap_or_else(|e| e.into_inner()) = language;
+}
+
+/// 出力言語の既定値
+pub fn default_language() -> Language {
+    *language_slot().read().unwrap_or_else(|e| e.into_inner())
+}
+
+// ユーザー自己紹介の項目ごとの上限文字数（発言プロンプトに毎回入るため短く保つ）
+const MAX_USER_NAME_CHARS: usize = 30;
+const MAX_USER_ROLE_CHARS: usize = 60;
+const MAX_USER_STANCE_CHARS: usize = 200;
+
+/// 人間の参加者（ユーザー）の自己紹介（アプリ設定の userPersona）
+/// 発言プロンプトの <user_profile> に入り、AI参加者が名前で呼びかけ、専門・立場を踏まえて応答する
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct UserPersona {
+    pub name: String,
+    /// 役職・専門（例: "小学校教員"）
+    pub role: String,
+    /// テーマへの立場・考え方（例: "導入には慎重"）
+    pub stance: String,
+}
+
+impl UserPersona {
+    /// 前後の空白を除き、長すぎる項目を切り詰める
+    pub fn sanitized(mut self) -> Self {
+        for (value, max) in [
+            (&mut self.name, MAX_USER_NAME_CHARS),
+            (&mut self.role, MAX_USER_ROLE_CHARS),
+            (&mut self.stance, MAX_USER_STANCE_CHARS),
+        ] {
+            *value = value.trim().chars().take(max).collect();
+        }
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.name.is_empty() && self.role.is_empty() && self.stance.is_empty()
+    }
+
+    /// <user_profile> の中身
+    fn profile_block(&self) -> String {
+        if self.is_empty() {
+            return "（登録されていません。「ユーザー」と呼んでください）".to_string();
+        }
+        let mut lines = Vec::new();
+        for (label, value) in [("名前", &self.name), ("役職・専門", &self.role), ("立場", &self.stance)] {
+            if !value.is_empty() {
+                lines.push(format!("{}: {}", label, xml_escape(value)));
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+fn user_persona_slot() -> &'static RwLock<UserPersona> {
+    static SLOT: OnceLock<RwLock<UserPersona>> = OnceLock::new();
+    SLOT.get_or_init(|| RwLock::new(UserPersona::default()))
+}
+
+/// ユーザーの自己紹介を設定（アプリ設定の読み込み・保存時）
+pub fn set_user_persona(persona: &UserPersona) {
+    *user_persona_slot().write().unwrap_or_else(|e| e.into_inner()) = persona.clone();
+}
+
+/// 発言プロンプトに使うユーザーの自己紹介
+pub fn user_persona() -> UserPersona {
+    user_persona_slot().read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// 日本語以外の出力言語なら、プロンプト末尾に出力言語の指示を付ける
+fn with_language(prompt: String, language: Language) -> String {
+    match language.output_directive() {
+        Some(directive) => format!("{}\n<output_language>\n{}\n</output_language>", prompt, directive),
+        None => prompt,
+    }
+}
+
+/// 添付資料の抜粋（with_reference_material の入力）
+pub struct ReferenceChunk {
+    /// 資料名（ファイル名）
+    pub source: String,
+    pub content: String,
+}
+
+/// 添付資料の抜粋を <reference_material> としてプロンプトに差し込む（<instructions> の直前。なければ末尾）
+pub fn with_reference_material(prompt: String, chunks: &[ReferenceChunk]) -> String {
+    if chunks.is_empty() {
+        return prompt;
+    }
+    let excerpts = chunks
+        .iter()
+        .map(|c| format!("<excerpt source=\"{}\">\n{}\n</excerpt>", xml_escape(&c.source), xml_escape(c.content.trim())))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let block = format!(
+        "<reference_material>\n{}\n<usage>議論のテーマに関する添付資料の抜粋です。資料に基づく内容は資料名に触れて述べ、資料に書かれていないことを資料の内容として述べないでください。</usage>\n</reference_material>",
+        excerpts
+    );
+    match prompt.find("<instructions>") {
+        Some(at) => format!("{}{}\n\n{}", &prompt[..at], block, &prompt[at..]),
+        None => format!("{}\n{}", prompt, block),
+    }
+}
+
+/// 参加者の過去の議論での記憶を <participant_memory> としてプロンプトに差し込む（<instructions> の直前。なければ末尾）
+pub fn with_participant_memory(prompt: String, memory: Option<&str>) -> String {
+    let Some(memory) = memory.map(str::trim).filter(|m| !m.is_empty()) else {
+        return prompt;
+    };
+    let block = format!(
+        "<participant_memory>\n{}\n<usage>あなたが過去の議論で取った立場や知ったことの記憶です。考えを改める理由がなければこれと一貫した立場で発言してください。記憶にない過去の発言を作り上げないでください。</usage>\n</participant_memory>",
+        xml_escape(memory)
+    );
+    match prompt.find("<instructions>") {
+        Some(at) => format!("{}{}\n\n{}", &prompt[..at], block, &prompt[at..]),
+        None => format!("{}\n{}", prompt, block),
+    }
+}
+
+/// 議論の中での今の状態（同意度・苛立ち 0〜1 と理由）を <current_state> としてプロンプトに差し込む（<instructions> の直前。なければ末尾）
+pub fn with_persona_state(prompt: String, state: Option<PersonaStateArgs>) -> String {
+    let Some((agreement, frustration, note)) = state else {
+        return prompt;
+    };
+    let block = format!(
+        "<current_state agreement=\"{:.2}\" frustration=\"{:.2}\">{}\n<usage>ここまでの議論でのあなたの気持ちです（agreement: 他の意見への同意の度合い、frustration: 苛立ちの強さ。いずれも0〜1）。語調や反応に自然ににじませ、状態そのものを数値で口にしないでください。</usage>\n</current_state>",
+        agreement,
+        frustration,
+        xml_escape(note)
+    );
+    match prompt.find("<instructions>") {
+        Some(at) => format!("{}{}\n\n{}", &prompt[..at], block, &prompt[at..]),
+        None => format!("{}\n{}", prompt, block),
+    }
+}
+
+/// 添付画像があることを <attached_images> としてプロンプトに伝える（<instructions> の直前。なければ末尾）
+pub fn with_attached_images(prompt: String, count: usize) -> String {
+    if count == 0 {
+        return prompt;
+    }
+    let block = format!(
+        "<attached_images count=\"{}\">ユーザーが会話に画像を{}枚添付しました。グラフやスクリーンショットなどの内容を読み取り、議論に関係する点に触れて発言してください。画像から読み取れないことを推測で断定しないでください。</attached_images>",
+        count, count
+    );
+    match prompt.find("<instructions>") {
+        Some(at) => format!("{}{}\n\n{}", &prompt[..at], block, &prompt[at..]),
+        None => format!("{}\n{}", prompt, block),
+    }
+}
+
+// 会話履歴に割り当てるトークン数（Ollama 既定の num_ctx から指示文と出力の分を差し引いた量）
+// gemma3:1b を含め、num_ctx 未指定でも履歴が黙って切り捨てられないようにする
+pub const HISTORY_TOKEN_BUDGET: usize = tokens::DEFAULT_NUM_CTX / 2;
+// PromptVersion::Compact 用の履歴予算
+const COMPACT_HISTORY_TOKEN_BUDGET: usize = tokens::DEFAULT_NUM_CTX / 4;
+
+/// 想定読者の読解レベル
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReadingLevel {
+    Elementary,
+    HighSchool,
+    Expert,
+}
+
+impl ReadingLevel {
+    /// プロンプトに差し込む読解レベルの指示
+    pub fn guideline(self) -> &'static str {
+        match self {
+            ReadingLevel::Elementary => "- 読み手は小学生：一文は短く、身近な例えを使い、難しい漢字や専門用語は使わない",
+            ReadingLevel::HighSchool => "- 読み手は高校生：一般的な語彙で書き、専門用語を使うときは一言で意味を添える",
+            ReadingLevel::Expert => "- 読み手は専門家：正確な専門用語を用い、前提知識の説明は省いてよい",
+        }
+    }
+}
+
+/// 出力スタイル指定（セッション設定から組み立てて各 build_* に渡す）
+#[derive(Debug, Clone, Default)]
+pub struct PromptStyle {
+    /// やさしい日本語モード
+    pub plain_language: bool,
+    /// 想定読者の読解レベル（未指定なら指示なし）
+    pub reading_level: Option<ReadingLevel>,
+    /// 出力言語
+    pub language: Language,
+}
+
+impl PromptStyle {
+    /// instructions 末尾に差し込むスタイル指示（指定がなければ空文字）
+    fn guidelines(&self) -> String {
+        let mut lines: Vec<&str> = Vec::new();
+        if self.plain_language {
+            lines.push(self.language.plain_language_guide());
+        }
+        if let Some(level) = self.reading_level {
+            lines.push(level.guideline());
+        }
+        if lines.is_empty() {
+            return String::new();
+        }
+        format!("\n<style_guidelines>\n{}\n</style_guidelines>\n", lines.join("\n"))
+    }
+}
+
+/// AI応答生成用のプロンプトテンプレートを構築
+pub fn build_ai_response_prompt(
+    participant_name: &str,
+    role: &str,
+    description: &str,
+    conversation_history: &str,
+    discussion_topic: &str,
+    style: &PromptStyle,
+) -> String {
+    let formatted_history = if conversation_history.is_empty() {
+        "まだ発言はありません。議論を開始してください。".to_string()
+    } else {
+        // 会話履歴を最適化（最新15発言程度に制限してパフォーマンス向上）
+        optimize_conversation_for_analysis(conversation_history, HISTORY_TOKEN_BUDGET)
+    };
+    let topic_e = xml_escape(discussion_topic);
+    let name_e = xml_escape(participant_name);
+    let role_e = xml_escape(role);
+    let desc_e = xml_escape(description);
+    let hist_e = xml_escape(&formatted_history);
+
+    let prompt = render(&template(TemplateKind::AiResponse), &[
+        ("discussion_topic", &topic_e),
+        ("participant_name", &name_e),
+        ("role", &role_e),
+        ("description", &desc_e),
+        ("user_profile", &user_persona().profile_block()),
+        ("conversation_history", &hist_e),
+        ("style_guidelines", &style.guidelines()),
+    ]);
+    with_language(prompt, style.language)
+}
+
+/// 反論役（悪魔の代弁者）の発言プロンプト
+/// consensus は最新の分析から得た現在の合意（空ならモデル自身に会話履歴から見極めさせる）
+pub fn build_devils_advocate_prompt(
+    participant_name: &str,
+    role: &str,
+    description: &str,
+    conversation_history: &str,
+    discussion_topic: &str,
+    consensus: &[String],
+    style: &PromptStyle,
+) -> String {
+    let formatted_history = if conversation_history.is_empty() {
+        "まだ発言はありません。".to_string()
+    } else {
+        optimize_conversation_for_analysis(conversation_history, HISTORY_TOKEN_BUDGET)
+    };
+    let consensus_block = if consensus.is_empty() {
+        "（分析結果なし。会話履歴から最も支持を集めている見解を自分で見極めること）".to_string()
+    } else {
+        consensus.iter().map(|c| format!("- {}", xml_escape(c))).collect::<Vec<_>>().join("\n")
+    };
+
+    let prompt = render(&template(TemplateKind::DevilsAdvocate), &[
+        ("discussion_topic", &xml_escape(discussion_topic)),
+        ("participant_name", &xml_escape(participant_name)),
+        ("role", &xml_escape(role)),
+        ("description", &xml_escape(description)),
+        ("consensus", &consensus_block),
+        ("conversation_history", &xml_escape(&formatted_history)),
+        ("style_guidelines", &style.guidelines()),
+    ]);
+    with_language(prompt, style.language)
+}
+
+/// 形式のある議論の1フェーズ分の指示（formats.rs がフェーズごとに組み立てる）
+pub struct PhaseInstructions {
+    /// 議論形式の名前（例: ディベート）
+    pub format_name: &'static str,
+    /// フェーズの名前（例: 立論）
+    pub phase_name: &'static str,
+    /// このフェーズでの参加者の役割（例: 肯定側、白い帽子）
+    pub assignment: String,
+    /// このフェーズで発言に求めること
+    pub instructions: String,
+}
+
+/// 形式のある議論（ディベート・ブレインストーミング・六つの帽子）の発言プロンプト
+pub fn build_format_phase_prompt(
+    participant_name: &str,
+    role: &str,
+    description: &str,
+    conversation_history: &str,
+    discussion_topic: &str,
+    phase: &PhaseInstructions,
+    style: &PromptStyle,
+) -> String {
+    let formatted_history = if conversation_history.is_empty() {
+        "まだ発言はありません。".to_string()
+    } else {
+        optimize_conversation_for_analysis(conversation_history, HISTORY_TOKEN_BUDGET)
+    };
+
+    let prompt = format!(
+        r#"<formatted_discussion>
+<discussion_topic>{discussion_topic}</discussion_topic>
+<format>{format_name}</format>
+<phase>{phase_name}</phase>
+
+<participant>
+<name>{participant_name}</name>
+<role>{role}</role>
+<description>{description}</description>
+<assignment>{assignment}</assignment>
+</participant>
+
+<conversation_history>
+{conversation_history}
+</conversation_history>
+
+<instructions>
+あなたは{participant_name}で、役職または職業が{role}です。{description}
+この議論は「{format_name}」の形式で進行しており、現在は「{phase_name}」のフェーズです。あなたの役割は「{assignment}」です。
+
+このフェーズでの発言：
+{phase_instructions}
+
+必須要件：
+- フェーズと役割から外れた発言をしない
+- {participant_name}らしい口調を保ち、発言は二言三言程度で短くする
+
+回答は{participant_name}の発言内容のみを返してください。説明や注釈は不要です。
+日本語で口語の文章で発言してください。
+{style_guidelines}</instructions>
+</formatted_discussion>"#,
+        discussion_topic = xml_escape(discussion_topic),
+        format_name = phase.format_name,
+        phase_name = phase.phase_name,
+        participant_name = xml_escape(participant_name),
+        role = xml_escape(role),
+        description = xml_escape(description),
+        assignment = xml_escape(&phase.assignment),
+        conversation_history = xml_escape(&formatted_history),
+        phase_instructions = phase.instructions,
+        style_guidelines = style.guidelines()
+    );
+    with_language(prompt, style.language)
+}
+
+/// AI応答プロンプトの版（パラメータ比較実験で切り替える）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PromptVersion {
+    /// 現行の build_ai_response_prompt
+    #[default]
+    Standard,
+    /// ガイドラインを省いた短いプロンプト（小さいモデル向け）
+    Compact,
+}
+
+/// 版を指定してAI応答プロンプトを構築
+pub fn build_ai_response_prompt_versioned(
+    version: PromptVersion,
+    participant_name: &str,
+    role: &str,
+    description: &str,
+    conversation_history: &str,
+    discussion_topic: &str,
+    style: &PromptStyle,
+) -> String {
+    if version == PromptVersion::Standard {
+        return build_ai_response_prompt(participant_name, role, description, conversation_history, discussion_topic, style);
+    }
+    let formatted_history = if conversation_history.is_empty() {
+        "まだ発言はありません。".to_string()
+    } else {
+        optimize_conversation_for_analysis(conversation_history, COMPACT_HISTORY_TOKEN_BUDGET)
+    };
+
+    let prompt = format!(
+        r#"<discussion_context>
+<discussion_topic>{discussion_topic}</discussion_topic>
+<conversation_history>
+{conversation_history}
+</conversation_history>
+<instructions>
+あなたは{participant_name}（{role}）です。{description}
+直前の発言に具体的に反応し、{participant_name}として一言二言で発言してください。
+発言内容のみを日本語の口語で返してください。
+{style_guidelines}</instructions>
+</discussion_context>"#,
+        discussion_topic = xml_escape(discussion_topic),
+        participant_name = xml_escape(participant_name),
+        role = xml_escape(role),
+        description = xml_escape(description),
+        conversation_history = xml_escape(&formatted_history),
+        style_guidelines = style.guidelines()
+    );
+    with_language(prompt, style.language)
+}
+
+/// 議論開始用のプロンプトテンプレートを構築
+pub fn build_discussion_start_prompt(topic: &str, participants: &[String], style: &PromptStyle) -> String {
+    let participants_list = participants.iter().map(|s| xml_escape(s)).collect::<Vec<_>>().join(", ");
+    let topic_e = xml_escape(topic);
+    
+    let prompt = render(&template(TemplateKind::DiscussionStart), &[
+        ("discussion_topic", &topic_e),
+        ("participants_list", &participants_list),
+        ("style_guidelines", &style.guidelines()),
+    ]);
+    with_language(prompt, style.language)
+}
+
+/// 議論分析用のプロンプトテンプレートを構築
+pub fn build_discussion_analysis_prompt(
+    discussion_topic: &str,
+    conversation_history: &str,
+    participants: &[String],
+    language: Language,
+) -> String {
+    let participants_list = participants.iter().map(|s| xml_escape(s)).collect::<Vec<_>>().join(", ");
+    let topic_e = xml_escape(discussion_topic);
+    let hist_e = xml_escape(conversation_history);
+
+    let prompt = render(&template(TemplateKind::DiscussionAnalysis), &[
+        ("discussion_topic", &topic_e),
+        ("participants_list", &participants_list),
+        ("conversation_history", &hist_e),
+    ]);
+    with_language(prompt, language)
+}
+
+/// インクリメンタル分析プロンプト（既存の分析 JSON + 差分発言を統合して最新の分析を再構築）
+pub fn build_incremental_analysis_prompt(
+    discussion_topic: &str,
+    previous_analysis: &str,
+    new_messages: &str,
+    participants: &[String],
+    language: Language,
+) -> String {
+    let participants_list = participants.iter().map(|s| xml_escape(s)).collect::<Vec<_>>().join(", ");
+
+    let prompt = render(&template(TemplateKind::IncrementalAnalysis), &[
+        ("discussion_topic", &xml_escape(discussion_topic)),
+        ("participants_list", &participants_list),
+        ("previous_analysis", &xml_escape(previous_analysis)),
+        ("new_messages", &xml_escape(new_messages)),
+    ]);
+    with_language(prompt, language)
+}
+
+/// 議論要約用のプロンプトテンプレートを構築
+pub fn build_discussion_summary_prompt(
+    discussion_topic: &str,
+    conversation_history: &str,
+    participants: &[String],
+    style: &PromptStyle,
+) -> String {
+    let participants_list = participants.iter().map(|s| xml_escape(s)).collect::<Vec<_>>().join(", ");
+    let topic_e = xml_escape(discussion_topic);
+    let hist_e = xml_escape(conversation_history);
+    
+    let prompt = render(&template(TemplateKind::DiscussionSummary), &[
+        ("discussion_topic", &topic_e),
+        ("participants_list", &participants_list),
+        ("conversation_history", &hist_e),
+        ("style_guidelines", &style.guidelines()),
+    ]);
+    with_language(prompt, style.language)
+}
+
+/// インクリメンタル要約プロンプト（既存要約 + 差分発言を統合して新しい要約を再構築）
+pub fn build_incremental_summary_prompt(
+    discussion_topic: &str,
+    previous_summary: &str,
+    new_messages: &str,
+    participants: &[String],
+    style: &PromptStyle,
+) -> String {
+    let topic_e = xml_escape(discussion_topic);
+    let prev_e = xml_escape(previous_summary);
+    let diff_e = xml_escape(new_messages);
+    let participants_list = participants.iter().map(|s| xml_escape(s)).collect::<Vec<_>>().join(", ");
+    let prompt = render(&template(TemplateKind::IncrementalSummary), &[
+        ("discussion_topic", &topic_e),
+        ("participants_list", &participants_list),
+        ("previous_summary", &prev_e),
+        ("new_messages", &diff_e),
+        ("style_guidelines", &style.guidelines()),
+    ]);
+    with_language(prompt, style.language)
+}
+
+/// 部分ごとの要約（時系列順）を1つの要約に統合するプロンプト（長い議論の分割要約の最後に使う）
+pub fn build_summary_merge_prompt(
+    discussion_topic: &str,
+    partial_summaries: &[String],
+    participants: &[String],
+    style: &PromptStyle,
+) -> String {
+    let participants_list = participants.iter().map(|s| xml_escape(s)).collect::<Vec<_>>().join(", ");
+    let partials = partial_summaries
+        .iter()
+        .enumerate()
+        .map(|(i, s)| format!("<part index=\"{}\">\n{}\n</part>", i + 1, xml_escape(s.trim())))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let prompt = render(&template(TemplateKind::SummaryMerge), &[
+        ("discussion_topic", &xml_escape(discussion_topic)),
+        ("participants_list", &participants_list),
+        ("partial_summaries", &partials),
+        ("style_guidelines", &style.guidelines()),
+    ]);
+    with_language(prompt, style.language)
+}
+
+/// 投票ラウンドで参加者1人に投票と理由を求めるプロンプト
+#[allow(clippy::too_many_arguments)]
+pub fn build_vote_prompt(
+    participant_name: &str,
+    role: &str,
+    description: &str,
+    discussion_topic: &str,
+    conversation_history: &str,
+    question: &str,
+    options: &[String],
+    language: Language,
+) -> String {
+    let options_list = options.iter().map(|o| format!("<option>{}</option>", xml_escape(o))).collect::<Vec<_>>().join("\n");
+    let hist_e = xml_escape(&optimize_conversation_for_analysis(conversation_history, HISTORY_TOKEN_BUDGET));
+
+    let prompt = format!(
+        r#"<vote>
+<discussion_topic>{discussion_topic}</discussion_topic>
+
+<participant>
+<name>{participant_name}</name>
+<role>{role}</role>
+<description>{description}</description>
+</participant>
+
+<conversation_history>
+{conversation_history}
+</conversation_history>
+
+<question>{question}</question>
+<options>
+{options}
+</options>
+
+<instructions>
+あなたは{participant_name}で、役職または職業が{role}です。{description}
+これまでの議論を踏まえ、question に対して options の中から1つだけ選んで投票してください。
+あなた自身の立場とこれまでの発言に一貫した選択をし、その理由を1〜2文で述べてください。
+
+JSON形式で以下の構造のみを出力してください：
+
+{{
+  "choice": "options のいずれか（表記はそのまま）",
+  "rationale": "投票の理由"
+}}
+
+重要：
+- choice は options の表記と完全に一致させること
+- 必ず有効なJSON形式で応答すること
+- 日本語で記述すること
+</instructions>
+</vote>"#,
+        discussion_topic = xml_escape(discussion_topic),
+        participant_name = xml_escape(participant_name),
+        role = xml_escape(role),
+        description = xml_escape(description),
+        conversation_history = hist_e,
+        question = xml_escape(question),
+        options = options_list
+    );
+    with_language(prompt, language)
+}
+
+/// ユーザーの下書き発言を添削・講評するコーチング用プロンプト
+pub fn build_coaching_prompt(
+    discussion_topic: &str,
+    conversation_history: &str,
+    participants: &[String],
+    draft: &str,
+    language: Language,
+) -> String {
+    let participants_list = participants.iter().map(|s| xml_escape(s)).collect::<Vec<_>>().join(", ");
+    let topic_e = xml_escape(discussion_topic);
+    let hist_e = xml_escape(&optimize_conversation_for_analysis(conversation_history, HISTORY_TOKEN_BUDGET));
+    let draft_e = xml_escape(draft);
+
+    let prompt = format!(
+        r#"<argumentation_coaching>
+<topic>{discussion_topic}</topic>
+<participants>{participants_list}</participants>
+
+<conversation_history>
+{conversation_history}
+</conversation_history>
+
+<user_draft>
+{draft}
+</user_draft>
+
+<instructions>
+あなたは議論・ディベートのコーチです。user_draft はユーザーがこれから送信しようとしている発言の下書きです。
+これまでの会話の流れを踏まえ、以下の観点で講評してください：
+
+1. 明確さ（clarity） - 主張が一読で伝わるか
+2. 根拠（evidence） - 主張を支える具体例・データ・理由があるか
+3. 論理の飛躍（logicalGaps） - 前提の抜けや飛躍、反論されやすい箇所
+4. 語調（tone） - 相手への敬意、建設的かどうか
+
+各観点は 1〜5 の整数で採点し、短いコメントを付けてください。
+さらに、ユーザーの主張の趣旨を保ったまま改善した書き直し案（improvedDraft）を示してください。
+
+JSON形式で以下の構造のみを出力してください：
+
+{{
+  "clarity": {{ "score": 3, "comment": "" }},
+  "evidence": {{ "score": 3, "comment": "" }},
+  "tone": {{ "score": 3, "comment": "" }},
+  "logicalGaps": ["飛躍している点1"],
+  "overall": "全体講評（1〜2文）",
+  "improvedDraft": "改善した発言"
+}}
+
+重要：
+- 必ず有効なJSON形式で応答すること
+- 日本語で記述すること
+</instructions>
+</argumentation_coaching>"#,
+        discussion_topic = topic_e,
+        participants_list = participants_list,
+        conversation_history = hist_e,
+        draft = draft_e
+    );
+    with_language(prompt, language)
+}
+
+/// 既存テキストをやさしい日本語に書き換えるプロンプト
+pub fn build_simplify_prompt(text: &str, language: Language) -> String {
+    let text_e = xml_escape(text);
+    let prompt = format!(
+        r#"<text_simplification>
+<original_text>
+{text}
+</original_text>
+
+<instructions>
+original_text の内容と意味を変えずに、やさしい日本語に書き換えてください。
+
+{plain_language_guide}
+- 情報を省略したり、新しい意見を付け加えたりしない
+
+書き換えた文章のみを返してください。説明や注釈は不要です。
+</instructions>
+</text_simplification>"#,
+        text = text_e,
+        plain_language_guide = language.plain_language_guide()
+    );
+    with_language(prompt, language)
+}
+
+/// 発言の一括翻訳プロンプト（items は (番号, 本文) の組）
+pub fn build_translation_prompt(target_language: &str, items: &[(usize, String)]) -> String {
+    let items_xml = items
+        .iter()
+        .map(|(index, text)| format!("<item index=\"{}\">{}</item>", index, xml_escape(text)))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let lang_e = xml_escape(target_language);
+
+    format!(
+        r#"<batch_translation>
+<target_language>{target_language}</target_language>
+
+<items>
+{items}
+</items>
+
+<instructions>
+items の各 item を {target_language} に翻訳してください。
+
+要件：
+- 意味・語調・話者の個性を保ち、自然な {target_language} にする
+- 要約や省略、補足説明の追加はしない
+- 見出し記号（【】や - など）や改行はそのまま維持する
+- すべての item を漏れなく翻訳し、index は入力と同じ値を使う
+
+JSON配列のみで出力してください：
+
+[
+  {{ "index": 0, "text": "翻訳文" }}
+]
+</instructions>
+</batch_translation>"#,
+        target_language = lang_e,
+        items = items_xml
+    )
+}
+
+/// 入力文の誤字脱字・文法チェック用プロンプト
+pub fn build_proofread_prompt(text: &str, language: &str) -> String {
+    let text_e = xml_escape(text);
+    let lang_e = xml_escape(language);
+
+    format!(
+        r#"<proofreading>
+<language>{language}</language>
+<text>
+{text}
+</text>
+
+<instructions>
+text を {language} の文章として校正し、誤字・脱字・変換ミス・文法の誤りだけを指摘してください。
+文体や主張の内容は変えないでください。好みの問題による言い換えは不要です。
+
+各指摘は、text 中に実際に現れる誤りの部分（original）をそのまま抜き出し、
+置き換え後の文字列（replacement）と理由（reason）を付けてください。
+original は text と1文字も違わない連続した部分文字列にし、前後の数文字を含めて一意に特定できるようにしてください。
+誤りがなければ空配列を返してください。
+
+JSON配列のみで出力してください：
+
+[
+  {{ "original": "誤っている部分", "replacement": "修正後", "reason": "理由" }}
+]
+</instructions>
+</proofreading>"#,
+        language = lang_e,
+        text = text_e
+    )
+}
+
+/// 読解レベルを超えた発言を書き直すプロンプト
+pub fn build_reading_level_rewrite_prompt(text: &str, level: ReadingLevel, language: Language) -> String {
+    let text_e = xml_escape(text);
+
+    let prompt = format!(
+        r#"<reading_level_rewrite>
+<original_text>
+{text}
+</original_text>
+
+<instructions>
+original_text は議論参加者の発言ですが、想定読者にとって難しすぎます。
+発言者の主張・口調・問いかけを保ったまま、次の条件に合うように書き直してください。
+
+{guideline}
+- 一文を短くし、一文に一つの内容だけを書く
+- 新しい主張を付け加えない
+
+書き直した発言のみを返してください。説明や注釈は不要です。
+</instructions>
+</reading_level_rewrite>"#,
+        text = text_e,
+        guideline = level.guideline()
+    );
+    with_language(prompt, language)
+}
+
+/// ディベートの審査用プロンプト（ルーブリック採点）
+pub fn build_debate_judge_prompt(
+    resolution: &str,
+    side_a: &str,
+    side_b: &str,
+    conversation_history: &str,
+    language: Language,
+) -> String {
+    let resolution_e = xml_escape(resolution);
+    let a_e = xml_escape(side_a);
+    let b_e = xml_escape(side_b);
+    let hist_e = xml_escape(conversation_history);
+
+    let prompt = format!(
+        r#"<debate_judgement>
+<resolution>{resolution}</resolution>
+<affirmative>{side_a}</affirmative>
+<negative>{side_b}</negative>
+
+<debate_transcript>
+{conversation_history}
+</debate_transcript>
+
+<instructions>
+あなたは公平なディベート審査員です。論題「{resolution}」について、肯定側 {side_a} と否定側 {side_b} のディベートを審査してください。
+どちらの立場が正しいかではなく、議論の質だけを評価してください。
+
+ルーブリック（各項目 1〜10 の整数）：
+- logic: 主張の論理的一貫性
+- evidence: 根拠・具体例の質
+- rebuttal: 相手の主張への反論・応答の的確さ
+- clarity: 表現の明確さ・簡潔さ
+
+JSON形式で以下の構造のみを出力してください：
+
+{{
+  "affirmative": {{ "logic": 5, "evidence": 5, "rebuttal": 5, "clarity": 5 }},
+  "negative": {{ "logic": 5, "evidence": 5, "rebuttal": 5, "clarity": 5 }},
+  "reason": "判定理由（2〜3文）"
+}}
+
+重要：
+- 必ず有効なJSON形式で応答すること
+</instructions>
+</debate_judgement>"#,
+        resolution = resolution_e,
+        side_a = a_e,
+        side_b = b_e,
+        conversation_history = hist_e
+    );
+    with_language(prompt, language)
+}
+
+/// 議論全体の質を採点するプロンプト（パラメータ比較実験の評価用）
+pub fn build_discussion_quality_prompt(discussion_topic: &str, conversation_history: &str, language: Language) -> String {
+    let prompt = format!(
+        r#"<discussion_quality_evaluation>
+<discussion_topic>{discussion_topic}</discussion_topic>
+
+<conversation_history>
+{conversation_history}
+</conversation_history>
+
+<instructions>
+あなたは議論の質を評価する審査員です。テーマ「{discussion_topic}」についての上記の議論を評価してください。
+
+評価観点：
+- 各発言が前の発言に具体的に反応しているか
+- 新しい視点や具体例によって議論が深まっているか
+- 参加者ごとの立場・口調が一貫しているか
+- 同じ内容の繰り返しや脱線がないか
+
+JSON形式で以下の構造のみを出力してください：
+
+{{
+  "score": 5,
+  "reason": "評価理由（1〜2文）"
+}}
+
+重要：
+- score は 1〜10 の整数
+- 必ず有効なJSON形式で応答すること
+</instructions>
+</discussion_quality_evaluation>"#,
+        discussion_topic = xml_escape(discussion_topic),
+        conversation_history = xml_escape(conversation_history)
+    );
+    with_language(prompt, language)
+}
+
+/// 文章中の人名を列挙させるプロンプト（個人情報の伏せ字処理の補助）
+pub fn build_pii_detection_prompt(text: &str) -> String {
+    format!(
+        r#"<pii_detection>
+<text>
+{text}
+</text>
+
+<instructions>
+上記の文章に含まれる実在の個人を指す人名（姓・名・フルネーム・ニックネーム）をすべて抜き出してください。
+役職名・組織名・地名・一般名詞は含めないでください。
+
+JSON形式で以下の構造のみを出力してください：
+
+["人名1", "人名2"]
+
+重要：
+- 文章に現れる表記のまま抜き出すこと
+- 人名がなければ [] を返すこと
+- 必ず有効なJSON形式で応答すること
+</instructions>
+</pii_detection>"#,
+        text = xml_escape(text)
+    )
+}
+
+/// 次の話者の候補（build_next_speaker_prompt の入力）
+pub struct SpeakerCandidate<'a> {
+    pub name: &'a str,
+    pub role: &'a str,
+    /// 未発言・名指しされた・対立の当事者などの手がかり
+    pub notes: Vec<String>,
+}
+
+/// 次に発言させる参加者を選ばせるプロンプト
+pub fn build_next_speaker_prompt(
+    discussion_topic: &str,
+    candidates: &[SpeakerCandidate],
+    conversation_history: &str,
+    conflicts: &[String],
+    language: Language,
+) -> String {
+    let candidates_xml = candidates
+        .iter()
+        .map(|c| {
+            format!(
+                "<candidate>\n<name>{}</name>\n<role>{}</role>\n<notes>{}</notes>\n</candidate>",
+                xml_escape(c.name),
+                xml_escape(c.role),
+                xml_escape(&c.notes.join(" / "))
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let conflicts_block = if conflicts.is_empty() {
+        "（なし）".to_string()
+    } else {
+        conflicts.iter().map(|c| format!("- {}", xml_escape(c))).collect::<Vec<_>>().join("\n")
+    };
+    let hist_e = xml_escape(&optimize_conversation_for_analysis(conversation_history, COMPACT_HISTORY_TOKEN_BUDGET));
+
+    let prompt = render(&template(TemplateKind::NextSpeaker), &[
+        ("discussion_topic", &xml_escape(discussion_topic)),
+        ("participants_list", &candidates_xml),
+        ("conflicts", &conflicts_block),
+        ("conversation_history", &hist_e),
+    ]);
+    with_language(prompt, language)
+}
+
+/// 質問応答に渡す発言の抜粋（number は1始まりの発言番号）
+pub struct SessionExcerpt<'a> {
+    pub number: usize,
+    pub speaker: &'a str,
+    pub message: &'a str,
+}
+
+/// 保存済みの議論への質問応答プロンプト（要約と関連する発言の抜粋を根拠にする）
+pub fn build_session_qa_prompt(
+    discussion_topic: &str,
+    summary: Option<&str>,
+    excerpts: &[SessionExcerpt],
+    question: &str,
+    language: Language,
+) -> String {
+    let excerpts_block = if excerpts.is_empty() {
+        "（該当する発言なし）".to_string()
+    } else {
+        excerpts
+            .iter()
+            .map(|e| format!("[#{}] {}: {}", e.number, xml_escape(e.speaker), xml_escape(e.message)))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    let summary = summary.map(xml_escape).unwrap_or_else(|| "（要約なし）".to_string());
+    let prompt = render(&template(TemplateKind::SessionQa), &[
+        ("discussion_topic", &xml_escape(discussion_topic)),
+        ("summary", &summary),
+        ("excerpts", &excerpts_block),
+        ("question", &xml_escape(question)),
+    ]);
+    with_language(prompt, language)
+}
+
+/// 要約からセッションのタグ（キーワード）を挙げさせるプロンプト
+pub fn build_session_tags_prompt(discussion_topic: &str, summary: &str, language: Language) -> String {
+    let prompt = render(&template(TemplateKind::SessionTags), &[
+        ("discussion_topic", &xml_escape(discussion_topic)),
+        ("summary", &xml_escape(summary)),
+    ]);
+    with_language(prompt, language)
+}
+
+/// 参加者の議論をまたいだ記憶を、今回の発言を反映して書き直させるプロンプト
+pub fn build_participant_memory_prompt(
+    participant_name: &str,
+    role: &str,
+    discussion_topic: &str,
+    previous_memory: &str,
+    statements: &[String],
+    max_chars: usize,
+    language: Language,
+) -> String {
+    let previous = if previous_memory.trim().is_empty() { "（まだ記憶はありません）" } else { previous_memory.trim() };
+    let statements = statements
+        .iter()
+        .map(|s| format!("<statement>{}</statement>", xml_escape(s.trim())))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let prompt = render(&template(TemplateKind::ParticipantMemory), &[
+        ("participant_name", &xml_escape(participant_name)),
+        ("role", &xml_escape(role)),
+        ("discussion_topic", &xml_escape(discussion_topic)),
+        ("previous_memory", &xml_escape(previous)),
+        ("statements", &statements),
+        ("max_chars", &max_chars.to_string()),
+    ]);
+    with_language(prompt, language)
+}
+
+/// 司会者が議論の状況（堂々巡り・発言の偏り・脱線など。situation に説明文）に介入する発言のプロンプト
+pub fn build_moderator_intervention_prompt(
+    moderator_name: &str,
+    discussion_topic: &str,
+    participants: &[String],
+    situation: &str,
+    conversation_history: &str,
+    style: &PromptStyle,
+) -> String {
+    let participants_list = participants.iter().map(|s| xml_escape(s)).collect::<Vec<_>>().join(", ");
+    let history = optimize_conversation_for_analysis(conversation_history, HISTORY_TOKEN_BUDGET);
+    let prompt = render(&template(TemplateKind::ModeratorIntervention), &[
+        ("discussion_topic", &xml_escape(discussion_topic)),
+        ("moderator_name", &xml_escape(moderator_name)),
+        ("participants_list", &participants_list),
+        ("conversation_history", &xml_escape(&history)),
+        ("situation", &xml_escape(situation)),
+        ("style_guidelines", &style.guidelines()),
+    ]);
+    with_language(prompt, style.language)
+}
+
+/// 直近の発言がテーマからどれだけ逸れているかを判定させるプロンプト
+pub fn build_topic_drift_prompt(discussion_topic: &str, recent_messages: &str, language: Language) -> String {
+    let prompt = render(&template(TemplateKind::TopicDrift), &[
+        ("discussion_topic", &xml_escape(discussion_topic)),
+        ("recent_messages", &xml_escape(recent_messages)),
+    ]);
+    with_language(prompt, language)
+}
+
+/// 議論からアクションアイテム（行うこと・担当・期限の手がかり）を抜き出させるプロンプト
+pub fn build_action_items_prompt(
+    discussion_topic: &str,
+    participants: &[String],
+    conversation_history: &str,
+    language: Language,
+) -> String {
+    let participants_list = participants.iter().map(|s| xml_escape(s)).collect::<Vec<_>>().join(", ");
+    let history = optimize_conversation_for_analysis(conversation_history, HISTORY_TOKEN_BUDGET);
+    let prompt = render(&template(TemplateKind::ActionItems), &[
+        ("discussion_topic", &xml_escape(discussion_topic)),
+        ("participants_list", &participants_list),
+        ("conversation_history", &xml_escape(&history)),
+    ]);
+    with_language(prompt, language)
+}
+
+/// 議論中の参加者の状態（同意度 0〜1, 苛立ち 0〜1, 理由）
+pub type PersonaStateArgs<'a> = (f32, f32, &'a str);
+
+/// 参加者ごとの前回の状態（未評価なら None）とその後の発言から、各参加者の今の状態を見積もらせるプロンプト
+pub fn build_persona_state_prompt(
+    discussion_topic: &str,
+    participants: &[(&str, Option<PersonaStateArgs>)],
+    new_messages: &str,
+    language: Language,
+) -> String {
+    let participants = participants
+        .iter()
+        .map(|(name, state)| match state {
+            Some((agreement, frustration, note)) => format!(
+                "<participant name=\"{}\" agreement=\"{:.2}\" frustration=\"{:.2}\">{}</participant>",
+                xml_escape(name),
+                agreement,
+                frustration,
+                xml_escape(note)
+            ),
+            None => format!("<participant name=\"{}\">（まだ評価していません）</participant>", xml_escape(name)),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let prompt = render(&template(TemplateKind::PersonaState), &[
+        ("discussion_topic", &xml_escape(discussion_topic)),
+        ("participants", &participants),
+        ("new_messages", &xml_escape(new_messages)),
+    ]);
+    with_language(prompt, language)
+}
+
+/// 会話履歴を分析用に最適化（トークン予算に収まる直近の発言だけを残す）
+/// 最新の発言は予算を超えても必ず残す
+pub fn optimize_conversation_for_analysis(conversation_history: &str, max_tokens: usize) -> String {
+    if conversation_history.is_empty() || conversation_history == "まだ発言はありません。議論を開始してください。" {
+        return "まだ発言はありません。".to_string();
+    }
+    if tokens::count(conversation_history) <= max_tokens {
+        return conversation_history.to_string();
+    }
+
+    let msgs = split_messages_heuristic(conversation_history);
+    let mut used = 0;
+    let mut start = msgs.len();
+    for (i, msg) in msgs.iter().enumerate().rev() {
+        // 改行の分として1トークン足す
+        let cost = tokens::count(msg) + 1;
+        if used + cost > max_tokens && start < msgs.len() {
+            break;
+        }
+        used += cost;
+        start = i;
+    }
+    if start == 0 {
+        return conversation_history.to_string();
+    }
+    format!("[...以前の発言は省略...]\n{}", msgs[start..].join("\n"))
+}
+
+/// AI参加者設定（名前・役職・説明）をJSONで生成するプロンプト
+/// 参加者の立場の内訳（generate_ai_profiles の constraints.stances）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct StanceDistribution {
+    /// 賛成派の人数
+    pub pro: usize,
+    /// 反対派の人数
+    pub con: usize,
+    /// 中立の人数
+    pub neutral: usize,
+}
+
+impl StanceDistribution {
+    pub fn total(&self) -> usize {
+        self.pro + self.con + self.neutral
+    }
+}
+
+/// 参加者の話し方の丁寧さ
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Formality {
+    Casual,
+    Neutral,
+    Formal,
+}
+
+impl Formality {
+    fn guideline(self) -> &'static str {
+        match self {
+            Formality::Casual => "全員くだけた口語で話す人物にし、description にもそう書く",
+            Formality::Neutral => "全員丁寧すぎずくだけすぎない普通の話し方をする人物にする",
+            Formality::Formal => "全員敬語を使う改まった話し方の人物にし、description にもそう書く",
+        }
+    }
+}
+
+/// 参加者の顔ぶれの条件（generate_ai_profiles の constraints）
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ProfileConstraints {
+    /// 必ず含める役職（例: "弁護士"）。1つにつき1人
+    pub required_roles: Vec<String>,
+    /// 立場の内訳（合計が人数より少なければ残りは自由）
+    pub stances: Option<StanceDistribution>,
+    pub formality: Option<Formality>,
+    /// 年齢層をばらけさせる
+    pub diverse_ages: bool,
+    /// 職業をばらけさせる
+    pub diverse_occupations: bool,
+}
+
+// 必須の役職の最大文字数
+const MAX_REQUIRED_ROLE_CHARS: usize = 60;
+
+impl ProfileConstraints {
+    /// 人数に対して満たせる条件か確認し、役職の前後の空白・空の役職を除いたものを返す
+    pub fn validated(mut self, count: usize) -> Result<Self, String> {
+        let mut roles: Vec<String> = Vec::new();
+        for role in self.required_roles.iter().map(|r| r.trim()).filter(|r| !r.is_empty()) {
+            if role.chars().count() > MAX_REQUIRED_ROLE_CHARS {
+                return Err(format!("必須の役職は{}文字以内で指定してください", MAX_REQUIRED_ROLE_CHARS));
+            }
+            if !roles.iter().any(|r| r == role) {
+                roles.push(role.to_string());
+            }
+        }
+        if roles.len() > count {
+            return Err(format!("必須の役職（{}件）が人数（{}人）より多くなっています", roles.len(), count));
+        }
+        self.required_roles = roles;
+        if let Some(stances) = self.stances {
+            if stances.total() > count {
+                return Err(format!("立場の内訳の合計（{}人）が人数（{}人）より多くなっています", stances.total(), count));
+            }
+            if stances.total() == 0 {
+                self.stances = None;
+            }
+        }
+        Ok(self)
+    }
+
+    /// <constraints> に差し込む条件の一覧（条件がなければ「特になし」）
+    fn lines(&self) -> String {
+        let mut lines: Vec<String> = Vec::new();
+        for role in &self.required_roles {
+            lines.push(format!("- role が「{}」の参加者を必ず1人含める", xml_escape(role)));
+        }
+        if let Some(stances) = self.stances {
+            let mut parts = Vec::new();
+            for (label, n) in [("賛成", stances.pro), ("反対", stances.con), ("中立", stances.neutral)] {
+                if n > 0 {
+                    parts.push(format!("{}{}人", label, n));
+                }
+            }
+            lines.push(format!("- テーマへの立場を {} にし、description に立場が分かるように書く", parts.join("・")));
+        }
+        if let Some(formality) = self.formality {
+            lines.push(format!("- {}", formality.guideline()));
+        }
+        if self.diverse_ages {
+            lines.push("- 10代から70代まで年齢層をばらけさせ、description に年代を書く".to_string());
+        }
+        if self.diverse_occupations {
+            lines.push("- 職業・業界が互いに重ならないようにする".to_string());
+        }
+        if lines.is_empty() {
+            "（特になし）".to_string()
+        } else {
+            lines.join("\n")
+        }
+    }
+}
+
+pub fn build_ai_profiles_prompt(
+    discussion_topic: &str,
+    desired_count: usize,
+    style_hint: &str,
+    constraints: &ProfileConstraints,
+    language: Language,
+) -> String {
+    // 条件の検証は呼び出し側（ProfileConstraints::validated）で行い、ここでは上限のみ適用
+    let count = if desired_count == 0 { 1 } else { desired_count.min(10) };
+    let hint_line = if style_hint.is_empty() {
+        String::from("（特別な指定はありません）")
+    } else {
+        format!("ヒント: {}", xml_escape(style_hint))
+    };
+
+    let topic_e = xml_escape(discussion_topic);
+
+    let prompt = render(&template(TemplateKind::AiProfiles), &[
+        ("discussion_topic", &topic_e),
+        ("count", &count.to_string()),
+        ("hint_line", &hint_line),
+        ("constraints", &constraints.lines()),
+    ]);
+    with_language(prompt, language)
+}
+
+/// 検証で弾かれた参加者プロフィールの出力を直させるプロンプト（generate_ai_profiles の再試行用）
+pub fn build_ai_profiles_repair_prompt(
+    discussion_topic: &str,
+    desired_count: usize,
+    previous_output: &str,
+    problems: &[String],
+    language: Language,
+) -> String {
+    let count = if desired_count == 0 { 1 } else { desired_count.min(10) };
+    let problems = problems.iter().map(|p| format!("- {}", xml_escape(p))).collect::<Vec<_>>().join("\n");
+    let prompt = render(&template(TemplateKind::AiProfilesRepair), &[
+        ("discussion_topic", &xml_escape(discussion_topic)),
+        ("count", &count.to_string()),
+        ("previous_output", &xml_escape(previous_output.trim())),
+        ("problems", &problems),
+    ]);
+    with_language(prompt, language)
+}
+
+// ---（以下 split_messages_heuristic など既存の補助関数がこの下にある場合そのまま）---
+// 既存のヘルパー関数がこのファイル末尾にあるなら保持
+
+/// 発言履歴文字列をざっくり行単位で分割するヒューリスティック関数（元実装から必要最小限再構築）
+fn split_messages_heuristic(history: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    for line in history.split('\n') {
+        let trimmed = line.trim();
+        if trimmed.is_empty() { continue; }
+        // 発言者ラベルっぽいパターンで始まるか、適度な長さの文を1行として扱う
+        if trimmed.contains(':') || trimmed.chars().count() > 8 {
+            out.push(trimmed.to_string());
+        }
+    }
+    if out.is_empty() && !history.trim().is_empty() {
+        out.push(history.trim().to_string());
+    }
+    out
+}
@@ -0,0 +1,176 @@
+// コンテンツ安全ポリシー（アプリと dewai-cli で共通）
+// 設定のルール（カテゴリ・重大度・対応）を生成の入力と出力に適用する
+// 適用結果の記録（アプリでは監査ログ）は呼び出し側で行う
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+// 伏せ字にした箇所の置き換え文字列
+const REDACTED: &str = "＊＊＊";
+// 重大度の範囲
+const MAX_SEVERITY: u8 = 5;
+
+/// ルールに一致したときの対応
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SafetyAction {
+    /// 生成を中止してエラーにする
+    Block,
+    /// 一致箇所を伏せ字にして続行
+    Redact,
+    /// 記録のみ行い続行
+    Warn,
+}
+
+impl SafetyAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SafetyAction::Block => "block",
+            SafetyAction::Redact => "redact",
+            SafetyAction::Warn => "warn",
+        }
+    }
+}
+
+/// 1つのルール（pattern は正規表現）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SafetyRule {
+    pub category: String,
+    pub pattern: String,
+    /// 1〜5
+    pub severity: u8,
+    pub action: SafetyAction,
+}
+
+/// 安全ポリシー（アプリ設定の safety）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SafetyPolicy {
+    pub enabled: bool,
+    /// 適用するカテゴリ（空ならすべてのルールを適用）
+    pub blocked_categories: Vec<String>,
+    /// この重大度以上のルールだけを適用
+    pub min_severity: u8,
+    pub rules: Vec<SafetyRule>,
+}
+
+impl Default for SafetyPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            blocked_categories: Vec::new(),
+            min_severity: 1,
+            rules: vec![
+                SafetyRule {
+                    category: "dangerous-instructions".into(),
+                    pattern: "(爆弾|爆発物|毒物)の(作り方|製造方法|入手方法)".into(),
+                    severity: 5,
+                    action: SafetyAction::Block,
+                },
+                SafetyRule {
+                    category: "self-harm".into(),
+                    pattern: "(自殺|自傷)の(方法|やり方)".into(),
+                    severity: 4,
+                    action: SafetyAction::Warn,
+                },
+            ],
+        }
+    }
+}
+
+impl SafetyPolicy {
+    /// 正規表現と重大度を検証
+    pub fn validate(&self) -> Result<(), String> {
+        for rule in &self.rules {
+            if rule.category.trim().is_empty() {
+                return Err("安全ルールのカテゴリが空です".into());
+            }
+            if !(1..=MAX_SEVERITY).contains(&rule.severity) {
+                return Err(format!("安全ルールの重大度は1〜{}で指定してください: {}", MAX_SEVERITY, rule.category));
+            }
+            Regex::new(&rule.pattern).map_err(|e| format!("安全ルールの正規表現が不正です ({}): {}", rule.category, e))?;
+        }
+        Ok(())
+    }
+}
+
+/// どちら向きのテキストか
+#[derive(Debug, Clone, Copy)]
+pub enum Direction {
+    /// モデルへの入力（ユーザー入力を含むプロンプト）
+    Input,
+    /// モデルの出力
+    Output,
+}
+
+impl Direction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Direction::Input => "input",
+            Direction::Output => "output",
+        }
+    }
+}
+
+/// 適用対象に絞り込み、正規表現をコンパイル済みのルール
+struct CompiledRule {
+    rule: SafetyRule,
+    regex: Regex,
+}
+
+/// 適用するルール（ポリシーから作る）
+pub struct SafetyRules(Vec<CompiledRule>);
+
+impl SafetyRules {
+    pub fn compile(policy: &SafetyPolicy) -> Self {
+        if !policy.enabled {
+            return Self(Vec::new());
+        }
+        let mut compiled: Vec<CompiledRule> = policy
+            .rules
+            .iter()
+            .filter(|r| r.severity >= policy.min_severity)
+            .filter(|r| policy.blocked_categories.is_empty() || policy.blocked_categories.contains(&r.category))
+            .filter_map(|r| Regex::new(&r.pattern).ok().map(|regex| CompiledRule { rule: r.clone(), regex }))
+            .collect();
+        // 伏せ字で一致箇所が消える前に block を判定する
+        compiled.sort_by_key(|c| c.rule.action != SafetyAction::Block);
+        Self(compiled)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// テキストにルールを適用する
+    /// block に一致すればエラー、redact は伏せ字にしたテキストを返す。一致したルールと件数を on_match に渡す
+    pub fn enforce(&self, text: &str, mut on_match: impl FnMut(&SafetyRule, usize)) -> Result<String, String> {
+        let mut result = text.to_string();
+        for compiled in &self.0 {
+            let matches = compiled.regex.find_iter(&result).count();
+            if matches == 0 {
+                continue;
+            }
+            let rule = &compiled.rule;
+            on_match(rule, matches);
+            match rule.action {
+                SafetyAction::Block => {
+                    return Err(format!("安全ポリシーにより生成を中止しました（カテゴリ: {}）", rule.category));
+                }
+                SafetyAction::Redact => result = compiled.regex.replace_all(&result, REDACTED).into_owned(),
+                SafetyAction::Warn => {}
+            }
+        }
+        Ok(result)
+    }
+}
+
+impl Default for SafetyRules {
+    fn default() -> Self {
+        Self::compile(&SafetyPolicy::default())
+    }
+}
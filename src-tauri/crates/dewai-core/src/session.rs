@@ -0,0 +1,70 @@
+// セッションの中身（参加者・発言）の型
+// フロントエンドの participants / messages JSON と同じ形で、アプリの DB・JSON エクスポートと CLI の出力で共通に使う
+use serde::{Deserialize, Serialize};
+
+use crate::generation::GenerationMeta;
+
+/// 保存済みメッセージ（フロントエンドの messages JSON と同じ形）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoredMessage {
+    pub speaker: String,
+    pub message: String,
+    #[serde(default)]
+    pub is_user: bool,
+    #[serde(default)]
+    pub timestamp: String,
+    /// 生成時のシード（AI発言のみ。再現・不具合報告用）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+    /// トークン数・生成時間（AI発言のみ）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generation: Option<GenerationMeta>,
+    /// edit_message で本文を書き換えた日時
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub edited_at: Option<String>,
+    /// regenerate_message で生成し直した日時
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub regenerated_at: Option<String>,
+}
+
+/// AI参加者設定（participants JSON の aiData 要素）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AiParticipant {
+    pub name: String,
+    #[serde(default)]
+    pub role: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+/// participants JSON（新形式）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParticipantsData {
+    #[serde(default)]
+    pub user_participates: bool,
+    #[serde(default)]
+    pub ai_data: Vec<AiParticipant>,
+}
+
+impl ParticipantsData {
+    /// 参加者名の一覧（ユーザー参加時は「ユーザー」を先頭に含める）
+    pub fn names(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        if self.user_participates {
+            names.push("ユーザー".to_string());
+        }
+        names.extend(self.ai_data.iter().map(|p| p.name.clone()));
+        names
+    }
+}
+
+/// メッセージ列を "発言者: 内容" の行形式に整形
+pub fn format_history(messages: &[StoredMessage]) -> String {
+    messages
+        .iter()
+        .map(|m| format!("{}: {}", m.speaker, m.message))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,24 @@
+// モデルを使わない軽いテキストの比較（2文字単位の重なり）
+// アプリの関連セッション検索と、司会役の堂々巡りの判定で使う
+use std::collections::HashSet;
+
+/// テーマ（や発言）を2文字単位に分ける（空白・記号は区切りとして扱い、1文字の語はそのまま使う）
+pub fn topic_grams(topic: &str) -> HashSet<String> {
+    let mut grams = HashSet::new();
+    for word in topic.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()) {
+        let chars: Vec<char> = word.to_lowercase().chars().collect();
+        if chars.len() == 1 {
+            grams.insert(chars[0].to_string());
+        }
+        grams.extend(chars.windows(2).map(|w| w.iter().collect::<String>()));
+    }
+    grams
+}
+
+pub fn jaccard(a: &HashSet<String>, b: &HashSet<String>) -> f32 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f32 / union as f32
+}
@@ -0,0 +1,27 @@
+// トークン数の見積もりとコンテキスト予算
+// gemma の tokenizer.json は同梱していないため、多言語の語彙が大きい o200k_base（tiktoken）で近似する
+// 日本語では gemma の語彙（約26万）の方が分割が粗く、見積もりは実際より多め（安全側）になる
+/// Ollama が num_ctx 未指定時に使うコンテキスト長
+pub const DEFAULT_NUM_CTX: usize = 4096;
+
+/// テキストのトークン数（見積もり）
+pub fn count(text: &str) -> usize {
+    if text.is_empty() {
+        return 0;
+    }
+    tiktoken_rs::o200k_base_singleton().encode_with_special_tokens(text).len()
+}
+
+/// モデル本来のコンテキスト長（gemma3 は 1b が 32K、4b 以上が 128K）
+pub fn model_context_length(model: &str) -> usize {
+    if model.ends_with(":1b") || model.ends_with(":270m") {
+        32_768
+    } else {
+        131_072
+    }
+}
+
+/// num_ctx 未指定で生成した場合に使えるコンテキスト長
+pub fn context_window(model: &str) -> usize {
+    model_context_length(model).min(DEFAULT_NUM_CTX)
+}
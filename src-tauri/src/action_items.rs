@@ -0,0 +1,208 @@
+// アクションアイテムの抽出
+// 議論で決まった・提案された具体的な行動をテンプレート action_items で抜き出し、セッションごとに action_items へ保存する
+// 実際の会議の予行演習・計画に使う人向けに、Markdown のチェックリストとして書き出せる（セッションの Markdown / HTML 出力にも含める）
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{command, AppHandle};
+use tracing::info;
+
+use crate::{
+    call_ollama_generate_with, db, encryption,
+    generation::{GenerationOptions, OutputFormat},
+    is_allowed_model, llm_json, prompts, session_settings, ERR_UNSUPPORTED_MODEL,
+};
+
+// 1セッションで保存するアクションアイテムの上限
+const MAX_ITEMS: usize = 30;
+const MAX_DESCRIPTION_CHARS: usize = 200;
+const MAX_FIELD_CHARS: usize = 50;
+
+/// アクションアイテム1件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActionItem {
+    /// 行うこと
+    pub description: String,
+    /// 担当者（名前が挙がっていなければ None）
+    pub owner: Option<String>,
+    /// 期限・時期の手がかり（「来週まで」など。なければ None）
+    pub due_hint: Option<String>,
+}
+
+impl ActionItem {
+    /// チェックリストの1行（"- [ ] 行うこと（担当: 〇〇、期限: 来週まで）"）
+    fn checklist_line(&self) -> String {
+        let mut notes = Vec::new();
+        if let Some(owner) = &self.owner {
+            notes.push(format!("担当: {}", owner));
+        }
+        if let Some(due) = &self.due_hint {
+            notes.push(format!("期限: {}", due));
+        }
+        let description = self.description.lines().map(str::trim).collect::<Vec<_>>().join(" ");
+        if notes.is_empty() {
+            format!("- [ ] {}", description)
+        } else {
+            format!("- [ ] {}（{}）", description, notes.join("、"))
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RawItem {
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    owner: String,
+    #[serde(default)]
+    due_hint: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawItems {
+    #[serde(default)]
+    items: Vec<RawItem>,
+}
+
+fn output_format() -> OutputFormat {
+    OutputFormat::Schema(json!({
+        "type": "object",
+        "properties": {
+            "items": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "description": { "type": "string" },
+                        "owner": { "type": "string" },
+                        "due_hint": { "type": "string" }
+                    },
+                    "required": ["description", "owner", "due_hint"]
+                }
+            }
+        },
+        "required": ["items"]
+    }))
+}
+
+/// 空白だけの値を None にして長さを揃える
+fn optional(text: &str) -> Option<String> {
+    let text: String = text.trim().chars().take(MAX_FIELD_CHARS).collect();
+    (!text.is_empty()).then_some(text)
+}
+
+/// モデルの回答からアクションアイテムを取り出す（説明のないもの・同じ説明の2件目以降を除く）
+fn parse_items(raw: &str) -> Result<Vec<ActionItem>, String> {
+    let parsed: RawItems = llm_json::parse_llm_json(raw)?;
+    let mut items: Vec<ActionItem> = Vec::new();
+    for raw in parsed.items {
+        let description: String = raw.description.trim().chars().take(MAX_DESCRIPTION_CHARS).collect();
+        if description.is_empty() || items.iter().any(|i| i.description == description) {
+            continue;
+        }
+        items.push(ActionItem { description, owner: optional(&raw.owner), due_hint: optional(&raw.due_hint) });
+    }
+    items.truncate(MAX_ITEMS);
+    Ok(items)
+}
+
+/// セッションのアクションアイテムを入れ替える
+async fn replace(app: &AppHandle, session_id: i64, model: &str, items: &[ActionItem]) -> Result<(), String> {
+    let pool = db::pool(app).await?;
+    let mut tx = pool.begin().await.map_err(|e| format!("トランザクション開始失敗: {}", e))?;
+    sqlx::query("DELETE FROM action_items WHERE session_id = ?")
+        .bind(session_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("アクションアイテム削除失敗: {}", e))?;
+    let keys = encryption::keys(app);
+    let now = db::now_string();
+    for (position, item) in items.iter().enumerate() {
+        sqlx::query(
+            "INSERT INTO action_items (session_id, position, description, owner, due_hint, model, created_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(session_id)
+        .bind(position as i64)
+        .bind(keys.seal(&item.description)?)
+        .bind(keys.seal_optional(item.owner.as_deref())?)
+        .bind(keys.seal_optional(item.due_hint.as_deref())?)
+        .bind(model)
+        .bind(&now)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("アクションアイテム保存失敗: {}", e))?;
+    }
+    tx.commit().await.map_err(|e| format!("トランザクション確定失敗: {}", e))
+}
+
+/// 保存済みのアクションアイテム（抽出した順）
+pub async fn load(app: &AppHandle, session_id: i64) -> Result<Vec<ActionItem>, String> {
+    let pool = db::pool(app).await?;
+    let rows: Vec<(String, Option<String>, Option<String>)> = sqlx::query_as(
+        "SELECT description, owner, due_hint FROM action_items WHERE session_id = ? ORDER BY position, id",
+    )
+    .bind(session_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("アクションアイテム取得失敗: {}", e))?;
+    let keys = encryption::keys(app);
+    rows.into_iter()
+        .map(|(description, owner, due_hint)| {
+            Ok(ActionItem {
+                description: keys.open(&description)?,
+                owner: keys.open_optional(owner.as_deref())?,
+                due_hint: keys.open_optional(due_hint.as_deref())?,
+            })
+        })
+        .collect()
+}
+
+/// Markdown のチェックリスト（アイテムがなければ空文字）
+pub fn to_markdown(items: &[ActionItem]) -> String {
+    items.iter().map(|item| item.checklist_line() + "\n").collect()
+}
+
+// 議論からアクションアイテムを抜き出して保存する（model 未指定ならセッションのモデル。以前の抽出結果は置き換える）
+#[command]
+pub async fn extract_action_items(
+    app: AppHandle,
+    session_id: i64,
+    model: Option<String>,
+) -> Result<Vec<ActionItem>, String> {
+    info!("extract_action_items 呼び出し: session_id={}, model={:?}", session_id, model);
+    let session = db::load_session(&app, session_id).await?;
+    let model = model.unwrap_or_else(|| session.model.clone());
+    if !is_allowed_model(&app, &model) {
+        return Err(ERR_UNSUPPORTED_MODEL.to_string());
+    }
+    if session.messages.is_empty() {
+        return Err("発言がまだありません".into());
+    }
+    let language = session_settings::load(&app, session_id).await?.language();
+    let participants = session.participant_names();
+    let prompt = prompts::build_action_items_prompt(&session.topic, &participants, &session.history_text(), language);
+    let options = GenerationOptions::default().with_format(output_format());
+    let items = parse_items(&call_ollama_generate_with(&app, &model, &prompt, &options).await?)?;
+    replace(&app, session_id, &model, &items).await?;
+    info!("アクションアイテムを抽出: session_id={}, {}件", session_id, items.len());
+    Ok(items)
+}
+
+// 保存済みのアクションアイテム（まだ抽出していなければ空）
+#[command]
+pub async fn get_action_items(app: AppHandle, session_id: i64) -> Result<Vec<ActionItem>, String> {
+    info!("get_action_items 呼び出し: session_id={}", session_id);
+    load(&app, session_id).await
+}
+
+// 保存済みのアクションアイテムを Markdown のチェックリストにして返す（クリップボードへのコピー用）
+#[command]
+pub async fn export_action_items(app: AppHandle, session_id: i64) -> Result<String, String> {
+    info!("export_action_items 呼び出し: session_id={}", session_id);
+    let items = load(&app, session_id).await?;
+    if items.is_empty() {
+        return Err("アクションアイテムがありません。先に抽出してください".into());
+    }
+    Ok(to_markdown(&items))
+}
@@ -0,0 +1,318 @@
+// 議論分析（analyze_discussion_points）の結果型と履歴
+// モデルのJSON出力を Rust 側でパース・修復し、型付きの構造体としてフロントエンドへ返す
+// セッション指定時は発言数ごとのスナップショットを analysis_snapshots に残し、立場・対立点の推移を追えるようにする
+// 参加者ごとの立場ラベルと確信度は stance_history にも展開し、参加者単位の推移を取り出せるようにする
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{command, AppHandle};
+
+use crate::db;
+use crate::encryption::{self, KeyStore};
+use crate::generation::OutputFormat;
+use crate::llm_json;
+
+/// 主要論点
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MainPoint {
+    pub point: String,
+    pub description: String,
+}
+
+/// 参加者ごとの立場
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ParticipantStance {
+    /// 参加者名（"ユーザー" は人間）
+    pub participant: String,
+    /// 立場の短いラベル（例: "賛成", "条件付き賛成", "反対"）
+    pub label: String,
+    /// 立場の確信度（0.0〜1.0）
+    pub confidence: f32,
+    pub stance: String,
+    pub key_arguments: Vec<String>,
+}
+
+/// 対立点
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Conflict {
+    pub issue: String,
+    /// 代表的な立場（例: "賛成", "慎重論"）
+    pub sides: Vec<String>,
+    pub description: String,
+}
+
+/// 議論分析の結果（欠けた項目は空で補う）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DiscussionAnalysis {
+    pub main_points: Vec<MainPoint>,
+    pub participant_stances: Vec<ParticipantStance>,
+    pub conflicts: Vec<Conflict>,
+    pub common_ground: Vec<String>,
+    pub unexplored_areas: Vec<String>,
+}
+
+impl DiscussionAnalysis {
+    /// 空の項目（論点名・参加者名・争点が空のもの、空文字列）を除く
+    fn cleaned(mut self) -> Self {
+        self.main_points.retain(|p| !p.point.trim().is_empty());
+        self.participant_stances.retain(|s| !s.participant.trim().is_empty());
+        for stance in &mut self.participant_stances {
+            stance.label = stance.label.trim().to_string();
+            stance.confidence = if stance.confidence.is_finite() { stance.confidence.clamp(0.0, 1.0) } else { 0.0 };
+        }
+        self.conflicts.retain(|c| !c.issue.trim().is_empty());
+        self.common_ground.retain(|x| !x.trim().is_empty());
+        self.unexplored_areas.retain(|x| !x.trim().is_empty());
+        self
+    }
+
+    /// 現在の合意とみなす内容（共通認識。なければ主要論点）
+    pub fn consensus(&self) -> Vec<String> {
+        if !self.common_ground.is_empty() {
+            return self.common_ground.clone();
+        }
+        self.main_points
+            .iter()
+            .map(|p| if p.description.trim().is_empty() { p.point.clone() } else { format!("{}: {}", p.point, p.description) })
+            .collect()
+    }
+}
+
+/// JSON モードで渡す出力スキーマ（DiscussionAnalysis と同じ形）
+pub fn output_format() -> OutputFormat {
+    let strings = json!({ "type": "array", "items": { "type": "string" } });
+    OutputFormat::Schema(json!({
+        "type": "object",
+        "properties": {
+            "mainPoints": { "type": "array", "items": {
+                "type": "object",
+                "properties": { "point": { "type": "string" }, "description": { "type": "string" } },
+                "required": ["point", "description"]
+            }},
+            "participantStances": { "type": "array", "items": {
+                "type": "object",
+                "properties": {
+                    "participant": { "type": "string" },
+                    "label": { "type": "string" },
+                    "confidence": { "type": "number", "minimum": 0, "maximum": 1 },
+                    "stance": { "type": "string" },
+                    "keyArguments": strings
+                },
+                "required": ["participant", "label", "confidence", "stance", "keyArguments"]
+            }},
+            "conflicts": { "type": "array", "items": {
+                "type": "object",
+                "properties": {
+                    "issue": { "type": "string" },
+                    "sides": strings,
+                    "description": { "type": "string" }
+                },
+                "required": ["issue", "sides", "description"]
+            }},
+            "commonGround": strings,
+            "unexploredAreas": strings
+        },
+        "required": ["mainPoints", "participantStances", "conflicts", "commonGround", "unexploredAreas"]
+    }))
+}
+
+/// モデル出力をパース（コードフェンス・末尾カンマ・途中切れは修復を試みる）
+pub fn parse(raw: &str) -> Result<DiscussionAnalysis, String> {
+    let analysis: DiscussionAnalysis =
+        llm_json::parse_llm_json(raw).map_err(|e| format!("議論分析の{}", e))?;
+    Ok(analysis.cleaned())
+}
+
+/// 分析結果のスナップショット（analysis_snapshots の1行）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalysisSnapshot {
+    pub id: i64,
+    pub session_id: i64,
+    /// 分析時点の発言数
+    pub message_count: i64,
+    pub model: String,
+    pub analysis: DiscussionAnalysis,
+    pub created_at: String,
+}
+
+/// スナップショットを保存（同じ発言数での再分析は上書き）
+/// message_count 未指定なら messages テーブルの現在の件数を使う
+pub async fn save_snapshot(
+    app: &AppHandle,
+    session_id: i64,
+    message_count: Option<i64>,
+    model: &str,
+    analysis: &DiscussionAnalysis,
+) -> Result<i64, String> {
+    let pool = db::pool(app).await?;
+    let message_count = match message_count {
+        Some(n) => n.max(0),
+        None => {
+            let (n,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM messages WHERE session_id = ?")
+                .bind(session_id)
+                .fetch_one(&pool)
+                .await
+                .map_err(|e| format!("発言数取得失敗: {}", e))?;
+            n
+        }
+    };
+    let payload = serde_json::to_string(analysis).map_err(|e| format!("分析結果のシリアライズ失敗: {}", e))?;
+    let keys = encryption::keys(app);
+    let (id,): (i64,) = sqlx::query_as(
+        "INSERT INTO analysis_snapshots (session_id, message_count, model, payload, created_at) VALUES (?, ?, ?, ?, ?)
+         ON CONFLICT(session_id, message_count) DO UPDATE SET
+             model = excluded.model, payload = excluded.payload, created_at = excluded.created_at
+         RETURNING id",
+    )
+    .bind(session_id)
+    .bind(message_count)
+    .bind(model)
+    .bind(keys.seal(&payload)?)
+    .bind(db::now_string())
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| format!("分析スナップショット保存失敗: {}", e))?;
+    save_stances(&pool, keys, id, session_id, message_count, analysis).await?;
+    Ok(id)
+}
+
+/// スナップショットの立場を stance_history に展開（再分析での上書き時は入れ替える）
+async fn save_stances(
+    pool: &sqlx::SqlitePool,
+    keys: &KeyStore,
+    snapshot_id: i64,
+    session_id: i64,
+    message_count: i64,
+    analysis: &DiscussionAnalysis,
+) -> Result<(), String> {
+    let mut tx = pool.begin().await.map_err(|e| format!("トランザクション開始失敗: {}", e))?;
+    sqlx::query("DELETE FROM stance_history WHERE snapshot_id = ?")
+        .bind(snapshot_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("立場の推移削除失敗: {}", e))?;
+    for stance in &analysis.participant_stances {
+        sqlx::query(
+            "INSERT OR REPLACE INTO stance_history
+               (snapshot_id, session_id, message_count, participant, label, confidence, stance)
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(snapshot_id)
+        .bind(session_id)
+        .bind(message_count)
+        .bind(stance.participant.trim())
+        .bind(keys.seal(&stance.label)?)
+        .bind(stance.confidence as f64)
+        .bind(keys.seal(&stance.stance)?)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("立場の推移保存失敗: {}", e))?;
+    }
+    tx.commit().await.map_err(|e| format!("トランザクション確定失敗: {}", e))
+}
+
+/// 最新の（発言数が最も多い）スナップショットの発言数と分析結果
+pub async fn latest_snapshot(app: &AppHandle, session_id: i64) -> Result<Option<(usize, DiscussionAnalysis)>, String> {
+    let pool = db::pool(app).await?;
+    let row: Option<(i64, String)> = sqlx::query_as(
+        "SELECT message_count, payload FROM analysis_snapshots WHERE session_id = ? ORDER BY message_count DESC LIMIT 1",
+    )
+    .bind(session_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| format!("分析スナップショット取得失敗: {}", e))?;
+    let Some((count, payload)) = row else { return Ok(None) };
+    let payload = encryption::keys(app).open(&payload)?;
+    Ok(serde_json::from_str(&payload).ok().map(|analysis| (count.max(0) as usize, analysis)))
+}
+
+/// 最新の分析から見た現在の合意（分析がなければ空）
+pub async fn latest_consensus(app: &AppHandle, session_id: i64) -> Result<Vec<String>, String> {
+    Ok(latest_snapshot(app, session_id).await?.map(|(_, a)| a.consensus()).unwrap_or_default())
+}
+
+// セッションの分析スナップショットを発言数の古い順に取得（立場・対立点の推移表示用）
+#[command]
+pub async fn get_analysis_history(app: AppHandle, session_id: i64) -> Result<Vec<AnalysisSnapshot>, String> {
+    let pool = db::pool(&app).await?;
+    let rows: Vec<(i64, i64, String, String, String)> = sqlx::query_as(
+        "SELECT id, message_count, model, payload, created_at FROM analysis_snapshots
+         WHERE session_id = ? ORDER BY message_count, id",
+    )
+    .bind(session_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("分析履歴取得失敗: {}", e))?;
+    let keys = encryption::keys(&app);
+    let mut snapshots = Vec::with_capacity(rows.len());
+    for (id, message_count, model, payload, created_at) in rows {
+        let Ok(analysis) = serde_json::from_str(&keys.open(&payload)?) else { continue };
+        snapshots.push(AnalysisSnapshot { id, session_id, message_count, model, analysis, created_at });
+    }
+    Ok(snapshots)
+}
+
+/// 立場の推移の1点（stance_history の1行）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StancePoint {
+    pub snapshot_id: i64,
+    /// 分析時点の発言数（グラフの横軸）
+    pub message_count: i64,
+    pub label: String,
+    pub confidence: f32,
+    pub stance: String,
+}
+
+// 参加者1人の立場ラベルと確信度を発言数の古い順に取得（立場の変化をグラフにする用）
+#[command]
+pub async fn get_stance_timeline(app: AppHandle, session_id: i64, participant: String) -> Result<Vec<StancePoint>, String> {
+    let pool = db::pool(&app).await?;
+    let rows: Vec<(i64, i64, String, f64, String)> = sqlx::query_as(
+        "SELECT snapshot_id, message_count, label, confidence, stance FROM stance_history
+         WHERE session_id = ? AND participant = ? ORDER BY message_count, snapshot_id",
+    )
+    .bind(session_id)
+    .bind(participant.trim())
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("立場の推移取得失敗: {}", e))?;
+    let keys = encryption::keys(&app);
+    rows.into_iter()
+        .map(|(snapshot_id, message_count, label, confidence, stance)| {
+            Ok(StancePoint {
+                snapshot_id,
+                message_count,
+                label: keys.open(&label)?,
+                confidence: confidence as f32,
+                stance: keys.open(&stance)?,
+            })
+        })
+        .collect()
+}
+
+// 画面の分析結果を session_analysis に保存し、行IDを返す（暗号化が有効なら暗号化する。要約は session_summaries にも記録する）
+#[command]
+pub async fn save_session_analysis(
+    app: AppHandle,
+    session_id: i64,
+    kind: String,
+    payload: String,
+) -> Result<i64, String> {
+    db::save_analysis(&app, session_id, &kind, &payload).await
+}
+
+// セッションの分析結果を新しい順に取得（kind 未指定なら全種類。暗号化されていれば復号する）
+#[command]
+pub async fn get_session_analysis(
+    app: AppHandle,
+    session_id: i64,
+    kind: Option<String>,
+    limit: Option<i64>,
+) -> Result<Vec<db::AnalysisRow>, String> {
+    db::load_analysis(&app, session_id, kind.as_deref(), limit.unwrap_or(10).clamp(1, 100)).await
+}
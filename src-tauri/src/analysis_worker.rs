@@ -0,0 +1,242 @@
+// 分析のバックグラウンドワーカー
+// 発言保存の通知を受け、一定件数の発言が増えるか一定時間発言が途絶えたら議論分析を実行して analysis://updated で通知する
+// 発言が途絶えた時は要約も未反映分まで更新する（件数による要約は discussion_engine が行う）
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use crate::{
+    analysis,
+    analysis::DiscussionAnalysis,
+    autosave, call_ollama_generate_background, config, db, discussion_engine,
+    generation::GenerationOptions,
+    is_allowed_model, prompts, session_settings,
+};
+
+pub const EVENT_ANALYSIS_UPDATED: &str = "analysis://updated";
+pub const EVENT_ANALYSIS_FAILED: &str = "analysis://failed";
+
+// アイドル判定の確認間隔
+const TICK_INTERVAL: Duration = Duration::from_secs(5);
+// 自動分析の温度（フロントエンドの ANALYSIS_OPTIONS と同じ）
+const ANALYSIS_TEMPERATURE: f32 = 0.3;
+
+/// ワーカーへの通知口（tauri::State で管理）
+pub struct AnalysisWorker {
+    tx: mpsc::UnboundedSender<(i64, usize)>,
+}
+
+/// analysis://updated のペイロード
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalysisUpdatedEvent {
+    pub session_id: i64,
+    pub analysis: DiscussionAnalysis,
+    /// 分析に反映済みの発言数
+    pub message_count: usize,
+    /// 前回の分析に差分を統合したものなら true
+    pub incremental: bool,
+}
+
+/// analysis://failed のペイロード
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AnalysisFailedEvent {
+    session_id: i64,
+    error: String,
+}
+
+/// 前回の分析以降の発言の状況
+struct PendingMessages {
+    new_messages: usize,
+    last_message_at: Instant,
+}
+
+/// セッションごとの分析待ちの発言（手動の発言・自動進行の発言のどちらも保存の通知で数える）
+#[derive(Default)]
+pub(crate) struct Schedule {
+    pending: HashMap<i64, PendingMessages>,
+    /// 最後に通知された発言数
+    totals: HashMap<i64, usize>,
+}
+
+impl Schedule {
+    /// 発言数が total になった保存を記録する
+    /// 増えた件数だけ数える（編集・再生成のように件数が変わらない保存は数えず、まとめて保存された発言はすべて数える）
+    pub(crate) fn record(&mut self, session_id: i64, total: usize) {
+        let added = match self.totals.insert(session_id, total) {
+            Some(previous) => total.saturating_sub(previous),
+            None => 1,
+        };
+        let pending = self
+            .pending
+            .entry(session_id)
+            .or_insert(PendingMessages { new_messages: 0, last_message_at: Instant::now() });
+        pending.new_messages += added;
+        pending.last_message_at = Instant::now();
+    }
+
+    /// 分析する時期のセッションと、発言が途絶えたためか（idle）
+    pub(crate) fn due(&self, interval: usize, idle_after: Option<Duration>) -> Vec<(i64, bool)> {
+        self.pending
+            .iter()
+            .filter_map(|(id, p)| {
+                let idle = idle_after.is_some_and(|d| p.last_message_at.elapsed() >= d);
+                (p.new_messages >= interval || idle).then_some((*id, idle))
+            })
+            .collect()
+    }
+
+    /// 分析を始めたセッションの待ちを消す
+    fn take(&mut self, session_id: i64) {
+        self.pending.remove(&session_id);
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    fn clear(&mut self) {
+        self.pending.clear();
+    }
+}
+
+/// ワーカーを起動して状態として登録する（アプリ起動時に1回呼ぶ）
+pub fn start(app: &AppHandle) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    app.manage(AnalysisWorker { tx });
+    let app = app.clone();
+    tauri::async_runtime::spawn(run(app, rx));
+}
+
+/// 発言が保存されたことをワーカーに伝える（total は保存後の発言数）
+pub fn notify(app: &AppHandle, session_id: i64, total: usize) {
+    if let Some(worker) = app.try_state::<AnalysisWorker>() {
+        let _ = worker.tx.send((session_id, total));
+    }
+}
+
+async fn run(app: AppHandle, mut rx: mpsc::UnboundedReceiver<(i64, usize)>) {
+    let mut schedule = Schedule::default();
+    let in_flight: Arc<Mutex<HashSet<i64>>> = Arc::default();
+    let mut tick = tokio::time::interval(TICK_INTERVAL);
+    loop {
+        tokio::select! {
+            received = rx.recv() => {
+                let Some((session_id, total)) = received else { break };
+                schedule.record(session_id, total);
+            }
+            _ = tick.tick() => {}
+        }
+        if schedule.is_empty() {
+            continue;
+        }
+        let settings = match config::load(&app).await {
+            Ok(s) => s,
+            Err(e) => {
+                warn!("自動分析の設定読込失敗: {}", e);
+                continue;
+            }
+        };
+        if !settings.auto_analysis {
+            schedule.clear();
+            continue;
+        }
+
+        let idle_after = (settings.analysis_idle_secs > 0).then(|| Duration::from_secs(settings.analysis_idle_secs));
+        for (session_id, idle) in schedule.due(settings.analysis_interval, idle_after) {
+            // 同じセッションの分析中は、終わった後の次の判定に回す
+            if !in_flight.lock().unwrap_or_else(|e| e.into_inner()).insert(session_id) {
+                continue;
+            }
+            schedule.take(session_id);
+            let app = app.clone();
+            let in_flight = in_flight.clone();
+            let summarize = idle && settings.auto_summary;
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = analyze_session(&app, session_id).await {
+                    warn!("自動分析失敗 (session_id={}): {}", session_id, e);
+                    let _ = app.emit(EVENT_ANALYSIS_FAILED, AnalysisFailedEvent { session_id, error: e });
+                }
+                if summarize {
+                    discussion_engine::summarize_when_idle(&app, session_id);
+                }
+                in_flight.lock().unwrap_or_else(|e| e.into_inner()).remove(&session_id);
+            });
+        }
+    }
+}
+
+/// 前回の分析（スナップショット）以降の発言を反映して分析し、保存・通知する
+/// 前回の分析がなければ全発言から分析する。新しい発言がなければ何もしない
+pub async fn analyze_session(app: &AppHandle, session_id: i64) -> Result<Option<AnalysisUpdatedEvent>, String> {
+    let session = db::load_session(app, session_id).await?;
+    let total = session.messages.len();
+    if total == 0 || !is_allowed_model(app, &session.model) {
+        return Ok(None);
+    }
+    let previous = analysis::latest_snapshot(app, session_id).await?.filter(|(count, _)| *count <= total);
+    if previous.as_ref().is_some_and(|(count, _)| *count == total) {
+        return Ok(None);
+    }
+
+    info!("自動分析開始: session_id={}, 発言数={}", session_id, total);
+    let participants = session.participant_names();
+    let language = session_settings::load(app, session_id).await?.language();
+    let (prompt, incremental) = match &previous {
+        Some((count, prev)) if *count > 0 => {
+            let prev = serde_json::to_string_pretty(prev).map_err(|e| format!("前回の分析のシリアライズ失敗: {}", e))?;
+            let new_messages = db::format_history(&session.messages[*count..]);
+            (prompts::build_incremental_analysis_prompt(&session.topic, &prev, &new_messages, &participants, language), true)
+        }
+        _ => (prompts::build_discussion_analysis_prompt(&session.topic, &session.history_text(), &participants, language), false),
+    };
+    let options = GenerationOptions { temperature: Some(ANALYSIS_TEMPERATURE), ..Default::default() }
+        .with_format(analysis::output_format());
+    let raw =
+        call_ollama_generate_background(app, &format!("analysis:{}", session_id), &session.model, &prompt, &options).await?;
+    let result = analysis::parse(&raw)?;
+
+    analysis::save_snapshot(app, session_id, Some(total as i64), &session.model, &result).await?;
+    let payload = serde_json::to_string(&result).map_err(|e| format!("分析結果のシリアライズ失敗: {}", e))?;
+    db::save_analysis(app, session_id, "analysis", &payload).await?;
+    autosave::schedule(app, session_id);
+
+    let event = AnalysisUpdatedEvent { session_id, analysis: result, message_count: total, incremental };
+    app.emit(EVENT_ANALYSIS_UPDATED, event.clone()).map_err(|e| format!("イベント送信失敗: {}", e))?;
+    info!("自動分析完了: session_id={}", session_id);
+    Ok(Some(event))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn analysis_is_due_when_messages_stop() {
+        let mut schedule = Schedule::default();
+        schedule.record(1, 1);
+        assert!(schedule.due(10, Some(Duration::from_secs(60))).is_empty());
+        assert_eq!(schedule.due(10, Some(Duration::ZERO)), vec![(1, true)]);
+    }
+
+    #[test]
+    fn only_added_messages_count_toward_the_interval() {
+        let mut schedule = Schedule::default();
+        schedule.record(1, 1);
+        // 編集・再生成は件数が変わらない
+        schedule.record(1, 1);
+        schedule.record(1, 1);
+        assert!(schedule.due(3, None).is_empty());
+        // 画面からまとめて保存された発言
+        schedule.record(1, 3);
+        assert_eq!(schedule.due(3, None), vec![(1, false)]);
+        schedule.take(1);
+        assert!(schedule.due(3, None).is_empty());
+    }
+}
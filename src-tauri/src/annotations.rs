@@ -0,0 +1,137 @@
+// 発言へのメモ・ハイライト・リアクション
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle};
+use tracing::info;
+
+use crate::{db, encryption};
+
+// リアクション（絵文字など）の最大文字数
+const MAX_REACTION_CHARS: usize = 16;
+
+/// 注釈の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnnotationKind {
+    Note,
+    Highlight,
+    Reaction,
+}
+
+impl AnnotationKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            AnnotationKind::Note => "note",
+            AnnotationKind::Highlight => "highlight",
+            AnnotationKind::Reaction => "reaction",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "note" => Some(AnnotationKind::Note),
+            "highlight" => Some(AnnotationKind::Highlight),
+            "reaction" => Some(AnnotationKind::Reaction),
+            _ => None,
+        }
+    }
+}
+
+/// 注釈1件
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Annotation {
+    pub id: i64,
+    pub session_id: i64,
+    pub message_index: i64,
+    pub kind: AnnotationKind,
+    pub content: String,
+    pub created_at: String,
+}
+
+/// セッションの注釈を発言順に取得
+pub async fn load_for_session(app: &AppHandle, session_id: i64) -> Result<Vec<Annotation>, String> {
+    let pool = db::pool(app).await?;
+    let rows: Vec<(i64, i64, String, String, String)> = sqlx::query_as(
+        "SELECT id, message_index, kind, content, created_at FROM annotations
+         WHERE session_id = ? ORDER BY message_index, id",
+    )
+    .bind(session_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("注釈取得失敗: {}", e))?;
+
+    let keys = encryption::keys(app);
+    let mut annotations = Vec::with_capacity(rows.len());
+    for (id, message_index, kind, content, created_at) in rows {
+        let Some(kind) = AnnotationKind::parse(&kind) else { continue };
+        annotations.push(Annotation { id, session_id, message_index, kind, content: keys.open(&content)?, created_at });
+    }
+    Ok(annotations)
+}
+
+// 発言に注釈を付ける
+#[command]
+pub async fn annotate_message(
+    app: AppHandle,
+    session_id: i64,
+    message_index: i64,
+    kind: AnnotationKind,
+    content: Option<String>,
+) -> Result<Annotation, String> {
+    info!("annotate_message 呼び出し: session_id={}, index={}, kind={:?}", session_id, message_index, kind);
+    let content = content.unwrap_or_default().trim().to_string();
+    match kind {
+        AnnotationKind::Note if content.is_empty() => return Err("メモの内容が空です".into()),
+        AnnotationKind::Reaction if content.is_empty() || content.chars().count() > MAX_REACTION_CHARS => {
+            return Err(format!("リアクションは1〜{}文字で指定してください", MAX_REACTION_CHARS));
+        }
+        _ => {}
+    }
+
+    let session = db::load_session(&app, session_id).await?;
+    if message_index < 0 || message_index as usize >= session.messages.len() {
+        return Err(format!("発言番号が範囲外です: {}", message_index));
+    }
+
+    let pool = db::pool(&app).await?;
+    let created_at = db::now_string();
+    let result = sqlx::query(
+        "INSERT INTO annotations (session_id, message_index, kind, content, created_at) VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(session_id)
+    .bind(message_index)
+    .bind(kind.as_str())
+    .bind(encryption::keys(&app).seal(&content)?)
+    .bind(&created_at)
+    .execute(&pool)
+    .await
+    .map_err(|e| format!("注釈保存失敗: {}", e))?;
+
+    Ok(Annotation {
+        id: result.last_insert_rowid(),
+        session_id,
+        message_index,
+        kind,
+        content,
+        created_at,
+    })
+}
+
+// セッションの注釈一覧
+#[command]
+pub async fn list_annotations(app: AppHandle, session_id: i64) -> Result<Vec<Annotation>, String> {
+    load_for_session(&app, session_id).await
+}
+
+// 注釈を削除
+#[command]
+pub async fn delete_annotation(app: AppHandle, annotation_id: i64) -> Result<(), String> {
+    info!("delete_annotation 呼び出し: id={}", annotation_id);
+    let pool = db::pool(&app).await?;
+    sqlx::query("DELETE FROM annotations WHERE id = ?")
+        .bind(annotation_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| format!("注釈削除失敗: {}", e))?;
+    Ok(())
+}
@@ -0,0 +1,105 @@
+// アプリ全体で共有する状態
+// 生成キュー・サーキットブレーカー・DB 暗号化の鍵・candle で読み込んだモデル・現在のバックエンド・実行中の設定をまとめ、起動時に .manage() で登録する
+// コマンドは State<'_, AppState>、それ以外は app.state::<AppState>() で受け取る。生成バックエンドには作成時に必要なものを渡す
+use std::sync::{Arc, RwLock, RwLockReadGuard};
+
+use tauri::AppHandle;
+
+#[cfg(feature = "candle")]
+use crate::backend_candle::ModelCache;
+use crate::{
+    backend::{self, BackendKind, CandleConfig, LlmBackend, OllamaClient},
+    circuit_breaker::CircuitBreaker,
+    db,
+    discussion_engine::EngineState,
+    embeddings::EmbeddingSettings,
+    encryption::KeyStore,
+    gen_queue::{GenQueue, DEFAULT_MAX_CONCURRENT_GENERATIONS},
+    model_access::ModelAccess,
+    openai_backend::OpenAiCompatConfig,
+    postprocess::PostprocessSettings,
+    privacy::ActivePrivacy,
+    requests::RequestRegistry,
+};
+
+/// 設定の1項目（起動時と設定保存時に config::apply が差し替え、参照側は読み出した時点の値を使う）
+#[derive(Default)]
+pub struct Setting<T>(RwLock<T>);
+
+impl<T: Clone> Setting<T> {
+    pub fn get(&self) -> T {
+        self.read().clone()
+    }
+
+    pub fn read(&self) -> RwLockReadGuard<'_, T> {
+        self.0.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn set(&self, value: T) {
+        *self.0.write().unwrap_or_else(|e| e.into_inner()) = value;
+    }
+}
+
+/// アプリ設定のうち、実行中のモジュールが参照するもの（生成バックエンドとログ出力は Arc で共有する）
+#[derive(Default)]
+pub struct RuntimeSettings {
+    /// 接続設定を反映した Ollama クライアント
+    pub client: Setting<OllamaClient>,
+    pub candle: Setting<CandleConfig>,
+    pub openai: Setting<OpenAiCompatConfig>,
+    pub models: Setting<ModelAccess>,
+    pub privacy: Setting<ActivePrivacy>,
+    pub postprocess: Setting<PostprocessSettings>,
+    pub embeddings: Setting<EmbeddingSettings>,
+}
+
+pub struct AppState {
+    /// 生成キュー（同時実行数の制限と優先度順の待機）
+    pub queue: GenQueue,
+    /// 生成バックエンドのサーキットブレーカー
+    pub breaker: Arc<CircuitBreaker>,
+    /// 暗号化の状態と展開したデータ鍵
+    pub keys: KeyStore,
+    /// candle で読み込んだモデル
+    #[cfg(feature = "candle")]
+    pub candle: Arc<ModelCache>,
+    pub settings: Arc<RuntimeSettings>,
+    /// 現在の生成バックエンド（backend::select で切り替える）
+    pub backend: RwLock<Arc<dyn LlmBackend>>,
+    /// 選択中のワークスペースの接続URL（DbInstances のキー）
+    pub db_url: Setting<String>,
+    /// 個別キャンセル用の実行中リクエスト
+    pub requests: RequestRegistry,
+    /// 議論エンジンの実行時状態
+    pub engine: EngineState,
+}
+
+impl AppState {
+    /// 状態の変化（生成の開始・バックエンドの停止と復旧）は app へイベントで通知する
+    /// バックエンドは環境変数で指定されたもの、なければ既定のもので始める
+    pub fn new(app: &AppHandle) -> Self {
+        let settings = Arc::new(RuntimeSettings::default());
+        let breaker = Arc::new(CircuitBreaker::new(Some(app.clone()), settings.clone()));
+        #[cfg(feature = "candle")]
+        let candle = Arc::<ModelCache>::default();
+        let backend = backend::instantiate(
+            BackendKind::from_env().unwrap_or_default(),
+            &breaker,
+            &settings,
+            #[cfg(feature = "candle")]
+            &candle,
+        );
+        Self {
+            queue: GenQueue::new(DEFAULT_MAX_CONCURRENT_GENERATIONS, Some(app.clone()), settings.clone()),
+            breaker,
+            keys: KeyStore::default(),
+            #[cfg(feature = "candle")]
+            candle,
+            settings,
+            backend: RwLock::new(backend),
+            db_url: Setting(RwLock::new(db::DB_URL.to_string())),
+            requests: RequestRegistry::default(),
+            engine: EngineState::default(),
+        }
+    }
+}
@@ -0,0 +1,369 @@
+// 議論の添付資料
+// テキスト・Markdown・PDF（と fetch_url_context で取得したWebページ）からテキストを取り出して段落単位のチャンクに分けて保存し（埋め込みが有効ならベクトルも）、
+// 参加者の発言プロンプトにはテーマと直近の発言に近いチャンクを <reference_material> として差し込む
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde_json::json;
+use tauri::{command, AppHandle};
+use tracing::{info, warn};
+
+use crate::{audit, db, embeddings, encryption, permissions, prompts::ReferenceChunk, session_qa};
+
+// 読み込むファイルの上限
+const MAX_FILE_BYTES: u64 = 20 * 1024 * 1024;
+// 取り出したテキストの上限（超えた分は捨てる）
+const MAX_TEXT_CHARS: usize = 400_000;
+// 1チャンクの目安の文字数
+const CHUNK_CHARS: usize = 800;
+// 1セッションあたりの添付資料の上限
+const MAX_ATTACHMENTS_PER_SESSION: i64 = 10;
+// プロンプトに差し込むチャンクの数と合計文字数
+const MAX_REFERENCE_CHUNKS: usize = 3;
+const MAX_REFERENCE_CHARS: usize = 2400;
+// 検索語に使う直近の発言の文字数
+const QUERY_HISTORY_CHARS: usize = 400;
+
+/// 資料の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DocumentKind {
+    Text,
+    Markdown,
+    Pdf,
+    /// fetch_url_context で取得したWebページ
+    Web,
+}
+
+impl DocumentKind {
+    fn from_path(path: &Path) -> Result<Self, String> {
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_lowercase();
+        match ext.as_str() {
+            "txt" | "text" => Ok(DocumentKind::Text),
+            "md" | "markdown" => Ok(DocumentKind::Markdown),
+            "pdf" => Ok(DocumentKind::Pdf),
+            _ => Err(format!("対応していないファイル形式です: '{}'（txt / md / pdf）", ext)),
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            DocumentKind::Text => "text",
+            DocumentKind::Markdown => "markdown",
+            DocumentKind::Pdf => "pdf",
+            DocumentKind::Web => "web",
+        }
+    }
+}
+
+/// 添付資料（attachments の1行）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttachmentInfo {
+    pub id: i64,
+    pub session_id: i64,
+    pub file_name: String,
+    pub kind: String,
+    pub chars: i64,
+    pub chunks: i64,
+    /// ベクトル化できたチャンクの数（埋め込みが無効・失敗なら 0）
+    pub embedded_chunks: i64,
+    pub created_at: String,
+}
+
+/// ファイルからテキストを取り出す（PDF の解析は別スレッドで行い、解析中のパニックもエラーとして返す）
+async fn extract_text(path: &Path, kind: DocumentKind) -> Result<String, String> {
+    let bytes = tokio::fs::read(path).await.map_err(|e| format!("ファイル読み込み失敗: {}", e))?;
+    let text = match kind {
+        DocumentKind::Text | DocumentKind::Markdown | DocumentKind::Web => String::from_utf8_lossy(&bytes).into_owned(),
+        DocumentKind::Pdf => tauri::async_runtime::spawn_blocking(move || pdf_extract::extract_text_from_mem(&bytes))
+            .await
+            .map_err(|_| "PDF の解析に失敗しました".to_string())?
+            .map_err(|e| format!("PDF からテキストを取り出せません: {}", e))?,
+    };
+    let text = text.replace("\r\n", "\n").replace('\u{0}', "");
+    Ok(text.chars().take(MAX_TEXT_CHARS).collect())
+}
+
+/// 長すぎる段落を文の区切り（なければ文字数）で分ける
+fn split_long(paragraph: &str) -> Vec<String> {
+    let mut pieces = Vec::new();
+    let mut current = String::new();
+    for c in paragraph.chars() {
+        current.push(c);
+        let count = current.chars().count();
+        let sentence_end = matches!(c, '。' | '！' | '？' | '.' | '!' | '?');
+        if count >= CHUNK_CHARS || (sentence_end && count >= CHUNK_CHARS * 3 / 4) {
+            pieces.push(std::mem::take(&mut current));
+        }
+    }
+    if !current.trim().is_empty() {
+        pieces.push(current);
+    }
+    pieces
+}
+
+/// 空行区切りの段落をまとめて CHUNK_CHARS 前後のチャンクにする
+fn chunk_text(text: &str) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    let paragraphs = text.split("\n\n").map(str::trim).filter(|p| !p.is_empty());
+    for paragraph in paragraphs.flat_map(split_long) {
+        let paragraph = paragraph.trim();
+        if !current.is_empty() && current.chars().count() + paragraph.chars().count() > CHUNK_CHARS {
+            chunks.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push_str("\n\n");
+        }
+        current.push_str(paragraph);
+    }
+    if !current.trim().is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+/// attachments の1行とチャンク数（id, セッション, ファイル名, 種類, 文字数, 作成日時, チャンク数, ベクトル化済み数）
+type AttachmentRow = (i64, i64, String, String, i64, String, i64, i64);
+
+async fn load_info(pool: &sqlx::SqlitePool, attachment_id: i64) -> Result<AttachmentInfo, String> {
+    let (id, session_id, file_name, kind, chars, created_at, chunks, embedded_chunks): AttachmentRow = sqlx::query_as(
+        "SELECT a.id, a.session_id, a.file_name, a.kind, a.chars, a.created_at,
+                COUNT(c.id), COUNT(c.vector)
+         FROM attachments a LEFT JOIN attachment_chunks c ON c.attachment_id = a.id
+         WHERE a.id = ? GROUP BY a.id",
+    )
+    .bind(attachment_id)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| format!("添付資料取得失敗: {}", e))?;
+    Ok(AttachmentInfo { id, session_id, file_name, kind, chars, chunks, embedded_chunks, created_at })
+}
+
+// 資料をセッションに添付する（ツール権限 document-attach（filesystem-read）が必要）
+#[command]
+pub async fn attach_document(app: AppHandle, session_id: i64, path: String) -> Result<AttachmentInfo, String> {
+    info!("attach_document 呼び出し: session_id={}, path={}", session_id, path);
+    permissions::authorize(&app, permissions::TOOL_DOCUMENT_ATTACH).await?;
+    let path = PathBuf::from(path);
+    let kind = DocumentKind::from_path(&path)?;
+    let size = std::fs::metadata(&path).map_err(|e| format!("ファイルを開けません: {}", e))?.len();
+    if size > MAX_FILE_BYTES {
+        return Err(format!("ファイルが大きすぎます: {} bytes（上限 {} bytes）", size, MAX_FILE_BYTES));
+    }
+    db::load_session(&app, session_id).await?;
+    let text = extract_text(&path, kind).await?;
+    let file_name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    store_document(&app, session_id, &file_name, kind, &text).await
+}
+
+/// 取り出したテキストをチャンクに分けて添付資料として保存する（attach_document / fetch_url_context）
+pub async fn store_document(
+    app: &AppHandle,
+    session_id: i64,
+    file_name: &str,
+    kind: DocumentKind,
+    text: &str,
+) -> Result<AttachmentInfo, String> {
+    let pool = db::pool(app).await?;
+    let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM attachments WHERE session_id = ?")
+        .bind(session_id)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| format!("添付資料取得失敗: {}", e))?;
+    if count >= MAX_ATTACHMENTS_PER_SESSION {
+        return Err(format!("添付できる資料は1セッションあたり{}件までです", MAX_ATTACHMENTS_PER_SESSION));
+    }
+
+    let chunks = chunk_text(text);
+    if chunks.is_empty() {
+        return Err("資料からテキストを取り出せませんでした".into());
+    }
+    let hash = db::content_hash(text);
+    let duplicate: Option<(i64,)> =
+        sqlx::query_as("SELECT id FROM attachments WHERE session_id = ? AND content_hash = ?")
+            .bind(session_id)
+            .bind(&hash)
+            .fetch_optional(&pool)
+            .await
+            .map_err(|e| format!("添付資料取得失敗: {}", e))?;
+    if duplicate.is_some() {
+        return Err("同じ内容の資料がすでに添付されています".into());
+    }
+
+    // 埋め込みはトランザクションの外で先に計算する（失敗したチャンクはキーワード照合だけで使う）
+    let mut vectors = Vec::with_capacity(chunks.len());
+    for chunk in &chunks {
+        let vector = match embeddings::embed_text(app, chunk).await {
+            Ok(v) => v,
+            Err(e) => {
+                warn!("資料のベクトル化に失敗（キーワード照合のみ）: {}", e);
+                None
+            }
+        };
+        let failed = vector.is_none();
+        vectors.push(vector.map(|v| embeddings::to_blob(&v)));
+        if failed {
+            // 1件失敗したら残りも失敗する可能性が高いので打ち切る
+            vectors.resize(chunks.len(), None);
+            break;
+        }
+    }
+
+    let mut tx = pool.begin().await.map_err(|e| format!("トランザクション開始失敗: {}", e))?;
+    let attachment_id = sqlx::query(
+        "INSERT INTO attachments (session_id, file_name, kind, content_hash, chars, created_at) VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(session_id)
+    .bind(file_name)
+    .bind(kind.as_str())
+    .bind(&hash)
+    .bind(text.chars().count() as i64)
+    .bind(db::now_string())
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("添付資料保存失敗: {}", e))?
+    .last_insert_rowid();
+    let keys = encryption::keys(app);
+    for (position, (chunk, vector)) in chunks.iter().zip(vectors).enumerate() {
+        sqlx::query(
+            "INSERT INTO attachment_chunks (attachment_id, session_id, position, content, vector) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(attachment_id)
+        .bind(session_id)
+        .bind(position as i64)
+        .bind(keys.seal(chunk)?)
+        .bind(vector)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("資料のチャンク保存失敗: {}", e))?;
+    }
+    tx.commit().await.map_err(|e| format!("トランザクション確定失敗: {}", e))?;
+    audit::record(
+        "attach",
+        "document",
+        json!({ "sessionId": session_id, "kind": kind.as_str(), "chunks": chunks.len() }),
+    );
+    load_info(&pool, attachment_id).await
+}
+
+// セッションの添付資料の一覧（古い順）
+#[command]
+pub async fn list_attachments(app: AppHandle, session_id: i64) -> Result<Vec<AttachmentInfo>, String> {
+    let pool = db::pool(&app).await?;
+    let ids: Vec<(i64,)> = sqlx::query_as("SELECT id FROM attachments WHERE session_id = ? ORDER BY id")
+        .bind(session_id)
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| format!("添付資料取得失敗: {}", e))?;
+    let mut out = Vec::with_capacity(ids.len());
+    for (id,) in ids {
+        out.push(load_info(&pool, id).await?);
+    }
+    Ok(out)
+}
+
+// 添付資料を削除（チャンクも削除される）
+#[command]
+pub async fn delete_attachment(app: AppHandle, attachment_id: i64) -> Result<(), String> {
+    info!("delete_attachment 呼び出し: id={}", attachment_id);
+    let pool = db::pool(&app).await?;
+    let result = sqlx::query("DELETE FROM attachments WHERE id = ?")
+        .bind(attachment_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| format!("添付資料削除失敗: {}", e))?;
+    if result.rows_affected() == 0 {
+        return Err(format!("添付資料が見つかりません: id={}", attachment_id));
+    }
+    Ok(())
+}
+
+/// 発言プロンプトに差し込む資料の抜粋を選ぶ（テーマと直近の発言に近いチャンク）
+/// 添付資料がない・取得に失敗した場合は空（発言の生成は止めない）
+pub async fn reference_material(
+    app: &AppHandle,
+    session_id: Option<i64>,
+    topic: &str,
+    history: &str,
+) -> Vec<ReferenceChunk> {
+    let Some(session_id) = session_id else { return Vec::new() };
+    match select_chunks(app, session_id, topic, history).await {
+        Ok(chunks) => chunks,
+        Err(e) => {
+            warn!("添付資料の抜粋を取得できません (session_id={}): {}", session_id, e);
+            Vec::new()
+        }
+    }
+}
+
+async fn select_chunks(
+    app: &AppHandle,
+    session_id: i64,
+    topic: &str,
+    history: &str,
+) -> Result<Vec<ReferenceChunk>, String> {
+    let pool = db::pool(app).await?;
+    let rows: Vec<(String, String, Option<Vec<u8>>)> = sqlx::query_as(
+        "SELECT a.file_name, c.content, c.vector FROM attachment_chunks c
+         JOIN attachments a ON a.id = c.attachment_id
+         WHERE c.session_id = ? ORDER BY a.id, c.position",
+    )
+    .bind(session_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("資料のチャンク取得失敗: {}", e))?;
+    if rows.is_empty() {
+        return Ok(Vec::new());
+    }
+    let keys = encryption::keys(app);
+    let rows = rows
+        .into_iter()
+        .map(|(source, content, vector)| Ok((source, keys.open(&content)?, vector)))
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let tail: String = {
+        let chars: Vec<char> = history.chars().collect();
+        chars[chars.len().saturating_sub(QUERY_HISTORY_CHARS)..].iter().collect()
+    };
+    let query = format!("{}\n{}", topic, tail);
+    // ベクトルのあるチャンクは意味の近さ、ないチャンク（または埋め込み失敗時）は文字の重なりで順位付けする
+    let wanted_vector = if rows.iter().any(|(_, _, v)| v.is_some()) {
+        embeddings::embed_text(app, &query).await.unwrap_or_else(|e| {
+            warn!("検索語のベクトル化に失敗（文字の重なりで選択）: {}", e);
+            None
+        })
+    } else {
+        None
+    };
+    let wanted_fragments = session_qa::fragments(&query);
+    let mut scored: Vec<(f32, String, String)> = rows
+        .into_iter()
+        .map(|(source, content, vector)| {
+            let semantic = match (&wanted_vector, vector) {
+                (Some(wanted), Some(blob)) => embeddings::similarity(wanted, &embeddings::from_blob(&blob)),
+                _ => None,
+            };
+            let score = semantic.unwrap_or_else(|| {
+                let have = session_qa::fragments(&content);
+                let overlap = wanted_fragments.iter().filter(|f| have.contains(*f)).count();
+                overlap as f32 / wanted_fragments.len().max(1) as f32
+            });
+            (score, source, content)
+        })
+        .collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    let mut total = 0;
+    let mut picked = Vec::new();
+    for (score, source, content) in scored.into_iter().take(MAX_REFERENCE_CHUNKS) {
+        let len = content.chars().count();
+        if score <= 0.0 || (!picked.is_empty() && total + len > MAX_REFERENCE_CHARS) {
+            break;
+        }
+        total += len;
+        picked.push(ReferenceChunk { source, content });
+    }
+    Ok(picked)
+}
@@ -0,0 +1,90 @@
+// 監査ログ
+// 安全ポリシーの適用などの記録を audit_log に残す。生成呼び出しの奥から使うため、AppHandle は起動時に保持しておく
+use std::sync::OnceLock;
+
+use serde::Serialize;
+use tauri::{command, AppHandle, Manager};
+use tracing::warn;
+
+use crate::{app_state::AppState, db, privacy};
+
+// 保持する監査ログの件数
+const MAX_LOG_ROWS: i64 = 5000;
+
+static APP: OnceLock<AppHandle> = OnceLock::new();
+
+/// 監査ログ1件
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    pub id: i64,
+    pub created_at: String,
+    /// 記録元の種別（"safety" など）
+    pub category: String,
+    pub action: String,
+    pub detail: serde_json::Value,
+}
+
+/// 起動時に1回呼ぶ
+pub fn init(app: AppHandle) {
+    let _ = APP.set(app);
+}
+
+/// 監査ログを記録（detail の文字列は伏せ字処理してから保存。書き込みはバックグラウンド。失敗はログ出力のみ）
+pub fn record(category: &str, action: &str, mut detail: serde_json::Value) {
+    let Some(app) = APP.get().cloned() else {
+        warn!("監査ログ未初期化のため記録できません: {} {}", category, action);
+        return;
+    };
+    privacy::redact_json(&app.state::<AppState>().settings, &mut detail);
+    let (category, action) = (category.to_string(), action.to_string());
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = insert(&app, &category, &action, &detail).await {
+            warn!("監査ログ保存失敗: {}", e);
+        }
+    });
+}
+
+async fn insert(app: &AppHandle, category: &str, action: &str, detail: &serde_json::Value) -> Result<(), String> {
+    let pool = db::pool(app).await?;
+    let id = sqlx::query("INSERT INTO audit_log (created_at, category, action, detail) VALUES (?, ?, ?, ?)")
+        .bind(db::now_string())
+        .bind(category)
+        .bind(action)
+        .bind(detail.to_string())
+        .execute(&pool)
+        .await
+        .map_err(|e| e.to_string())?
+        .last_insert_rowid();
+    sqlx::query("DELETE FROM audit_log WHERE id <= ?")
+        .bind(id - MAX_LOG_ROWS)
+        .execute(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+// 監査ログを新しい順に取得（category 指定で絞り込み）
+#[command]
+pub async fn get_audit_log(app: AppHandle, limit: Option<i64>, category: Option<String>) -> Result<Vec<AuditEntry>, String> {
+    let pool = db::pool(&app).await?;
+    let rows: Vec<(i64, String, String, String, String)> = sqlx::query_as(
+        "SELECT id, created_at, category, action, detail FROM audit_log
+         WHERE (?1 IS NULL OR category = ?1) ORDER BY id DESC LIMIT ?2",
+    )
+    .bind(category)
+    .bind(limit.unwrap_or(100).clamp(1, MAX_LOG_ROWS))
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("監査ログ取得失敗: {}", e))?;
+    Ok(rows
+        .into_iter()
+        .map(|(id, created_at, category, action, detail)| AuditEntry {
+            id,
+            created_at,
+            category,
+            action,
+            detail: serde_json::from_str(&detail).unwrap_or(serde_json::Value::Null),
+        })
+        .collect())
+}
@@ -0,0 +1,148 @@
+// 自動保存のチェックポイントとクラッシュ後の復元
+// 発言の保存・要約・分析のたびに、保存済みの発言数と最新の要約（session_summaries）・分析の行IDを session_checkpoints に記録する
+// 画面を閉じる・議論を終えるなど正常に区切った時は close_session で閉じ、閉じられないまま残った最新のものを
+// 次回起動時に recover_unsaved_session で復元する
+use serde::Serialize;
+use tauri::{command, AppHandle};
+use tracing::{info, warn};
+
+use crate::{db, db::ParticipantsData, db::StoredMessage, encryption, encryption::KeyStore, participant_memory};
+
+const STATUS_ACTIVE: &str = "active";
+const STATUS_CLOSED: &str = "closed";
+
+/// セッションの現在の状態をチェックポイントとして記録する
+async fn checkpoint(app: &AppHandle, session_id: i64) -> Result<(), String> {
+    let pool = db::pool(app).await?;
+    sqlx::query(
+        "INSERT INTO session_checkpoints (session_id, message_count, summary_id, analysis_id, status, updated_at)
+         SELECT s.id,
+                json_array_length(CASE WHEN json_valid(s.messages) THEN s.messages ELSE '[]' END),
+                (SELECT MAX(id) FROM session_summaries WHERE session_id = s.id),
+                (SELECT MAX(id) FROM session_analysis WHERE session_id = s.id AND kind = 'analysis'),
+                ?, ?
+         FROM sessions s WHERE s.id = ?
+         ON CONFLICT(session_id) DO UPDATE SET
+           message_count = excluded.message_count, summary_id = excluded.summary_id,
+           analysis_id = excluded.analysis_id, status = excluded.status, updated_at = excluded.updated_at",
+    )
+    .bind(STATUS_ACTIVE)
+    .bind(db::now_string())
+    .bind(session_id)
+    .execute(&pool)
+    .await
+    .map_err(|e| format!("チェックポイント保存失敗: {}", e))?;
+    Ok(())
+}
+
+/// 発言・要約・分析の保存後に呼ぶ。チェックポイントをバックグラウンドで記録する
+pub fn schedule(app: &AppHandle, session_id: i64) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = checkpoint(&app, session_id).await {
+            warn!("自動保存に失敗 (session_id={}): {}", session_id, e);
+        }
+    });
+}
+
+/// チェックポイントの行（セッション, 発言数, 要約の行ID, 分析の行ID, 記録日時）
+type CheckpointRow = (i64, i64, Option<i64>, Option<i64>, String);
+
+/// recover_unsaved_session の戻り値
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveredSession {
+    pub session_id: i64,
+    pub topic: String,
+    pub participants: ParticipantsData,
+    pub messages: Vec<StoredMessage>,
+    pub model: String,
+    /// チェックポイント時点の要約
+    pub summary: Option<String>,
+    /// チェックポイント時点の分析（DiscussionAnalysis の JSON）
+    pub analysis: Option<serde_json::Value>,
+    /// 自動進行が途中で止まっている（resume_pending_runs で再開できる）
+    pub pending_run: bool,
+    pub checkpoint_at: String,
+}
+
+async fn analysis_payload(pool: &sqlx::SqlitePool, keys: &KeyStore, id: Option<i64>) -> Result<Option<String>, String> {
+    let Some(id) = id else { return Ok(None) };
+    let row: Option<(String,)> = sqlx::query_as("SELECT payload FROM session_analysis WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("分析結果取得失敗: {}", e))?;
+    row.map(|(payload,)| keys.open(&payload)).transpose()
+}
+
+async fn summary_text(pool: &sqlx::SqlitePool, keys: &KeyStore, id: Option<i64>) -> Result<Option<String>, String> {
+    let Some(id) = id else { return Ok(None) };
+    let row: Option<(String,)> = sqlx::query_as("SELECT summary FROM session_summaries WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("要約取得失敗: {}", e))?;
+    row.map(|(summary,)| keys.open(&summary)).transpose()
+}
+
+// 閉じられないまま残った最新のセッションを、チェックポイント時点の発言・要約・分析とともに返す（なければ None）
+#[command]
+pub async fn recover_unsaved_session(app: AppHandle) -> Result<Option<RecoveredSession>, String> {
+    info!("recover_unsaved_session 呼び出し");
+    let pool = db::pool(&app).await?;
+    let row: Option<CheckpointRow> = sqlx::query_as(
+        "SELECT c.session_id, c.message_count, c.summary_id, c.analysis_id, c.updated_at
+         FROM session_checkpoints c JOIN sessions s ON s.id = c.session_id
+         WHERE c.status = ? ORDER BY datetime(c.updated_at) DESC, c.session_id DESC LIMIT 1",
+    )
+    .bind(STATUS_ACTIVE)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| format!("チェックポイント取得失敗: {}", e))?;
+    let Some((session_id, message_count, summary_id, analysis_id, checkpoint_at)) = row else {
+        return Ok(None);
+    };
+
+    let session = db::load_session(&app, session_id).await?;
+    let mut messages = session.messages;
+    // チェックポイントより後に書きかけの発言があっても、記録済みの時点までを復元する
+    messages.truncate(message_count.max(0) as usize);
+    let summary = summary_text(&pool, encryption::keys(&app), summary_id).await?;
+    let analysis = analysis_payload(&pool, encryption::keys(&app), analysis_id)
+        .await?
+        .and_then(|payload| serde_json::from_str(&payload).ok());
+    let (pending,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM engine_runs WHERE session_id = ?")
+        .bind(session_id)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| format!("実行状態取得失敗: {}", e))?;
+
+    Ok(Some(RecoveredSession {
+        session_id,
+        topic: session.topic,
+        participants: session.participants,
+        messages,
+        model: session.model,
+        summary,
+        analysis,
+        pending_run: pending > 0,
+        checkpoint_at,
+    }))
+}
+
+// セッションを正常に区切ったことを記録する（以後 recover_unsaved_session の対象にしない）
+#[command]
+pub async fn close_session(app: AppHandle, session_id: i64) -> Result<(), String> {
+    info!("close_session 呼び出し: session_id={}", session_id);
+    let pool = db::pool(&app).await?;
+    sqlx::query("UPDATE session_checkpoints SET status = ?, updated_at = ? WHERE session_id = ?")
+        .bind(STATUS_CLOSED)
+        .bind(db::now_string())
+        .bind(session_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| format!("チェックポイント更新失敗: {}", e))?;
+    participant_memory::schedule(&app, session_id);
+    Ok(())
+}
@@ -0,0 +1,733 @@
+// LLM バックエンドの抽象化
+// 生成呼び出しはすべて current() のバックエンドを経由し、設定で Ollama / OpenAI 互換サーバー / モック / candle（--features candle）を切り替える
+// バックエンドを増やす時は LlmBackend を実装して BackendKind と instantiate に加えれば、既存のコマンドからそのまま使える
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use dewai_core::ollama;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Client, ClientBuilder, Method, Proxy, RequestBuilder};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{AppHandle, Manager};
+use tracing::{info, warn};
+
+use crate::{
+    app_state::{AppState, RuntimeSettings},
+    circuit_breaker::CircuitBreaker,
+    fixture_backend::{RecordingBackend, ReplayBackend},
+    gen_queue,
+    gen_error::GenError,
+    generation::{GenerationOptions, GenerationResult},
+    health::LoadedModel,
+    mock_backend::MockBackend,
+    openai_backend::OpenAiCompatBackend,
+    timeouts,
+};
+
+// 接続設定の範囲
+const MIN_TIMEOUT_SECS: u64 = 5;
+const MAX_TIMEOUT_SECS: u64 = 600;
+const MIN_DEADLINE_SECS: u64 = 30;
+const MAX_DEADLINE_SECS: u64 = 3600;
+const MAX_DEADLINE_GRACE_SECS: u64 = 600;
+const MAX_RETRIES_LIMIT: u8 = 10;
+const MIN_RETRY_BACKOFF_MS: u64 = 50;
+const MAX_RETRY_BACKOFF_MS: u64 = 10_000;
+const MAX_BREAKER_THRESHOLD: u8 = 50;
+const MIN_BREAKER_COOLDOWN_SECS: u64 = 5;
+const MAX_BREAKER_COOLDOWN_SECS: u64 = 600;
+const MAX_PARALLEL_LIMIT: usize = 8;
+// 疎通確認は短い待ち時間で打ち切る
+pub const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+// 使われていない接続をプールに残す時間（連続した発言生成で接続を使い回す）
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+// 起動時にバックエンドを強制する環境変数（デモ・CI 用。"mock" / "record" / "replay" / "candle" / "openai"）
+const ENV_BACKEND: &str = "DEWAI_LLM_BACKEND";
+
+/// ストリーミング生成で断片を受け取るコールバック
+pub type ChunkSink<'a> = &'a (dyn Fn(&str) + Send + Sync);
+
+/// LLM バックエンドの共通インターフェース
+#[async_trait]
+pub trait LlmBackend: Send + Sync {
+    /// ログ・設定表示用の名前
+    fn name(&self) -> &'static str;
+
+    /// バックエンドが応答可能か
+    async fn is_available(&self) -> bool;
+
+    /// 利用可能なモデル名一覧
+    async fn list_models(&self) -> Result<Vec<String>, String>;
+
+    /// 一括生成（本文とトークン数などのメタデータを返す）
+    async fn generate(&self, model: &str, prompt: &str, options: &GenerationOptions)
+        -> Result<GenerationResult, GenError>;
+
+    /// ストリーミング生成（断片ごとに on_chunk を呼び、最後に全文とメタデータを返す）
+    async fn generate_stream(
+        &self,
+        model: &str,
+        prompt: &str,
+        options: &GenerationOptions,
+        on_chunk: ChunkSink<'_>,
+    ) -> Result<GenerationResult, GenError>;
+
+    /// モデルを事前に読み込む（読み込みの概念がないバックエンドは何もしない）
+    async fn warm_up(&self, _model: &str, _keep_alive: Option<&str>) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// 死活監視用の状態（バージョン・読み込み済みモデル）。既定は疎通可否のみ
+    async fn health(&self) -> BackendHealth {
+        let available = self.is_available().await;
+        BackendHealth {
+            available,
+            error: (!available).then(|| format!("{} が応答しません", self.name())),
+            ..BackendHealth::default()
+        }
+    }
+}
+
+/// LlmBackend::health の結果
+#[derive(Debug, Clone, Default)]
+pub struct BackendHealth {
+    pub available: bool,
+    pub version: Option<String>,
+    /// メモリに読み込まれているモデル（分からないバックエンドは空）
+    pub loaded_models: Vec<LoadedModel>,
+    /// 応答がなかった時の理由
+    pub error: Option<String>,
+}
+
+/// バックエンドの種類（アプリ設定に保存）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendKind {
+    #[default]
+    Ollama,
+    Mock,
+    /// Ollama を使いつつ、やり取りをフィクスチャとして記録
+    Record,
+    /// 記録済みフィクスチャのみで応答
+    Replay,
+    /// アプリ内で GGUF を直接読み込んで推論（--features candle でビルドした場合のみ）
+    Candle,
+    /// OpenAI 互換 API のサーバー（llama.cpp server・LM Studio など）
+    #[serde(rename = "openai")]
+    OpenAi,
+}
+
+impl BackendKind {
+    pub fn from_env() -> Option<Self> {
+        match std::env::var(ENV_BACKEND).ok()?.as_str() {
+            "mock" => Some(BackendKind::Mock),
+            "record" => Some(BackendKind::Record),
+            "replay" => Some(BackendKind::Replay),
+            "candle" => Some(BackendKind::Candle),
+            "openai" => Some(BackendKind::OpenAi),
+            "ollama" => Some(BackendKind::Ollama),
+            _ => None,
+        }
+    }
+}
+
+/// Ollama サーバーへの接続設定（アプリ設定に保存）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct OllamaConnection {
+    /// ホスト名（"http://" などのスキーム付きも可）
+    pub host: String,
+    pub port: u16,
+    /// 一括生成・モデル一覧取得のタイムアウト（秒。再試行する場合は1回の試行ごと）。ストリーミングは接続確立までに適用
+    pub request_timeout_secs: u64,
+    /// 一括生成の最大試行回数
+    pub max_retries: u8,
+    /// 再試行の初回待ち時間（ミリ秒。以降は倍々）
+    pub retry_backoff_ms: u64,
+    /// この回数だけ生成の失敗が続いたら送信を一時停止する（0 でサーキットブレーカー無効）
+    pub breaker_threshold: u8,
+    /// 一時停止する時間（秒）
+    pub breaker_cooldown_secs: u64,
+    /// 生成1件の全体の期限（秒。再試行・ストリーミングを含み、生成キューの待ち時間は含まない）
+    pub generation_deadline_secs: u64,
+    /// ストリーミングでこの秒数だけ何も届かなければ打ち切る
+    pub stream_idle_timeout_secs: u64,
+    /// 期限の時点でストリーミングの断片が届き続けていれば、さらに待つ秒数（0 で猶予なし）
+    pub deadline_grace_secs: u64,
+    /// 生成後にモデルをメモリに残す時間（"10m", "1h", 秒数、"-1" で無期限）。空なら Ollama の既定（5分）
+    pub keep_alive: String,
+    /// 同時に送る生成リクエストの上限（Ollama 側の OLLAMA_NUM_PARALLEL に合わせる）
+    pub max_parallel: usize,
+    /// HTTP(S) プロキシ（"http://proxy.example:8080" など）。空なら環境変数 HTTP_PROXY / HTTPS_PROXY に従う
+    pub proxy: String,
+    /// 自己署名証明書などの検証に失敗する https の接続先も許可する
+    pub accept_invalid_certs: bool,
+    /// すべてのリクエストに付けるヘッダー（リバースプロキシ越しの Authorization など）
+    pub headers: HttpHeaders,
+}
+
+/// 追加ヘッダー（名前 -> 値）。値には認証情報が入るため、ログにはヘッダー名だけを出す
+#[derive(Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct HttpHeaders(pub BTreeMap<String, String>);
+
+impl std::fmt::Debug for HttpHeaders {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.0.keys()).finish()
+    }
+}
+
+impl Default for OllamaConnection {
+    fn default() -> Self {
+        Self {
+            host: "localhost".into(),
+            port: 11434,
+            request_timeout_secs: 120,
+            max_retries: 3,
+            retry_backoff_ms: 300,
+            breaker_threshold: 5,
+            breaker_cooldown_secs: 30,
+            generation_deadline_secs: 300,
+            stream_idle_timeout_secs: 60,
+            deadline_grace_secs: 120,
+            keep_alive: String::new(),
+            max_parallel: gen_queue::DEFAULT_MAX_CONCURRENT_GENERATIONS,
+            proxy: String::new(),
+            accept_invalid_certs: false,
+            headers: HttpHeaders::default(),
+        }
+    }
+}
+
+/// keep_alive の値を Ollama API の形式に変換（数値のみなら秒数、単位付きは文字列。不正なら None）
+pub fn keep_alive_value(keep_alive: &str) -> Option<serde_json::Value> {
+    let s = keep_alive.trim();
+    let digits = s.strip_prefix('-').unwrap_or(s);
+    let unit_at = digits.find(|c: char| !c.is_ascii_digit()).unwrap_or(digits.len());
+    let (number, unit) = digits.split_at(unit_at);
+    if number.is_empty() || !matches!(unit, "" | "ms" | "s" | "m" | "h") {
+        return None;
+    }
+    if unit.is_empty() {
+        s.parse::<i64>().ok().map(Into::into)
+    } else {
+        Some(serde_json::Value::String(s.to_string()))
+    }
+}
+
+impl OllamaConnection {
+    /// 範囲外の値を補正
+    pub fn sanitized(mut self) -> Self {
+        self.host = self.host.trim().trim_end_matches('/').to_string();
+        if self.host.is_empty() {
+            self.host = OllamaConnection::default().host;
+        }
+        if self.port == 0 {
+            self.port = OllamaConnection::default().port;
+        }
+        self.request_timeout_secs = self.request_timeout_secs.clamp(MIN_TIMEOUT_SECS, MAX_TIMEOUT_SECS);
+        self.max_retries = self.max_retries.clamp(1, MAX_RETRIES_LIMIT);
+        self.retry_backoff_ms = self.retry_backoff_ms.clamp(MIN_RETRY_BACKOFF_MS, MAX_RETRY_BACKOFF_MS);
+        self.breaker_threshold = self.breaker_threshold.min(MAX_BREAKER_THRESHOLD);
+        self.breaker_cooldown_secs =
+            self.breaker_cooldown_secs.clamp(MIN_BREAKER_COOLDOWN_SECS, MAX_BREAKER_COOLDOWN_SECS);
+        self.generation_deadline_secs = self.generation_deadline_secs.clamp(MIN_DEADLINE_SECS, MAX_DEADLINE_SECS);
+        self.stream_idle_timeout_secs = self.stream_idle_timeout_secs.clamp(MIN_TIMEOUT_SECS, MAX_TIMEOUT_SECS);
+        self.deadline_grace_secs = self.deadline_grace_secs.min(MAX_DEADLINE_GRACE_SECS);
+        self.max_parallel = self.max_parallel.clamp(1, MAX_PARALLEL_LIMIT);
+        self.keep_alive = self.keep_alive.trim().to_string();
+        if !self.keep_alive.is_empty() && keep_alive_value(&self.keep_alive).is_none() {
+            info!("keep_alive の値が不正なため既定に戻します: '{}'", self.keep_alive);
+            self.keep_alive.clear();
+        }
+        self.proxy = self.proxy.trim().to_string();
+        self.headers = HttpHeaders(
+            std::mem::take(&mut self.headers.0)
+                .into_iter()
+                .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+                .filter(|(name, _)| !name.is_empty())
+                .collect(),
+        );
+        self
+    }
+
+    /// プロキシ・追加ヘッダーの指定を検査（保存前）
+    pub fn validate(&self) -> Result<(), String> {
+        http_builder(self).map(|_| ())
+    }
+
+    /// "http://host:port" 形式のベースURL
+    pub fn base_url(&self) -> String {
+        if self.host.starts_with("http://") || self.host.starts_with("https://") {
+            format!("{}:{}", self.host, self.port)
+        } else {
+            format!("http://{}:{}", self.host, self.port)
+        }
+    }
+}
+
+/// candle で推論する時のデバイス
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CandleDevice {
+    /// CUDA → Metal → CPU の順に使えるものを選ぶ
+    #[default]
+    Auto,
+    Cpu,
+    Cuda,
+    Metal,
+}
+
+/// candle バックエンドの設定（アプリ設定に保存。--features candle なしのビルドでは使われない）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CandleConfig {
+    /// GGUF 形式のモデルファイル（Gemma 3）
+    pub model_path: String,
+    /// tokenizer.json（空なら GGUF と同じフォルダの tokenizer.json）
+    pub tokenizer_path: String,
+    /// 一覧・メタデータに出すモデル名（許可するモデルの判定にも使う）
+    pub model_name: String,
+    pub device: CandleDevice,
+    /// バックエンドが Ollama で、Ollama が応答しない時に candle で生成するか
+    pub fallback: bool,
+}
+
+impl Default for CandleConfig {
+    fn default() -> Self {
+        Self {
+            model_path: String::new(),
+            tokenizer_path: String::new(),
+            model_name: "gemma3:1b".into(),
+            device: CandleDevice::Auto,
+            fallback: true,
+        }
+    }
+}
+
+impl CandleConfig {
+    /// 範囲外の値を補正
+    pub fn sanitized(mut self) -> Self {
+        self.model_path = self.model_path.trim().to_string();
+        self.tokenizer_path = self.tokenizer_path.trim().to_string();
+        self.model_name = self.model_name.trim().to_string();
+        if self.model_name.is_empty() {
+            self.model_name = CandleConfig::default().model_name;
+        }
+        self
+    }
+
+    /// モデルファイルが設定されているか
+    #[cfg_attr(not(feature = "candle"), allow(dead_code))]
+    pub fn is_configured(&self) -> bool {
+        !self.model_path.is_empty()
+    }
+}
+
+/// candle の設定を反映（起動時と設定保存時）。読み込み済みのモデルは次の生成時に読み直す
+pub fn set_candle_config(settings: &RuntimeSettings, config: &CandleConfig) {
+    if *settings.candle.read() != *config {
+        info!("candle 設定: model='{}', device={:?}, fallback={}", config.model_path, config.device, config.fallback);
+        settings.candle.set(config.clone());
+    }
+}
+
+/// 再試行の方針（一括生成など、失敗時に指数バックオフで再送する呼び出しで共有）
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// 最大試行回数（接続設定の max_retries）
+    pub max_attempts: u8,
+    base_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// attempt 回目（1始まり）の失敗後に待つ時間
+    pub fn backoff(&self, attempt: u8) -> Duration {
+        self.base_backoff.saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1) as u32))
+    }
+}
+
+/// Ollama への HTTP クライアント（接続設定とコネクションプールをアプリ全体で共有する）
+/// reqwest::Client は内部で Arc を持つため、clone しても同じプールを使う
+#[derive(Clone)]
+pub struct OllamaClient {
+    http: Client,
+    connection: OllamaConnection,
+}
+
+/// 接続設定のプロキシ・証明書検証・追加ヘッダーを反映したクライアントの設定
+fn http_builder(connection: &OllamaConnection) -> Result<ClientBuilder, String> {
+    let mut headers = HeaderMap::new();
+    for (name, value) in &connection.headers.0 {
+        let name = HeaderName::from_bytes(name.as_bytes()).map_err(|e| format!("ヘッダー名が不正です '{}': {}", name, e))?;
+        let mut value = HeaderValue::from_str(value).map_err(|e| format!("ヘッダー '{}' の値が不正です: {}", name, e))?;
+        value.set_sensitive(true);
+        headers.insert(name, value);
+    }
+    let mut builder =
+        Client::builder().default_headers(headers).danger_accept_invalid_certs(connection.accept_invalid_certs);
+    if !connection.proxy.is_empty() {
+        let proxy = Proxy::all(&connection.proxy).map_err(|e| format!("プロキシの指定が不正です '{}': {}", connection.proxy, e))?;
+        builder = builder.proxy(proxy);
+    }
+    Ok(builder)
+}
+
+impl Default for OllamaClient {
+    fn default() -> Self {
+        Self::new(OllamaConnection::default())
+    }
+}
+
+impl OllamaClient {
+    fn new(connection: OllamaConnection) -> Self {
+        if connection.accept_invalid_certs {
+            warn!("Ollama への接続で証明書の検証を無効にしています");
+        }
+        // 全体のタイムアウトは呼び出しごとに付け、クライアントには接続確立までの時間だけを設定する
+        let http = http_builder(&connection)
+            .and_then(|builder| {
+                builder
+                    .connect_timeout(Duration::from_secs(connection.request_timeout_secs))
+                    .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+                    .tcp_keepalive(POOL_IDLE_TIMEOUT)
+                    .build()
+                    .map_err(|e| e.to_string())
+            })
+            .unwrap_or_else(|e| {
+                warn!("HTTPクライアント初期化失敗（既定の設定で作成）: {}", e);
+                Client::new()
+            });
+        Self { http, connection }
+    }
+
+    pub fn connection(&self) -> &OllamaConnection {
+        &self.connection
+    }
+
+    /// 接続設定のタイムアウト付きリクエスト（モデル一覧・一括生成など）
+    pub fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        self.streaming(method, path).timeout(Duration::from_secs(self.connection.request_timeout_secs))
+    }
+
+    /// 全体のタイムアウトなしのリクエスト（ストリーミング・モデル取得など所要時間が読めないもの）
+    pub fn streaming(&self, method: Method, path: &str) -> RequestBuilder {
+        self.http.request(method, format!("{}{}", self.connection.base_url(), path))
+    }
+
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: self.connection.max_retries,
+            base_backoff: Duration::from_millis(self.connection.retry_backoff_ms),
+        }
+    }
+}
+
+/// 接続設定を反映（起動時と設定保存時）。変更があった時だけクライアントを作り直す
+pub fn set_connection(settings: &RuntimeSettings, connection: &OllamaConnection) {
+    if settings.client.read().connection != *connection {
+        info!(
+            "Ollama 接続設定: {} (timeout={}s, retries={}, backoff={}ms, breaker={}/{}s, keep_alive='{}', proxy='{}', headers={:?})",
+            connection.base_url(),
+            connection.request_timeout_secs,
+            connection.max_retries,
+            connection.retry_backoff_ms,
+            connection.breaker_threshold,
+            connection.breaker_cooldown_secs,
+            connection.keep_alive,
+            connection.proxy,
+            connection.headers
+        );
+        settings.client.set(OllamaClient::new(connection.clone()));
+    }
+}
+
+/// 共有の Ollama クライアント
+pub fn client(app: &AppHandle) -> OllamaClient {
+    app.state::<AppState>().settings.client.get()
+}
+
+/// 現在の接続設定
+pub fn connection(app: &AppHandle) -> OllamaConnection {
+    client(app).connection
+}
+
+/// バックエンドを作る（ブレーカー・設定・読み込んだモデルは AppState と共有する）
+pub fn instantiate(
+    kind: BackendKind,
+    breaker: &Arc<CircuitBreaker>,
+    settings: &Arc<RuntimeSettings>,
+    #[cfg(feature = "candle")] cache: &Arc<crate::backend_candle::ModelCache>,
+) -> Arc<dyn LlmBackend> {
+    let ollama = OllamaBackend { breaker: breaker.clone(), settings: settings.clone() };
+    #[cfg(feature = "candle")]
+    let candle = crate::backend_candle::CandleBackend { cache: cache.clone(), settings: settings.clone() };
+    match kind {
+        // candle 付きのビルドでは、Ollama が応答しない時に candle へ切り替える
+        #[cfg(feature = "candle")]
+        BackendKind::Ollama => Arc::new(crate::backend_candle::OllamaWithCandleFallback { ollama, candle }),
+        #[cfg(not(feature = "candle"))]
+        BackendKind::Ollama => Arc::new(ollama),
+        BackendKind::Mock => Arc::new(MockBackend),
+        BackendKind::Record => Arc::new(RecordingBackend { ollama }),
+        BackendKind::Replay => Arc::new(ReplayBackend),
+        BackendKind::OpenAi => Arc::new(OpenAiCompatBackend::new(breaker.clone(), settings.clone())),
+        #[cfg(feature = "candle")]
+        BackendKind::Candle => Arc::new(candle),
+        #[cfg(not(feature = "candle"))]
+        BackendKind::Candle => {
+            warn!("candle なしでビルドされているため Ollama を使います（--features candle でビルドしてください）");
+            Arc::new(ollama)
+        }
+    }
+}
+
+/// 現在のバックエンド
+pub fn current(app: &AppHandle) -> Arc<dyn LlmBackend> {
+    app.state::<AppState>().backend.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// バックエンドを切り替える（環境変数で強制されている場合はそちらを優先）
+pub fn select(state: &AppState, kind: BackendKind) {
+    let kind = BackendKind::from_env().unwrap_or(kind);
+    let mut guard = state.backend.write().unwrap_or_else(|e| e.into_inner());
+    let backend = instantiate(
+        kind,
+        &state.breaker,
+        &state.settings,
+        #[cfg(feature = "candle")]
+        &state.candle,
+    );
+    if guard.name() != backend.name() {
+        info!("LLMバックエンド切替: {} -> {:?}", guard.name(), kind);
+        *guard = backend;
+    }
+}
+
+/// ローカルの Ollama サーバー
+#[derive(Clone)]
+pub struct OllamaBackend {
+    pub breaker: Arc<CircuitBreaker>,
+    pub settings: Arc<RuntimeSettings>,
+}
+
+impl OllamaBackend {
+    fn client(&self) -> OllamaClient {
+        self.settings.client.get()
+    }
+}
+
+/// /api/generate のリクエスト本体（JSON モードなら format、添付画像があれば images、keep_alive 設定があればそれも付ける）
+fn request_body(
+    connection: &OllamaConnection,
+    model: &str,
+    prompt: &str,
+    stream: bool,
+    options: &GenerationOptions,
+) -> serde_json::Value {
+    let mut body = ollama::request_body(model, prompt, stream, options);
+    if let Some(keep_alive) = keep_alive_value(&connection.keep_alive) {
+        body["keep_alive"] = keep_alive;
+    }
+    body
+}
+
+#[async_trait]
+impl LlmBackend for OllamaBackend {
+    fn name(&self) -> &'static str {
+        "ollama"
+    }
+
+    async fn is_available(&self) -> bool {
+        match self.client().streaming(Method::GET, "").timeout(HEALTH_CHECK_TIMEOUT).send().await {
+            Ok(_) => true,
+            Err(e) => {
+                warn!("Ollama からの応答なし: {}", e);
+                false
+            }
+        }
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>, String> {
+        let res = self
+            .client()
+            .request(Method::GET, "/api/tags")
+            .send()
+            .await
+            .map_err(|e| format!("モデル一覧取得失敗: {}", e))?;
+        let json: serde_json::Value = res.json().await.map_err(|e| format!("JSONパース失敗: {}", e))?;
+        Ok(ollama::model_names(&json))
+    }
+
+    //生成呼び出し。失敗時指数バックオフで再試行。失敗が続いている間はサーキットブレーカーで送信を止める
+    async fn generate(
+        &self,
+        model: &str,
+        prompt: &str,
+        options: &GenerationOptions,
+    ) -> Result<GenerationResult, GenError> {
+        let client = self.client();
+        let retry = client.retry_policy();
+        let body = request_body(&client.connection, model, prompt, false, options);
+
+        let mut attempt: u8 = 1;
+        loop {
+            self.breaker.check()?;
+            info!("Ollama API リクエスト送信 (model={}, attempt={}/{})", model, attempt, retry.max_attempts);
+            let resp = client.request(Method::POST, "/api/generate").json(&body).send().await;
+            match resp {
+                Ok(res) => {
+                    info!("ステータス: {}", res.status());
+                    let json: serde_json::Value = match res.json().await {
+                        Ok(json) => json,
+                        Err(e) => {
+                            let err = format!("JSONパース失敗: {}", e);
+                            self.breaker.record_failure(&err);
+                            return Err(err.into());
+                        }
+                    };
+                    if let Some(resp_text) = json["response"].as_str() {
+                        info!("応答取得成功: {}文字", resp_text.len());
+                        self.breaker.record_success();
+                        let mut result = GenerationResult::from_ollama(model, resp_text.to_string(), &json);
+                        result.meta.retries = Some((attempt - 1) as u32);
+                        return Ok(result);
+                    } else {
+                        let err = format!("応答フィールドなし: {:?}", json);
+                        warn!("{}", err);
+                        self.breaker.record_failure(&err);
+                        if attempt >= retry.max_attempts { return Err("応答なし".into()); }
+                    }
+                }
+                Err(e) => {
+                    warn!("リクエスト失敗: {}", e);
+                    self.breaker.record_failure(&e.to_string());
+                    if attempt >= retry.max_attempts { return Err(format!("リクエスト失敗: {}", e).into()); }
+                }
+            }
+            let backoff = retry.backoff(attempt);
+            info!("{}ms 後に再試行...", backoff.as_millis());
+            tokio::time::sleep(backoff).await;
+            attempt += 1;
+        }
+    }
+
+    async fn generate_stream(
+        &self,
+        model: &str,
+        prompt: &str,
+        options: &GenerationOptions,
+        on_chunk: ChunkSink<'_>,
+    ) -> Result<GenerationResult, GenError> {
+        let client = self.client();
+        let body = request_body(&client.connection, model, prompt, true, options);
+        self.breaker.check()?;
+        // 生成全体の時間は長くなり得るため、ストリーミングでは接続確立までの時間だけを制限する
+        let mut res = match client.streaming(Method::POST, "/api/generate").json(&body).send().await {
+            Ok(res) => res,
+            Err(e) => {
+                self.breaker.record_failure(&e.to_string());
+                return Err(format!("リクエスト失敗: {}", e).into());
+            }
+        };
+        self.breaker.record_success();
+
+        let mut decoder = ollama::StreamDecoder::default();
+        while let Some(bytes) = timeouts::next_chunk(&self.breaker, &client.connection, res.chunk()).await? {
+            decoder.push(&bytes, on_chunk)?;
+        }
+        let mut result = decoder.finish(model);
+        result.meta.retries = Some(0);
+        Ok(result)
+    }
+
+    // プロンプトなしの生成リクエストでモデルを読み込ませる
+    async fn warm_up(&self, model: &str, keep_alive: Option<&str>) -> Result<(), String> {
+        let client = self.client();
+        let mut body = json!({ "model": model, "stream": false });
+        let keep_alive = keep_alive.map(str::to_string).unwrap_or(client.connection().keep_alive.clone());
+        if let Some(value) = keep_alive_value(&keep_alive) {
+            body["keep_alive"] = value;
+        } else if !keep_alive.trim().is_empty() {
+            return Err(format!("keep_alive の値が不正です: '{}'", keep_alive));
+        }
+        let res = client
+            .request(Method::POST, "/api/generate")
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| format!("モデル読み込みリクエスト失敗: {}", e))?;
+        if !res.status().is_success() {
+            return Err(format!("モデル読み込み失敗: HTTP {}", res.status()));
+        }
+        Ok(())
+    }
+
+    // /api/version でバージョンを、/api/ps でメモリ上のモデルを取得する
+    async fn health(&self) -> BackendHealth {
+        let client = self.client();
+        let version = match fetch_health_json(&client, "/api/version").await {
+            Ok(json) => json["version"].as_str().map(|s| s.to_string()),
+            Err(e) => return BackendHealth { error: Some(e), ..BackendHealth::default() },
+        };
+        // /api/ps がない古い Ollama では読み込み済みモデルは不明として扱う
+        let loaded_models = match fetch_health_json(&client, "/api/ps").await {
+            Ok(json) => parse_loaded_models(&json),
+            Err(e) => {
+                warn!("読み込み済みモデルの取得失敗: {}", e);
+                Vec::new()
+            }
+        };
+        BackendHealth { available: true, version, loaded_models, error: None }
+    }
+}
+
+/// 指定した接続設定の Ollama に問い合わせ、バージョンとインストール済みモデルを返す
+/// （接続プロファイルの疎通確認用。共有クライアントは作り直さない）
+pub async fn probe_ollama(connection: &OllamaConnection) -> Result<(Option<String>, Vec<String>), String> {
+    let client = OllamaClient::new(connection.clone());
+    let version = fetch_health_json(&client, "/api/version").await?["version"].as_str().map(|s| s.to_string());
+    let tags = fetch_health_json(&client, "/api/tags").await?;
+    let models = tags["models"]
+        .as_array()
+        .map(|models| models.iter().filter_map(|m| m["name"].as_str()).map(|s| s.to_string()).collect())
+        .unwrap_or_default();
+    Ok((version, models))
+}
+
+async fn fetch_health_json(client: &OllamaClient, path: &str) -> Result<serde_json::Value, String> {
+    let res = client
+        .streaming(Method::GET, path)
+        .timeout(HEALTH_CHECK_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| format!("Ollama からの応答なし: {}", e))?;
+    if !res.status().is_success() {
+        return Err(format!("Ollama エラー ({}): {}", path, res.status()));
+    }
+    res.json().await.map_err(|e| format!("JSONパース失敗: {}", e))
+}
+
+/// /api/ps の応答からメモリ上のモデルを取り出す
+fn parse_loaded_models(json: &serde_json::Value) -> Vec<LoadedModel> {
+    json["models"]
+        .as_array()
+        .map(|models| {
+            models
+                .iter()
+                .filter_map(|m| {
+                    Some(LoadedModel {
+                        name: m["name"].as_str()?.to_string(),
+                        size: m["size"].as_u64().unwrap_or(0),
+                        size_vram: m["size_vram"].as_u64().unwrap_or(0),
+                        expires_at: m["expires_at"].as_str().map(|s| s.to_string()),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
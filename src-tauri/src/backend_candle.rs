@@ -0,0 +1,405 @@
+// candle による組み込み推論バックエンド（--features candle でビルドした場合のみ）
+// Ollama を使わず、GGUF 形式の Gemma 3 をアプリ内で直接読み込んで生成する
+// バックエンドが Ollama のままでも、Ollama が応答しない時はこちらで生成できる（candle.fallback）
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use async_trait::async_trait;
+use candle_core::{quantized::gguf_file, DType, Device, Tensor};
+use candle_transformers::generation::{LogitsProcessor, Sampling};
+use candle_transformers::models::quantized_gemma3::ModelWeights;
+use tokenizers::Tokenizer;
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use dewai_core::generation::generate_seed;
+
+use crate::{
+    app_state::RuntimeSettings,
+    backend::{BackendHealth, CandleConfig, CandleDevice, ChunkSink, LlmBackend, OllamaBackend},
+    gen_error::GenError,
+    generation::{GenerationMeta, GenerationOptions, GenerationResult},
+    health, tokens,
+};
+
+// num_predict 未指定時の最大出力トークン数
+const DEFAULT_MAX_TOKENS: usize = 512;
+// temperature 未指定時の値（Ollama の既定に合わせる）
+const DEFAULT_TEMPERATURE: f64 = 0.8;
+// 繰り返し抑制で見る直近のトークン数（Ollama の repeat_last_n の既定）
+const REPEAT_LAST_N: usize = 64;
+// 出力の終わりを示すトークン
+const EOS_TOKENS: [&str; 2] = ["<end_of_turn>", "<eos>"];
+
+/// 読み込み済みのモデル（設定が変わるまで使い回す）
+struct LoadedModel {
+    key: (PathBuf, PathBuf, CandleDevice),
+    /// KV キャッシュが空の重み。生成ごとに clone して使う（テンソルは共有されるので複製は軽い）
+    weights: ModelWeights,
+    tokenizer: Tokenizer,
+    device: Device,
+    eos: Vec<u32>,
+}
+
+/// 読み込み済みのモデルの置き場（AppState が持ち、candle のバックエンドには作成時に渡す）
+#[derive(Default)]
+pub struct ModelCache {
+    model: Mutex<Option<Arc<LoadedModel>>>,
+}
+
+fn candle_error(e: impl std::fmt::Display) -> String {
+    format!("candle 推論失敗: {}", e)
+}
+
+fn tokenizer_path(config: &CandleConfig) -> PathBuf {
+    if !config.tokenizer_path.is_empty() {
+        return PathBuf::from(&config.tokenizer_path);
+    }
+    Path::new(&config.model_path).with_file_name("tokenizer.json")
+}
+
+/// モデルファイルと tokenizer が揃っているか
+fn files_present(config: &CandleConfig) -> bool {
+    config.is_configured() && Path::new(&config.model_path).is_file() && tokenizer_path(config).is_file()
+}
+
+/// 設定に合うデバイス（Auto は CUDA → Metal → CPU の順に試す）
+fn select_device(preference: CandleDevice) -> Result<Device, String> {
+    match preference {
+        CandleDevice::Cpu => Ok(Device::Cpu),
+        CandleDevice::Cuda => Device::new_cuda(0).map_err(|e| format!("CUDA を使えません: {}", e)),
+        CandleDevice::Metal => Device::new_metal(0).map_err(|e| format!("Metal を使えません: {}", e)),
+        CandleDevice::Auto => {
+            if candle_core::utils::cuda_is_available() {
+                if let Ok(device) = Device::new_cuda(0) {
+                    return Ok(device);
+                }
+            }
+            if candle_core::utils::metal_is_available() {
+                if let Ok(device) = Device::new_metal(0) {
+                    return Ok(device);
+                }
+            }
+            Ok(Device::Cpu)
+        }
+    }
+}
+
+/// 設定のモデルを読み込む（読み込み済みで設定が同じならそれを返す）。重い処理なので blocking スレッドで呼ぶ
+fn load(cache: &ModelCache, config: &CandleConfig) -> Result<Arc<LoadedModel>, String> {
+    if !config.is_configured() {
+        return Err("candle のモデルファイルが設定されていません".into());
+    }
+    let key = (PathBuf::from(&config.model_path), tokenizer_path(config), config.device);
+    // 読み込み中に同じモデルを二重に読み込まないよう、読み込みの間もロックを保持する
+    let mut cached = cache.model.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(model) = cached.as_ref().filter(|m| m.key == key) {
+        return Ok(model.clone());
+    }
+    let started = Instant::now();
+    let device = select_device(config.device)?;
+    let mut file = std::fs::File::open(&key.0)
+        .map_err(|e| format!("モデルファイルを開けません ({}): {}", key.0.display(), e))?;
+    let content = gguf_file::Content::read(&mut file).map_err(|e| format!("GGUF の読み込み失敗: {}", e))?;
+    let weights =
+        ModelWeights::from_gguf(content, &mut file, &device).map_err(|e| format!("モデルの読み込み失敗: {}", e))?;
+    let tokenizer = Tokenizer::from_file(&key.1)
+        .map_err(|e| format!("tokenizer の読み込み失敗 ({}): {}", key.1.display(), e))?;
+    let eos = EOS_TOKENS.iter().filter_map(|t| tokenizer.token_to_id(t)).collect();
+    info!("candle モデル読み込み完了: {} ({:?}, {}ms)", key.0.display(), device, started.elapsed().as_millis());
+    let model = Arc::new(LoadedModel { key, weights, tokenizer, device, eos });
+    *cached = Some(model.clone());
+    Ok(model)
+}
+
+/// 生成オプションからサンプリング方法を決める（temperature 0 は最尤のトークンを選ぶ）
+fn sampling(options: &GenerationOptions) -> Sampling {
+    let temperature = options.temperature.map(f64::from).unwrap_or(DEFAULT_TEMPERATURE);
+    if temperature <= 0.0 {
+        return Sampling::ArgMax;
+    }
+    let top_p = options.top_p.map(f64::from).filter(|p| *p > 0.0 && *p < 1.0);
+    let top_k = options.top_k.map(|k| k as usize).filter(|k| *k > 0);
+    match (top_k, top_p) {
+        (Some(k), Some(p)) => Sampling::TopKThenTopP { k, p, temperature },
+        (Some(k), None) => Sampling::TopK { k, temperature },
+        (None, Some(p)) => Sampling::TopP { p, temperature },
+        (None, None) => Sampling::All { temperature },
+    }
+}
+
+/// Gemma の対話形式に包む（Ollama はモデルのテンプレートで同じことをしている）
+fn chat_prompt(prompt: &str) -> String {
+    format!("<start_of_turn>user\n{}<end_of_turn>\n<start_of_turn>model\n", prompt)
+}
+
+/// 生成結果（本文と入出力のトークン数）
+struct Completion {
+    text: String,
+    prompt_tokens: usize,
+    completion_tokens: usize,
+}
+
+/// トークンを1つずつ生成する。on_text には新しく確定した文字列を渡す
+/// cancelled が立ったら（呼び出し側が破棄されたら）その時点までで打ち切る
+fn run_generation(
+    model: &LoadedModel,
+    prompt: &str,
+    options: &GenerationOptions,
+    cancelled: &AtomicBool,
+    mut on_text: impl FnMut(&str),
+) -> Result<Completion, String> {
+    let encoding = model.tokenizer.encode(chat_prompt(prompt), true).map_err(candle_error)?;
+    let mut tokens = encoding.get_ids().to_vec();
+    let max_tokens = options.num_predict.filter(|n| *n > 0).map(|n| n as usize).unwrap_or(DEFAULT_MAX_TOKENS);
+    // コンテキスト長に収まらない分は古い側から削る
+    let context = options.num_ctx.map(|n| n as usize).unwrap_or(tokens::DEFAULT_NUM_CTX);
+    let prompt_budget = context.saturating_sub(max_tokens).max(1);
+    if tokens.len() > prompt_budget {
+        warn!("プロンプトがコンテキスト長を超えるため先頭を切り詰めます: {} -> {}", tokens.len(), prompt_budget);
+        tokens.drain(..tokens.len() - prompt_budget);
+    }
+    let prompt_tokens = tokens.len();
+
+    let seed = options.seed.unwrap_or_else(generate_seed) as u64;
+    let mut sampler = LogitsProcessor::from_sampling(seed, sampling(options));
+    let repeat_penalty = options.repeat_penalty.filter(|r| *r != 1.0 && *r > 0.0);
+    let mut weights = model.weights.clone();
+    let mut next_token = |weights: &mut ModelWeights, input: &[u32], position: usize, history: &[u32]| {
+        let input = Tensor::new(input, &model.device).and_then(|t| t.unsqueeze(0)).map_err(candle_error)?;
+        let logits = weights
+            .forward(&input, position)
+            .and_then(|l| l.squeeze(0))
+            .and_then(|l| l.to_dtype(DType::F32))
+            .map_err(candle_error)?;
+        let logits = match repeat_penalty {
+            Some(penalty) => {
+                let start = history.len().saturating_sub(REPEAT_LAST_N);
+                candle_transformers::utils::apply_repeat_penalty(&logits, penalty, &history[start..])
+                    .map_err(candle_error)?
+            }
+            None => logits,
+        };
+        sampler.sample(&logits).map_err(candle_error)
+    };
+
+    let mut generated: Vec<u32> = Vec::new();
+    let mut emitted = 0;
+    let mut token = next_token(&mut weights, &tokens, 0, &tokens)?;
+    while !model.eos.contains(&token) && !cancelled.load(Ordering::Relaxed) {
+        generated.push(token);
+        tokens.push(token);
+        // 複数トークンで1文字になる場合があるため、全体を復号して確定した分だけ渡す
+        let text = model.tokenizer.decode(&generated, true).map_err(candle_error)?;
+        if text.len() > emitted && !text.ends_with('\u{FFFD}') {
+            on_text(&text[emitted..]);
+            emitted = text.len();
+        }
+        if generated.len() >= max_tokens {
+            break;
+        }
+        token = next_token(&mut weights, &[token], tokens.len() - 1, &tokens)?;
+    }
+    let text = model.tokenizer.decode(&generated, true).map_err(candle_error)?;
+    if text.len() > emitted {
+        on_text(&text[emitted..]);
+    }
+    Ok(Completion { text, prompt_tokens, completion_tokens: generated.len() })
+}
+
+/// 呼び出し側が破棄されたら生成を止める（blocking スレッドの生成は中断できないため旗で伝える）
+struct CancelOnDrop(Arc<AtomicBool>);
+
+impl Drop for CancelOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+/// blocking スレッドで生成する（断片は chunks へ送る）
+/// 読み込めるモデルは設定の1つだけなので、指定のモデル名ではなく設定の名前を記録する
+async fn generate_blocking(
+    cache: &Arc<ModelCache>,
+    config: CandleConfig,
+    prompt: &str,
+    options: &GenerationOptions,
+    chunks: Option<mpsc::UnboundedSender<String>>,
+) -> Result<GenerationResult, GenError> {
+    let model_name = config.model_name.clone();
+    let prompt = prompt.to_string();
+    let options = options.clone();
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let _guard = CancelOnDrop(cancelled.clone());
+    let started = Instant::now();
+    let cache = cache.clone();
+    let completion = tokio::task::spawn_blocking(move || {
+        let model = load(&cache, &config)?;
+        run_generation(&model, &prompt, &options, &cancelled, |text| {
+            if let Some(tx) = &chunks {
+                let _ = tx.send(text.to_string());
+            }
+        })
+    })
+    .await
+    .map_err(|e| format!("candle 推論タスク失敗: {}", e))??;
+    Ok(GenerationResult {
+        text: completion.text,
+        seed: None,
+        meta: GenerationMeta {
+            model: model_name,
+            prompt_tokens: Some(completion.prompt_tokens as u32),
+            completion_tokens: Some(completion.completion_tokens as u32),
+            duration_ms: Some(started.elapsed().as_millis() as u64),
+            ..Default::default()
+        },
+    })
+}
+
+/// candle で推論するバックエンド
+/// JSON モード（format）には対応しないため、構造化出力は呼び出し側の寛容なパースに任せる
+pub struct CandleBackend {
+    pub cache: Arc<ModelCache>,
+    pub settings: Arc<RuntimeSettings>,
+}
+
+#[async_trait]
+impl LlmBackend for CandleBackend {
+    fn name(&self) -> &'static str {
+        "candle"
+    }
+
+    async fn is_available(&self) -> bool {
+        files_present(&self.settings.candle.read())
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>, String> {
+        let config = self.settings.candle.get();
+        if !files_present(&config) {
+            return Err("candle のモデルファイルまたは tokenizer.json が見つかりません".into());
+        }
+        Ok(vec![config.model_name])
+    }
+
+    async fn generate(
+        &self,
+        _model: &str,
+        prompt: &str,
+        options: &GenerationOptions,
+    ) -> Result<GenerationResult, GenError> {
+        generate_blocking(&self.cache, self.settings.candle.get(), prompt, options, None).await
+    }
+
+    async fn generate_stream(
+        &self,
+        _model: &str,
+        prompt: &str,
+        options: &GenerationOptions,
+        on_chunk: ChunkSink<'_>,
+    ) -> Result<GenerationResult, GenError> {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let generation = generate_blocking(&self.cache, self.settings.candle.get(), prompt, options, Some(tx));
+        tokio::pin!(generation);
+        loop {
+            tokio::select! {
+                Some(chunk) = rx.recv() => on_chunk(&chunk),
+                result = &mut generation => {
+                    // 終了までに届いた残りの断片を渡す
+                    while let Ok(chunk) = rx.try_recv() {
+                        on_chunk(&chunk);
+                    }
+                    return result;
+                }
+            }
+        }
+    }
+
+    async fn warm_up(&self, _model: &str, _keep_alive: Option<&str>) -> Result<(), String> {
+        let config = self.settings.candle.get();
+        let cache = self.cache.clone();
+        tokio::task::spawn_blocking(move || load(&cache, &config).map(|_| ()))
+            .await
+            .map_err(|e| format!("candle 読み込みタスク失敗: {}", e))?
+    }
+}
+
+/// Ollama を使い、Ollama が応答しない時だけ candle で生成するバックエンド（candle 付きビルドの既定）
+/// 名前・疎通確認は Ollama のものを返すため、死活監視や自動起動は Ollama を対象にしたまま動く
+pub struct OllamaWithCandleFallback {
+    pub ollama: OllamaBackend,
+    pub candle: CandleBackend,
+}
+
+impl OllamaWithCandleFallback {
+    /// 今回の呼び出しを candle に回すか
+    async fn use_candle(&self) -> bool {
+        let config = self.candle.settings.candle.get();
+        if !config.fallback || !files_present(&config) {
+            return false;
+        }
+        let down = match health::current().state {
+            health::OllamaState::Down => true,
+            health::OllamaState::Unknown => !self.ollama.is_available().await,
+            _ => false,
+        };
+        if down {
+            info!("Ollama が応答しないため candle で生成します");
+        }
+        down
+    }
+}
+
+#[async_trait]
+impl LlmBackend for OllamaWithCandleFallback {
+    fn name(&self) -> &'static str {
+        self.ollama.name()
+    }
+
+    async fn is_available(&self) -> bool {
+        self.ollama.is_available().await
+    }
+
+    async fn health(&self) -> BackendHealth {
+        self.ollama.health().await
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>, String> {
+        if self.use_candle().await {
+            return self.candle.list_models().await;
+        }
+        self.ollama.list_models().await
+    }
+
+    async fn generate(
+        &self,
+        model: &str,
+        prompt: &str,
+        options: &GenerationOptions,
+    ) -> Result<GenerationResult, GenError> {
+        if self.use_candle().await {
+            return self.candle.generate(model, prompt, options).await;
+        }
+        self.ollama.generate(model, prompt, options).await
+    }
+
+    async fn generate_stream(
+        &self,
+        model: &str,
+        prompt: &str,
+        options: &GenerationOptions,
+        on_chunk: ChunkSink<'_>,
+    ) -> Result<GenerationResult, GenError> {
+        if self.use_candle().await {
+            return self.candle.generate_stream(model, prompt, options, on_chunk).await;
+        }
+        self.ollama.generate_stream(model, prompt, options, on_chunk).await
+    }
+
+    async fn warm_up(&self, model: &str, keep_alive: Option<&str>) -> Result<(), String> {
+        if self.use_candle().await {
+            return self.candle.warm_up(model, keep_alive).await;
+        }
+        self.ollama.warm_up(model, keep_alive).await
+    }
+}
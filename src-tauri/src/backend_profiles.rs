@@ -0,0 +1,189 @@
+// 接続プロファイル
+// 「手元のノートPC」「自宅のGPUサーバー」のように名前を付けたバックエンドの接続先をアプリ設定に保存し、
+// set_active_profile で接続設定（llmBackend・ollama）を丸ごと切り替える。プロファイルごとに使うモデルも持てる
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{command, AppHandle, Manager};
+use tokio::task::JoinSet;
+use tracing::info;
+
+use crate::{
+    app_state::AppState,
+    audit,
+    backend::{self, BackendKind, OllamaConnection},
+    config, health,
+};
+
+// 保存できるプロファイル数の上限
+pub const MAX_PROFILES: usize = 20;
+const MAX_NAME_CHARS: usize = 50;
+
+/// 名前付きの接続先（アプリ設定に保存）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BackendProfile {
+    pub name: String,
+    pub llm_backend: BackendKind,
+    pub ollama: OllamaConnection,
+    /// 切り替えた時に選ぶモデル（空なら選択中のモデルのまま）
+    pub model: String,
+}
+
+impl Default for BackendProfile {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            llm_backend: BackendKind::Ollama,
+            ollama: OllamaConnection::default(),
+            model: String::new(),
+        }
+    }
+}
+
+impl BackendProfile {
+    /// 範囲外の値を補正
+    pub fn sanitized(mut self) -> Self {
+        self.name = self.name.trim().chars().take(MAX_NAME_CHARS).collect();
+        self.ollama = self.ollama.sanitized();
+        self.model = self.model.trim().to_string();
+        self
+    }
+}
+
+/// プロファイル一覧を補正（名前のないもの・同名の2件目以降を除き、上限で切る）
+pub fn sanitize_profiles(profiles: Vec<BackendProfile>) -> Vec<BackendProfile> {
+    let mut result: Vec<BackendProfile> = Vec::new();
+    for profile in profiles.into_iter().map(BackendProfile::sanitized) {
+        if profile.name.is_empty() || result.iter().any(|p| p.name == profile.name) {
+            continue;
+        }
+        result.push(profile);
+    }
+    result.truncate(MAX_PROFILES);
+    result
+}
+
+/// list_backend_profiles の要素
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackendProfileStatus {
+    #[serde(flatten)]
+    pub profile: BackendProfile,
+    pub active: bool,
+    /// 疎通確認の結果（確認しなかった・Ollama 以外のバックエンドなら None）
+    pub reachable: Option<bool>,
+    pub version: Option<String>,
+    /// 接続先にインストールされているモデル
+    pub models: Vec<String>,
+    pub error: Option<String>,
+}
+
+async fn check(profile: &BackendProfile) -> (Option<bool>, Option<String>, Vec<String>, Option<String>) {
+    if !matches!(profile.llm_backend, BackendKind::Ollama | BackendKind::Record) {
+        return (None, None, Vec::new(), None);
+    }
+    match backend::probe_ollama(&profile.ollama).await {
+        Ok((version, models)) => (Some(true), version, models, None),
+        Err(e) => (Some(false), None, Vec::new(), Some(e)),
+    }
+}
+
+// 接続プロファイルの一覧（check が true なら各接続先に問い合わせて、応答・バージョン・モデルも返す）
+#[command]
+pub async fn list_backend_profiles(app: AppHandle, check: Option<bool>) -> Result<Vec<BackendProfileStatus>, String> {
+    info!("list_backend_profiles 呼び出し: check={:?}", check);
+    let settings = config::load(&app).await?;
+    let mut statuses: Vec<BackendProfileStatus> = settings
+        .backend_profiles
+        .into_iter()
+        .map(|profile| BackendProfileStatus {
+            active: profile.name == settings.active_profile,
+            profile,
+            reachable: None,
+            version: None,
+            models: Vec::new(),
+            error: None,
+        })
+        .collect();
+    if check.unwrap_or(false) {
+        // 応答しない接続先の待ち時間が積み重ならないよう、まとめて問い合わせる
+        let mut checks = JoinSet::new();
+        for (index, status) in statuses.iter().enumerate() {
+            let profile = status.profile.clone();
+            checks.spawn(async move { (index, self::check(&profile).await) });
+        }
+        while let Some(joined) = checks.join_next().await {
+            let Ok((index, (reachable, version, models, error))) = joined else { continue };
+            let status = &mut statuses[index];
+            status.reachable = reachable;
+            status.version = version;
+            status.models = models;
+            status.error = error;
+        }
+    }
+    Ok(statuses)
+}
+
+// 接続プロファイルを追加・更新（同名があれば置き換え。使用中のプロファイルなら接続設定にもすぐ反映する）
+#[command]
+pub async fn save_backend_profile(app: AppHandle, profile: BackendProfile) -> Result<BackendProfile, String> {
+    info!("save_backend_profile 呼び出し: name='{}'", profile.name);
+    let profile = profile.sanitized();
+    if profile.name.is_empty() {
+        return Err("プロファイル名を入力してください".into());
+    }
+    profile.ollama.validate()?;
+    let mut settings = config::load(&app).await?;
+    match settings.backend_profiles.iter().position(|p| p.name == profile.name) {
+        Some(index) => settings.backend_profiles[index] = profile.clone(),
+        None if settings.backend_profiles.len() >= MAX_PROFILES => {
+            return Err(format!("プロファイルは{}件までです", MAX_PROFILES));
+        }
+        None => settings.backend_profiles.push(profile.clone()),
+    }
+    if settings.active_profile == profile.name {
+        settings.llm_backend = profile.llm_backend;
+        settings.ollama = profile.ollama.clone();
+    }
+    config::save(&app, settings).await?;
+    Ok(profile)
+}
+
+// 接続プロファイルを削除（使用中のプロファイルを消しても接続設定はそのまま残す）
+#[command]
+pub async fn delete_backend_profile(app: AppHandle, name: String) -> Result<bool, String> {
+    info!("delete_backend_profile 呼び出し: name='{}'", name);
+    let mut settings = config::load(&app).await?;
+    let before = settings.backend_profiles.len();
+    settings.backend_profiles.retain(|p| p.name != name);
+    if settings.backend_profiles.len() == before {
+        return Ok(false);
+    }
+    if settings.active_profile == name {
+        settings.active_profile.clear();
+    }
+    config::save(&app, settings).await?;
+    Ok(true)
+}
+
+// 接続プロファイルに切り替える（接続設定を置き換えて保存し、すぐ反映する）
+#[command]
+pub async fn set_active_profile(app: AppHandle, name: String) -> Result<BackendProfile, String> {
+    info!("set_active_profile 呼び出し: name='{}'", name);
+    let mut settings = config::load(&app).await?;
+    let profile = settings
+        .backend_profiles
+        .iter()
+        .find(|p| p.name == name)
+        .cloned()
+        .ok_or_else(|| format!("プロファイルが見つかりません: {}", name))?;
+    let previous = std::mem::replace(&mut settings.active_profile, profile.name.clone());
+    settings.llm_backend = profile.llm_backend;
+    settings.ollama = profile.ollama.clone();
+    config::save(&app, settings).await?;
+    // 前の接続先での失敗を持ち越さず、新しい接続先の状態をすぐ通知する
+    app.state::<AppState>().breaker.record_success();
+    health::refresh(&app).await;
+    audit::record("backend", "switch_profile", json!({ "from": previous, "to": profile.name }));
+    Ok(profile)
+}
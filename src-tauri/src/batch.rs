@@ -0,0 +1,170 @@
+// 複数テーマの一括議論実行
+// テーマごとにセッションを作成 → 指定ラウンド進行 → 要約、を順次（または少数並列で）行う
+use std::sync::Arc;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Emitter};
+use tokio::sync::Semaphore;
+use tracing::{info, warn};
+
+pub use dewai_core::engine::profiles_format;
+use dewai_core::engine::DEFAULT_GENERATED_PARTICIPANTS;
+
+use crate::{
+    db,
+    db::{AiParticipant, ParticipantsData},
+    discussion_engine::{self, RoundConfig},
+    generation::GenerationOptions,
+    is_allowed_model, profile_generation, ERR_UNSUPPORTED_MODEL,
+};
+
+pub const EVENT_BATCH_PROGRESS: &str = "batch://progress";
+
+// 並列実行数の上限（ローカルLLMの負荷を考慮）
+const MAX_PARALLEL: usize = 4;
+
+/// 1テーマ分の実行条件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScenarioSpec {
+    pub topic: String,
+    /// 空の場合はテーマから参加者を自動生成
+    #[serde(default)]
+    pub participants: Vec<AiParticipant>,
+    pub rounds: u32,
+    pub model: String,
+}
+
+/// 1テーマ分の結果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchItemResult {
+    pub topic: String,
+    pub session_id: Option<i64>,
+    pub generated_messages: usize,
+    pub summary: Option<String>,
+    pub error: Option<String>,
+    pub duration_ms: u128,
+}
+
+/// 全体の結果レポート
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchReport {
+    pub results: Vec<BatchItemResult>,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub total_duration_ms: u128,
+}
+
+/// batch://progress のペイロード
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BatchProgressEvent {
+    index: usize,
+    total: usize,
+    topic: String,
+    /// "started" | "completed" | "failed"
+    status: &'static str,
+    session_id: Option<i64>,
+}
+
+/// テーマから参加者プロフィールを生成
+pub async fn generate_participants(app: &AppHandle, topic: &str, model: &str) -> Result<Vec<AiParticipant>, String> {
+    let options = GenerationOptions::default();
+    profile_generation::generate(app, topic, DEFAULT_GENERATED_PARTICIPANTS, "", Default::default(), model, &options).await
+}
+
+/// 1テーマを実行（セッション作成 → ラウンド進行 → 要約）
+async fn run_scenario(app: &AppHandle, spec: &ScenarioSpec, session_id: &mut Option<i64>) -> Result<(usize, Option<String>), String> {
+    if !is_allowed_model(app, &spec.model) {
+        return Err(ERR_UNSUPPORTED_MODEL.to_string());
+    }
+    let participants = if spec.participants.is_empty() {
+        generate_participants(app, &spec.topic, &spec.model).await?
+    } else {
+        spec.participants.clone()
+    };
+    let data = ParticipantsData { user_participates: false, ai_data: participants };
+    let id = db::create_session(app, &spec.topic, &data, &spec.model).await?;
+    *session_id = Some(id);
+
+    let config = RoundConfig { summarize_at_end: true, ..Default::default() };
+    let stats = discussion_engine::run_rounds_with(app, id, spec.rounds, &config).await?;
+    let summary = db::latest_summary(app, id).await?.map(|r| r.summary);
+    Ok((stats.generated, summary))
+}
+
+// 複数テーマの議論を一括実行し、結果レポートを返す
+#[command]
+pub async fn run_batch(
+    app: AppHandle,
+    topics: Vec<ScenarioSpec>,
+    max_parallel: Option<usize>,
+) -> Result<BatchReport, String> {
+    let parallel = max_parallel.unwrap_or(1).clamp(1, MAX_PARALLEL);
+    info!("run_batch 呼び出し: {}件, 並列数={}", topics.len(), parallel);
+    if topics.is_empty() {
+        return Err("テーマが指定されていません".into());
+    }
+    if let Some(bad) = topics.iter().find(|t| t.topic.trim().is_empty() || t.rounds == 0) {
+        return Err(format!("テーマまたはラウンド数が不正です: '{}'", bad.topic));
+    }
+
+    let started = Instant::now();
+    let total = topics.len();
+    let semaphore = Arc::new(Semaphore::new(parallel));
+    let mut handles = Vec::with_capacity(total);
+
+    for (index, spec) in topics.into_iter().enumerate() {
+        let app = app.clone();
+        let semaphore = semaphore.clone();
+        handles.push(tauri::async_runtime::spawn(async move {
+            let _permit = semaphore.acquire_owned().await.map_err(|e| e.to_string())?;
+            let item_started = Instant::now();
+            let _ = app.emit(
+                EVENT_BATCH_PROGRESS,
+                BatchProgressEvent { index, total, topic: spec.topic.clone(), status: "started", session_id: None },
+            );
+
+            let mut session_id = None;
+            let outcome = run_scenario(&app, &spec, &mut session_id).await;
+            let status = if outcome.is_ok() { "completed" } else { "failed" };
+            let _ = app.emit(
+                EVENT_BATCH_PROGRESS,
+                BatchProgressEvent { index, total, topic: spec.topic.clone(), status, session_id },
+            );
+
+            let (generated_messages, summary, error) = match outcome {
+                Ok((n, summary)) => (n, summary, None),
+                Err(e) => {
+                    warn!("バッチ項目失敗 ({}/{}): {}", index + 1, total, e);
+                    (0, None, Some(e))
+                }
+            };
+            Ok::<_, String>(BatchItemResult {
+                topic: spec.topic,
+                session_id,
+                generated_messages,
+                summary,
+                error,
+                duration_ms: item_started.elapsed().as_millis(),
+            })
+        }));
+    }
+
+    let mut results = Vec::with_capacity(total);
+    for handle in handles {
+        results.push(handle.await.map_err(|e| format!("バッチ実行タスク失敗: {}", e))??);
+    }
+    let failed = results.iter().filter(|r| r.error.is_some()).count();
+    info!("run_batch 完了: 成功={}, 失敗={}", total - failed, failed);
+
+    Ok(BatchReport {
+        succeeded: total - failed,
+        failed,
+        results,
+        total_duration_ms: started.elapsed().as_millis(),
+    })
+}
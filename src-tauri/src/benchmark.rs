@@ -0,0 +1,305 @@
+// モデルの比較ベンチマーク
+// 議論の発言に近い固定のプロンプト集を各モデルで順番に生成し、応答時間・生成速度を計測する
+// 審査モデルを指定すると各応答の質も採点し、4b を使う価値があるかなどを手元の環境で判断できるようにする
+use std::collections::HashSet;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Emitter, Manager};
+use tracing::{info, warn};
+
+use crate::{
+    app_state::AppState,
+    backend, call_ollama_generate,
+    generation::GenerationOptions,
+    is_allowed_model, llm_json, prompts,
+    prompts::PromptStyle,
+    ERR_UNSUPPORTED_MODEL,
+};
+
+pub const EVENT_BENCHMARK_PROGRESS: &str = "benchmark://progress";
+
+// 一度に比べるモデル数の上限
+const MAX_MODELS: usize = 6;
+// 計測を揃えるための固定シード
+const BENCHMARK_SEED: i64 = 20240401;
+// レポートに残す応答の最大文字数
+const MAX_OUTPUT_CHARS: usize = 400;
+
+/// プロンプト集の規模
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BenchmarkPromptSet {
+    /// 3件（数分で終わる）
+    #[default]
+    Quick,
+    /// 6件（長い履歴を含む）
+    Standard,
+}
+
+/// 1件分のプロンプト（テーマ・話者・役割・それまでの履歴）
+struct BenchmarkCase {
+    topic: &'static str,
+    speaker: &'static str,
+    role: &'static str,
+    history: &'static str,
+}
+
+const CASES: [BenchmarkCase; 6] = [
+    BenchmarkCase { topic: "週休3日制を導入すべきか", speaker: "佐藤", role: "人事担当者", history: "" },
+    BenchmarkCase {
+        topic: "高校での生成AIの利用を認めるべきか",
+        speaker: "鈴木",
+        role: "高校教師",
+        history: "田中: 宿題をAIに任せる生徒が増えると、考える力が育たないのではないでしょうか。\n",
+    },
+    BenchmarkCase {
+        topic: "地方都市の公共交通をどう維持するか",
+        speaker: "高橋",
+        role: "市役所職員",
+        history: "伊藤: 赤字路線は廃止して、オンデマンドの乗合タクシーに切り替えるべきです。\n\
+                  渡辺: 高齢者にはスマホでの予約が難しいという声も多いですよ。\n",
+    },
+    BenchmarkCase {
+        topic: "リモートワークと出社、どちらを基本にすべきか",
+        speaker: "山本",
+        role: "スタートアップの経営者",
+        history: "中村: 新人の育成は対面の方が圧倒的に早いと感じています。\n\
+                  小林: 通勤時間がなくなった分、集中できる時間が増えました。\n\
+                  中村: ただ、雑談から生まれるアイデアが減った気がします。\n",
+    },
+    BenchmarkCase {
+        topic: "食品ロスを減らすために家庭でできること",
+        speaker: "加藤",
+        role: "管理栄養士",
+        history: "吉田: 賞味期限と消費期限の違いを知らない人がまだ多いです。\n\
+                  山田: 買いすぎを防ぐには、冷蔵庫の中身を写真で残すのが効果的でした。\n",
+    },
+    BenchmarkCase {
+        topic: "大学の授業をすべてオンラインにすべきか",
+        speaker: "佐々木",
+        role: "大学生",
+        history: "松本: 録画なら何度でも見返せるので理解が深まります。\n\
+                  井上: でも、質問しにくくなって分からないまま進んでしまうこともあります。\n\
+                  木村: 実験や実習はオンラインでは代わりになりません。\n\
+                  松本: それなら講義だけオンラインにして、実習は対面にする形はどうでしょう。\n",
+    },
+];
+
+impl BenchmarkPromptSet {
+    fn cases(self) -> &'static [BenchmarkCase] {
+        match self {
+            BenchmarkPromptSet::Quick => &CASES[..3],
+            BenchmarkPromptSet::Standard => &CASES,
+        }
+    }
+}
+
+/// 審査モデルの回答
+#[derive(Debug, Deserialize)]
+struct Judgement {
+    score: f32,
+    #[serde(default)]
+    reason: String,
+}
+
+/// 1件分の結果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchmarkCaseResult {
+    pub topic: String,
+    /// 生成キューの待ちを除いた応答時間
+    pub latency_ms: Option<u128>,
+    pub tokens_per_sec: Option<f64>,
+    pub completion_tokens: Option<u32>,
+    /// 審査モデルによる質の評価（1〜10）
+    pub score: Option<f32>,
+    pub reason: Option<String>,
+    pub output: Option<String>,
+    pub error: Option<String>,
+}
+
+/// モデル1つ分の結果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelBenchmark {
+    pub model: String,
+    /// 最初の読み込みにかかった時間（計測には含めない）
+    pub load_ms: Option<u128>,
+    pub completed: usize,
+    pub failed: usize,
+    pub avg_latency_ms: Option<f64>,
+    pub max_latency_ms: Option<u128>,
+    pub avg_tokens_per_sec: Option<f64>,
+    pub avg_score: Option<f32>,
+    pub cases: Vec<BenchmarkCaseResult>,
+}
+
+/// benchmark_models の戻り値
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BenchmarkReport {
+    pub backend: &'static str,
+    pub prompt_set: BenchmarkPromptSet,
+    pub judge_model: Option<String>,
+    pub models: Vec<ModelBenchmark>,
+    /// 平均応答時間が最も短いモデル
+    pub fastest: Option<String>,
+    /// 平均評価が最も高いモデル（採点した場合のみ）
+    pub best_quality: Option<String>,
+    pub total_ms: u128,
+}
+
+/// benchmark://progress のペイロード
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BenchmarkProgressEvent {
+    model: String,
+    done: usize,
+    total: usize,
+}
+
+fn average<I: Iterator<Item = f64>>(values: I) -> Option<f64> {
+    let (sum, count) = values.fold((0.0, 0usize), |(sum, count), v| (sum + v, count + 1));
+    (count > 0).then(|| sum / count as f64)
+}
+
+/// 応答を審査モデルに採点させる
+async fn judge(app: &AppHandle, judge_model: &str, case: &BenchmarkCase, output: &str) -> Result<Judgement, String> {
+    let conversation = format!("{}{}: {}\n", case.history, case.speaker, output);
+    let prompt = prompts::build_discussion_quality_prompt(case.topic, &conversation, prompts::default_language());
+    let raw = call_ollama_generate(app, judge_model, &prompt).await?;
+    let mut judgement: Judgement = llm_json::parse_llm_json(&raw)?;
+    judgement.score = judgement.score.clamp(1.0, 10.0);
+    Ok(judgement)
+}
+
+async fn run_case(app: &AppHandle, model: &str, case: &BenchmarkCase, judge_model: Option<&str>) -> BenchmarkCaseResult {
+    let backend = backend::current(app);
+    let prompt =
+        prompts::build_ai_response_prompt(case.speaker, case.role, "", case.history, case.topic, &PromptStyle::default());
+    let options = GenerationOptions::seeded(Some(BENCHMARK_SEED));
+    let output = app.state::<AppState>().queue.generate(&*backend, model, &prompt, &options).await;
+    let mut result = BenchmarkCaseResult {
+        topic: case.topic.to_string(),
+        latency_ms: None,
+        tokens_per_sec: None,
+        completion_tokens: None,
+        score: None,
+        reason: None,
+        output: None,
+        error: None,
+    };
+    let generated = match output.result {
+        Ok(generated) => generated,
+        Err(e) => {
+            result.error = Some(e.to_string());
+            return result;
+        }
+    };
+    result.latency_ms = Some(output.elapsed.as_millis());
+    result.tokens_per_sec = generated.meta.tokens_per_sec;
+    result.completion_tokens = generated.meta.completion_tokens;
+    result.output = Some(generated.text.trim().chars().take(MAX_OUTPUT_CHARS).collect());
+    if let Some(judge_model) = judge_model {
+        match judge(app, judge_model, case, generated.text.trim()).await {
+            Ok(j) => {
+                result.score = Some(j.score);
+                result.reason = Some(j.reason);
+            }
+            Err(e) => warn!("ベンチマークの採点失敗 ({}): {}", model, e),
+        }
+    }
+    result
+}
+
+fn summarize(model: String, load_ms: Option<u128>, cases: Vec<BenchmarkCaseResult>) -> ModelBenchmark {
+    let latencies: Vec<u128> = cases.iter().filter_map(|c| c.latency_ms).collect();
+    ModelBenchmark {
+        model,
+        load_ms,
+        completed: latencies.len(),
+        failed: cases.len() - latencies.len(),
+        avg_latency_ms: average(latencies.iter().map(|ms| *ms as f64)),
+        max_latency_ms: latencies.iter().max().copied(),
+        avg_tokens_per_sec: average(cases.iter().filter_map(|c| c.tokens_per_sec)),
+        avg_score: average(cases.iter().filter_map(|c| c.score.map(f64::from))).map(|s| s as f32),
+        cases,
+    }
+}
+
+// 固定のプロンプト集で各モデルの応答時間・生成速度（judge_model 指定時は質の評価も）を比べる
+// models が空なら、インストール済みで許可されているモデルをすべて対象にする
+#[command]
+pub async fn benchmark_models(
+    app: AppHandle,
+    models: Vec<String>,
+    prompt_set: Option<BenchmarkPromptSet>,
+    judge_model: Option<String>,
+) -> Result<BenchmarkReport, String> {
+    let prompt_set = prompt_set.unwrap_or_default();
+    info!("benchmark_models 呼び出し: models={:?}, prompt_set={:?}, judge={:?}", models, prompt_set, judge_model);
+    let backend = backend::current(&app);
+    let mut models = if models.is_empty() {
+        backend.list_models().await?.into_iter().filter(|m| is_allowed_model(&app, m)).collect()
+    } else {
+        models
+    };
+    let mut seen = HashSet::new();
+    models.retain(|m| seen.insert(m.clone()));
+    if models.is_empty() {
+        return Err("比較できるモデルがありません".into());
+    }
+    if models.len() > MAX_MODELS {
+        return Err(format!("一度に比べられるモデルは{}個までです", MAX_MODELS));
+    }
+    if models.iter().chain(judge_model.iter()).any(|m| !is_allowed_model(&app, m)) {
+        return Err(ERR_UNSUPPORTED_MODEL.to_string());
+    }
+
+    let started = Instant::now();
+    let cases = prompt_set.cases();
+    let total = models.len() * cases.len();
+    let mut results = Vec::with_capacity(models.len());
+    for (index, model) in models.iter().enumerate() {
+        // 読み込み時間が最初の計測に混ざらないよう、先にモデルを読み込んでおく
+        let load_started = Instant::now();
+        let load_ms = match backend.warm_up(model, None).await {
+            Ok(()) => Some(load_started.elapsed().as_millis()),
+            Err(e) => {
+                warn!("ベンチマークのモデル読み込み失敗 ({}): {}", model, e);
+                None
+            }
+        };
+        let mut case_results = Vec::with_capacity(cases.len());
+        for (done, case) in cases.iter().enumerate() {
+            case_results.push(run_case(&app, model, case, judge_model.as_deref()).await);
+            let _ = app.emit(
+                EVENT_BENCHMARK_PROGRESS,
+                BenchmarkProgressEvent { model: model.clone(), done: index * cases.len() + done + 1, total },
+            );
+        }
+        results.push(summarize(model.clone(), load_ms, case_results));
+    }
+
+    let fastest = results
+        .iter()
+        .filter_map(|m| m.avg_latency_ms.map(|ms| (m, ms)))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(m, _)| m.model.clone());
+    let best_quality = results
+        .iter()
+        .filter_map(|m| m.avg_score.map(|s| (m, s)))
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(m, _)| m.model.clone());
+    info!("ベンチマーク完了: {}モデル, {}ms", results.len(), started.elapsed().as_millis());
+    Ok(BenchmarkReport {
+        backend: backend.name(),
+        prompt_set,
+        judge_model,
+        models: results,
+        fastest,
+        best_quality,
+        total_ms: started.elapsed().as_millis(),
+    })
+}
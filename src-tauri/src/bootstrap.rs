@@ -0,0 +1,192 @@
+// Ollama の自動起動
+// Ollama がインストールされているか（PATH と OS ごとの既定のインストール先）を調べ、起動していなければ `ollama serve` を子プロセスとして起動する
+// 初回起動の利用者が「応答なし」だけを見て止まらないよう、アプリ起動時に ensure_ollama_running を呼ぶ
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{command, AppHandle};
+use tracing::{info, warn};
+
+use crate::{backend, backend::OllamaConnection, health};
+
+// 起動後に応答を待つ上限
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(20);
+// 起動待ちの確認間隔
+const STARTUP_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+pub const DOWNLOAD_URL: &str = "https://ollama.com/download";
+
+#[cfg(windows)]
+const BINARY_NAME: &str = "ollama.exe";
+#[cfg(not(windows))]
+const BINARY_NAME: &str = "ollama";
+
+/// アプリが起動した `ollama serve`（アプリ終了時に停止する）
+static CHILD: Mutex<Option<Child>> = Mutex::new(None);
+
+/// ensure_ollama_running の結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BootstrapOutcome {
+    /// 既に起動していた
+    AlreadyRunning,
+    /// アプリが起動した
+    Started,
+    /// Ollama が見つからない
+    NotInstalled,
+    /// 接続先が別のマシン、または Ollama 以外のバックエンドのため起動しない
+    Skipped,
+    /// 起動したが応答がない
+    Failed,
+}
+
+/// ensure_ollama_running の戻り値
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BootstrapStatus {
+    pub outcome: BootstrapOutcome,
+    /// 見つかった Ollama の実行ファイル
+    pub binary_path: Option<String>,
+    /// 画面に出す説明
+    pub message: String,
+}
+
+impl BootstrapStatus {
+    fn new(outcome: BootstrapOutcome, binary: Option<&Path>, message: impl Into<String>) -> Self {
+        Self { outcome, binary_path: binary.map(|p| p.display().to_string()), message: message.into() }
+    }
+}
+
+/// OS ごとの既定のインストール先
+fn default_install_paths() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    if cfg!(windows) {
+        if let Some(local) = std::env::var_os("LOCALAPPDATA") {
+            paths.push(PathBuf::from(local).join("Programs").join("Ollama").join(BINARY_NAME));
+        }
+        if let Some(program_files) = std::env::var_os("ProgramFiles") {
+            paths.push(PathBuf::from(program_files).join("Ollama").join(BINARY_NAME));
+        }
+    } else if cfg!(target_os = "macos") {
+        paths.push(PathBuf::from("/Applications/Ollama.app/Contents/Resources/ollama"));
+        paths.push(PathBuf::from("/opt/homebrew/bin/ollama"));
+        paths.push(PathBuf::from("/usr/local/bin/ollama"));
+    } else {
+        paths.push(PathBuf::from("/usr/local/bin/ollama"));
+        paths.push(PathBuf::from("/usr/bin/ollama"));
+        if let Some(home) = std::env::var_os("HOME") {
+            paths.push(PathBuf::from(home).join(".local").join("bin").join("ollama"));
+        }
+    }
+    paths
+}
+
+/// PATH 上の実行ファイルを探す
+pub fn find_on_path(file: &str) -> Option<PathBuf> {
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path).map(|dir| dir.join(file)).find(|p| p.is_file())
+}
+
+/// Ollama の実行ファイルを探す（PATH を優先し、なければ既定のインストール先）
+/// GUI から起動したアプリはシェルの PATH を引き継がないことがあるため、既定の場所も確認する
+pub fn find_binary() -> Option<PathBuf> {
+    find_on_path(BINARY_NAME).or_else(|| default_install_paths().into_iter().find(|p| p.is_file()))
+}
+
+/// 接続先がこのマシンか（別のマシンの Ollama は起動できない）
+fn is_local_host(host: &str) -> bool {
+    let host = host.split("://").last().unwrap_or(host);
+    matches!(host, "localhost" | "127.0.0.1" | "0.0.0.0" | "::1" | "[::1]")
+}
+
+/// `ollama serve` を起動する（接続設定のポートで待ち受けさせる）
+fn spawn_serve(binary: &Path, connection: &OllamaConnection) -> Result<Child, String> {
+    let mut command = Command::new(binary);
+    command
+        .arg("serve")
+        .env("OLLAMA_HOST", format!("127.0.0.1:{}", connection.port))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        // コンソールウィンドウを出さない
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    command.spawn().map_err(|e| format!("Ollama の起動に失敗しました: {}", e))
+}
+
+/// Ollama が起動していなければ起動する（アプリ起動時と画面からの再試行で呼ぶ）
+pub async fn ensure_running(app: &AppHandle) -> BootstrapStatus {
+    let connection = backend::connection(app);
+    if backend::current(app).name() != "ollama" || !is_local_host(&connection.host) {
+        return BootstrapStatus::new(BootstrapOutcome::Skipped, None, "Ollama の自動起動は対象外です");
+    }
+    let binary = find_binary();
+    if backend::current(app).is_available().await {
+        return BootstrapStatus::new(BootstrapOutcome::AlreadyRunning, binary.as_deref(), "Ollama は起動しています");
+    }
+    let Some(binary) = binary else {
+        warn!("Ollama が見つかりません");
+        return BootstrapStatus::new(
+            BootstrapOutcome::NotInstalled,
+            None,
+            format!("Ollama がインストールされていません。{} からインストールしてください", DOWNLOAD_URL),
+        );
+    };
+
+    // 二重起動を防ぐ（前回起動した子プロセスが残っていれば、それが応答するのを待つ）
+    {
+        let mut child = CHILD.lock().unwrap_or_else(|e| e.into_inner());
+        let alive = child.as_mut().is_some_and(|c| matches!(c.try_wait(), Ok(None)));
+        if !alive {
+            info!("Ollama を起動します: {}", binary.display());
+            match spawn_serve(&binary, &connection) {
+                Ok(spawned) => *child = Some(spawned),
+                Err(e) => {
+                    warn!("{}", e);
+                    return BootstrapStatus::new(BootstrapOutcome::Failed, Some(&binary), e);
+                }
+            }
+        }
+    }
+
+    let started = Instant::now();
+    while started.elapsed() < STARTUP_TIMEOUT {
+        tokio::time::sleep(STARTUP_POLL_INTERVAL).await;
+        if backend::current(app).is_available().await {
+            info!("Ollama の起動を確認しました ({}ms)", started.elapsed().as_millis());
+            return BootstrapStatus::new(BootstrapOutcome::Started, Some(&binary), "Ollama を起動しました");
+        }
+    }
+    BootstrapStatus::new(
+        BootstrapOutcome::Failed,
+        Some(&binary),
+        format!("Ollama を起動しましたが {} 秒以内に応答がありません", STARTUP_TIMEOUT.as_secs()),
+    )
+}
+
+/// アプリが起動した Ollama を停止する（アプリ終了時）
+pub fn shutdown() {
+    if let Some(mut child) = CHILD.lock().unwrap_or_else(|e| e.into_inner()).take() {
+        info!("起動した Ollama を停止します");
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+}
+
+// Ollama が起動していなければ起動する（インストールされていなければその旨を返す）
+#[command]
+pub async fn ensure_ollama_running(app: AppHandle) -> BootstrapStatus {
+    let status = ensure_running(&app).await;
+    if status.outcome == BootstrapOutcome::Started {
+        // 死活監視の次の周期を待たずに状態を通知する
+        health::refresh(&app).await;
+    }
+    status
+}
@@ -0,0 +1,213 @@
+// 生成バックエンドのサーキットブレーカー
+// 生成リクエストの失敗が続いたら一定時間（接続設定の breakerCooldownSecs）送信を止め、backend://degraded で通知する
+// 停止時間が過ぎたら1件だけ試しに通し、成功すれば復旧（degraded: false を通知）、失敗すれば再び停止する
+// ブレーカーは AppState が持ち、生成バックエンドには作成時に渡す
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use serde_json::json;
+use tauri::{command, AppHandle, Emitter, State};
+use tracing::{info, warn};
+
+use crate::{
+    app_state::{AppState, RuntimeSettings},
+    audit,
+};
+
+pub const EVENT_BACKEND_DEGRADED: &str = "backend://degraded";
+
+/// ブレーカーの状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum BreakerState {
+    /// 通常どおり送信する
+    Closed,
+    /// 失敗が続いたため送信を止めている
+    Open,
+    /// 停止時間が過ぎ、試しの1件を送っている
+    HalfOpen,
+}
+
+struct Breaker {
+    state: BreakerState,
+    consecutive_failures: u32,
+    opened_at: Option<Instant>,
+    last_error: Option<String>,
+    /// 停止した累計回数
+    trips: u64,
+}
+
+/// サーキットブレーカー（状態が変わったら app へ backend://degraded を通知する）
+pub struct CircuitBreaker {
+    breaker: Mutex<Breaker>,
+    app: Option<AppHandle>,
+    /// 閾値と停止時間は接続設定から読む
+    settings: Arc<RuntimeSettings>,
+}
+
+/// get_circuit_breaker_status の戻り値と backend://degraded のペイロード
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BreakerStatus {
+    pub state: BreakerState,
+    /// 送信を止めている（試しの1件を送っている間も含む）
+    pub degraded: bool,
+    pub consecutive_failures: u32,
+    /// 送信を再開するまでの秒数（停止中のみ）
+    pub retry_after_secs: Option<u64>,
+    pub last_error: Option<String>,
+    pub trips: u64,
+}
+
+fn status_of(breaker: &Breaker, cooldown: Duration) -> BreakerStatus {
+    let retry_after_secs = match (breaker.state, breaker.opened_at) {
+        (BreakerState::Open, Some(opened_at)) => Some(cooldown.saturating_sub(opened_at.elapsed()).as_secs()),
+        _ => None,
+    };
+    BreakerStatus {
+        state: breaker.state,
+        degraded: breaker.state != BreakerState::Closed,
+        consecutive_failures: breaker.consecutive_failures,
+        retry_after_secs,
+        last_error: breaker.last_error.clone(),
+        trips: breaker.trips,
+    }
+}
+
+impl CircuitBreaker {
+    pub fn new(app: Option<AppHandle>, settings: Arc<RuntimeSettings>) -> Self {
+        Self {
+            breaker: Mutex::new(Breaker {
+                state: BreakerState::Closed,
+                consecutive_failures: 0,
+                opened_at: None,
+                last_error: None,
+                trips: 0,
+            }),
+            app,
+            settings,
+        }
+    }
+
+    /// 接続設定の (閾値, 停止時間)。閾値 0 はブレーカー無効
+    fn settings(&self) -> (u32, Duration) {
+        let client = self.settings.client.read();
+        let connection = client.connection();
+        (connection.breaker_threshold as u32, Duration::from_secs(connection.breaker_cooldown_secs))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Breaker> {
+        self.breaker.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn notify(&self, status: BreakerStatus) {
+        if let Some(app) = &self.app {
+            let _ = app.emit(EVENT_BACKEND_DEGRADED, status);
+        }
+    }
+
+    /// 送信してよいか確認する（停止中ならエラー。停止時間が過ぎていれば試しの1件として通す）
+    pub fn check(&self) -> Result<(), String> {
+        let (threshold, cooldown) = self.settings();
+        if threshold == 0 {
+            return Ok(());
+        }
+        let mut breaker = self.lock();
+        match breaker.state {
+            BreakerState::Closed => Ok(()),
+            BreakerState::Open => {
+                let elapsed = breaker.opened_at.map(|t| t.elapsed()).unwrap_or(cooldown);
+                if elapsed >= cooldown {
+                    info!("バックエンドへの送信を試験的に再開");
+                    breaker.state = BreakerState::HalfOpen;
+                    breaker.opened_at = Some(Instant::now());
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "LLMバックエンドの失敗が続いたため送信を停止しています（あと{}秒）",
+                        (cooldown - elapsed).as_secs().max(1)
+                    ))
+                }
+            }
+            BreakerState::HalfOpen => {
+                // 試しの1件が中断されて結果が届かない場合に備え、停止時間ごとに次の1件を通す
+                if breaker.opened_at.is_some_and(|t| t.elapsed() >= cooldown) {
+                    breaker.opened_at = Some(Instant::now());
+                    return Ok(());
+                }
+                Err("LLMバックエンドの復旧を確認中です。しばらくしてから再試行してください".into())
+            }
+        }
+    }
+
+    /// 送信が成功した
+    pub fn record_success(&self) {
+        let (_, cooldown) = self.settings();
+        let mut breaker = self.lock();
+        let recovered = breaker.state != BreakerState::Closed;
+        breaker.state = BreakerState::Closed;
+        breaker.consecutive_failures = 0;
+        breaker.opened_at = None;
+        if recovered {
+            info!("LLMバックエンドが復旧しました");
+            breaker.last_error = None;
+            let status = status_of(&breaker, cooldown);
+            drop(breaker);
+            audit::record("backend", "recovered", json!({}));
+            self.notify(status);
+        }
+    }
+
+    /// 送信が失敗した（閾値に達したら送信を止める）
+    pub fn record_failure(&self, error: &str) {
+        let (threshold, cooldown) = self.settings();
+        if threshold == 0 {
+            return;
+        }
+        let mut breaker = self.lock();
+        breaker.consecutive_failures += 1;
+        breaker.last_error = Some(error.to_string());
+        let trip = match breaker.state {
+            BreakerState::HalfOpen => true,
+            BreakerState::Closed => breaker.consecutive_failures >= threshold,
+            BreakerState::Open => false,
+        };
+        if !trip {
+            return;
+        }
+        breaker.state = BreakerState::Open;
+        breaker.opened_at = Some(Instant::now());
+        breaker.trips += 1;
+        warn!(
+            "LLMバックエンドの失敗が{}回続いたため{}秒間送信を停止: {}",
+            breaker.consecutive_failures,
+            cooldown.as_secs(),
+            error
+        );
+        let status = status_of(&breaker, cooldown);
+        drop(breaker);
+        audit::record("backend", "degraded", json!({ "failures": status.consecutive_failures, "error": error }));
+        self.notify(status);
+    }
+
+    /// 現在の状態
+    pub fn status(&self) -> BreakerStatus {
+        let (_, cooldown) = self.settings();
+        status_of(&self.lock(), cooldown)
+    }
+}
+
+// サーキットブレーカーの状態
+#[command]
+pub fn get_circuit_breaker_status(state: State<'_, AppState>) -> BreakerStatus {
+    state.breaker.status()
+}
+
+// 停止を解除して送信を再開する（Ollama を起動し直した時など）
+#[command]
+pub fn reset_circuit_breaker(state: State<'_, AppState>) -> BreakerStatus {
+    info!("reset_circuit_breaker 呼び出し");
+    state.breaker.record_success();
+    state.breaker.status()
+}
@@ -0,0 +1,102 @@
+// 議論コーチング（ユーザー発言の下書き講評）
+use serde::{Deserialize, Deserializer, Serialize};
+use tauri::{command, AppHandle};
+use tracing::info;
+
+use crate::{
+    call_ollama_generate_with, db, generation::GenerationOptions, is_allowed_model, llm_json, prompts, session_settings,
+    ERR_UNSUPPORTED_MODEL,
+};
+
+/// 観点ごとの採点とコメント
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoachingAspect {
+    /// 1〜5（小数・範囲外・文字列の数字で返されても丸めて受け取る）
+    #[serde(deserialize_with = "lenient_score")]
+    pub score: u8,
+    #[serde(default)]
+    pub comment: String,
+}
+
+/// 下書きに対する講評結果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CoachingFeedback {
+    pub clarity: CoachingAspect,
+    pub evidence: CoachingAspect,
+    pub tone: CoachingAspect,
+    #[serde(default)]
+    pub logical_gaps: Vec<String>,
+    #[serde(default)]
+    pub overall: String,
+    #[serde(default)]
+    pub improved_draft: String,
+}
+
+/// モデルが返した点数（3.5・-1・"4" など）を四捨五入して 1〜5 に収める
+/// 小さいモデルは整数で返さないことが多く、そのたびに講評全体を捨てないようにする
+fn lenient_score<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u8, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Score {
+        Number(f64),
+        Text(String),
+    }
+    let score = match Score::deserialize(deserializer)? {
+        Score::Number(n) => n,
+        Score::Text(text) => text.trim().parse().map_err(serde::de::Error::custom)?,
+    };
+    if !score.is_finite() {
+        return Err(serde::de::Error::custom(format!("点数が数値ではありません: {}", score)));
+    }
+    Ok(score.round().clamp(1.0, 5.0) as u8)
+}
+
+// ユーザー発言の下書きを講評し、改善案を返す
+#[command]
+pub async fn coach_user_message(
+    app: AppHandle,
+    session_id: i64,
+    draft: String,
+    seed: Option<i64>,
+    options: Option<GenerationOptions>,
+) -> Result<CoachingFeedback, String> {
+    info!("coach_user_message 呼び出し: session_id={}, draft=[{}文字]", session_id, draft.chars().count());
+    if draft.trim().is_empty() {
+        return Err("下書きが空です".into());
+    }
+
+    let session = db::load_session(&app, session_id).await?;
+    if !is_allowed_model(&app, &session.model) {
+        return Err(ERR_UNSUPPORTED_MODEL.to_string());
+    }
+
+    let prompt = prompts::build_coaching_prompt(
+        &session.topic,
+        &session.history_text(),
+        &session.participant_names(),
+        &draft,
+        session_settings::load(&app, session_id).await?.language(),
+    );
+    let options = GenerationOptions::from_request(options, seed);
+    let raw = call_ollama_generate_with(&app, &session.model, &prompt, &options).await?;
+    llm_json::parse_llm_json(&raw)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score_of(value: serde_json::Value) -> Result<u8, serde_json::Error> {
+        serde_json::from_value::<CoachingAspect>(serde_json::json!({ "score": value })).map(|a| a.score)
+    }
+
+    #[test]
+    fn fractional_and_out_of_range_scores_are_rounded_into_range() {
+        assert_eq!(score_of(serde_json::json!(3.5)).unwrap(), 4);
+        assert_eq!(score_of(serde_json::json!(-1)).unwrap(), 1);
+        assert_eq!(score_of(serde_json::json!(9)).unwrap(), 5);
+        assert_eq!(score_of(serde_json::json!(" 2 ")).unwrap(), 2);
+        assert!(score_of(serde_json::json!("よい")).is_err());
+    }
+}
@@ -0,0 +1,200 @@
+// アプリ全体の設定
+// app_settings テーブルに1行の JSON として保存し、項目追加時もマイグレーション不要にする
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Manager};
+use tracing::info;
+
+use crate::{app_state::AppState, backend, backend_profiles, backend_profiles::BackendProfile, backend::{BackendKind, CandleConfig, OllamaConnection}, db, embeddings::EmbeddingSettings, facilitator::FacilitatorSettings, logging, model_access::ModelAccess, openai_backend, openai_backend::OpenAiCompatConfig, postprocess::PostprocessSettings, privacy, privacy::PrivacySettings, prompts, prompts::{Language, UserPersona}, safety, safety::SafetyPolicy};
+
+/// アプリ全体の設定値（未指定の項目は既定値で補完）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AppSettings {
+    /// 発言保存に合わせてバックエンドで自動要約するか
+    pub auto_summary: bool,
+    /// 初回フル要約を行う最小発言数
+    pub summary_min_initial: usize,
+    /// 前回要約からこの件数の発言が増えたらインクリメンタル要約
+    pub summary_interval: usize,
+    /// 発言保存に合わせてバックエンドで自動分析するか
+    pub auto_analysis: bool,
+    /// 前回分析からこの件数の発言が増えたら分析
+    pub analysis_interval: usize,
+    /// 最後の発言からこの秒数が経ったら、件数に満たなくても分析（0 で無効）
+    pub analysis_idle_secs: u64,
+    /// 定期メンテナンスを行うか
+    pub maintenance_enabled: bool,
+    /// メンテナンス実行時刻（ローカル時刻の時、0〜23）。未指定ならアイドル時に実行
+    pub maintenance_hour: Option<u8>,
+    /// この日数より古いセッションを削除する（未指定なら削除しない）
+    pub retention_days: Option<u32>,
+    /// LLM バックエンド（mock なら Ollama なしで定型応答を返す）
+    pub llm_backend: BackendKind,
+    /// Ollama サーバーの接続先・タイムアウト・再試行回数
+    pub ollama: OllamaConnection,
+    /// 名前付きの接続先（set_active_profile で llmBackend・ollama を切り替える）
+    pub backend_profiles: Vec<BackendProfile>,
+    /// 最後に切り替えた接続プロファイルの名前（未使用なら空）
+    pub active_profile: String,
+    /// candle（組み込み推論）のモデルファイル・デバイス・Ollama 不在時の切り替え
+    pub candle: CandleConfig,
+    /// OpenAI 互換サーバー（llama.cpp server・LM Studio）の接続先
+    pub openai: OpenAiCompatConfig,
+    /// 使用を許可するモデル
+    pub models: ModelAccess,
+    /// 入力・出力に適用する安全ポリシー
+    pub safety: SafetyPolicy,
+    /// ログ・監査ログ・共有用出力での個人情報の伏せ字設定
+    pub privacy: PrivacySettings,
+    /// ログレベル（off / error / warn / info / debug / trace）
+    pub log_level: String,
+    /// 起動時に Ollama が動いていなければ `ollama serve` を起動するか
+    pub auto_start_ollama: bool,
+    /// 議論・要約・分析の出力言語（ja / en。セッション設定で上書きできる）
+    pub language: Language,
+    /// 発言の後処理（話者名・コードフェンスなどの除去と最大文字数）
+    pub postprocess: PostprocessSettings,
+    /// 発言の埋め込み（意味検索用）
+    pub embeddings: EmbeddingSettings,
+    /// 保存済みプロフィールの参加者に、議論をまたいだ記憶を持たせるか
+    pub participant_memory: bool,
+    /// 自動進行で議論の停滞・偏り・脱線に介入する司会役
+    pub facilitator: FacilitatorSettings,
+    /// 自動分析の後にテーマからの脱線を判定し、しきい値を超えたら通知するか
+    pub topic_drift_alerts: bool,
+    /// 脱線を通知する度合い（0〜1）
+    pub topic_drift_threshold: f32,
+    /// 人間の参加者の自己紹介（AI参加者が名前で呼びかけ、専門・立場を踏まえて応答する）
+    pub user_persona: UserPersona,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            auto_summary: true,
+            summary_min_initial: 12,
+            summary_interval: 4,
+            auto_analysis: true,
+            analysis_interval: 3,
+            analysis_idle_secs: 60,
+            maintenance_enabled: true,
+            maintenance_hour: None,
+            retention_days: None,
+            llm_backend: BackendKind::Ollama,
+            ollama: OllamaConnection::default(),
+            backend_profiles: Vec::new(),
+            active_profile: String::new(),
+            candle: CandleConfig::default(),
+            openai: OpenAiCompatConfig::default(),
+            models: ModelAccess::default(),
+            safety: SafetyPolicy::default(),
+            privacy: PrivacySettings::default(),
+            log_level: logging::DEFAULT_LOG_LEVEL.into(),
+            auto_start_ollama: true,
+            language: Language::default(),
+            postprocess: PostprocessSettings::default(),
+            embeddings: EmbeddingSettings::default(),
+            participant_memory: false,
+            facilitator: FacilitatorSettings::default(),
+            topic_drift_alerts: true,
+            topic_drift_threshold: 0.6,
+            user_persona: UserPersona::default(),
+        }
+    }
+}
+
+impl AppSettings {
+    /// 範囲外の値を補正
+    fn sanitized(mut self) -> Self {
+        self.summary_min_initial = self.summary_min_initial.max(1);
+        self.summary_interval = self.summary_interval.max(1);
+        self.analysis_interval = self.analysis_interval.max(1);
+        self.maintenance_hour = self.maintenance_hour.filter(|h| *h < 24);
+        self.retention_days = self.retention_days.filter(|d| *d > 0);
+        self.ollama = self.ollama.sanitized();
+        self.backend_profiles = backend_profiles::sanitize_profiles(self.backend_profiles);
+        if !self.backend_profiles.iter().any(|p| p.name == self.active_profile) {
+            self.active_profile.clear();
+        }
+        self.candle = self.candle.sanitized();
+        self.openai = self.openai.sanitized();
+        self.models = self.models.sanitized();
+        self.postprocess = self.postprocess.sanitized();
+        self.embeddings = self.embeddings.sanitized();
+        self.privacy = self.privacy.sanitized();
+        self.facilitator = self.facilitator.sanitized();
+        if !self.topic_drift_threshold.is_finite() {
+            self.topic_drift_threshold = Self::default().topic_drift_threshold;
+        }
+        self.topic_drift_threshold = self.topic_drift_threshold.clamp(0.0, 1.0);
+        self.user_persona = self.user_persona.sanitized();
+        if logging::parse_level(&self.log_level).is_err() {
+            self.log_level = logging::DEFAULT_LOG_LEVEL.into();
+        }
+        self
+    }
+}
+
+/// 実行中のモジュールへ設定を反映（起動時と保存時）
+pub fn apply(app: &AppHandle, settings: &AppSettings) {
+    let state = app.state::<AppState>();
+    backend::select(&state, settings.llm_backend);
+    backend::set_connection(&state.settings, &settings.ollama);
+    backend::set_candle_config(&state.settings, &settings.candle);
+    openai_backend::set_config(&state.settings, &settings.openai);
+    state.queue.set_limit(settings.ollama.max_parallel);
+    state.settings.models.set(settings.models.clone());
+    safety::set_policy(&settings.safety);
+    privacy::set_settings(&state.settings, &settings.privacy);
+    logging::set_level(&settings.log_level);
+    prompts::set_default_language(settings.language);
+    prompts::set_user_persona(&settings.user_persona);
+    state.settings.postprocess.set(settings.postprocess.clone());
+    state.settings.embeddings.set(settings.embeddings.clone());
+}
+
+/// 設定を読み込む（未保存なら既定値）
+pub async fn load(app: &AppHandle) -> Result<AppSettings, String> {
+    let pool = db::pool(app).await?;
+    let row: Option<(String,)> = sqlx::query_as("SELECT settings FROM app_settings WHERE id = 1")
+        .fetch_optional(&pool)
+        .await
+        .map_err(|e| format!("設定取得失敗: {}", e))?;
+    Ok(row
+        .and_then(|(json,)| serde_json::from_str::<AppSettings>(&json).ok())
+        .unwrap_or_default()
+        .sanitized())
+}
+
+// アプリ設定を取得
+#[command]
+pub async fn get_settings(app: AppHandle) -> Result<AppSettings, String> {
+    load(&app).await
+}
+
+/// 設定を検証・保存し、実行中のモジュールへ反映する
+pub async fn save(app: &AppHandle, settings: AppSettings) -> Result<AppSettings, String> {
+    let settings = settings.sanitized();
+    settings.safety.validate()?;
+    settings.ollama.validate()?;
+    let pool = db::pool(app).await?;
+    let json = serde_json::to_string(&settings).map_err(|e| format!("設定のシリアライズ失敗: {}", e))?;
+    sqlx::query(
+        "INSERT INTO app_settings (id, settings, updated_at) VALUES (1, ?, ?)
+         ON CONFLICT(id) DO UPDATE SET settings = excluded.settings, updated_at = excluded.updated_at",
+    )
+    .bind(json)
+    .bind(db::now_string())
+    .execute(&pool)
+    .await
+    .map_err(|e| format!("設定保存失敗: {}", e))?;
+    apply(app, &settings);
+    Ok(settings)
+}
+
+// アプリ設定を保存（全項目を上書き）
+#[command]
+pub async fn set_settings(app: AppHandle, settings: AppSettings) -> Result<AppSettings, String> {
+    info!("set_settings 呼び出し: {:?}", settings);
+    save(&app, settings).await
+}
@@ -0,0 +1,1204 @@
+// データベースアクセスモジュール
+// フロントエンドと同じ SQL プラグインのプール（既定は sqlite:dewai.db。ワークスペースで切り替わる）を Rust 側からも共有する
+use serde::Serialize;
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_sql::{DbInstances, DbPool, Migration, MigrationKind};
+
+pub use dewai_core::session::{format_history, AiParticipant, ParticipantsData, StoredMessage};
+
+use crate::{
+    app_state::{AppState, RuntimeSettings},
+    encryption,
+    encryption::KeyStore,
+    privacy,
+};
+
+/// フロントエンドと共通の接続URL（tauri.conf.json の preload と一致させる）
+pub const DB_URL: &str = "sqlite:dewai.db";
+
+/// 現在のワークスペースの接続URL（DbInstances のキー）
+pub fn active_url(app: &AppHandle) -> String {
+    app.state::<AppState>().db_url.get()
+}
+
+/// スキーマのマイグレーション一覧（preload 時に SQL プラグインが適用する）
+/// 適用済みのものは書き換えず、必ず末尾に追加すること
+pub fn migrations() -> Vec<Migration> {
+    vec![
+        Migration {
+            version: 1,
+            description: "baseline_schema",
+            // フロントエンドの ensureSchema() と同一定義（既存DBでは何もしない）
+            sql: "CREATE TABLE IF NOT EXISTS sessions (
+                    id INTEGER PRIMARY KEY,
+                    topic TEXT NOT NULL,
+                    participants TEXT NOT NULL,
+                    messages TEXT NOT NULL,
+                    model TEXT NOT NULL,
+                    created_at TEXT NOT NULL,
+                    updated_at TEXT NOT NULL
+                );
+                CREATE TABLE IF NOT EXISTS session_analysis (
+                    id INTEGER PRIMARY KEY,
+                    session_id INTEGER NOT NULL,
+                    kind TEXT NOT NULL,
+                    payload TEXT NOT NULL,
+                    created_at TEXT NOT NULL,
+                    FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE
+                );
+                CREATE TABLE IF NOT EXISTS session_meta (
+                    session_id INTEGER PRIMARY KEY,
+                    last_opened_at TEXT NOT NULL
+                );
+                CREATE INDEX IF NOT EXISTS idx_sessions_updated_at ON sessions(updated_at);
+                CREATE INDEX IF NOT EXISTS idx_session_meta_last_opened ON session_meta(last_opened_at);
+                CREATE INDEX IF NOT EXISTS idx_session_analysis_session_created ON session_analysis(session_id, created_at);",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 2,
+            description: "session_settings",
+            sql: "CREATE TABLE IF NOT EXISTS session_settings (
+                    session_id INTEGER PRIMARY KEY,
+                    settings TEXT NOT NULL,
+                    updated_at TEXT NOT NULL,
+                    FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE
+                );",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 3,
+            description: "translations",
+            sql: "CREATE TABLE IF NOT EXISTS translations (
+                    id INTEGER PRIMARY KEY,
+                    session_id INTEGER NOT NULL,
+                    source_kind TEXT NOT NULL,
+                    source_key INTEGER NOT NULL,
+                    lang TEXT NOT NULL,
+                    source_hash TEXT NOT NULL,
+                    text TEXT NOT NULL,
+                    created_at TEXT NOT NULL,
+                    UNIQUE(session_id, source_kind, source_key, lang),
+                    FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE
+                );",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 4,
+            description: "annotations",
+            sql: "CREATE TABLE IF NOT EXISTS annotations (
+                    id INTEGER PRIMARY KEY,
+                    session_id INTEGER NOT NULL,
+                    message_index INTEGER NOT NULL,
+                    kind TEXT NOT NULL,
+                    content TEXT NOT NULL,
+                    created_at TEXT NOT NULL,
+                    FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE
+                );
+                CREATE INDEX IF NOT EXISTS idx_annotations_session_message ON annotations(session_id, message_index);",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 5,
+            description: "app_settings",
+            sql: "CREATE TABLE IF NOT EXISTS app_settings (
+                    id INTEGER PRIMARY KEY CHECK (id = 1),
+                    settings TEXT NOT NULL,
+                    updated_at TEXT NOT NULL
+                );",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 6,
+            description: "tournaments",
+            sql: "CREATE TABLE IF NOT EXISTS tournaments (
+                    id INTEGER PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    resolution TEXT NOT NULL,
+                    model TEXT NOT NULL,
+                    rounds_per_debate INTEGER NOT NULL,
+                    current_round INTEGER NOT NULL DEFAULT 1,
+                    status TEXT NOT NULL,
+                    created_at TEXT NOT NULL
+                );
+                CREATE TABLE IF NOT EXISTS tournament_entrants (
+                    id INTEGER PRIMARY KEY,
+                    tournament_id INTEGER NOT NULL,
+                    name TEXT NOT NULL,
+                    role TEXT NOT NULL,
+                    description TEXT NOT NULL,
+                    FOREIGN KEY(tournament_id) REFERENCES tournaments(id) ON DELETE CASCADE
+                );
+                CREATE TABLE IF NOT EXISTS tournament_matches (
+                    id INTEGER PRIMARY KEY,
+                    tournament_id INTEGER NOT NULL,
+                    round INTEGER NOT NULL,
+                    entrant_a INTEGER NOT NULL,
+                    entrant_b INTEGER,
+                    session_id INTEGER,
+                    score_a REAL,
+                    score_b REAL,
+                    winner INTEGER,
+                    judge_notes TEXT,
+                    status TEXT NOT NULL,
+                    FOREIGN KEY(tournament_id) REFERENCES tournaments(id) ON DELETE CASCADE
+                );
+                CREATE INDEX IF NOT EXISTS idx_tournament_matches_round ON tournament_matches(tournament_id, round);",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 7,
+            description: "generation_log",
+            sql: "CREATE TABLE IF NOT EXISTS generation_log (
+                    id INTEGER PRIMARY KEY,
+                    session_id INTEGER NOT NULL,
+                    message_index INTEGER NOT NULL,
+                    speaker TEXT NOT NULL,
+                    model TEXT NOT NULL,
+                    prompt TEXT NOT NULL,
+                    options TEXT NOT NULL,
+                    seed INTEGER,
+                    output TEXT NOT NULL,
+                    created_at TEXT NOT NULL,
+                    FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE
+                );
+                CREATE INDEX IF NOT EXISTS idx_generation_log_session ON generation_log(session_id, message_index);",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 8,
+            description: "experiments",
+            sql: "CREATE TABLE IF NOT EXISTS experiments (
+                    id INTEGER PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    spec TEXT NOT NULL,
+                    created_at TEXT NOT NULL
+                );
+                CREATE TABLE IF NOT EXISTS experiment_runs (
+                    id INTEGER PRIMARY KEY,
+                    experiment_id INTEGER NOT NULL,
+                    session_id INTEGER,
+                    config TEXT NOT NULL,
+                    quality_score REAL,
+                    avg_length REAL,
+                    avg_latency_ms REAL,
+                    total_ms INTEGER NOT NULL,
+                    error TEXT,
+                    created_at TEXT NOT NULL,
+                    FOREIGN KEY(experiment_id) REFERENCES experiments(id) ON DELETE CASCADE
+                );
+                CREATE INDEX IF NOT EXISTS idx_experiment_runs_experiment ON experiment_runs(experiment_id);",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 9,
+            description: "maintenance",
+            sql: "CREATE TABLE IF NOT EXISTS maintenance_log (
+                    id INTEGER PRIMARY KEY,
+                    trigger_kind TEXT NOT NULL,
+                    started_at TEXT NOT NULL,
+                    finished_at TEXT NOT NULL,
+                    status TEXT NOT NULL,
+                    steps TEXT NOT NULL
+                );
+                CREATE TABLE IF NOT EXISTS model_info_cache (
+                    name TEXT PRIMARY KEY,
+                    details TEXT NOT NULL,
+                    fetched_at TEXT NOT NULL
+                );",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 10,
+            description: "engine_runs",
+            sql: "CREATE TABLE IF NOT EXISTS engine_runs (
+                    session_id INTEGER PRIMARY KEY,
+                    total_rounds INTEGER NOT NULL,
+                    current_round INTEGER NOT NULL,
+                    next_speaker INTEGER NOT NULL,
+                    phase TEXT NOT NULL,
+                    config TEXT NOT NULL,
+                    updated_at TEXT NOT NULL,
+                    FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE
+                );",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 11,
+            description: "scenarios",
+            sql: "CREATE TABLE IF NOT EXISTS scenarios (
+                    id INTEGER PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    definition TEXT NOT NULL,
+                    created_at TEXT NOT NULL,
+                    updated_at TEXT NOT NULL
+                );",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 12,
+            description: "audit_log",
+            sql: "CREATE TABLE IF NOT EXISTS audit_log (
+                    id INTEGER PRIMARY KEY,
+                    created_at TEXT NOT NULL,
+                    category TEXT NOT NULL,
+                    action TEXT NOT NULL,
+                    detail TEXT NOT NULL
+                );
+                CREATE INDEX IF NOT EXISTS idx_audit_log_category ON audit_log(category, id);",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 13,
+            description: "tool_grants",
+            sql: "CREATE TABLE IF NOT EXISTS tool_grants (
+                    tool_id TEXT NOT NULL,
+                    capability TEXT NOT NULL,
+                    granted_at TEXT NOT NULL,
+                    PRIMARY KEY(tool_id, capability)
+                );",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 14,
+            description: "messages_table",
+            // sessions.messages（JSON）はフロントエンド互換のため残し、トリガーで messages と同期する
+            sql: "CREATE TABLE IF NOT EXISTS messages (
+                    id INTEGER PRIMARY KEY,
+                    session_id INTEGER NOT NULL,
+                    position INTEGER NOT NULL,
+                    speaker TEXT NOT NULL,
+                    role TEXT NOT NULL,
+                    content TEXT NOT NULL,
+                    created_at TEXT NOT NULL,
+                    metadata TEXT NOT NULL DEFAULT '{}',
+                    UNIQUE(session_id, position),
+                    FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE
+                );
+                CREATE INDEX IF NOT EXISTS idx_messages_speaker ON messages(speaker);
+                CREATE TRIGGER IF NOT EXISTS trg_sessions_messages_insert AFTER INSERT ON sessions
+                BEGIN
+                  INSERT INTO messages (session_id, position, speaker, role, content, created_at, metadata)
+                  SELECT NEW.id, CAST(j.key AS INTEGER),
+                         COALESCE(json_extract(j.value, '$.speaker'), ''),
+                         CASE WHEN json_extract(j.value, '$.isUser') THEN 'user' ELSE 'ai' END,
+                         COALESCE(json_extract(j.value, '$.message'), ''),
+                         COALESCE(json_extract(j.value, '$.timestamp'), ''),
+                         json_remove(j.value, '$.speaker', '$.message', '$.isUser', '$.timestamp')
+                  FROM json_each(CASE WHEN json_valid(NEW.messages) THEN NEW.messages ELSE '[]' END) AS j
+                  WHERE j.type = 'object';
+                END;
+                CREATE TRIGGER IF NOT EXISTS trg_sessions_messages_update AFTER UPDATE OF messages ON sessions
+                BEGIN
+                  DELETE FROM messages WHERE session_id = NEW.id;
+                  INSERT INTO messages (session_id, position, speaker, role, content, created_at, metadata)
+                  SELECT NEW.id, CAST(j.key AS INTEGER),
+                         COALESCE(json_extract(j.value, '$.speaker'), ''),
+                         CASE WHEN json_extract(j.value, '$.isUser') THEN 'user' ELSE 'ai' END,
+                         COALESCE(json_extract(j.value, '$.message'), ''),
+                         COALESCE(json_extract(j.value, '$.timestamp'), ''),
+                         json_remove(j.value, '$.speaker', '$.message', '$.isUser', '$.timestamp')
+                  FROM json_each(CASE WHEN json_valid(NEW.messages) THEN NEW.messages ELSE '[]' END) AS j
+                  WHERE j.type = 'object';
+                END;
+                CREATE TRIGGER IF NOT EXISTS trg_sessions_messages_delete AFTER DELETE ON sessions
+                BEGIN
+                  DELETE FROM messages WHERE session_id = OLD.id;
+                END;
+                INSERT INTO messages (session_id, position, speaker, role, content, created_at, metadata)
+                  SELECT s.id, CAST(j.key AS INTEGER),
+                         COALESCE(json_extract(j.value, '$.speaker'), ''),
+                         CASE WHEN json_extract(j.value, '$.isUser') THEN 'user' ELSE 'ai' END,
+                         COALESCE(json_extract(j.value, '$.message'), ''),
+                         COALESCE(json_extract(j.value, '$.timestamp'), ''),
+                         json_remove(j.value, '$.speaker', '$.message', '$.isUser', '$.timestamp')
+                  FROM sessions AS s, json_each(CASE WHEN json_valid(s.messages) THEN s.messages ELSE '[]' END) AS j
+                  WHERE j.type = 'object';",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 15,
+            description: "generation_log_usage",
+            // 発言ごとのトークン数・生成時間（Ollama の応答メタデータ）
+            sql: "ALTER TABLE generation_log ADD COLUMN prompt_tokens INTEGER;
+                  ALTER TABLE generation_log ADD COLUMN completion_tokens INTEGER;
+                  ALTER TABLE generation_log ADD COLUMN duration_ms INTEGER;",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 16,
+            description: "session_search",
+            // テーマと発言本文の全文検索（日本語は単語区切りがないため trigram で部分一致させる）
+            // sessions / messages を外部コンテンツとし、トリガーで索引を同期する
+            sql: "CREATE VIRTUAL TABLE IF NOT EXISTS sessions_fts USING fts5(
+                      topic, content='sessions', content_rowid='id', tokenize='trigram'
+                  );
+                  CREATE VIRTUAL TABLE IF NOT EXISTS messages_fts USING fts5(
+                      content, content='messages', content_rowid='id', tokenize='trigram'
+                  );
+                  CREATE TRIGGER IF NOT EXISTS trg_sessions_fts_insert AFTER INSERT ON sessions
+                  BEGIN
+                    INSERT INTO sessions_fts(rowid, topic) VALUES (NEW.id, NEW.topic);
+                  END;
+                  CREATE TRIGGER IF NOT EXISTS trg_sessions_fts_update AFTER UPDATE OF topic ON sessions
+                  BEGIN
+                    INSERT INTO sessions_fts(sessions_fts, rowid, topic) VALUES ('delete', OLD.id, OLD.topic);
+                    INSERT INTO sessions_fts(rowid, topic) VALUES (NEW.id, NEW.topic);
+                  END;
+                  CREATE TRIGGER IF NOT EXISTS trg_sessions_fts_delete AFTER DELETE ON sessions
+                  BEGIN
+                    INSERT INTO sessions_fts(sessions_fts, rowid, topic) VALUES ('delete', OLD.id, OLD.topic);
+                  END;
+                  CREATE TRIGGER IF NOT EXISTS trg_messages_fts_insert AFTER INSERT ON messages
+                  BEGIN
+                    INSERT INTO messages_fts(rowid, content) VALUES (NEW.id, NEW.content);
+                  END;
+                  CREATE TRIGGER IF NOT EXISTS trg_messages_fts_update AFTER UPDATE OF content ON messages
+                  BEGIN
+                    INSERT INTO messages_fts(messages_fts, rowid, content) VALUES ('delete', OLD.id, OLD.content);
+                    INSERT INTO messages_fts(rowid, content) VALUES (NEW.id, NEW.content);
+                  END;
+                  CREATE TRIGGER IF NOT EXISTS trg_messages_fts_delete AFTER DELETE ON messages
+                  BEGIN
+                    INSERT INTO messages_fts(messages_fts, rowid, content) VALUES ('delete', OLD.id, OLD.content);
+                  END;
+                  INSERT INTO sessions_fts(sessions_fts) VALUES ('rebuild');
+                  INSERT INTO messages_fts(messages_fts) VALUES ('rebuild');",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 17,
+            description: "ai_profiles",
+            sql: "CREATE TABLE IF NOT EXISTS ai_profiles (
+                    id INTEGER PRIMARY KEY,
+                    name TEXT NOT NULL,
+                    role TEXT NOT NULL DEFAULT '',
+                    description TEXT NOT NULL DEFAULT '',
+                    tags TEXT NOT NULL DEFAULT '[]',
+                    created_at TEXT NOT NULL,
+                    updated_at TEXT NOT NULL
+                );",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 18,
+            description: "analysis_snapshots",
+            sql: "CREATE TABLE IF NOT EXISTS analysis_snapshots (
+                    id INTEGER PRIMARY KEY,
+                    session_id INTEGER NOT NULL,
+                    message_count INTEGER NOT NULL,
+                    model TEXT NOT NULL,
+                    payload TEXT NOT NULL,
+                    created_at TEXT NOT NULL,
+                    UNIQUE(session_id, message_count),
+                    FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE
+                );",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 19,
+            description: "votes",
+            sql: "CREATE TABLE IF NOT EXISTS votes (
+                    id INTEGER PRIMARY KEY,
+                    session_id INTEGER NOT NULL,
+                    question TEXT NOT NULL,
+                    options TEXT NOT NULL,
+                    payload TEXT NOT NULL,
+                    model TEXT NOT NULL,
+                    created_at TEXT NOT NULL,
+                    FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE
+                );
+                CREATE INDEX IF NOT EXISTS idx_votes_session ON votes(session_id);",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 20,
+            description: "prompt_templates",
+            sql: "CREATE TABLE IF NOT EXISTS prompt_templates (
+                    kind TEXT PRIMARY KEY,
+                    body TEXT NOT NULL,
+                    customized INTEGER NOT NULL DEFAULT 0,
+                    updated_at TEXT NOT NULL
+                );",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 21,
+            description: "stance_history",
+            // 分析スナップショットごとの参加者の立場（既存のスナップショットからも展開する）
+            sql: "CREATE TABLE IF NOT EXISTS stance_history (
+                    id INTEGER PRIMARY KEY,
+                    snapshot_id INTEGER NOT NULL,
+                    session_id INTEGER NOT NULL,
+                    message_count INTEGER NOT NULL,
+                    participant TEXT NOT NULL,
+                    label TEXT NOT NULL DEFAULT '',
+                    confidence REAL NOT NULL DEFAULT 0,
+                    stance TEXT NOT NULL DEFAULT '',
+                    UNIQUE(snapshot_id, participant),
+                    FOREIGN KEY(snapshot_id) REFERENCES analysis_snapshots(id) ON DELETE CASCADE,
+                    FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE
+                );
+                CREATE INDEX IF NOT EXISTS idx_stance_history_participant
+                    ON stance_history(session_id, participant, message_count);
+                INSERT OR IGNORE INTO stance_history (snapshot_id, session_id, message_count, participant, stance)
+                    SELECT s.id, s.session_id, s.message_count,
+                           TRIM(json_extract(j.value, '$.participant')),
+                           COALESCE(json_extract(j.value, '$.stance'), '')
+                    FROM analysis_snapshots s, json_each(s.payload, '$.participantStances') j
+                    WHERE json_valid(s.payload) AND TRIM(COALESCE(json_extract(j.value, '$.participant'), '')) <> '';",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 22,
+            description: "message_embeddings",
+            // 途中の発言を消すと後ろの位置の中身が入れ替わるため、(session_id, position) と本文のハッシュで対応付ける
+            sql: "CREATE TABLE IF NOT EXISTS message_embeddings (
+                    session_id INTEGER NOT NULL,
+                    position INTEGER NOT NULL,
+                    model TEXT NOT NULL,
+                    content_hash TEXT NOT NULL,
+                    dim INTEGER NOT NULL,
+                    vector BLOB NOT NULL,
+                    created_at TEXT NOT NULL,
+                    PRIMARY KEY(session_id, position, model),
+                    FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE
+                );
+                CREATE INDEX IF NOT EXISTS idx_message_embeddings_model ON message_embeddings(model);",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 23,
+            description: "attachments",
+            sql: "CREATE TABLE IF NOT EXISTS attachments (
+                    id INTEGER PRIMARY KEY,
+                    session_id INTEGER NOT NULL,
+                    file_name TEXT NOT NULL,
+                    kind TEXT NOT NULL,
+                    content_hash TEXT NOT NULL,
+                    chars INTEGER NOT NULL,
+                    created_at TEXT NOT NULL,
+                    FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE
+                );
+                CREATE INDEX IF NOT EXISTS idx_attachments_session ON attachments(session_id);
+                CREATE TABLE IF NOT EXISTS attachment_chunks (
+                    id INTEGER PRIMARY KEY,
+                    attachment_id INTEGER NOT NULL,
+                    session_id INTEGER NOT NULL,
+                    position INTEGER NOT NULL,
+                    content TEXT NOT NULL,
+                    vector BLOB,
+                    UNIQUE(attachment_id, position),
+                    FOREIGN KEY(attachment_id) REFERENCES attachments(id) ON DELETE CASCADE,
+                    FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE
+                );
+                CREATE INDEX IF NOT EXISTS idx_attachment_chunks_session ON attachment_chunks(session_id);",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 24,
+            description: "url_cache",
+            sql: "CREATE TABLE IF NOT EXISTS url_cache (
+                    url TEXT PRIMARY KEY,
+                    title TEXT NOT NULL,
+                    content TEXT NOT NULL,
+                    fetched_at TEXT NOT NULL
+                );",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 25,
+            description: "tags",
+            sql: "CREATE TABLE IF NOT EXISTS tags (
+                    session_id INTEGER NOT NULL,
+                    tag TEXT NOT NULL,
+                    source TEXT NOT NULL DEFAULT 'user',
+                    created_at TEXT NOT NULL,
+                    PRIMARY KEY(session_id, tag),
+                    FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE
+                );
+                CREATE INDEX IF NOT EXISTS idx_tags_tag ON tags(tag);",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 26,
+            description: "session_lineage",
+            sql: "CREATE TABLE IF NOT EXISTS session_lineage (
+                    session_id INTEGER PRIMARY KEY,
+                    parent_id INTEGER,
+                    kind TEXT NOT NULL,
+                    message_index INTEGER,
+                    created_at TEXT NOT NULL,
+                    FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE,
+                    FOREIGN KEY(parent_id) REFERENCES sessions(id) ON DELETE SET NULL
+                );
+                CREATE INDEX IF NOT EXISTS idx_session_lineage_parent ON session_lineage(parent_id);",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 27,
+            description: "session_checkpoints",
+            sql: "CREATE TABLE IF NOT EXISTS session_checkpoints (
+                    session_id INTEGER PRIMARY KEY,
+                    message_count INTEGER NOT NULL,
+                    summary_id INTEGER,
+                    analysis_id INTEGER,
+                    status TEXT NOT NULL DEFAULT 'active',
+                    updated_at TEXT NOT NULL,
+                    FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE
+                );
+                CREATE INDEX IF NOT EXISTS idx_session_checkpoints_status ON session_checkpoints(status, updated_at);",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 28,
+            description: "encryption_keys",
+            sql: "CREATE TABLE IF NOT EXISTS encryption_keys (
+                    id INTEGER PRIMARY KEY CHECK (id = 1),
+                    salt TEXT NOT NULL,
+                    iterations INTEGER NOT NULL,
+                    wrapped_key TEXT NOT NULL,
+                    created_at TEXT NOT NULL,
+                    updated_at TEXT NOT NULL
+                );",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 29,
+            description: "session_summaries",
+            // 既存の要約（session_analysis の kind='summary'）は同じ行IDのまま移す（チェックポイント・翻訳が参照するため）
+            // 画面から session_analysis に保存される要約もトリガーで同じ表に記録する
+            sql: "CREATE TABLE IF NOT EXISTS session_summaries (
+                    id INTEGER PRIMARY KEY,
+                    session_id INTEGER NOT NULL,
+                    kind TEXT NOT NULL,
+                    summary TEXT NOT NULL,
+                    covered INTEGER,
+                    model TEXT NOT NULL DEFAULT '',
+                    created_at TEXT NOT NULL,
+                    FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE
+                );
+                CREATE INDEX IF NOT EXISTS idx_session_summaries_session ON session_summaries(session_id, id);
+                INSERT OR IGNORE INTO session_summaries (id, session_id, kind, summary, covered, created_at)
+                  SELECT id, session_id,
+                         CASE WHEN json_valid(payload)
+                                   AND json_extract(payload, '$.delta') < json_extract(payload, '$.covered')
+                              THEN 'incremental' ELSE 'full' END,
+                         CASE WHEN json_valid(payload) AND json_type(payload, '$.summary') = 'text'
+                              THEN json_extract(payload, '$.summary') ELSE payload END,
+                         CASE WHEN json_valid(payload) THEN json_extract(payload, '$.covered') END,
+                         created_at
+                  FROM session_analysis WHERE kind = 'summary';
+                CREATE TRIGGER IF NOT EXISTS trg_session_analysis_summary_insert AFTER INSERT ON session_analysis
+                WHEN NEW.kind = 'summary'
+                BEGIN
+                  INSERT INTO session_summaries (session_id, kind, summary, covered, created_at)
+                  VALUES (NEW.session_id,
+                          CASE WHEN json_valid(NEW.payload)
+                                    AND json_extract(NEW.payload, '$.delta') < json_extract(NEW.payload, '$.covered')
+                               THEN 'incremental' ELSE 'full' END,
+                          CASE WHEN json_valid(NEW.payload) AND json_type(NEW.payload, '$.summary') = 'text'
+                               THEN json_extract(NEW.payload, '$.summary') ELSE NEW.payload END,
+                          CASE WHEN json_valid(NEW.payload) THEN json_extract(NEW.payload, '$.covered') END,
+                          NEW.created_at);
+                END;
+                CREATE TRIGGER IF NOT EXISTS trg_sessions_summaries_delete AFTER DELETE ON sessions
+                BEGIN
+                  DELETE FROM session_summaries WHERE session_id = OLD.id;
+                END;",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 30,
+            description: "profile_memories",
+            // profile_memory_sources はセッションごとに記憶へ反映済みの発言数（同じ発言を二度反映しない）
+            sql: "CREATE TABLE IF NOT EXISTS profile_memories (
+                    profile_id INTEGER PRIMARY KEY,
+                    memory TEXT NOT NULL,
+                    sessions INTEGER NOT NULL DEFAULT 0,
+                    updated_at TEXT NOT NULL,
+                    FOREIGN KEY(profile_id) REFERENCES ai_profiles(id) ON DELETE CASCADE
+                );
+                CREATE TABLE IF NOT EXISTS profile_memory_sources (
+                    profile_id INTEGER NOT NULL,
+                    session_id INTEGER NOT NULL,
+                    covered INTEGER NOT NULL,
+                    updated_at TEXT NOT NULL,
+                    PRIMARY KEY(profile_id, session_id),
+                    FOREIGN KEY(profile_id) REFERENCES ai_profiles(id) ON DELETE CASCADE,
+                    FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE
+                );",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 31,
+            description: "persona_states",
+            sql: "CREATE TABLE IF NOT EXISTS persona_states (
+                    session_id INTEGER NOT NULL,
+                    participant TEXT NOT NULL,
+                    agreement REAL NOT NULL,
+                    frustration REAL NOT NULL,
+                    note TEXT NOT NULL DEFAULT '',
+                    covered INTEGER NOT NULL,
+                    updated_at TEXT NOT NULL,
+                    PRIMARY KEY(session_id, participant),
+                    FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE
+                );",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 32,
+            description: "action_items",
+            sql: "CREATE TABLE IF NOT EXISTS action_items (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    session_id INTEGER NOT NULL,
+                    position INTEGER NOT NULL,
+                    description TEXT NOT NULL,
+                    owner TEXT,
+                    due_hint TEXT,
+                    model TEXT NOT NULL,
+                    created_at TEXT NOT NULL,
+                    FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE
+                );
+                CREATE INDEX IF NOT EXISTS idx_action_items_session ON action_items(session_id, position);",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 33,
+            description: "messages_incremental_sync",
+            // 更新のたびに全行を消して入れ直すと件数の2乗の手間がかかり、行IDも変わって再利用されるため、行IDを AUTOINCREMENT にする
+            // 末尾への追記（json_insert の '$[#]'。以前の配列の閉じ括弧より前がそのまま残る）は増えた分だけ追加し、
+            // それ以外の更新（編集・削除・全体の保存）は位置ごとに比べて、消えた行の削除・変わった行の書き換え・増えた行の追加だけを行う
+            sql: "DROP TRIGGER IF EXISTS trg_sessions_messages_insert;
+                  DROP TRIGGER IF EXISTS trg_sessions_messages_update;
+                  DROP TRIGGER IF EXISTS trg_sessions_messages_delete;
+                  CREATE TABLE messages_v2 (
+                    id INTEGER PRIMARY KEY AUTOINCREMENT,
+                    session_id INTEGER NOT NULL,
+                    position INTEGER NOT NULL,
+                    speaker TEXT NOT NULL,
+                    role TEXT NOT NULL,
+                    content TEXT NOT NULL,
+                    created_at TEXT NOT NULL,
+                    metadata TEXT NOT NULL DEFAULT '{}',
+                    UNIQUE(session_id, position),
+                    FOREIGN KEY(session_id) REFERENCES sessions(id) ON DELETE CASCADE
+                  );
+                  INSERT INTO messages_v2 (id, session_id, position, speaker, role, content, created_at, metadata)
+                    SELECT id, session_id, position, speaker, role, content, created_at, metadata FROM messages;
+                  DROP TABLE messages;
+                  ALTER TABLE messages_v2 RENAME TO messages;
+                  CREATE INDEX IF NOT EXISTS idx_messages_speaker ON messages(speaker);
+                  CREATE TRIGGER trg_messages_fts_insert AFTER INSERT ON messages
+                  BEGIN
+                    INSERT INTO messages_fts(rowid, content) VALUES (NEW.id, NEW.content);
+                  END;
+                  CREATE TRIGGER trg_messages_fts_update AFTER UPDATE OF content ON messages
+                  BEGIN
+                    INSERT INTO messages_fts(messages_fts, rowid, content) VALUES ('delete', OLD.id, OLD.content);
+                    INSERT INTO messages_fts(rowid, content) VALUES (NEW.id, NEW.content);
+                  END;
+                  CREATE TRIGGER trg_messages_fts_delete AFTER DELETE ON messages
+                  BEGIN
+                    INSERT INTO messages_fts(messages_fts, rowid, content) VALUES ('delete', OLD.id, OLD.content);
+                  END;
+                  INSERT INTO messages_fts(messages_fts) VALUES ('rebuild');
+                  CREATE TRIGGER trg_sessions_messages_insert AFTER INSERT ON sessions
+                  BEGIN
+                    INSERT INTO messages (session_id, position, speaker, role, content, created_at, metadata)
+                    SELECT NEW.id, CAST(j.key AS INTEGER),
+                           COALESCE(json_extract(j.value, '$.speaker'), ''),
+                           CASE WHEN json_extract(j.value, '$.isUser') THEN 'user' ELSE 'ai' END,
+                           COALESCE(json_extract(j.value, '$.message'), ''),
+                           COALESCE(json_extract(j.value, '$.timestamp'), ''),
+                           json_remove(j.value, '$.speaker', '$.message', '$.isUser', '$.timestamp')
+                    FROM json_each(CASE WHEN json_valid(NEW.messages) THEN NEW.messages ELSE '[]' END) AS j
+                    WHERE j.type = 'object';
+                  END;
+                  CREATE TRIGGER trg_sessions_messages_append AFTER UPDATE OF messages ON sessions
+                  WHEN json_valid(OLD.messages) AND json_valid(NEW.messages)
+                    AND length(NEW.messages) > length(OLD.messages)
+                    AND substr(NEW.messages, 1, length(OLD.messages) - 1)
+                      = substr(OLD.messages, 1, length(OLD.messages) - 1)
+                  BEGIN
+                    INSERT INTO messages (session_id, position, speaker, role, content, created_at, metadata)
+                    SELECT NEW.id, CAST(j.key AS INTEGER),
+                           COALESCE(json_extract(j.value, '$.speaker'), ''),
+                           CASE WHEN json_extract(j.value, '$.isUser') THEN 'user' ELSE 'ai' END,
+                           COALESCE(json_extract(j.value, '$.message'), ''),
+                           COALESCE(json_extract(j.value, '$.timestamp'), ''),
+                           json_remove(j.value, '$.speaker', '$.message', '$.isUser', '$.timestamp')
+                    FROM json_each(NEW.messages) AS j
+                    WHERE j.type = 'object' AND CAST(j.key AS INTEGER) >= json_array_length(OLD.messages);
+                  END;
+                  CREATE TRIGGER trg_sessions_messages_update AFTER UPDATE OF messages ON sessions
+                  WHEN NOT (json_valid(OLD.messages) AND json_valid(NEW.messages)
+                    AND length(NEW.messages) > length(OLD.messages)
+                    AND substr(NEW.messages, 1, length(OLD.messages) - 1)
+                      = substr(OLD.messages, 1, length(OLD.messages) - 1))
+                  BEGIN
+                    DELETE FROM messages WHERE session_id = NEW.id AND position NOT IN (
+                      SELECT CAST(j.key AS INTEGER)
+                      FROM json_each(CASE WHEN json_valid(NEW.messages) THEN NEW.messages ELSE '[]' END) AS j
+                      WHERE j.type = 'object'
+                    );
+                    UPDATE messages SET (speaker, role, content, created_at, metadata) = (
+                      COALESCE(json_extract(NEW.messages, '$[' || position || '].speaker'), ''),
+                      CASE WHEN json_extract(NEW.messages, '$[' || position || '].isUser') THEN 'user' ELSE 'ai' END,
+                      COALESCE(json_extract(NEW.messages, '$[' || position || '].message'), ''),
+                      COALESCE(json_extract(NEW.messages, '$[' || position || '].timestamp'), ''),
+                      json_remove(json_extract(NEW.messages, '$[' || position || ']'),
+                                  '$.speaker', '$.message', '$.isUser', '$.timestamp')
+                    )
+                    WHERE session_id = NEW.id AND (speaker, role, content, created_at, metadata) IS NOT (
+                      COALESCE(json_extract(NEW.messages, '$[' || position || '].speaker'), ''),
+                      CASE WHEN json_extract(NEW.messages, '$[' || position || '].isUser') THEN 'user' ELSE 'ai' END,
+                      COALESCE(json_extract(NEW.messages, '$[' || position || '].message'), ''),
+                      COALESCE(json_extract(NEW.messages, '$[' || position || '].timestamp'), ''),
+                      json_remove(json_extract(NEW.messages, '$[' || position || ']'),
+                                  '$.speaker', '$.message', '$.isUser', '$.timestamp')
+                    );
+                    INSERT INTO messages (session_id, position, speaker, role, content, created_at, metadata)
+                    SELECT NEW.id, CAST(j.key AS INTEGER),
+                           COALESCE(json_extract(j.value, '$.speaker'), ''),
+                           CASE WHEN json_extract(j.value, '$.isUser') THEN 'user' ELSE 'ai' END,
+                           COALESCE(json_extract(j.value, '$.message'), ''),
+                           COALESCE(json_extract(j.value, '$.timestamp'), ''),
+                           json_remove(j.value, '$.speaker', '$.message', '$.isUser', '$.timestamp')
+                    FROM json_each(CASE WHEN json_valid(NEW.messages) THEN NEW.messages ELSE '[]' END) AS j
+                    WHERE j.type = 'object' AND NOT EXISTS (
+                      SELECT 1 FROM messages WHERE session_id = NEW.id AND position = CAST(j.key AS INTEGER)
+                    );
+                  END;
+                  CREATE TRIGGER trg_sessions_messages_delete AFTER DELETE ON sessions
+                  BEGIN
+                    DELETE FROM messages WHERE session_id = OLD.id;
+                  END;",
+            kind: MigrationKind::Up,
+        },
+        Migration {
+            version: 34,
+            description: "summaries_without_trigger",
+            // 暗号化した session_analysis.payload は SQL から読めないため、要約の session_summaries への記録は save_analysis が行う
+            sql: "DROP TRIGGER IF EXISTS trg_session_analysis_summary_insert;",
+            kind: MigrationKind::Up,
+        },
+    ]
+}
+
+/// 内容のハッシュ（SHA-256 の16進表記）。キャッシュの鮮度判定に使う
+pub fn content_hash(text: &str) -> String {
+    use sha2::{Digest, Sha256};
+    format!("{:x}", Sha256::digest(text.as_bytes()))
+}
+
+/// フロントエンドと同じ形式の現在時刻（"YYYY-MM-DD HH:MM:SS", UTC）
+pub fn now_string() -> String {
+    chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+/// 保存する発言本文（設定に従って伏せ字にし、暗号化が有効なら暗号化する）
+pub fn storable_text(keys: &KeyStore, settings: &RuntimeSettings, text: &str) -> Result<String, String> {
+    keys.seal(&privacy::redact_for_storage(settings, text))
+}
+
+/// SQL プラグインが保持している SQLite プールを取得
+pub async fn pool(app: &AppHandle) -> Result<SqlitePool, String> {
+    let instances = app
+        .try_state::<DbInstances>()
+        .ok_or_else(|| "データベースが初期化されていません".to_string())?;
+    let url = active_url(app);
+    let map = instances.0.read().await;
+    match map.get(&url) {
+        Some(DbPool::Sqlite(pool)) => Ok(pool.clone()),
+        None => Err(format!("データベース未ロード: {}", url)),
+    }
+}
+
+/// sessions テーブルの1行をデコードしたもの
+#[derive(Debug, Clone)]
+pub struct SessionRecord {
+    pub topic: String,
+    pub participants: ParticipantsData,
+    pub messages: Vec<StoredMessage>,
+    pub model: String,
+}
+
+impl SessionRecord {
+    /// 参加者名の一覧（ユーザー参加時は「ユーザー」を先頭に含める）
+    pub fn participant_names(&self) -> Vec<String> {
+        self.participants.names()
+    }
+
+    /// プロンプト用の会話履歴テキスト（"発言者: 内容" の行形式）
+    pub fn history_text(&self) -> String {
+        format_history(&self.messages)
+    }
+}
+
+/// participants JSON を解釈（旧形式の名前配列にも対応）
+pub fn parse_participants(raw: &str) -> ParticipantsData {
+    if let Ok(data) = serde_json::from_str::<ParticipantsData>(raw) {
+        return data;
+    }
+    // 旧形式: ["ユーザー", "スミス", ...]
+    let names: Vec<String> = serde_json::from_str(raw).unwrap_or_default();
+    ParticipantsData {
+        user_participates: names.iter().any(|n| n == "ユーザー"),
+        ai_data: names
+            .into_iter()
+            .filter(|n| n != "ユーザー")
+            .map(|name| AiParticipant { name, role: String::new(), description: String::new() })
+            .collect(),
+    }
+}
+
+/// 要約の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SummaryKind {
+    /// 履歴全体から作った要約
+    Full,
+    /// 前回の要約に新しい発言を反映した要約
+    Incremental,
+}
+
+impl SummaryKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            SummaryKind::Full => "full",
+            SummaryKind::Incremental => "incremental",
+        }
+    }
+}
+
+/// 保存済み要約（session_summaries の行）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SummaryRecord {
+    pub id: i64,
+    pub kind: SummaryKind,
+    pub summary: String,
+    /// 要約が反映済みの発言数（フロントエンド保存分など不明な場合は None）
+    pub covered: Option<usize>,
+    /// 要約したモデル（不明なら空）
+    pub model: String,
+    pub created_at: String,
+}
+
+/// 最新の要約を取得（暗号化されていれば復号する）
+pub async fn latest_summary(app: &AppHandle, session_id: i64) -> Result<Option<SummaryRecord>, String> {
+    let pool = pool(app).await?;
+    let row: Option<(i64, String, String, Option<i64>, String, String)> = sqlx::query_as(
+        "SELECT id, kind, summary, covered, model, created_at FROM session_summaries WHERE session_id = ?
+         ORDER BY datetime(created_at) DESC, id DESC LIMIT 1",
+    )
+    .bind(session_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| format!("要約取得失敗: {}", e))?;
+
+    row.map(|(id, kind, summary, covered, model, created_at)| {
+        Ok(SummaryRecord {
+            id,
+            kind: if kind == "incremental" { SummaryKind::Incremental } else { SummaryKind::Full },
+            summary: encryption::keys(app).open(&summary)?,
+            covered: covered.and_then(|c| usize::try_from(c).ok()),
+            model,
+            created_at,
+        })
+    })
+    .transpose()
+}
+
+/// 生成した要約を session_summaries に保存し、行IDを返す（暗号化が有効なら暗号化する）
+pub async fn save_summary(
+    app: &AppHandle,
+    session_id: i64,
+    kind: SummaryKind,
+    summary: &str,
+    covered: usize,
+    model: &str,
+) -> Result<i64, String> {
+    let pool = pool(app).await?;
+    let result = sqlx::query(
+        "INSERT INTO session_summaries (session_id, kind, summary, covered, model, created_at)
+         VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(session_id)
+    .bind(kind.as_str())
+    .bind(encryption::keys(app).seal(summary)?)
+    .bind(covered as i64)
+    .bind(model)
+    .bind(now_string())
+    .execute(&pool)
+    .await
+    .map_err(|e| format!("要約保存失敗: {}", e))?;
+    Ok(result.last_insert_rowid())
+}
+
+/// 要約の payload（{"summary", "covered", "delta"} の JSON か要約そのもの）から session_summaries の種類・要約・反映済みの発言数を取り出す
+fn summary_from_payload(payload: &str) -> (SummaryKind, String, Option<i64>) {
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(payload) else {
+        return (SummaryKind::Full, payload.to_string(), None);
+    };
+    let covered = value.get("covered").and_then(|c| c.as_i64());
+    let kind = match (value.get("delta").and_then(|d| d.as_f64()), value.get("covered").and_then(|c| c.as_f64())) {
+        (Some(delta), Some(covered)) if delta < covered => SummaryKind::Incremental,
+        _ => SummaryKind::Full,
+    };
+    let summary = match value.get("summary").and_then(|s| s.as_str()) {
+        Some(summary) => summary.to_string(),
+        None => payload.to_string(),
+    };
+    (kind, summary, covered)
+}
+
+/// 分析結果を session_analysis に書き込み、行IDを返す（暗号化が有効なら暗号化する）
+/// 要約（kind = "summary"）は session_summaries にも記録する
+pub async fn insert_analysis(
+    conn: &mut sqlx::SqliteConnection,
+    keys: &KeyStore,
+    session_id: i64,
+    kind: &str,
+    payload: &str,
+) -> Result<i64, String> {
+    let now = now_string();
+    let id = sqlx::query("INSERT INTO session_analysis (session_id, kind, payload, created_at) VALUES (?, ?, ?, ?)")
+        .bind(session_id)
+        .bind(kind)
+        .bind(keys.seal(payload)?)
+        .bind(&now)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("分析結果保存失敗: {}", e))?
+        .last_insert_rowid();
+    if kind == "summary" {
+        let (summary_kind, summary, covered) = summary_from_payload(payload);
+        sqlx::query(
+            "INSERT INTO session_summaries (session_id, kind, summary, covered, created_at) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(session_id)
+        .bind(summary_kind.as_str())
+        .bind(keys.seal(&summary)?)
+        .bind(covered)
+        .bind(&now)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| format!("要約保存失敗: {}", e))?;
+    }
+    Ok(id)
+}
+
+/// 分析結果を session_analysis に保存し、行IDを返す
+pub async fn save_analysis(app: &AppHandle, session_id: i64, kind: &str, payload: &str) -> Result<i64, String> {
+    let pool = pool(app).await?;
+    let mut tx = pool.begin().await.map_err(|e| format!("トランザクション開始失敗: {}", e))?;
+    let id = insert_analysis(&mut tx, encryption::keys(app), session_id, kind, payload).await?;
+    tx.commit().await.map_err(|e| format!("コミット失敗: {}", e))?;
+    Ok(id)
+}
+
+/// session_analysis の行（暗号化されていれば復号する）
+#[derive(Debug, Clone, Serialize)]
+pub struct AnalysisRow {
+    pub id: i64,
+    pub session_id: i64,
+    pub kind: String,
+    pub payload: String,
+    pub created_at: String,
+}
+
+/// セッションの分析結果を新しい順に取得（kind 指定時はその種類だけ）
+pub async fn load_analysis(
+    app: &AppHandle,
+    session_id: i64,
+    kind: Option<&str>,
+    limit: i64,
+) -> Result<Vec<AnalysisRow>, String> {
+    let pool = pool(app).await?;
+    let rows: Vec<(i64, String, String, String)> = sqlx::query_as(
+        "SELECT id, kind, payload, created_at FROM session_analysis
+         WHERE session_id = ? AND (? IS NULL OR kind = ?) ORDER BY datetime(created_at) DESC LIMIT ?",
+    )
+    .bind(session_id)
+    .bind(kind)
+    .bind(kind)
+    .bind(limit)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("分析結果取得失敗: {}", e))?;
+    let keys = encryption::keys(app);
+    rows.into_iter()
+        .map(|(id, kind, payload, created_at)| {
+            Ok(AnalysisRow { id, session_id, kind, payload: keys.open(&payload)?, created_at })
+        })
+        .collect()
+}
+
+/// 新しいセッションを作成し、IDを返す
+pub async fn create_session(
+    app: &AppHandle,
+    topic: &str,
+    participants: &ParticipantsData,
+    model: &str,
+) -> Result<i64, String> {
+    let pool = pool(app).await?;
+    let participants_json =
+        serde_json::to_string(participants).map_err(|e| format!("参加者のシリアライズ失敗: {}", e))?;
+    let now = now_string();
+    let result = sqlx::query(
+        "INSERT INTO sessions (topic, participants, messages, model, created_at, updated_at) VALUES (?, ?, '[]', ?, ?, ?)",
+    )
+    .bind(topic)
+    .bind(participants_json)
+    .bind(model)
+    .bind(&now)
+    .bind(&now)
+    .execute(&pool)
+    .await
+    .map_err(|e| format!("セッション作成失敗: {}", e))?;
+    Ok(result.last_insert_rowid())
+}
+
+/// セッションを1件読み込む
+pub async fn load_session(app: &AppHandle, session_id: i64) -> Result<SessionRecord, String> {
+    let pool = pool(app).await?;
+    let row: Option<(String, String, String, String)> = sqlx::query_as(
+        "SELECT topic, participants, messages, model FROM sessions WHERE id = ?",
+    )
+    .bind(session_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| format!("セッション取得失敗: {}", e))?;
+
+    let (topic, participants, messages, model) =
+        row.ok_or_else(|| format!("セッションが見つかりません: id={}", session_id))?;
+    let mut messages: Vec<StoredMessage> = serde_json::from_str(&messages).unwrap_or_default();
+    encryption::keys(app).open_messages(&mut messages)?;
+
+    Ok(SessionRecord {
+        topic,
+        participants: parse_participants(&participants),
+        messages,
+        model,
+    })
+}
+
+/// すべてのマイグレーションを適用したメモリ上の DB（テスト用）
+#[cfg(test)]
+pub async fn migrated_pool() -> SqlitePool {
+    // 接続ごとに別の DB にならないよう、接続を1本に絞る
+    let pool = sqlx::sqlite::SqlitePoolOptions::new().max_connections(1).connect("sqlite::memory:").await.unwrap();
+    for migration in migrations() {
+        sqlx::raw_sql(migration.sql).execute(&pool).await.unwrap();
+    }
+    pool
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn message_rows(pool: &SqlitePool, session_id: i64) -> Vec<(i64, i64, String)> {
+        sqlx::query_as("SELECT id, position, content FROM messages WHERE session_id = ? ORDER BY position")
+            .bind(session_id)
+            .fetch_all(pool)
+            .await
+            .unwrap()
+    }
+
+    fn message_json(speaker: &str, text: &str) -> String {
+        serde_json::json!({ "speaker": speaker, "message": text, "isUser": false, "timestamp": "t" }).to_string()
+    }
+
+    #[tokio::test]
+    async fn messages_rows_follow_appends_and_edits_without_changing_ids() {
+        let pool = migrated_pool().await;
+        let session_id = sqlx::query(
+            "INSERT INTO sessions (topic, participants, messages, model, created_at, updated_at)
+             VALUES ('テーマ', '{}', '[]', 'gemma3:4b', '', '')",
+        )
+        .execute(&pool)
+        .await
+        .unwrap()
+        .last_insert_rowid();
+        for text in ["最初の発言", "二番目の発言", "三番目の発言"] {
+            sqlx::query("UPDATE sessions SET messages = json_insert(messages, '$[#]', json(?)) WHERE id = ?")
+                .bind(message_json("田中", text))
+                .bind(session_id)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+        let appended = message_rows(&pool, session_id).await;
+        assert_eq!(appended.iter().map(|r| r.1).collect::<Vec<_>>(), vec![0, 1, 2]);
+
+        // 2件目だけ書き換えても、どの行の ID も変わらない
+        sqlx::query("UPDATE sessions SET messages = json_set(messages, '$[1].message', '書き換えた発言') WHERE id = ?")
+            .bind(session_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let edited = message_rows(&pool, session_id).await;
+        assert_eq!(edited.iter().map(|r| r.0).collect::<Vec<_>>(), appended.iter().map(|r| r.0).collect::<Vec<_>>());
+        assert_eq!(edited[1].2, "書き換えた発言");
+        let (hits,): (i64,) =
+            sqlx::query_as("SELECT COUNT(*) FROM messages_fts WHERE messages_fts MATCH '書き換え' AND rowid = ?")
+                .bind(edited[1].0)
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(hits, 1);
+
+        // 末尾を消した後に追記しても、消えた行の ID は使い回されない
+        sqlx::query("UPDATE sessions SET messages = json_remove(messages, '$[2]') WHERE id = ?")
+            .bind(session_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("UPDATE sessions SET messages = json_insert(messages, '$[#]', json(?)) WHERE id = ?")
+            .bind(message_json("佐藤", "追加の発言"))
+            .bind(session_id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        let rows = message_rows(&pool, session_id).await;
+        assert_eq!(rows.len(), 3);
+        assert!(rows[2].0 > appended[2].0);
+        assert_eq!(rows[2].2, "追加の発言");
+    }
+
+    #[tokio::test]
+    async fn summary_analysis_is_recorded_in_session_summaries() {
+        let pool = migrated_pool().await;
+        sqlx::query(
+            "INSERT INTO sessions (id, topic, participants, messages, model, created_at, updated_at)
+             VALUES (1, 'テーマ', '{}', '[]', 'gemma3:4b', '', '')",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let keys = KeyStore::default();
+        let mut conn = pool.acquire().await.unwrap();
+        insert_analysis(&mut conn, &keys, 1, "summary", r#"{"summary":"全体の要約","covered":8}"#).await.unwrap();
+        insert_analysis(&mut conn, &keys, 1, "summary", r#"{"summary":"差分の要約","covered":12,"delta":4}"#).await.unwrap();
+        insert_analysis(&mut conn, &keys, 1, "summary", "JSON でない要約").await.unwrap();
+        // 要約以外は session_summaries に記録しない
+        insert_analysis(&mut conn, &keys, 1, "analysis", r#"{"summary":"分析"}"#).await.unwrap();
+        let rows: Vec<(String, String, Option<i64>)> =
+            sqlx::query_as("SELECT kind, summary, covered FROM session_summaries ORDER BY id")
+                .fetch_all(&mut *conn)
+                .await
+                .unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                ("full".to_string(), "全体の要約".to_string(), Some(8)),
+                ("incremental".to_string(), "差分の要約".to_string(), Some(12)),
+                ("full".to_string(), "JSON でない要約".to_string(), None),
+            ]
+        );
+    }
+}
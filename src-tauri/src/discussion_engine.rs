@@ -0,0 +1,1207 @@
+// 議論エンジン（バックエンド側の進行管理）
+// 発言の永続化を起点に、要約などの定期処理をバックエンドで判断・実行する
+// AI参加者のターン進行は dewai-core の engine::drive（dewai-cli と共通）に、保存・通知・持ち時間・
+// 一時停止・再開・停止の受け付けを TurnHooks として差し込んで行う
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Emitter, Manager};
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+// メッセージ用のタイムスタンプ（フロントエンドの toISOString() と同形式。dewai-cli の発言と共通）
+pub use dewai_core::engine::now_timestamp;
+// ラウンド進行の集計
+pub use dewai_core::engine::RoundsStats;
+use dewai_core::engine::{
+    self, Checkpoint, Facilitation, Generation, Reply, RoundEnd, TurnFailure, TurnHooks, TurnPosition,
+    MAX_TURN_ATTEMPTS,
+};
+
+use crate::{
+    analysis, analysis_worker,
+    app_state::{AppState, RuntimeSettings},
+    attachments, autosave, backend, call_ollama_generate_background, call_ollama_generate_full, config, db,
+    db::{AiParticipant, SessionRecord, StoredMessage, SummaryKind},
+    embeddings,
+    encryption::KeyStore,
+    facilitator,
+    facilitator::Intervention,
+    formats,
+    formats::DiscussionFormat,
+    generation,
+    generation::{GenerationMeta, GenerationOptions},
+    is_allowed_model, messages, next_speaker, participant_memory, persona_state,
+    persona_state::PersonaState,
+    postprocess, prompts, readability, rolling_summary,
+    round_timer::{self, RoundEndReason, RoundTimer},
+    run_state::{self, RunPhase, RunState},
+    session_settings, summarize, tags, topic_drift, ERR_UNSUPPORTED_MODEL,
+};
+
+pub const EVENT_SUMMARY_STARTED: &str = "summary://started";
+pub const EVENT_SUMMARY_UPDATED: &str = "summary://updated";
+pub const EVENT_SUMMARY_FAILED: &str = "summary://failed";
+pub const EVENT_NEW_MESSAGE: &str = "discussion://new-message";
+pub const EVENT_TURN_FAILED: &str = "discussion://turn-failed";
+
+// start_auto_discussion で指定できるラウンド数の上限
+pub const MAX_AUTO_ROUNDS: u32 = 50;
+
+pub const ERR_TOO_FEW_SPEAKERS: &str = "AI参加者が足りません";
+
+/// 自動進行への指示（pause/resume/stop_discussion から送る）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RunSignal {
+    Run,
+    Pause,
+    Stop,
+}
+
+/// エンジンの実行時状態（AppState が持つ）
+#[derive(Default)]
+pub struct EngineState {
+    /// 要約実行中のセッション（同一セッションの多重実行を防ぐ）
+    summarizing: Mutex<HashSet<i64>>,
+    /// 最後に発言が保存された時刻（アイドル判定に使う）
+    last_activity: Mutex<Option<Instant>>,
+    /// 自動進行中のセッション（同一セッションの二重進行を防ぐ）
+    running: Mutex<HashSet<i64>>,
+    /// 自動進行中のセッションへの指示チャネル
+    controls: Mutex<HashMap<i64, watch::Sender<RunSignal>>>,
+}
+
+/// AppState が持つエンジンの実行時状態
+pub fn engine_state(app: &AppHandle) -> &EngineState {
+    &app.state::<AppState>().inner().engine
+}
+
+impl EngineState {
+    /// 最後の発言保存からの経過時間（起動後まだ発言がなければ None）
+    pub fn idle_for(&self) -> Option<Duration> {
+        self.last_activity.lock().unwrap_or_else(|e| e.into_inner()).map(|t| t.elapsed())
+    }
+}
+
+/// summary://started / summary://failed のペイロード
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SummaryStatusEvent {
+    session_id: i64,
+    error: Option<String>,
+}
+
+/// summary://updated のペイロード
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SummaryUpdatedEvent {
+    pub session_id: i64,
+    pub summary: String,
+    /// 要約に反映済みの発言数
+    pub covered: usize,
+    /// フル要約なら false、差分要約なら true
+    pub incremental: bool,
+}
+
+/// discussion://new-message のペイロード（自動進行で発言が保存されるたびに通知）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct NewMessageEvent {
+    session_id: i64,
+    /// セッション内の位置（0始まり）
+    index: usize,
+    round: u32,
+    message: StoredMessage,
+}
+
+/// discussion://turn-failed のペイロード
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TurnFailedEvent {
+    session_id: i64,
+    round: u32,
+    speaker: String,
+    /// 何回目の失敗か
+    attempt: u32,
+    error: String,
+    /// やり直さずにこの参加者を飛ばしたか
+    skipped: bool,
+}
+
+/// セッションの messages JSON に1件追記し、追記後の件数を返す（messages::append_json を参照）
+pub async fn append_message(app: &AppHandle, session_id: i64, message: StoredMessage) -> Result<usize, String> {
+    let json = serde_json::to_value(&message).map_err(|e| format!("メッセージのシリアライズ失敗: {}", e))?;
+    messages::append_json(app, session_id, json).await
+}
+
+/// 発言の保存後に走らせる処理
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PersistedHook {
+    /// チェックポイントの記録（autosave）
+    Autosave,
+    /// 分析ワーカーへの通知（N件ごと・無発言時の分析）
+    AnalysisWorker,
+    Embeddings,
+    PersonaState,
+    /// 要約の自動実行（件数の条件を満たした時だけ）
+    AutoSummary,
+    /// テーマからの脱線の判定（分析と同じ件数ごと。通知と司会役の判断に使う）
+    TopicDrift,
+}
+
+/// 発言を保存するたびに走らせる処理
+const EVERY_MESSAGE_HOOKS: [PersistedHook; 4] =
+    [PersistedHook::Autosave, PersistedHook::AnalysisWorker, PersistedHook::Embeddings, PersistedHook::PersonaState];
+
+/// 発言数が total になった時に走らせる処理
+/// summary_covered は最新の要約に反映済みの発言数（要約がない・反映済み件数が不明なら None）
+fn due_hooks(total: usize, summary_covered: Option<usize>, settings: &config::AppSettings) -> Vec<PersistedHook> {
+    let mut hooks = EVERY_MESSAGE_HOOKS.to_vec();
+    // 反映済み件数が発言数を超える要約（発言を削除した後など）は、summarize_session と同じくないものとして扱う
+    let covered = summary_covered.filter(|c| *c <= total);
+    if settings.auto_summary && summary_due(total, covered.unwrap_or(0), covered.is_some(), false, settings) {
+        hooks.push(PersistedHook::AutoSummary);
+    }
+    let drift_wanted = settings.topic_drift_alerts || settings.facilitator.enabled;
+    if drift_wanted && total > 0 && total.is_multiple_of(settings.analysis_interval) {
+        hooks.push(PersistedHook::TopicDrift);
+    }
+    hooks
+}
+
+/// 発言を追記し、progress があれば実行状態も同じトランザクションで保存する。追記後の件数を返す
+/// （発言の保存後・進み具合の保存前に落ちると、再開時に同じ参加者がもう一度発言してしまうため）
+async fn append_with_progress(
+    pool: &sqlx::SqlitePool,
+    keys: &KeyStore,
+    settings: &RuntimeSettings,
+    session_id: i64,
+    message: StoredMessage,
+    progress: Option<&RunState>,
+) -> Result<usize, String> {
+    let json = serde_json::to_value(&message).map_err(|e| format!("メッセージのシリアライズ失敗: {}", e))?;
+    let mut tx = pool.begin().await.map_err(|e| format!("トランザクション開始失敗: {}", e))?;
+    let count = messages::append_json_in(&mut *tx, keys, settings, session_id, json).await?;
+    if let Some(progress) = progress {
+        run_state::save_in(&mut *tx, progress).await?;
+    }
+    tx.commit().await.map_err(|e| format!("コミット失敗: {}", e))?;
+    Ok(count)
+}
+
+/// 発言の保存先と保存後の処理（アプリでは AppHandle。テストでは記録するだけの実装に差し替える）
+trait MessageSink {
+    async fn append(&self, session_id: i64, message: StoredMessage, progress: Option<&RunState>) -> Result<usize, String>;
+    async fn settings(&self) -> Result<config::AppSettings, String>;
+    /// 最新の要約に反映済みの発言数（要約がない・不明なら None）
+    async fn summary_covered(&self, session_id: i64) -> Result<Option<usize>, String>;
+    fn touch_activity(&self);
+    fn run_hook(&self, session_id: i64, total: usize, hook: PersistedHook);
+    fn emit_new_message(&self, event: NewMessageEvent);
+}
+
+impl MessageSink for AppHandle {
+    async fn append(&self, session_id: i64, message: StoredMessage, progress: Option<&RunState>) -> Result<usize, String> {
+        let pool = db::pool(self).await?;
+        let state = self.state::<AppState>();
+        append_with_progress(&pool, &state.keys, &state.settings, session_id, message, progress).await
+    }
+
+    async fn settings(&self) -> Result<config::AppSettings, String> {
+        config::load(self).await
+    }
+
+    async fn summary_covered(&self, session_id: i64) -> Result<Option<usize>, String> {
+        Ok(db::latest_summary(self, session_id).await?.and_then(|s| s.covered))
+    }
+
+    fn touch_activity(&self) {
+        let state = engine_state(self);
+        *state.last_activity.lock().unwrap_or_else(|e| e.into_inner()) = Some(Instant::now());
+    }
+
+    fn run_hook(&self, session_id: i64, total: usize, hook: PersistedHook) {
+        match hook {
+            PersistedHook::Autosave => autosave::schedule(self, session_id),
+            PersistedHook::AnalysisWorker => analysis_worker::notify(self, session_id, total),
+            PersistedHook::Embeddings => embeddings::schedule(self, session_id),
+            PersistedHook::PersonaState => persona_state::schedule(self, session_id),
+            PersistedHook::AutoSummary => spawn_auto_summary(self, session_id, false),
+            PersistedHook::TopicDrift => topic_drift::schedule(self, session_id),
+        }
+    }
+
+    fn emit_new_message(&self, event: NewMessageEvent) {
+        let _ = self.emit(EVENT_NEW_MESSAGE, event);
+    }
+}
+
+/// 発言数が total になったセッションの保存後の処理を走らせる
+async fn notify_persisted(sink: &impl MessageSink, session_id: i64, total: usize) {
+    sink.touch_activity();
+    let hooks = match sink.settings().await {
+        Ok(settings) => {
+            let covered = if settings.auto_summary {
+                sink.summary_covered(session_id).await.unwrap_or_else(|e| {
+                    warn!("要約の反映済み件数の取得失敗 (session_id={}): {}", session_id, e);
+                    None
+                })
+            } else {
+                None
+            };
+            due_hooks(total, covered, &settings)
+        }
+        Err(e) => {
+            warn!("保存後の処理の設定読込失敗 (session_id={}): {}", session_id, e);
+            EVERY_MESSAGE_HOOKS.to_vec()
+        }
+    };
+    for hook in hooks {
+        sink.run_hook(session_id, total, hook);
+    }
+}
+
+/// 発言が永続化された後に呼ぶ。チェックポイントを記録し、分析ワーカー・埋め込みに伝え、件数の条件を満たせば要約ジョブを起動する
+pub fn on_message_persisted(app: &AppHandle, session_id: i64) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        match messages::count(&app, session_id).await {
+            Ok(total) => notify_persisted(&app, session_id, total).await,
+            Err(e) => warn!("保存後の処理の発言数取得失敗 (session_id={}): {}", session_id, e),
+        }
+    });
+}
+
+/// 自動進行の発言を保存し、保存後の処理を走らせて discussion://new-message を送る。追記後の件数を返す
+/// （司会者の介入・参加者の発言のどちらもここを通す。参加者の発言は進めた後の実行状態を progress に渡す）
+async fn persist_run_message(
+    sink: &impl MessageSink,
+    session_id: i64,
+    round: u32,
+    message: StoredMessage,
+    progress: Option<&RunState>,
+) -> Result<usize, String> {
+    let count = sink.append(session_id, message.clone(), progress).await?;
+    notify_persisted(sink, session_id, count).await;
+    sink.emit_new_message(NewMessageEvent { session_id, index: count.saturating_sub(1), round, message });
+    Ok(count)
+}
+
+/// 発言が途絶えた時に呼ぶ（分析ワーカーから）。既存の要約があれば未反映の発言まで更新する
+pub fn summarize_when_idle(app: &AppHandle, session_id: i64) {
+    spawn_auto_summary(app, session_id, true);
+}
+
+/// 要約ジョブをバックグラウンドで起動（同じセッションの要約が実行中なら何もしない）
+fn spawn_auto_summary(app: &AppHandle, session_id: i64, idle: bool) {
+    {
+        let state = engine_state(app);
+        let mut running = state.summarizing.lock().unwrap_or_else(|e| e.into_inner());
+        if !running.insert(session_id) {
+            // 実行中の要約が終わった後、次の発言保存時に改めて判定される
+            return;
+        }
+    }
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = run_auto_summary(&app, session_id, idle).await {
+            warn!("自動要約失敗 (session_id={}): {}", session_id, e);
+            let _ = app.emit(EVENT_SUMMARY_FAILED, SummaryStatusEvent { session_id, error: Some(e) });
+        }
+        engine_state(&app).summarizing.lock().unwrap_or_else(|e| e.into_inner()).remove(&session_id);
+    });
+}
+
+/// 要約が必要か判定し、必要ならフル/差分要約を実行して保存・通知する
+/// idle=true なら件数条件によらず、既存の要約に未反映の発言をすべて反映する（初回のフル要約は件数条件に従う）
+async fn run_auto_summary(app: &AppHandle, session_id: i64, idle: bool) -> Result<(), String> {
+    let settings = config::load(app).await?;
+    if !settings.auto_summary {
+        return Ok(());
+    }
+    let force = idle && db::latest_summary(app, session_id).await?.is_some();
+    summarize_session(app, session_id, force).await.map(|_| ())
+}
+
+/// 要約を実行する時期か（covered は既存の要約に反映済みの発言数。has_base なら差分要約になる）
+fn summary_due(total: usize, covered: usize, has_base: bool, force: bool, settings: &config::AppSettings) -> bool {
+    match (has_base, force) {
+        (_, true) => total > covered,
+        (true, false) => total - covered >= settings.summary_interval,
+        (false, false) => total >= settings.summary_min_initial,
+    }
+}
+
+/// 要約を生成して保存し、summary://updated を通知する
+/// force=false なら設定の件数条件を満たす場合のみ、true なら未反映の発言があれば実行する
+pub async fn summarize_session(
+    app: &AppHandle,
+    session_id: i64,
+    force: bool,
+) -> Result<Option<SummaryUpdatedEvent>, String> {
+    let settings = config::load(app).await?;
+    let session = db::load_session(app, session_id).await?;
+    if !is_allowed_model(app, &session.model) {
+        return Ok(None);
+    }
+    let total = session.messages.len();
+    let previous = db::latest_summary(app, session_id).await?;
+
+    // 反映済み件数が分かる要約があれば差分、なければ（件数が揃い次第）フル要約
+    let (base, covered) = match &previous {
+        Some(p) => match p.covered {
+            Some(c) if c <= total => (Some(p.summary.as_str()), c),
+            _ => (None, 0),
+        },
+        None => (None, 0),
+    };
+    if !summary_due(total, covered, base.is_some(), force, &settings) {
+        return Ok(None);
+    }
+
+    info!("要約開始: session_id={}, 発言数={}, 反映済み={}", session_id, total, covered);
+    let _ = app.emit(EVENT_SUMMARY_STARTED, SummaryStatusEvent { session_id, error: None });
+
+    let style = session_settings::load(app, session_id).await?.prompt_style();
+    let participants = session.participant_names();
+    let key = format!("summary:{}", session_id);
+    let options = GenerationOptions::default();
+    let summary = match base {
+        Some(prev) => {
+            let prompt = prompts::build_incremental_summary_prompt(
+                &session.topic,
+                prev,
+                &db::format_history(&session.messages[covered..]),
+                &participants,
+                &style,
+            );
+            call_ollama_generate_background(app, &key, &session.model, &prompt, &options).await?
+        }
+        // 初回のフル要約は、履歴がコンテキストに収まらなければ分割して要約する
+        None => {
+            let request = summarize::SummaryRequest {
+                session_id: Some(session_id),
+                model: &session.model,
+                discussion_topic: &session.topic,
+                participants: &participants,
+                style: &style,
+                options: &options,
+                background_key: Some(&key),
+            };
+            summarize::summarize_history(app, &request, &session.history_text()).await?.text
+        }
+    };
+
+    let kind = if base.is_some() { SummaryKind::Incremental } else { SummaryKind::Full };
+    db::save_summary(app, session_id, kind, &summary, total, &session.model).await?;
+    autosave::schedule(app, session_id);
+    if base.is_none() {
+        tags::schedule_after_first_summary(app, session_id, &session.model, &session.topic, &summary);
+    }
+
+    let event = SummaryUpdatedEvent { session_id, summary, covered: total, incremental: base.is_some() };
+    app.emit(EVENT_SUMMARY_UPDATED, event.clone()).map_err(|e| format!("イベント送信失敗: {}", e))?;
+    info!("要約完了: session_id={}", session_id);
+    Ok(Some(event))
+}
+
+/// 発言順の決め方
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TurnStrategy {
+    /// 毎ラウンド同じ順番
+    #[default]
+    RoundRobin,
+    /// ラウンドごとに先頭の話者を1人ずつずらす
+    Rotating,
+    /// 発言ごとに、未発言・名指し・対立の当事者などの手がかりから次の話者を選ぶ（1ラウンドの発言数は参加者数と同じ）
+    Suggested,
+}
+
+/// ラウンド進行の条件（中断時の再開用に engine_runs へ保存される）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RoundConfig {
+    /// 生成オプション（シード未指定なら発言ごとに採番）
+    pub options: GenerationOptions,
+    pub turn_strategy: TurnStrategy,
+    pub prompt_version: prompts::PromptVersion,
+    /// 全ラウンド終了後に要約まで行うか
+    pub summarize_at_end: bool,
+    /// 発言させるAI参加者名（空なら全員）
+    pub speakers: Vec<String>,
+    /// 形式のある議論（指定時は1ラウンド = 1フェーズで、フェーズごとの指示で発言させる）
+    pub format: Option<DiscussionFormat>,
+    /// 1ラウンドの持ち時間（秒）。使い切ると生成中の発言を打ち切って次のラウンドへ進み、要約を実行する
+    pub round_time_limit_secs: Option<u64>,
+}
+
+impl RoundConfig {
+    pub fn time_limit(&self) -> Option<Duration> {
+        self.round_time_limit_secs.map(Duration::from_secs)
+    }
+}
+
+/// 指定ラウンド数だけAI参加者に順番に発言させ、生成した発言数を返す
+/// （1ラウンド = 全AI参加者が1回ずつ発言）
+pub async fn run_rounds(app: &AppHandle, session_id: i64, rounds: u32) -> Result<usize, String> {
+    run_rounds_with(app, session_id, rounds, &RoundConfig::default()).await.map(|stats| stats.generated)
+}
+
+/// 条件を指定してラウンドを進行し、集計を返す
+pub async fn run_rounds_with(
+    app: &AppHandle,
+    session_id: i64,
+    rounds: u32,
+    config: &RoundConfig,
+) -> Result<RoundsStats, String> {
+    drive_run(app, RunState::new(session_id, rounds, config.clone())).await
+}
+
+/// 自動進行中のセッションか
+pub fn is_running(app: &AppHandle, session_id: i64) -> bool {
+    engine_state(app).running.lock().unwrap_or_else(|e| e.into_inner()).contains(&session_id)
+}
+
+/// 自動進行中のセッションが1つでもあるか
+pub fn any_running(app: &AppHandle) -> bool {
+    !engine_state(app).running.lock().unwrap_or_else(|e| e.into_inner()).is_empty()
+}
+
+/// 現在ラウンドの発言順
+pub fn speaking_order(participants: &[AiParticipant], state: &RunState) -> Vec<AiParticipant> {
+    let speakers = &state.config.speakers;
+    let mut order: Vec<AiParticipant> =
+        participants.iter().filter(|p| speakers.is_empty() || speakers.contains(&p.name)).cloned().collect();
+    if state.config.turn_strategy == TurnStrategy::Rotating && !order.is_empty() {
+        let len = order.len();
+        order.rotate_left((state.position.current_round as usize - 1) % len);
+    }
+    order
+}
+
+/// 実行状態から進行を続ける（新規開始・再開の共通処理）
+/// 発言ごとに状態を保存し、完了したら削除する。失敗時は状態を残して再開できるようにする
+pub async fn drive_run(app: &AppHandle, state: RunState) -> Result<RoundsStats, String> {
+    let session_id = state.session_id;
+    let (tx, rx) = watch::channel(RunSignal::Run);
+    {
+        let engine = engine_state(app);
+        let mut running = engine.running.lock().unwrap_or_else(|e| e.into_inner());
+        if !running.insert(session_id) {
+            return Err(format!("このセッションは自動進行中です: id={}", session_id));
+        }
+        engine.controls.lock().unwrap_or_else(|e| e.into_inner()).insert(session_id, tx);
+    }
+    let result = drive_run_inner(app, state, rx).await;
+    let engine = engine_state(app);
+    engine.controls.lock().unwrap_or_else(|e| e.into_inner()).remove(&session_id);
+    engine.running.lock().unwrap_or_else(|e| e.into_inner()).remove(&session_id);
+    result
+}
+
+/// 一時停止中なら再開・停止まで待ち、進行を続けてよければ true を返す
+async fn wait_while_paused(rx: &mut watch::Receiver<RunSignal>) -> bool {
+    match rx.wait_for(|s| *s != RunSignal::Pause).await {
+        Ok(signal) => *signal == RunSignal::Run,
+        Err(_) => false,
+    }
+}
+
+/// 自動進行のアプリ側の処理（dewai-core の drive に渡す）
+/// 発言を DB に保存して通知し、一時停止・停止の指示と持ち時間で手番を区切る
+struct AppRun<'a> {
+    app: &'a AppHandle,
+    state: RunState,
+    rx: watch::Receiver<RunSignal>,
+    timer: Option<RoundTimer>,
+    /// 手番ごとに読み直すセッション
+    session: SessionRecord,
+    /// 今の手番のプロンプトの文体（読みやすさの調整にも使う）
+    style: prompts::PromptStyle,
+}
+
+impl AppRun<'_> {
+    fn emit_round_ended(&self, reason: RoundEndReason) {
+        let position = self.state.position;
+        let limit = self.state.config.time_limit();
+        let (round, total) = (position.current_round, position.total_rounds);
+        round_timer::emit_ended(self.app, self.state.session_id, round, total, self.timer.as_ref(), limit, reason);
+    }
+}
+
+#[async_trait]
+impl TurnHooks for AppRun<'_> {
+    fn position(&mut self) -> &mut TurnPosition {
+        &mut self.state.position
+    }
+
+    async fn checkpoint(&mut self) -> Result<Checkpoint, String> {
+        let (session_id, position) = (self.state.session_id, self.state.position);
+        let limit = self.state.config.time_limit();
+        // 一時停止中は持ち時間を止め、再開後に残りから計時し直す
+        let paused = match &self.timer {
+            Some(t) if *self.rx.borrow() == RunSignal::Pause => Some(t.remaining()),
+            _ => None,
+        };
+        if paused.is_some() {
+            self.timer = None;
+        }
+        if !wait_while_paused(&mut self.rx).await {
+            return Ok(Checkpoint::Stop);
+        }
+        let (round, total) = (position.current_round, position.total_rounds);
+        if let (Some(remaining), Some(limit)) = (paused, limit) {
+            self.timer = Some(RoundTimer::start(self.app, session_id, round, total, limit, remaining));
+        }
+        if self.timer.as_ref().is_some_and(RoundTimer::expired) {
+            return Ok(Checkpoint::TimeUp);
+        }
+        // 再開時にラウンドの途中から始まった場合も、そのラウンドの持ち時間を改めて計る
+        if let Some(limit) = limit {
+            if self.timer.as_ref().map(|t| t.round) != Some(round) {
+                self.timer = Some(RoundTimer::start(self.app, session_id, round, total, limit, limit));
+            }
+        }
+        Ok(Checkpoint::Continue)
+    }
+
+    async fn speaking_order(&mut self) -> Result<Vec<AiParticipant>, String> {
+        self.session = db::load_session(self.app, self.state.session_id).await?;
+        if self.session.participants.ai_data.is_empty() {
+            return Err("AI参加者がいません".into());
+        }
+        Ok(speaking_order(&self.session.participants.ai_data, &self.state))
+    }
+
+    async fn round_started(&mut self) -> Result<(), String> {
+        let (session_id, position) = (self.state.session_id, self.state.position);
+        let (round, total) = (position.current_round, position.total_rounds);
+        info!("ラウンド {}/{} 開始: session_id={}", round, total, session_id);
+        round_timer::emit_started(self.app, session_id, round, total, self.state.config.time_limit());
+        if let Some(format) = self.state.config.format {
+            formats::enter_phase(self.app, session_id, format, round).await?;
+        }
+        Ok(())
+    }
+
+    async fn round_ended(&mut self, end: RoundEnd) -> Result<(), String> {
+        let session_id = self.state.session_id;
+        let position = self.state.position;
+        let reason = match end {
+            RoundEnd::Completed => RoundEndReason::Completed,
+            RoundEnd::TimeUp => RoundEndReason::TimeUp,
+        };
+        if end == RoundEnd::TimeUp {
+            info!("ラウンド {} の持ち時間切れ: session_id={}", position.current_round, session_id);
+        }
+        self.emit_round_ended(reason);
+        self.timer = None;
+        // 持ち時間切れなら要約を実行する（最終ラウンドで summarizeAtEnd なら終了時の要約に任せる）
+        let last = position.current_round >= position.total_rounds;
+        if end == RoundEnd::TimeUp && !(last && self.state.config.summarize_at_end) {
+            if let Err(e) = summarize_session(self.app, session_id, true).await {
+                warn!("持ち時間切れの要約に失敗 (session_id={}): {}", session_id, e);
+                let _ = self.app.emit(EVENT_SUMMARY_FAILED, SummaryStatusEvent { session_id, error: Some(e) });
+            }
+        }
+        Ok(())
+    }
+
+    async fn pick_speaker(&mut self, order: &[AiParticipant]) -> Result<Option<AiParticipant>, String> {
+        if self.state.config.turn_strategy != TurnStrategy::Suggested {
+            return Ok(None);
+        }
+        next_speaker::pick(self.app, self.state.session_id, &self.session, order).await
+    }
+
+    fn options(&self) -> GenerationOptions {
+        self.state.config.options.clone()
+    }
+
+    async fn facilitate(&mut self, options: &GenerationOptions) -> Result<Facilitation, String> {
+        let session_id = self.state.session_id;
+        let app_settings = config::load(self.app).await?;
+        let facilitation = &app_settings.facilitator;
+        if !facilitation.enabled {
+            return Ok(Facilitation::Continue);
+        }
+        let drift_threshold = app_settings.topic_drift_threshold;
+        let intervening =
+            facilitator::intervene(self.app, facilitation, drift_threshold, session_id, &self.session, options);
+        let intervention = tokio::select! {
+            intervention = intervening => intervention,
+            _ = self.rx.wait_for(|s| *s == RunSignal::Stop) => return Ok(Facilitation::Stop),
+        };
+        // 司会者の介入に失敗しても進行は続ける
+        match intervention {
+            Ok(Some(intervention)) => Ok(Facilitation::Intervened(Box::new(intervention))),
+            Ok(None) => Ok(Facilitation::Continue),
+            Err(e) => {
+                warn!("司会者の介入に失敗 (session_id={}): {}", session_id, e);
+                Ok(Facilitation::Continue)
+            }
+        }
+    }
+
+    async fn prompt(&mut self, participant: &AiParticipant, options: &GenerationOptions) -> Result<String, String> {
+        let app = self.app;
+        let session_id = self.state.session_id;
+        let session = &self.session;
+        let session_settings = session_settings::load(app, session_id).await?;
+        self.style = session_settings.prompt_style();
+        let style = &self.style;
+        // 反論役に指定された参加者は、最新の分析の合意に異議を唱える
+        let consensus = if session_settings.devils_advocate.as_deref() == Some(participant.name.as_str()) {
+            Some(analysis::latest_consensus(app, session_id).await?)
+        } else {
+            None
+        };
+        let round = self.state.position.current_round;
+        let phase = self.state.config.format.and_then(|f| Some((f, f.phase_at(round)?)));
+        let position = session.participants.ai_data.iter().position(|p| p.name == participant.name).unwrap_or(0);
+        let full_history = session.history_text();
+        let material = attachments::reference_material(app, Some(session_id), &session.topic, &full_history).await;
+        let memory = participant_memory::memory_for(app, &participant.name, &participant.role).await;
+        let persona = persona_state::state_for(app, Some(session_id), &participant.name).await;
+        let prompt_version = self.state.config.prompt_version;
+        let build_turn_prompt = |history: &str| match (&phase, &consensus) {
+            (Some((format, phase)), _) => {
+                formats::phase_prompt(*format, *phase, participant, position, history, &session.topic, style)
+            }
+            (None, Some(consensus)) => prompts::build_devils_advocate_prompt(
+                &participant.name,
+                &participant.role,
+                &participant.description,
+                history,
+                &session.topic,
+                consensus,
+                style,
+            ),
+            (None, None) => prompts::build_ai_response_prompt_versioned(
+                prompt_version,
+                &participant.name,
+                &participant.role,
+                &participant.description,
+                history,
+                &session.topic,
+                style,
+            ),
+        };
+        let build = |history: &str| {
+            let prompt = prompts::with_participant_memory(build_turn_prompt(history), memory.as_deref());
+            let prompt = prompts::with_persona_state(prompt, persona.as_ref().map(PersonaState::prompt_args));
+            prompts::with_reference_material(prompt, &material)
+        };
+        // コンテキストに収まらない場合は古い発言を要約に畳み込む
+        let history = rolling_summary::fit_history(
+            app,
+            &session.model,
+            Some(session_id),
+            &session.topic,
+            &full_history,
+            options,
+            style,
+            build,
+        )
+        .await;
+        Ok(build(&history))
+    }
+
+    async fn generate(&mut self, prompt: &str, options: &GenerationOptions) -> Generation {
+        // 停止指示・持ち時間切れがあれば生成の完了を待たずに打ち切る（一時停止は発言の区切りで反映）
+        tokio::select! {
+            generated = call_ollama_generate_full(self.app, &self.session.model, prompt, options) => {
+                Generation::Done(generated.map_err(String::from))
+            }
+            _ = self.rx.wait_for(|s| *s == RunSignal::Stop) => Generation::Stopped,
+            _ = RoundTimer::wait(self.timer.as_ref()) => Generation::TimeUp,
+        }
+    }
+
+    async fn finish_reply(&mut self, participant: &AiParticipant, text: &str) -> String {
+        let reply = postprocess::process_reply(self.app, text, &participant.name);
+        let (level, language) = (self.style.reading_level, self.style.language);
+        readability::enforce_reading_level(self.app, &self.session.model, reply, level, language).await
+    }
+
+    async fn save_intervention(&mut self, intervention: Intervention) -> Result<(), String> {
+        let (session_id, round) = (self.state.session_id, self.state.position.current_round);
+        let message = intervention.message.clone();
+        let count = persist_run_message(self.app, session_id, round, message, None).await?;
+        facilitator::notify(self.app, session_id, count.saturating_sub(1), &intervention);
+        Ok(())
+    }
+
+    async fn save_reply(&mut self, reply: Reply<'_>) -> Result<(), String> {
+        let (session_id, round) = (self.state.session_id, self.state.position.current_round);
+        let count = persist_run_message(self.app, session_id, round, reply.message, Some(&self.state)).await?;
+        generation::record(
+            self.app,
+            generation::GenerationRecord {
+                session_id,
+                message_index: count as i64 - 1,
+                speaker: &reply.participant.name,
+                model: &self.session.model,
+                prompt: reply.prompt,
+                options: reply.options,
+                output: &reply.generated.text,
+                meta: &reply.generated.meta,
+            },
+        )
+        .await;
+        Ok(())
+    }
+
+    async fn save_position(&mut self) -> Result<(), String> {
+        run_state::save(self.app, &self.state).await
+    }
+
+    fn turn_failed(&mut self, failure: &TurnFailure<'_>) {
+        let session_id = self.state.session_id;
+        warn!(
+            "発言の生成に失敗 (session_id={}, 話者={}, {}/{}回目): {}",
+            session_id, failure.speaker, failure.attempt, MAX_TURN_ATTEMPTS, failure.error
+        );
+        let _ = self.app.emit(
+            EVENT_TURN_FAILED,
+            TurnFailedEvent {
+                session_id,
+                round: failure.round,
+                speaker: failure.speaker.to_string(),
+                attempt: failure.attempt,
+                error: failure.error.to_string(),
+                skipped: failure.skipped,
+            },
+        );
+    }
+
+    async fn backoff(&mut self, attempt: u32) -> bool {
+        let backoff = backend::client(self.app).retry_policy().backoff(attempt as u8);
+        tokio::select! {
+            _ = tokio::time::sleep(backoff) => true,
+            _ = self.rx.wait_for(|s| *s == RunSignal::Stop) => false,
+        }
+    }
+}
+
+/// 話者の選択から保存・通知までの手番の進め方は dewai-core の drive に任せ、
+/// 進行の前後（実行状態の保存・停止時の通知・終了時の要約・実行状態の削除）をここで行う
+async fn drive_run_inner(
+    app: &AppHandle,
+    mut state: RunState,
+    rx: watch::Receiver<RunSignal>,
+) -> Result<RoundsStats, String> {
+    let session_id = state.session_id;
+    let mut stats = RoundsStats::default();
+    let mut timer = None;
+    run_state::save(app, &state).await?;
+
+    if state.phase == RunPhase::Speaking {
+        let session = db::load_session(app, session_id).await?;
+        let mut run = AppRun { app, state, rx, timer: None, session, style: Default::default() };
+        stats = engine::drive(&mut run).await?;
+        (state, timer) = (run.state, run.timer);
+    }
+
+    if stats.stopped {
+        info!("自動進行を停止: session_id={}", session_id);
+        let (position, limit) = (state.position, state.config.time_limit());
+        let reason = RoundEndReason::Stopped;
+        let (round, total) = (position.current_round, position.total_rounds);
+        round_timer::emit_ended(app, session_id, round, total, timer.as_ref(), limit, reason);
+    } else if state.config.summarize_at_end {
+        state.phase = RunPhase::Summarizing;
+        run_state::save(app, &state).await?;
+        summarize_session(app, session_id, true).await?;
+    }
+    run_state::clear(app, session_id).await?;
+    Ok(stats)
+}
+
+// 発言を1件保存し、保存後の件数を返す（要約の自動実行判定も行う）
+#[command]
+pub async fn append_session_message(
+    app: AppHandle,
+    session_id: i64,
+    speaker: String,
+    message: String,
+    is_user: bool,
+    seed: Option<i64>,
+    generation: Option<GenerationMeta>,
+) -> Result<usize, String> {
+    let stored = StoredMessage {
+        speaker,
+        message,
+        is_user,
+        timestamp: now_timestamp(),
+        seed,
+        generation,
+        edited_at: None,
+        regenerated_at: None,
+    };
+    let count = append_message(&app, session_id, stored).await?;
+    on_message_persisted(&app, session_id);
+    Ok(count)
+}
+
+// フロントエンドが独自に messages を保存した後の通知（要約の自動実行判定）
+#[command]
+pub async fn notify_messages_persisted(app: AppHandle, session_id: i64) -> Result<(), String> {
+    on_message_persisted(&app, session_id);
+    Ok(())
+}
+
+// セッションの最新の要約（画面を開き直した時に再生成せず表示する。なければ None）
+#[command]
+pub async fn get_latest_summary(app: AppHandle, session_id: i64) -> Result<Option<db::SummaryRecord>, String> {
+    info!("get_latest_summary 呼び出し: session_id={}", session_id);
+    db::latest_summary(&app, session_id).await
+}
+
+// AI参加者だけで議論を自動進行する（バックグラウンドで実行し、すぐに戻る）
+// 発言ごとに discussion://new-message、ラウンドごとに round://started / round://ended、終了時に run://finished を通知する
+#[command]
+pub async fn start_auto_discussion(
+    app: AppHandle,
+    session_id: i64,
+    rounds: u32,
+    participants: Option<Vec<String>>,
+    config: Option<RoundConfig>,
+) -> Result<(), String> {
+    info!("start_auto_discussion 呼び出し: session_id={}, rounds={}, participants={:?}", session_id, rounds, participants);
+    start_run(&app, session_id, rounds, participants, config.unwrap_or_default(), 1).await
+}
+
+/// 自動進行を検証して開始する（start_auto_discussion / start_formatted_discussion の共通処理）
+/// min_speakers に満たない場合は ERR_TOO_FEW_SPEAKERS を返す
+pub async fn start_run(
+    app: &AppHandle,
+    session_id: i64,
+    rounds: u32,
+    participants: Option<Vec<String>>,
+    mut config: RoundConfig,
+    min_speakers: usize,
+) -> Result<(), String> {
+    if rounds == 0 || rounds > MAX_AUTO_ROUNDS {
+        return Err(format!("ラウンド数は1〜{}で指定してください", MAX_AUTO_ROUNDS));
+    }
+    let limits = round_timer::MIN_TIME_LIMIT_SECS..=round_timer::MAX_TIME_LIMIT_SECS;
+    if config.round_time_limit_secs.is_some_and(|secs| !limits.contains(&secs)) {
+        return Err(format!("ラウンドの持ち時間は{}〜{}秒で指定してください", limits.start(), limits.end()));
+    }
+    if is_running(app, session_id) {
+        return Err(format!("このセッションは自動進行中です: id={}", session_id));
+    }
+    let session = db::load_session(app, session_id).await?;
+    if !is_allowed_model(app, &session.model) {
+        return Err(ERR_UNSUPPORTED_MODEL.to_string());
+    }
+    if let Some(names) = participants {
+        if let Some(unknown) = names.iter().find(|n| !session.participants.ai_data.iter().any(|p| &p.name == *n)) {
+            return Err(format!("セッションにいないAI参加者です: {}", unknown));
+        }
+        config.speakers = names;
+    }
+    let state = RunState::new(session_id, rounds, config);
+    let speakers = speaking_order(&session.participants.ai_data, &state).len();
+    if speakers == 0 {
+        return Err("AI参加者がいません".into());
+    }
+    if speakers < min_speakers {
+        return Err(ERR_TOO_FEW_SPEAKERS.into());
+    }
+    run_state::spawn_runs(app, vec![state]);
+    Ok(())
+}
+
+/// 自動進行中のセッションへ指示を送る（進行中でなければ false）
+fn send_signal(app: &AppHandle, session_id: i64, signal: RunSignal) -> bool {
+    let engine = engine_state(app);
+    let controls = engine.controls.lock().unwrap_or_else(|e| e.into_inner());
+    match controls.get(&session_id) {
+        Some(tx) => {
+            tx.send_if_modified(|current| {
+                // 停止後は一時停止・再開を受け付けない
+                let changed = *current != RunSignal::Stop && *current != signal;
+                if changed {
+                    *current = signal;
+                }
+                changed
+            });
+            true
+        }
+        None => false,
+    }
+}
+
+// 自動進行を一時停止（生成中の発言は保存してから止まる）
+#[command]
+pub fn pause_discussion(app: AppHandle, session_id: i64) -> bool {
+    info!("pause_discussion 呼び出し: session_id={}", session_id);
+    send_signal(&app, session_id, RunSignal::Pause)
+}
+
+// 一時停止した自動進行を再開
+#[command]
+pub fn resume_discussion(app: AppHandle, session_id: i64) -> bool {
+    info!("resume_discussion 呼び出し: session_id={}", session_id);
+    send_signal(&app, session_id, RunSignal::Run)
+}
+
+// 自動進行を停止（生成中の発言は破棄し、再開待ちにも残さない）
+#[command]
+pub fn stop_discussion(app: AppHandle, session_id: i64) -> bool {
+    info!("stop_discussion 呼び出し: session_id={}", session_id);
+    stop(&app, session_id)
+}
+
+/// 自動進行中のセッションを停止する（シミュレーションの中止など、バックエンド内から止める時に使う）
+pub fn stop(app: &AppHandle, session_id: i64) -> bool {
+    send_signal(app, session_id, RunSignal::Stop)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 保存・保存後の処理・通知を記録するだけの保存先
+    /// AutoSummary の処理が走ると、その時点の発言数まで要約したものとして扱う
+    /// AnalysisWorker への通知は分析ワーカーと同じ Schedule に記録し、
+    /// TopicDrift の処理は drift を判定結果として topic_drift::alert に通す
+    #[derive(Default)]
+    struct RecordingSink {
+        settings: config::AppSettings,
+        drift: Option<topic_drift::TopicDrift>,
+        messages: Mutex<Vec<StoredMessage>>,
+        summary_covered: Mutex<Option<usize>>,
+        analysis: Mutex<analysis_worker::Schedule>,
+        drift_alerts: Mutex<Vec<topic_drift::TopicDriftEvent>>,
+        hooks: Mutex<Vec<(i64, usize, PersistedHook)>>,
+        events: Mutex<Vec<(i64, usize, u32)>>,
+    }
+
+    impl RecordingSink {
+        fn with_settings(settings: config::AppSettings) -> Self {
+            Self { settings, ..Default::default() }
+        }
+
+        /// hook が走った時点の発言数
+        fn hook_totals(&self, hook: PersistedHook) -> Vec<usize> {
+            self.hooks.lock().unwrap().iter().filter(|(_, _, h)| *h == hook).map(|(_, total, _)| *total).collect()
+        }
+    }
+
+    impl MessageSink for RecordingSink {
+        async fn append(&self, _session_id: i64, message: StoredMessage, _progress: Option<&RunState>) -> Result<usize, String> {
+            let mut messages = self.messages.lock().unwrap();
+            messages.push(message);
+            Ok(messages.len())
+        }
+
+        async fn settings(&self) -> Result<config::AppSettings, String> {
+            Ok(self.settings.clone())
+        }
+
+        async fn summary_covered(&self, _session_id: i64) -> Result<Option<usize>, String> {
+            Ok(*self.summary_covered.lock().unwrap())
+        }
+
+        fn touch_activity(&self) {}
+
+        fn run_hook(&self, session_id: i64, total: usize, hook: PersistedHook) {
+            match hook {
+                PersistedHook::AutoSummary => *self.summary_covered.lock().unwrap() = Some(total),
+                PersistedHook::AnalysisWorker => self.analysis.lock().unwrap().record(session_id, total),
+                PersistedHook::TopicDrift => {
+                    let drift = self.drift.clone().map(|d| topic_drift::TopicDrift { message_count: total, ..d });
+                    if let Some(event) = drift.and_then(|d| topic_drift::alert(session_id, d, &self.settings)) {
+                        self.drift_alerts.lock().unwrap().push(event);
+                    }
+                }
+                _ => {}
+            }
+            self.hooks.lock().unwrap().push((session_id, total, hook));
+        }
+
+        fn emit_new_message(&self, event: NewMessageEvent) {
+            self.events.lock().unwrap().push((event.session_id, event.index, event.round));
+        }
+    }
+
+    fn ai_message(speaker: &str, text: &str) -> StoredMessage {
+        StoredMessage {
+            speaker: speaker.into(),
+            message: text.into(),
+            is_user: false,
+            timestamp: now_timestamp(),
+            seed: Some(1),
+            generation: None,
+            edited_at: None,
+            regenerated_at: None,
+        }
+    }
+
+    #[test]
+    fn auto_run_message_triggers_autosave() {
+        let sink = RecordingSink::default();
+        let count =
+            tauri::async_runtime::block_on(persist_run_message(&sink, 7, 1, ai_message("田中", "賛成です"), None)).unwrap();
+        assert_eq!(count, 1);
+        assert!(sink.hooks.lock().unwrap().contains(&(7, 1, PersistedHook::Autosave)));
+        assert_eq!(sink.events.lock().unwrap().as_slice(), &[(7, 0, 1)]);
+    }
+
+    #[test]
+    fn every_auto_run_message_runs_the_per_message_hooks() {
+        let sink = RecordingSink::default();
+        tauri::async_runtime::block_on(async {
+            persist_run_message(&sink, 3, 1, ai_message("田中", "賛成です"), None).await.unwrap();
+            persist_run_message(&sink, 3, 1, ai_message("佐藤", "反対です"), None).await.unwrap();
+        });
+        for hook in EVERY_MESSAGE_HOOKS {
+            assert_eq!(sink.hook_totals(hook), vec![1, 2], "{:?}", hook);
+        }
+        let indexes: Vec<usize> = sink.events.lock().unwrap().iter().map(|(_, index, _)| *index).collect();
+        assert_eq!(indexes, vec![0, 1]);
+    }
+
+    #[test]
+    fn auto_run_reaches_summary_cadence() {
+        let settings = config::AppSettings { summary_min_initial: 3, summary_interval: 2, ..Default::default() };
+        let sink = RecordingSink::with_settings(settings);
+        tauri::async_runtime::block_on(async {
+            for i in 0..7 {
+                persist_run_message(&sink, 5, 1, ai_message("田中", &format!("発言{}", i)), None).await.unwrap();
+            }
+        });
+        assert_eq!(sink.hook_totals(PersistedHook::AutoSummary), vec![3, 5, 7]);
+    }
+
+    #[test]
+    fn auto_summary_is_not_started_when_disabled() {
+        let settings = config::AppSettings { auto_summary: false, summary_min_initial: 1, ..Default::default() };
+        let sink = RecordingSink::with_settings(settings);
+        tauri::async_runtime::block_on(async {
+            for i in 0..3 {
+                persist_run_message(&sink, 5, 1, ai_message("田中", &format!("発言{}", i)), None).await.unwrap();
+            }
+        });
+        assert!(sink.hook_totals(PersistedHook::AutoSummary).is_empty());
+    }
+
+    #[test]
+    fn auto_run_messages_make_analysis_due_at_the_interval() {
+        let settings = config::AppSettings::default();
+        let interval = settings.analysis_interval;
+        let sink = RecordingSink::with_settings(settings);
+        tauri::async_runtime::block_on(async {
+            for i in 1..=interval {
+                assert!(sink.analysis.lock().unwrap().due(interval, None).is_empty());
+                persist_run_message(&sink, 9, 1, ai_message("田中", &format!("発言{}", i)), None).await.unwrap();
+            }
+        });
+        assert_eq!(sink.analysis.lock().unwrap().due(interval, None), vec![(9, false)]);
+    }
+
+    fn drifting(score: f32) -> topic_drift::TopicDrift {
+        topic_drift::TopicDrift { score, tangent: "昼食の話".into(), message_count: 0, model: "gemma3:4b".into() }
+    }
+
+    #[test]
+    fn auto_run_raises_drift_alerts_at_the_analysis_interval() {
+        let settings = config::AppSettings { analysis_interval: 2, topic_drift_threshold: 0.6, ..Default::default() };
+        let sink = RecordingSink { drift: Some(drifting(0.8)), ..RecordingSink::with_settings(settings) };
+        tauri::async_runtime::block_on(async {
+            for i in 0..5 {
+                persist_run_message(&sink, 4, 1, ai_message("田中", &format!("発言{}", i)), None).await.unwrap();
+            }
+        });
+        assert_eq!(sink.hook_totals(PersistedHook::TopicDrift), vec![2, 4]);
+        let alerts: Vec<(i64, usize)> =
+            sink.drift_alerts.lock().unwrap().iter().map(|e| (e.session_id, e.drift.message_count)).collect();
+        assert_eq!(alerts, vec![(4, 2), (4, 4)]);
+    }
+
+    #[test]
+    fn drift_below_threshold_or_with_alerts_off_is_not_alerted() {
+        let settings = config::AppSettings { analysis_interval: 1, topic_drift_threshold: 0.6, ..Default::default() };
+        let calm = RecordingSink { drift: Some(drifting(0.3)), ..RecordingSink::with_settings(settings.clone()) };
+        let muted = RecordingSink {
+            drift: Some(drifting(0.8)),
+            ..RecordingSink::with_settings(config::AppSettings { topic_drift_alerts: false, ..settings })
+        };
+        tauri::async_runtime::block_on(async {
+            persist_run_message(&calm, 4, 1, ai_message("田中", "発言"), None).await.unwrap();
+            persist_run_message(&muted, 4, 1, ai_message("田中", "発言"), None).await.unwrap();
+        });
+        assert_eq!(calm.hook_totals(PersistedHook::TopicDrift), vec![1]);
+        assert!(calm.drift_alerts.lock().unwrap().is_empty());
+        // 通知も司会役も無効なら判定自体を走らせない
+        assert!(muted.hook_totals(PersistedHook::TopicDrift).is_empty());
+        assert!(muted.drift_alerts.lock().unwrap().is_empty());
+    }
+
+    async fn insert_session(pool: &sqlx::SqlitePool) -> i64 {
+        sqlx::query(
+            "INSERT INTO sessions (topic, participants, messages, model, created_at, updated_at)
+             VALUES ('テーマ', '{}', '[]', 'gemma3:4b', '', '')",
+        )
+        .execute(pool)
+        .await
+        .unwrap()
+        .last_insert_rowid()
+    }
+
+    async fn saved_next_speaker(pool: &sqlx::SqlitePool, session_id: i64) -> Option<i64> {
+        sqlx::query_scalar("SELECT next_speaker FROM engine_runs WHERE session_id = ?")
+            .bind(session_id)
+            .fetch_optional(pool)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn turn_message_and_progress_are_saved_together() {
+        let pool = db::migrated_pool().await;
+        let session_id = insert_session(&pool).await;
+        let mut state = RunState::new(session_id, 2, RoundConfig::default());
+        state.position.next_speaker = 1;
+        let (keys, settings) = (KeyStore::default(), RuntimeSettings::default());
+        let count = append_with_progress(&pool, &keys, &settings, session_id, ai_message("田中", "賛成です"), Some(&state))
+            .await
+            .unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(saved_next_speaker(&pool, session_id).await, Some(1));
+    }
+
+    #[tokio::test]
+    async fn failed_progress_save_does_not_keep_the_message() {
+        let pool = db::migrated_pool().await;
+        let session_id = insert_session(&pool).await;
+        sqlx::raw_sql("CREATE TRIGGER fail_run_save BEFORE INSERT ON engine_runs BEGIN SELECT RAISE(ABORT, 'down'); END;")
+            .execute(&pool)
+            .await
+            .unwrap();
+        let state = RunState::new(session_id, 2, RoundConfig::default());
+        let (keys, settings) = (KeyStore::default(), RuntimeSettings::default());
+        let message = ai_message("田中", "賛成です");
+        let saved = append_with_progress(&pool, &keys, &settings, session_id, message, Some(&state)).await;
+        assert!(saved.is_err());
+        // 進み具合が保存できなければ発言も残さない（再開時に同じ手番をやり直しても重複しない）
+        let messages: String =
+            sqlx::query_scalar("SELECT messages FROM sessions WHERE id = ?").bind(session_id).fetch_one(&pool).await.unwrap();
+        assert_eq!(messages, "[]");
+    }
+
+    #[test]
+    fn forced_summary_needs_unsummarized_messages() {
+        let settings = config::AppSettings::default();
+        assert!(summary_due(5, 4, true, true, &settings));
+        assert!(!summary_due(4, 4, true, true, &settings));
+    }
+}
@@ -0,0 +1,299 @@
+// 発言の埋め込みベクトルと意味検索
+// 発言の保存をきっかけに Ollama の /api/embeddings でベクトル化し、message_embeddings に BLOB（f32 リトルエンディアン、正規化済み）で保存する
+// messages の行は sessions.messages の更新のたびに作り直されるため、(セッション, 位置) と本文のハッシュで発言と対応付ける
+// 検索は対象のベクトルを読み込んでメモリ上でコサイン類似度を計算する
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, OnceLock};
+
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Manager};
+use tracing::{info, warn};
+
+use crate::{app_state::AppState, backend, db, encryption};
+
+pub const DEFAULT_EMBEDDING_MODEL: &str = "nomic-embed-text";
+// 検索結果の件数の既定値と上限
+const DEFAULT_LIMIT: usize = 10;
+const MAX_LIMIT: usize = 100;
+// 埋め込む本文の最大文字数（長い発言は先頭だけを使う）
+const MAX_EMBED_CHARS: usize = 2000;
+// 検索結果に含める本文の最大文字数
+const MAX_SNIPPET_CHARS: usize = 160;
+
+/// 埋め込みの設定（アプリ設定の embeddings）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct EmbeddingSettings {
+    /// 発言の保存時にベクトル化するか
+    pub enabled: bool,
+    /// 埋め込みに使う Ollama のモデル
+    pub model: String,
+}
+
+impl Default for EmbeddingSettings {
+    fn default() -> Self {
+        Self { enabled: true, model: DEFAULT_EMBEDDING_MODEL.into() }
+    }
+}
+
+impl EmbeddingSettings {
+    pub fn sanitized(mut self) -> Self {
+        self.model = self.model.trim().to_string();
+        if self.model.is_empty() {
+            self.model = DEFAULT_EMBEDDING_MODEL.into();
+        }
+        self
+    }
+}
+
+pub fn current_settings(app: &AppHandle) -> EmbeddingSettings {
+    app.state::<AppState>().settings.embeddings.get()
+}
+
+/// ベクトル化中のセッションと、その間に発言が増えたセッション（同じセッションを並行して処理しない）
+#[derive(Default)]
+struct Indexing {
+    running: HashSet<i64>,
+    pending: HashSet<i64>,
+}
+
+fn indexing() -> &'static Mutex<Indexing> {
+    static INDEXING: OnceLock<Mutex<Indexing>> = OnceLock::new();
+    INDEXING.get_or_init(|| Mutex::new(Indexing::default()))
+}
+
+/// 1件の文をベクトル化する（正規化して返す）
+async fn embed(app: &AppHandle, model: &str, text: &str) -> Result<Vec<f32>, String> {
+    let text: String = text.chars().take(MAX_EMBED_CHARS).collect();
+    let res = backend::client(app)
+        .request(Method::POST, "/api/embeddings")
+        .json(&serde_json::json!({ "model": model, "prompt": text }))
+        .send()
+        .await
+        .map_err(|e| format!("埋め込みリクエスト失敗: {}", e))?;
+    if !res.status().is_success() {
+        let status = res.status();
+        let body = res.text().await.unwrap_or_default();
+        return Err(format!("埋め込み失敗 ({}): {}", status, body.trim()));
+    }
+    let json: serde_json::Value = res.json().await.map_err(|e| format!("JSONパース失敗: {}", e))?;
+    let vector: Vec<f32> = json["embedding"]
+        .as_array()
+        .map(|v| v.iter().filter_map(|x| x.as_f64()).map(|x| x as f32).collect())
+        .unwrap_or_default();
+    if vector.is_empty() {
+        return Err(format!("埋め込みが空です (model={})", model));
+    }
+    Ok(normalized(vector))
+}
+
+pub fn normalized(mut vector: Vec<f32>) -> Vec<f32> {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|x| *x /= norm);
+    }
+    vector
+}
+
+/// 設定のモデルで文をベクトル化する（埋め込みが無効なら None）
+pub async fn embed_text(app: &AppHandle, text: &str) -> Result<Option<Vec<f32>>, String> {
+    let settings = current_settings(app);
+    if !settings.enabled {
+        return Ok(None);
+    }
+    embed(app, &settings.model, text).await.map(Some)
+}
+
+pub fn to_blob(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|x| x.to_le_bytes()).collect()
+}
+
+pub fn from_blob(blob: &[u8]) -> Vec<f32> {
+    blob.chunks_exact(4).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect()
+}
+
+/// 正規化済みのベクトル同士のコサイン類似度（次元が違えば None）
+pub fn similarity(a: &[f32], b: &[f32]) -> Option<f32> {
+    (a.len() == b.len()).then(|| a.iter().zip(b).map(|(x, y)| x * y).sum())
+}
+
+/// セッションのまだベクトル化していない（または本文が変わった）発言をベクトル化し、件数を返す
+async fn index_session(app: &AppHandle, session_id: i64, model: &str) -> Result<usize, String> {
+    let pool = db::pool(app).await?;
+    let messages: Vec<(i64, String)> =
+        sqlx::query_as("SELECT position, content FROM messages WHERE session_id = ? ORDER BY position")
+            .bind(session_id)
+            .fetch_all(&pool)
+            .await
+            .map_err(|e| format!("発言取得失敗: {}", e))?;
+    let stored: HashMap<i64, String> = sqlx::query_as::<_, (i64, String)>(
+        "SELECT position, content_hash FROM message_embeddings WHERE session_id = ? AND model = ?",
+    )
+    .bind(session_id)
+    .bind(model)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("埋め込み取得失敗: {}", e))?
+    .into_iter()
+    .collect();
+
+    let keys = encryption::keys(app);
+    let mut embedded = 0;
+    for (position, content) in messages {
+        // 暗号化された本文は暗号文のハッシュで変更を判定し、復号した本文をベクトル化する
+        let hash = db::content_hash(&content);
+        let text = keys.open(&content)?;
+        if text.trim().is_empty() || stored.get(&position) == Some(&hash) {
+            continue;
+        }
+        let vector = embed(app, model, &text).await?;
+        sqlx::query(
+            "INSERT INTO message_embeddings (session_id, position, model, content_hash, dim, vector, created_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(session_id, position, model) DO UPDATE SET
+               content_hash = excluded.content_hash, dim = excluded.dim,
+               vector = excluded.vector, created_at = excluded.created_at",
+        )
+        .bind(session_id)
+        .bind(position)
+        .bind(model)
+        .bind(hash)
+        .bind(vector.len() as i64)
+        .bind(to_blob(&vector))
+        .bind(db::now_string())
+        .execute(&pool)
+        .await
+        .map_err(|e| format!("埋め込み保存失敗: {}", e))?;
+        embedded += 1;
+    }
+    Ok(embedded)
+}
+
+/// 発言の保存後に呼ぶ。設定が有効ならセッションのベクトル化をバックグラウンドで行う
+pub fn schedule(app: &AppHandle, session_id: i64) {
+    let settings = current_settings(app);
+    if !settings.enabled {
+        return;
+    }
+    {
+        let mut state = indexing().lock().unwrap_or_else(|e| e.into_inner());
+        if !state.running.insert(session_id) {
+            state.pending.insert(session_id);
+            return;
+        }
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            match index_session(&app, session_id, &settings.model).await {
+                Ok(0) => {}
+                Ok(n) => info!("発言をベクトル化: session_id={}, {}件", session_id, n),
+                Err(e) => warn!("発言のベクトル化に失敗 (session_id={}): {}", session_id, e),
+            }
+            let mut state = indexing().lock().unwrap_or_else(|e| e.into_inner());
+            if !state.pending.remove(&session_id) {
+                state.running.remove(&session_id);
+                break;
+            }
+        }
+    });
+}
+
+/// 検索対象の行（セッション, 位置, 話者, 本文, 本文のハッシュ, ベクトル, テーマ）
+type EmbeddingRow = (i64, i64, String, String, String, Vec<u8>, String);
+
+/// 意味検索の結果1件
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SemanticHit {
+    pub session_id: i64,
+    pub topic: String,
+    /// セッション内の位置（0始まり）
+    pub position: i64,
+    pub speaker: String,
+    pub snippet: String,
+    /// コサイン類似度（-1〜1、大きいほど近い）
+    pub score: f32,
+}
+
+fn snippet(text: &str) -> String {
+    let text = text.trim();
+    if text.chars().count() <= MAX_SNIPPET_CHARS {
+        return text.to_string();
+    }
+    let mut cut: String = text.chars().take(MAX_SNIPPET_CHARS - 1).collect();
+    cut.push('…');
+    cut
+}
+
+// 意味の近い発言を検索（session_id 未指定なら全セッション）。ベクトル化済みの発言だけが対象
+#[command]
+pub async fn semantic_search(
+    app: AppHandle,
+    query: String,
+    session_id: Option<i64>,
+    limit: Option<usize>,
+) -> Result<Vec<SemanticHit>, String> {
+    let query = query.trim();
+    info!("semantic_search 呼び出し: session_id={:?}, {}文字", session_id, query.chars().count());
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+    let model = current_settings(&app).model;
+    let wanted = embed(&app, &model, query).await?;
+    let pool = db::pool(&app).await?;
+    let rows: Vec<EmbeddingRow> = sqlx::query_as(
+        "SELECT e.session_id, e.position, m.speaker, m.content, e.content_hash, e.vector, s.topic
+         FROM message_embeddings e
+         JOIN messages m ON m.session_id = e.session_id AND m.position = e.position
+         JOIN sessions s ON s.id = e.session_id
+         WHERE e.model = ? AND (? IS NULL OR e.session_id = ?)",
+    )
+    .bind(&model)
+    .bind(session_id)
+    .bind(session_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("埋め込み取得失敗: {}", e))?;
+
+    let keys = encryption::keys(&app);
+    let mut hits: Vec<SemanticHit> = rows
+        .into_iter()
+        // 本文が変わった発言の古いベクトルは使わない（次の保存時に作り直される）
+        .filter(|(_, _, _, content, hash, _, _)| db::content_hash(content) == *hash)
+        .filter_map(|(session_id, position, speaker, content, _, vector, topic)| {
+            let score = similarity(&wanted, &from_blob(&vector))?;
+            let text = keys.open(&content).ok()?;
+            Some(SemanticHit { session_id, topic, position, speaker, snippet: snippet(&text), score })
+        })
+        .collect();
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+    hits.truncate(limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT));
+    Ok(hits)
+}
+
+// 保存済みの発言をまとめてベクトル化（session_id 未指定なら全セッション）。ベクトル化した件数を返す
+#[command]
+pub async fn index_embeddings(app: AppHandle, session_id: Option<i64>) -> Result<usize, String> {
+    info!("index_embeddings 呼び出し: session_id={:?}", session_id);
+    let model = current_settings(&app).model;
+    let session_ids: Vec<i64> = match session_id {
+        Some(id) => vec![id],
+        None => {
+            let pool = db::pool(&app).await?;
+            sqlx::query_as::<_, (i64,)>("SELECT id FROM sessions ORDER BY id")
+                .fetch_all(&pool)
+                .await
+                .map_err(|e| format!("セッション一覧取得失敗: {}", e))?
+                .into_iter()
+                .map(|(id,)| id)
+                .collect()
+        }
+    };
+    let mut total = 0;
+    for id in session_ids {
+        total += index_session(&app, id, &model).await?;
+    }
+    Ok(total)
+}
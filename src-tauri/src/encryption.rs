@@ -0,0 +1,691 @@
+// 発言本文の暗号化（任意）
+// 有効にすると sessions.messages の各発言の本文と、議論から作る内容（要約・分析結果・立場の推移・参加者の状態・
+// アクションアイテム・投票・注釈・添付資料と取得したページの本文・参加者の記憶・翻訳のキャッシュ・生成ログ）を
+// AES-256-GCM で暗号化して保存する（対象の列は TEXT_COLUMNS）
+// 本文は乱数で作ったデータ鍵で暗号化し、データ鍵はパスフレーズから PBKDF2 で導いた鍵で包んで encryption_keys に置く
+// （パスフレーズを変えても本文は暗号化し直さない）。データ鍵は unlock_db でメモリに展開し、lock_db か終了まで保持する
+// 本文の nonce はデータ鍵と本文の HMAC から決めるため、同じ本文は同じ暗号文になる（保存し直しても変わっていない発言の行は書き換わらない）
+// 暗号化した本文は "enc:v1:" に続く base64 で表し、接頭辞のない本文は平文として扱うため、暗号化前のデータが混在してもよい
+// 平文のまま保存する本文が "enc:v1:" か "enc:raw:" で始まる場合は "enc:raw:" を付けて暗号文と区別する
+// テーマ・参加者名・添付ファイル名・URL・埋め込みベクトル（発言と添付資料の意味検索用）は暗号化せず、
+// 暗号化した本文は全文検索の対象にならない
+// remember を指定するとパスフレーズを OS のキーチェーンにワークスペースごとに保存し、起動時・切り替え時に自動で解除する
+// 展開したデータ鍵は AppState の KeyStore が持つ
+use std::num::NonZeroU32;
+use std::sync::RwLock;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::{hmac, pbkdf2};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::Serialize;
+use serde_json::json;
+use tauri::{command, AppHandle, Manager, State};
+use tracing::{info, warn};
+
+use crate::{app_state::AppState, audit, db, db::StoredMessage, discussion_engine};
+
+const PREFIX: &str = "enc:v1:";
+// 暗号文の接頭辞で始まる平文に付ける印
+const ESCAPE: &str = "enc:raw:";
+const KEY_LEN: usize = 32;
+const SALT_LEN: usize = 16;
+const PBKDF2_ITERATIONS: u32 = 210_000;
+const MIN_PASSPHRASE_CHARS: usize = 8;
+const ERR_LOCKED: &str = "暗号化されたデータベースがロックされています。パスフレーズで解除してください";
+const KEYCHAIN_SERVICE: &str = "DewAI";
+// 暗号化しても平文のまま残る項目（画面で案内するため EncryptionStatus で返す）
+const PLAINTEXT_FIELDS: [&str; 5] = ["テーマ", "参加者名", "添付ファイル名", "URL", "埋め込みベクトル"];
+
+/// 暗号化の状態（有効か、データ鍵を展開済みか、パスフレーズをキーチェーンに保存済みか）
+#[derive(Default)]
+struct KeyState {
+    enabled: bool,
+    key: Option<[u8; KEY_LEN]>,
+    remembered: bool,
+}
+
+/// 暗号化の状態とデータ鍵（AppState が持つ）
+#[derive(Default)]
+pub struct KeyStore {
+    state: RwLock<KeyState>,
+}
+
+/// アプリの KeyStore
+pub fn keys(app: &AppHandle) -> &KeyStore {
+    &app.state::<AppState>().inner().keys
+}
+
+/// get_encryption_status などの戻り値
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptionStatus {
+    pub enabled: bool,
+    pub unlocked: bool,
+    pub remembered: bool,
+    /// 暗号化の対象外で平文のまま保存される項目
+    pub plaintext: [&'static str; 5],
+}
+
+fn aead_key(key: &[u8; KEY_LEN]) -> LessSafeKey {
+    LessSafeKey::new(UnboundKey::new(&AES_256_GCM, key).expect("AES-256 の鍵長は32バイト"))
+}
+
+fn random_bytes<const N: usize>() -> Result<[u8; N], String> {
+    let mut bytes = [0u8; N];
+    SystemRandom::new().fill(&mut bytes).map_err(|_| "乱数の生成に失敗しました".to_string())?;
+    Ok(bytes)
+}
+
+/// 本文から決まる nonce（データ鍵から導いた HMAC 鍵で本文を署名した先頭12バイト）
+fn synthetic_nonce(key: &[u8; KEY_LEN], plain: &[u8]) -> [u8; NONCE_LEN] {
+    let nonce_key = hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, key), b"dewai-message-nonce");
+    let tag = hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, nonce_key.as_ref()), plain);
+    let mut nonce = [0u8; NONCE_LEN];
+    nonce.copy_from_slice(&tag.as_ref()[..NONCE_LEN]);
+    nonce
+}
+
+/// nonce と暗号文（タグ付き）を連結したバイト列にする
+fn encrypt_bytes(key: &[u8; KEY_LEN], nonce: [u8; NONCE_LEN], plain: &[u8]) -> Result<Vec<u8>, String> {
+    let mut buffer = plain.to_vec();
+    aead_key(key)
+        .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut buffer)
+        .map_err(|_| "暗号化に失敗しました".to_string())?;
+    let mut out = nonce.to_vec();
+    out.extend(buffer);
+    Ok(out)
+}
+
+/// encrypt_bytes の逆（鍵が違う・改ざんされていれば None）
+fn decrypt_bytes(key: &[u8; KEY_LEN], sealed: &[u8]) -> Option<Vec<u8>> {
+    if sealed.len() < NONCE_LEN {
+        return None;
+    }
+    let (nonce, body) = sealed.split_at(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce).ok()?;
+    let mut buffer = body.to_vec();
+    let plain = aead_key(key).open_in_place(nonce, Aad::empty(), &mut buffer).ok()?;
+    Some(plain.to_vec())
+}
+
+fn encrypt_with(key: &[u8; KEY_LEN], text: &str) -> Result<String, String> {
+    let nonce = synthetic_nonce(key, text.as_bytes());
+    Ok(format!("{}{}", PREFIX, STANDARD.encode(encrypt_bytes(key, nonce, text.as_bytes())?)))
+}
+
+fn decrypt_with(key: &[u8; KEY_LEN], text: &str) -> Result<String, String> {
+    let Some(encoded) = text.strip_prefix(PREFIX) else {
+        return Ok(text.to_string());
+    };
+    let sealed = STANDARD.decode(encoded).map_err(|e| format!("暗号文の形式が不正です: {}", e))?;
+    let plain = decrypt_bytes(key, &sealed).ok_or_else(|| "暗号文を復号できません（鍵が違うか、データが壊れています）".to_string())?;
+    String::from_utf8(plain).map_err(|e| format!("復号した本文が不正です: {}", e))
+}
+
+/// 保存する形にする（key がなければ平文のまま。暗号文と紛らわしい平文には印を付ける）
+fn seal_with(key: Option<&[u8; KEY_LEN]>, text: &str) -> Result<String, String> {
+    match key {
+        None if text.starts_with(PREFIX) || text.starts_with(ESCAPE) => Ok(format!("{}{}", ESCAPE, text)),
+        None => Ok(text.to_string()),
+        // この鍵で復号できるものは暗号化済み（"enc:v1:" で始まるだけの入力は復号できないので暗号化する）
+        Some(key) if text.starts_with(PREFIX) && decrypt_with(key, text).is_ok() => Ok(text.to_string()),
+        Some(key) => encrypt_with(key, text),
+    }
+}
+
+/// seal_with の逆（暗号文なのに key がなければロック中のエラー）
+fn open_with(key: Option<&[u8; KEY_LEN]>, text: &str) -> Result<String, String> {
+    if let Some(plain) = text.strip_prefix(ESCAPE) {
+        return Ok(plain.to_string());
+    }
+    if !text.starts_with(PREFIX) {
+        return Ok(text.to_string());
+    }
+    decrypt_with(key.ok_or_else(|| ERR_LOCKED.to_string())?, text)
+}
+
+impl KeyStore {
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, KeyState> {
+        self.state.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn set_state(&self, enabled: bool, key: Option<[u8; KEY_LEN]>) {
+        let mut state = self.state.write().unwrap_or_else(|e| e.into_inner());
+        state.enabled = enabled;
+        state.key = key;
+    }
+
+    fn set_remembered(&self, remembered: bool) {
+        self.state.write().unwrap_or_else(|e| e.into_inner()).remembered = remembered;
+    }
+
+    pub fn status(&self) -> EncryptionStatus {
+        let state = self.read();
+        EncryptionStatus {
+            enabled: state.enabled,
+            unlocked: state.key.is_some(),
+            remembered: state.remembered,
+            plaintext: PLAINTEXT_FIELDS,
+        }
+    }
+
+    /// 保存前の本文を暗号化する（暗号化が無効なら平文のまま、ロック中ならエラー）
+    pub fn seal(&self, text: &str) -> Result<String, String> {
+        let state = self.read();
+        if !state.enabled {
+            return seal_with(None, text);
+        }
+        let key = state.key.as_ref().ok_or_else(|| ERR_LOCKED.to_string())?;
+        seal_with(Some(key), text)
+    }
+
+    /// 読み込んだ本文を復号する（平文ならそのまま、ロック中ならエラー）
+    pub fn open(&self, text: &str) -> Result<String, String> {
+        if !text.starts_with(PREFIX) && !text.starts_with(ESCAPE) {
+            return Ok(text.to_string());
+        }
+        let state = self.read();
+        open_with(state.key.as_ref(), text)
+    }
+
+    /// 省略できる項目用の seal
+    pub fn seal_optional(&self, text: Option<&str>) -> Result<Option<String>, String> {
+        text.map(|text| self.seal(text)).transpose()
+    }
+
+    /// 省略できる項目用の open
+    pub fn open_optional(&self, text: Option<&str>) -> Result<Option<String>, String> {
+        text.map(|text| self.open(text)).transpose()
+    }
+
+    pub fn seal_messages(&self, messages: &mut [StoredMessage]) -> Result<(), String> {
+        for message in messages {
+            message.message = self.seal(&message.message)?;
+        }
+        Ok(())
+    }
+
+    pub fn open_messages(&self, messages: &mut [StoredMessage]) -> Result<(), String> {
+        for message in messages {
+            message.message = self.open(&message.message)?;
+        }
+        Ok(())
+    }
+}
+
+/// messages JSON の各要素の message を書き換える（フロントエンドが保存する他の項目はそのまま残す）
+fn map_messages_json(messages: &str, f: impl Fn(&str) -> Result<String, String>) -> Result<String, String> {
+    let mut items: Vec<serde_json::Value> = match serde_json::from_str(messages) {
+        Ok(items) => items,
+        // 配列でない（壊れた）JSON は触らない
+        Err(_) => return Ok(messages.to_string()),
+    };
+    for item in &mut items {
+        if let Some(text) = item.get("message").and_then(|m| m.as_str()) {
+            item["message"] = serde_json::Value::String(f(text)?);
+        }
+    }
+    serde_json::to_string(&items).map_err(|e| format!("メッセージのシリアライズ失敗: {}", e))
+}
+
+/// パスフレーズからデータ鍵を包む鍵を導く
+fn derive_wrapping_key(passphrase: &str, salt: &[u8], iterations: u32) -> [u8; KEY_LEN] {
+    let mut key = [0u8; KEY_LEN];
+    let iterations = NonZeroU32::new(iterations.max(1)).expect("1以上");
+    pbkdf2::derive(pbkdf2::PBKDF2_HMAC_SHA256, iterations, salt, passphrase.as_bytes(), &mut key);
+    key
+}
+
+/// encryption_keys の行（ソルト, 反復回数, 包んだデータ鍵）
+type KeyRow = (String, i64, String);
+
+async fn load_key_row(pool: &sqlx::SqlitePool) -> Result<Option<KeyRow>, String> {
+    sqlx::query_as("SELECT salt, iterations, wrapped_key FROM encryption_keys WHERE id = 1")
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("暗号鍵の取得失敗: {}", e))
+}
+
+/// パスフレーズでデータ鍵を取り出す
+fn unwrap_key(row: &KeyRow, passphrase: &str) -> Result<[u8; KEY_LEN], String> {
+    let (salt, iterations, wrapped) = row;
+    let salt = STANDARD.decode(salt).map_err(|e| format!("暗号鍵の形式が不正です: {}", e))?;
+    let wrapped = STANDARD.decode(wrapped).map_err(|e| format!("暗号鍵の形式が不正です: {}", e))?;
+    let wrapping_key = derive_wrapping_key(passphrase, &salt, *iterations as u32);
+    let key = decrypt_bytes(&wrapping_key, &wrapped).ok_or_else(|| "パスフレーズが違います".to_string())?;
+    key.try_into().map_err(|_| "暗号鍵の形式が不正です".to_string())
+}
+
+/// データ鍵を新しいパスフレーズで包む（ソルト, 反復回数, 包んだデータ鍵）
+fn wrap_key(key: &[u8; KEY_LEN], passphrase: &str) -> Result<KeyRow, String> {
+    let salt = random_bytes::<SALT_LEN>()?;
+    let wrapping_key = derive_wrapping_key(passphrase, &salt, PBKDF2_ITERATIONS);
+    let wrapped = encrypt_bytes(&wrapping_key, random_bytes::<NONCE_LEN>()?, key)?;
+    Ok((STANDARD.encode(salt), PBKDF2_ITERATIONS as i64, STANDARD.encode(wrapped)))
+}
+
+/// 選択中のワークスペースのパスフレーズを置くキーチェーンの項目
+fn keychain_entry(app: &AppHandle) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(KEYCHAIN_SERVICE, &format!("db-passphrase:{}", db::active_url(app)))
+        .map_err(|e| format!("キーチェーンを開けません: {}", e))
+}
+
+/// キーチェーンの操作はブロックするため別スレッドで行う
+async fn with_keychain<T: Send + 'static>(
+    app: &AppHandle,
+    f: impl FnOnce(keyring::Entry) -> keyring::Result<T> + Send + 'static,
+) -> Result<T, String> {
+    let entry = keychain_entry(app)?;
+    tauri::async_runtime::spawn_blocking(move || f(entry))
+        .await
+        .map_err(|_| "キーチェーンの操作に失敗しました".to_string())?
+        .map_err(|e| format!("キーチェーンの操作に失敗しました: {}", e))
+}
+
+async fn keychain_load(app: &AppHandle) -> Result<Option<String>, String> {
+    with_keychain(app, |entry| match entry.get_password() {
+        Ok(passphrase) => Ok(Some(passphrase)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(e),
+    })
+    .await
+}
+
+async fn keychain_store(app: &AppHandle, passphrase: String) -> Result<(), String> {
+    with_keychain(app, move |entry| entry.set_password(&passphrase)).await
+}
+
+async fn keychain_forget(app: &AppHandle) -> Result<(), String> {
+    with_keychain(app, |entry| match entry.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(e),
+    })
+    .await
+}
+
+/// remember の指定に従ってキーチェーンのパスフレーズを保存・削除する（None なら保存済みのときだけ差し替える）
+async fn apply_remember(
+    app: &AppHandle,
+    keys: &KeyStore,
+    passphrase: &str,
+    remember: Option<bool>,
+) -> Result<(), String> {
+    match remember.unwrap_or(keys.status().remembered) {
+        true => keychain_store(app, passphrase.to_string()).await?,
+        false if remember.is_some() => keychain_forget(app).await?,
+        false => return Ok(()),
+    }
+    keys.set_remembered(remember.unwrap_or(true));
+    Ok(())
+}
+
+/// 起動時とワークスペース切り替え時に呼ぶ。暗号化の有無を読み込み、ロックした状態にする
+/// キーチェーンにパスフレーズがあればそれで解除する
+pub async fn reload(app: &AppHandle) {
+    let result = async { load_key_row(&db::pool(app).await?).await }.await;
+    let row = match result {
+        Ok(row) => row,
+        Err(e) => {
+            warn!("暗号化設定の読み込みに失敗: {}", e);
+            return;
+        }
+    };
+    let keys = keys(app);
+    keys.set_state(row.is_some(), None);
+    keys.set_remembered(false);
+    let Some(row) = row else {
+        return;
+    };
+    let passphrase = match keychain_load(app).await {
+        Ok(passphrase) => passphrase,
+        Err(e) => {
+            warn!("キーチェーンの読み込みに失敗: {}", e);
+            None
+        }
+    };
+    let Some(passphrase) = passphrase else {
+        info!("発言本文の暗号化: 有効（ロック中）");
+        return;
+    };
+    keys.set_remembered(true);
+    match unwrap_key(&row, &passphrase) {
+        Ok(key) => {
+            keys.set_state(true, Some(key));
+            info!("発言本文の暗号化: 有効（キーチェーンのパスフレーズで解除）");
+        }
+        Err(e) => warn!("キーチェーンのパスフレーズで解除できません（ロック中）: {}", e),
+    }
+}
+
+/// 本文・議論から作る内容を保存する列（sessions.messages の JSON 以外。行は rowid で特定する）
+const TEXT_COLUMNS: [(&str, &str); 20] = [
+    ("session_summaries", "summary"),
+    ("session_analysis", "payload"),
+    ("analysis_snapshots", "payload"),
+    ("stance_history", "label"),
+    ("stance_history", "stance"),
+    ("persona_states", "note"),
+    ("action_items", "description"),
+    ("action_items", "owner"),
+    ("action_items", "due_hint"),
+    ("votes", "question"),
+    ("votes", "options"),
+    ("votes", "payload"),
+    ("annotations", "content"),
+    ("attachment_chunks", "content"),
+    ("url_cache", "title"),
+    ("url_cache", "content"),
+    ("profile_memories", "memory"),
+    ("translations", "text"),
+    ("generation_log", "prompt"),
+    ("generation_log", "output"),
+];
+
+/// 有効化時の変換（暗号化前の本文はすべて平文）
+fn enable_text(key: &[u8; KEY_LEN], text: &str) -> Result<String, String> {
+    seal_with(Some(key), text.strip_prefix(ESCAPE).unwrap_or(text))
+}
+
+/// 無効化時の変換
+fn disable_text(key: &[u8; KEY_LEN], text: &str) -> Result<String, String> {
+    seal_with(None, &open_with(Some(key), text)?)
+}
+
+/// 発言本文と TEXT_COLUMNS の全行を変換し、書き換えたセッション数を返す（暗号化の有効化・無効化時の移行）
+async fn convert_all(
+    tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>,
+    f: impl Fn(&str) -> Result<String, String>,
+) -> Result<usize, String> {
+    let sessions: Vec<(i64, String)> = sqlx::query_as("SELECT id, messages FROM sessions")
+        .fetch_all(&mut **tx)
+        .await
+        .map_err(|e| format!("セッション取得失敗: {}", e))?;
+    let mut converted = 0;
+    for (id, messages) in sessions {
+        let updated = map_messages_json(&messages, &f)?;
+        if updated == messages {
+            continue;
+        }
+        sqlx::query("UPDATE sessions SET messages = ? WHERE id = ?")
+            .bind(updated)
+            .bind(id)
+            .execute(&mut **tx)
+            .await
+            .map_err(|e| format!("セッション更新失敗: {}", e))?;
+        converted += 1;
+    }
+    for (table, column) in TEXT_COLUMNS {
+        let rows: Vec<(i64, String)> =
+            sqlx::query_as(&format!("SELECT rowid, {0} FROM {1} WHERE {0} IS NOT NULL", column, table))
+            .fetch_all(&mut **tx)
+            .await
+            .map_err(|e| format!("{}の取得失敗: {}", table, e))?;
+        for (id, text) in rows {
+            let updated = f(&text)?;
+            if updated == text {
+                continue;
+            }
+            sqlx::query(&format!("UPDATE {} SET {} = ? WHERE rowid = ?", table, column))
+                .bind(updated)
+                .bind(id)
+                .execute(&mut **tx)
+                .await
+                .map_err(|e| format!("{}の更新失敗: {}", table, e))?;
+        }
+    }
+    Ok(converted)
+}
+
+// 暗号化の状態を返す
+#[command]
+pub fn get_encryption_status(state: State<'_, AppState>) -> EncryptionStatus {
+    state.keys.status()
+}
+
+// パスフレーズでデータ鍵を展開し、暗号化された本文を読み書きできるようにする
+// remember が true ならパスフレーズをキーチェーンに保存し、false なら保存済みのものを削除する
+// （キーチェーンに失敗しても解除はそのまま。結果は status の remembered で分かる）
+#[command]
+pub async fn unlock_db(app: AppHandle, passphrase: String, remember: Option<bool>) -> Result<EncryptionStatus, String> {
+    info!("unlock_db 呼び出し: remember={:?}", remember);
+    let pool = db::pool(&app).await?;
+    let row = load_key_row(&pool).await?.ok_or_else(|| "暗号化は有効になっていません".to_string())?;
+    let key = unwrap_key(&row, &passphrase)?;
+    let keys = keys(&app);
+    keys.set_state(true, Some(key));
+    audit::record("encryption", "unlock", json!({}));
+    if remember.is_some() {
+        if let Err(e) = apply_remember(&app, keys, &passphrase, remember).await {
+            warn!("キーチェーンの更新に失敗: {}", e);
+        }
+    }
+    Ok(keys.status())
+}
+
+// データ鍵をメモリから消し、暗号化された本文を読めない状態に戻す（キーチェーンの保存はそのまま）
+#[command]
+pub fn lock_db(state: State<'_, AppState>) -> EncryptionStatus {
+    info!("lock_db 呼び出し");
+    let enabled = state.keys.status().enabled;
+    state.keys.set_state(enabled, None);
+    state.keys.status()
+}
+
+// パスフレーズを設定する
+// - 未暗号化のデータベースに設定すると、既存の発言本文と議論から作った内容（TEXT_COLUMNS）をすべて暗号化して有効にする
+//   （PLAINTEXT_FIELDS のテーマ・参加者名・添付ファイル名・URL・埋め込みベクトルは平文のまま残る）
+// - 有効な状態では current_passphrase が必要。passphrase を渡せば変更し、None なら本文をすべて復号して無効にする
+// - remember でキーチェーンへの保存を指定する（省略時は保存済みなら新しいパスフレーズに差し替える）。無効にすると削除する
+#[command]
+pub async fn set_db_passphrase(
+    app: AppHandle,
+    passphrase: Option<String>,
+    current_passphrase: Option<String>,
+    remember: Option<bool>,
+) -> Result<EncryptionStatus, String> {
+    info!("set_db_passphrase 呼び出し: enable={}", passphrase.is_some());
+    if let Some(passphrase) = &passphrase {
+        if passphrase.chars().count() < MIN_PASSPHRASE_CHARS {
+            return Err(format!("パスフレーズは{}文字以上にしてください", MIN_PASSPHRASE_CHARS));
+        }
+    }
+    if discussion_engine::any_running(&app) {
+        return Err("自動進行中のセッションがあるため変更できません".into());
+    }
+    let keys = keys(&app);
+    let pool = db::pool(&app).await?;
+    let existing = load_key_row(&pool).await?;
+    let now = db::now_string();
+    let mut tx = pool.begin().await.map_err(|e| format!("トランザクション開始失敗: {}", e))?;
+
+    let (action, key) = match (existing, passphrase.clone()) {
+        (None, None) => return Ok(keys.status()),
+        (None, Some(passphrase)) => {
+            let key = random_bytes::<KEY_LEN>()?;
+            let converted = convert_all(&mut tx, |text| enable_text(&key, text)).await?;
+            let (salt, iterations, wrapped) = wrap_key(&key, &passphrase)?;
+            sqlx::query(
+                "INSERT INTO encryption_keys (id, salt, iterations, wrapped_key, created_at, updated_at)
+                 VALUES (1, ?, ?, ?, ?, ?)",
+            )
+            .bind(salt)
+            .bind(iterations)
+            .bind(wrapped)
+            .bind(&now)
+            .bind(&now)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("暗号鍵の保存失敗: {}", e))?;
+            info!("発言本文の暗号化を有効化: {}セッション", converted);
+            ("enable", Some(key))
+        }
+        (Some(row), passphrase) => {
+            let current = current_passphrase.ok_or_else(|| "現在のパスフレーズを入力してください".to_string())?;
+            let key = unwrap_key(&row, &current)?;
+            match passphrase {
+                Some(passphrase) => {
+                    let (salt, iterations, wrapped) = wrap_key(&key, &passphrase)?;
+                    sqlx::query(
+                        "UPDATE encryption_keys SET salt = ?, iterations = ?, wrapped_key = ?, updated_at = ? WHERE id = 1",
+                    )
+                    .bind(salt)
+                    .bind(iterations)
+                    .bind(wrapped)
+                    .bind(&now)
+                    .execute(&mut *tx)
+                    .await
+                    .map_err(|e| format!("暗号鍵の保存失敗: {}", e))?;
+                    ("change", Some(key))
+                }
+                None => {
+                    let converted = convert_all(&mut tx, |text| disable_text(&key, text)).await?;
+                    sqlx::query("DELETE FROM encryption_keys")
+                        .execute(&mut *tx)
+                        .await
+                        .map_err(|e| format!("暗号鍵の削除失敗: {}", e))?;
+                    info!("発言本文の暗号化を無効化: {}セッション", converted);
+                    ("disable", None)
+                }
+            }
+        }
+    };
+    tx.commit().await.map_err(|e| format!("コミット失敗: {}", e))?;
+    keys.set_state(key.is_some(), key);
+    audit::record("encryption", action, json!({}));
+    // データベースは変更済みなので、キーチェーンの失敗は警告に留める（status の remembered で分かる）
+    let keychain = match &passphrase {
+        Some(passphrase) => apply_remember(&app, keys, passphrase, remember).await,
+        None => keychain_forget(&app).await.map(|_| keys.set_remembered(false)),
+    };
+    if let Err(e) = keychain {
+        warn!("キーチェーンの更新に失敗: {}", e);
+        keys.set_remembered(false);
+    }
+    Ok(keys.status())
+}
+
+// キーチェーンに保存したパスフレーズを削除する（次回の起動からは解除が必要になる）
+#[command]
+pub async fn forget_db_passphrase(app: AppHandle, state: State<'_, AppState>) -> Result<EncryptionStatus, String> {
+    info!("forget_db_passphrase 呼び出し");
+    keychain_forget(&app).await?;
+    state.keys.set_remembered(false);
+    audit::record("encryption", "forget", json!({}));
+    Ok(state.keys.status())
+}
+
+// フロントエンドが保存する messages JSON の本文を暗号化する（無効なら入力のまま）
+#[command]
+pub fn seal_messages_json(state: State<'_, AppState>, messages: String) -> Result<String, String> {
+    if !state.keys.status().enabled {
+        return Ok(messages);
+    }
+    map_messages_json(&messages, |text| state.keys.seal(text))
+}
+
+// フロントエンドが読み込んだ messages JSON の本文を復号する
+#[command]
+pub fn open_messages_json(state: State<'_, AppState>, messages: String) -> Result<String, String> {
+    if !messages.contains(PREFIX) && !messages.contains(ESCAPE) {
+        return Ok(messages);
+    }
+    map_messages_json(&messages, |text| state.keys.open(text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_text_seals_to_the_same_ciphertext() {
+        let key = [7u8; KEY_LEN];
+        let sealed = encrypt_with(&key, "賛成です").unwrap();
+        assert_eq!(encrypt_with(&key, "賛成です").unwrap(), sealed);
+        assert_ne!(encrypt_with(&key, "反対です").unwrap(), sealed);
+        assert_eq!(decrypt_with(&key, &sealed).unwrap(), "賛成です");
+        assert!(decrypt_with(&[8u8; KEY_LEN], &sealed).is_err());
+    }
+
+    #[test]
+    fn plaintext_that_looks_sealed_round_trips() {
+        let key = [7u8; KEY_LEN];
+        for text in ["enc:v1:賛成です", "enc:raw:賛成です", "賛成です"] {
+            let sealed = seal_with(Some(&key), text).unwrap();
+            assert!(sealed.starts_with(PREFIX), "{}", text);
+            assert_eq!(open_with(Some(&key), &sealed).unwrap(), text);
+            let plain = seal_with(None, text).unwrap();
+            assert_eq!(open_with(None, &plain).unwrap(), text);
+        }
+        // 暗号化済みの本文は二重に暗号化しない
+        let sealed = seal_with(Some(&key), "賛成です").unwrap();
+        assert_eq!(seal_with(Some(&key), &sealed).unwrap(), sealed);
+    }
+
+    /// TEXT_COLUMNS の全列の値（NULL を除き、表・列の順）
+    async fn stored_texts(pool: &sqlx::SqlitePool) -> Vec<String> {
+        let mut texts = Vec::new();
+        for (table, column) in TEXT_COLUMNS {
+            let rows: Vec<(String,)> =
+                sqlx::query_as(&format!("SELECT {0} FROM {1} WHERE {0} IS NOT NULL ORDER BY rowid", column, table))
+                    .fetch_all(pool)
+                    .await
+                    .unwrap();
+            assert!(!rows.is_empty(), "{}.{} の行がありません", table, column);
+            texts.extend(rows.into_iter().map(|(text,)| text));
+        }
+        texts
+    }
+
+    #[tokio::test]
+    async fn conversion_covers_every_text_column() {
+        let pool = db::migrated_pool().await;
+        sqlx::raw_sql(
+            "INSERT INTO sessions (id, topic, participants, messages, model, created_at, updated_at)
+               VALUES (1, 'テーマ', '{}', '[{\"speaker\":\"田中\",\"message\":\"賛成です\"}]', 'gemma3:4b', '', '');
+             INSERT INTO session_analysis (session_id, kind, payload, created_at) VALUES (1, 'analysis', '{\"consensus\":[]}', '');
+             INSERT INTO session_summaries (session_id, kind, summary, covered, created_at) VALUES (1, 'full', '要約', 1, '');
+             INSERT INTO analysis_snapshots (id, session_id, message_count, model, payload, created_at)
+               VALUES (1, 1, 1, 'gemma3:4b', '{}', '');
+             INSERT INTO stance_history (snapshot_id, session_id, message_count, participant, label, confidence, stance)
+               VALUES (1, 1, 1, '田中', '賛成', 0.8, '週休3日に賛成');
+             INSERT INTO persona_states (session_id, participant, agreement, frustration, note, covered, updated_at)
+               VALUES (1, '田中', 0.5, 0.1, '納得していない', 1, '');
+             INSERT INTO action_items (session_id, position, description, owner, due_hint, model, created_at)
+               VALUES (1, 0, '試算を作る', '田中', '来週', 'gemma3:4b', ''),
+                      (1, 1, '議事録を回す', NULL, NULL, 'gemma3:4b', '');
+             INSERT INTO votes (session_id, question, options, payload, model, created_at)
+               VALUES (1, '導入するか', '[\"はい\",\"いいえ\"]', '[]', 'gemma3:4b', '');
+             INSERT INTO annotations (session_id, message_index, kind, content, created_at) VALUES (1, 0, 'note', 'メモ', '');
+             INSERT INTO attachments (id, session_id, file_name, kind, content_hash, chars, created_at)
+               VALUES (1, 1, '資料.txt', 'text', '', 4, '');
+             INSERT INTO attachment_chunks (attachment_id, session_id, position, content) VALUES (1, 1, 0, '資料の本文');
+             INSERT INTO url_cache (url, title, content, fetched_at) VALUES ('https://example.com/', 'タイトル', 'ページ本文', '');
+             INSERT INTO ai_profiles (id, name, created_at, updated_at) VALUES (1, '田中', '', '');
+             INSERT INTO profile_memories (profile_id, memory, updated_at) VALUES (1, '以前は反対していた', '');
+             INSERT INTO translations (session_id, source_kind, source_key, lang, source_hash, text, created_at)
+               VALUES (1, 'message', 0, 'en', '', 'I agree', '');
+             INSERT INTO generation_log (session_id, message_index, speaker, model, prompt, options, output, created_at)
+               VALUES (1, 0, '田中', 'gemma3:4b', 'プロンプト', '{}', '賛成です', '');",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        let plain = stored_texts(&pool).await;
+        let key = [7u8; KEY_LEN];
+
+        let mut tx = pool.begin().await.unwrap();
+        assert_eq!(convert_all(&mut tx, |text| enable_text(&key, text)).await.unwrap(), 1);
+        tx.commit().await.unwrap();
+        assert!(stored_texts(&pool).await.iter().all(|text| text.starts_with(PREFIX)));
+        // 省略された項目は NULL のまま
+        let (owner,): (Option<String>,) =
+            sqlx::query_as("SELECT owner FROM action_items WHERE position = 1").fetch_one(&pool).await.unwrap();
+        assert_eq!(owner, None);
+
+        let mut tx = pool.begin().await.unwrap();
+        convert_all(&mut tx, |text| disable_text(&key, text)).await.unwrap();
+        tx.commit().await.unwrap();
+        assert_eq!(stored_texts(&pool).await, plain);
+    }
+}
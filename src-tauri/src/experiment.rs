@@ -0,0 +1,290 @@
+// パラメータ比較実験
+// 同じシナリオをモデル・温度・発言順・プロンプト版の組み合わせごとに実行し、比較レポートを作る
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Emitter};
+use tracing::{info, warn};
+
+use crate::{
+    batch, call_ollama_generate, db,
+    db::{AiParticipant, ParticipantsData},
+    discussion_engine::{self, RoundConfig, TurnStrategy},
+    generation::GenerationOptions,
+    is_allowed_model, llm_json, prompts,
+    prompts::PromptVersion,
+    ERR_UNSUPPORTED_MODEL,
+};
+
+pub const EVENT_EXPERIMENT_PROGRESS: &str = "experiment://progress";
+
+// 組み合わせ数・ラウンド数の上限（ローカルLLMで現実的に回せる範囲）
+const MAX_CELLS: usize = 48;
+const MAX_ROUNDS: u32 = 6;
+
+/// 実験条件（各軸が空なら既定値1つで実行）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExperimentSpec {
+    #[serde(default)]
+    pub name: Option<String>,
+    pub topic: String,
+    /// 空の場合はテーマから自動生成し、全組み合わせで共有する
+    #[serde(default)]
+    pub participants: Vec<AiParticipant>,
+    pub rounds: u32,
+    pub models: Vec<String>,
+    #[serde(default)]
+    pub temperatures: Vec<f32>,
+    #[serde(default)]
+    pub turn_strategies: Vec<TurnStrategy>,
+    #[serde(default)]
+    pub prompt_versions: Vec<PromptVersion>,
+    /// 指定すると全発言を同じシードで生成する（条件間の比較を揃える）
+    #[serde(default)]
+    pub seed: Option<i64>,
+    /// 質の採点に使うモデル（未指定なら models の先頭）
+    #[serde(default)]
+    pub judge_model: Option<String>,
+}
+
+/// 1セル分の条件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CellConfig {
+    pub model: String,
+    /// None はモデル既定値
+    pub temperature: Option<f32>,
+    pub turn_strategy: TurnStrategy,
+    pub prompt_version: PromptVersion,
+}
+
+/// 1セル分の結果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CellResult {
+    pub config: CellConfig,
+    pub session_id: Option<i64>,
+    /// 審査モデルによる質の評価（1〜10）
+    pub quality_score: Option<f32>,
+    pub quality_reason: Option<String>,
+    /// 1発言あたりの平均文字数
+    pub avg_length: Option<f32>,
+    /// 1発言あたりの平均生成時間
+    pub avg_latency_ms: Option<f32>,
+    pub total_ms: u128,
+    pub error: Option<String>,
+}
+
+/// 比較レポート
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExperimentReport {
+    pub experiment_id: i64,
+    pub cells: Vec<CellResult>,
+    /// 質の評価が最も高いセルの位置
+    pub best_cell: Option<usize>,
+}
+
+/// experiment://progress のペイロード
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExperimentProgressEvent {
+    experiment_id: i64,
+    index: usize,
+    total: usize,
+    config: CellConfig,
+    /// "started" | "completed" | "failed"
+    status: &'static str,
+}
+
+#[derive(Debug, Deserialize)]
+struct QualityJudgement {
+    score: f32,
+    #[serde(default)]
+    reason: String,
+}
+
+/// 各軸の直積で全セルを列挙
+fn expand_grid(spec: &ExperimentSpec) -> Vec<CellConfig> {
+    let temperatures: Vec<Option<f32>> = if spec.temperatures.is_empty() {
+        vec![None]
+    } else {
+        spec.temperatures.iter().map(|t| Some(*t)).collect()
+    };
+    let strategies = if spec.turn_strategies.is_empty() { vec![TurnStrategy::default()] } else { spec.turn_strategies.clone() };
+    let versions = if spec.prompt_versions.is_empty() { vec![PromptVersion::default()] } else { spec.prompt_versions.clone() };
+
+    let mut cells = Vec::new();
+    for model in &spec.models {
+        for temperature in &temperatures {
+            for turn_strategy in &strategies {
+                for prompt_version in &versions {
+                    cells.push(CellConfig {
+                        model: model.clone(),
+                        temperature: *temperature,
+                        turn_strategy: *turn_strategy,
+                        prompt_version: *prompt_version,
+                    });
+                }
+            }
+        }
+    }
+    cells
+}
+
+/// 議論全体の質を採点
+async fn judge_quality(judge_model: &str, session_id: i64, app: &AppHandle) -> Result<QualityJudgement, String> {
+    let session = db::load_session(app, session_id).await?;
+    let prompt = prompts::build_discussion_quality_prompt(&session.topic, &session.history_text(), prompts::default_language());
+    let raw = call_ollama_generate(app, judge_model, &prompt).await?;
+    let mut judgement: QualityJudgement = llm_json::parse_llm_json(&raw)?;
+    judgement.score = judgement.score.clamp(1.0, 10.0);
+    Ok(judgement)
+}
+
+/// 1セルを実行（セッション作成 → ラウンド進行 → 採点）
+async fn run_cell(
+    app: &AppHandle,
+    spec: &ExperimentSpec,
+    participants: &[AiParticipant],
+    judge_model: &str,
+    config: &CellConfig,
+) -> CellResult {
+    let started = Instant::now();
+    let mut result = CellResult {
+        config: config.clone(),
+        session_id: None,
+        quality_score: None,
+        quality_reason: None,
+        avg_length: None,
+        avg_latency_ms: None,
+        total_ms: 0,
+        error: None,
+    };
+
+    let outcome = async {
+        let data = ParticipantsData { user_participates: false, ai_data: participants.to_vec() };
+        let session_id = db::create_session(app, &spec.topic, &data, &config.model).await?;
+        result.session_id = Some(session_id);
+
+        let round_config = RoundConfig {
+            options: GenerationOptions { temperature: config.temperature, seed: spec.seed, ..Default::default() },
+            turn_strategy: config.turn_strategy,
+            prompt_version: config.prompt_version,
+            ..Default::default()
+        };
+        let stats = discussion_engine::run_rounds_with(app, session_id, spec.rounds, &round_config).await?;
+        if stats.generated > 0 {
+            result.avg_length = Some(stats.total_chars as f32 / stats.generated as f32);
+            result.avg_latency_ms = Some(stats.generation_ms as f32 / stats.generated as f32);
+        }
+
+        // 採点失敗はセル全体の失敗にしない
+        match judge_quality(judge_model, session_id, app).await {
+            Ok(j) => {
+                result.quality_score = Some(j.score);
+                result.quality_reason = Some(j.reason);
+            }
+            Err(e) => warn!("質の採点失敗 (session_id={}): {}", session_id, e),
+        }
+        Ok::<_, String>(())
+    }
+    .await;
+
+    result.error = outcome.err();
+    result.total_ms = started.elapsed().as_millis();
+    result
+}
+
+/// セル結果を experiment_runs に保存
+async fn save_cell(app: &AppHandle, experiment_id: i64, cell: &CellResult) -> Result<(), String> {
+    let pool = db::pool(app).await?;
+    let config = serde_json::to_string(&cell.config).map_err(|e| e.to_string())?;
+    sqlx::query(
+        "INSERT INTO experiment_runs (experiment_id, session_id, config, quality_score, avg_length, avg_latency_ms, total_ms, error, created_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(experiment_id)
+    .bind(cell.session_id)
+    .bind(config)
+    .bind(cell.quality_score)
+    .bind(cell.avg_length)
+    .bind(cell.avg_latency_ms)
+    .bind(cell.total_ms as i64)
+    .bind(&cell.error)
+    .bind(db::now_string())
+    .execute(&pool)
+    .await
+    .map_err(|e| format!("実験結果保存失敗: {}", e))?;
+    Ok(())
+}
+
+// 同じシナリオをパラメータの組み合わせごとに実行し、比較レポートを返す
+#[command]
+pub async fn run_experiment(app: AppHandle, spec: ExperimentSpec) -> Result<ExperimentReport, String> {
+    info!("run_experiment 呼び出し: topic='{}', models={:?}", spec.topic, spec.models);
+    if spec.topic.trim().is_empty() {
+        return Err("テーマが指定されていません".into());
+    }
+    if spec.rounds == 0 || spec.rounds > MAX_ROUNDS {
+        return Err(format!("ラウンド数は1〜{}で指定してください", MAX_ROUNDS));
+    }
+    if spec.models.is_empty() {
+        return Err("モデルが指定されていません".into());
+    }
+    let judge_model = spec.judge_model.clone().unwrap_or_else(|| spec.models[0].clone());
+    if spec.models.iter().chain(std::iter::once(&judge_model)).any(|m| !is_allowed_model(&app, m)) {
+        return Err(ERR_UNSUPPORTED_MODEL.to_string());
+    }
+    let cells = expand_grid(&spec);
+    if cells.len() > MAX_CELLS {
+        return Err(format!("組み合わせが多すぎます（{}件、上限{}件）", cells.len(), MAX_CELLS));
+    }
+
+    // 全セルで同じ参加者を使う
+    let participants = if spec.participants.is_empty() {
+        batch::generate_participants(&app, &spec.topic, &judge_model).await?
+    } else {
+        spec.participants.clone()
+    };
+
+    let pool = db::pool(&app).await?;
+    let spec_json = serde_json::to_string(&spec).map_err(|e| e.to_string())?;
+    let name = spec.name.clone().filter(|n| !n.trim().is_empty()).unwrap_or_else(|| spec.topic.clone());
+    let experiment_id = sqlx::query("INSERT INTO experiments (name, spec, created_at) VALUES (?, ?, ?)")
+        .bind(name)
+        .bind(spec_json)
+        .bind(db::now_string())
+        .execute(&pool)
+        .await
+        .map_err(|e| format!("実験作成失敗: {}", e))?
+        .last_insert_rowid();
+
+    // レイテンシを比較できるよう、セルは1つずつ順番に実行する
+    let total = cells.len();
+    let mut results = Vec::with_capacity(total);
+    for (index, config) in cells.into_iter().enumerate() {
+        let _ = app.emit(
+            EVENT_EXPERIMENT_PROGRESS,
+            ExperimentProgressEvent { experiment_id, index, total, config: config.clone(), status: "started" },
+        );
+        let cell = run_cell(&app, &spec, &participants, &judge_model, &config).await;
+        if let Some(e) = &cell.error {
+            warn!("実験セル失敗 ({}/{}): {}", index + 1, total, e);
+        }
+        save_cell(&app, experiment_id, &cell).await?;
+        let status = if cell.error.is_none() { "completed" } else { "failed" };
+        let _ = app.emit(EVENT_EXPERIMENT_PROGRESS, ExperimentProgressEvent { experiment_id, index, total, config, status });
+        results.push(cell);
+    }
+
+    let best_cell = results
+        .iter()
+        .enumerate()
+        .filter_map(|(i, c)| c.quality_score.map(|s| (i, s)))
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(i, _)| i);
+    info!("run_experiment 完了: experiment_id={}, セル数={}", experiment_id, total);
+    Ok(ExperimentReport { experiment_id, cells: results, best_cell })
+}
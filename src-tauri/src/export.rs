@@ -0,0 +1,406 @@
+// セッションのエクスポート（Markdown / HTML / JSON）とインポート（JSON）
+// テーマ・参加者・全発言・最新の要約（とアクションアイテム）を議事録形式に整形し、保存ダイアログで選んだ場所へ書き出す
+// JSON は別の環境へ議論を移すための形式で、import_session で新しいセッションとして取り込める
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use serde_json::json;
+use tauri::{command, AppHandle, Manager};
+use tauri_plugin_dialog::DialogExt;
+use tracing::info;
+
+pub use dewai_core::archive::SessionArchive;
+
+use crate::{
+    action_items,
+    action_items::ActionItem,
+    app_state::{AppState, RuntimeSettings},
+    audit, db, encryption, permissions, privacy,
+};
+
+// ファイル名に使うテーマの最大文字数
+const MAX_FILE_STEM_CHARS: usize = 40;
+// 取り込むファイルの上限サイズ
+const MAX_IMPORT_BYTES: u64 = 32 * 1024 * 1024;
+
+/// 出力形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Markdown,
+    Html,
+    Json,
+}
+
+impl ExportFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "md",
+            ExportFormat::Html => "html",
+            ExportFormat::Json => "json",
+        }
+    }
+
+    fn filter_name(self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "Markdown",
+            ExportFormat::Html => "HTML",
+            ExportFormat::Json => "DewAI セッション (JSON)",
+        }
+    }
+}
+
+/// セッションを JSON エクスポートの形にする（redact があればその設定で伏せ字にする）
+fn archive_of(
+    session_id: i64,
+    session: db::SessionRecord,
+    summary: Option<String>,
+    created_at: String,
+    redact: Option<&RuntimeSettings>,
+) -> SessionArchive {
+    let clean = |text: &str| redact.map_or_else(|| text.to_string(), |settings| privacy::redact(settings, text));
+    let mut participants = session.participants;
+    for p in &mut participants.ai_data {
+        p.role = clean(&p.role);
+        p.description = clean(&p.description);
+    }
+    let messages = session
+        .messages
+        .into_iter()
+        .map(|mut m| {
+            m.message = clean(&m.message);
+            m
+        })
+        .collect();
+    SessionArchive {
+        exported_at: Some(db::now_string()),
+        ..SessionArchive::new(
+            Some(session_id),
+            clean(&session.topic),
+            session.model,
+            participants,
+            messages,
+            summary.map(|s| clean(&s)),
+            Some(created_at),
+        )
+    }
+}
+
+/// 書き出す内容（伏せ字処理済み）
+struct ExportDocument {
+    topic: String,
+    model: String,
+    /// (名前, 役割)
+    participants: Vec<(String, String)>,
+    /// (発言者, 本文)
+    messages: Vec<(String, String)>,
+    summary: Option<String>,
+    action_items: Vec<ActionItem>,
+    exported_at: String,
+}
+
+impl ExportDocument {
+    fn new(
+        session: db::SessionRecord,
+        summary: Option<String>,
+        action_items: Vec<ActionItem>,
+        redact: Option<&RuntimeSettings>,
+    ) -> Self {
+        let clean = |text: &str| redact.map_or_else(|| text.to_string(), |settings| privacy::redact(settings, text));
+        let mut participants = Vec::new();
+        if session.participants.user_participates {
+            participants.push(("ユーザー".to_string(), String::new()));
+        }
+        participants.extend(session.participants.ai_data.iter().map(|p| (p.name.clone(), clean(&p.role))));
+        Self {
+            topic: clean(&session.topic),
+            model: session.model,
+            participants,
+            messages: session.messages.iter().map(|m| (m.speaker.clone(), clean(&m.message))).collect(),
+            summary: summary.map(|s| clean(&s)),
+            action_items: action_items
+                .into_iter()
+                .map(|item| ActionItem {
+                    description: clean(&item.description),
+                    owner: item.owner.map(|o| clean(&o)),
+                    due_hint: item.due_hint.map(|d| clean(&d)),
+                })
+                .collect(),
+            exported_at: chrono::Local::now().format("%Y-%m-%d %H:%M").to_string(),
+        }
+    }
+}
+
+/// HTML の特殊文字をエスケープ
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+fn render_markdown(doc: &ExportDocument) -> String {
+    let mut out = format!("# {}\n\n- 出力日時: {}\n", doc.topic, doc.exported_at);
+    if !doc.model.is_empty() {
+        out.push_str(&format!("- モデル: {}\n", doc.model));
+    }
+
+    out.push_str("\n## 参加者\n\n");
+    for (name, role) in &doc.participants {
+        if role.is_empty() {
+            out.push_str(&format!("- {}\n", name));
+        } else {
+            out.push_str(&format!("- {}（{}）\n", name, role));
+        }
+    }
+
+    if let Some(summary) = &doc.summary {
+        out.push_str(&format!("\n## 要約\n\n{}\n", summary.trim()));
+    }
+
+    if !doc.action_items.is_empty() {
+        out.push_str(&format!("\n## アクションアイテム\n\n{}", action_items::to_markdown(&doc.action_items)));
+    }
+
+    out.push_str("\n## 発言録\n");
+    for (speaker, message) in &doc.messages {
+        // 改行を含む発言もリスト項目内に収める
+        let body = message.trim().lines().collect::<Vec<_>>().join("  \n  ");
+        out.push_str(&format!("\n- **{}**: {}", speaker, body));
+    }
+    out.push('\n');
+    out
+}
+
+fn render_html(doc: &ExportDocument) -> String {
+    let paragraphs = |text: &str| {
+        text.trim()
+            .split("\n\n")
+            .map(|p| format!("<p>{}</p>", escape_html(p).replace('\n', "<br>")))
+            .collect::<String>()
+    };
+
+    let mut body = format!("<h1>{}</h1>\n<p class=\"meta\">出力日時: {}", escape_html(&doc.topic), doc.exported_at);
+    if !doc.model.is_empty() {
+        body.push_str(&format!(" / モデル: {}", escape_html(&doc.model)));
+    }
+    body.push_str("</p>\n<h2>参加者</h2>\n<ul>\n");
+    for (name, role) in &doc.participants {
+        if role.is_empty() {
+            body.push_str(&format!("<li>{}</li>\n", escape_html(name)));
+        } else {
+            body.push_str(&format!("<li>{}（{}）</li>\n", escape_html(name), escape_html(role)));
+        }
+    }
+    body.push_str("</ul>\n");
+
+    if let Some(summary) = &doc.summary {
+        body.push_str(&format!("<h2>要約</h2>\n<section class=\"summary\">{}</section>\n", paragraphs(summary)));
+    }
+
+    if !doc.action_items.is_empty() {
+        body.push_str("<h2>アクションアイテム</h2>\n<ul class=\"actions\">\n");
+        for item in &doc.action_items {
+            let mut notes = Vec::new();
+            if let Some(owner) = &item.owner {
+                notes.push(format!("担当: {}", escape_html(owner)));
+            }
+            if let Some(due) = &item.due_hint {
+                notes.push(format!("期限: {}", escape_html(due)));
+            }
+            let notes = if notes.is_empty() { String::new() } else { format!("（{}）", notes.join("、")) };
+            body.push_str(&format!("<li>☐ {}{}</li>\n", escape_html(&item.description), notes));
+        }
+        body.push_str("</ul>\n");
+    }
+
+    body.push_str("<h2>発言録</h2>\n");
+    for (speaker, message) in &doc.messages {
+        body.push_str(&format!(
+            "<div class=\"message\"><div class=\"speaker\">{}</div>{}</div>\n",
+            escape_html(speaker),
+            paragraphs(message)
+        ));
+    }
+
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"ja\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n\
+         body {{ font-family: sans-serif; max-width: 48rem; margin: 2rem auto; padding: 0 1rem; line-height: 1.7; color: #222; }}\n\
+         .meta {{ color: #666; font-size: 0.9rem; }}\n\
+         .summary {{ background: #f3f8f3; border-left: 4px solid #4a9a5a; padding: 0.5rem 1rem; }}\n\
+         .actions {{ list-style: none; padding-left: 0.5rem; }}\n\
+         .message {{ border-bottom: 1px solid #eee; padding: 0.5rem 0; }}\n\
+         .speaker {{ font-weight: bold; }}\n\
+         .message p {{ margin: 0.25rem 0; }}\n\
+         </style>\n</head>\n<body>\n{}</body>\n</html>\n",
+        escape_html(&doc.topic),
+        body
+    )
+}
+
+/// 保存ダイアログの既定ファイル名（ファイル名に使えない文字は置き換える）
+fn default_file_name(topic: &str, format: ExportFormat) -> String {
+    let stem: String = topic
+        .chars()
+        .map(|c| if c.is_control() || r#"\/:*?"<>|"#.contains(c) { '_' } else { c })
+        .take(MAX_FILE_STEM_CHARS)
+        .collect();
+    let stem = stem.trim();
+    format!("{}.{}", if stem.is_empty() { "discussion" } else { stem }, format.extension())
+}
+
+/// 保存ダイアログを開き、選ばれたパスを返す（キャンセル時は None）
+async fn pick_save_path(app: &AppHandle, file_name: String, format: ExportFormat) -> Result<Option<PathBuf>, String> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .set_title("セッションのエクスポート")
+        .set_file_name(file_name)
+        .add_filter(format.filter_name(), &[format.extension()])
+        .save_file(move |path| {
+            let _ = tx.send(path);
+        });
+    let Some(path) = rx.await.map_err(|_| "保存ダイアログが閉じられました".to_string())? else {
+        return Ok(None);
+    };
+    path.into_path().map(Some).map_err(|e| format!("保存先の解釈に失敗: {}", e))
+}
+
+/// ファイル選択ダイアログを開き、選ばれたパスを返す（キャンセル時は None）
+async fn pick_open_path(app: &AppHandle) -> Result<Option<PathBuf>, String> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .set_title("セッションのインポート")
+        .add_filter(ExportFormat::Json.filter_name(), &[ExportFormat::Json.extension()])
+        .pick_file(move |path| {
+            let _ = tx.send(path);
+        });
+    let Some(path) = rx.await.map_err(|_| "ファイル選択ダイアログが閉じられました".to_string())? else {
+        return Ok(None);
+    };
+    path.into_path().map(Some).map_err(|e| format!("ファイルパスの解釈に失敗: {}", e))
+}
+
+async fn session_created_at(app: &AppHandle, session_id: i64) -> Result<String, String> {
+    let pool = db::pool(app).await?;
+    let (created_at,): (String,) = sqlx::query_as("SELECT created_at FROM sessions WHERE id = ?")
+        .bind(session_id)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| format!("セッション取得失敗: {}", e))?;
+    Ok(created_at)
+}
+
+// セッションを Markdown / HTML / JSON に書き出す（保存したパスを返す。ダイアログをキャンセルした場合は None）
+// redact=true なら個人情報を伏せ字にしてから書き出す
+#[command]
+pub async fn export_session(
+    app: AppHandle,
+    session_id: i64,
+    format: ExportFormat,
+    redact: Option<bool>,
+) -> Result<Option<String>, String> {
+    info!("export_session 呼び出し: session_id={}, format={:?}", session_id, format);
+    let session = db::load_session(&app, session_id).await?;
+    let summary = db::latest_summary(&app, session_id).await?.map(|s| s.summary);
+    let settings = &app.state::<AppState>().inner().settings;
+    let redact = redact.unwrap_or(false).then_some(&**settings);
+    let message_count = session.messages.len();
+    let (topic, content) = match format {
+        ExportFormat::Json => {
+            let created_at = session_created_at(&app, session_id).await?;
+            let archive = archive_of(session_id, session, summary, created_at, redact);
+            let content =
+                serde_json::to_string_pretty(&archive).map_err(|e| format!("JSONシリアライズ失敗: {}", e))?;
+            (archive.topic, content)
+        }
+        ExportFormat::Markdown | ExportFormat::Html => {
+            let action_items = action_items::load(&app, session_id).await?;
+            let doc = ExportDocument::new(session, summary, action_items, redact);
+            let content =
+                if format == ExportFormat::Markdown { render_markdown(&doc) } else { render_html(&doc) };
+            (doc.topic, content)
+        }
+    };
+
+    let Some(path) = pick_save_path(&app, default_file_name(&topic, format), format).await? else {
+        info!("エクスポートをキャンセル: session_id={}", session_id);
+        return Ok(None);
+    };
+    std::fs::write(&path, content).map_err(|e| format!("ファイル書き込み失敗: {}", e))?;
+    audit::record(
+        "export",
+        "session",
+        json!({ "sessionId": session_id, "format": format.extension(), "messages": message_count }),
+    );
+    info!("エクスポート完了: {}", path.display());
+    Ok(Some(path.display().to_string()))
+}
+
+// JSON エクスポートを新しいセッションとして取り込み、新しいセッションIDを返す（ダイアログをキャンセルした場合は None）
+// path 未指定ならファイル選択ダイアログを開く。path を直接指定する場合はツール権限 session-import（filesystem-read）が必要
+#[command]
+pub async fn import_session(app: AppHandle, path: Option<String>) -> Result<Option<i64>, String> {
+    info!("import_session 呼び出し: path={:?}", path);
+    let path = match path {
+        Some(p) => {
+            permissions::authorize(&app, permissions::TOOL_SESSION_IMPORT).await?;
+            PathBuf::from(p)
+        }
+        None => match pick_open_path(&app).await? {
+            Some(p) => p,
+            None => return Ok(None),
+        },
+    };
+    let size = std::fs::metadata(&path).map_err(|e| format!("ファイルを開けません: {}", e))?.len();
+    if size > MAX_IMPORT_BYTES {
+        return Err(format!("ファイルが大きすぎます: {} bytes（上限 {} bytes）", size, MAX_IMPORT_BYTES));
+    }
+    let raw = std::fs::read_to_string(&path).map_err(|e| format!("ファイル読み込み失敗: {}", e))?;
+    let archive: SessionArchive =
+        serde_json::from_str(&raw).map_err(|e| format!("セッションファイルの形式が不正です: {}", e))?;
+    archive.validate()?;
+
+    let participants = serde_json::to_string(&archive.participants)
+        .map_err(|e| format!("参加者のシリアライズ失敗: {}", e))?;
+    let mut messages = archive.messages.clone();
+    let state = app.state::<AppState>();
+    for message in &mut messages {
+        message.message = db::storable_text(&state.keys, &state.settings, &message.message)?;
+    }
+    let messages = serde_json::to_string(&messages).map_err(|e| format!("発言のシリアライズ失敗: {}", e))?;
+    let now = db::now_string();
+    let created_at = archive.created_at.clone().unwrap_or_else(|| now.clone());
+
+    // セッションと要約をまとめて登録（発言は sessions のトリガーで messages テーブルへ展開される）
+    let pool = db::pool(&app).await?;
+    let mut tx = pool.begin().await.map_err(|e| format!("トランザクション開始失敗: {}", e))?;
+    let session_id = sqlx::query(
+        "INSERT INTO sessions (topic, participants, messages, model, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&archive.topic)
+    .bind(participants)
+    .bind(messages)
+    .bind(&archive.model)
+    .bind(&created_at)
+    .bind(&now)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("セッション登録失敗: {}", e))?
+    .last_insert_rowid();
+    if let Some(summary) = archive.summary.as_deref().filter(|s| !s.trim().is_empty()) {
+        let payload = json!({ "summary": summary, "covered": archive.messages.len() }).to_string();
+        db::insert_analysis(&mut tx, encryption::keys(&app), session_id, "summary", &payload).await?;
+    }
+    tx.commit().await.map_err(|e| format!("コミット失敗: {}", e))?;
+
+    audit::record(
+        "import",
+        "session",
+        json!({ "sessionId": session_id, "sourceSessionId": archive.session_id, "messages": archive.messages.len() }),
+    );
+    info!("インポート完了: session_id={} ({}件の発言)", session_id, archive.messages.len());
+    Ok(Some(session_id))
+}
@@ -0,0 +1,88 @@
+// 司会役（ファシリテーター）
+// 自動進行の発言の合間に直近の発言を調べ、堂々巡り・1人の参加者による独占・テーマからの脱線を見つけたら、
+// テンプレート moderator_intervention で司会者の発言を生成してエンジンが議論に差し込む
+// 判定と発言の生成は dewai-core にあり（dewai-cli と共通）、しきい値はアプリ設定の facilitator で変えられる
+// ここではモデルによる脱線の判定（topic_drift）・セッション設定の文体を渡し、介入を通知する
+// 脱線は、介入後の発言を含む判定がアプリ設定の topicDriftThreshold を超えた時に介入する
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+use tracing::info;
+
+pub use dewai_core::facilitator::{FacilitatorSettings, Intervention, InterventionReason};
+use dewai_core::facilitator::{DetectedDrift, Discussion};
+
+use crate::{
+    app_state::AppState, db::SessionRecord, generation::GenerationOptions, session_settings, topic_drift, AppGenerator,
+};
+
+pub const EVENT_FACILITATOR_INTERVENED: &str = "facilitator://intervened";
+
+/// facilitator://intervened のペイロード
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct InterventionEvent {
+    session_id: i64,
+    /// 司会者の発言の位置
+    index: usize,
+    reason: InterventionReason,
+    situation: String,
+    score: f32,
+}
+
+/// 問題があれば司会者の発言を生成する（問題がなければ None。保存・通知は呼び出し側で行う）
+/// drift_threshold はモデルによる脱線の判定を使う時のしきい値（アプリ設定の topicDriftThreshold）
+pub async fn intervene(
+    app: &AppHandle,
+    settings: &FacilitatorSettings,
+    drift_threshold: f32,
+    session_id: i64,
+    session: &SessionRecord,
+    options: &GenerationOptions,
+) -> Result<Option<Intervention>, String> {
+    let detected = topic_drift::latest(app, session_id).await?;
+    let detected = detected.as_ref().map(|d| DetectedDrift {
+        score: d.score,
+        tangent: &d.tangent,
+        message_count: d.message_count,
+        threshold: drift_threshold,
+    });
+    let style = session_settings::load(app, session_id).await?.prompt_style();
+    let postprocess = app.state::<AppState>().settings.postprocess.get();
+    let discussion = Discussion {
+        topic: &session.topic,
+        model: &session.model,
+        participants: &session.participants,
+        messages: &session.messages,
+    };
+    let generator = AppGenerator(app);
+    let intervention = dewai_core::facilitator::intervene(
+        &generator,
+        settings,
+        &postprocess,
+        &discussion,
+        detected,
+        &style,
+        options,
+    )
+    .await?;
+    if let Some(intervention) = &intervention {
+        let finding = &intervention.finding;
+        info!("司会者が介入: session_id={}, reason={:?}, score={:.2}", session_id, finding.reason, finding.score);
+    }
+    Ok(intervention)
+}
+
+/// 司会者の発言を保存した後に、介入の理由を通知する
+pub fn notify(app: &AppHandle, session_id: i64, index: usize, intervention: &Intervention) {
+    let finding = &intervention.finding;
+    let _ = app.emit(
+        EVENT_FACILITATOR_INTERVENED,
+        InterventionEvent {
+            session_id,
+            index,
+            reason: finding.reason,
+            situation: finding.situation.clone(),
+            score: finding.score,
+        },
+    );
+}
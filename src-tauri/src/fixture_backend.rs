@@ -0,0 +1,191 @@
+// 記録・再生用の LLM バックエンド
+// 記録モードは Ollama とのやり取りをプロンプトのハッシュごとにファイルへ保存し、
+// 再生モードは Ollama に接続せず保存済みの応答を返す（結合テストや不具合報告の再現用）
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+
+use crate::{
+    backend::{BackendHealth, ChunkSink, LlmBackend, OllamaBackend},
+    db,
+    gen_error::GenError,
+    generation::{GenerationOptions, GenerationResult},
+    privacy,
+};
+
+// フィクスチャの保存先を上書きする環境変数
+const ENV_FIXTURE_DIR: &str = "DEWAI_FIXTURE_DIR";
+// 再生時に1断片として返す文字数
+const REPLAY_CHUNK_CHARS: usize = 16;
+
+static FIXTURE_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// 既定のフィクスチャ保存先を設定（起動時に1回。環境変数の指定が優先）
+pub fn init_fixture_dir(default_dir: PathBuf) {
+    let dir = std::env::var(ENV_FIXTURE_DIR).map(PathBuf::from).unwrap_or(default_dir);
+    let _ = FIXTURE_DIR.set(dir);
+}
+
+fn fixture_dir() -> PathBuf {
+    FIXTURE_DIR
+        .get()
+        .cloned()
+        .or_else(|| std::env::var(ENV_FIXTURE_DIR).ok().map(PathBuf::from))
+        .unwrap_or_else(|| PathBuf::from("fixtures"))
+}
+
+/// フィクスチャ1件（1リクエスト/1レスポンス）
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Fixture {
+    model: String,
+    prompt: String,
+    options: GenerationOptions,
+    response: String,
+    recorded_at: String,
+}
+
+/// モデルとプロンプトから決まるキー（シードなどのオプションは含めない）
+fn fixture_key(model: &str, prompt: &str) -> String {
+    db::content_hash(&format!("{}\n{}", model, prompt))
+}
+
+fn fixture_path(model: &str, prompt: &str) -> PathBuf {
+    fixture_dir().join(format!("{}.json", fixture_key(model, prompt)))
+}
+
+/// Ollama の応答をフィクスチャとして保存しながら返す
+pub struct RecordingBackend {
+    pub ollama: OllamaBackend,
+}
+
+impl RecordingBackend {
+    fn save(&self, model: &str, prompt: &str, options: &GenerationOptions, response: &str) {
+        // フィクスチャは不具合報告に添付して共有されるため、本文は伏せ字にして保存する（キーは元のプロンプトから計算）
+        let fixture = Fixture {
+            model: model.to_string(),
+            prompt: privacy::redact(&self.ollama.settings, prompt),
+            options: options.clone(),
+            response: privacy::redact(&self.ollama.settings, response),
+            recorded_at: db::now_string(),
+        };
+        let path = fixture_path(model, prompt);
+        let result = std::fs::create_dir_all(fixture_dir())
+            .map_err(|e| e.to_string())
+            .and_then(|_| serde_json::to_string_pretty(&fixture).map_err(|e| e.to_string()))
+            .and_then(|json| std::fs::write(&path, json).map_err(|e| e.to_string()));
+        match result {
+            Ok(()) => info!("フィクスチャ記録: {}", path.display()),
+            Err(e) => warn!("フィクスチャ記録失敗 ({}): {}", path.display(), e),
+        }
+    }
+}
+
+#[async_trait]
+impl LlmBackend for RecordingBackend {
+    fn name(&self) -> &'static str {
+        "record"
+    }
+
+    async fn is_available(&self) -> bool {
+        self.ollama.is_available().await
+    }
+
+    async fn health(&self) -> BackendHealth {
+        self.ollama.health().await
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>, String> {
+        self.ollama.list_models().await
+    }
+
+    async fn generate(
+        &self,
+        model: &str,
+        prompt: &str,
+        options: &GenerationOptions,
+    ) -> Result<GenerationResult, GenError> {
+        let response = self.ollama.generate(model, prompt, options).await?;
+        self.save(model, prompt, options, &response.text);
+        Ok(response)
+    }
+
+    async fn generate_stream(
+        &self,
+        model: &str,
+        prompt: &str,
+        options: &GenerationOptions,
+        on_chunk: ChunkSink<'_>,
+    ) -> Result<GenerationResult, GenError> {
+        let response = self.ollama.generate_stream(model, prompt, options, on_chunk).await?;
+        self.save(model, prompt, options, &response.text);
+        Ok(response)
+    }
+    async fn warm_up(&self, model: &str, keep_alive: Option<&str>) -> Result<(), String> {
+        self.ollama.warm_up(model, keep_alive).await
+    }
+}
+
+/// 保存済みフィクスチャだけで応答する（Ollama には接続しない）
+pub struct ReplayBackend;
+
+impl ReplayBackend {
+    fn load(model: &str, prompt: &str) -> Result<Fixture, String> {
+        let path = fixture_path(model, prompt);
+        let json = std::fs::read_to_string(&path)
+            .map_err(|_| format!("フィクスチャが見つかりません: {}", path.display()))?;
+        serde_json::from_str(&json).map_err(|e| format!("フィクスチャの解析失敗 ({}): {}", path.display(), e))
+    }
+}
+
+#[async_trait]
+impl LlmBackend for ReplayBackend {
+    fn name(&self) -> &'static str {
+        "replay"
+    }
+
+    async fn is_available(&self) -> bool {
+        fixture_dir().is_dir()
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>, String> {
+        let entries = std::fs::read_dir(fixture_dir()).map_err(|e| format!("フィクスチャ一覧取得失敗: {}", e))?;
+        let mut models: Vec<String> = entries
+            .filter_map(|entry| std::fs::read_to_string(entry.ok()?.path()).ok())
+            .filter_map(|json| serde_json::from_str::<Fixture>(&json).ok())
+            .map(|f| f.model)
+            .collect();
+        models.sort();
+        models.dedup();
+        Ok(models)
+    }
+
+    async fn generate(
+        &self,
+        model: &str,
+        prompt: &str,
+        _options: &GenerationOptions,
+    ) -> Result<GenerationResult, GenError> {
+        let fixture = Self::load(model, prompt)?;
+        debug!("フィクスチャ再生: key={}", fixture_key(model, prompt));
+        Ok(GenerationResult::text_only(model, fixture.response))
+    }
+
+    async fn generate_stream(
+        &self,
+        model: &str,
+        prompt: &str,
+        options: &GenerationOptions,
+        on_chunk: ChunkSink<'_>,
+    ) -> Result<GenerationResult, GenError> {
+        let response = self.generate(model, prompt, options).await?;
+        let chars: Vec<char> = response.text.chars().collect();
+        for piece in chars.chunks(REPLAY_CHUNK_CHARS) {
+            on_chunk(&piece.iter().collect::<String>());
+        }
+        Ok(response)
+    }
+}
@@ -0,0 +1,225 @@
+// 形式のある議論（ディベート・ブレインストーミング・六つの帽子）
+// 形式ごとにフェーズの並びと各フェーズの発言指示を定め、議論エンジンが1フェーズ = 1ラウンドとして進行する
+// 現在のフェーズはセッション設定に記録し、フェーズが変わるたびに discussion://phase-changed で通知する
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Emitter};
+use tracing::info;
+
+use crate::{
+    db::AiParticipant,
+    discussion_engine::{self, RoundConfig},
+    prompts::{self, PhaseInstructions, PromptStyle},
+    session_settings,
+};
+
+pub const EVENT_PHASE_CHANGED: &str = "discussion://phase-changed";
+
+/// 議論形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DiscussionFormat {
+    /// 肯定側・否定側に分かれたディベート（立論 → 反駁 → 最終弁論）
+    Debate,
+    /// ブレインストーミング（発散 → 発展 → 収束）
+    Brainstorm,
+    /// 六つの思考帽子（全員が同じ帽子をかぶって1つの観点ずつ考える）
+    SixHats,
+}
+
+/// 形式ごとのフェーズ
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FormatPhase {
+    Opening,
+    Rebuttal,
+    Closing,
+    Diverge,
+    Build,
+    Converge,
+    WhiteHat,
+    RedHat,
+    BlackHat,
+    YellowHat,
+    GreenHat,
+    BlueHat,
+}
+
+impl DiscussionFormat {
+    pub fn name(self) -> &'static str {
+        match self {
+            DiscussionFormat::Debate => "ディベート",
+            DiscussionFormat::Brainstorm => "ブレインストーミング",
+            DiscussionFormat::SixHats => "六つの思考帽子",
+        }
+    }
+
+    /// フェーズの並び（1フェーズ = 1ラウンド）
+    pub fn phases(self) -> &'static [FormatPhase] {
+        match self {
+            DiscussionFormat::Debate => &[FormatPhase::Opening, FormatPhase::Rebuttal, FormatPhase::Closing],
+            DiscussionFormat::Brainstorm => &[FormatPhase::Diverge, FormatPhase::Build, FormatPhase::Converge],
+            DiscussionFormat::SixHats => &[
+                FormatPhase::WhiteHat,
+                FormatPhase::RedHat,
+                FormatPhase::BlackHat,
+                FormatPhase::YellowHat,
+                FormatPhase::GreenHat,
+                FormatPhase::BlueHat,
+            ],
+        }
+    }
+
+    /// 必要なAI参加者の数
+    pub fn min_participants(self) -> usize {
+        match self {
+            DiscussionFormat::Debate => 2,
+            DiscussionFormat::Brainstorm | DiscussionFormat::SixHats => 1,
+        }
+    }
+
+    /// ラウンド（1始まり）のフェーズ
+    pub fn phase_at(self, round: u32) -> Option<FormatPhase> {
+        self.phases().get((round as usize).checked_sub(1)?).copied()
+    }
+}
+
+impl FormatPhase {
+    pub fn name(self) -> &'static str {
+        match self {
+            FormatPhase::Opening => "立論",
+            FormatPhase::Rebuttal => "反駁",
+            FormatPhase::Closing => "最終弁論",
+            FormatPhase::Diverge => "発散",
+            FormatPhase::Build => "発展",
+            FormatPhase::Converge => "収束",
+            FormatPhase::WhiteHat => "白い帽子（事実・情報）",
+            FormatPhase::RedHat => "赤い帽子（感情・直感）",
+            FormatPhase::BlackHat => "黒い帽子（リスク・慎重）",
+            FormatPhase::YellowHat => "黄色い帽子（利点・楽観）",
+            FormatPhase::GreenHat => "緑の帽子（創造・代案）",
+            FormatPhase::BlueHat => "青い帽子（整理・まとめ）",
+        }
+    }
+}
+
+/// ディベートの立場（参加者の並び順で交互に割り当てる）
+fn debate_side(index: usize) -> &'static str {
+    if index.is_multiple_of(2) {
+        "肯定側"
+    } else {
+        "否定側"
+    }
+}
+
+/// ディベートの各フェーズの指示
+fn debate_instructions(phase: FormatPhase, side: &str) -> String {
+    let body = match phase {
+        FormatPhase::Opening => "- テーマに対する自分の側の主張を1つ示し、その根拠を具体例とともに述べる\n- 相手側への反論はまだしない",
+        FormatPhase::Rebuttal => "- 相手側の立論から最も弱い論拠を1つ選び、反例や前提の誤りを指摘する\n- 新しい主張を増やすより、相手の主張への応答を優先する",
+        _ => "- これまでのやり取りを踏まえ、自分の側が優れている理由を要点2つにまとめる\n- 新しい論点は出さず、相手の反駁に答えきれていない点があれば補う",
+    };
+    format!("- あなたは{}として発言する\n{}", side, body)
+}
+
+/// ブレインストーミングの各フェーズの指示
+fn brainstorm_instructions(phase: FormatPhase) -> String {
+    match phase {
+        FormatPhase::Diverge => "- テーマに関するアイデアを1〜2個、自由に出す\n- 他の人のアイデアを批判しない。突飛なアイデアも歓迎する",
+        FormatPhase::Build => "- これまでに出たアイデアを1つ選び、組み合わせたり広げたりしてより良くする\n- 批判ではなく「こうすればもっと良くなる」という形で述べる",
+        _ => "- これまでのアイデアから、実現性と効果の両面で有望なものを1つ選ぶ\n- 選んだ理由と、最初に試すべき具体的な一歩を述べる",
+    }
+    .to_string()
+}
+
+/// 六つの帽子の各フェーズの指示（全員が同じ帽子をかぶる）
+fn six_hats_instructions(phase: FormatPhase) -> String {
+    match phase {
+        FormatPhase::WhiteHat => "- 事実・データ・分かっていないことだけを述べる。意見や評価は含めない",
+        FormatPhase::RedHat => "- テーマについての感情や直感を、理由づけせずに率直に述べる",
+        FormatPhase::BlackHat => "- リスク・欠点・うまくいかない可能性を具体的に指摘する",
+        FormatPhase::YellowHat => "- 利点・価値・うまくいった場合の効果を具体的に述べる",
+        FormatPhase::GreenHat => "- これまでの観点を踏まえ、新しいアイデアや代案を出す",
+        _ => "- ここまでの議論を整理し、結論と次に取るべき行動をまとめる",
+    }
+    .to_string()
+}
+
+/// フェーズの発言プロンプト（position はセッションのAI参加者の並びでの位置。ディベートの立場に使う）
+pub fn phase_prompt(
+    format: DiscussionFormat,
+    phase: FormatPhase,
+    participant: &AiParticipant,
+    position: usize,
+    history: &str,
+    topic: &str,
+    style: &PromptStyle,
+) -> String {
+    let (assignment, instructions) = match format {
+        DiscussionFormat::Debate => (debate_side(position).to_string(), debate_instructions(phase, debate_side(position))),
+        DiscussionFormat::Brainstorm => ("アイデアを出し合うメンバー".to_string(), brainstorm_instructions(phase)),
+        DiscussionFormat::SixHats => (phase.name().to_string(), six_hats_instructions(phase)),
+    };
+    let phase = PhaseInstructions { format_name: format.name(), phase_name: phase.name(), assignment, instructions };
+    prompts::build_format_phase_prompt(
+        &participant.name,
+        &participant.role,
+        &participant.description,
+        history,
+        topic,
+        &phase,
+        style,
+    )
+}
+
+/// discussion://phase-changed のペイロード
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PhaseChangedEvent {
+    session_id: i64,
+    format: DiscussionFormat,
+    phase: FormatPhase,
+    /// 1始まりのフェーズ番号
+    index: usize,
+    total: usize,
+}
+
+/// 現在のフェーズをセッション設定に記録して通知する（ラウンドの開始時）
+pub async fn enter_phase(app: &AppHandle, session_id: i64, format: DiscussionFormat, round: u32) -> Result<(), String> {
+    let Some(phase) = format.phase_at(round) else { return Ok(()) };
+    let mut settings = session_settings::load(app, session_id).await?;
+    if settings.format == Some(format) && settings.phase == Some(phase) {
+        return Ok(());
+    }
+    settings.format = Some(format);
+    settings.phase = Some(phase);
+    session_settings::save(app, session_id, &settings).await?;
+    info!("フェーズ開始: session_id={}, {} / {}", session_id, format.name(), phase.name());
+    let _ = app.emit(
+        EVENT_PHASE_CHANGED,
+        PhaseChangedEvent { session_id, format, phase, index: round as usize, total: format.phases().len() },
+    );
+    Ok(())
+}
+
+// 形式を指定して議論を自動進行する（フェーズの数だけラウンドを進め、すぐに戻る）
+#[command]
+pub async fn start_formatted_discussion(
+    app: AppHandle,
+    session_id: i64,
+    format: DiscussionFormat,
+    participants: Option<Vec<String>>,
+    config: Option<RoundConfig>,
+) -> Result<(), String> {
+    info!("start_formatted_discussion 呼び出し: session_id={}, format={:?}", session_id, format);
+    let mut config = config.unwrap_or_default();
+    config.format = Some(format);
+    let rounds = format.phases().len() as u32;
+    let started = discussion_engine::start_run(&app, session_id, rounds, participants, config, format.min_participants());
+    started.await.map_err(|e| {
+        if e == discussion_engine::ERR_TOO_FEW_SPEAKERS {
+            format!("{}にはAI参加者が{}人以上必要です", format.name(), format.min_participants())
+        } else {
+            e
+        }
+    })
+}
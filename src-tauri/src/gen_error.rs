@@ -0,0 +1,85 @@
+// 生成のエラー
+// タイムアウト・ユーザーのキャンセル・その他の失敗を型で区別し、呼び出し側が match で見分けられるようにする
+// 文字列にするのは Tauri コマンドの境界だけ（文面は従来どおりで、フロントエンドはタイムアウトを先頭の ERR_TIMEOUT で見分ける）
+use std::fmt;
+use std::time::Duration;
+
+pub const ERR_TIMEOUT: &str = "生成がタイムアウトしました";
+pub const ERR_CANCELLED: &str = "生成はキャンセルされました";
+
+/// タイムアウトした段階
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeoutStage {
+    /// 全体の期限を過ぎた
+    Deadline,
+    /// ストリーミングの応答が途切れた
+    Idle,
+}
+
+/// 生成の失敗
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GenError {
+    /// after だけ待って打ち切った
+    Timeout { stage: TimeoutStage, after: Duration },
+    /// ユーザーがキャンセルした
+    Cancelled,
+    /// バックエンド・安全フィルタ等の失敗
+    Failed(String),
+}
+
+impl fmt::Display for GenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Timeout { stage: TimeoutStage::Deadline, after } => {
+                write!(f, "{}（{}秒以内に生成が終わりませんでした）", ERR_TIMEOUT, after.as_secs())
+            }
+            Self::Timeout { stage: TimeoutStage::Idle, after } => {
+                write!(f, "{}（{}秒間応答がありませんでした）", ERR_TIMEOUT, after.as_secs())
+            }
+            Self::Cancelled => f.write_str(ERR_CANCELLED),
+            Self::Failed(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for GenError {}
+
+impl From<String> for GenError {
+    fn from(message: String) -> Self {
+        Self::Failed(message)
+    }
+}
+
+impl From<&str> for GenError {
+    fn from(message: &str) -> Self {
+        Self::Failed(message.to_string())
+    }
+}
+
+// Tauri コマンドの戻り値（Result<_, String>）へ ? で渡せるようにする
+impl From<GenError> for String {
+    fn from(err: GenError) -> Self {
+        err.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timeout_message_starts_with_the_prefix_the_ui_checks() {
+        let deadline = GenError::Timeout { stage: TimeoutStage::Deadline, after: Duration::from_secs(300) };
+        let idle = GenError::Timeout { stage: TimeoutStage::Idle, after: Duration::from_secs(60) };
+        assert!(String::from(deadline).starts_with(ERR_TIMEOUT));
+        assert!(String::from(idle).starts_with(ERR_TIMEOUT));
+        assert_eq!(String::from(GenError::Cancelled), ERR_CANCELLED);
+    }
+
+    #[test]
+    fn plain_errors_keep_their_message() {
+        let err: GenError = String::from("リクエスト失敗: connection refused").into();
+        assert_eq!(err, GenError::Failed("リクエスト失敗: connection refused".into()));
+        assert_eq!(err.to_string(), "リクエスト失敗: connection refused");
+    }
+}
@@ -0,0 +1,546 @@
+// 生成キュー
+// ローカルLLMへ同時に投げる生成の数を制限し、空きを待つ呼び出しは優先度順・同じ優先度なら到着順に待たせる
+// ユーザー操作による発言生成（Interactive）は、自動分析・自動要約などの裏方の生成（Background）より先に枠を得る
+// 同じキーの裏方の生成が待機中なら新しい依頼で内容を差し替え、1回の生成の結果を全員で受け取る
+// 生成が枠を得て始まるたびに queue://job-started で通知し、待機中・実行中の生成は get_queue_status で確認できる
+// キューは AppState が持つ（呼び出し側は state.queue.generate(...) のように使う）
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{command, AppHandle, Emitter, State};
+use tokio::sync::{broadcast, Notify};
+use tracing::info;
+
+use crate::{
+    app_state::{AppState, RuntimeSettings},
+    backend::{ChunkSink, LlmBackend, OllamaConnection},
+    gen_error::GenError,
+    generation::{GenerationOptions, GenerationResult},
+    timeouts::{self, Activity},
+};
+
+// 同時に実行する生成の上限の既定値（ローカルLLMは並列にしても速くならないため小さく保つ）
+// Ollama 側で OLLAMA_NUM_PARALLEL を上げた場合は接続設定の maxParallel で合わせる
+pub const DEFAULT_MAX_CONCURRENT_GENERATIONS: usize = 2;
+
+pub const EVENT_JOB_STARTED: &str = "queue://job-started";
+
+/// 生成の優先度（並びが先のものほど優先）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Priority {
+    /// ユーザーが結果を待っている生成（発言・画面からの分析など）
+    Interactive,
+    /// 自動分析・自動要約など、遅れても困らない生成
+    Background,
+}
+
+/// 待ち行列の状態
+struct QueueState {
+    limit: usize,
+    running: usize,
+    /// 待機中の (優先度, 受付番号)。先頭が次に枠を得る
+    waiting: BTreeSet<(Priority, u64)>,
+    /// 待機中・実行中の生成（受付番号 -> 内容）
+    jobs: BTreeMap<u64, JobInfo>,
+    next_ticket: u64,
+    /// 待機中の裏方の生成（キー -> 差し替え可能な依頼）
+    pending: HashMap<String, Arc<PendingJob>>,
+    /// 合流した依頼の累計
+    coalesced: u64,
+}
+
+/// 待機中の裏方の生成（開始までは後から来た同じキーの依頼で内容を差し替える）
+struct PendingJob {
+    request: Mutex<JobRequest>,
+    result: broadcast::Sender<Result<GenerationResult, GenError>>,
+}
+
+struct JobRequest {
+    model: String,
+    prompt: String,
+    options: GenerationOptions,
+}
+
+/// get_queue_status で返す生成1件分の情報
+struct JobInfo {
+    model: String,
+    priority: Priority,
+    queued_at: Instant,
+    started_at: Option<Instant>,
+}
+
+struct Queue {
+    state: Mutex<QueueState>,
+    /// 枠が空いた・上限が変わった・枠が埋まった時に待機中の呼び出しを起こす
+    changed: Notify,
+}
+
+impl Queue {
+    fn new(limit: usize) -> Self {
+        Queue {
+            state: Mutex::new(QueueState {
+                limit,
+                running: 0,
+                waiting: BTreeSet::new(),
+                jobs: BTreeMap::new(),
+                next_ticket: 0,
+                pending: HashMap::new(),
+                coalesced: 0,
+            }),
+            changed: Notify::new(),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, QueueState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 上限を変えて待機中の呼び出しを起こす（変わらなければ false）
+    fn set_limit(&self, limit: usize) -> bool {
+        let mut state = self.lock();
+        if state.limit == limit {
+            return false;
+        }
+        state.limit = limit;
+        drop(state);
+        self.changed.notify_waiters();
+        true
+    }
+}
+
+/// 生成キュー（枠を得て始まった生成を app へ queue://job-started で通知する）
+pub struct GenQueue {
+    queue: Arc<Queue>,
+    app: Option<AppHandle>,
+    /// 生成の期限は接続設定から読む
+    settings: Arc<RuntimeSettings>,
+}
+
+/// 実行枠（破棄時に返却して次の待機者を起こす）
+struct Slot {
+    queue: Arc<Queue>,
+    job_id: u64,
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        let mut state = self.queue.lock();
+        state.running -= 1;
+        state.jobs.remove(&self.job_id);
+        drop(state);
+        self.queue.changed.notify_waiters();
+    }
+}
+
+/// 待機の登録（枠を得る前に呼び出しが破棄された場合も待ち行列から外す）
+struct Ticket<'a> {
+    queue: &'a Queue,
+    entry: Option<(Priority, u64)>,
+}
+
+impl Drop for Ticket<'_> {
+    fn drop(&mut self) {
+        if let Some(entry) = self.entry.take() {
+            let mut state = self.queue.lock();
+            state.waiting.remove(&entry);
+            state.jobs.remove(&entry.1);
+            drop(state);
+            self.queue.changed.notify_waiters();
+        }
+    }
+}
+
+/// queue://job-started のペイロード
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JobStartedEvent {
+    job_id: u64,
+    model: String,
+    priority: Priority,
+    /// 枠を得るまでの待ち時間
+    waited_ms: u64,
+    running: usize,
+    waiting: usize,
+}
+
+impl GenQueue {
+    pub fn new(limit: usize, app: Option<AppHandle>, settings: Arc<RuntimeSettings>) -> Self {
+        Self { queue: Arc::new(Queue::new(limit)), app, settings }
+    }
+
+    fn connection(&self) -> OllamaConnection {
+        self.settings.client.read().connection().clone()
+    }
+
+    /// 同時実行の上限を反映（起動時と設定保存時）。実行中の生成はそのまま完了させる
+    pub fn set_limit(&self, limit: usize) {
+        let previous = self.queue.lock().limit;
+        if self.queue.set_limit(limit) {
+            info!("生成の同時実行数: {} -> {}", previous, limit);
+        }
+    }
+
+    /// 現在の同時実行の上限
+    pub fn limit(&self) -> usize {
+        self.queue.lock().limit
+    }
+
+    /// 優先度順・到着順で実行枠を得る
+    async fn acquire(&self, priority: Priority, model: &str) -> Slot {
+        let (slot, event) = acquire_in(&self.queue, priority, model).await;
+        if let (Some(app), Some(event)) = (&self.app, event) {
+            let _ = app.emit(EVENT_JOB_STARTED, event);
+        }
+        slot
+    }
+
+    /// 空きを待ってから一括生成（Interactive）。生成そのものには接続設定の全体の期限を適用する
+    pub async fn generate(
+        &self,
+        backend: &dyn LlmBackend,
+        model: &str,
+        prompt: &str,
+        options: &GenerationOptions,
+    ) -> QueuedOutput {
+        let queued_at = Instant::now();
+        let _slot = self.acquire(Priority::Interactive, model).await;
+        let waited = queued_at.elapsed();
+        let started = Instant::now();
+        let result = timeouts::with_deadline(&self.connection(), backend.generate(model, prompt, options), None)
+            .await
+            .map(|r| with_duration(r, started, queued_at));
+        QueuedOutput { result, waited, elapsed: started.elapsed() }
+    }
+
+    /// 裏方の一括生成（Background）。Interactive の待機がなくなってから枠を得る
+    /// 同じ key の生成が待機中なら、その依頼を今回の内容に差し替えて結果を共有する（例: key = "analysis:{セッションID}"）
+    pub async fn generate_background(
+        &self,
+        backend: &dyn LlmBackend,
+        key: &str,
+        model: &str,
+        prompt: &str,
+        options: &GenerationOptions,
+    ) -> Result<GenerationResult, GenError> {
+        let queued_at = Instant::now();
+        let request = JobRequest { model: model.to_string(), prompt: prompt.to_string(), options: options.clone() };
+        let registration = {
+            let mut state = self.queue.lock();
+            match state.pending.get(key).cloned() {
+                Some(job) => {
+                    // 待機中の依頼に合流（内容は新しい方を使う）
+                    *job.request.lock().unwrap_or_else(|e| e.into_inner()) = request;
+                    state.coalesced += 1;
+                    Registration::Follower(job.result.subscribe())
+                }
+                None => {
+                    let (sender, _) = broadcast::channel(1);
+                    let job = Arc::new(PendingJob { request: Mutex::new(request), result: sender });
+                    state.pending.insert(key.to_string(), job.clone());
+                    Registration::Leader(job)
+                }
+            }
+        };
+        let job = match registration {
+            Registration::Leader(job) => job,
+            Registration::Follower(mut receiver) => {
+                info!("待機中の生成に合流: {}", key);
+                return receiver.recv().await.unwrap_or_else(|_| Err("合流先の生成が中断されました".into()));
+            }
+        };
+
+        // 実行枠を得るまで待機（この間に来た同じキーの依頼は合流する）
+        let guard = PendingGuard { queue: &self.queue, key, job: &job, acquired: false };
+        let _slot = self.acquire(Priority::Background, model).await;
+        let request = guard.start();
+
+        let started = Instant::now();
+        let generation = backend.generate(&request.model, &request.prompt, &request.options);
+        let result = timeouts::with_deadline(&self.connection(), generation, None)
+            .await
+            .map(|r| with_duration(r, started, queued_at));
+        let _ = job.result.send(result.clone());
+        result
+    }
+
+    /// 空きを待ってからストリーミング生成（Interactive）。期限の時点で断片が届いていれば猶予を与える
+    pub async fn generate_stream(
+        &self,
+        backend: &dyn LlmBackend,
+        model: &str,
+        prompt: &str,
+        options: &GenerationOptions,
+        on_chunk: ChunkSink<'_>,
+    ) -> Result<GenerationResult, GenError> {
+        let queued_at = Instant::now();
+        let _slot = self.acquire(Priority::Interactive, model).await;
+        let started = Instant::now();
+        let activity = Activity::new();
+        let tracked = |piece: &str| {
+            activity.touch();
+            on_chunk(piece);
+        };
+        let generation = backend.generate_stream(model, prompt, options, &tracked);
+        timeouts::with_deadline(&self.connection(), generation, Some(&activity))
+            .await
+            .map(|r| with_duration(r, started, queued_at))
+    }
+
+    /// 実行中・待機中の生成の状況
+    pub fn status(&self) -> QueueStatus {
+        let state = self.queue.lock();
+        let waiting_interactive = state.waiting.iter().filter(|(p, _)| *p == Priority::Interactive).count();
+        QueueStatus {
+            limit: state.limit,
+            running: state.running,
+            waiting_interactive,
+            waiting_background: state.waiting.len() - waiting_interactive,
+            coalesced: state.coalesced,
+            jobs: state
+                .jobs
+                .iter()
+                .map(|(id, job)| QueuedJob {
+                    job_id: *id,
+                    model: job.model.clone(),
+                    priority: job.priority,
+                    running: job.started_at.is_some(),
+                    waited_ms: job.started_at.unwrap_or_else(Instant::now).duration_since(job.queued_at).as_millis() as u64,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// 待ち行列の先頭から「上限 - 実行中」件までに入っていれば枠を得る（得た時の開始通知も返す）
+/// 上限が一度に複数増えた時も、空いた枠の数だけの待機者が先頭の順番待ちをせずに進める
+async fn acquire_in(queue: &Arc<Queue>, priority: Priority, model: &str) -> (Slot, Option<JobStartedEvent>) {
+    let mut ticket = {
+        let mut state = queue.lock();
+        let number = state.next_ticket;
+        state.next_ticket += 1;
+        state.waiting.insert((priority, number));
+        state
+            .jobs
+            .insert(number, JobInfo { model: model.to_string(), priority, queued_at: Instant::now(), started_at: None });
+        Ticket { queue, entry: Some((priority, number)) }
+    };
+    loop {
+        // 判定より先に通知を受け取る準備をして、判定直後の返却を取りこぼさない
+        let notified = queue.changed.notified();
+        {
+            let mut state = queue.lock();
+            let entry = ticket.entry.expect("待機中の受付番号");
+            let free = state.limit.saturating_sub(state.running);
+            if state.waiting.iter().take(free).any(|e| *e == entry) {
+                state.waiting.remove(&entry);
+                state.running += 1;
+                ticket.entry = None;
+                let (limit, running, waiting) = (state.limit, state.running, state.waiting.len());
+                let event = state.jobs.get_mut(&entry.1).map(|job| {
+                    job.started_at = Some(Instant::now());
+                    JobStartedEvent {
+                        job_id: entry.1,
+                        model: job.model.clone(),
+                        priority,
+                        waited_ms: job.queued_at.elapsed().as_millis() as u64,
+                        running,
+                        waiting,
+                    }
+                });
+                drop(state);
+                // まだ枠が残っていれば、次の待機者にも判定し直させる
+                if running < limit && waiting > 0 {
+                    queue.changed.notify_waiters();
+                }
+                return (Slot { queue: queue.clone(), job_id: entry.1 }, event);
+            }
+        }
+        notified.await;
+    }
+}
+
+/// キュー経由の生成結果（待ち時間と生成時間を含む）
+pub struct QueuedOutput {
+    pub result: Result<GenerationResult, GenError>,
+    pub waited: Duration,
+    pub elapsed: Duration,
+}
+
+/// 裏方の生成の受付結果
+enum Registration {
+    /// 自分が生成する
+    Leader(Arc<PendingJob>),
+    /// 待機中の同じキーの生成に合流した
+    Follower(broadcast::Receiver<Result<GenerationResult, GenError>>),
+}
+
+/// 待機中の裏方の生成の登録（開始時、または開始前に破棄された時に合流受付を閉じる）
+struct PendingGuard<'a> {
+    queue: &'a Queue,
+    key: &'a str,
+    job: &'a Arc<PendingJob>,
+    acquired: bool,
+}
+
+impl PendingGuard<'_> {
+    /// 合流受付を閉じて、最新の依頼内容を取り出す
+    fn start(mut self) -> JobRequest {
+        self.acquired = true;
+        self.close();
+        let mut request = self.job.request.lock().unwrap_or_else(|e| e.into_inner());
+        std::mem::replace(
+            &mut *request,
+            JobRequest { model: String::new(), prompt: String::new(), options: GenerationOptions::default() },
+        )
+    }
+
+    fn close(&self) {
+        let mut state = self.queue.lock();
+        if state.pending.get(self.key).is_some_and(|j| Arc::ptr_eq(j, self.job)) {
+            state.pending.remove(self.key);
+        }
+    }
+}
+
+impl Drop for PendingGuard<'_> {
+    fn drop(&mut self) {
+        if !self.acquired {
+            self.close();
+        }
+    }
+}
+
+/// バックエンドが所要時間・生成速度を返さなかった場合は計測値で補い、待ち時間を含む実時間を記録する
+fn with_duration(mut result: GenerationResult, started: Instant, queued_at: Instant) -> GenerationResult {
+    let meta = &mut result.meta;
+    if meta.duration_ms.is_none() {
+        meta.duration_ms = Some(started.elapsed().as_millis() as u64);
+    }
+    if meta.tokens_per_sec.is_none() {
+        meta.tokens_per_sec = match (meta.completion_tokens, meta.duration_ms) {
+            (Some(tokens), Some(ms)) if ms > 0 => Some(tokens as f64 * 1000.0 / ms as f64),
+            _ => None,
+        };
+    }
+    meta.wall_ms = Some(queued_at.elapsed().as_millis() as u64);
+    result
+}
+
+/// get_queue_status の戻り値
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueStatus {
+    /// 同時実行の上限
+    pub limit: usize,
+    /// 実行中の生成数
+    pub running: usize,
+    /// 待機中の Interactive の生成数
+    pub waiting_interactive: usize,
+    /// 待機中の Background の生成数（合流した依頼は含まない）
+    pub waiting_background: usize,
+    /// 待機中の生成に合流した依頼の累計
+    pub coalesced: u64,
+    /// 実行中・待機中の生成（受付順）
+    pub jobs: Vec<QueuedJob>,
+}
+
+/// 実行中・待機中の生成1件
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedJob {
+    pub job_id: u64,
+    pub model: String,
+    pub priority: Priority,
+    pub running: bool,
+    /// 受付から枠を得るまで（待機中なら現在まで）の時間
+    pub waited_ms: u64,
+}
+
+// 生成キューの状況（実行中・待機中の数）
+#[command]
+pub fn get_queue_status(state: State<'_, AppState>) -> QueueStatus {
+    state.queue.status()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 枠を得たら知らせて、hold が閉じるまで枠を持ち続ける待機者
+    fn spawn_waiter(
+        queue: &Arc<Queue>,
+        priority: Priority,
+        acquired: tokio::sync::mpsc::UnboundedSender<u64>,
+        hold: &tokio::sync::watch::Receiver<bool>,
+    ) {
+        let queue = queue.clone();
+        let mut hold = hold.clone();
+        tokio::spawn(async move {
+            let (slot, _) = acquire_in(&queue, priority, "gemma3:4b").await;
+            let _ = acquired.send(slot.job_id);
+            let _ = hold.wait_for(|released| *released).await;
+        });
+    }
+
+    #[tokio::test]
+    async fn raising_the_limit_starts_every_waiter_that_fits() {
+        let queue = Arc::new(Queue::new(1));
+        let (first, _) = acquire_in(&queue, Priority::Interactive, "gemma3:4b").await;
+        let (acquired_tx, mut acquired) = tokio::sync::mpsc::unbounded_channel();
+        let (release, hold) = tokio::sync::watch::channel(false);
+        // 先頭（Interactive）が最後に待機に入るので、上限を上げた時は後ろの待機者から先に判定する
+        for priority in [Priority::Background, Priority::Background, Priority::Interactive] {
+            spawn_waiter(&queue, priority, acquired_tx.clone(), &hold);
+            let queued = queue.lock().waiting.len() + 1;
+            while queue.lock().waiting.len() < queued {
+                tokio::task::yield_now().await;
+            }
+        }
+
+        assert!(queue.set_limit(3));
+        let wait = Duration::from_secs(5);
+        for _ in 0..2 {
+            tokio::time::timeout(wait, acquired.recv()).await.expect("空いた枠で始まるはず").unwrap();
+        }
+        assert_eq!(queue.lock().running, 3);
+        assert_eq!(queue.lock().waiting.len(), 1);
+
+        // 最初の枠を返すと、残りの1件が始まる
+        drop(first);
+        tokio::time::timeout(wait, acquired.recv()).await.expect("返却された枠で始まるはず").unwrap();
+        assert!(queue.lock().waiting.is_empty());
+        let _ = release.send(true);
+    }
+
+    #[tokio::test]
+    async fn waiters_start_in_priority_order() {
+        let queue = Arc::new(Queue::new(1));
+        let (first, _) = acquire_in(&queue, Priority::Background, "gemma3:4b").await;
+        let background = tokio::spawn({
+            let queue = queue.clone();
+            async move { acquire_in(&queue, Priority::Background, "gemma3:4b").await.0.job_id }
+        });
+        while queue.lock().waiting.is_empty() {
+            tokio::task::yield_now().await;
+        }
+        let interactive = tokio::spawn({
+            let queue = queue.clone();
+            async move {
+                let (slot, _) = acquire_in(&queue, Priority::Interactive, "gemma3:4b").await;
+                let id = slot.job_id;
+                drop(slot);
+                id
+            }
+        });
+        while queue.lock().waiting.len() < 2 {
+            tokio::task::yield_now().await;
+        }
+        drop(first);
+        // 後から来た Interactive が先に枠を得て、返却後に Background が続く
+        assert_eq!(interactive.await.unwrap(), 2);
+        assert_eq!(background.await.unwrap(), 1);
+    }
+}
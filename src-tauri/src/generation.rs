@@ -0,0 +1,56 @@
+// 生成ログ
+// 発言ごとにシード・オプション・プロンプト・出力を記録し、後から同条件で再実行できるようにする
+// プロンプトと出力は発言本文を含むため、暗号化が有効なら暗号化して保存する
+// 生成オプション・生成結果の型は dewai-core（CLI と共通）にある
+use tauri::AppHandle;
+use tracing::warn;
+
+pub use dewai_core::generation::{GenerationMeta, GenerationOptions, GenerationResult, OutputFormat};
+
+use crate::{db, encryption};
+
+/// 生成ログ1件分の記録内容
+pub struct GenerationRecord<'a> {
+    pub session_id: i64,
+    pub message_index: i64,
+    pub speaker: &'a str,
+    pub model: &'a str,
+    pub prompt: &'a str,
+    pub options: &'a GenerationOptions,
+    pub output: &'a str,
+    pub meta: &'a GenerationMeta,
+}
+
+/// 生成ログを保存（記録失敗は生成結果に影響させない）
+pub async fn record(app: &AppHandle, rec: GenerationRecord<'_>) {
+    let result = async {
+        let pool = db::pool(app).await?;
+        let keys = encryption::keys(app);
+        let options_json = serde_json::to_string(rec.options).map_err(|e| e.to_string())?;
+        sqlx::query(
+            "INSERT INTO generation_log (session_id, message_index, speaker, model, prompt, options, seed, output,
+                                         prompt_tokens, completion_tokens, duration_ms, created_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(rec.session_id)
+        .bind(rec.message_index)
+        .bind(rec.speaker)
+        .bind(rec.model)
+        .bind(keys.seal(rec.prompt)?)
+        .bind(options_json)
+        .bind(rec.options.seed)
+        .bind(keys.seal(rec.output)?)
+        .bind(rec.meta.prompt_tokens)
+        .bind(rec.meta.completion_tokens)
+        .bind(rec.meta.duration_ms.map(|ms| ms as i64))
+        .bind(db::now_string())
+        .execute(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+        Ok::<_, String>(())
+    }
+    .await;
+    if let Err(e) = result {
+        warn!("生成ログ保存失敗 (session_id={}): {}", rec.session_id, e);
+    }
+}
@@ -0,0 +1,149 @@
+// Ollama の死活監視
+// バックグラウンドで数秒ごとに現在のバックエンド（通常は Ollama）へ問い合わせ、状態（停止・起動・モデル読込済み）が変わった時に ollama://status-changed で通知する
+// フロントエンドは is_model_loaded を繰り返し呼ばずに、このイベントと get_ollama_status で接続状態を知る
+use std::sync::{OnceLock, RwLock};
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::{command, AppHandle, Emitter};
+use tracing::info;
+
+use crate::{backend, db};
+
+pub const EVENT_STATUS_CHANGED: &str = "ollama://status-changed";
+
+// 問い合わせの間隔
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Ollama の状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OllamaState {
+    /// まだ問い合わせていない
+    Unknown,
+    /// 応答なし
+    Down,
+    /// 起動しているがメモリ上のモデルはない
+    Up,
+    /// モデルがメモリに読み込まれている（すぐに生成できる）
+    ModelLoaded,
+}
+
+/// メモリに読み込まれているモデル（/api/ps の主要項目）
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadedModel {
+    pub name: String,
+    /// モデル全体のサイズ（バイト）
+    pub size: u64,
+    /// VRAM に載っている分（バイト）
+    pub size_vram: u64,
+    /// keep_alive による解放予定時刻
+    pub expires_at: Option<String>,
+}
+
+/// get_ollama_status の戻り値と ollama://status-changed のペイロード
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OllamaStatus {
+    pub state: OllamaState,
+    /// Ollama のバージョン（/api/version）
+    pub version: Option<String>,
+    pub loaded_models: Vec<LoadedModel>,
+    /// 最後に応答があった時刻（"YYYY-MM-DD HH:MM:SS", UTC）
+    pub last_seen: Option<String>,
+    /// 最後に問い合わせた時刻
+    pub checked_at: Option<String>,
+    /// 応答がなかった時の理由
+    pub error: Option<String>,
+}
+
+impl Default for OllamaStatus {
+    fn default() -> Self {
+        Self {
+            state: OllamaState::Unknown,
+            version: None,
+            loaded_models: Vec::new(),
+            last_seen: None,
+            checked_at: None,
+            error: None,
+        }
+    }
+}
+
+impl OllamaStatus {
+    /// 通知に値する変化か（状態か読み込み済みモデルの組み合わせが変わった）
+    fn differs_from(&self, other: &OllamaStatus) -> bool {
+        self.state != other.state
+            || self.loaded_models.iter().map(|m| &m.name).ne(other.loaded_models.iter().map(|m| &m.name))
+    }
+}
+
+fn status_slot() -> &'static RwLock<OllamaStatus> {
+    static STATUS: OnceLock<RwLock<OllamaStatus>> = OnceLock::new();
+    STATUS.get_or_init(|| RwLock::new(OllamaStatus::default()))
+}
+
+/// 直近の問い合わせ結果
+pub fn current() -> OllamaStatus {
+    status_slot().read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// 現在のバックエンドに問い合わせて状態を得る（last_seen は前回の値を引き継ぐ）
+async fn probe(app: &AppHandle, previous: &OllamaStatus) -> OllamaStatus {
+    let checked_at = db::now_string();
+    let health = backend::current(app).health().await;
+    let state = match (health.available, health.loaded_models.is_empty()) {
+        (false, _) => OllamaState::Down,
+        (true, true) => OllamaState::Up,
+        (true, false) => OllamaState::ModelLoaded,
+    };
+    OllamaStatus {
+        state,
+        version: health.version,
+        loaded_models: health.loaded_models,
+        last_seen: if health.available { Some(checked_at.clone()) } else { previous.last_seen.clone() },
+        checked_at: Some(checked_at),
+        error: health.error,
+    }
+}
+
+/// 1回問い合わせて結果を保存し、変化があれば通知する
+pub async fn refresh(app: &AppHandle) -> OllamaStatus {
+    let previous = current();
+    let status = probe(app, &previous).await;
+    *status_slot().write().unwrap_or_else(|e| e.into_inner()) = status.clone();
+    if status.differs_from(&previous) {
+        info!(
+            "Ollama 状態: {:?} -> {:?} (読込済み: {:?})",
+            previous.state,
+            status.state,
+            status.loaded_models.iter().map(|m| m.name.as_str()).collect::<Vec<_>>()
+        );
+        let _ = app.emit(EVENT_STATUS_CHANGED, status.clone());
+    }
+    status
+}
+
+/// 死活監視をバックグラウンドで起動（アプリ起動時に1回呼ぶ）
+pub fn start_monitor(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut tick = tokio::time::interval(POLL_INTERVAL);
+        tick.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            tick.tick().await;
+            refresh(&app).await;
+        }
+    });
+}
+
+// Ollama の状態（バージョン・読み込み済みモデル・最終応答時刻）を取得
+// 監視がまだ一度も問い合わせていなければ、その場で問い合わせる
+#[command]
+pub async fn get_ollama_status(app: AppHandle) -> OllamaStatus {
+    let status = current();
+    if status.state == OllamaState::Unknown {
+        return refresh(&app).await;
+    }
+    status
+}
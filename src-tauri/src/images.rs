@@ -0,0 +1,50 @@
+// 発言生成への画像入力
+// マルチモーダルのモデル（gemma3:4b など）向けに、ユーザーが会話に添付した画像を読み込んで base64 にし、
+// Ollama の /api/generate の images フィールドで渡す
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+// 1回の発言に添付できる画像の数と、1枚あたりの上限
+pub const MAX_IMAGES: usize = 4;
+const MAX_IMAGE_BYTES: u64 = 10 * 1024 * 1024;
+// 画像を扱えるモデルの接頭辞（gemma3 は 1b だけがテキスト専用）
+const VISION_MODEL_PREFIXES: &[&str] = &["gemma3", "llava", "llama3.2-vision", "qwen2.5vl", "minicpm-v"];
+const TEXT_ONLY_MODELS: &[&str] = &["gemma3:1b"];
+
+/// モデルが画像入力に対応しているか
+pub fn supports_images(model: &str) -> bool {
+    let model = model.trim().to_lowercase();
+    VISION_MODEL_PREFIXES.iter().any(|p| model.starts_with(p)) && !TEXT_ONLY_MODELS.iter().any(|m| model.starts_with(m))
+}
+
+/// 先頭のバイト列から形式を判定する（Ollama が読める PNG / JPEG / WebP のみ）
+fn image_format(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("png")
+    } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("jpeg")
+    } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        Some("webp")
+    } else {
+        None
+    }
+}
+
+/// 画像ファイルを読み込んで base64 の一覧にする
+pub async fn load_images(paths: &[String]) -> Result<Vec<String>, String> {
+    if paths.len() > MAX_IMAGES {
+        return Err(format!("添付できる画像は{}枚までです", MAX_IMAGES));
+    }
+    let mut images = Vec::with_capacity(paths.len());
+    for path in paths {
+        let size = tokio::fs::metadata(path).await.map_err(|e| format!("画像を開けません ({}): {}", path, e))?.len();
+        if size > MAX_IMAGE_BYTES {
+            return Err(format!("画像が大きすぎます ({}): {} bytes（上限 {} bytes）", path, size, MAX_IMAGE_BYTES));
+        }
+        let bytes = tokio::fs::read(path).await.map_err(|e| format!("画像の読み込みに失敗 ({}): {}", path, e))?;
+        if image_format(&bytes).is_none() {
+            return Err(format!("対応していない画像形式です ({})。PNG / JPEG / WebP を指定してください", path));
+        }
+        images.push(STANDARD.encode(&bytes));
+    }
+    Ok(images)
+}
@@ -1,5 +1,5 @@
-// プロンプトモジュールを公開
-pub mod prompts;
+// プロンプトモジュールを公開（本体は dewai-core）
+pub use dewai_core::{prompts, tokens};
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #[tauri::command]
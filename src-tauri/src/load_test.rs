@@ -0,0 +1,199 @@
+// 負荷・耐久試験
+// 複数セッションが同時に生成を要求する状況を再現し、生成キュー経由のスループット・待ち時間・メモリ増加を計測する
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Manager};
+use tracing::info;
+
+use crate::{
+    app_state::AppState,
+    backend::{self, LlmBackend},
+    gen_queue,
+    generation::GenerationOptions,
+    is_allowed_model,
+    mock_backend::MockBackend,
+    prompts::{self, PromptStyle},
+    ERR_UNSUPPORTED_MODEL,
+};
+
+// 試験規模の上限（誤って長時間の試験を始めないように）
+const MAX_SESSIONS: usize = 32;
+const MAX_GENERATIONS_PER_SESSION: usize = 50;
+
+const SPEAKERS: [(&str, &str); 3] = [("佐藤", "高校教師"), ("鈴木", "経済アナリスト"), ("高橋", "大学生")];
+
+/// 試験条件
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadTestConfig {
+    /// 同時に進行させるセッション数
+    pub sessions: usize,
+    /// 1セッションあたりの生成回数（順番に実行）
+    pub generations_per_session: usize,
+    #[serde(default)]
+    pub model: Option<String>,
+    /// true ならモックバックエンドで実行（未指定なら設定中のバックエンド）
+    #[serde(default)]
+    pub use_mock: bool,
+}
+
+/// 試験結果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoadTestReport {
+    pub backend: &'static str,
+    pub model: String,
+    pub sessions: usize,
+    pub requested: usize,
+    pub completed: usize,
+    pub failed: usize,
+    pub total_ms: u128,
+    /// 完了した生成の数 / 秒
+    pub throughput_per_sec: f64,
+    pub avg_queue_wait_ms: f64,
+    pub p95_queue_wait_ms: u128,
+    pub max_queue_wait_ms: u128,
+    pub avg_generation_ms: f64,
+    pub p95_generation_ms: u128,
+    /// 常駐メモリ（取得できない環境では None）
+    pub rss_start_kb: Option<u64>,
+    pub rss_end_kb: Option<u64>,
+    pub rss_peak_kb: Option<u64>,
+    /// 最初の数件のエラー
+    pub errors: Vec<String>,
+}
+
+/// 現在の常駐メモリ（Linux のみ）
+fn resident_kb() -> Option<u64> {
+    let statm = std::fs::read_to_string("/proc/self/statm").ok()?;
+    let pages: u64 = statm.split_whitespace().nth(1)?.parse().ok()?;
+    Some(pages * 4)
+}
+
+fn percentile(sorted: &[Duration], p: f64) -> u128 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let index = ((sorted.len() as f64 * p).ceil() as usize).clamp(1, sorted.len()) - 1;
+    sorted[index].as_millis()
+}
+
+fn average_ms(values: &[Duration]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.iter().map(|d| d.as_secs_f64() * 1000.0).sum::<f64>() / values.len() as f64
+}
+
+/// 1セッション分の生成を順番に実行（履歴を伸ばしながら）
+async fn run_session(
+    app: AppHandle,
+    backend: Arc<dyn LlmBackend>,
+    model: String,
+    session: usize,
+    generations: usize,
+    rss_peak: Arc<AtomicU64>,
+) -> Vec<gen_queue::QueuedOutput> {
+    let topic = format!("負荷試験のテーマ{}", session + 1);
+    let mut history = String::new();
+    let mut outputs = Vec::with_capacity(generations);
+    for turn in 0..generations {
+        let (name, role) = SPEAKERS[turn % SPEAKERS.len()];
+        let prompt = prompts::build_ai_response_prompt(name, role, "", &history, &topic, &PromptStyle::default());
+        let options = GenerationOptions::seeded(Some((session * MAX_GENERATIONS_PER_SESSION + turn) as i64));
+        let output = app.state::<AppState>().queue.generate(&*backend, &model, &prompt, &options).await;
+        if let Ok(result) = &output.result {
+            history.push_str(&format!("{}: {}\n", name, result.text));
+        }
+        if let Some(kb) = resident_kb() {
+            rss_peak.fetch_max(kb, Ordering::Relaxed);
+        }
+        outputs.push(output);
+    }
+    outputs
+}
+
+// 複数セッションの同時生成を再現し、スループット・キュー待ち時間・メモリ増加を計測
+#[command]
+pub async fn run_load_test(app: AppHandle, config: LoadTestConfig) -> Result<LoadTestReport, String> {
+    info!(
+        "run_load_test 呼び出し: sessions={}, generations={}, mock={}",
+        config.sessions, config.generations_per_session, config.use_mock
+    );
+    if config.sessions == 0 || config.sessions > MAX_SESSIONS {
+        return Err(format!("セッション数は1〜{}で指定してください", MAX_SESSIONS));
+    }
+    if config.generations_per_session == 0 || config.generations_per_session > MAX_GENERATIONS_PER_SESSION {
+        return Err(format!("生成回数は1〜{}で指定してください", MAX_GENERATIONS_PER_SESSION));
+    }
+    let model = config.model.clone().unwrap_or_else(|| "gemma3:1b".to_string());
+    if !is_allowed_model(&app, &model) {
+        return Err(ERR_UNSUPPORTED_MODEL.to_string());
+    }
+    let backend: Arc<dyn LlmBackend> = if config.use_mock { Arc::new(MockBackend) } else { backend::current(&app) };
+
+    let rss_start = resident_kb();
+    let rss_peak = Arc::new(AtomicU64::new(rss_start.unwrap_or(0)));
+    let started = Instant::now();
+    let handles: Vec<_> = (0..config.sessions)
+        .map(|session| {
+            tauri::async_runtime::spawn(run_session(
+                app.clone(),
+                backend.clone(),
+                model.clone(),
+                session,
+                config.generations_per_session,
+                rss_peak.clone(),
+            ))
+        })
+        .collect();
+
+    let mut waits = Vec::new();
+    let mut latencies = Vec::new();
+    let mut errors = Vec::new();
+    let mut failed = 0;
+    for handle in handles {
+        let outputs = handle.await.map_err(|e| format!("試験タスク失敗: {}", e))?;
+        for output in outputs {
+            waits.push(output.waited);
+            match output.result {
+                Ok(_) => latencies.push(output.elapsed),
+                Err(e) => {
+                    failed += 1;
+                    if errors.len() < 5 {
+                        errors.push(e.to_string());
+                    }
+                }
+            }
+        }
+    }
+    let total = started.elapsed();
+    waits.sort();
+    latencies.sort();
+
+    let completed = latencies.len();
+    let rss_end = resident_kb();
+    info!("負荷試験完了: {}件成功 / {}件失敗, {}ms", completed, failed, total.as_millis());
+    Ok(LoadTestReport {
+        backend: backend.name(),
+        model,
+        sessions: config.sessions,
+        requested: config.sessions * config.generations_per_session,
+        completed,
+        failed,
+        total_ms: total.as_millis(),
+        throughput_per_sec: completed as f64 / total.as_secs_f64().max(f64::EPSILON),
+        avg_queue_wait_ms: average_ms(&waits),
+        p95_queue_wait_ms: percentile(&waits, 0.95),
+        max_queue_wait_ms: waits.last().map(|d| d.as_millis()).unwrap_or(0),
+        avg_generation_ms: average_ms(&latencies),
+        p95_generation_ms: percentile(&latencies, 0.95),
+        rss_start_kb: rss_start,
+        rss_end_kb: rss_end,
+        rss_peak_kb: rss_start.map(|_| rss_peak.load(Ordering::Relaxed)),
+        errors,
+    })
+}
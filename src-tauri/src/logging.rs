@@ -0,0 +1,135 @@
+// 構造化ログ（tracing）
+// 標準出力に加え、アプリデータ配下の logs/ に日ごとにローテーションするファイルへ書き出す
+// Windows のリリースビルドはコンソールを持たず標準出力が見えないため、不具合調査ではファイルのログを使う
+// どちらの出力先も、書き出す前に1行ずつ個人情報を伏せ字にする（privacy の設定に従う。呼び出し側で伏せる必要はない）
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+
+use tauri::{command, AppHandle};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{Builder, Rotation};
+use tracing_subscriber::{
+    filter::LevelFilter, fmt, fmt::MakeWriter, layer::SubscriberExt, reload, util::SubscriberInitExt, Registry,
+};
+
+use crate::{app_state::RuntimeSettings, config, privacy};
+
+pub const DEFAULT_LOG_LEVEL: &str = "info";
+
+const LOG_FILE_PREFIX: &str = "dewai";
+const LOG_FILE_SUFFIX: &str = "log";
+// 残すログファイルの数（日ごとにローテーションするので日数）
+const MAX_LOG_FILES: usize = 14;
+
+/// 実行中にログレベルを変えるためのハンドル
+static LEVEL: OnceLock<reload::Handle<LevelFilter, Registry>> = OnceLock::new();
+/// ファイル書き込みスレッドの保持（破棄すると未書き込みのログが失われる）
+static FILE_GUARD: OnceLock<WorkerGuard> = OnceLock::new();
+
+/// 書き出す内容を伏せ字にしてから渡すライター（fmt レイヤーはイベント1件を1回の書き込みで渡す）
+struct Redacting<'a, W>(W, &'a RuntimeSettings);
+
+impl<W: io::Write> io::Write for Redacting<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write_all(privacy::redact(self.1, &String::from_utf8_lossy(buf)).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+/// 出力先を Redacting で包む（伏せ字の設定は AppState と共有する）
+struct RedactingWriter<M>(M, Arc<RuntimeSettings>);
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for RedactingWriter<M> {
+    type Writer = Redacting<'a, M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        Redacting(self.0.make_writer(), &self.1)
+    }
+}
+
+/// ログレベル名を解釈（off / error / warn / info / debug / trace）
+pub fn parse_level(level: &str) -> Result<LevelFilter, String> {
+    level
+        .trim()
+        .parse::<LevelFilter>()
+        .map_err(|_| format!("ログレベルが不正です: '{}'（off / error / warn / info / debug / trace）", level))
+}
+
+/// ログ出力を初期化（起動時に1回）。log_dir が作れない場合は標準出力のみ
+pub fn init(log_dir: Option<&Path>, settings: &Arc<RuntimeSettings>) {
+    let (level, handle) = reload::Layer::new(LevelFilter::INFO);
+    let file = log_dir.and_then(|dir| {
+        let appender = Builder::new()
+            .rotation(Rotation::DAILY)
+            .filename_prefix(LOG_FILE_PREFIX)
+            .filename_suffix(LOG_FILE_SUFFIX)
+            .max_log_files(MAX_LOG_FILES)
+            .build(dir);
+        match appender {
+            Ok(appender) => {
+                let (writer, guard) = tracing_appender::non_blocking(appender);
+                let _ = FILE_GUARD.set(guard);
+                Some(fmt::layer().with_writer(RedactingWriter(writer, settings.clone())).with_ansi(false))
+            }
+            Err(e) => {
+                eprintln!("ログファイルを作成できません（標準出力のみ）: {}", e);
+                None
+            }
+        }
+    });
+    let initialized = tracing_subscriber::registry()
+        .with(level)
+        .with(fmt::layer().with_target(false).with_writer(RedactingWriter(io::stdout, settings.clone())))
+        .with(file)
+        .try_init();
+    if initialized.is_ok() {
+        let _ = LEVEL.set(handle);
+    }
+}
+
+/// ログレベルを反映（起動時と設定保存時）
+pub fn set_level(level: &str) {
+    let Some(handle) = LEVEL.get() else { return };
+    let Ok(filter) = parse_level(level) else { return };
+    if handle.clone_current() != Some(filter) && handle.modify(|current| *current = filter).is_ok() {
+        tracing::info!("ログレベル: {}", filter);
+    }
+}
+
+/// Ollama 呼び出し1回分のスパン（ログの各行に request_id が付き、並行する生成を見分けられる）
+pub fn ollama_span(model: &str) -> tracing::Span {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+    let request_id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    tracing::info_span!("ollama", request_id, model)
+}
+
+// ログレベルを変更して保存する
+#[command]
+pub async fn set_log_level(app: AppHandle, level: String) -> Result<String, String> {
+    let filter = parse_level(&level)?;
+    let mut settings = config::load(&app).await?;
+    settings.log_level = filter.to_string().to_lowercase();
+    let saved = config::save(&app, settings).await?;
+    Ok(saved.log_level)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn log_lines_are_redacted_before_writing() {
+        let settings = RuntimeSettings::default();
+        let mut out = Redacting(Vec::new(), &settings);
+        out.write_all("バッチ項目失敗 'taro@example.com への連絡': 090-1234-5678".as_bytes()).unwrap();
+        let written = String::from_utf8(out.0).unwrap();
+        assert_eq!(written, "バッチ項目失敗 '[メール] への連絡': [電話番号]");
+    }
+}
@@ -1,25 +1,111 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-mod prompts;
-
-use tauri::command;
-use reqwest::Client;
+mod action_items;
+mod analysis;
+mod analysis_worker;
+mod annotations;
+mod app_state;
+mod attachments;
+mod audit;
+mod autosave;
+mod backend;
+#[cfg(feature = "candle")]
+mod backend_candle;
+mod backend_profiles;
+mod batch;
+mod benchmark;
+mod bootstrap;
+mod circuit_breaker;
+mod coaching;
+mod config;
+mod db;
+mod discussion_engine;
+mod embeddings;
+mod encryption;
+mod experiment;
+mod export;
+mod facilitator;
+mod fixture_backend;
+mod formats;
+mod gen_error;
+mod gen_queue;
+mod generation;
+mod health;
+mod images;
+mod load_test;
+mod logging;
+mod maintenance;
+mod messages;
+mod metrics;
+mod mock_backend;
+mod model_access;
+mod model_manager;
+mod models;
+mod next_speaker;
+mod openai_backend;
+mod parallel;
+mod participant_memory;
+mod permissions;
+mod persona_state;
+mod postprocess;
+mod privacy;
+mod profile_generation;
+mod profiles;
+mod prompt_eval;
+mod prompt_templates;
+mod proofread;
+mod readability;
+mod related;
+mod replay;
+mod requests;
+mod rolling_summary;
+mod round_timer;
+mod run_state;
+mod safety;
+mod scenarios;
+mod search;
+mod session_list;
+mod session_qa;
+mod session_settings;
+mod sessions;
+mod setup;
+mod simulation;
+mod summarize;
+mod system_info;
+mod tags;
+mod timeouts;
+mod tokens;
+mod topic_drift;
+mod tournament;
+mod translation;
+mod url_context;
+mod user_persona;
+mod voting;
+mod workspaces;
+
+use dewai_core::{llm_json, prompts};
+use tauri::{command, AppHandle, Emitter, Manager};
 use serde_json::json;
 use tauri_plugin_sql::Builder as SqlBuilder;
+use tracing::{info, warn, Instrument};
+
+use app_state::AppState;
+use gen_error::GenError;
 
-// リトライ最大回数
-const MAX_RETRIES: u8 = 3;
+// 許可外モデルのエラーメッセージ（共通化）
+const ERR_UNSUPPORTED_MODEL: &str = "許可されていないモデルです。設定の使用可能モデルを確認してください。";
 
-// 許可モデルとエラーメッセージ（共通化）
-const ALLOWED_MODEL_PREFIXES: [&str; 2] = ["gemma3:1b", "gemma3:4b"];
-const ERR_UNSUPPORTED_MODEL: &str = "サポートされていないモデルです。gemma3:1bまたはgemma3:4bを使用してください。";
+// ストリーミング生成の断片通知
+const EVENT_GENERATE_CHUNK: &str = "generate://chunk";
 
-fn is_allowed_model(model: &str) -> bool {
-    ALLOWED_MODEL_PREFIXES.iter().any(|p| model.starts_with(p))
+// 許可モデルの判定（設定の models に従う）
+fn is_allowed_model(app: &AppHandle, model: &str) -> bool {
+    model_access::is_allowed(app, model)
 }
 
-// ログ用のプロンプトマスキング関数
-fn mask_prompt_for_log(prompt: &str) -> String {
+// ログ用のプロンプトマスキング関数（個人情報は伏せ字にしてから切り詰める）
+fn mask_prompt_for_log(app: &AppHandle, prompt: &str) -> String {
+    let prompt = &privacy::redact(&app.state::<AppState>().settings, prompt);
     if prompt.len() <= 100 {
         prompt.to_string()
     } else {
@@ -33,40 +119,71 @@ fn mask_prompt_for_log(prompt: &str) -> String {
     }
 }
 
-//生成呼び出し。失敗時指数バックオフで再試行。
-async fn call_ollama_generate(model: &str, prompt: &str) -> Result<String, String> {
-    let client = Client::builder()
-        .build()
-        .map_err(|e| format!("HTTPクライアント初期化失敗: {}", e))?;
-
-    let body = json!({ "model": model, "prompt": prompt, "stream": false });
-
-    let mut attempt: u8 = 1;
-    loop {
-        println!("Ollama API リクエスト送信 (model={}, attempt={}/{})", model, attempt, MAX_RETRIES);
-        let resp = client.post("http://localhost:11434/api/generate").json(&body).send().await;
-        match resp {
-            Ok(res) => {
-                println!("ステータス: {}", res.status());
-                let json: serde_json::Value = res.json().await.map_err(|e| format!("JSONパース失敗: {}", e))?;
-                if let Some(resp_text) = json["response"].as_str() {
-                    println!("応答取得成功: {}文字", resp_text.len());
-                    return Ok(resp_text.to_string());
-                } else {
-                    let err = format!("応答フィールドなし: {:?}", json);
-                    println!("{}", err);
-                    if attempt >= MAX_RETRIES { return Err("応答なし".into()); }
-                }
-            }
-            Err(e) => {
-                println!("リクエスト失敗: {}", e);
-                if attempt >= MAX_RETRIES { return Err(format!("リクエスト失敗: {}", e)); }
-            }
-        }
-        let backoff_ms = 300u64.saturating_mul(2u64.saturating_pow((attempt - 1) as u32));
-        println!("{}ms 後に再試行...", backoff_ms);
-        attempt += 1;
+//生成呼び出し（モデル既定の生成オプション）
+async fn call_ollama_generate(app: &AppHandle, model: &str, prompt: &str) -> Result<String, GenError> {
+    call_ollama_generate_with(app, model, prompt, &generation::GenerationOptions::default()).await
+}
+
+//生成呼び出し（シード・JSON モード等の生成オプション指定）。本文のみ返す
+async fn call_ollama_generate_with(
+    app: &AppHandle,
+    model: &str,
+    prompt: &str,
+    options: &generation::GenerationOptions,
+) -> Result<String, GenError> {
+    call_ollama_generate_full(app, model, prompt, options).await.map(|r| r.text)
+}
+
+//生成呼び出し（本文 + トークン数・所要時間）。生成キューで順番を待ち、設定中のバックエンドへ委譲する
+async fn call_ollama_generate_full(
+    app: &AppHandle,
+    model: &str,
+    prompt: &str,
+    options: &generation::GenerationOptions,
+) -> Result<generation::GenerationResult, GenError> {
+    async {
+        let prompt = safety::enforce(safety::Direction::Input, model, prompt)?;
+        let state = app.state::<AppState>();
+        let mut result = state.queue.generate(&*backend::current(app), model, &prompt, options).await.result?;
+        result.text = safety::enforce(safety::Direction::Output, model, &result.text)?;
+        result.seed = options.seed;
+        Ok(result)
     }
+    .instrument(logging::ollama_span(model))
+    .await
+}
+
+/// dewai-core の議論の進行・司会役から使う生成（call_ollama_generate_full と同じく生成キューと安全ポリシーを通す）
+struct AppGenerator<'a>(&'a AppHandle);
+
+#[async_trait::async_trait]
+impl dewai_core::engine::Generator for AppGenerator<'_> {
+    async fn generate(
+        &self,
+        model: &str,
+        prompt: &str,
+        options: &generation::GenerationOptions,
+    ) -> Result<generation::GenerationResult, String> {
+        Ok(call_ollama_generate_full(self.0, model, prompt, options).await?)
+    }
+}
+
+//裏方の生成呼び出し（自動分析・自動要約）。ユーザー操作の生成を優先し、同じ key の待機中の依頼はまとめる
+async fn call_ollama_generate_background(
+    app: &AppHandle,
+    key: &str,
+    model: &str,
+    prompt: &str,
+    options: &generation::GenerationOptions,
+) -> Result<String, GenError> {
+    async {
+        let prompt = safety::enforce(safety::Direction::Input, model, prompt)?;
+        let state = app.state::<AppState>();
+        let result = state.queue.generate_background(&*backend::current(app), key, model, &prompt, options).await?;
+        Ok(safety::enforce(safety::Direction::Output, model, &result.text)?)
+    }
+    .instrument(logging::ollama_span(model))
+    .await
 }
 
 
@@ -75,116 +192,150 @@ async fn call_ollama_generate(model: &str, prompt: &str) -> Result<String, Strin
 
 // モデルロード状態チェック
 #[command]
-async fn is_model_loaded() -> bool {
-    println!("モデルロード状態確認中...");
-    match reqwest::get("http://localhost:11434").await {
-        Ok(_) => {
-            println!("Ollama 応答あり。モデル起動可能。");
-            true
-        },
-        Err(e) => {
-            println!("Ollama からの応答なし: {}", e);
-            false
-        },
+async fn is_model_loaded(app: AppHandle) -> bool {
+    info!("モデルロード状態確認中...");
+    let backend = backend::current(&app);
+    let available = backend.is_available().await;
+    if available {
+        info!("{} 応答あり。モデル起動可能。", backend.name());
     }
+    available
 }
 
 // テキスト生成のテスト用コマンド
 #[command]
-async fn test_generate_text() -> Result<String, String> {
-    println!("テスト用generate_text呼び出し開始");
+async fn test_generate_text(app: AppHandle) -> Result<generation::GenerationResult, String> {
+    info!("テスト用generate_text呼び出し開始");
     
     let test_prompt = "こんにちは。あなたの名前は何ですか？日本語で短く答えてください。".to_string();
-    println!("テストプロンプト: {}", test_prompt);
+    info!("テストプロンプト: {}", test_prompt);
     
-    generate_text(test_prompt).await
+    generate_text(app, test_prompt, None, None).await
 }
 
 // テキスト生成（デフォルトモデル）
 #[command]
-async fn generate_text(prompt: String) -> Result<String, String> {
-    println!("generate_text 呼び出し: prompt = {}", mask_prompt_for_log(&prompt));
-    println!("プロンプト長: {}文字", prompt.len());
-
-    // デフォルトは gemma3:4b を使用（フロントからは generate_text_with_model を推奨）
-    let model_name = "gemma3:4b".to_string();
-    println!("使用モデル: {}", model_name);
-
-    call_ollama_generate(&model_name, &prompt).await
+async fn generate_text(
+    app: AppHandle,
+    prompt: String,
+    seed: Option<i64>,
+    options: Option<generation::GenerationOptions>,
+) -> Result<generation::GenerationResult, String> {
+    info!("generate_text 呼び出し: prompt = {}", mask_prompt_for_log(&app, &prompt));
+    info!("プロンプト長: {}文字", prompt.len());
+
+    // デフォルトは gemma3:4b を使用（メモリが明らかに足りなければ gemma3:1b。フロントからは generate_text_with_model を推奨）
+    let model_name = system_info::default_model().to_string();
+    info!("使用モデル: {}", model_name);
+
+    let options = generation::GenerationOptions::from_request(options, seed);
+    call_ollama_generate_full(&app, &model_name, &prompt, &options).await.map_err(String::from)
 }
 
 // 利用可能なモデル一覧を取得
 #[command]
-async fn get_available_models() -> Result<Vec<String>, String> {
-    println!("利用可能なモデル一覧を取得中...");
-    let client = Client::builder()
-        // タイムアウト指定撤廃
-        .build()
-        .map_err(|e| format!("HTTPクライアント初期化失敗: {}", e))?;
-
-    let res = client
-        .get("http://localhost:11434/api/tags")
-        .send()
-        .await
-        .map_err(|e| {
-            let error_msg = format!("モデル一覧取得失敗: {}", e);
-            println!("{}", error_msg);
-            error_msg
-        })?;
-
-    let json: serde_json::Value = res
-        .json()
-        .await
-        .map_err(|e| {
-            let error_msg = format!("JSONパース失敗: {}", e);
-            println!("{}", error_msg);
-            error_msg
-        })?;
-
-    if let Some(models) = json["models"].as_array() {
-        let model_names: Vec<String> = models
-            .iter()
-            .filter_map(|model| model["name"].as_str())
-            .filter(|name| is_allowed_model(name))
-            .map(|s| s.to_string())
-            .collect();
-        
-        println!("利用可能なGemma3モデル: {:?}", model_names);
-        Ok(model_names)
-    } else {
-        println!("モデル一覧が見つかりません");
-        Ok(vec!["gemma3:4b".to_string(), "gemma3:1b".to_string()])
+async fn get_available_models(app: AppHandle) -> Result<Vec<String>, String> {
+    info!("利用可能なモデル一覧を取得中...");
+    let models = backend::current(&app).list_models().await.map_err(|e| {
+        warn!("{}", e);
+        e
+    })?;
+
+    if models.is_empty() {
+        warn!("モデル一覧が見つかりません");
+        // 先頭がフロントエンドの既定になるため、マシンに合うモデルを先に置く
+        let recommended = system_info::default_model();
+        let other = if recommended == system_info::LARGE_MODEL { system_info::SMALL_MODEL } else { system_info::LARGE_MODEL };
+        return Ok(vec![recommended.to_string(), other.to_string()]);
     }
+    // 全モデル許可の設定ならローカルの全タグを返す
+    let model_names: Vec<String> = models.into_iter().filter(|name| is_allowed_model(&app, name)).collect();
+    info!("利用可能なモデル: {:?}", model_names);
+    Ok(model_names)
 }
 
 // モデル選択付きテキスト生成
 #[command]
-async fn generate_text_with_model(prompt: String, model: String) -> Result<String, String> {
-    println!(
+async fn generate_text_with_model(
+    app: AppHandle,
+    prompt: String,
+    model: String,
+    seed: Option<i64>,
+    options: Option<generation::GenerationOptions>,
+    request_id: Option<String>,
+) -> Result<generation::GenerationResult, String> {
+    info!(
         "generate_text_with_model 呼び出し: model = {}, prompt = {}",
         model,
-        mask_prompt_for_log(&prompt)
+        mask_prompt_for_log(&app, &prompt)
     );
     
     // 指定されたモデルが許可リストにあるかチェック
-    if !is_allowed_model(&model) {
+    if !is_allowed_model(&app, &model) {
         return Err(ERR_UNSUPPORTED_MODEL.to_string());
     }
 
-    call_ollama_generate(&model, &prompt).await
+    let options = generation::GenerationOptions::from_request(options, seed);
+    requests::run_cancellable(&app, request_id.as_deref(), call_ollama_generate_full(&app, &model, &prompt, &options))
+        .await
+        .map_err(String::from)
+}
+
+// モデル選択付きテキスト生成（ストリーミング）
+// 断片ごとに generate://chunk を通知し、最後に全文を返す
+#[command]
+async fn generate_text_stream(
+    app: AppHandle,
+    prompt: String,
+    model: String,
+    request_id: String,
+    seed: Option<i64>,
+    options: Option<generation::GenerationOptions>,
+) -> Result<generation::GenerationResult, String> {
+    info!(
+        "generate_text_stream 呼び出し: model = {}, request_id = {}, prompt = {}",
+        model,
+        request_id,
+        mask_prompt_for_log(&app, &prompt)
+    );
+    if !is_allowed_model(&app, &model) {
+        return Err(ERR_UNSUPPORTED_MODEL.to_string());
+    }
+    let on_chunk = |chunk: &str| {
+        let _ = app.emit(EVENT_GENERATE_CHUNK, json!({ "requestId": request_id, "chunk": chunk }));
+    };
+    let prompt = safety::enforce(safety::Direction::Input, &model, &prompt)?;
+    let options = generation::GenerationOptions::from_request(options, seed);
+    let mut result = requests::run_cancellable(
+        &app,
+        Some(&request_id),
+        app.state::<AppState>().queue.generate_stream(&*backend::current(&app), &model, &prompt, &options, &on_chunk)
+            .instrument(logging::ollama_span(&model)),
+    )
+    .await?;
+    // 断片は送信済みのため、出力の判定結果は戻り値（全文）に反映する
+    result.text = safety::enforce(safety::Direction::Output, &model, &result.text)?;
+    result.seed = options.seed;
+    Ok(result)
 }
 
 // AI応答生成（XMLフォーマットプロンプト）
 #[command]
+#[allow(clippy::too_many_arguments)] // フロントエンドからの引数をそのまま受ける
 async fn generate_ai_response(
+    app: AppHandle,
     participant_name: String,
     role: String,
     description: String,
     conversation_history: String,
     discussion_topic: String,
     model: String,
-) -> Result<String, String> {
-    println!(
+    session_id: Option<i64>,
+    seed: Option<i64>,
+    options: Option<generation::GenerationOptions>,
+    request_id: Option<String>,
+) -> Result<generation::GenerationResult, String> {
+    info!(
         "generate_ai_response 呼び出し: participant_name={}, role={}, description={}, conversation_history=[{}文字], discussion_topic={}, model={}",
         participant_name,
         role,
@@ -195,118 +346,450 @@ async fn generate_ai_response(
     );
 
     // モデル許可チェック
-    if !is_allowed_model(&model) {
+    if !is_allowed_model(&app, &model) {
         return Err(ERR_UNSUPPORTED_MODEL.to_string());
     }
 
-    println!("プロンプト生成開始...");
-    let xml_prompt = prompts::build_ai_response_prompt(
+    let options = generation::GenerationOptions::from_request(options, seed);
+    generate_persona_reply(
+        &app,
         &participant_name,
         &role,
         &description,
         &conversation_history,
         &discussion_topic,
+        &model,
+        session_id,
+        None,
+        options,
+        request_id,
+    )
+    .await
+}
+
+// 画像付きのAI応答生成（gemma3:4b などのマルチモーダルモデル）
+// ユーザーが会話に添付したグラフやスクリーンショットを base64 にして Ollama の images で渡す
+#[command]
+#[allow(clippy::too_many_arguments)] // フロントエンドからの引数をそのまま受ける
+async fn generate_ai_response_with_images(
+    app: AppHandle,
+    participant_name: String,
+    role: String,
+    description: String,
+    conversation_history: String,
+    discussion_topic: String,
+    model: String,
+    image_paths: Vec<String>,
+    session_id: Option<i64>,
+    seed: Option<i64>,
+    options: Option<generation::GenerationOptions>,
+    request_id: Option<String>,
+) -> Result<generation::GenerationResult, String> {
+    info!(
+        "generate_ai_response_with_images 呼び出し: participant_name={}, model={}, 画像{}枚",
+        participant_name,
+        model,
+        image_paths.len()
     );
-    println!("プロンプト生成完了: {}文字", xml_prompt.len());
+    if !is_allowed_model(&app, &model) {
+        return Err(ERR_UNSUPPORTED_MODEL.to_string());
+    }
+    if !images::supports_images(&model) {
+        return Err(format!("モデル {} は画像入力に対応していません（gemma3:4b などを使ってください）", model));
+    }
+    match backend::current(&app).name() {
+        "ollama" | "record" => {}
+        other => return Err(format!("画像入力は Ollama でのみ使えます（現在のバックエンド: {}）", other)),
+    }
+    permissions::authorize(&app, permissions::TOOL_IMAGE_INPUT).await?;
+
+    let mut options = generation::GenerationOptions::from_request(options, seed);
+    options.images = images::load_images(&image_paths).await?;
+    generate_persona_reply(
+        &app,
+        &participant_name,
+        &role,
+        &description,
+        &conversation_history,
+        &discussion_topic,
+        &model,
+        session_id,
+        None,
+        options,
+        request_id,
+    )
+    .await
+}
 
-    call_ollama_generate(&model, &xml_prompt).await
+// 通常の参加者プロンプトを組み立てて発言を生成する（generate_ai_response / generate_responses_parallel / regenerate_message）
+// message_index は生成ログに記録する発言の位置（None ならセッションの末尾に追加される発言とみなす）
+#[allow(clippy::too_many_arguments)]
+async fn generate_persona_reply(
+    app: &AppHandle,
+    participant_name: &str,
+    role: &str,
+    description: &str,
+    conversation_history: &str,
+    discussion_topic: &str,
+    model: &str,
+    session_id: Option<i64>,
+    message_index: Option<i64>,
+    options: generation::GenerationOptions,
+    request_id: Option<String>,
+) -> Result<generation::GenerationResult, String> {
+    info!("プロンプト生成開始...");
+    let style = session_settings::prompt_style_for(app, session_id).await?;
+    let material = attachments::reference_material(app, session_id, discussion_topic, conversation_history).await;
+    let memory = participant_memory::memory_for(app, participant_name, role).await;
+    let state = persona_state::state_for(app, session_id, participant_name).await;
+    let build = |history: &str| {
+        let prompt =
+            prompts::build_ai_response_prompt(participant_name, role, description, history, discussion_topic, &style);
+        let prompt = prompts::with_participant_memory(prompt, memory.as_deref());
+        let prompt = prompts::with_persona_state(prompt, state.as_ref().map(persona_state::PersonaState::prompt_args));
+        prompts::with_attached_images(prompts::with_reference_material(prompt, &material), options.images.len())
+    };
+    // コンテキストに収まらない場合は古い発言を要約に畳み込む
+    let conversation_history = rolling_summary::fit_history(
+        app,
+        model,
+        session_id,
+        discussion_topic,
+        conversation_history,
+        &options,
+        &style,
+        build,
+    )
+    .await;
+    let xml_prompt = build(&conversation_history);
+    info!("プロンプト生成完了: {}文字", xml_prompt.len());
+
+    generate_participant_reply(app, participant_name, model, &xml_prompt, options, session_id, message_index, request_id, &style)
+        .await
+}
+
+// 参加者の発言を生成する共通処理（generate_ai_response / generate_devils_advocate_response）
+#[allow(clippy::too_many_arguments)]
+async fn generate_participant_reply(
+    app: &AppHandle,
+    participant_name: &str,
+    model: &str,
+    xml_prompt: &str,
+    options: generation::GenerationOptions,
+    session_id: Option<i64>,
+    message_index: Option<i64>,
+    request_id: Option<String>,
+    style: &prompts::PromptStyle,
+) -> Result<generation::GenerationResult, String> {
+    // シード未指定なら採番し、発言のメタデータとして返す
+    let options = options.with_seed_assigned();
+    // request_id 指定時は cancel_request でこの発言の生成だけを中断できる
+    let mut result =
+        requests::run_cancellable(app, request_id.as_deref(), call_ollama_generate_full(app, model, xml_prompt, &options))
+            .await?;
+    // セッションに紐づく発言は再現用に生成ログへ記録する
+    if let Some(id) = session_id {
+        let message_index = match message_index {
+            Some(index) => index,
+            None => db::load_session(app, id).await.map(|s| s.messages.len() as i64).unwrap_or(-1),
+        };
+        generation::record(
+            app,
+            generation::GenerationRecord {
+                session_id: id,
+                message_index,
+                speaker: participant_name,
+                model,
+                prompt: xml_prompt,
+                options: &options,
+                output: &result.text,
+                meta: &result.meta,
+            },
+        )
+        .await;
+    }
+    // 話者名・コードフェンスなどを除き、読解レベル指定があれば簡易チェックして超過時は1回だけ書き直す
+    result.text = postprocess::process_reply(app, &result.text, participant_name);
+    result.text = readability::enforce_reading_level(app, model, result.text, style.reading_level, style.language).await;
+    Ok(result)
+}
+
+// 反論役（悪魔の代弁者）の発言を生成
+// 最新の分析（session_id 指定時）の共通認識・主要論点を「現在の合意」とみなし、それを体系的に問い直させる
+#[command]
+#[allow(clippy::too_many_arguments)] // フロントエンドからの引数をそのまま受ける
+async fn generate_devils_advocate_response(
+    app: AppHandle,
+    participant_name: String,
+    role: String,
+    description: String,
+    conversation_history: String,
+    discussion_topic: String,
+    model: String,
+    session_id: Option<i64>,
+    seed: Option<i64>,
+    options: Option<generation::GenerationOptions>,
+    request_id: Option<String>,
+) -> Result<generation::GenerationResult, String> {
+    info!(
+        "generate_devils_advocate_response 呼び出し: participant_name={}, conversation_history=[{}文字], model={}",
+        participant_name,
+        conversation_history.len(),
+        model
+    );
+    if !is_allowed_model(&app, &model) {
+        return Err(ERR_UNSUPPORTED_MODEL.to_string());
+    }
+    let style = session_settings::prompt_style_for(&app, session_id).await?;
+    let consensus = match session_id {
+        Some(id) => analysis::latest_consensus(&app, id).await?,
+        None => Vec::new(),
+    };
+    let options = generation::GenerationOptions::from_request(options, seed);
+    let material = attachments::reference_material(&app, session_id, &discussion_topic, &conversation_history).await;
+    let memory = participant_memory::memory_for(&app, &participant_name, &role).await;
+    let state = persona_state::state_for(&app, session_id, &participant_name).await;
+    let build = |history: &str| {
+        let prompt = prompts::build_devils_advocate_prompt(
+            &participant_name,
+            &role,
+            &description,
+            history,
+            &discussion_topic,
+            &consensus,
+            &style,
+        );
+        let prompt = prompts::with_participant_memory(prompt, memory.as_deref());
+        let prompt = prompts::with_persona_state(prompt, state.as_ref().map(persona_state::PersonaState::prompt_args));
+        prompts::with_reference_material(prompt, &material)
+    };
+    let conversation_history = rolling_summary::fit_history(
+        &app,
+        &model,
+        session_id,
+        &discussion_topic,
+        &conversation_history,
+        &options,
+        &style,
+        build,
+    )
+    .await;
+    let xml_prompt = build(&conversation_history);
+    generate_participant_reply(&app, &participant_name, &model, &xml_prompt, options, session_id, None, request_id, &style)
+        .await
 }
 
 // 議論開始のためのファシリテート
 #[command]
 async fn start_discussion(
+    app: AppHandle,
     topic: String,
     participants: Vec<String>, // AI名のリスト
-) -> Result<String, String> {
-    println!("start_discussion 呼び出し: {}", topic);
+    session_id: Option<i64>,
+    seed: Option<i64>,
+    options: Option<generation::GenerationOptions>,
+) -> Result<generation::GenerationResult, String> {
+    info!("start_discussion 呼び出し: {}", topic);
     
-    let xml_prompt = prompts::build_discussion_start_prompt(&topic, &participants);
+    let style = session_settings::prompt_style_for(&app, session_id).await?;
+    let xml_prompt = prompts::build_discussion_start_prompt(&topic, &participants, &style);
 
-    generate_text(xml_prompt).await
+    generate_text(app, xml_prompt, seed, options).await
 }
 
 // 議論分析エンジン - 論点と立場をリアルタイム分析
+// session_id 指定時は発言数（message_count。省略時は保存済みの件数）ごとのスナップショットも保存する
 #[command]
+#[allow(clippy::too_many_arguments)] // フロントエンドからの引数をそのまま受ける
 async fn analyze_discussion_points(
+    app: AppHandle,
     discussion_topic: String,
     conversation_history: String,
     participants: Vec<String>,
     model: String,
-) -> Result<String, String> {
-    println!("analyze_discussion_points 呼び出し (model={})", model);
-    if !is_allowed_model(&model) { return Err(ERR_UNSUPPORTED_MODEL.to_string()); }
+    session_id: Option<i64>,
+    message_count: Option<i64>,
+    seed: Option<i64>,
+    options: Option<generation::GenerationOptions>,
+) -> Result<analysis::DiscussionAnalysis, String> {
+    info!("analyze_discussion_points 呼び出し (model={})", model);
+    if !is_allowed_model(&app, &model) { return Err(ERR_UNSUPPORTED_MODEL.to_string()); }
+    let language = session_settings::language_for(&app, session_id).await?;
     let xml_prompt = prompts::build_discussion_analysis_prompt(
         &discussion_topic,
         &conversation_history,
         &participants,
+        language,
     );
-    call_ollama_generate(&model, &xml_prompt).await
+    let options = generation::GenerationOptions::from_request(options, seed).with_format(analysis::output_format());
+    let raw = call_ollama_generate_with(&app, &model, &xml_prompt, &options).await?;
+    let result = analysis::parse(&raw)?;
+    if let Some(session_id) = session_id {
+        // 履歴の保存に失敗しても分析結果は返す
+        if let Err(e) = analysis::save_snapshot(&app, session_id, message_count, &model, &result).await {
+            warn!("分析スナップショット保存失敗: {}", e);
+        }
+    }
+    Ok(result)
 }
 
 // 議論要約（全文対象）
 #[command]
+#[allow(clippy::too_many_arguments)] // フロントエンドからの引数をそのまま受ける
 async fn summarize_discussion(
+    app: AppHandle,
     discussion_topic: String,
     conversation_history: String,
     participants: Vec<String>,
     model: String,
-) -> Result<String, String> {
-    println!("summarize_discussion 呼び出し (model={})", model);
-    if !is_allowed_model(&model) { return Err(ERR_UNSUPPORTED_MODEL.to_string()); }
-    let xml_prompt = prompts::build_discussion_summary_prompt(
-        &discussion_topic,
-        &conversation_history,
-        &participants,
-    );
-    call_ollama_generate(&model, &xml_prompt).await
+    session_id: Option<i64>,
+    seed: Option<i64>,
+    options: Option<generation::GenerationOptions>,
+) -> Result<generation::GenerationResult, String> {
+    info!("summarize_discussion 呼び出し (model={})", model);
+    if !is_allowed_model(&app, &model) { return Err(ERR_UNSUPPORTED_MODEL.to_string()); }
+    let style = session_settings::prompt_style_for(&app, session_id).await?;
+    let options = generation::GenerationOptions::from_request(options, seed);
+    // コンテキストに収まらない長い議論は分割して要約し、統合する
+    let request = summarize::SummaryRequest {
+        session_id,
+        model: &model,
+        discussion_topic: &discussion_topic,
+        participants: &participants,
+        style: &style,
+        options: &options,
+        background_key: None,
+    };
+    let mut result = summarize::summarize_history(&app, &request, &conversation_history).await?;
+    result.seed = options.seed;
+    Ok(result)
 }
 
-// AIプロフィール生成
+// AIプロフィール生成（検証済みの name / role / description の配列を返す。検証に通らなければ1回だけ直させる）
+// constraints で必須の役職・立場の内訳・話し方の丁寧さ・年齢層や職業のばらつきを指定できる
 #[command]
+#[allow(clippy::too_many_arguments)] // フロントエンドからの引数をそのまま受ける
 async fn generate_ai_profiles(
+    app: AppHandle,
     discussion_topic: String,
     desired_count: Option<u32>,
     style_hint: Option<String>,
+    constraints: Option<prompts::ProfileConstraints>,
     model: String,
-) -> Result<String, String> {
-    println!(
+    seed: Option<i64>,
+    options: Option<generation::GenerationOptions>,
+) -> Result<Vec<db::AiParticipant>, String> {
+    info!(
         "generate_ai_profiles 呼び出し: topic='{}', count={:?}, model={}",
         discussion_topic,
         desired_count,
         model
     );
-    if !is_allowed_model(&model) { return Err(ERR_UNSUPPORTED_MODEL.to_string()); }
-    let prompt = prompts::build_ai_profiles_prompt(
+    if !is_allowed_model(&app, &model) { return Err(ERR_UNSUPPORTED_MODEL.to_string()); }
+    let options = generation::GenerationOptions::from_request(options, seed);
+    profile_generation::generate(
+        &app,
         &discussion_topic,
         desired_count.unwrap_or(4) as usize,
         style_hint.unwrap_or_default().as_str(),
-    );
-    call_ollama_generate(&model, &prompt).await
+        constraints.unwrap_or_default(),
+        &model,
+        &options,
+    )
+    .await
 }
 
 // インクリメンタル要約（前回要約 + 新規メッセージのみ）
 #[command]
+#[allow(clippy::too_many_arguments)] // フロントエンドからの引数をそのまま受ける
 async fn incremental_summarize_discussion(
+    app: AppHandle,
     discussion_topic: String,
     previous_summary: String,
     new_messages: String,
     participants: Vec<String>,
     model: String,
-) -> Result<String, String> {
-    println!(
+    session_id: Option<i64>,
+    seed: Option<i64>,
+    options: Option<generation::GenerationOptions>,
+) -> Result<generation::GenerationResult, String> {
+    info!(
         "incremental_summarize_discussion 呼び出し (model={}, prev_summary_len={}, new_msgs_len={})",
         model,
         previous_summary.len(),
         new_messages.len()
     );
-    if !is_allowed_model(&model) { return Err(ERR_UNSUPPORTED_MODEL.to_string()); }
+    if !is_allowed_model(&app, &model) { return Err(ERR_UNSUPPORTED_MODEL.to_string()); }
+    let style = session_settings::prompt_style_for(&app, session_id).await?;
     let prompt = prompts::build_incremental_summary_prompt(
         &discussion_topic,
         &previous_summary,
         &new_messages,
         &participants,
+        &style,
+    );
+    let options = generation::GenerationOptions::from_request(options, seed);
+    call_ollama_generate_full(&app, &model, &prompt, &options).await.map_err(String::from)
+}
+
+// インクリメンタル分析（前回の分析結果 + 差分発言から最新の分析を再構築）
+// 長い議論で分析のたびに全履歴を送らずに済む。前回の分析が空なら差分発言だけで通常の分析を行う
+#[command]
+#[allow(clippy::too_many_arguments)] // フロントエンドからの引数をそのまま受ける
+async fn incremental_analyze_discussion(
+    app: AppHandle,
+    discussion_topic: String,
+    previous_analysis_json: String,
+    new_messages: String,
+    participants: Vec<String>,
+    model: String,
+    session_id: Option<i64>,
+    message_count: Option<i64>,
+    seed: Option<i64>,
+    options: Option<generation::GenerationOptions>,
+) -> Result<analysis::DiscussionAnalysis, String> {
+    info!(
+        "incremental_analyze_discussion 呼び出し (model={}, prev_analysis_len={}, new_msgs_len={})",
+        model,
+        previous_analysis_json.len(),
+        new_messages.len()
     );
-    call_ollama_generate(&model, &prompt).await
+    if !is_allowed_model(&app, &model) { return Err(ERR_UNSUPPORTED_MODEL.to_string()); }
+    let language = session_settings::language_for(&app, session_id).await?;
+    let prompt = if previous_analysis_json.trim().is_empty() {
+        prompts::build_discussion_analysis_prompt(&discussion_topic, &new_messages, &participants, language)
+    } else {
+        // 前回の結果も同じ修復・整形を通し、空項目を除いた JSON をプロンプトに渡す
+        let previous = analysis::parse(&previous_analysis_json)?;
+        let previous = serde_json::to_string_pretty(&previous).map_err(|e| format!("前回の分析のシリアライズ失敗: {}", e))?;
+        prompts::build_incremental_analysis_prompt(&discussion_topic, &previous, &new_messages, &participants, language)
+    };
+    let options = generation::GenerationOptions::from_request(options, seed).with_format(analysis::output_format());
+    let raw = call_ollama_generate_with(&app, &model, &prompt, &options).await?;
+    let result = analysis::parse(&raw)?;
+    if let Some(session_id) = session_id {
+        if let Err(e) = analysis::save_snapshot(&app, session_id, message_count, &model, &result).await {
+            warn!("分析スナップショット保存失敗: {}", e);
+        }
+    }
+    Ok(result)
+}
+
+// 既存テキスト（過去の発言など）をやさしい日本語に書き換え
+#[command]
+async fn simplify_text(
+    app: AppHandle,
+    text: String,
+    model: String,
+    seed: Option<i64>,
+    options: Option<generation::GenerationOptions>,
+) -> Result<generation::GenerationResult, String> {
+    info!("simplify_text 呼び出し (model={}, text_len={})", model, text.len());
+    if !is_allowed_model(&app, &model) { return Err(ERR_UNSUPPORTED_MODEL.to_string()); }
+    if text.trim().is_empty() { return Ok(generation::GenerationResult::text_only(&model, text)); }
+    let prompt = prompts::build_simplify_prompt(&text, prompts::default_language());
+    let options = generation::GenerationOptions::from_request(options, seed);
+    call_ollama_generate_full(&app, &model, &prompt, &options).await.map_err(String::from)
 }
 
 // =========================
@@ -316,20 +799,220 @@ async fn incremental_summarize_discussion(
 pub fn main() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
-        .plugin(SqlBuilder::default().build())
+        .plugin(tauri_plugin_dialog::init())
+        .setup(|app| {
+            let state = app_state::AppState::new(app.handle());
+            logging::init(app.path().app_data_dir().ok().map(|dir| dir.join("logs")).as_deref(), &state.settings);
+            audit::init(app.handle().clone());
+            app.manage(state);
+            if let Ok(dir) = app.path().app_data_dir() {
+                fixture_backend::init_fixture_dir(dir.join("fixtures"));
+            }
+            // 保存済み設定（バックエンド・接続先・安全ポリシー・伏せ字設定）を反映（SQL プラグインの preload 後に実行される）
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                // 前回のワークスペースを開いてから、そのデータベースの設定を読む
+                workspaces::init(&handle).await;
+                encryption::reload(&handle).await;
+                let settings = match config::load(&handle).await {
+                    Ok(settings) => settings,
+                    Err(e) => {
+                        warn!("設定読込失敗（既定のバックエンドを使用）: {}", e);
+                        config::AppSettings::default()
+                    }
+                };
+                config::apply(&handle, &settings);
+                match maintenance::enable_wal(&handle).await {
+                    Ok(mode) => info!("ジャーナルモード: {}", mode),
+                    Err(e) => warn!("WAL モードへの切り替えに失敗: {}", e),
+                }
+                if let Err(e) = prompt_templates::load(&handle).await {
+                    warn!("テンプレート読込失敗（組み込みを使用）: {}", e);
+                }
+                // 初回起動などで Ollama が止まっていれば起動する
+                if settings.auto_start_ollama {
+                    let status = bootstrap::ensure_running(&handle).await;
+                    info!("Ollama 自動起動: {:?} {}", status.outcome, status.message);
+                }
+            });
+            maintenance::start_scheduler(app.handle().clone());
+            health::start_monitor(app.handle().clone());
+            analysis_worker::start(app.handle());
+            Ok(())
+        })
+        .plugin(
+            SqlBuilder::default()
+                .add_migrations(db::DB_URL, db::migrations())
+                .build(),
+        )
         .invoke_handler(tauri::generate_handler![
             is_model_loaded,
+            health::get_ollama_status,
+            circuit_breaker::get_circuit_breaker_status,
+            circuit_breaker::reset_circuit_breaker,
+            backend_profiles::list_backend_profiles,
+            backend_profiles::save_backend_profile,
+            backend_profiles::delete_backend_profile,
+            backend_profiles::set_active_profile,
+            bootstrap::ensure_ollama_running,
+            openai_backend::test_openai_connection,
+            models::list_curated_gguf,
+            models::download_gguf,
+            models::list_local_gguf,
+            models::delete_local_gguf,
+            models::use_local_gguf,
+            models::get_models_dir_usage,
+            prompt_templates::list_templates,
+            prompt_templates::get_template,
+            prompt_templates::update_template,
+            prompt_templates::reset_template,
+            postprocess::postprocess_text,
+            next_speaker::suggest_next_speaker,
+            formats::start_formatted_discussion,
+            session_qa::ask_session,
+            embeddings::semantic_search,
+            embeddings::index_embeddings,
+            related::find_related_sessions,
+            attachments::attach_document,
+            attachments::list_attachments,
+            attachments::delete_attachment,
+            url_context::fetch_url_context,
+            tags::add_tag,
+            tags::remove_tag,
+            tags::list_tags,
+            tags::auto_tag_session,
+            session_list::get_sessions_page,
+            sessions::rename_session,
+            sessions::duplicate_session,
+            sessions::fork_session,
+            sessions::get_session_lineage,
+            setup::check_prerequisites,
+            setup::install_recommended_model,
+            setup::run_smoke_test,
+            system_info::system_info,
+            system_info::recommend_model,
             test_generate_text,
             generate_text,
             get_available_models,
             generate_text_with_model,
+            generate_text_stream,
             generate_ai_response,
+            generate_ai_response_with_images,
+            generate_devils_advocate_response,
+            parallel::generate_responses_parallel,
+            gen_queue::get_queue_status,
             start_discussion,
             analyze_discussion_points,
+            analysis::get_analysis_history,
+            analysis::get_stance_timeline,
+            analysis::save_session_analysis,
+            analysis::get_session_analysis,
             summarize_discussion,
             generate_ai_profiles,
-            incremental_summarize_discussion
+            incremental_summarize_discussion,
+            incremental_analyze_discussion,
+            simplify_text,
+            coaching::coach_user_message,
+            session_settings::get_session_settings,
+            session_settings::set_session_settings,
+            translation::translate_session,
+            proofread::proofread_text,
+            annotations::annotate_message,
+            annotations::list_annotations,
+            annotations::delete_annotation,
+            config::get_settings,
+            config::set_settings,
+            user_persona::get_user_persona,
+            user_persona::set_user_persona,
+            logging::set_log_level,
+            discussion_engine::append_session_message,
+            discussion_engine::notify_messages_persisted,
+            discussion_engine::get_latest_summary,
+            discussion_engine::start_auto_discussion,
+            discussion_engine::pause_discussion,
+            discussion_engine::resume_discussion,
+            discussion_engine::stop_discussion,
+            batch::run_batch,
+            simulation::run_simulation,
+            simulation::cancel_simulation,
+            simulation::list_simulations,
+            tournament::create_tournament,
+            tournament::advance_round,
+            tournament::get_standings,
+            replay::replay_session,
+            prompt_eval::evaluate_prompts,
+            load_test::run_load_test,
+            benchmark::benchmark_models,
+            audit::get_audit_log,
+            privacy::redact_text,
+            privacy::redact_messages_json,
+            permissions::list_tool_permissions,
+            permissions::grant_capability,
+            permissions::revoke_capability,
+            requests::cancel_request,
+            messages::append_message,
+            messages::get_messages,
+            messages::delete_message,
+            messages::edit_message,
+            messages::regenerate_message,
+            metrics::get_session_metrics,
+            autosave::recover_unsaved_session,
+            autosave::close_session,
+            model_access::set_allowed_models,
+            model_manager::pull_model,
+            model_manager::delete_model,
+            model_manager::show_model_info,
+            model_manager::warm_up_model,
+            model_manager::set_keep_alive,
+            search::search_sessions,
+            export::export_session,
+            export::import_session,
+            profile_generation::regenerate_single_profile,
+            profiles::save_profile,
+            profiles::list_profiles,
+            profiles::update_profile,
+            profiles::delete_profile,
+            participant_memory::get_profile_memory,
+            participant_memory::clear_profile_memory,
+            persona_state::get_persona_states,
+            topic_drift::detect_topic_drift,
+            action_items::extract_action_items,
+            action_items::get_action_items,
+            action_items::export_action_items,
+            voting::run_vote,
+            voting::list_votes,
+            tokens::count_tokens,
+            experiment::run_experiment,
+            maintenance::run_maintenance_now,
+            maintenance::get_maintenance_log,
+            maintenance::check_db_integrity,
+            maintenance::vacuum_db,
+            workspaces::list_workspaces,
+            workspaces::create_workspace,
+            workspaces::switch_workspace,
+            workspaces::set_db_directory,
+            encryption::get_encryption_status,
+            encryption::unlock_db,
+            encryption::lock_db,
+            encryption::set_db_passphrase,
+            encryption::forget_db_passphrase,
+            encryption::seal_messages_json,
+            encryption::open_messages_json,
+            run_state::list_pending_runs,
+            run_state::resume_pending_runs,
+            run_state::discard_pending_run,
+            scenarios::create_scenario,
+            scenarios::list_scenarios,
+            scenarios::update_scenario,
+            scenarios::delete_scenario,
+            scenarios::start_session_from_scenario
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|_app, event| {
+            // アプリが起動した Ollama は終了時に止める
+            if let tauri::RunEvent::Exit = event {
+                bootstrap::shutdown();
+            }
+        });
 }
@@ -0,0 +1,395 @@
+// 定期メンテナンス
+// アイドル時または設定した時刻に、保持期間の適用・索引の再構築・VACUUM・モデル情報の更新を行い、結果を記録する
+// 起動時に WAL モードへ切り替え、整合性チェックと VACUUM は画面からも個別に実行できる
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use chrono::{NaiveDateTime, Timelike};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::{command, AppHandle};
+use tracing::{info, warn};
+
+use crate::{backend, config, db, discussion_engine};
+
+// スケジューラの判定間隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+// この時間発言がなければアイドルとみなす
+const IDLE_THRESHOLD: Duration = Duration::from_secs(30 * 60);
+// 時刻指定時は同じ日に2回走らないよう、前回から最低この時間を空ける
+const MIN_HOURS_BETWEEN_SCHEDULED: i64 = 20;
+// アイドル実行は1日1回まで
+const MIN_HOURS_BETWEEN_IDLE: i64 = 24;
+// 保持するメンテナンスログの件数
+const MAX_LOG_ROWS: i64 = 200;
+
+const TRIGGER_SCHEDULED: &str = "scheduled";
+const TRIGGER_MANUAL: &str = "manual";
+
+// 多重実行防止
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// 1ステップ分の実行結果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MaintenanceStep {
+    pub name: String,
+    pub ok: bool,
+    pub detail: String,
+    pub duration_ms: u128,
+}
+
+/// 1回分のメンテナンス結果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MaintenanceReport {
+    pub id: i64,
+    pub trigger: String,
+    pub started_at: String,
+    pub finished_at: String,
+    /// "ok" | "partial"
+    pub status: String,
+    pub steps: Vec<MaintenanceStep>,
+}
+
+/// ステップを実行して結果を記録
+async fn run_step<F>(steps: &mut Vec<MaintenanceStep>, name: &str, step: F)
+where
+    F: std::future::Future<Output = Result<String, String>>,
+{
+    let started = Instant::now();
+    let (ok, detail) = match step.await {
+        Ok(detail) => (true, detail),
+        Err(e) => (false, e),
+    };
+    info!("メンテナンス {}: {} ({})", name, if ok { "完了" } else { "失敗" }, detail);
+    steps.push(MaintenanceStep { name: name.to_string(), ok, detail, duration_ms: started.elapsed().as_millis() });
+}
+
+/// 整合性チェックの結果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IntegrityReport {
+    pub ok: bool,
+    /// PRAGMA integrity_check が返した問題（正常なら空）
+    pub problems: Vec<String>,
+    /// 参照先が存在しない外部キー（"テーブル#rowid -> 参照先" の形）
+    pub foreign_key_violations: Vec<String>,
+    pub journal_mode: String,
+    pub checked_at: String,
+}
+
+/// VACUUM の結果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VacuumReport {
+    pub size_before: i64,
+    pub size_after: i64,
+    /// 実行前の未使用ページ数
+    pub freelist_pages: i64,
+    pub duration_ms: u128,
+}
+
+/// 書き込み中も読み取りを妨げないよう WAL モードにする（設定はDBファイルに保存される）
+pub async fn enable_wal(app: &AppHandle) -> Result<String, String> {
+    let pool = db::pool(app).await?;
+    let (mode,): (String,) = sqlx::query_as("PRAGMA journal_mode=WAL")
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| format!("WAL 切り替え失敗: {}", e))?;
+    Ok(mode)
+}
+
+async fn pragma_i64(pool: &SqlitePool, name: &str) -> Result<i64, String> {
+    let (value,): (i64,) = sqlx::query_as(&format!("PRAGMA {}", name))
+        .fetch_one(pool)
+        .await
+        .map_err(|e| format!("PRAGMA {} 失敗: {}", name, e))?;
+    Ok(value)
+}
+
+/// データベースの使用サイズ（ページ数 × ページサイズ）
+async fn db_size(pool: &SqlitePool) -> Result<i64, String> {
+    Ok(pragma_i64(pool, "page_count").await? * pragma_i64(pool, "page_size").await?)
+}
+
+/// VACUUM で未使用領域を回収し、WAL ファイルも切り詰める
+async fn vacuum(pool: &SqlitePool) -> Result<VacuumReport, String> {
+    let started = Instant::now();
+    let size_before = db_size(pool).await?;
+    let freelist_pages = pragma_i64(pool, "freelist_count").await?;
+    sqlx::query("VACUUM").execute(pool).await.map_err(|e| format!("VACUUM 失敗: {}", e))?;
+    sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)")
+        .execute(pool)
+        .await
+        .map_err(|e| format!("チェックポイント失敗: {}", e))?;
+    Ok(VacuumReport {
+        size_before,
+        size_after: db_size(pool).await?,
+        freelist_pages,
+        duration_ms: started.elapsed().as_millis(),
+    })
+}
+
+/// 保持期間を過ぎたセッションと生成ログを削除
+async fn enforce_retention(pool: &SqlitePool, retention_days: Option<u32>) -> Result<String, String> {
+    let Some(days) = retention_days else {
+        return Ok("保持期間の指定なし（スキップ）".into());
+    };
+    let cutoff = (chrono::Utc::now() - chrono::Duration::days(days as i64)).format("%Y-%m-%d %H:%M:%S").to_string();
+    let sessions = sqlx::query("DELETE FROM sessions WHERE updated_at < ?")
+        .bind(&cutoff)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("セッション削除失敗: {}", e))?
+        .rows_affected();
+    let logs = sqlx::query("DELETE FROM generation_log WHERE created_at < ?")
+        .bind(&cutoff)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("生成ログ削除失敗: {}", e))?
+        .rows_affected();
+    Ok(format!("{}日より前を削除: セッション{}件, 生成ログ{}件", days, sessions, logs))
+}
+
+/// 全文検索(FTS5)テーブルを再構築
+async fn rebuild_fts(pool: &SqlitePool) -> Result<String, String> {
+    let tables: Vec<(String,)> =
+        sqlx::query_as("SELECT name FROM sqlite_master WHERE type = 'table' AND sql LIKE '%USING fts5%'")
+            .fetch_all(pool)
+            .await
+            .map_err(|e| format!("FTSテーブル取得失敗: {}", e))?;
+    if tables.is_empty() {
+        return Ok("FTSテーブルなし（スキップ）".into());
+    }
+    for (name,) in &tables {
+        let escaped = name.replace('"', "\"\"");
+        sqlx::query(&format!("INSERT INTO \"{0}\"(\"{0}\") VALUES('rebuild')", escaped))
+            .execute(pool)
+            .await
+            .map_err(|e| format!("FTS再構築失敗 ({}): {}", name, e))?;
+    }
+    Ok(format!("{}件を再構築", tables.len()))
+}
+
+/// Ollama のモデル一覧を取得してキャッシュを更新
+async fn refresh_model_cache(app: &AppHandle, pool: &SqlitePool) -> Result<String, String> {
+    let res = backend::client(app)
+        .request(reqwest::Method::GET, "/api/tags")
+        .send()
+        .await
+        .map_err(|e| format!("モデル一覧取得失敗: {}", e))?;
+    let json: serde_json::Value = res.json().await.map_err(|e| format!("JSONパース失敗: {}", e))?;
+    let models = json["models"].as_array().cloned().unwrap_or_default();
+    let now = db::now_string();
+    let mut tx = pool.begin().await.map_err(|e| format!("トランザクション開始失敗: {}", e))?;
+    sqlx::query("DELETE FROM model_info_cache")
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("モデル情報削除失敗: {}", e))?;
+    for model in &models {
+        let Some(name) = model["name"].as_str() else { continue };
+        sqlx::query("INSERT OR REPLACE INTO model_info_cache (name, details, fetched_at) VALUES (?, ?, ?)")
+            .bind(name)
+            .bind(model.to_string())
+            .bind(&now)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("モデル情報保存失敗: {}", e))?;
+    }
+    tx.commit().await.map_err(|e| format!("コミット失敗: {}", e))?;
+    Ok(format!("{}件のモデル情報を更新", models.len()))
+}
+
+/// メンテナンスを1回実行してログに保存
+async fn run_maintenance(app: &AppHandle, trigger: &str) -> Result<MaintenanceReport, String> {
+    if RUNNING.swap(true, Ordering::SeqCst) {
+        return Err("メンテナンスは実行中です".into());
+    }
+    let result = run_maintenance_inner(app, trigger).await;
+    RUNNING.store(false, Ordering::SeqCst);
+    result
+}
+
+async fn run_maintenance_inner(app: &AppHandle, trigger: &str) -> Result<MaintenanceReport, String> {
+    info!("メンテナンス開始 (trigger={})", trigger);
+    let settings = config::load(app).await?;
+    let pool = db::pool(app).await?;
+    let started_at = db::now_string();
+    let mut steps = Vec::new();
+
+    // 削除を先に行い、その後の再構築・VACUUM で領域を回収する
+    run_step(&mut steps, "retention", enforce_retention(&pool, settings.retention_days)).await;
+    run_step(&mut steps, "rebuild_fts", rebuild_fts(&pool)).await;
+    run_step(&mut steps, "reindex", async {
+        sqlx::query("REINDEX").execute(&pool).await.map_err(|e| e.to_string())?;
+        Ok("完了".into())
+    })
+    .await;
+    run_step(&mut steps, "vacuum", async {
+        let report = vacuum(&pool).await?;
+        Ok(format!("{} → {} バイト", report.size_before, report.size_after))
+    })
+    .await;
+    run_step(&mut steps, "refresh_model_cache", refresh_model_cache(app, &pool)).await;
+
+    let finished_at = db::now_string();
+    let status = if steps.iter().all(|s| s.ok) { "ok" } else { "partial" };
+    let steps_json = serde_json::to_string(&steps).map_err(|e| e.to_string())?;
+    let id = sqlx::query(
+        "INSERT INTO maintenance_log (trigger_kind, started_at, finished_at, status, steps) VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(trigger)
+    .bind(&started_at)
+    .bind(&finished_at)
+    .bind(status)
+    .bind(steps_json)
+    .execute(&pool)
+    .await
+    .map_err(|e| format!("メンテナンスログ保存失敗: {}", e))?
+    .last_insert_rowid();
+    sqlx::query("DELETE FROM maintenance_log WHERE id <= ?")
+        .bind(id - MAX_LOG_ROWS)
+        .execute(&pool)
+        .await
+        .map_err(|e| format!("メンテナンスログ整理失敗: {}", e))?;
+
+    info!("メンテナンス完了 (status={})", status);
+    Ok(MaintenanceReport {
+        id,
+        trigger: trigger.to_string(),
+        started_at,
+        finished_at,
+        status: status.to_string(),
+        steps,
+    })
+}
+
+/// 前回実行からの経過時間（時間単位、未実行なら None）
+async fn hours_since_last_run(pool: &SqlitePool) -> Result<Option<i64>, String> {
+    let row: Option<(String,)> = sqlx::query_as("SELECT started_at FROM maintenance_log ORDER BY id DESC LIMIT 1")
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| format!("メンテナンスログ取得失敗: {}", e))?;
+    Ok(row
+        .and_then(|(at,)| NaiveDateTime::parse_from_str(&at, "%Y-%m-%d %H:%M:%S").ok())
+        .map(|at| (chrono::Utc::now().naive_utc() - at).num_hours()))
+}
+
+/// 今スケジュール実行すべきか判定
+async fn is_due(app: &AppHandle) -> Result<bool, String> {
+    let settings = config::load(app).await?;
+    if !settings.maintenance_enabled {
+        return Ok(false);
+    }
+    let pool = db::pool(app).await?;
+    let since = hours_since_last_run(&pool).await?;
+    let due = match settings.maintenance_hour {
+        Some(hour) => {
+            chrono::Local::now().hour() == hour as u32 && since.is_none_or(|h| h >= MIN_HOURS_BETWEEN_SCHEDULED)
+        }
+        None => {
+            let idle = discussion_engine::engine_state(app).idle_for().is_none_or(|d| d >= IDLE_THRESHOLD);
+            idle && since.is_none_or(|h| h >= MIN_HOURS_BETWEEN_IDLE)
+        }
+    };
+    Ok(due)
+}
+
+/// スケジューラをバックグラウンドで起動（アプリ起動時に1回呼ぶ）
+pub fn start_scheduler(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(CHECK_INTERVAL).await;
+            match is_due(&app).await {
+                Ok(true) => {
+                    if let Err(e) = run_maintenance(&app, TRIGGER_SCHEDULED).await {
+                        warn!("定期メンテナンス失敗: {}", e);
+                    }
+                }
+                Ok(false) => {}
+                Err(e) => warn!("メンテナンス判定失敗: {}", e),
+            }
+        }
+    });
+}
+
+// メンテナンスを今すぐ実行
+#[command]
+pub async fn run_maintenance_now(app: AppHandle) -> Result<MaintenanceReport, String> {
+    info!("run_maintenance_now 呼び出し");
+    run_maintenance(&app, TRIGGER_MANUAL).await
+}
+
+// 直近のメンテナンス実行履歴を取得（新しい順）
+#[command]
+pub async fn get_maintenance_log(app: AppHandle, limit: Option<i64>) -> Result<Vec<MaintenanceReport>, String> {
+    let pool = db::pool(&app).await?;
+    let rows: Vec<(i64, String, String, String, String, String)> = sqlx::query_as(
+        "SELECT id, trigger_kind, started_at, finished_at, status, steps FROM maintenance_log ORDER BY id DESC LIMIT ?",
+    )
+    .bind(limit.unwrap_or(20).clamp(1, MAX_LOG_ROWS))
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("メンテナンスログ取得失敗: {}", e))?;
+    Ok(rows
+        .into_iter()
+        .map(|(id, trigger, started_at, finished_at, status, steps)| MaintenanceReport {
+            id,
+            trigger,
+            started_at,
+            finished_at,
+            status,
+            steps: serde_json::from_str(&steps).unwrap_or_default(),
+        })
+        .collect())
+}
+
+// データベースの整合性をチェックする（PRAGMA integrity_check と foreign_key_check）
+#[command]
+pub async fn check_db_integrity(app: AppHandle) -> Result<IntegrityReport, String> {
+    info!("check_db_integrity 呼び出し");
+    let pool = db::pool(&app).await?;
+    let rows: Vec<(String,)> = sqlx::query_as("PRAGMA integrity_check")
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| format!("整合性チェック失敗: {}", e))?;
+    let problems: Vec<String> = rows.into_iter().map(|(line,)| line).filter(|line| line != "ok").collect();
+    let violations: Vec<(String, Option<i64>, String, i64)> = sqlx::query_as("PRAGMA foreign_key_check")
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| format!("外部キーチェック失敗: {}", e))?;
+    let foreign_key_violations: Vec<String> = violations
+        .into_iter()
+        .map(|(table, rowid, parent, _)| match rowid {
+            Some(rowid) => format!("{}#{} -> {}", table, rowid, parent),
+            None => format!("{} -> {}", table, parent),
+        })
+        .collect();
+    let (journal_mode,): (String,) = sqlx::query_as("PRAGMA journal_mode")
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| format!("ジャーナルモード取得失敗: {}", e))?;
+    let ok = problems.is_empty() && foreign_key_violations.is_empty();
+    info!(
+        "整合性チェック: {} (問題{}件, 外部キー違反{}件)",
+        if ok { "正常" } else { "異常" },
+        problems.len(),
+        foreign_key_violations.len()
+    );
+    Ok(IntegrityReport { ok, problems, foreign_key_violations, journal_mode, checked_at: db::now_string() })
+}
+
+// VACUUM を今すぐ実行し、実行前後のサイズを返す（メンテナンス実行中はエラー）
+#[command]
+pub async fn vacuum_db(app: AppHandle) -> Result<VacuumReport, String> {
+    info!("vacuum_db 呼び出し");
+    if RUNNING.swap(true, Ordering::SeqCst) {
+        return Err("メンテナンスは実行中です".into());
+    }
+    let result = async { vacuum(&db::pool(&app).await?).await }.await;
+    RUNNING.store(false, Ordering::SeqCst);
+    let report = result?;
+    info!("VACUUM 完了: {} → {} バイト", report.size_before, report.size_after);
+    Ok(report)
+}
@@ -0,0 +1,421 @@
+// 発言テーブル（messages）の参照・編集・削除・再生成
+// sessions.messages の JSON はトリガーで messages テーブルに同期されるため、検索・ページングはこちらを使う
+// 追記は append_message（JSON の末尾に json_insert で足す → トリガーは増えた行だけを追加する）
+// 編集・再生成も JSON を書き換えてトリガーで同期する。トリガーは変わった行だけを書き換えるため発言の id は変わらず、
+// 削除は後続の行の位置を先に詰めてから JSON を書き換えるので、残った発言の id もそのまま使える
+// JSON を書き換える時は、引いた時点の本文がその位置に残っていることを確かめ、別の更新と食い違えば保存しない
+use serde::Serialize;
+use tauri::{command, AppHandle, Manager};
+use tracing::info;
+
+use crate::{
+    app_state::{AppState, RuntimeSettings},
+    db, discussion_engine, encryption, encryption::KeyStore, generate_persona_reply, generation::GenerationOptions, is_allowed_model,
+    ERR_UNSUPPORTED_MODEL,
+};
+
+// 1回に取得する件数の上限
+const MAX_PAGE: i64 = 500;
+
+const ERR_CHANGED: &str = "発言が別の操作で変更されたため、保存しませんでした。読み込み直してください";
+
+// JSON の位置（1つ目の ? に '$[i]'）の本文が、引いた時の本文（2つ目の ?）のままか
+// message キーのない発言はトリガーが '' として行にしているので、JSON 側も '' とみなして比べる
+const SAME_CONTENT: &str = "COALESCE(json_extract(messages, ? || '.message'), '') = COALESCE(?, '')";
+
+/// messages テーブルの1行
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageRow {
+    pub id: i64,
+    pub session_id: i64,
+    /// セッション内の位置（annotations / generation_log の message_index と同じ）
+    pub position: i64,
+    pub speaker: String,
+    /// "user" | "ai"
+    pub role: String,
+    pub content: String,
+    pub created_at: String,
+    /// シードなど、発言本文以外の項目
+    pub metadata: serde_json::Value,
+}
+
+/// messages の行（ID, 位置, 話者, 役割, 本文, 日時, メタデータ）
+type MessageTuple = (i64, i64, String, String, String, String, String);
+
+/// 行を MessageRow にする（暗号化された本文は復号する）
+fn to_row(
+    keys: &KeyStore,
+    session_id: i64,
+    (id, position, speaker, role, content, created_at, metadata): MessageTuple,
+) -> Result<MessageRow, String> {
+    Ok(MessageRow {
+        id,
+        session_id,
+        position,
+        speaker,
+        role,
+        content: keys.open(&content)?,
+        created_at,
+        metadata: serde_json::from_str(&metadata).unwrap_or(serde_json::Value::Null),
+    })
+}
+
+/// セッションの messages JSON の末尾に発言を1件追記し、追記後の件数を返す
+/// （Rust 側で配列を読み込まず SQLite の json_insert で足す。フロントエンドが保存する他の項目もそのまま残す）
+pub async fn append_json(app: &AppHandle, session_id: i64, message: serde_json::Value) -> Result<usize, String> {
+    let pool = db::pool(app).await?;
+    let state = app.state::<AppState>();
+    append_json_in(&pool, &state.keys, &state.settings, session_id, message).await
+}
+
+/// append_json のトランザクション内版（自動進行は実行状態の保存と同じトランザクションで追記する）
+pub async fn append_json_in<'e>(
+    executor: impl sqlx::SqliteExecutor<'e>,
+    keys: &KeyStore,
+    settings: &RuntimeSettings,
+    session_id: i64,
+    mut message: serde_json::Value,
+) -> Result<usize, String> {
+    if !message.is_object() {
+        return Err("発言の形式が正しくありません".into());
+    }
+    if let Some(text) = message.get("message").and_then(|m| m.as_str()) {
+        message["message"] = serde_json::Value::String(db::storable_text(keys, settings, text)?);
+    }
+    let row: Option<(i64,)> = sqlx::query_as(
+        "UPDATE sessions SET messages = json_insert(CASE WHEN json_valid(messages) THEN messages ELSE '[]' END, '$[#]', json(?)),
+                             updated_at = ?
+         WHERE id = ? RETURNING json_array_length(messages)",
+    )
+    .bind(message.to_string())
+    .bind(db::now_string())
+    .bind(session_id)
+    .fetch_optional(executor)
+    .await
+    .map_err(|e| format!("メッセージ保存失敗: {}", e))?;
+    let (count,) = row.ok_or_else(|| format!("セッションが見つかりません: id={}", session_id))?;
+    Ok(count.max(0) as usize)
+}
+
+/// セッションの発言数
+pub async fn count(app: &AppHandle, session_id: i64) -> Result<usize, String> {
+    let pool = db::pool(app).await?;
+    let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM messages WHERE session_id = ?")
+        .bind(session_id)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| format!("発言数取得失敗: {}", e))?;
+    Ok(count.max(0) as usize)
+}
+
+/// 発言IDから引いたセッション・位置・保存されている本文（暗号化されていればそのまま）
+struct Located {
+    session_id: i64,
+    position: i64,
+    stored_content: String,
+}
+
+/// 発言IDからセッションと位置を引く
+async fn locate<'e>(executor: impl sqlx::SqliteExecutor<'e>, message_id: i64) -> Result<Located, String> {
+    let row: Option<(i64, i64, String)> =
+        sqlx::query_as("SELECT session_id, position, content FROM messages WHERE id = ?")
+            .bind(message_id)
+            .fetch_optional(executor)
+            .await
+            .map_err(|e| format!("発言取得失敗: {}", e))?;
+    let (session_id, position, stored_content) =
+        row.ok_or_else(|| format!("発言が見つかりません: id={}", message_id))?;
+    Ok(Located { session_id, position, stored_content })
+}
+
+/// 位置を指定して1行取得（JSON を書き換えた後の新しい行）
+async fn row_at(pool: &sqlx::SqlitePool, keys: &KeyStore, session_id: i64, position: i64) -> Result<MessageRow, String> {
+    let row: MessageTuple = sqlx::query_as(
+        "SELECT id, position, speaker, role, content, created_at, metadata FROM messages
+         WHERE session_id = ? AND position = ?",
+    )
+    .bind(session_id)
+    .bind(position)
+    .fetch_one(pool)
+    .await
+    .map_err(|e| format!("発言取得失敗: {}", e))?;
+    to_row(keys, session_id, row)
+}
+
+// 既存のセッションの末尾に発言を1件追記し、追記後の件数を返す（保存後の処理も行う）
+// message はフロントエンドの発言オブジェクト（speaker・message・isUser・timestamp など）
+#[command]
+pub async fn append_message(app: AppHandle, session_id: i64, message: serde_json::Value) -> Result<usize, String> {
+    let count = append_json(&app, session_id, message).await?;
+    discussion_engine::on_message_persisted(&app, session_id);
+    Ok(count)
+}
+
+// セッションの発言を位置順に取得（offset / limit でページング）
+#[command]
+pub async fn get_messages(
+    app: AppHandle,
+    session_id: i64,
+    offset: Option<i64>,
+    limit: Option<i64>,
+) -> Result<Vec<MessageRow>, String> {
+    let pool = db::pool(&app).await?;
+    let rows: Vec<MessageTuple> = sqlx::query_as(
+        "SELECT id, position, speaker, role, content, created_at, metadata FROM messages
+         WHERE session_id = ? ORDER BY position LIMIT ? OFFSET ?",
+    )
+    .bind(session_id)
+    .bind(limit.unwrap_or(100).clamp(1, MAX_PAGE))
+    .bind(offset.unwrap_or(0).max(0))
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("発言取得失敗: {}", e))?;
+    let keys = encryption::keys(&app);
+    rows.into_iter().map(|row| to_row(keys, session_id, row)).collect()
+}
+
+/// 行と JSON の両方から発言を消し、残りの件数を返す（JSON が引いた時と食い違えば ERR_CHANGED）
+async fn remove_located(tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>, located: &Located) -> Result<usize, String> {
+    let Located { session_id, position, stored_content } = located;
+    // 先に行を消して後続の位置を詰めておけば、JSON を書き換えた時のトリガーは何も作り直さない
+    // （UNIQUE(session_id, position) に触れないよう、いったん負の位置に移してから詰める）
+    sqlx::query("DELETE FROM messages WHERE session_id = ? AND position = ?")
+        .bind(session_id)
+        .bind(position)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("発言削除失敗: {}", e))?;
+    sqlx::query("UPDATE messages SET position = -position WHERE session_id = ? AND position > ?")
+        .bind(session_id)
+        .bind(position)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("発言の位置更新失敗: {}", e))?;
+    sqlx::query("UPDATE messages SET position = -position - 1 WHERE session_id = ? AND position < 0")
+        .bind(session_id)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("発言の位置更新失敗: {}", e))?;
+    let path = format!("$[{}]", position);
+    let row: Option<(i64,)> = sqlx::query_as(&format!(
+        "UPDATE sessions SET messages = json_remove(messages, ?), updated_at = ?
+         WHERE id = ? AND {}
+         RETURNING json_array_length(messages)",
+        SAME_CONTENT
+    ))
+    .bind(&path)
+    .bind(db::now_string())
+    .bind(session_id)
+    .bind(&path)
+    .bind(stored_content)
+    .fetch_optional(&mut **tx)
+    .await
+    .map_err(|e| format!("発言削除失敗: {}", e))?;
+    // messages と JSON が食い違っていれば（別の更新の途中など）何も消さない
+    let Some((remaining,)) = row else {
+        return Err(ERR_CHANGED.to_string());
+    };
+
+    for table in ["annotations", "generation_log"] {
+        sqlx::query(&format!("DELETE FROM {} WHERE session_id = ? AND message_index = ?", table))
+            .bind(session_id)
+            .bind(position)
+            .execute(&mut **tx)
+            .await
+            .map_err(|e| format!("{} の削除失敗: {}", table, e))?;
+        sqlx::query(&format!(
+            "UPDATE {} SET message_index = message_index - 1 WHERE session_id = ? AND message_index > ?",
+            table
+        ))
+        .bind(session_id)
+        .bind(position)
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| format!("{} の位置更新失敗: {}", table, e))?;
+    }
+    Ok(remaining.max(0) as usize)
+}
+
+/// JSON の本文を保存用の本文（暗号化・伏せ字済み）に書き換え、editedAt を記録する（食い違えば ERR_CHANGED）
+async fn replace_located<'e>(
+    executor: impl sqlx::SqliteExecutor<'e>,
+    located: &Located,
+    stored_text: &str,
+) -> Result<(), String> {
+    let path = format!("$[{}]", located.position);
+    let updated = sqlx::query(&format!(
+        "UPDATE sessions SET messages = json_set(messages, ? || '.message', ?, ? || '.editedAt', ?), updated_at = ?
+         WHERE id = ? AND {}",
+        SAME_CONTENT
+    ))
+    .bind(&path)
+    .bind(stored_text)
+    .bind(&path)
+    .bind(discussion_engine::now_timestamp())
+    .bind(db::now_string())
+    .bind(located.session_id)
+    .bind(&path)
+    .bind(&located.stored_content)
+    .execute(executor)
+    .await
+    .map_err(|e| format!("発言更新失敗: {}", e))?;
+    if updated.rows_affected() == 0 {
+        return Err(ERR_CHANGED.to_string());
+    }
+    Ok(())
+}
+
+// 発言を1件削除し、残りの件数を返す
+// 後続の発言の位置が1つずつ詰まるため、注釈・生成ログの message_index も合わせて詰める
+#[command]
+pub async fn delete_message(app: AppHandle, message_id: i64) -> Result<usize, String> {
+    info!("delete_message 呼び出し: message_id={}", message_id);
+    let pool = db::pool(&app).await?;
+    let mut tx = pool.begin().await.map_err(|e| format!("トランザクション開始失敗: {}", e))?;
+    let located = locate(&mut *tx, message_id).await?;
+    let remaining = remove_located(&mut tx, &located).await?;
+    tx.commit().await.map_err(|e| format!("コミット失敗: {}", e))?;
+    info!("発言を削除: session_id={}, position={}", located.session_id, located.position);
+    Ok(remaining)
+}
+
+// 発言の本文を書き換え、更新後の行を返す（editedAt を記録する）
+#[command]
+pub async fn edit_message(app: AppHandle, message_id: i64, new_content: String) -> Result<MessageRow, String> {
+    info!("edit_message 呼び出し: message_id={}", message_id);
+    let content = new_content.trim();
+    if content.is_empty() {
+        return Err("発言の本文を入力してください".into());
+    }
+    let pool = db::pool(&app).await?;
+    let located = locate(&pool, message_id).await?;
+    let state = app.state::<AppState>();
+    replace_located(&pool, &located, &db::storable_text(&state.keys, &state.settings, content)?).await?;
+    discussion_engine::on_message_persisted(&app, located.session_id);
+    row_at(&pool, encryption::keys(&app), located.session_id, located.position).await
+}
+
+// AIの発言を、それより前の発言だけを文脈にして生成し直し、更新後の行を返す（regeneratedAt を記録する）
+// model 未指定ならセッションのモデル。生成ログには同じ位置の発言として記録する
+#[command]
+pub async fn regenerate_message(app: AppHandle, message_id: i64, model: Option<String>) -> Result<MessageRow, String> {
+    info!("regenerate_message 呼び出し: message_id={}, model={:?}", message_id, model);
+    let pool = db::pool(&app).await?;
+    let Located { session_id, position, stored_content } = locate(&pool, message_id).await?;
+    let session = db::load_session(&app, session_id).await?;
+    let model = model.unwrap_or_else(|| session.model.clone());
+    if !is_allowed_model(&app, &model) {
+        return Err(ERR_UNSUPPORTED_MODEL.to_string());
+    }
+    let index = position as usize;
+    let original = session.messages.get(index).ok_or_else(|| format!("発言が見つかりません: id={}", message_id))?;
+    if original.is_user {
+        return Err("ユーザーの発言は生成し直せません".into());
+    }
+    let participant = session.participants.ai_data.iter().find(|p| p.name == original.speaker);
+    let history = db::format_history(&session.messages[..index]);
+    let result = generate_persona_reply(
+        &app,
+        &original.speaker,
+        participant.map(|p| p.role.as_str()).unwrap_or_default(),
+        participant.map(|p| p.description.as_str()).unwrap_or_default(),
+        &history,
+        &session.topic,
+        &model,
+        Some(session_id),
+        Some(position),
+        GenerationOptions::default(),
+        None,
+    )
+    .await?;
+
+    let path = format!("$[{}]", position);
+    let state = app.state::<AppState>();
+    let generation = serde_json::to_string(&result.meta).map_err(|e| format!("メタデータのシリアライズ失敗: {}", e))?;
+    let updated = sqlx::query(&format!(
+        "UPDATE sessions SET messages = json_remove(
+                json_set(messages, ? || '.message', ?, ? || '.seed', ?, ? || '.generation', json(?), ? || '.regeneratedAt', ?),
+                ? || '.editedAt'),
+             updated_at = ?
+         WHERE id = ? AND json_extract(messages, ? || '.speaker') = ?
+           AND {}",
+        SAME_CONTENT
+    ))
+    .bind(&path)
+    .bind(db::storable_text(&state.keys, &state.settings, result.text.trim())?)
+    .bind(&path)
+    .bind(result.seed)
+    .bind(&path)
+    .bind(generation)
+    .bind(&path)
+    .bind(discussion_engine::now_timestamp())
+    .bind(&path)
+    .bind(db::now_string())
+    .bind(session_id)
+    .bind(&path)
+    .bind(&original.speaker)
+    .bind(&path)
+    .bind(&stored_content)
+    .execute(&pool)
+    .await
+    .map_err(|e| format!("発言更新失敗: {}", e))?;
+    // 生成中に発言が削除・編集・並べ替えられていたら書き込まない
+    if updated.rows_affected() == 0 {
+        return Err("生成中に発言が変更されたため、結果を保存しませんでした".into());
+    }
+    discussion_engine::on_message_persisted(&app, session_id);
+    row_at(&pool, encryption::keys(&app), session_id, position).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// messages キーのない発言を含むセッションを作り、その発言の行を引く
+    async fn session_with_bare_message(pool: &sqlx::SqlitePool) -> Located {
+        let messages = serde_json::json!([
+            { "speaker": "田中", "message": "最初の発言", "isUser": false, "timestamp": "t" },
+            { "speaker": "佐藤", "isUser": false, "timestamp": "t" },
+            { "speaker": "鈴木", "message": "最後の発言", "isUser": false, "timestamp": "t" }
+        ]);
+        sqlx::query(
+            "INSERT INTO sessions (topic, participants, messages, model, created_at, updated_at)
+             VALUES ('テーマ', '{}', ?, 'gemma3:4b', '', '')",
+        )
+        .bind(messages.to_string())
+        .execute(pool)
+        .await
+        .unwrap();
+        let (id,): (i64,) = sqlx::query_as("SELECT id FROM messages WHERE speaker = '佐藤'").fetch_one(pool).await.unwrap();
+        locate(pool, id).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn message_without_text_can_be_edited() {
+        let pool = db::migrated_pool().await;
+        let located = session_with_bare_message(&pool).await;
+        assert_eq!(located.stored_content, "");
+        replace_located(&pool, &located, "書き足した発言").await.unwrap();
+        let row = row_at(&pool, &KeyStore::default(), located.session_id, located.position).await.unwrap();
+        assert_eq!(row.content, "書き足した発言");
+        // 引いた時の本文から変わっていれば保存しない
+        assert_eq!(replace_located(&pool, &located, "もう一度").await.unwrap_err(), ERR_CHANGED);
+    }
+
+    #[tokio::test]
+    async fn message_without_text_can_be_deleted() {
+        let pool = db::migrated_pool().await;
+        let located = session_with_bare_message(&pool).await;
+        let mut tx = pool.begin().await.unwrap();
+        assert_eq!(remove_located(&mut tx, &located).await.unwrap(), 2);
+        tx.commit().await.unwrap();
+        let speakers: Vec<(String, i64)> =
+            sqlx::query_as("SELECT speaker, position FROM messages WHERE session_id = ? ORDER BY position")
+                .bind(located.session_id)
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        assert_eq!(speakers, vec![("田中".to_string(), 0), ("鈴木".to_string(), 1)]);
+    }
+}
@@ -0,0 +1,122 @@
+// 発言ごとの生成計測の集計
+// AIの発言に保存された generation（モデル・トークン数・生成速度・再送回数・実時間）をモデル別にまとめ、
+// gemma3:1b と 4b などの速度・待ち時間の違いを画面で比べられるようにする
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use tauri::{command, AppHandle};
+use tracing::info;
+
+use crate::{db, generation::GenerationMeta};
+
+/// 値の合計と件数（値のない発言は平均に含めない）
+#[derive(Default)]
+struct Mean {
+    sum: f64,
+    count: usize,
+}
+
+impl Mean {
+    fn add(&mut self, value: Option<f64>) {
+        if let Some(value) = value {
+            self.sum += value;
+            self.count += 1;
+        }
+    }
+
+    fn value(&self) -> Option<f64> {
+        (self.count > 0).then(|| self.sum / self.count as f64)
+    }
+}
+
+#[derive(Default)]
+struct Accumulator {
+    messages: usize,
+    prompt_tokens: u64,
+    completion_tokens: u64,
+    retries: u64,
+    tokens_per_sec: Mean,
+    duration_ms: Mean,
+    wall_ms: Mean,
+}
+
+impl Accumulator {
+    fn add(&mut self, meta: &GenerationMeta) {
+        self.messages += 1;
+        self.prompt_tokens += meta.prompt_tokens.unwrap_or(0) as u64;
+        self.completion_tokens += meta.completion_tokens.unwrap_or(0) as u64;
+        self.retries += meta.retries.unwrap_or(0) as u64;
+        self.tokens_per_sec.add(meta.tokens_per_sec);
+        self.duration_ms.add(meta.duration_ms.map(|ms| ms as f64));
+        self.wall_ms.add(meta.wall_ms.map(|ms| ms as f64));
+    }
+
+    fn summary(&self, model: Option<String>) -> MetricsSummary {
+        MetricsSummary {
+            model,
+            messages: self.messages,
+            prompt_tokens: self.prompt_tokens,
+            completion_tokens: self.completion_tokens,
+            retries: self.retries,
+            avg_tokens_per_sec: self.tokens_per_sec.value(),
+            avg_duration_ms: self.duration_ms.value(),
+            avg_wall_ms: self.wall_ms.value(),
+        }
+    }
+}
+
+/// モデル別（model が None なら全体）の集計
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricsSummary {
+    pub model: Option<String>,
+    /// 計測値のあるAIの発言数
+    pub messages: usize,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// 再送回数の合計
+    pub retries: u64,
+    pub avg_tokens_per_sec: Option<f64>,
+    /// 生成そのものの平均時間
+    pub avg_duration_ms: Option<f64>,
+    /// 待ち時間・再送を含む平均の実時間
+    pub avg_wall_ms: Option<f64>,
+}
+
+/// get_session_metrics の戻り値
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionMetrics {
+    pub session_id: i64,
+    pub ai_messages: usize,
+    /// 計測値のないAIの発言数（記録前に作られた発言など）
+    pub unmeasured: usize,
+    pub total: MetricsSummary,
+    /// 発言数の多い順
+    pub by_model: Vec<MetricsSummary>,
+}
+
+// セッションのAIの発言の生成計測をモデル別に集計する
+#[command]
+pub async fn get_session_metrics(app: AppHandle, session_id: i64) -> Result<SessionMetrics, String> {
+    info!("get_session_metrics 呼び出し: session_id={}", session_id);
+    let session = db::load_session(&app, session_id).await?;
+    let mut total = Accumulator::default();
+    let mut by_model: BTreeMap<String, Accumulator> = BTreeMap::new();
+    let mut ai_messages = 0;
+    for message in session.messages.iter().filter(|m| !m.is_user) {
+        ai_messages += 1;
+        let Some(meta) = &message.generation else { continue };
+        total.add(meta);
+        by_model.entry(meta.model.clone()).or_default().add(meta);
+    }
+    let mut by_model: Vec<MetricsSummary> = by_model.into_iter().map(|(model, acc)| acc.summary(Some(model))).collect();
+    by_model.sort_by_key(|m| std::cmp::Reverse(m.messages));
+    Ok(SessionMetrics {
+        session_id,
+        ai_messages,
+        unmeasured: ai_messages - total.messages,
+        total: total.summary(None),
+        by_model,
+    })
+}
@@ -0,0 +1,330 @@
+// モック LLM バックエンド
+// Ollama なしでデモ・UI 開発・動作確認ができるよう、プロンプトの種類に応じた定型応答を擬似的な遅延付きで返す
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde_json::json;
+use tracing::debug;
+
+use crate::{
+    backend::{ChunkSink, LlmBackend},
+    gen_error::GenError,
+    generation::{GenerationOptions, GenerationResult},
+};
+
+// 応答開始までの待ち時間と、1断片あたりの待ち時間
+const FIRST_TOKEN_DELAY: Duration = Duration::from_millis(400);
+const CHUNK_DELAY: Duration = Duration::from_millis(35);
+// 1断片の文字数
+const CHUNK_CHARS: usize = 4;
+
+const MOCK_MODELS: [&str; 2] = ["gemma3:4b", "gemma3:1b"];
+
+const REPLY_TEMPLATES: [&str; 6] = [
+    "{name}の立場から言うと、「{topic}」はまず身近な具体例で考えるべきだと思います。",
+    "なるほど、今の意見には一理ありますね。ただ、{topic}には別の側面もあるのではないでしょうか。",
+    "{role}として気になるのは、それを実際に続けられるかどうかです。どうやって確かめますか？",
+    "仮にそれが実現したとして、一番影響を受けるのは誰なのかを考えてみたいです。",
+    "私は少し懐疑的です。その主張を支える根拠があれば教えてください。",
+    "前の発言に補足すると、短期と長期で分けて考えると整理しやすいと思います。",
+];
+
+// 参加者の説明（description）の1文目を口調に使う発言
+const PERSONA_TEMPLATES: [&str; 3] = [
+    "{trait}私としては、「{topic}」も結局はそこにつながる話だと思います。",
+    "{role}の経験から言うと、今の話は現場では少し違って見えます。{trait}",
+    "{trait}だからこそ、今の意見にはもう一歩踏み込んで考えたいです。",
+];
+
+// 司会者の介入（自動進行の司会役）
+const MODERATOR_TEMPLATES: [&str; 2] = [
+    "少し整理しましょう。ここまでの論点は出そろってきたので、{quiet}の意見も聞かせてください。",
+    "話がテーマから離れてきたようです。「{topic}」に戻って、まだ話していない観点を挙げてみましょう。",
+];
+
+const PROFILE_POOL: [(&str, &str, &str); 6] = [
+    ("佐藤", "高校教師", "現場の実感を大切にし、生徒への影響を第一に考える。穏やかだが具体例を求める。"),
+    ("鈴木", "経済アナリスト", "数字とコストで物事を判断する。楽観論には必ず反証を探す慎重派。"),
+    ("高橋", "大学生", "当事者として率直に意見を言う。新しい技術や価値観に前向き。"),
+    ("田中", "自治体職員", "制度と実務の両面から考える。実現可能性と公平性を重視する。"),
+    ("伊藤", "エンジニア", "仕組みで問題を解決したい実務家。抽象論より手順を語る。"),
+    ("渡辺", "看護師", "人の健康と生活の質を軸に考える。弱い立場の人の声を代弁する。"),
+];
+
+/// 定型応答を返すモックバックエンド
+pub struct MockBackend;
+
+/// 最初に現れる <tag>...</tag> の中身（XML エスケープを戻す）
+fn tag_content(prompt: &str, tag: &str) -> Option<String> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let start = prompt.find(&open)? + open.len();
+    let end = start + prompt[start..].find(&close)?;
+    Some(
+        prompt[start..end]
+            .trim()
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&apos;", "'")
+            .replace("&amp;", "&"),
+    )
+}
+
+/// <tag>...</tag> の中身をすべて（出現順）
+fn tag_contents(section: &str, tag: &str) -> Vec<String> {
+    let open = format!("<{}>", tag);
+    let mut found = Vec::new();
+    let mut rest = section;
+    while let Some(content) = tag_content(rest, tag) {
+        found.push(content);
+        let Some(at) = rest.find(&open) else { break };
+        rest = &rest[at + open.len()..];
+    }
+    found
+}
+
+/// 説明の1文目（句点まで。説明がなければ空文字）
+fn first_sentence(description: &str) -> String {
+    match description.find('。') {
+        Some(end) => description[..end + '。'.len_utf8()].to_string(),
+        None if description.is_empty() => String::new(),
+        None => format!("{}。", description),
+    }
+}
+
+/// プロンプトのルート要素名
+fn root_tag(prompt: &str) -> &str {
+    let trimmed = prompt.trim_start();
+    trimmed
+        .strip_prefix('<')
+        .and_then(|rest| rest.split(['>', ' ']).next())
+        .unwrap_or("")
+}
+
+/// プロンプトとシードから決まる擬似乱数（同じ入力なら同じ応答にする）
+fn pick(prompt: &str, options: &GenerationOptions, len: usize) -> usize {
+    let mut hasher = DefaultHasher::new();
+    prompt.hash(&mut hasher);
+    options.seed.hash(&mut hasher);
+    (hasher.finish() % len as u64) as usize
+}
+
+impl MockBackend {
+    /// プロンプトの種類に応じた応答を組み立てる
+    fn respond(&self, prompt: &str, options: &GenerationOptions) -> String {
+        let topic = tag_content(prompt, "discussion_topic")
+            .or_else(|| tag_content(prompt, "topic"))
+            .unwrap_or_else(|| "このテーマ".to_string());
+
+        match root_tag(prompt) {
+            "discussion_context" => {
+                let name = tag_content(prompt, "name").unwrap_or_else(|| "参加者".to_string());
+                let role = tag_content(prompt, "role").unwrap_or_else(|| "参加者".to_string());
+                let persona = first_sentence(&tag_content(prompt, "description").unwrap_or_default());
+                // 説明があれば半分程度はその人らしさの出る発言にする
+                let template = if !persona.is_empty() && pick(&format!("{}-persona", prompt), options, 2) == 0 {
+                    PERSONA_TEMPLATES[pick(prompt, options, PERSONA_TEMPLATES.len())]
+                } else {
+                    REPLY_TEMPLATES[pick(prompt, options, REPLY_TEMPLATES.len())]
+                };
+                template
+                    .replace("{name}", &name)
+                    .replace("{role}", &role)
+                    .replace("{trait}", &persona)
+                    .replace("{topic}", &topic)
+            }
+            "devils_advocate" => {
+                let role = tag_content(prompt, "role").unwrap_or_else(|| "参加者".to_string());
+                format!(
+                    "あえて反対の立場から言います。{}として見ると、皆さんが前提にしている「うまくいく」という仮定は、\
+                     費用や負担が想定より大きい場合に崩れます。その場合でも同じ結論になりますか？",
+                    role
+                )
+            }
+            "moderator_intervention" => {
+                let participants = tag_content(prompt, "participants").unwrap_or_default();
+                let quiet = participants.split(", ").last().filter(|n| !n.is_empty()).unwrap_or("皆さん");
+                MODERATOR_TEMPLATES[pick(prompt, options, MODERATOR_TEMPLATES.len())]
+                    .replace("{quiet}", &format!("{}さん", quiet.trim_end_matches("さん")))
+                    .replace("{topic}", &topic)
+            }
+            "discussion_start" => format!(
+                "それでは「{}」について議論を始めましょう。私はまず、身近な影響から考えるのが大切だと考えます。皆さんはどこに一番の課題があると思いますか？",
+                topic
+            ),
+            "discussion_summary" | "incremental_discussion_summary" | "summary_merge" => format!(
+                "【モック要約】\n- テーマ：{}\n- 主な論点：実現可能性、影響を受ける人、根拠の確かさ\n- 合意点：具体例で考える必要がある\n- 今後の課題：短期と長期の影響の整理",
+                topic
+            ),
+            "discussion_analysis" | "incremental_discussion_analysis" => json!({
+                "mainPoints": [{ "point": "実現可能性", "description": "続けられる仕組みがあるか" }],
+                "participantStances": [],
+                "conflicts": [{ "issue": "根拠の確かさ", "sides": ["楽観", "慎重"], "description": "データの解釈が分かれている" }],
+                "commonGround": ["具体例で考える必要がある"],
+                "unexploredAreas": ["長期的な影響"]
+            })
+            .to_string(),
+            "ai_profiles_generation" | "ai_profiles_repair" => {
+                let count = tag_content(prompt, "count").and_then(|c| c.parse::<usize>().ok()).unwrap_or(3);
+                // constraints の必須の役職（「role が「弁護士」の参加者を必ず1人含める」）は先頭から当てはめる
+                let constraints = tag_content(prompt, "constraints").unwrap_or_default();
+                let required: Vec<&str> = constraints
+                    .split("role が「")
+                    .skip(1)
+                    .filter_map(|rest| rest.split_once('」').map(|(role, _)| role))
+                    .collect();
+                let profiles: Vec<_> = PROFILE_POOL
+                    .iter()
+                    .cycle()
+                    .take(count.clamp(1, PROFILE_POOL.len()))
+                    .enumerate()
+                    .map(|(i, (name, role, description))| {
+                        let role = required.get(i).copied().unwrap_or(role);
+                        json!({ "name": name, "role": role, "description": description })
+                    })
+                    .collect();
+                serde_json::Value::Array(profiles).to_string()
+            }
+            "argumentation_coaching" => {
+                let draft = tag_content(prompt, "user_draft").unwrap_or_default();
+                json!({
+                    "clarity": { "score": 3, "comment": "主張は伝わります。結論を先に述べるとより明確です。" },
+                    "evidence": { "score": 2, "comment": "具体例や根拠を1つ加えましょう。" },
+                    "tone": { "score": 4, "comment": "丁寧で建設的な口調です。" },
+                    "logicalGaps": [],
+                    "overall": "方向性は良いので、根拠を補うと説得力が増します。",
+                    "improvedDraft": draft
+                })
+                .to_string()
+            }
+            "text_simplification" | "reading_level_rewrite" => tag_content(prompt, "original_text").unwrap_or_default(),
+            "batch_translation" => {
+                let lang = tag_content(prompt, "target_language").unwrap_or_default();
+                let items: Vec<_> = prompt
+                    .split("<item index=\"")
+                    .skip(1)
+                    .filter_map(|rest| {
+                        let (index, rest) = rest.split_once("\">")?;
+                        let (text, _) = rest.split_once("</item>")?;
+                        Some(json!({ "index": index.parse::<usize>().ok()?, "text": format!("[{}] {}", lang, text) }))
+                    })
+                    .collect();
+                serde_json::Value::Array(items).to_string()
+            }
+            "proofreading" | "pii_detection" => "[]".to_string(),
+            "debate_judgement" => {
+                let a = 5 + pick(prompt, options, 4) as u8;
+                let b = 5 + pick(&format!("{}-b", prompt), options, 4) as u8;
+                json!({
+                    "affirmative": { "logic": a, "evidence": a, "rebuttal": a, "clarity": a },
+                    "negative": { "logic": b, "evidence": b, "rebuttal": b, "clarity": b },
+                    "reason": "（モック審査）双方とも論点は明確でした。"
+                })
+                .to_string()
+            }
+            "discussion_quality_evaluation" => {
+                json!({ "score": 4 + pick(prompt, options, 5), "reason": "（モック評価）発言同士の応答はおおむね成立しています。" })
+                    .to_string()
+            }
+            "next_speaker_selection" => {
+                let names = tag_contents(&tag_content(prompt, "candidates").unwrap_or_default(), "name");
+                let speaker = names.get(pick(prompt, options, names.len().max(1))).cloned().unwrap_or_default();
+                json!({ "speaker": speaker, "reason": "（モック推薦）しばらく発言していないため" }).to_string()
+            }
+            "persona_state_update" => {
+                let section = tag_content(prompt, "participants").unwrap_or_default();
+                let states: Vec<_> = section
+                    .split("<participant name=\"")
+                    .skip(1)
+                    .filter_map(|rest| rest.split_once('"').map(|(name, _)| name.to_string()))
+                    .map(|name| {
+                        let frustration = pick(&format!("{}-{}", prompt, name), options, 5) as f32 / 10.0;
+                        json!({ "name": name, "agreement": 0.5, "frustration": frustration, "note": "（モック）様子見" })
+                    })
+                    .collect();
+                json!({ "states": states }).to_string()
+            }
+            "participant_memory_update" => {
+                format!("（モック記憶）「{}」の議論では、具体例と根拠を重視する立場をとった。", topic)
+            }
+            "topic_drift_check" => {
+                json!({ "drift": pick(prompt, options, 4) as f32 / 10.0, "tangent": "" }).to_string()
+            }
+            "action_item_extraction" => {
+                let owner = tag_content(prompt, "participants")
+                    .and_then(|p| p.split(", ").next().map(str::to_string))
+                    .unwrap_or_default();
+                json!({
+                    "items": [
+                        { "description": format!("{}に関する具体例を集める", topic), "owner": owner, "due_hint": "次回まで" },
+                        { "description": "短期と長期の影響を表に整理する", "owner": "", "due_hint": "" }
+                    ]
+                })
+                .to_string()
+            }
+            "session_question_answering" => {
+                let excerpts = tag_content(prompt, "excerpts").unwrap_or_default();
+                let references: Vec<usize> = excerpts
+                    .lines()
+                    .filter_map(|line| line.strip_prefix("[#")?.split_once(']')?.0.parse().ok())
+                    .take(2)
+                    .collect();
+                let cited = references.iter().map(|n| format!("[#{}]", n)).collect::<Vec<_>>().join("");
+                json!({ "answer": format!("（モック回答）議論では具体例で考える必要があるとされていました{}。", cited), "references": references })
+                    .to_string()
+            }
+            "session_tagging" => json!({ "tags": ["モック", "議論", "デモ"] }).to_string(),
+            _ => "（モック応答）これはオフラインのデモ用の応答です。".to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl LlmBackend for MockBackend {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    async fn is_available(&self) -> bool {
+        true
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>, String> {
+        Ok(MOCK_MODELS.iter().map(|m| m.to_string()).collect())
+    }
+
+    async fn generate(
+        &self,
+        model: &str,
+        prompt: &str,
+        options: &GenerationOptions,
+    ) -> Result<GenerationResult, GenError> {
+        debug!("モック生成 (model={})", model);
+        let reply = self.respond(prompt, options);
+        // ストリーミングと同程度の待ち時間を再現する
+        let chunks = reply.chars().count().div_ceil(CHUNK_CHARS) as u32;
+        tokio::time::sleep(FIRST_TOKEN_DELAY + CHUNK_DELAY * chunks).await;
+        Ok(GenerationResult::text_only(model, reply))
+    }
+
+    async fn generate_stream(
+        &self,
+        model: &str,
+        prompt: &str,
+        options: &GenerationOptions,
+        on_chunk: ChunkSink<'_>,
+    ) -> Result<GenerationResult, GenError> {
+        debug!("モックストリーミング生成 (model={})", model);
+        let reply = self.respond(prompt, options);
+        tokio::time::sleep(FIRST_TOKEN_DELAY).await;
+        let chars: Vec<char> = reply.chars().collect();
+        for piece in chars.chunks(CHUNK_CHARS) {
+            on_chunk(&piece.iter().collect::<String>());
+            tokio::time::sleep(CHUNK_DELAY).await;
+        }
+        Ok(GenerationResult::text_only(model, reply))
+    }
+}
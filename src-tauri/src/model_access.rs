@@ -0,0 +1,58 @@
+// 使用を許可するモデル
+// 既定は gemma3:1b / gemma3:4b のみ。設定で接頭辞の一覧を変えるか、ローカルの全モデルを許可できる
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Manager};
+use tracing::info;
+
+use crate::{app_state::AppState, config};
+
+const DEFAULT_ALLOWED_PREFIXES: [&str; 2] = ["gemma3:1b", "gemma3:4b"];
+
+/// モデルの許可設定（アプリ設定に保存）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ModelAccess {
+    /// 許可するモデル名の接頭辞（例: "qwen2.5", "llama3.2:3b"）
+    pub allowed_prefixes: Vec<String>,
+    /// true ならローカルにある全モデルを許可
+    pub allow_any: bool,
+}
+
+impl Default for ModelAccess {
+    fn default() -> Self {
+        Self { allowed_prefixes: DEFAULT_ALLOWED_PREFIXES.iter().map(|p| p.to_string()).collect(), allow_any: false }
+    }
+}
+
+impl ModelAccess {
+    /// 空白・重複を除去（一覧が空になったら既定に戻す）
+    pub fn sanitized(mut self) -> Self {
+        let mut prefixes: Vec<String> =
+            self.allowed_prefixes.iter().map(|p| p.trim().to_string()).filter(|p| !p.is_empty()).collect();
+        prefixes.dedup();
+        self.allowed_prefixes = if prefixes.is_empty() { ModelAccess::default().allowed_prefixes } else { prefixes };
+        self
+    }
+
+    fn allows(&self, model: &str) -> bool {
+        if model.trim().is_empty() {
+            return false;
+        }
+        self.allow_any || self.allowed_prefixes.iter().any(|p| model.starts_with(p.as_str()))
+    }
+}
+
+/// モデルが許可されているか
+pub fn is_allowed(app: &AppHandle, model: &str) -> bool {
+    app.state::<AppState>().settings.models.read().allows(model)
+}
+
+// 許可するモデルの接頭辞と「全モデル許可」を保存
+#[command]
+pub async fn set_allowed_models(app: AppHandle, prefixes: Vec<String>, allow_any: bool) -> Result<ModelAccess, String> {
+    info!("set_allowed_models 呼び出し: {:?}, allow_any={}", prefixes, allow_any);
+    let mut settings = config::load(&app).await?;
+    settings.models = ModelAccess { allowed_prefixes: prefixes, allow_any };
+    let saved = config::save(&app, settings).await?;
+    Ok(saved.models)
+}
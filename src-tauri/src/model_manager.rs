@@ -0,0 +1,227 @@
+// Ollama モデルの管理（取得・削除・詳細表示・事前読み込み）
+// ターミナルで `ollama pull` しなくても、アプリから初回利用に必要なモデルを入れられるようにする
+use std::time::Duration;
+
+use reqwest::{Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{command, AppHandle, Emitter};
+use tracing::{info, warn};
+
+use crate::{audit, backend, config, is_allowed_model, system_info, ERR_UNSUPPORTED_MODEL};
+
+pub const EVENT_PULL_PROGRESS: &str = "model://pull-progress";
+
+// 削除・詳細取得のタイムアウト（取得はダウンロード時間が読めないため接続確立のみ制限）
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// model://pull-progress のペイロード
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PullProgressEvent {
+    model: String,
+    /// Ollama の状態文字列（"pulling manifest", "downloading ...", "success" など）
+    status: String,
+    digest: Option<String>,
+    completed: Option<u64>,
+    total: Option<u64>,
+    /// completed / total が分かる場合の進捗率（0〜100）
+    percent: Option<f64>,
+}
+
+/// /api/pull のストリーム1行
+#[derive(Debug, Deserialize)]
+struct PullLine {
+    #[serde(default)]
+    status: String,
+    digest: Option<String>,
+    completed: Option<u64>,
+    total: Option<u64>,
+    error: Option<String>,
+}
+
+/// モデルの詳細（/api/show の主要項目）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelDetails {
+    #[serde(default)]
+    pub format: String,
+    #[serde(default)]
+    pub family: String,
+    #[serde(default, alias = "parameter_size")]
+    pub parameter_size: String,
+    #[serde(default, alias = "quantization_level")]
+    pub quantization_level: String,
+}
+
+/// show_model_info の戻り値
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelInfo {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub details: ModelDetails,
+    /// Modelfile の PARAMETER 行（temperature など）
+    #[serde(default)]
+    pub parameters: String,
+    #[serde(default)]
+    pub template: String,
+    #[serde(default)]
+    pub license: String,
+    /// "completion", "vision" など（古い Ollama では空）
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    #[serde(default, alias = "modified_at")]
+    pub modified_at: String,
+}
+
+/// モデルの取得・削除・詳細は Ollama の API なので、Ollama 以外のバックエンドでは断る
+fn require_ollama(app: &AppHandle) -> Result<(), String> {
+    match backend::current(app).name() {
+        "ollama" | "record" => Ok(()),
+        other => Err(format!("モデルの管理は Ollama でのみ使えます（現在のバックエンド: {}）", other)),
+    }
+}
+
+fn validate_name(name: &str) -> Result<&str, String> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().any(char::is_whitespace) {
+        return Err(format!("モデル名が不正です: '{}'", name));
+    }
+    Ok(name)
+}
+
+// モデルを取得（ダウンロード）。進捗は model://pull-progress で通知する
+#[command]
+pub async fn pull_model(app: AppHandle, name: String) -> Result<(), String> {
+    require_ollama(&app)?;
+    let name = validate_name(&name)?.to_string();
+    info!("pull_model 呼び出し: {}", name);
+    audit::record("model", "pull", json!({ "model": name }));
+    let mut res = backend::client(&app)
+        .streaming(Method::POST, "/api/pull")
+        .json(&json!({ "model": name, "stream": true }))
+        .send()
+        .await
+        .map_err(|e| format!("モデル取得リクエスト失敗: {}", e))?;
+    if !res.status().is_success() {
+        return Err(format!("モデル取得失敗: HTTP {}", res.status()));
+    }
+
+    // 応答は1行1JSONの NDJSON。行の途中で区切られることがあるのでバッファする
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut succeeded = false;
+    while let Some(bytes) = res.chunk().await.map_err(|e| format!("ストリーム受信失敗: {}", e))? {
+        buffer.extend_from_slice(&bytes);
+        while let Some(pos) = buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=pos).collect();
+            let Ok(line) = serde_json::from_slice::<PullLine>(&line) else { continue };
+            if let Some(err) = line.error {
+                return Err(format!("モデル取得失敗: {}", err));
+            }
+            succeeded |= line.status == "success";
+            let percent = match (line.completed, line.total) {
+                (Some(c), Some(t)) if t > 0 => Some((c as f64 / t as f64 * 100.0).min(100.0)),
+                _ => None,
+            };
+            let _ = app.emit(
+                EVENT_PULL_PROGRESS,
+                PullProgressEvent {
+                    model: name.clone(),
+                    status: line.status,
+                    digest: line.digest,
+                    completed: line.completed,
+                    total: line.total,
+                    percent,
+                },
+            );
+        }
+    }
+    if !succeeded {
+        return Err("モデル取得が完了しませんでした".into());
+    }
+    info!("モデル取得完了: {}", name);
+    Ok(())
+}
+
+// ローカルのモデルを削除
+#[command]
+pub async fn delete_model(app: AppHandle, name: String) -> Result<(), String> {
+    require_ollama(&app)?;
+    let name = validate_name(&name)?;
+    info!("delete_model 呼び出し: {}", name);
+    let res = backend::client(&app)
+        .streaming(Method::DELETE, "/api/delete")
+        .timeout(REQUEST_TIMEOUT)
+        .json(&json!({ "model": name }))
+        .send()
+        .await
+        .map_err(|e| format!("モデル削除リクエスト失敗: {}", e))?;
+    match res.status() {
+        s if s.is_success() => {
+            audit::record("model", "delete", json!({ "model": name }));
+            Ok(())
+        }
+        StatusCode::NOT_FOUND => Err(format!("モデルが見つかりません: {}", name)),
+        s => Err(format!("モデル削除失敗: HTTP {}", s)),
+    }
+}
+
+// モデルの詳細（パラメータ数・量子化・テンプレートなど）
+#[command]
+pub async fn show_model_info(app: AppHandle, name: String) -> Result<ModelInfo, String> {
+    require_ollama(&app)?;
+    let name = validate_name(&name)?;
+    info!("show_model_info 呼び出し: {}", name);
+    let res = backend::client(&app)
+        .streaming(Method::POST, "/api/show")
+        .timeout(REQUEST_TIMEOUT)
+        .json(&json!({ "model": name }))
+        .send()
+        .await
+        .map_err(|e| format!("モデル情報リクエスト失敗: {}", e))?;
+    match res.status() {
+        s if s.is_success() => {
+            let mut info: ModelInfo = res.json().await.map_err(|e| format!("JSONパース失敗: {}", e))?;
+            if info.name.is_empty() {
+                info.name = name.to_string();
+            }
+            Ok(info)
+        }
+        StatusCode::NOT_FOUND => Err(format!("モデルが見つかりません: {}", name)),
+        s => Err(format!("モデル情報取得失敗: HTTP {}", s)),
+    }
+}
+
+// モデルを事前に読み込み、最初の発言がモデル読み込みで遅くならないようにする
+// keep_alive 未指定なら設定値（set_keep_alive）を使う
+#[command]
+pub async fn warm_up_model(app: AppHandle, model: String, keep_alive: Option<String>) -> Result<(), String> {
+    info!("warm_up_model 呼び出し: model={}, keep_alive={:?}", model, keep_alive);
+    if !is_allowed_model(&app, &model) {
+        return Err(ERR_UNSUPPORTED_MODEL.to_string());
+    }
+    if let Some(warning) = system_info::memory_warning(&model, system_info::memory().0) {
+        warn!("{}", warning);
+    }
+    let started = std::time::Instant::now();
+    backend::current(&app).warm_up(&model, keep_alive.as_deref()).await?;
+    info!("モデル読み込み完了: {} ({}ms)", model, started.elapsed().as_millis());
+    Ok(())
+}
+
+// 生成後にモデルをメモリに残す時間を保存（以降のすべての生成リクエストに keep_alive として付く）
+// 例: "30m", "1h", "-1"（無期限）。空文字で Ollama の既定に戻す
+#[command]
+pub async fn set_keep_alive(app: AppHandle, duration: String) -> Result<String, String> {
+    info!("set_keep_alive 呼び出し: '{}'", duration);
+    let duration = duration.trim().to_string();
+    if !duration.is_empty() && backend::keep_alive_value(&duration).is_none() {
+        return Err(format!("keep_alive の値が不正です: '{}'（例: 30m, 1h, -1）", duration));
+    }
+    let mut settings = config::load(&app).await?;
+    settings.ollama.keep_alive = duration;
+    let saved = config::save(&app, settings).await?;
+    Ok(saved.ollama.keep_alive)
+}
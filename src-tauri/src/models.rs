@@ -0,0 +1,422 @@
+// GGUF モデルの取得と管理（candle バックエンド用）
+// 動作確認済みの GGUF を Hugging Face から取得し、アプリデータ配下の models/ にモデルごとのフォルダで保存する
+// 取得は途中から再開でき（.part に追記）、Hugging Face が公開している SHA256 と照合してから配置する
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use reqwest::{header, Client, StatusCode};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tauri::{command, AppHandle, Emitter, Manager};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tracing::{info, warn};
+
+use crate::{config, permissions, requests};
+
+pub const EVENT_DOWNLOAD_PROGRESS: &str = "gguf://download-progress";
+
+const HF_BASE_URL: &str = "https://huggingface.co";
+const MODELS_DIR: &str = "models";
+const PART_SUFFIX: &str = ".part";
+const TOKENIZER_FILE: &str = "tokenizer.json";
+// 進捗イベントの最短間隔
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+// 接続確立までの待ち時間（本体のダウンロード時間は読めないので制限しない）
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 取得できる GGUF（動作を確認した Gemma 3 の量子化版）
+pub struct CuratedModel {
+    /// 保存先のフォルダ名・コマンドの引数に使うID
+    pub id: &'static str,
+    /// DewAI のモデル名（candle 設定の modelName になる）
+    pub model_name: &'static str,
+    pub repo: &'static str,
+    pub file: &'static str,
+    /// tokenizer.json を取るリポジトリ（GGUF のリポジトリには含まれないため）
+    pub tokenizer_repo: &'static str,
+    /// 目安のサイズ（バイト）
+    pub approx_size: u64,
+    pub description: &'static str,
+}
+
+const CURATED: &[CuratedModel] = &[
+    CuratedModel {
+        id: "gemma-3-1b-it-q4_k_m",
+        model_name: "gemma3:1b",
+        repo: "unsloth/gemma-3-1b-it-GGUF",
+        file: "gemma-3-1b-it-Q4_K_M.gguf",
+        tokenizer_repo: "unsloth/gemma-3-1b-it",
+        approx_size: 806_000_000,
+        description: "Gemma 3 1B（4bit 量子化）。メモリ 8GB 未満のマシン向け",
+    },
+    CuratedModel {
+        id: "gemma-3-4b-it-q4_k_m",
+        model_name: "gemma3:4b",
+        repo: "unsloth/gemma-3-4b-it-GGUF",
+        file: "gemma-3-4b-it-Q4_K_M.gguf",
+        tokenizer_repo: "unsloth/gemma-3-4b-it",
+        approx_size: 2_490_000_000,
+        description: "Gemma 3 4B（4bit 量子化）。メモリ 8GB 以上を推奨",
+    },
+];
+
+fn curated(id: &str) -> Result<&'static CuratedModel, String> {
+    CURATED.iter().find(|m| m.id == id).ok_or_else(|| format!("取得できないモデルです: {}", id))
+}
+
+/// GGUF の保存先（<app_data_dir>/models）
+fn models_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app.path().app_data_dir().map_err(|e| format!("アプリデータの場所を取得できません: {}", e))?;
+    Ok(dir.join(MODELS_DIR))
+}
+
+/// フォルダ名として安全なIDか（削除で models/ の外を指さないようにする）
+fn validate_id(id: &str) -> Result<&str, String> {
+    let valid = !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')) && id != "..";
+    valid.then_some(id).ok_or_else(|| format!("モデルIDが不正です: '{}'", id))
+}
+
+/// list_curated_gguf の要素
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CuratedModelInfo {
+    pub id: &'static str,
+    pub model_name: &'static str,
+    pub repo: &'static str,
+    pub file: &'static str,
+    pub approx_size: u64,
+    pub description: &'static str,
+    /// 取得済みか
+    pub installed: bool,
+}
+
+// 取得できる GGUF モデルの一覧
+#[command]
+pub fn list_curated_gguf(app: AppHandle) -> Result<Vec<CuratedModelInfo>, String> {
+    let dir = models_dir(&app)?;
+    Ok(CURATED
+        .iter()
+        .map(|m| CuratedModelInfo {
+            id: m.id,
+            model_name: m.model_name,
+            repo: m.repo,
+            file: m.file,
+            approx_size: m.approx_size,
+            description: m.description,
+            installed: dir.join(m.id).join(m.file).is_file(),
+        })
+        .collect())
+}
+
+/// gguf://download-progress のペイロード
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DownloadProgressEvent {
+    id: String,
+    file: String,
+    downloaded: u64,
+    total: Option<u64>,
+    /// total が分かる場合の進捗率（0〜100）
+    percent: Option<f64>,
+    /// 前回の続きから再開したか
+    resumed: bool,
+}
+
+fn http() -> Result<Client, String> {
+    Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .build()
+        .map_err(|e| format!("HTTPクライアント初期化失敗: {}", e))
+}
+
+fn resolve_url(repo: &str, file: &str) -> String {
+    format!("{}/{}/resolve/main/{}", HF_BASE_URL, repo, file)
+}
+
+/// Hugging Face が公開しているファイルの SHA256（LFS のファイルのみ。通常のファイルは None）
+async fn published_sha256(client: &Client, url: &str) -> Result<Option<String>, String> {
+    // リダイレクト先（CDN）ではなく Hugging Face 自身の応答ヘッダーを見る
+    let res = client
+        .head(url)
+        .header("Accept-Encoding", "identity")
+        .send()
+        .await
+        .map_err(|e| format!("ファイル情報の取得失敗: {}", e))?;
+    if res.status().is_client_error() {
+        return Err(format!("ファイル情報の取得失敗: HTTP {} ({})", res.status(), url));
+    }
+    let etag = res
+        .headers()
+        .get("x-linked-etag")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.trim_matches('"').to_ascii_lowercase());
+    Ok(etag.filter(|e| e.len() == 64 && e.chars().all(|c| c.is_ascii_hexdigit())))
+}
+
+/// 既存の .part を読んでハッシュを途中まで進める
+async fn hash_existing(path: &Path, hasher: &mut Sha256) -> Result<u64, String> {
+    let mut file = tokio::fs::File::open(path).await.map_err(|e| format!("途中のファイルを開けません: {}", e))?;
+    let mut buf = vec![0u8; 1 << 20];
+    let mut total = 0u64;
+    loop {
+        let n = file.read(&mut buf).await.map_err(|e| format!("途中のファイルの読み込み失敗: {}", e))?;
+        if n == 0 {
+            return Ok(total);
+        }
+        hasher.update(&buf[..n]);
+        total += n as u64;
+    }
+}
+
+/// 1ファイルを取得する（.part があれば続きから）。SHA256 が公開されていれば照合してから配置する
+async fn download_file(
+    app: &AppHandle,
+    client: &Client,
+    id: &str,
+    url: &str,
+    dest: &Path,
+) -> Result<(), String> {
+    let file_name = dest.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let expected = published_sha256(client, url).await?;
+    let part = dest.with_file_name(format!("{}{}", file_name, PART_SUFFIX));
+
+    let mut hasher = Sha256::new();
+    let mut downloaded = if part.is_file() { hash_existing(&part, &mut hasher).await? } else { 0 };
+    let mut request = client.get(url);
+    if downloaded > 0 {
+        request = request.header(header::RANGE, format!("bytes={}-", downloaded));
+    }
+    let mut res = request.send().await.map_err(|e| format!("ダウンロード失敗: {}", e))?;
+    let resumed = match res.status() {
+        StatusCode::PARTIAL_CONTENT => true,
+        // 範囲指定を受け付けなかった（または最初から）場合は先頭から取り直す
+        s if s.is_success() => {
+            downloaded = 0;
+            hasher = Sha256::new();
+            false
+        }
+        StatusCode::RANGE_NOT_SATISFIABLE => {
+            // 途中のファイルが既に全体を含んでいる
+            true
+        }
+        s => return Err(format!("ダウンロード失敗: HTTP {} ({})", s, url)),
+    };
+    if resumed {
+        info!("ダウンロードを再開: {} ({}バイトから)", file_name, downloaded);
+    }
+    let total = if res.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+        Some(downloaded)
+    } else {
+        res.content_length().map(|len| len + downloaded)
+    };
+
+    let mut out = tokio::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(resumed)
+        .truncate(!resumed)
+        .open(&part)
+        .await
+        .map_err(|e| format!("保存先を開けません ({}): {}", part.display(), e))?;
+    let emit = |downloaded: u64| {
+        let percent = total.filter(|t| *t > 0).map(|t| (downloaded as f64 / t as f64 * 100.0).min(100.0));
+        let payload = DownloadProgressEvent {
+            id: id.to_string(),
+            file: file_name.clone(),
+            downloaded,
+            total,
+            percent,
+            resumed,
+        };
+        let _ = app.emit(EVENT_DOWNLOAD_PROGRESS, payload);
+    };
+    emit(downloaded);
+    let mut last_emit = Instant::now();
+    if res.status() != StatusCode::RANGE_NOT_SATISFIABLE {
+        while let Some(bytes) = res.chunk().await.map_err(|e| format!("ダウンロード中断: {}", e))? {
+            out.write_all(&bytes).await.map_err(|e| format!("書き込み失敗: {}", e))?;
+            hasher.update(&bytes);
+            downloaded += bytes.len() as u64;
+            if last_emit.elapsed() >= PROGRESS_INTERVAL {
+                emit(downloaded);
+                last_emit = Instant::now();
+            }
+        }
+    }
+    out.flush().await.map_err(|e| format!("書き込み失敗: {}", e))?;
+    drop(out);
+    emit(downloaded);
+
+    let actual = format!("{:x}", hasher.finalize());
+    match expected {
+        Some(expected) if expected != actual => {
+            // 壊れたファイルから再開し続けないよう破棄する
+            let _ = tokio::fs::remove_file(&part).await;
+            return Err(format!("SHA256 が一致しません（{}）。もう一度取得してください", file_name));
+        }
+        Some(_) => info!("SHA256 照合済み: {}", file_name),
+        None => warn!("SHA256 が公開されていないため照合を省略: {}", file_name),
+    }
+    tokio::fs::rename(&part, dest).await.map_err(|e| format!("ファイルの配置に失敗: {}", e))
+}
+
+/// download_gguf の戻り値
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadedModel {
+    pub id: String,
+    pub model_name: String,
+    pub model_path: String,
+    pub tokenizer_path: String,
+}
+
+// GGUF モデルと tokenizer.json を Hugging Face から取得する。進捗は gguf://download-progress で通知する
+// 中断しても次回は続きから取得する。request_id を cancel_request に渡すと中断できる
+#[command]
+pub async fn download_gguf(app: AppHandle, id: String, request_id: Option<String>) -> Result<DownloadedModel, String> {
+    let model = curated(&id)?;
+    permissions::authorize(&app, permissions::TOOL_GGUF_DOWNLOAD).await?;
+    let dir = models_dir(&app)?.join(model.id);
+    std::fs::create_dir_all(&dir).map_err(|e| format!("保存先を作成できません ({}): {}", dir.display(), e))?;
+    info!("GGUF 取得開始: {} ({})", model.id, model.repo);
+
+    let task = async {
+        let client = http()?;
+        let model_path = dir.join(model.file);
+        let tokenizer_path = dir.join(TOKENIZER_FILE);
+        if !tokenizer_path.is_file() {
+            download_file(&app, &client, model.id, &resolve_url(model.tokenizer_repo, TOKENIZER_FILE), &tokenizer_path)
+                .await?;
+        }
+        if !model_path.is_file() {
+            download_file(&app, &client, model.id, &resolve_url(model.repo, model.file), &model_path).await?;
+        }
+        Ok(DownloadedModel {
+            id: model.id.to_string(),
+            model_name: model.model_name.to_string(),
+            model_path: model_path.display().to_string(),
+            tokenizer_path: tokenizer_path.display().to_string(),
+        })
+    };
+    let result = requests::run_cancellable(&app, request_id.as_deref(), task).await;
+    if result.is_ok() {
+        info!("GGUF 取得完了: {}", model.id);
+    }
+    result
+}
+
+/// list_local_gguf の要素
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalGguf {
+    /// フォルダ名（delete_local_gguf に渡す）
+    pub id: String,
+    /// 取得元が一覧にあるモデルなら DewAI のモデル名
+    pub model_name: Option<String>,
+    pub model_path: String,
+    pub size: u64,
+    pub has_tokenizer: bool,
+    /// 途中まで取得したファイルがあるか（download_gguf で再開できる）
+    pub partial: bool,
+    /// candle の設定で使用中か
+    pub active: bool,
+}
+
+fn dir_size(path: &Path) -> u64 {
+    std::fs::read_dir(path)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| match e.metadata() {
+                    Ok(m) if m.is_dir() => dir_size(&e.path()),
+                    Ok(m) => m.len(),
+                    Err(_) => 0,
+                })
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+// 取得済みの GGUF モデル（途中のものを含む）
+#[command]
+pub async fn list_local_gguf(app: AppHandle) -> Result<Vec<LocalGguf>, String> {
+    let dir = models_dir(&app)?;
+    let active = config::load(&app).await?.candle.model_path;
+    let Ok(entries) = std::fs::read_dir(&dir) else { return Ok(Vec::new()) };
+    let mut models: Vec<LocalGguf> = entries
+        .flatten()
+        .filter(|e| e.path().is_dir())
+        .map(|e| {
+            let path = e.path();
+            let id = e.file_name().to_string_lossy().to_string();
+            let files: Vec<PathBuf> = std::fs::read_dir(&path).map(|f| f.flatten().map(|f| f.path()).collect()).unwrap_or_default();
+            let gguf = files.iter().find(|f| f.extension().is_some_and(|x| x == "gguf"));
+            let partial = files.iter().any(|f| f.to_string_lossy().ends_with(PART_SUFFIX));
+            let model_path = gguf.map(|g| g.display().to_string()).unwrap_or_default();
+            LocalGguf {
+                model_name: CURATED.iter().find(|m| m.id == id).map(|m| m.model_name.to_string()),
+                id,
+                active: !model_path.is_empty() && model_path == active,
+                model_path,
+                size: dir_size(&path),
+                has_tokenizer: path.join(TOKENIZER_FILE).is_file(),
+                partial,
+            }
+        })
+        .collect();
+    models.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(models)
+}
+
+// 取得済みの GGUF モデルをフォルダごと削除する（candle で使用中のものは削除しない）
+#[command]
+pub async fn delete_local_gguf(app: AppHandle, id: String) -> Result<(), String> {
+    let id = validate_id(&id)?;
+    let path = models_dir(&app)?.join(id);
+    if !path.is_dir() {
+        return Err(format!("モデルが見つかりません: {}", id));
+    }
+    let active = config::load(&app).await?.candle.model_path;
+    if !active.is_empty() && Path::new(&active).starts_with(&path) {
+        return Err("candle で使用中のモデルは削除できません。先に別のモデルを選んでください".into());
+    }
+    std::fs::remove_dir_all(&path).map_err(|e| format!("削除に失敗しました ({}): {}", path.display(), e))?;
+    info!("GGUF を削除: {}", id);
+    Ok(())
+}
+
+// 取得済みの GGUF モデルを candle で使うモデルにする
+#[command]
+pub async fn use_local_gguf(app: AppHandle, id: String) -> Result<config::AppSettings, String> {
+    let id = validate_id(&id)?;
+    let local = list_local_gguf(app.clone()).await?;
+    let model = local
+        .into_iter()
+        .find(|m| m.id == id && !m.model_path.is_empty() && m.has_tokenizer)
+        .ok_or_else(|| format!("取得済みのモデルが見つかりません: {}", id))?;
+    let mut settings = config::load(&app).await?;
+    settings.candle.model_path = model.model_path;
+    settings.candle.tokenizer_path = String::new();
+    if let Some(name) = model.model_name {
+        settings.candle.model_name = name;
+    }
+    config::save(&app, settings).await
+}
+
+/// get_models_dir_usage の戻り値
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelsDirUsage {
+    pub path: String,
+    /// 取得済み・途中のファイルの合計（バイト）
+    pub used_bytes: u64,
+    pub model_count: usize,
+}
+
+// GGUF の保存先と使用量
+#[command]
+pub fn get_models_dir_usage(app: AppHandle) -> Result<ModelsDirUsage, String> {
+    let dir = models_dir(&app)?;
+    let model_count = std::fs::read_dir(&dir).map(|e| e.flatten().filter(|e| e.path().is_dir()).count()).unwrap_or(0);
+    Ok(ModelsDirUsage { path: dir.display().to_string(), used_bytes: dir_size(&dir), model_count })
+}
@@ -0,0 +1,232 @@
+// 次の話者の推薦
+// 発言履歴と最新の分析から「まだ発言していない」「直前に名指しされた」「未解決の対立の当事者」などの手がかりを集めて候補を順位付けし、
+// モデルにはその手がかりを添えて1人を選ばせる（生成失敗・候補外の回答のときは手がかりの順位で決める）
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{command, AppHandle};
+use tracing::{info, warn};
+
+use crate::{
+    analysis,
+    analysis::DiscussionAnalysis,
+    call_ollama_generate_with, db,
+    db::{AiParticipant, SessionRecord, StoredMessage},
+    generation::{GenerationOptions, OutputFormat},
+    is_allowed_model, llm_json, prompts,
+    prompts::SpeakerCandidate,
+    session_settings, ERR_UNSUPPORTED_MODEL,
+};
+
+// 手がかりごとの加点
+const SCORE_NEVER_SPOKE: f32 = 3.0;
+const SCORE_ADDRESSED: f32 = 4.0;
+const SCORE_IN_CONFLICT: f32 = 2.0;
+const SCORE_NO_STANCE: f32 = 1.0;
+// 最後の発言から1発言経つごとの加点と、その上限の発言数
+const SCORE_PER_WAITING_MESSAGE: f32 = 0.5;
+const MAX_WAITING_MESSAGES: usize = 5;
+// 直前の発言者（連続して発言させない）
+const SCORE_LAST_SPEAKER: f32 = -10.0;
+// 推薦の温度（候補の選択なので低め）
+const SUGGESTION_TEMPERATURE: f32 = 0.2;
+
+/// 候補1人の順位付け
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CandidateScore {
+    pub name: String,
+    pub score: f32,
+    /// 加点・減点の理由
+    pub notes: Vec<String>,
+}
+
+/// 推薦の根拠
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SuggestionSource {
+    /// モデルが選んだ
+    Model,
+    /// 手がかりの順位で選んだ
+    Heuristic,
+}
+
+/// suggest_next_speaker の戻り値
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpeakerSuggestion {
+    pub speaker: String,
+    pub reason: String,
+    pub source: SuggestionSource,
+    /// 手がかりによる順位（高い順）
+    pub candidates: Vec<CandidateScore>,
+}
+
+/// モデルの回答（{ "speaker": ..., "reason": ... }）
+#[derive(Debug, Deserialize)]
+struct RawSuggestion {
+    #[serde(default)]
+    speaker: String,
+    #[serde(default)]
+    reason: String,
+}
+
+/// 発言履歴と分析から候補を順位付けする（同点なら参加者の並び順）
+pub fn rank_candidates(
+    participants: &[AiParticipant],
+    messages: &[StoredMessage],
+    analysis: Option<&DiscussionAnalysis>,
+) -> Vec<CandidateScore> {
+    let last = messages.last();
+    let mut ranked: Vec<CandidateScore> = participants
+        .iter()
+        .map(|p| {
+            let mut score = 0.0;
+            let mut notes = Vec::new();
+            match messages.iter().rposition(|m| m.speaker == p.name) {
+                None => {
+                    score += SCORE_NEVER_SPOKE;
+                    notes.push("まだ発言していない".to_string());
+                }
+                Some(index) => {
+                    let waiting = messages.len() - 1 - index;
+                    score += waiting.min(MAX_WAITING_MESSAGES) as f32 * SCORE_PER_WAITING_MESSAGE;
+                    if waiting > 0 {
+                        notes.push(format!("{}発言前に発言", waiting));
+                    }
+                }
+            }
+            if let Some(last) = last {
+                if last.speaker == p.name {
+                    score += SCORE_LAST_SPEAKER;
+                    notes.push("直前の発言者".to_string());
+                } else if last.message.contains(&p.name) {
+                    score += SCORE_ADDRESSED;
+                    notes.push("直前の発言で名指しされた".to_string());
+                }
+            }
+            if let Some(analysis) = analysis {
+                let conflict = analysis.conflicts.iter().find(|c| {
+                    c.sides.iter().any(|s| s.contains(&p.name)) || c.issue.contains(&p.name) || c.description.contains(&p.name)
+                });
+                if let Some(conflict) = conflict {
+                    score += SCORE_IN_CONFLICT;
+                    notes.push(format!("未解決の対立の当事者: {}", conflict.issue));
+                }
+                if !analysis.participant_stances.iter().any(|s| s.participant == p.name) {
+                    score += SCORE_NO_STANCE;
+                    notes.push("立場がまだはっきりしない".to_string());
+                }
+            }
+            CandidateScore { name: p.name.clone(), score, notes }
+        })
+        .collect();
+    // sort_by は安定ソートなので同点は参加者の並び順のまま
+    ranked.sort_by(|a, b| b.score.total_cmp(&a.score));
+    ranked
+}
+
+fn heuristic_reason(top: &CandidateScore) -> String {
+    if top.notes.is_empty() {
+        "発言の順番".to_string()
+    } else {
+        top.notes.join("・")
+    }
+}
+
+/// 自動進行用: 手がかりの順位で次の話者を選ぶ（モデルは呼ばない）
+pub async fn pick(
+    app: &AppHandle,
+    session_id: i64,
+    session: &SessionRecord,
+    candidates: &[AiParticipant],
+) -> Result<Option<AiParticipant>, String> {
+    let latest = analysis::latest_snapshot(app, session_id).await?;
+    let ranked = rank_candidates(candidates, &session.messages, latest.as_ref().map(|(_, a)| a));
+    let Some(top) = ranked.first() else { return Ok(None) };
+    info!("次の話者: {}（{}）", top.name, heuristic_reason(top));
+    Ok(candidates.iter().find(|p| p.name == top.name).cloned())
+}
+
+fn output_format(names: &[&str]) -> OutputFormat {
+    OutputFormat::Schema(json!({
+        "type": "object",
+        "properties": {
+            "speaker": { "type": "string", "enum": names },
+            "reason": { "type": "string" }
+        },
+        "required": ["speaker", "reason"]
+    }))
+}
+
+// 次に発言させるAI参加者を推薦する（model 未指定ならセッションのモデル）
+#[command]
+pub async fn suggest_next_speaker(
+    app: AppHandle,
+    session_id: i64,
+    model: Option<String>,
+) -> Result<SpeakerSuggestion, String> {
+    let session = db::load_session(&app, session_id).await?;
+    let participants = &session.participants.ai_data;
+    if participants.is_empty() {
+        return Err("AI参加者がいません".into());
+    }
+    let model = model.unwrap_or_else(|| session.model.clone());
+    if !is_allowed_model(&app, &model) {
+        return Err(ERR_UNSUPPORTED_MODEL.to_string());
+    }
+    let latest = analysis::latest_snapshot(&app, session_id).await?;
+    let analysis = latest.as_ref().map(|(_, a)| a);
+    let ranked = rank_candidates(participants, &session.messages, analysis);
+    let heuristic = |ranked: Vec<CandidateScore>| SpeakerSuggestion {
+        speaker: ranked[0].name.clone(),
+        reason: heuristic_reason(&ranked[0]),
+        source: SuggestionSource::Heuristic,
+        candidates: ranked,
+    };
+    if participants.len() == 1 {
+        return Ok(heuristic(ranked));
+    }
+
+    let candidates: Vec<SpeakerCandidate> = ranked
+        .iter()
+        .filter_map(|c| {
+            let p = participants.iter().find(|p| p.name == c.name)?;
+            Some(SpeakerCandidate { name: &p.name, role: &p.role, notes: c.notes.clone() })
+        })
+        .collect();
+    let conflicts: Vec<String> = analysis
+        .map(|a| a.conflicts.iter().map(|c| format!("{}（{}）", c.issue, c.sides.join(" / "))).collect())
+        .unwrap_or_default();
+    let language = session_settings::load(&app, session_id).await?.language();
+    let prompt =
+        prompts::build_next_speaker_prompt(&session.topic, &candidates, &session.history_text(), &conflicts, language);
+    let names: Vec<&str> = participants.iter().map(|p| p.name.as_str()).collect();
+    let options = GenerationOptions { temperature: Some(SUGGESTION_TEMPERATURE), ..Default::default() }
+        .with_format(output_format(&names));
+
+    let answer = match call_ollama_generate_with(&app, &model, &prompt, &options).await {
+        Ok(raw) => llm_json::parse_llm_json::<RawSuggestion>(&raw),
+        Err(e) => Err(e.to_string()),
+    };
+    match answer {
+        Ok(raw) => {
+            let speaker = raw.speaker.trim();
+            match participants.iter().find(|p| p.name == speaker) {
+                Some(p) => Ok(SpeakerSuggestion {
+                    speaker: p.name.clone(),
+                    reason: raw.reason.trim().to_string(),
+                    source: SuggestionSource::Model,
+                    candidates: ranked,
+                }),
+                None => {
+                    warn!("推薦された話者が参加者にいません（手がかりの順位を使用）: {}", speaker);
+                    Ok(heuristic(ranked))
+                }
+            }
+        }
+        Err(e) => {
+            warn!("次の話者の推薦に失敗（手がかりの順位を使用）: {}", e);
+            Ok(heuristic(ranked))
+        }
+    }
+}
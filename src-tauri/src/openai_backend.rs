@@ -0,0 +1,340 @@
+// OpenAI 互換 API のバックエンド（llama.cpp server・LM Studio など）
+// /v1/models と /v1/chat/completions を話すサーバーを、Ollama と同じコマンドから使えるようにする
+// プロンプトは user メッセージ1件として送り、チャットテンプレートはサーバー側で適用させる（Ollama の /api/generate と同じ扱い）
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use reqwest::{Client, RequestBuilder};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::command;
+use tracing::{info, warn};
+
+use crate::{
+    app_state::RuntimeSettings,
+    backend::{ChunkSink, LlmBackend, HEALTH_CHECK_TIMEOUT},
+    circuit_breaker::CircuitBreaker,
+    gen_error::GenError,
+    generation::{GenerationMeta, GenerationOptions, GenerationResult, OutputFormat},
+    timeouts,
+};
+
+/// OpenAI 互換サーバーへの接続設定（アプリ設定に保存）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct OpenAiCompatConfig {
+    /// "/v1" までのベースURL（LM Studio は http://localhost:1234/v1、llama.cpp server は http://localhost:8080/v1）
+    pub base_url: String,
+    /// Authorization: Bearer に付けるキー（ローカルのサーバーでは通常不要）
+    pub api_key: String,
+    /// DewAI のモデル名 → サーバーのモデルID（例: "gemma3:4b" → "google/gemma-3-4b"）
+    /// 対応付けたモデルは DewAI の名前で一覧に出るため、許可するモデルの設定を変えずに使える
+    pub model_aliases: BTreeMap<String, String>,
+}
+
+impl Default for OpenAiCompatConfig {
+    fn default() -> Self {
+        Self { base_url: "http://localhost:1234/v1".into(), api_key: String::new(), model_aliases: BTreeMap::new() }
+    }
+}
+
+impl OpenAiCompatConfig {
+    /// 範囲外の値を補正
+    pub fn sanitized(mut self) -> Self {
+        self.base_url = self.base_url.trim().trim_end_matches('/').to_string();
+        if self.base_url.is_empty() {
+            self.base_url = OpenAiCompatConfig::default().base_url;
+        }
+        self.api_key = self.api_key.trim().to_string();
+        self.model_aliases = self
+            .model_aliases
+            .into_iter()
+            .map(|(name, id)| (name.trim().to_string(), id.trim().to_string()))
+            .filter(|(name, id)| !name.is_empty() && !id.is_empty())
+            .collect();
+        self
+    }
+
+    /// サーバーに送るモデルID
+    fn server_model<'a>(&'a self, model: &'a str) -> &'a str {
+        self.model_aliases.get(model).map(String::as_str).unwrap_or(model)
+    }
+
+    /// サーバーのモデルIDを一覧に出す名前に戻す
+    fn display_name(&self, id: &str) -> String {
+        self.model_aliases
+            .iter()
+            .find(|(_, v)| v.as_str() == id)
+            .map(|(k, _)| k.clone())
+            .unwrap_or_else(|| id.to_string())
+    }
+}
+
+/// 接続設定を反映（起動時と設定保存時）
+pub fn set_config(settings: &RuntimeSettings, config: &OpenAiCompatConfig) {
+    if *settings.openai.read() != *config {
+        info!("OpenAI 互換サーバー: {}", config.base_url);
+        settings.openai.set(config.clone());
+    }
+}
+
+fn request_to(http: &Client, config: &OpenAiCompatConfig, method: reqwest::Method, path: &str) -> RequestBuilder {
+    let builder = http.request(method, format!("{}{}", config.base_url, path));
+    if config.api_key.is_empty() {
+        builder
+    } else {
+        builder.bearer_auth(&config.api_key)
+    }
+}
+
+/// /v1/models のモデルID一覧
+async fn fetch_model_ids(
+    http: &Client,
+    config: &OpenAiCompatConfig,
+    timeout: Duration,
+) -> Result<Vec<String>, String> {
+    let res = request_to(http, config, reqwest::Method::GET, "/models")
+        .timeout(timeout)
+        .send()
+        .await
+        .map_err(|e| format!("モデル一覧取得失敗: {}", e))?;
+    if !res.status().is_success() {
+        return Err(format!("モデル一覧取得失敗: HTTP {}", res.status()));
+    }
+    let json: serde_json::Value = res.json().await.map_err(|e| format!("JSONパース失敗: {}", e))?;
+    Ok(json["data"]
+        .as_array()
+        .map(|models| models.iter().filter_map(|m| m["id"].as_str()).map(|s| s.to_string()).collect())
+        .unwrap_or_default())
+}
+
+/// /v1/chat/completions のリクエスト本体
+fn request_body(
+    config: &OpenAiCompatConfig,
+    model: &str,
+    prompt: &str,
+    stream: bool,
+    options: &GenerationOptions,
+) -> serde_json::Value {
+    let mut body = json!({
+        "model": config.server_model(model),
+        "messages": [{ "role": "user", "content": prompt }],
+        "stream": stream,
+    });
+    let mut put = |key: &str, value: Option<serde_json::Value>| {
+        if let Some(v) = value {
+            body[key] = v;
+        }
+    };
+    put("temperature", options.temperature.map(Into::into));
+    put("top_p", options.top_p.map(Into::into));
+    // top_k・repeat_penalty は OpenAI の仕様にないが、llama.cpp server と LM Studio は受け付ける
+    put("top_k", options.top_k.map(Into::into));
+    put("repeat_penalty", options.repeat_penalty.map(Into::into));
+    put("max_tokens", options.num_predict.filter(|n| *n > 0).map(Into::into));
+    put("seed", options.seed.map(Into::into));
+    put(
+        "response_format",
+        options.format.as_ref().map(|format| match format {
+            OutputFormat::Json => json!({ "type": "json_object" }),
+            OutputFormat::Schema(schema) => {
+                json!({ "type": "json_schema", "json_schema": { "name": "response", "schema": schema } })
+            }
+        }),
+    );
+    if stream {
+        // 最後のチャンクにトークン数を付けさせる（対応していないサーバーは無視する）
+        put("stream_options", Some(json!({ "include_usage": true })));
+    }
+    body
+}
+
+/// 応答の usage からメタデータを作る
+fn meta(model: &str, usage: &serde_json::Value, elapsed: Duration) -> GenerationMeta {
+    let count = |key: &str| usage[key].as_u64().map(|n| n.min(u32::MAX as u64) as u32);
+    GenerationMeta {
+        model: model.to_string(),
+        prompt_tokens: count("prompt_tokens"),
+        completion_tokens: count("completion_tokens"),
+        duration_ms: Some(elapsed.as_millis() as u64),
+        ..Default::default()
+    }
+}
+
+/// OpenAI 互換 API を話すローカルサーバー
+pub struct OpenAiCompatBackend {
+    breaker: Arc<CircuitBreaker>,
+    settings: Arc<RuntimeSettings>,
+    /// 接続を使い回すためのクライアント（タイムアウトは Ollama の接続設定に合わせて呼び出しごとに付ける）
+    http: Client,
+}
+
+impl OpenAiCompatBackend {
+    pub fn new(breaker: Arc<CircuitBreaker>, settings: Arc<RuntimeSettings>) -> Self {
+        Self { breaker, settings, http: Client::new() }
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> RequestBuilder {
+        request_to(&self.http, &self.settings.openai.read(), method, path)
+    }
+
+    fn request_timeout(&self) -> Duration {
+        Duration::from_secs(self.settings.client.read().connection().request_timeout_secs)
+    }
+
+    /// 一括生成を1回送る（本文と usage を返す）
+    async fn complete(&self, body: &serde_json::Value) -> Result<(String, serde_json::Value), String> {
+        let res = self
+            .request(reqwest::Method::POST, "/chat/completions")
+            .timeout(self.request_timeout())
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| format!("リクエスト失敗: {}", e))?;
+        let status = res.status();
+        let json: serde_json::Value = res.json().await.map_err(|e| format!("JSONパース失敗: {}", e))?;
+        if !status.is_success() {
+            let message = json["error"]["message"].as_str().unwrap_or_default();
+            return Err(format!("生成失敗: HTTP {} {}", status, message));
+        }
+        let text = json["choices"][0]["message"]["content"]
+            .as_str()
+            .ok_or_else(|| format!("応答フィールドなし: {:?}", json))?
+            .to_string();
+        Ok((text, json["usage"].clone()))
+    }
+}
+
+#[async_trait]
+impl LlmBackend for OpenAiCompatBackend {
+    fn name(&self) -> &'static str {
+        "openai"
+    }
+
+    async fn is_available(&self) -> bool {
+        match self.request(reqwest::Method::GET, "/models").timeout(HEALTH_CHECK_TIMEOUT).send().await {
+            Ok(res) => res.status().is_success(),
+            Err(e) => {
+                warn!("OpenAI 互換サーバーからの応答なし: {}", e);
+                false
+            }
+        }
+    }
+
+    async fn list_models(&self) -> Result<Vec<String>, String> {
+        let config = self.settings.openai.get();
+        let ids = fetch_model_ids(&self.http, &config, self.request_timeout()).await?;
+        Ok(ids.iter().map(|id| config.display_name(id)).collect())
+    }
+
+    // 失敗時は Ollama と同じ再試行方針（接続設定の max_retries）で再送する
+    async fn generate(
+        &self,
+        model: &str,
+        prompt: &str,
+        options: &GenerationOptions,
+    ) -> Result<GenerationResult, GenError> {
+        let retry = self.settings.client.read().retry_policy();
+        let body = request_body(&self.settings.openai.read(), model, prompt, false, options);
+        let mut attempt: u8 = 1;
+        loop {
+            self.breaker.check()?;
+            let started = std::time::Instant::now();
+            let result = self.complete(&body).await;
+            match &result {
+                Ok(_) => self.breaker.record_success(),
+                Err(e) => self.breaker.record_failure(e),
+            }
+            match result {
+                Ok((text, usage)) => {
+                    let mut meta = meta(model, &usage, started.elapsed());
+                    meta.retries = Some((attempt - 1) as u32);
+                    return Ok(GenerationResult { text, seed: None, meta });
+                }
+                Err(e) if attempt >= retry.max_attempts => return Err(e.into()),
+                Err(e) => warn!("{}（{}/{}回目）", e, attempt, retry.max_attempts),
+            }
+            tokio::time::sleep(retry.backoff(attempt)).await;
+            attempt += 1;
+        }
+    }
+
+    async fn generate_stream(
+        &self,
+        model: &str,
+        prompt: &str,
+        options: &GenerationOptions,
+        on_chunk: ChunkSink<'_>,
+    ) -> Result<GenerationResult, GenError> {
+        self.breaker.check()?;
+        let started = std::time::Instant::now();
+        let body = request_body(&self.settings.openai.read(), model, prompt, true, options);
+        let connection = self.settings.client.read().connection().clone();
+        let mut res = match self.request(reqwest::Method::POST, "/chat/completions").json(&body).send().await {
+            Ok(res) => res,
+            Err(e) => {
+                self.breaker.record_failure(&e.to_string());
+                return Err(format!("リクエスト失敗: {}", e).into());
+            }
+        };
+        if !res.status().is_success() {
+            let err = format!("生成失敗: HTTP {}", res.status());
+            if res.status().is_server_error() {
+                self.breaker.record_failure(&err);
+            }
+            return Err(err.into());
+        }
+        self.breaker.record_success();
+
+        // 応答は Server-Sent Events（"data: {...}" の行が続き、"data: [DONE]" で終わる）
+        let mut buffer: Vec<u8> = Vec::new();
+        let mut full = String::new();
+        let mut usage = serde_json::Value::Null;
+        while let Some(bytes) = timeouts::next_chunk(&self.breaker, &connection, res.chunk()).await? {
+            buffer.extend_from_slice(&bytes);
+            while let Some(pos) = buffer.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=pos).collect();
+                let line = String::from_utf8_lossy(&line);
+                let Some(data) = line.trim().strip_prefix("data:").map(str::trim) else { continue };
+                if data == "[DONE]" {
+                    break;
+                }
+                let Ok(json) = serde_json::from_str::<serde_json::Value>(data) else { continue };
+                if let Some(err) = json["error"]["message"].as_str() {
+                    return Err(format!("生成失敗: {}", err).into());
+                }
+                if let Some(piece) = json["choices"][0]["delta"]["content"].as_str().filter(|p| !p.is_empty()) {
+                    on_chunk(piece);
+                    full.push_str(piece);
+                }
+                if json["usage"].is_object() {
+                    usage = json["usage"].clone();
+                }
+            }
+        }
+        Ok(GenerationResult { text: full, seed: None, meta: meta(model, &usage, started.elapsed()) })
+    }
+}
+
+/// test_openai_connection の戻り値
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConnectionTest {
+    pub ok: bool,
+    /// サーバーのモデルID
+    pub models: Vec<String>,
+    pub error: Option<String>,
+}
+
+// 保存前に OpenAI 互換サーバーへの接続を確かめる（/v1/models が取得できるか）
+#[command]
+pub async fn test_openai_connection(base_url: String, api_key: Option<String>) -> ConnectionTest {
+    let config =
+        OpenAiCompatConfig { base_url, api_key: api_key.unwrap_or_default(), ..OpenAiCompatConfig::default() }.sanitized();
+    match fetch_model_ids(&Client::new(), &config, HEALTH_CHECK_TIMEOUT).await {
+        Ok(models) => ConnectionTest { ok: true, models, error: None },
+        Err(error) => ConnectionTest { ok: false, models: Vec::new(), error: Some(error) },
+    }
+}
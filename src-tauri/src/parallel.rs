@@ -0,0 +1,125 @@
+// 複数のAI参加者の発言を並行して生成
+// 同じ会話履歴に対する各参加者の応答をまとめて依頼し、できた順に generate://participant-response で返す
+// 同時に Ollama へ送る数は生成キュー（gen_queue）の上限で抑えられる
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Emitter, Manager};
+use tokio::task::JoinSet;
+use tracing::{info, warn};
+
+use crate::{
+    app_state::AppState,
+    generate_persona_reply,
+    generation::{GenerationOptions, GenerationResult},
+    is_allowed_model, requests, ERR_UNSUPPORTED_MODEL,
+};
+
+pub const EVENT_PARTICIPANT_RESPONSE: &str = "generate://participant-response";
+
+// 1回に依頼できる参加者数の上限
+const MAX_PARTICIPANTS: usize = 10;
+
+/// 発言を生成する参加者
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParticipantSpec {
+    pub name: String,
+    #[serde(default)]
+    pub role: String,
+    #[serde(default)]
+    pub description: String,
+    /// 参加者ごとのシード（未指定なら採番）
+    #[serde(default)]
+    pub seed: Option<i64>,
+}
+
+/// 参加者1人分の結果（generate://participant-response のペイロードと戻り値の要素）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParticipantResponse {
+    pub request_id: Option<String>,
+    pub participant_name: String,
+    /// participants 内の位置
+    pub index: usize,
+    pub response: Option<GenerationResult>,
+    pub error: Option<String>,
+}
+
+// 複数参加者の発言を並行して生成する。結果は participants の順で返し、各結果はでき次第イベントでも通知する
+// request_id を cancel_request に渡すと、未完了の生成をまとめて中断できる
+#[command]
+#[allow(clippy::too_many_arguments)] // フロントエンドからの引数をそのまま受ける
+pub async fn generate_responses_parallel(
+    app: AppHandle,
+    participants: Vec<ParticipantSpec>,
+    conversation_history: String,
+    discussion_topic: String,
+    model: String,
+    session_id: Option<i64>,
+    options: Option<GenerationOptions>,
+    request_id: Option<String>,
+) -> Result<Vec<ParticipantResponse>, String> {
+    info!(
+        "generate_responses_parallel 呼び出し: 参加者={}, 同時実行上限={}, model={}",
+        participants.len(),
+        app.state::<AppState>().queue.limit(),
+        model
+    );
+    if !is_allowed_model(&app, &model) {
+        return Err(ERR_UNSUPPORTED_MODEL.to_string());
+    }
+    if participants.is_empty() {
+        return Err("参加者が指定されていません".into());
+    }
+    if participants.len() > MAX_PARTICIPANTS {
+        return Err(format!("参加者は{}人以内で指定してください", MAX_PARTICIPANTS));
+    }
+
+    let fan_out = async {
+        // JoinSet は破棄時に未完了のタスクを中断するため、キャンセル時に生成が残らない
+        let mut tasks = JoinSet::new();
+        for (index, participant) in participants.iter().cloned().enumerate() {
+            let app = app.clone();
+            let history = conversation_history.clone();
+            let topic = discussion_topic.clone();
+            let model = model.clone();
+            let options = GenerationOptions::from_request(options.clone(), participant.seed);
+            let request_id = request_id.clone();
+            tasks.spawn(async move {
+                let result = generate_persona_reply(
+                    &app,
+                    &participant.name,
+                    &participant.role,
+                    &participant.description,
+                    &history,
+                    &topic,
+                    &model,
+                    session_id,
+                    None,
+                    options,
+                    None,
+                )
+                .await;
+                let (response, error) = match result {
+                    Ok(r) => (Some(r), None),
+                    Err(e) => {
+                        warn!("並行生成失敗 ({}): {}", participant.name, e);
+                        (None, Some(e))
+                    }
+                };
+                let payload =
+                    ParticipantResponse { request_id, participant_name: participant.name, index, response, error };
+                let _ = app.emit(EVENT_PARTICIPANT_RESPONSE, payload.clone());
+                payload
+            });
+        }
+
+        let mut results: Vec<Option<ParticipantResponse>> = vec![None; participants.len()];
+        while let Some(joined) = tasks.join_next().await {
+            let payload = joined.map_err(|e| format!("並行生成タスク失敗: {}", e))?;
+            let index = payload.index;
+            results[index] = Some(payload);
+        }
+        Ok(results.into_iter().flatten().collect())
+    };
+    requests::run_cancellable(&app, request_id.as_deref(), fan_out).await
+}
@@ -0,0 +1,204 @@
+// 参加者の議論をまたいだ記憶（アプリ設定の participantMemory で有効にする）
+// 保存済みプロフィール（ai_profiles）と名前が一致する参加者ごとに、取った立場や知ったことの短い記憶を profile_memories に持つ
+// 自動進行の終了時と close_session の後に、その議論での本人の発言をテンプレート participant_memory で記憶に反映し、
+// 以後の議論では発言プロンプトに <participant_memory> として差し込んで、繰り返し登場するペルソナの一貫性を保つ
+use serde::Serialize;
+use tauri::{command, AppHandle};
+use tracing::{info, warn};
+
+use crate::{
+    call_ollama_generate_background, config, db, encryption, encryption::KeyStore, generation::GenerationOptions,
+    is_allowed_model, prompts,
+};
+
+// 記憶の最大文字数（発言プロンプトに毎回入るため短く保つ）
+const MAX_MEMORY_CHARS: usize = 600;
+// 1回の更新に使う本人の発言数の上限（新しいものを優先）
+const MAX_STATEMENTS: usize = 20;
+
+/// プロフィールの記憶
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileMemory {
+    pub profile_id: i64,
+    pub memory: String,
+    /// 記憶に反映した議論の数
+    pub sessions: i64,
+    pub updated_at: String,
+}
+
+/// 参加者に対応する保存済みプロフィール（同名が複数あれば役職の一致するもの、次に更新の新しいもの）
+async fn profile_id_for(pool: &sqlx::SqlitePool, name: &str, role: &str) -> Result<Option<i64>, String> {
+    let row: Option<(i64,)> = sqlx::query_as(
+        "SELECT id FROM ai_profiles WHERE name = ?
+         ORDER BY (role = ?) DESC, datetime(updated_at) DESC, id DESC LIMIT 1",
+    )
+    .bind(name)
+    .bind(role)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("プロフィール取得失敗: {}", e))?;
+    Ok(row.map(|(id,)| id))
+}
+
+async fn load(pool: &sqlx::SqlitePool, keys: &KeyStore, profile_id: i64) -> Result<Option<ProfileMemory>, String> {
+    let row: Option<(String, i64, String)> =
+        sqlx::query_as("SELECT memory, sessions, updated_at FROM profile_memories WHERE profile_id = ?")
+            .bind(profile_id)
+            .fetch_optional(pool)
+            .await
+            .map_err(|e| format!("記憶取得失敗: {}", e))?;
+    row.map(|(memory, sessions, updated_at)| {
+        Ok(ProfileMemory { profile_id, memory: keys.open(&memory)?, sessions, updated_at })
+    })
+    .transpose()
+}
+
+/// 発言プロンプトに差し込む記憶（無効・対応するプロフィールがない・記憶がまだない場合は None）
+pub async fn memory_for(app: &AppHandle, name: &str, role: &str) -> Option<String> {
+    let lookup = async {
+        if !config::load(app).await?.participant_memory {
+            return Ok(None);
+        }
+        let pool = db::pool(app).await?;
+        let Some(profile_id) = profile_id_for(&pool, name, role).await? else {
+            return Ok(None);
+        };
+        Ok::<_, String>(load(&pool, encryption::keys(app), profile_id).await?.map(|m| m.memory))
+    };
+    match lookup.await {
+        Ok(memory) => memory.filter(|m| !m.trim().is_empty()),
+        Err(e) => {
+            warn!("参加者の記憶の取得に失敗 ({}): {}", name, e);
+            None
+        }
+    }
+}
+
+/// セッションでの発言を、対応するプロフィールの記憶に反映する（反映済みの発言は除く）
+async fn update_from_session(app: &AppHandle, session_id: i64) -> Result<usize, String> {
+    let session = db::load_session(app, session_id).await?;
+    if !is_allowed_model(app, &session.model) {
+        return Ok(0);
+    }
+    let pool = db::pool(app).await?;
+    let language = prompts::default_language();
+    let total = session.messages.len();
+    let mut updated = 0;
+    for participant in &session.participants.ai_data {
+        let Some(profile_id) = profile_id_for(&pool, &participant.name, &participant.role).await? else {
+            continue;
+        };
+        let covered: Option<(i64,)> =
+            sqlx::query_as("SELECT covered FROM profile_memory_sources WHERE profile_id = ? AND session_id = ?")
+                .bind(profile_id)
+                .bind(session_id)
+                .fetch_optional(&pool)
+                .await
+                .map_err(|e| format!("記憶の反映状況取得失敗: {}", e))?;
+        let covered = covered.map(|(c,)| (c.max(0) as usize).min(total)).unwrap_or(0);
+        let mut statements: Vec<String> = session.messages[covered..]
+            .iter()
+            .filter(|m| !m.is_user && m.speaker == participant.name && !m.message.trim().is_empty())
+            .map(|m| m.message.clone())
+            .collect();
+        if statements.is_empty() {
+            continue;
+        }
+        statements.drain(..statements.len().saturating_sub(MAX_STATEMENTS));
+
+        let previous = load(&pool, encryption::keys(app), profile_id).await?;
+        let prompt = prompts::build_participant_memory_prompt(
+            &participant.name,
+            &participant.role,
+            &session.topic,
+            previous.as_ref().map(|m| m.memory.as_str()).unwrap_or(""),
+            &statements,
+            MAX_MEMORY_CHARS,
+            language,
+        );
+        let key = format!("memory:{}", profile_id);
+        let options = GenerationOptions::default();
+        let memory = call_ollama_generate_background(app, &key, &session.model, &prompt, &options).await?;
+        let memory: String = memory.trim().chars().take(MAX_MEMORY_CHARS).collect();
+        if memory.is_empty() {
+            continue;
+        }
+        let now = db::now_string();
+        // 同じセッションを続きから反映した場合は、反映した議論の数を増やさない
+        let new_session = covered == 0;
+        sqlx::query(
+            "INSERT INTO profile_memories (profile_id, memory, sessions, updated_at) VALUES (?, ?, 1, ?)
+             ON CONFLICT(profile_id) DO UPDATE SET
+               memory = excluded.memory, sessions = sessions + ?, updated_at = excluded.updated_at",
+        )
+        .bind(profile_id)
+        .bind(encryption::keys(app).seal(&memory)?)
+        .bind(&now)
+        .bind(new_session as i64)
+        .execute(&pool)
+        .await
+        .map_err(|e| format!("記憶保存失敗: {}", e))?;
+        sqlx::query(
+            "INSERT INTO profile_memory_sources (profile_id, session_id, covered, updated_at) VALUES (?, ?, ?, ?)
+             ON CONFLICT(profile_id, session_id) DO UPDATE SET
+               covered = excluded.covered, updated_at = excluded.updated_at",
+        )
+        .bind(profile_id)
+        .bind(session_id)
+        .bind(total as i64)
+        .bind(&now)
+        .execute(&pool)
+        .await
+        .map_err(|e| format!("記憶の反映状況保存失敗: {}", e))?;
+        updated += 1;
+    }
+    Ok(updated)
+}
+
+/// 議論の区切り（自動進行の終了・close_session）で呼ぶ。設定が有効なら記憶の更新をバックグラウンドで行う
+pub fn schedule(app: &AppHandle, session_id: i64) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        match config::load(&app).await {
+            Ok(settings) if settings.participant_memory => {}
+            _ => return,
+        }
+        match update_from_session(&app, session_id).await {
+            Ok(0) => {}
+            Ok(n) => info!("参加者の記憶を更新: session_id={}, {}人", session_id, n),
+            Err(e) => warn!("参加者の記憶の更新に失敗 (session_id={}): {}", session_id, e),
+        }
+    });
+}
+
+/// プロフィールの記憶を消す（delete_profile からも呼ぶ）
+pub async fn clear(app: &AppHandle, profile_id: i64) -> Result<bool, String> {
+    let pool = db::pool(app).await?;
+    sqlx::query("DELETE FROM profile_memory_sources WHERE profile_id = ?")
+        .bind(profile_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| format!("記憶削除失敗: {}", e))?;
+    let deleted = sqlx::query("DELETE FROM profile_memories WHERE profile_id = ?")
+        .bind(profile_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| format!("記憶削除失敗: {}", e))?;
+    Ok(deleted.rows_affected() > 0)
+}
+
+// プロフィールの議論をまたいだ記憶（まだなければ None）
+#[command]
+pub async fn get_profile_memory(app: AppHandle, profile_id: i64) -> Result<Option<ProfileMemory>, String> {
+    info!("get_profile_memory 呼び出し: profile_id={}", profile_id);
+    let pool = db::pool(&app).await?;
+    load(&pool, encryption::keys(&app), profile_id).await
+}
+
+// プロフィールの記憶を消して、次の議論から覚え直させる（消した場合は true）
+#[command]
+pub async fn clear_profile_memory(app: AppHandle, profile_id: i64) -> Result<bool, String> {
+    info!("clear_profile_memory 呼び出し: profile_id={}", profile_id);
+    clear(&app, profile_id).await
+}
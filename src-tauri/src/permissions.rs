@@ -0,0 +1,184 @@
+// ツール・外部連携の権限管理
+// 各ツールは必要な権限（ネットワーク・ファイル読み込み・外部送信）を宣言し、ユーザーがツールごとに付与する
+// ツールの実行前に authorize で付与状況を確認し、実行・拒否はすべて監査ログに残す
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{command, AppHandle};
+
+use crate::{audit, db};
+
+/// ツールが必要とする権限
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Capability {
+    /// ローカル以外へのネットワークアクセス
+    Network,
+    /// ユーザーが指定したファイルの読み込み
+    FilesystemRead,
+    /// 外部サービスへの送信（Webhook など）
+    ExternalPost,
+}
+
+impl Capability {
+    fn as_str(self) -> &'static str {
+        match self {
+            Capability::Network => "network",
+            Capability::FilesystemRead => "filesystem-read",
+            Capability::ExternalPost => "external-post",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "network" => Some(Capability::Network),
+            "filesystem-read" => Some(Capability::FilesystemRead),
+            "external-post" => Some(Capability::ExternalPost),
+            _ => None,
+        }
+    }
+}
+
+/// ツールの宣言
+pub struct ToolManifest {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    pub capabilities: &'static [Capability],
+}
+
+// 権限を必要とするツールの一覧（ツールを追加したらここに宣言する）
+pub const TOOL_PROMPT_SUITE: &str = "prompt-suite";
+pub const TOOL_SESSION_IMPORT: &str = "session-import";
+pub const TOOL_GGUF_DOWNLOAD: &str = "gguf-download";
+pub const TOOL_DOCUMENT_ATTACH: &str = "document-attach";
+pub const TOOL_URL_FETCH: &str = "url-fetch";
+pub const TOOL_IMAGE_INPUT: &str = "image-input";
+
+const TOOLS: &[ToolManifest] = &[
+    ToolManifest {
+        id: TOOL_PROMPT_SUITE,
+        name: "プロンプト評価スイートの読み込み",
+        description: "evaluate_prompts で指定されたスイートファイルを読み込む",
+        capabilities: &[Capability::FilesystemRead],
+    },
+    ToolManifest {
+        id: TOOL_SESSION_IMPORT,
+        name: "セッションファイルの読み込み",
+        description: "import_session でパスを直接指定されたセッションファイル（JSON）を読み込む",
+        capabilities: &[Capability::FilesystemRead],
+    },
+    ToolManifest {
+        id: TOOL_GGUF_DOWNLOAD,
+        name: "GGUF モデルの取得",
+        description: "download_gguf で Hugging Face からモデルファイルを取得する",
+        capabilities: &[Capability::Network],
+    },
+    ToolManifest {
+        id: TOOL_DOCUMENT_ATTACH,
+        name: "資料の添付",
+        description: "attach_document で指定されたテキスト・Markdown・PDF ファイルを読み込む",
+        capabilities: &[Capability::FilesystemRead],
+    },
+    ToolManifest {
+        id: TOOL_URL_FETCH,
+        name: "Webページの取り込み",
+        description: "fetch_url_context で指定された URL のページを取得する",
+        capabilities: &[Capability::Network],
+    },
+    ToolManifest {
+        id: TOOL_IMAGE_INPUT,
+        name: "画像の読み込み",
+        description: "generate_ai_response_with_images で指定された画像ファイルを読み込む",
+        capabilities: &[Capability::FilesystemRead],
+    },
+];
+
+/// ツールごとの権限の状況（フロントエンド表示用）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolPermissions {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    pub required: Vec<Capability>,
+    pub granted: Vec<Capability>,
+}
+
+fn manifest(tool_id: &str) -> Result<&'static ToolManifest, String> {
+    TOOLS.iter().find(|t| t.id == tool_id).ok_or_else(|| format!("未登録のツールです: {}", tool_id))
+}
+
+async fn granted(app: &AppHandle, tool_id: &str) -> Result<Vec<Capability>, String> {
+    let pool = db::pool(app).await?;
+    let rows: Vec<(String,)> = sqlx::query_as("SELECT capability FROM tool_grants WHERE tool_id = ?")
+        .bind(tool_id)
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| format!("権限取得失敗: {}", e))?;
+    Ok(rows.into_iter().filter_map(|(c,)| Capability::parse(&c)).collect())
+}
+
+/// ツール実行前の権限確認（不足していればエラー。結果は監査ログに記録）
+pub async fn authorize(app: &AppHandle, tool_id: &str) -> Result<(), String> {
+    let tool = manifest(tool_id)?;
+    let granted = granted(app, tool_id).await?;
+    let missing: Vec<&str> =
+        tool.capabilities.iter().filter(|c| !granted.contains(c)).map(|c| c.as_str()).collect();
+    if !missing.is_empty() {
+        audit::record("tool", "denied", json!({ "tool": tool_id, "missing": missing }));
+        return Err(format!("ツール「{}」に必要な権限が付与されていません: {}", tool.name, missing.join(", ")));
+    }
+    let used: Vec<&str> = tool.capabilities.iter().map(|c| c.as_str()).collect();
+    audit::record("tool", "invoke", json!({ "tool": tool_id, "capabilities": used }));
+    Ok(())
+}
+
+// 登録済みツールと権限の付与状況
+#[command]
+pub async fn list_tool_permissions(app: AppHandle) -> Result<Vec<ToolPermissions>, String> {
+    let mut out = Vec::with_capacity(TOOLS.len());
+    for tool in TOOLS {
+        out.push(ToolPermissions {
+            id: tool.id,
+            name: tool.name,
+            description: tool.description,
+            required: tool.capabilities.to_vec(),
+            granted: granted(&app, tool.id).await?,
+        });
+    }
+    Ok(out)
+}
+
+// ツールに権限を付与
+#[command]
+pub async fn grant_capability(app: AppHandle, tool_id: String, capability: Capability) -> Result<(), String> {
+    let tool = manifest(&tool_id)?;
+    if !tool.capabilities.contains(&capability) {
+        return Err(format!("ツール「{}」はこの権限を必要としません: {}", tool.name, capability.as_str()));
+    }
+    let pool = db::pool(&app).await?;
+    sqlx::query("INSERT OR IGNORE INTO tool_grants (tool_id, capability, granted_at) VALUES (?, ?, ?)")
+        .bind(&tool_id)
+        .bind(capability.as_str())
+        .bind(db::now_string())
+        .execute(&pool)
+        .await
+        .map_err(|e| format!("権限付与失敗: {}", e))?;
+    audit::record("tool", "grant", json!({ "tool": tool_id, "capability": capability.as_str() }));
+    Ok(())
+}
+
+// ツールの権限を取り消す
+#[command]
+pub async fn revoke_capability(app: AppHandle, tool_id: String, capability: Capability) -> Result<(), String> {
+    manifest(&tool_id)?;
+    let pool = db::pool(&app).await?;
+    sqlx::query("DELETE FROM tool_grants WHERE tool_id = ? AND capability = ?")
+        .bind(&tool_id)
+        .bind(capability.as_str())
+        .execute(&pool)
+        .await
+        .map_err(|e| format!("権限取り消し失敗: {}", e))?;
+    audit::record("tool", "revoke", json!({ "tool": tool_id, "capability": capability.as_str() }));
+    Ok(())
+}
@@ -0,0 +1,245 @@
+// 議論中の参加者の状態（同意度・苛立ち）
+// セッション設定の personaState が有効なら、発言が STATE_INTERVAL 件増えるたびにテンプレート persona_state で
+// 各AI参加者の状態を見積もって persona_states に保存し、persona://updated で通知する
+// 発言プロンプトには <current_state> として差し込み、繰り返し反論された参加者が苛立つなど自然な反応にする
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, OnceLock};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{command, AppHandle, Emitter};
+use tracing::{info, warn};
+
+use crate::{
+    call_ollama_generate_background, db, encryption,
+    generation::{GenerationOptions, OutputFormat},
+    is_allowed_model, llm_json,
+    prompts::{self, PersonaStateArgs},
+    session_settings,
+};
+
+pub const EVENT_PERSONA_UPDATED: &str = "persona://updated";
+
+// この件数の発言が増えるたびに状態を見積もる
+const STATE_INTERVAL: usize = 4;
+// 1回の見積もりに渡す発言数の上限（新しいものを優先）
+const MAX_NEW_MESSAGES: usize = 12;
+const MAX_NOTE_CHARS: usize = 40;
+
+/// 参加者1人の状態
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PersonaState {
+    pub participant: String,
+    /// 他の意見への同意の度合い（0〜1）
+    pub agreement: f32,
+    /// 苛立ちの強さ（0〜1）
+    pub frustration: f32,
+    pub note: String,
+    /// 状態に反映済みの発言数
+    pub covered: usize,
+    pub updated_at: String,
+}
+
+impl PersonaState {
+    /// prompts::with_persona_state に渡す形
+    pub fn prompt_args(&self) -> PersonaStateArgs<'_> {
+        (self.agreement, self.frustration, &self.note)
+    }
+}
+
+/// persona://updated のペイロード
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PersonaUpdatedEvent {
+    session_id: i64,
+    states: Vec<PersonaState>,
+}
+
+/// モデルの回答の1件
+#[derive(Debug, Deserialize)]
+struct RawState {
+    name: String,
+    agreement: f32,
+    frustration: f32,
+    #[serde(default)]
+    note: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawStates {
+    #[serde(default)]
+    states: Vec<RawState>,
+}
+
+/// 見積もり中のセッション（同じセッションを並行して見積もらない）
+fn running() -> &'static Mutex<HashSet<i64>> {
+    static RUNNING: OnceLock<Mutex<HashSet<i64>>> = OnceLock::new();
+    RUNNING.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+fn output_format() -> OutputFormat {
+    OutputFormat::Schema(json!({
+        "type": "object",
+        "properties": {
+            "states": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "name": { "type": "string" },
+                        "agreement": { "type": "number", "minimum": 0, "maximum": 1 },
+                        "frustration": { "type": "number", "minimum": 0, "maximum": 1 },
+                        "note": { "type": "string" }
+                    },
+                    "required": ["name", "agreement", "frustration", "note"]
+                }
+            }
+        },
+        "required": ["states"]
+    }))
+}
+
+/// セッションの保存済みの状態（参加者名の順）
+pub async fn load(app: &AppHandle, session_id: i64) -> Result<Vec<PersonaState>, String> {
+    let pool = db::pool(app).await?;
+    let rows: Vec<(String, f32, f32, String, i64, String)> = sqlx::query_as(
+        "SELECT participant, agreement, frustration, note, covered, updated_at FROM persona_states
+         WHERE session_id = ? ORDER BY participant",
+    )
+    .bind(session_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("参加者の状態取得失敗: {}", e))?;
+    let keys = encryption::keys(app);
+    rows.into_iter()
+        .map(|(participant, agreement, frustration, note, covered, updated_at)| {
+            Ok(PersonaState {
+                participant,
+                agreement,
+                frustration,
+                note: keys.open(&note)?,
+                covered: covered.max(0) as usize,
+                updated_at,
+            })
+        })
+        .collect()
+}
+
+/// 発言プロンプトに差し込む参加者の状態（無効・まだ見積もっていない場合は None）
+pub async fn state_for(app: &AppHandle, session_id: Option<i64>, participant: &str) -> Option<PersonaState> {
+    let session_id = session_id?;
+    let lookup = async {
+        if !session_settings::load(app, session_id).await?.persona_state {
+            return Ok(None);
+        }
+        Ok::<_, String>(load(app, session_id).await?.into_iter().find(|s| s.participant == participant))
+    };
+    lookup.await.unwrap_or_else(|e| {
+        warn!("参加者の状態の取得に失敗 (session_id={}): {}", session_id, e);
+        None
+    })
+}
+
+/// 前回の見積もり以降の発言が STATE_INTERVAL 件以上あれば、各AI参加者の状態を見積もって保存する
+async fn update(app: &AppHandle, session_id: i64) -> Result<Option<Vec<PersonaState>>, String> {
+    if !session_settings::load(app, session_id).await?.persona_state {
+        return Ok(None);
+    }
+    let session = db::load_session(app, session_id).await?;
+    if !is_allowed_model(app, &session.model) || session.participants.ai_data.is_empty() {
+        return Ok(None);
+    }
+    let previous: HashMap<String, PersonaState> =
+        load(app, session_id).await?.into_iter().map(|s| (s.participant.clone(), s)).collect();
+    let total = session.messages.len();
+    let covered = previous.values().map(|s| s.covered).min().unwrap_or(0).min(total);
+    if total - covered < STATE_INTERVAL {
+        return Ok(None);
+    }
+
+    let participants: Vec<(&str, Option<PersonaStateArgs>)> = session
+        .participants
+        .ai_data
+        .iter()
+        .map(|p| (p.name.as_str(), previous.get(&p.name).map(PersonaState::prompt_args)))
+        .collect();
+    let start = covered.max(total.saturating_sub(MAX_NEW_MESSAGES));
+    let language = session_settings::load(app, session_id).await?.language();
+    let prompt = prompts::build_persona_state_prompt(
+        &session.topic,
+        &participants,
+        &db::format_history(&session.messages[start..]),
+        language,
+    );
+    let options = GenerationOptions::default().with_format(output_format());
+    let raw = call_ollama_generate_background(app, &format!("persona:{}", session_id), &session.model, &prompt, &options)
+        .await?;
+    let parsed: RawStates = llm_json::parse_llm_json(&raw)?;
+
+    let pool = db::pool(app).await?;
+    let now = db::now_string();
+    let mut states = Vec::new();
+    for raw in parsed.states {
+        // 参加者にない名前（モデルの書き間違いなど）は捨てる
+        if !participants.iter().any(|(name, _)| *name == raw.name) || !raw.agreement.is_finite() {
+            continue;
+        }
+        let state = PersonaState {
+            participant: raw.name,
+            agreement: raw.agreement.clamp(0.0, 1.0),
+            frustration: if raw.frustration.is_finite() { raw.frustration.clamp(0.0, 1.0) } else { 0.0 },
+            note: raw.note.trim().chars().take(MAX_NOTE_CHARS).collect(),
+            covered: total,
+            updated_at: now.clone(),
+        };
+        sqlx::query(
+            "INSERT INTO persona_states (session_id, participant, agreement, frustration, note, covered, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?)
+             ON CONFLICT(session_id, participant) DO UPDATE SET
+               agreement = excluded.agreement, frustration = excluded.frustration, note = excluded.note,
+               covered = excluded.covered, updated_at = excluded.updated_at",
+        )
+        .bind(session_id)
+        .bind(&state.participant)
+        .bind(state.agreement)
+        .bind(state.frustration)
+        .bind(encryption::keys(app).seal(&state.note)?)
+        .bind(total as i64)
+        .bind(&now)
+        .execute(&pool)
+        .await
+        .map_err(|e| format!("参加者の状態保存失敗: {}", e))?;
+        states.push(state);
+    }
+    if states.is_empty() {
+        return Err("参加者の状態を取り出せませんでした".into());
+    }
+    Ok(Some(states))
+}
+
+/// 発言の保存後に呼ぶ。セッション設定が有効なら状態の見積もりをバックグラウンドで行う
+pub fn schedule(app: &AppHandle, session_id: i64) {
+    if !running().lock().unwrap_or_else(|e| e.into_inner()).insert(session_id) {
+        // 見積もり中に増えた発言は、次の発言保存時に改めて判定される
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        match update(&app, session_id).await {
+            Ok(Some(states)) => {
+                let _ = app.emit(EVENT_PERSONA_UPDATED, PersonaUpdatedEvent { session_id, states });
+            }
+            Ok(None) => {}
+            Err(e) => warn!("参加者の状態の見積もりに失敗 (session_id={}): {}", session_id, e),
+        }
+        running().lock().unwrap_or_else(|e| e.into_inner()).remove(&session_id);
+    });
+}
+
+// セッションの参加者ごとの状態（まだ見積もっていなければ空）
+#[command]
+pub async fn get_persona_states(app: AppHandle, session_id: i64) -> Result<Vec<PersonaState>, String> {
+    info!("get_persona_states 呼び出し: session_id={}", session_id);
+    load(&app, session_id).await
+}
@@ -0,0 +1,21 @@
+// 生成結果の後処理
+// モデルが指示に反して付ける「〇〇:」の話者名・コードフェンス・全体を囲む引用符・前置きや注釈を取り除き、
+// 設定があれば文の区切りで最大文字数に収めてからフロントエンドへ返す
+// 取り除く処理と設定の型は dewai-core にあり、ここでは実行中の設定を渡す
+use tauri::{command, AppHandle, Manager};
+
+pub use dewai_core::postprocess::PostprocessSettings;
+
+use crate::app_state::AppState;
+
+/// 設定に従って発言を後処理する（後処理で空になる場合は元の文を返す）
+pub fn process_reply(app: &AppHandle, text: &str, speaker: &str) -> String {
+    let settings = app.state::<AppState>().settings.postprocess.get();
+    dewai_core::postprocess::process_reply(&settings, text, speaker)
+}
+
+// 発言を後処理する（ストリーミングで受け取った発言を保存前に整える用）
+#[command]
+pub fn postprocess_text(app: AppHandle, text: String, speaker: String) -> String {
+    process_reply(&app, &text, &speaker)
+}
@@ -0,0 +1,250 @@
+// 個人情報の伏せ字処理
+// メールアドレス・電話番号・住所・人名を正規表現（任意で LLM 補助）で検出し、ログ・監査ログ・共有用の出力から取り除く
+// 人名はユーザーが登録した一覧とも照合し、設定で有効なら発言を保存する前にも伏せ字にする
+use std::sync::OnceLock;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Manager, State};
+use tracing::{info, warn};
+
+use crate::{
+    app_state::{AppState, RuntimeSettings},
+    call_ollama_generate, is_allowed_model, llm_json, prompts, ERR_UNSUPPORTED_MODEL,
+};
+
+const PLACEHOLDER_EMAIL: &str = "[メール]";
+const PLACEHOLDER_PHONE: &str = "[電話番号]";
+const PLACEHOLDER_ADDRESS: &str = "[住所]";
+const PLACEHOLDER_NAME: &str = "[氏名]";
+
+// LLM 補助検出に使う既定モデル
+const DEFAULT_DETECTION_MODEL: &str = "gemma3:1b";
+// 登録できる人名の数と、1件の最大文字数
+const MAX_CUSTOM_NAMES: usize = 500;
+const MAX_NAME_CHARS: usize = 40;
+
+/// 伏せ字にする種類（アプリ設定に保存）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PrivacySettings {
+    pub redact_emails: bool,
+    pub redact_phones: bool,
+    pub redact_addresses: bool,
+    /// 「〇〇さん」など敬称付きの人名
+    pub redact_names: bool,
+    /// redact_text で敬称のない人名も LLM に検出させる
+    pub llm_assisted_names: bool,
+    /// 伏せ字にする人名の一覧（敬称の有無を問わず一致した箇所を伏せる。redact_names が有効な時のみ）
+    pub custom_names: Vec<String>,
+    /// 発言を保存する前にも伏せ字にする（元の文面は残らない）
+    pub redact_before_storage: bool,
+}
+
+impl Default for PrivacySettings {
+    fn default() -> Self {
+        Self {
+            redact_emails: true,
+            redact_phones: true,
+            redact_addresses: true,
+            redact_names: true,
+            llm_assisted_names: false,
+            custom_names: Vec::new(),
+            redact_before_storage: false,
+        }
+    }
+}
+
+impl PrivacySettings {
+    pub fn sanitized(mut self) -> Self {
+        let mut names: Vec<String> = self
+            .custom_names
+            .iter()
+            .map(|n| n.trim().chars().take(MAX_NAME_CHARS).collect::<String>())
+            .filter(|n| !n.is_empty())
+            .collect();
+        names.sort();
+        names.dedup();
+        names.truncate(MAX_CUSTOM_NAMES);
+        self.custom_names = names;
+        self
+    }
+}
+
+/// 種類ごとの伏せ字件数
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RedactionCounts {
+    pub emails: usize,
+    pub phones: usize,
+    pub addresses: usize,
+    pub names: usize,
+}
+
+/// redact_text の結果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RedactionResult {
+    pub text: String,
+    pub counts: RedactionCounts,
+}
+
+struct Patterns {
+    email: Regex,
+    phone: Regex,
+    postal_code: Regex,
+    address: Regex,
+    honorific_name: Regex,
+}
+
+fn patterns() -> &'static Patterns {
+    static PATTERNS: OnceLock<Patterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| Patterns {
+        email: Regex::new(r"[A-Za-z0-9._%+\-]+@[A-Za-z0-9.\-]+\.[A-Za-z]{2,}").expect("email"),
+        // 区切りのない番号は携帯電話の形式のみ（一般の数値を誤検出しないため）
+        phone: Regex::new(r"(?:\+\d{1,3}[\s\-]?|0)\d{1,4}[\s\-]\d{1,4}[\s\-]\d{3,4}|0[5789]0\d{8}").expect("phone"),
+        postal_code: Regex::new(r"〒\s?\d{3}-?\d{4}").expect("postal_code"),
+        address: Regex::new(
+            r"(?:東京都|北海道|京都府|大阪府|\p{Han}{2,3}県)[\p{Han}\p{Hiragana}\p{Katakana}ー]{1,10}?[市区町村郡][^\s、。,]{0,24}",
+        )
+        .expect("address"),
+        honorific_name: Regex::new(r"[\p{Han}\p{Katakana}ー]{1,6}(さん|様|さま|氏|くん|君|ちゃん)").expect("honorific_name"),
+    })
+}
+
+/// 反映済みの設定（AppState が持ち、ログ出力とも共有する）
+#[derive(Clone, Default)]
+pub struct ActivePrivacy {
+    settings: PrivacySettings,
+    /// 登録済みの人名をまとめた正規表現（長い名前を先に照合する。未登録なら None）
+    names: Option<Regex>,
+}
+
+fn build_names_regex(names: &[String]) -> Option<Regex> {
+    if names.is_empty() {
+        return None;
+    }
+    let mut sorted: Vec<&String> = names.iter().collect();
+    sorted.sort_by_key(|n| std::cmp::Reverse(n.chars().count()));
+    let alternation = sorted.iter().map(|n| regex::escape(n)).collect::<Vec<_>>().join("|");
+    Regex::new(&alternation).map_err(|e| info!("人名一覧の正規表現を作れません: {}", e)).ok()
+}
+
+/// 設定を反映（起動時と設定保存時）
+pub fn set_settings(runtime: &RuntimeSettings, settings: &PrivacySettings) {
+    let names = build_names_regex(&settings.custom_names);
+    runtime.privacy.set(ActivePrivacy { settings: settings.clone(), names });
+}
+
+/// 一致箇所を置き換え、件数を返す
+fn replace_counted(regex: &Regex, text: &mut String, replacement: &str) -> usize {
+    let count = regex.find_iter(text).count();
+    if count > 0 {
+        *text = regex.replace_all(text, replacement).into_owned();
+    }
+    count
+}
+
+fn redact_with(active: &ActivePrivacy, text: &str) -> RedactionResult {
+    let settings = &active.settings;
+    let p = patterns();
+    let mut out = text.to_string();
+    let mut counts = RedactionCounts::default();
+    // 電話番号より先にメールを処理する（ローカル部の数字を誤検出しないため）
+    if settings.redact_emails {
+        counts.emails = replace_counted(&p.email, &mut out, PLACEHOLDER_EMAIL);
+    }
+    if settings.redact_addresses {
+        counts.addresses = replace_counted(&p.postal_code, &mut out, PLACEHOLDER_ADDRESS)
+            + replace_counted(&p.address, &mut out, PLACEHOLDER_ADDRESS);
+    }
+    if settings.redact_phones {
+        counts.phones = replace_counted(&p.phone, &mut out, PLACEHOLDER_PHONE);
+    }
+    if settings.redact_names {
+        // 登録済みの人名を先に伏せ、敬称付きの照合で名前の一部だけが残らないようにする
+        if let Some(names) = &active.names {
+            counts.names = replace_counted(names, &mut out, PLACEHOLDER_NAME);
+        }
+        counts.names += replace_counted(&p.honorific_name, &mut out, &format!("{}$1", PLACEHOLDER_NAME));
+    }
+    RedactionResult { text: out, counts }
+}
+
+/// 設定に従って伏せ字にする（正規表現のみ。ログ・監査ログ・フィクスチャ用）
+pub fn redact(runtime: &RuntimeSettings, text: &str) -> String {
+    redact_with(&runtime.privacy.read(), text).text
+}
+
+/// 保存前の発言本文を伏せ字にする（redact_before_storage が無効ならそのまま）
+pub fn redact_for_storage(runtime: &RuntimeSettings, text: &str) -> String {
+    let active = runtime.privacy.read();
+    if !active.settings.redact_before_storage {
+        return text.to_string();
+    }
+    redact_with(&active, text).text
+}
+
+/// JSON 内の文字列をすべて伏せ字にする
+pub fn redact_json(runtime: &RuntimeSettings, value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::String(s) => *s = redact(runtime, s),
+        serde_json::Value::Array(items) => items.iter_mut().for_each(|item| redact_json(runtime, item)),
+        serde_json::Value::Object(map) => map.values_mut().for_each(|item| redact_json(runtime, item)),
+        _ => {}
+    }
+}
+
+/// LLM に敬称のない人名を検出させて伏せ字にする（失敗時はそのまま返す）
+async fn redact_names_with_llm(app: &AppHandle, model: &str, text: &str, counts: &mut RedactionCounts) -> String {
+    let raw = match call_ollama_generate(app, model, &prompts::build_pii_detection_prompt(text)).await {
+        Ok(raw) => raw,
+        Err(e) => {
+            warn!("人名検出（LLM）失敗: {}", e);
+            return text.to_string();
+        }
+    };
+    let names: Vec<String> = llm_json::parse_llm_json(&raw).unwrap_or_default();
+    let mut out = text.to_string();
+    for name in names.iter().map(|n| n.trim()).filter(|n| n.chars().count() >= 2) {
+        let found = out.matches(name).count();
+        if found > 0 {
+            counts.names += found;
+            out = out.replace(name, PLACEHOLDER_NAME);
+        }
+    }
+    out
+}
+
+// 共有・エクスポート前のテキストを伏せ字にする（設定で有効なら LLM による人名検出も行う）
+#[command]
+pub async fn redact_text(app: AppHandle, text: String, model: Option<String>) -> Result<RedactionResult, String> {
+    let active = app.state::<AppState>().settings.privacy.get();
+    let mut result = redact_with(&active, &text);
+    if active.settings.redact_names && active.settings.llm_assisted_names {
+        let model = model.unwrap_or_else(|| DEFAULT_DETECTION_MODEL.to_string());
+        if !is_allowed_model(&app, &model) {
+            return Err(ERR_UNSUPPORTED_MODEL.to_string());
+        }
+        result.text = redact_names_with_llm(&app, &model, &result.text, &mut result.counts).await;
+    }
+    Ok(result)
+}
+
+// フロントエンドが保存する messages JSON の本文を伏せ字にする（redact_before_storage が無効なら入力のまま）
+#[command]
+pub fn redact_messages_json(state: State<'_, AppState>, messages: String) -> Result<String, String> {
+    if !state.settings.privacy.read().settings.redact_before_storage {
+        return Ok(messages);
+    }
+    let mut items: Vec<serde_json::Value> = match serde_json::from_str(&messages) {
+        Ok(items) => items,
+        Err(_) => return Ok(messages),
+    };
+    for item in &mut items {
+        if let Some(text) = item.get("message").and_then(|m| m.as_str()) {
+            item["message"] = serde_json::Value::String(redact_for_storage(&state.settings, text));
+        }
+    }
+    serde_json::to_string(&items).map_err(|e| format!("メッセージのシリアライズ失敗: {}", e))
+}
@@ -0,0 +1,204 @@
+// AI参加者プロフィールの生成
+// テンプレート ai_profiles の出力を Rust 側で JSON 配列として読み、名前・役職・説明が揃っているか、名前が重複していないか、
+// 説明の長さが妥当かを確かめる。問題があれば問題点を添えたテンプレート ai_profiles_repair で1回だけ直させ、型付きで返す
+// regenerate_single_profile は1人分だけ作り直し、ほかの参加者と名前・役職が重ならないことも同じ検証で確かめる
+// 顔ぶれの条件（ProfileConstraints）はプロンプトの前に検証し、必須の役職が含まれているかも出力の検証で確かめる
+use serde::Deserialize;
+use tauri::{command, AppHandle};
+use tracing::{info, warn};
+
+use crate::{
+    batch, call_ollama_generate_with,
+    db::AiParticipant,
+    generation::GenerationOptions,
+    is_allowed_model, llm_json, prompts,
+    prompts::ProfileConstraints,
+    ERR_UNSUPPORTED_MODEL,
+};
+
+// 項目ごとの上限文字数（保存済みプロフィール・プロンプト長に合わせる）
+const MAX_NAME_CHARS: usize = 30;
+const MAX_ROLE_CHARS: usize = 60;
+// 説明は100文字前後を求めている。短すぎると口調や視点が定まらない
+const MIN_DESCRIPTION_CHARS: usize = 10;
+const MAX_DESCRIPTION_CHARS: usize = 400;
+
+#[derive(Debug, Deserialize)]
+struct RawProfile {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    role: String,
+    #[serde(default)]
+    description: String,
+}
+
+/// 名前・役職の比較用（前後の空白と大文字小文字の違いを無視する）
+fn same(a: &str, b: &str) -> bool {
+    !a.trim().is_empty() && a.trim().to_lowercase() == b.trim().to_lowercase()
+}
+
+/// モデルの回答をプロフィールの配列として読み、問題があれば問題点の一覧を返す（多すぎる分は切り捨てる）
+/// taken は名前・役職を重ねてはいけない既存の参加者、required_roles は含まれていなければならない役職
+fn validate(
+    raw: &str,
+    count: usize,
+    taken: &[AiParticipant],
+    required_roles: &[String],
+) -> Result<Vec<AiParticipant>, Vec<String>> {
+    let parsed: Vec<RawProfile> = llm_json::parse_llm_json(raw).map_err(|e| vec![e])?;
+    let mut problems = Vec::new();
+    let mut profiles: Vec<AiParticipant> = Vec::new();
+    for (i, raw) in parsed.into_iter().take(count).enumerate() {
+        let profile = AiParticipant {
+            name: raw.name.trim().to_string(),
+            role: raw.role.trim().to_string(),
+            description: raw.description.trim().to_string(),
+        };
+        let label = format!("{}人目", i + 1);
+        for (field, value, max) in [
+            ("name", &profile.name, MAX_NAME_CHARS),
+            ("role", &profile.role, MAX_ROLE_CHARS),
+            ("description", &profile.description, MAX_DESCRIPTION_CHARS),
+        ] {
+            let chars = value.chars().count();
+            if chars == 0 {
+                problems.push(format!("{}の {} が空です", label, field));
+            } else if chars > max {
+                problems.push(format!("{}の {} が長すぎます（{}文字。{}文字以内）", label, field, chars, max));
+            }
+        }
+        let description_chars = profile.description.chars().count();
+        if description_chars > 0 && description_chars < MIN_DESCRIPTION_CHARS {
+            problems.push(format!("{}の description が短すぎます（{}文字以上）", label, MIN_DESCRIPTION_CHARS));
+        }
+        if !profile.name.is_empty() && profiles.iter().any(|p| p.name == profile.name) {
+            problems.push(format!("名前「{}」が重複しています", profile.name));
+        }
+        if taken.iter().any(|p| same(&p.name, &profile.name)) {
+            problems.push(format!("名前「{}」はほかの参加者が使っています", profile.name));
+        }
+        if taken.iter().any(|p| same(&p.role, &profile.role)) {
+            problems.push(format!("役職「{}」はほかの参加者と同じです", profile.role));
+        }
+        profiles.push(profile);
+    }
+    if profiles.len() < count {
+        problems.push(format!("{}名分が必要ですが{}名分しかありません", count, profiles.len()));
+    }
+    // 「弁護士」に「企業弁護士」のような表記の揺れは認める
+    for role in required_roles {
+        if !profiles.iter().any(|p| p.role.contains(role.as_str())) {
+            problems.push(format!("役職「{}」の参加者がいません", role));
+        }
+    }
+    if problems.is_empty() {
+        Ok(profiles)
+    } else {
+        Err(problems)
+    }
+}
+
+/// テーマに合う参加者プロフィールを count 名分生成する（検証に通らなければ1回だけ直させる）
+pub async fn generate(
+    app: &AppHandle,
+    topic: &str,
+    count: usize,
+    style_hint: &str,
+    constraints: ProfileConstraints,
+    model: &str,
+    options: &GenerationOptions,
+) -> Result<Vec<AiParticipant>, String> {
+    generate_avoiding(app, topic, count, style_hint, constraints, model, options, &[]).await
+}
+
+/// taken と名前・役職が重ならない参加者プロフィールを count 名分生成する
+#[allow(clippy::too_many_arguments)] // generate の引数に避ける参加者を足しただけ
+async fn generate_avoiding(
+    app: &AppHandle,
+    topic: &str,
+    count: usize,
+    style_hint: &str,
+    constraints: ProfileConstraints,
+    model: &str,
+    options: &GenerationOptions,
+    taken: &[AiParticipant],
+) -> Result<Vec<AiParticipant>, String> {
+    // プロンプトと同じ範囲に収める
+    let count = count.clamp(1, 10);
+    let constraints = constraints.validated(count)?;
+    let language = prompts::default_language();
+    let options = options.clone().with_format(batch::profiles_format());
+    let prompt = prompts::build_ai_profiles_prompt(topic, count, style_hint, &constraints, language);
+    let raw = call_ollama_generate_with(app, model, &prompt, &options).await?;
+    let problems = match validate(&raw, count, taken, &constraints.required_roles) {
+        Ok(profiles) => return Ok(profiles),
+        Err(problems) => problems,
+    };
+
+    warn!("参加者プロフィールの検証に失敗したため修正を依頼: {}", problems.join(" / "));
+    let prompt = prompts::build_ai_profiles_repair_prompt(topic, count, &raw, &problems, language);
+    let raw = call_ollama_generate_with(app, model, &prompt, &options).await?;
+    validate(&raw, count, taken, &constraints.required_roles)
+        .map_err(|problems| format!("参加者プロフィールを生成できませんでした: {}", problems.join(" / ")))
+}
+
+/// ほかの参加者と重ならないようにするためのヒント
+fn avoid_hint(taken: &[AiParticipant], style_hint: &str) -> String {
+    let listed: Vec<String> = taken
+        .iter()
+        .map(|p| if p.role.is_empty() { p.name.clone() } else { format!("{}（{}）", p.name, p.role) })
+        .collect();
+    let mut hint = String::from("1名分のみ生成。");
+    if !listed.is_empty() {
+        hint.push_str(&format!("次の参加者とは名前・役職が重ならず、異なる視点を持つ人物にする: {}", listed.join("、")));
+    }
+    if !style_hint.trim().is_empty() {
+        hint = format!("{} {}", style_hint.trim(), hint);
+    }
+    hint
+}
+
+// 参加者1人分だけプロフィールを作り直す（ほかの参加者と名前・役職が重ならないものを返す）
+// slot_index は existing_profiles の中の作り直す位置。その位置の今の名前も使わない
+#[command]
+pub async fn regenerate_single_profile(
+    app: AppHandle,
+    topic: String,
+    existing_profiles: Vec<AiParticipant>,
+    slot_index: usize,
+    style_hint: Option<String>,
+    model: String,
+) -> Result<AiParticipant, String> {
+    info!(
+        "regenerate_single_profile 呼び出し: topic='{}', 参加者={}人, slot_index={}, model={}",
+        topic,
+        existing_profiles.len(),
+        slot_index,
+        model
+    );
+    if !is_allowed_model(&app, &model) {
+        return Err(ERR_UNSUPPORTED_MODEL.to_string());
+    }
+    if topic.trim().is_empty() {
+        return Err("テーマを入力してください".into());
+    }
+    let Some(current) = existing_profiles.get(slot_index) else {
+        return Err(format!("slot_index が範囲外です: {}（参加者 {}人）", slot_index, existing_profiles.len()));
+    };
+    // 作り直す位置の名前は避け、役職は引き継げるように他の参加者の分だけ避ける
+    let mut taken: Vec<AiParticipant> = existing_profiles
+        .iter()
+        .enumerate()
+        .filter(|(i, p)| *i != slot_index && !p.name.trim().is_empty())
+        .map(|(_, p)| p.clone())
+        .collect();
+    if !current.name.trim().is_empty() {
+        taken.push(AiParticipant { name: current.name.clone(), role: String::new(), description: String::new() });
+    }
+    let hint = avoid_hint(&taken, style_hint.as_deref().unwrap_or(""));
+    let options = GenerationOptions::default();
+    let mut profiles =
+        generate_avoiding(&app, topic.trim(), 1, &hint, ProfileConstraints::default(), &model, &options, &taken).await?;
+    Ok(profiles.remove(0))
+}
@@ -0,0 +1,186 @@
+// AI参加者のペルソナライブラリ
+// generate_ai_profiles の結果や手入力したプロフィールを ai_profiles に保存し、新しい議論で再利用できるようにする
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle};
+use tracing::info;
+
+use crate::{db, participant_memory};
+
+// 項目ごとの上限文字数（プロンプト長を考慮）
+const MAX_NAME_CHARS: usize = 50;
+const MAX_ROLE_CHARS: usize = 100;
+const MAX_DESCRIPTION_CHARS: usize = 2000;
+const MAX_TAGS: usize = 10;
+
+/// 保存するプロフィールの内容（save_profile / update_profile の入力）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileInput {
+    pub name: String,
+    #[serde(default)]
+    pub role: String,
+    #[serde(default)]
+    pub description: String,
+    /// 分類用のタグ（例: "経済", "反対派"）
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl ProfileInput {
+    /// 前後の空白・空タグ・重複タグを除き、長さを確認する
+    fn validated(mut self) -> Result<Self, String> {
+        self.name = self.name.trim().to_string();
+        self.role = self.role.trim().to_string();
+        self.description = self.description.trim().to_string();
+        let mut tags: Vec<String> = Vec::new();
+        for tag in self.tags.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+            if !tags.iter().any(|t| t == tag) {
+                tags.push(tag.to_string());
+            }
+        }
+        self.tags = tags;
+
+        if self.name.is_empty() {
+            return Err("名前が指定されていません".into());
+        }
+        for (label, value, max) in [
+            ("名前", &self.name, MAX_NAME_CHARS),
+            ("役職", &self.role, MAX_ROLE_CHARS),
+            ("説明", &self.description, MAX_DESCRIPTION_CHARS),
+        ] {
+            if value.chars().count() > max {
+                return Err(format!("{}は{}文字以内で指定してください", label, max));
+            }
+        }
+        if self.tags.len() > MAX_TAGS {
+            return Err(format!("タグは{}個以内で指定してください", MAX_TAGS));
+        }
+        Ok(self)
+    }
+}
+
+/// 保存済みプロフィール（name / role / description は participants JSON の aiData と同じ形）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AiProfile {
+    pub id: i64,
+    pub name: String,
+    pub role: String,
+    pub description: String,
+    pub tags: Vec<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+type ProfileRow = (i64, String, String, String, String, String, String);
+
+fn from_row((id, name, role, description, tags, created_at, updated_at): ProfileRow) -> AiProfile {
+    AiProfile {
+        id,
+        name,
+        role,
+        description,
+        tags: serde_json::from_str(&tags).unwrap_or_default(),
+        created_at,
+        updated_at,
+    }
+}
+
+async fn load(app: &AppHandle, profile_id: i64) -> Result<AiProfile, String> {
+    let pool = db::pool(app).await?;
+    let row: Option<ProfileRow> = sqlx::query_as(
+        "SELECT id, name, role, description, tags, created_at, updated_at FROM ai_profiles WHERE id = ?",
+    )
+    .bind(profile_id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| format!("プロフィール取得失敗: {}", e))?;
+    row.map(from_row).ok_or_else(|| format!("プロフィールが見つかりません: id={}", profile_id))
+}
+
+// プロフィールをライブラリに保存
+#[command]
+pub async fn save_profile(app: AppHandle, profile: ProfileInput) -> Result<AiProfile, String> {
+    let profile = profile.validated()?;
+    info!("save_profile 呼び出し: name='{}'", profile.name);
+    let pool = db::pool(&app).await?;
+    let tags = serde_json::to_string(&profile.tags).map_err(|e| format!("タグのシリアライズ失敗: {}", e))?;
+    let now = db::now_string();
+    let id = sqlx::query(
+        "INSERT INTO ai_profiles (name, role, description, tags, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&profile.name)
+    .bind(&profile.role)
+    .bind(&profile.description)
+    .bind(tags)
+    .bind(&now)
+    .bind(&now)
+    .execute(&pool)
+    .await
+    .map_err(|e| format!("プロフィール保存失敗: {}", e))?
+    .last_insert_rowid();
+    Ok(AiProfile {
+        id,
+        name: profile.name,
+        role: profile.role,
+        description: profile.description,
+        tags: profile.tags,
+        created_at: now.clone(),
+        updated_at: now,
+    })
+}
+
+// プロフィール一覧（tag 指定時はそのタグを持つものだけ。更新の新しい順）
+#[command]
+pub async fn list_profiles(app: AppHandle, tag: Option<String>) -> Result<Vec<AiProfile>, String> {
+    let pool = db::pool(&app).await?;
+    let rows: Vec<ProfileRow> = sqlx::query_as(
+        "SELECT id, name, role, description, tags, created_at, updated_at FROM ai_profiles
+         ORDER BY updated_at DESC, id DESC",
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("プロフィール一覧取得失敗: {}", e))?;
+    let tag = tag.map(|t| t.trim().to_string()).filter(|t| !t.is_empty());
+    Ok(rows
+        .into_iter()
+        .map(from_row)
+        .filter(|p| tag.as_ref().is_none_or(|t| p.tags.contains(t)))
+        .collect())
+}
+
+// プロフィールを更新
+#[command]
+pub async fn update_profile(app: AppHandle, profile_id: i64, profile: ProfileInput) -> Result<AiProfile, String> {
+    let profile = profile.validated()?;
+    let pool = db::pool(&app).await?;
+    let tags = serde_json::to_string(&profile.tags).map_err(|e| format!("タグのシリアライズ失敗: {}", e))?;
+    let updated =
+        sqlx::query("UPDATE ai_profiles SET name = ?, role = ?, description = ?, tags = ?, updated_at = ? WHERE id = ?")
+            .bind(&profile.name)
+            .bind(&profile.role)
+            .bind(&profile.description)
+            .bind(tags)
+            .bind(db::now_string())
+            .bind(profile_id)
+            .execute(&pool)
+            .await
+            .map_err(|e| format!("プロフィール更新失敗: {}", e))?;
+    if updated.rows_affected() == 0 {
+        return Err(format!("プロフィールが見つかりません: id={}", profile_id));
+    }
+    load(&app, profile_id).await
+}
+
+// プロフィールを削除（保存済みセッションの参加者には影響しない。議論をまたいだ記憶も消える）
+#[command]
+pub async fn delete_profile(app: AppHandle, profile_id: i64) -> Result<(), String> {
+    participant_memory::clear(&app, profile_id).await?;
+    let pool = db::pool(&app).await?;
+    sqlx::query("DELETE FROM ai_profiles WHERE id = ?")
+        .bind(profile_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| format!("プロフィール削除失敗: {}", e))?;
+    Ok(())
+}
@@ -0,0 +1,290 @@
+// プロンプトの回帰評価
+// 保存済みのケース集を現行の prompts.rs のテンプレートで生成し、出力の構造（JSON・必須項目・長さ・名前の使い方）を検査する
+// テンプレート変更をリリース前に確認するためのもの。モック・フィクスチャ再生のバックエンドでも実行できる
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle};
+use tracing::info;
+
+use crate::{
+    call_ollama_generate_with,
+    generation::GenerationOptions,
+    is_allowed_model, llm_json, permissions, prompts,
+    prompts::{PromptStyle, PromptVersion},
+    ERR_UNSUPPORTED_MODEL,
+};
+
+// 既定のモデルとシード（同じスイートは同じ条件で回す）
+const DEFAULT_MODEL: &str = "gemma3:4b";
+const DEFAULT_SEED: i64 = 42;
+// レポートに載せる出力の文字数
+const PREVIEW_CHARS: usize = 120;
+// 1スイートあたりのケース上限
+const MAX_CASES: usize = 200;
+
+/// 評価スイート（JSON ファイル）
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptSuite {
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub seed: Option<i64>,
+    pub cases: Vec<PromptCase>,
+}
+
+/// 1ケース（テンプレートの種類と入力、出力への期待）
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptCase {
+    pub name: String,
+    #[serde(flatten)]
+    pub template: PromptTemplate,
+    #[serde(default)]
+    pub expect: Expectations,
+}
+
+/// 評価対象のテンプレートと入力
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "template", rename_all = "kebab-case", rename_all_fields = "camelCase")]
+pub enum PromptTemplate {
+    AiResponse {
+        participant_name: String,
+        role: String,
+        #[serde(default)]
+        description: String,
+        topic: String,
+        #[serde(default)]
+        history: String,
+        #[serde(default)]
+        prompt_version: PromptVersion,
+    },
+    DiscussionStart {
+        topic: String,
+        participants: Vec<String>,
+    },
+    Summary {
+        topic: String,
+        history: String,
+        participants: Vec<String>,
+    },
+    Analysis {
+        topic: String,
+        history: String,
+        participants: Vec<String>,
+    },
+    Profiles {
+        topic: String,
+        count: usize,
+        #[serde(default)]
+        hint: String,
+    },
+    Quality {
+        topic: String,
+        history: String,
+    },
+}
+
+impl PromptTemplate {
+    fn label(&self) -> &'static str {
+        match self {
+            PromptTemplate::AiResponse { .. } => "ai-response",
+            PromptTemplate::DiscussionStart { .. } => "discussion-start",
+            PromptTemplate::Summary { .. } => "summary",
+            PromptTemplate::Analysis { .. } => "analysis",
+            PromptTemplate::Profiles { .. } => "profiles",
+            PromptTemplate::Quality { .. } => "quality",
+        }
+    }
+
+    /// 現行のテンプレートでプロンプトを組み立てる
+    fn build(&self) -> String {
+        let style = PromptStyle::default();
+        match self {
+            PromptTemplate::AiResponse { participant_name, role, description, topic, history, prompt_version } => {
+                prompts::build_ai_response_prompt_versioned(
+                    *prompt_version,
+                    participant_name,
+                    role,
+                    description,
+                    history,
+                    topic,
+                    &style,
+                )
+            }
+            PromptTemplate::DiscussionStart { topic, participants } => {
+                prompts::build_discussion_start_prompt(topic, participants, &style)
+            }
+            PromptTemplate::Summary { topic, history, participants } => {
+                prompts::build_discussion_summary_prompt(topic, history, participants, &style)
+            }
+            PromptTemplate::Analysis { topic, history, participants } => {
+                prompts::build_discussion_analysis_prompt(topic, history, participants, style.language)
+            }
+            PromptTemplate::Profiles { topic, count, hint } => {
+                prompts::build_ai_profiles_prompt(topic, *count, hint, &Default::default(), style.language)
+            }
+            PromptTemplate::Quality { topic, history } => prompts::build_discussion_quality_prompt(topic, history, style.language),
+        }
+    }
+}
+
+/// 出力への期待（未指定の項目は検査しない）
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Expectations {
+    /// 出力が JSON として解析できること
+    pub valid_json: bool,
+    /// JSON のトップレベル（配列なら各要素）に必要なキー
+    pub required_keys: Vec<String>,
+    /// 出力に含まれるべき見出し・語句
+    pub required_sections: Vec<String>,
+    pub min_chars: Option<usize>,
+    pub max_chars: Option<usize>,
+    /// 出力に含まれるべき名前（参加者名など）
+    pub must_mention: Vec<String>,
+    /// 出力に含まれてはいけない名前・語句
+    pub must_not_mention: Vec<String>,
+    /// 発言の冒頭に「名前：」のような話者ラベルを付けていないこと（ai-response 用）
+    pub no_speaker_label: bool,
+}
+
+/// 1ケース分の結果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptCaseResult {
+    pub name: String,
+    pub template: &'static str,
+    pub passed: bool,
+    /// 満たさなかった期待の説明
+    pub failures: Vec<String>,
+    pub prompt_chars: usize,
+    pub output_chars: usize,
+    pub output_preview: String,
+    pub latency_ms: u128,
+}
+
+/// スイート全体の結果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptEvalReport {
+    pub suite_path: String,
+    pub backend: &'static str,
+    pub model: String,
+    pub total: usize,
+    pub passed: usize,
+    pub failed: usize,
+    pub cases: Vec<PromptCaseResult>,
+}
+
+/// 出力を期待と照合し、満たさなかった項目を返す
+fn check(template: &PromptTemplate, expect: &Expectations, output: &str) -> Vec<String> {
+    let mut failures = Vec::new();
+    let chars = output.trim().chars().count();
+
+    if expect.valid_json || !expect.required_keys.is_empty() {
+        match llm_json::parse_llm_json::<serde_json::Value>(output) {
+            Ok(value) => {
+                let objects: Vec<&serde_json::Value> = match &value {
+                    serde_json::Value::Array(items) => items.iter().collect(),
+                    other => vec![other],
+                };
+                for key in &expect.required_keys {
+                    if objects.is_empty() || objects.iter().any(|o| o.get(key).is_none()) {
+                        failures.push(format!("JSONにキー「{}」がありません", key));
+                    }
+                }
+                if let (PromptTemplate::Profiles { count, .. }, serde_json::Value::Array(items)) = (template, &value) {
+                    if items.len() != (*count).clamp(1, 10) {
+                        failures.push(format!("参加者数が{}人です（期待: {}人）", items.len(), count));
+                    }
+                }
+            }
+            Err(e) => failures.push(e),
+        }
+    }
+    for section in &expect.required_sections {
+        if !output.contains(section.as_str()) {
+            failures.push(format!("「{}」が含まれていません", section));
+        }
+    }
+    if let Some(min) = expect.min_chars.filter(|min| chars < *min) {
+        failures.push(format!("短すぎます（{}文字 < {}文字）", chars, min));
+    }
+    if let Some(max) = expect.max_chars.filter(|max| chars > *max) {
+        failures.push(format!("長すぎます（{}文字 > {}文字）", chars, max));
+    }
+    for name in &expect.must_mention {
+        if !output.contains(name.as_str()) {
+            failures.push(format!("「{}」に言及していません", name));
+        }
+    }
+    for name in &expect.must_not_mention {
+        if output.contains(name.as_str()) {
+            failures.push(format!("「{}」が含まれています", name));
+        }
+    }
+    if expect.no_speaker_label {
+        if let PromptTemplate::AiResponse { participant_name, .. } = template {
+            let head = output.trim_start();
+            if [":", "：", "「"].iter().any(|sep| head.starts_with(&format!("{}{}", participant_name, sep))) {
+                failures.push(format!("冒頭に話者ラベル「{}」が付いています", participant_name));
+            }
+        }
+    }
+    failures
+}
+
+// スイートのケースを現行テンプレートで生成し、構造の期待を満たすか検査
+#[command]
+pub async fn evaluate_prompts(app: AppHandle, suite_path: String) -> Result<PromptEvalReport, String> {
+    info!("evaluate_prompts 呼び出し: {}", suite_path);
+    permissions::authorize(&app, permissions::TOOL_PROMPT_SUITE).await?;
+    let json = std::fs::read_to_string(&suite_path).map_err(|e| format!("スイート読み込み失敗: {}", e))?;
+    let suite: PromptSuite = serde_json::from_str(&json).map_err(|e| format!("スイートの解析失敗: {}", e))?;
+    if suite.cases.is_empty() || suite.cases.len() > MAX_CASES {
+        return Err(format!("ケースは1〜{}件で指定してください", MAX_CASES));
+    }
+    let model = suite.model.clone().unwrap_or_else(|| DEFAULT_MODEL.to_string());
+    if !is_allowed_model(&app, &model) {
+        return Err(ERR_UNSUPPORTED_MODEL.to_string());
+    }
+    let options = GenerationOptions::seeded(Some(suite.seed.unwrap_or(DEFAULT_SEED)));
+
+    let mut cases = Vec::with_capacity(suite.cases.len());
+    for case in &suite.cases {
+        let prompt = case.template.build();
+        let started = Instant::now();
+        let (output, failures) = match call_ollama_generate_with(&app, &model, &prompt, &options).await {
+            Ok(output) => {
+                let failures = check(&case.template, &case.expect, &output);
+                (output, failures)
+            }
+            Err(e) => (String::new(), vec![format!("生成失敗: {}", e)]),
+        };
+        let passed = failures.is_empty();
+        info!("プロンプト評価: {} -> {}", case.name, if passed { "合格" } else { "不合格" });
+        cases.push(PromptCaseResult {
+            name: case.name.clone(),
+            template: case.template.label(),
+            passed,
+            failures,
+            prompt_chars: prompt.chars().count(),
+            output_chars: output.chars().count(),
+            output_preview: output.chars().take(PREVIEW_CHARS).collect(),
+            latency_ms: started.elapsed().as_millis(),
+        });
+    }
+
+    let passed = cases.iter().filter(|c| c.passed).count();
+    Ok(PromptEvalReport {
+        suite_path,
+        backend: crate::backend::current(&app).name(),
+        model,
+        total: cases.len(),
+        passed,
+        failed: cases.len() - passed,
+        cases,
+    })
+}
@@ -0,0 +1,150 @@
+// 編集できるプロンプトテンプレート
+// prompts.rs の組み込みテンプレートを prompt_templates に登録し、利用者が書き換えたものを生成に使う
+// 書き換えていない行は起動時に組み込みの最新版で上書きするので、アプリ更新でのテンプレート改善がそのまま届く
+use serde::Serialize;
+use tauri::{command, AppHandle};
+use tracing::warn;
+
+use crate::{
+    db,
+    prompts::{self, TemplateKind},
+};
+
+// テンプレート本文の上限文字数
+const MAX_TEMPLATE_CHARS: usize = 20_000;
+
+/// get_template などの戻り値
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptTemplate {
+    pub kind: TemplateKind,
+    pub body: String,
+    /// 組み込みのテンプレート（比較・元に戻す前の確認用）
+    pub default_body: &'static str,
+    /// 使えるプレースホルダー
+    pub placeholders: &'static [&'static str],
+    /// 省けないプレースホルダー
+    pub required: &'static [&'static str],
+    /// 利用者が書き換えたか
+    pub customized: bool,
+    pub updated_at: Option<String>,
+}
+
+fn to_template(kind: TemplateKind, row: Option<(String, i64, String)>) -> PromptTemplate {
+    let (body, customized, updated_at) = match row {
+        Some((body, customized, updated_at)) => (body, customized != 0, Some(updated_at)),
+        None => (kind.default_body().to_string(), false, None),
+    };
+    PromptTemplate {
+        kind,
+        body,
+        default_body: kind.default_body(),
+        placeholders: kind.placeholders(),
+        required: kind.required_placeholders(),
+        customized,
+        updated_at,
+    }
+}
+
+async fn fetch(app: &AppHandle, kind: TemplateKind) -> Result<PromptTemplate, String> {
+    let pool = db::pool(app).await?;
+    let row: Option<(String, i64, String)> =
+        sqlx::query_as("SELECT body, customized, updated_at FROM prompt_templates WHERE kind = ?")
+            .bind(kind.key())
+            .fetch_optional(&pool)
+            .await
+            .map_err(|e| format!("テンプレート取得失敗: {}", e))?;
+    Ok(to_template(kind, row))
+}
+
+/// 組み込みのテンプレートを登録し、書き換えられたものを反映する（起動時）
+pub async fn load(app: &AppHandle) -> Result<(), String> {
+    let pool = db::pool(app).await?;
+    let now = db::now_string();
+    for kind in TemplateKind::ALL {
+        sqlx::query(
+            "INSERT INTO prompt_templates (kind, body, customized, updated_at) VALUES (?, ?, 0, ?)
+             ON CONFLICT(kind) DO UPDATE SET body = excluded.body, updated_at = excluded.updated_at
+             WHERE prompt_templates.customized = 0 AND prompt_templates.body <> excluded.body",
+        )
+        .bind(kind.key())
+        .bind(kind.default_body())
+        .bind(&now)
+        .execute(&pool)
+        .await
+        .map_err(|e| format!("テンプレート登録失敗: {}", e))?;
+    }
+    let rows: Vec<(String, String)> =
+        sqlx::query_as("SELECT kind, body FROM prompt_templates WHERE customized = 1")
+            .fetch_all(&pool)
+            .await
+            .map_err(|e| format!("テンプレート取得失敗: {}", e))?;
+    for (key, body) in rows {
+        let Ok(kind) = TemplateKind::parse(&key) else { continue };
+        // 旧版で保存した後にプレースホルダーが変わった場合などは組み込みを使う
+        match prompts::validate_template(kind, &body) {
+            Ok(()) => prompts::set_template_override(kind, Some(body)),
+            Err(e) => warn!("テンプレート {} が不正なため組み込みを使用: {}", key, e),
+        }
+    }
+    Ok(())
+}
+
+// テンプレートの一覧
+#[command]
+pub async fn list_templates(app: AppHandle) -> Result<Vec<PromptTemplate>, String> {
+    let mut templates = Vec::new();
+    for kind in TemplateKind::ALL {
+        templates.push(fetch(&app, kind).await?);
+    }
+    Ok(templates)
+}
+
+// テンプレートを取得（kind: ai_response / devils_advocate / discussion_start / discussion_analysis /
+// incremental_analysis / discussion_summary / incremental_summary / ai_profiles）
+#[command]
+pub async fn get_template(app: AppHandle, kind: String) -> Result<PromptTemplate, String> {
+    fetch(&app, TemplateKind::parse(&kind)?).await
+}
+
+// テンプレートを書き換える（必須のプレースホルダーが欠けたもの・使えないプレースホルダーを含むものは拒否）
+#[command]
+pub async fn update_template(app: AppHandle, kind: String, body: String) -> Result<PromptTemplate, String> {
+    let kind = TemplateKind::parse(&kind)?;
+    if body.chars().count() > MAX_TEMPLATE_CHARS {
+        return Err(format!("テンプレートは{}文字以内で指定してください", MAX_TEMPLATE_CHARS));
+    }
+    prompts::validate_template(kind, &body)?;
+    let pool = db::pool(&app).await?;
+    sqlx::query(
+        "INSERT INTO prompt_templates (kind, body, customized, updated_at) VALUES (?, ?, 1, ?)
+         ON CONFLICT(kind) DO UPDATE SET body = excluded.body, customized = 1, updated_at = excluded.updated_at",
+    )
+    .bind(kind.key())
+    .bind(&body)
+    .bind(db::now_string())
+    .execute(&pool)
+    .await
+    .map_err(|e| format!("テンプレート保存失敗: {}", e))?;
+    prompts::set_template_override(kind, Some(body));
+    fetch(&app, kind).await
+}
+
+// テンプレートを組み込みに戻す
+#[command]
+pub async fn reset_template(app: AppHandle, kind: String) -> Result<PromptTemplate, String> {
+    let kind = TemplateKind::parse(&kind)?;
+    let pool = db::pool(&app).await?;
+    sqlx::query(
+        "INSERT INTO prompt_templates (kind, body, customized, updated_at) VALUES (?, ?, 0, ?)
+         ON CONFLICT(kind) DO UPDATE SET body = excluded.body, customized = 0, updated_at = excluded.updated_at",
+    )
+    .bind(kind.key())
+    .bind(kind.default_body())
+    .bind(db::now_string())
+    .execute(&pool)
+    .await
+    .map_err(|e| format!("テンプレートの初期化失敗: {}", e))?;
+    prompts::set_template_override(kind, None);
+    fetch(&app, kind).await
+}
@@ -0,0 +1,112 @@
+// ユーザー入力の校正（誤字脱字・文法）
+// モデルには誤り箇所の文字列だけを答えさせ、位置（span）はこちらで原文から求める
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle};
+use tracing::{info, warn};
+
+use crate::{call_ollama_generate_with, generation::GenerationOptions, is_allowed_model, llm_json, prompts, ERR_UNSUPPORTED_MODEL};
+
+/// 校正の1指摘。start/end はフロントエンド（JS文字列）と同じ UTF-16 単位の位置
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProofreadEdit {
+    pub start: usize,
+    pub end: usize,
+    pub original: String,
+    pub replacement: String,
+    pub reason: String,
+}
+
+/// 校正結果（全指摘を適用した文面も併せて返す）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProofreadResult {
+    pub edits: Vec<ProofreadEdit>,
+    pub corrected: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawEdit {
+    original: String,
+    replacement: String,
+    #[serde(default)]
+    reason: String,
+}
+
+fn utf16_len(s: &str) -> usize {
+    s.encode_utf16().count()
+}
+
+/// 指摘を原文上の位置に対応付ける（見つからない・重なる指摘は捨てる）
+fn locate_edits(text: &str, raw: Vec<RawEdit>) -> Vec<(usize, usize, RawEdit)> {
+    let mut located: Vec<(usize, usize, RawEdit)> = Vec::new();
+    let mut cursor = 0; // バイト位置。指摘は原文の出現順で返る前提で前から探す
+    for edit in raw {
+        if edit.original.is_empty() || edit.original == edit.replacement {
+            continue;
+        }
+        let found = text[cursor..]
+            .find(&edit.original)
+            .map(|p| p + cursor)
+            .or_else(|| text.find(&edit.original));
+        let Some(byte_start) = found else {
+            warn!("校正指摘を原文中に特定できません: {}", edit.original);
+            continue;
+        };
+        let byte_end = byte_start + edit.original.len();
+        if located.iter().any(|(s, e, _)| byte_start < *e && *s < byte_end) {
+            continue;
+        }
+        cursor = byte_end;
+        located.push((byte_start, byte_end, edit));
+    }
+    located.sort_by_key(|(s, _, _)| *s);
+    located
+}
+
+// 入力文を校正し、インライン修正用の指摘一覧を返す
+#[command]
+pub async fn proofread_text(
+    app: AppHandle,
+    text: String,
+    lang: Option<String>,
+    model: String,
+    seed: Option<i64>,
+    options: Option<GenerationOptions>,
+) -> Result<ProofreadResult, String> {
+    let lang = lang.unwrap_or_else(|| "日本語".to_string());
+    info!("proofread_text 呼び出し (model={}, lang={}, text_len={})", model, lang, text.len());
+    if !is_allowed_model(&app, &model) {
+        return Err(ERR_UNSUPPORTED_MODEL.to_string());
+    }
+    if text.trim().is_empty() {
+        return Ok(ProofreadResult { edits: Vec::new(), corrected: text });
+    }
+
+    let prompt = prompts::build_proofread_prompt(&text, &lang);
+    let options = GenerationOptions::from_request(options, seed);
+    let raw = call_ollama_generate_with(&app, &model, &prompt, &options).await?;
+    let raw_edits: Vec<RawEdit> = llm_json::parse_llm_json(&raw)?;
+
+    let located = locate_edits(&text, raw_edits);
+    let mut corrected = String::with_capacity(text.len());
+    let mut last = 0;
+    let mut edits = Vec::with_capacity(located.len());
+    for (byte_start, byte_end, edit) in located {
+        corrected.push_str(&text[last..byte_start]);
+        corrected.push_str(&edit.replacement);
+        last = byte_end;
+        let start = utf16_len(&text[..byte_start]);
+        edits.push(ProofreadEdit {
+            start,
+            end: start + utf16_len(&edit.original),
+            original: edit.original,
+            replacement: edit.replacement,
+            reason: edit.reason,
+        });
+    }
+    corrected.push_str(&text[last..]);
+
+    info!("校正指摘: {}件", edits.len());
+    Ok(ProofreadResult { edits, corrected })
+}
@@ -0,0 +1,79 @@
+// 読みやすさの簡易判定と、読解レベル超過時の書き直し
+// 形態素解析は使わず、文の長さと漢字・カタカナ語の割合だけで軽量に判定する
+use tauri::AppHandle;
+use tracing::{info, warn};
+
+use crate::{
+    call_ollama_generate, prompts,
+    prompts::{Language, ReadingLevel},
+};
+
+/// 文章の簡易統計
+#[derive(Debug, Clone, Copy)]
+struct ReadingStats {
+    /// 1文あたりの平均文字数
+    avg_sentence_chars: f32,
+    /// 空白・記号を除いた文字に占める漢字の割合
+    kanji_ratio: f32,
+    /// 同じく、カタカナの割合（カタカナ語の多さの目安）
+    katakana_ratio: f32,
+}
+
+fn is_kanji(c: char) -> bool {
+    ('\u{4E00}'..='\u{9FFF}').contains(&c) || ('\u{3400}'..='\u{4DBF}').contains(&c)
+}
+
+fn is_katakana(c: char) -> bool {
+    ('\u{30A1}'..='\u{30FA}').contains(&c) || c == 'ー'
+}
+
+fn stats(text: &str) -> ReadingStats {
+    let sentences: Vec<&str> = text
+        .split(['。', '！', '？', '!', '?', '\n'])
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .collect();
+    let letters: Vec<char> = text.chars().filter(|c| c.is_alphanumeric()).collect();
+    let total = letters.len().max(1) as f32;
+    let sentence_chars: usize = sentences.iter().map(|s| s.chars().count()).sum();
+    ReadingStats {
+        avg_sentence_chars: sentence_chars as f32 / sentences.len().max(1) as f32,
+        kanji_ratio: letters.iter().filter(|c| is_kanji(**c)).count() as f32 / total,
+        katakana_ratio: letters.iter().filter(|c| is_katakana(**c)).count() as f32 / total,
+    }
+}
+
+/// 指定レベルに対して難しすぎるか
+fn exceeds(text: &str, level: ReadingLevel) -> bool {
+    let s = stats(text);
+    match level {
+        ReadingLevel::Elementary => s.avg_sentence_chars > 35.0 || s.kanji_ratio > 0.25 || s.katakana_ratio > 0.20,
+        ReadingLevel::HighSchool => s.avg_sentence_chars > 60.0 || s.kanji_ratio > 0.40,
+        ReadingLevel::Expert => false,
+    }
+}
+
+/// 読解レベルを超えていれば1回だけ書き直す（書き直し失敗時は元の文を返す）
+/// 判定は日本語の文字種に基づくため、日本語以外の出力言語では書き直さない
+pub async fn enforce_reading_level(
+    app: &AppHandle,
+    model: &str,
+    text: String,
+    level: Option<ReadingLevel>,
+    language: Language,
+) -> String {
+    let Some(level) = level else { return text };
+    if language != Language::Ja || !exceeds(&text, level) {
+        return text;
+    }
+    info!("読解レベル超過を検出 ({:?}): {:?}。書き直しを実行します", level, stats(&text));
+    let prompt = prompts::build_reading_level_rewrite_prompt(&text, level, language);
+    match call_ollama_generate(app, model, &prompt).await {
+        Ok(rewritten) if !rewritten.trim().is_empty() => rewritten,
+        Ok(_) => text,
+        Err(e) => {
+            warn!("書き直し失敗（元の発言を使用）: {}", e);
+            text
+        }
+    }
+}
@@ -0,0 +1,168 @@
+// 関連セッションの検索
+// 発言の埋め込み（embeddings）をセッションごとに平均したベクトルの近さで、過去の議論から似たテーマのものを探す
+// どちらかのセッションがベクトル化されていなければ、タグとテーマの重なり（テーマは2文字単位）で比べる
+// 結果には各セッションの最新の要約を付け、以前の議論でAIがどんな結論に至ったかをすぐ確認できるようにする
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+use tauri::{command, AppHandle};
+use tracing::info;
+
+use dewai_core::similarity::{jaccard, topic_grams};
+
+use crate::{db, embeddings};
+
+// 結果の件数の既定値と上限
+const DEFAULT_LIMIT: usize = 5;
+const MAX_LIMIT: usize = 50;
+// キーワードでの比較のうちタグの重み（残りはテーマ）
+const TAG_WEIGHT: f32 = 0.6;
+
+/// 関連の判定方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RelatedBy {
+    /// 発言の埋め込みの近さ
+    Embedding,
+    /// タグとテーマの重なり
+    Keywords,
+}
+
+/// 関連セッション1件
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelatedSession {
+    pub session_id: i64,
+    pub topic: String,
+    pub updated_at: String,
+    /// 近さ（埋め込みはコサイン類似度、キーワードは 0〜1 の重なり）
+    pub score: f32,
+    pub by: RelatedBy,
+    /// 共通するタグ
+    pub shared_tags: Vec<String>,
+    /// 最新の要約（なければ None）
+    pub summary: Option<String>,
+}
+
+/// 比較に使うセッションの特徴
+#[derive(Default)]
+struct Features {
+    topic: String,
+    updated_at: String,
+    tags: HashSet<String>,
+    topic_grams: HashSet<String>,
+    /// 発言のベクトルの平均（正規化済み。ベクトル化していなければ None）
+    centroid: Option<Vec<f32>>,
+}
+
+/// セッションごとの発言ベクトルの平均（本文が変わった発言の古いベクトルは使わない）
+async fn centroids(pool: &sqlx::SqlitePool, model: &str) -> Result<HashMap<i64, Vec<f32>>, String> {
+    let rows: Vec<(i64, String, String, Vec<u8>)> = sqlx::query_as(
+        "SELECT e.session_id, m.content, e.content_hash, e.vector
+         FROM message_embeddings e
+         JOIN messages m ON m.session_id = e.session_id AND m.position = e.position
+         WHERE e.model = ?",
+    )
+    .bind(model)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("埋め込み取得失敗: {}", e))?;
+
+    let mut sums: HashMap<i64, Vec<f32>> = HashMap::new();
+    for (session_id, content, hash, vector) in rows {
+        if db::content_hash(&content) != hash {
+            continue;
+        }
+        let vector = embeddings::from_blob(&vector);
+        let sum = sums.entry(session_id).or_insert_with(|| vec![0.0; vector.len()]);
+        // 埋め込みモデルの途中変更などで次元が違うものは混ぜない
+        if sum.len() == vector.len() {
+            sum.iter_mut().zip(&vector).for_each(|(s, v)| *s += v);
+        }
+    }
+    Ok(sums.into_iter().map(|(id, sum)| (id, embeddings::normalized(sum))).collect())
+}
+
+async fn load_features(app: &AppHandle) -> Result<HashMap<i64, Features>, String> {
+    let pool = db::pool(app).await?;
+    let sessions: Vec<(i64, String, String)> = sqlx::query_as("SELECT id, topic, updated_at FROM sessions")
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| format!("セッション一覧取得失敗: {}", e))?;
+    let mut features: HashMap<i64, Features> = sessions
+        .into_iter()
+        .map(|(id, topic, updated_at)| {
+            let topic_grams = topic_grams(&topic);
+            (id, Features { topic, updated_at, topic_grams, ..Features::default() })
+        })
+        .collect();
+
+    let tags: Vec<(i64, String)> = sqlx::query_as("SELECT session_id, tag FROM tags")
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| format!("タグ取得失敗: {}", e))?;
+    for (session_id, tag) in tags {
+        if let Some(f) = features.get_mut(&session_id) {
+            f.tags.insert(tag.to_lowercase());
+        }
+    }
+
+    let model = embeddings::current_settings(app).model;
+    for (session_id, centroid) in centroids(&pool, &model).await? {
+        if let Some(f) = features.get_mut(&session_id) {
+            f.centroid = Some(centroid);
+        }
+    }
+    Ok(features)
+}
+
+// 似たテーマの過去のセッションを近い順に返す（各セッションの最新の要約付き）
+#[command]
+pub async fn find_related_sessions(
+    app: AppHandle,
+    session_id: i64,
+    limit: Option<usize>,
+) -> Result<Vec<RelatedSession>, String> {
+    info!("find_related_sessions 呼び出し: session_id={}, limit={:?}", session_id, limit);
+    let mut features = load_features(&app).await?;
+    let target = features.remove(&session_id).ok_or_else(|| format!("セッションが見つかりません: id={}", session_id))?;
+
+    let mut related: Vec<RelatedSession> = features
+        .into_iter()
+        .filter_map(|(id, f)| {
+            let embedding = match (&target.centroid, &f.centroid) {
+                (Some(a), Some(b)) => embeddings::similarity(a, b),
+                _ => None,
+            };
+            let (score, by) = match embedding {
+                Some(score) => (score, RelatedBy::Embedding),
+                None => {
+                    let score = TAG_WEIGHT * jaccard(&target.tags, &f.tags)
+                        + (1.0 - TAG_WEIGHT) * jaccard(&target.topic_grams, &f.topic_grams);
+                    (score, RelatedBy::Keywords)
+                }
+            };
+            if score <= 0.0 {
+                return None;
+            }
+            let mut shared_tags: Vec<String> = target.tags.intersection(&f.tags).cloned().collect();
+            shared_tags.sort();
+            Some(RelatedSession {
+                session_id: id,
+                topic: f.topic,
+                updated_at: f.updated_at,
+                score,
+                by,
+                shared_tags,
+                summary: None,
+            })
+        })
+        .collect();
+    related.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| b.updated_at.cmp(&a.updated_at)));
+    related.truncate(limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT));
+
+    for item in &mut related {
+        item.summary = db::latest_summary(&app, item.session_id).await?.map(|s| s.summary);
+    }
+    Ok(related)
+}
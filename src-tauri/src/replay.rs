@@ -0,0 +1,116 @@
+// 決定的リプレイ
+// 生成ログに記録したプロンプト・シード・オプションで同じモデルを再実行し、出力の再現性を検証する
+use serde::Serialize;
+use tauri::{command, AppHandle};
+use tracing::info;
+
+use crate::{call_ollama_generate_with, db, encryption, generation::GenerationOptions, is_allowed_model, ERR_UNSUPPORTED_MODEL};
+
+// 差分表示用のプレビュー文字数
+const PREVIEW_CHARS: usize = 80;
+
+/// 1発言分のリプレイ結果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayEntry {
+    pub message_index: i64,
+    pub speaker: String,
+    pub seed: Option<i64>,
+    pub identical: bool,
+    /// 最初に食い違った文字位置（一致時は None）
+    pub diverged_at: Option<usize>,
+    /// 食い違い位置付近の元の出力 / 再実行の出力
+    pub original_preview: Option<String>,
+    pub replayed_preview: Option<String>,
+    pub error: Option<String>,
+}
+
+/// セッション全体のリプレイ結果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplayReport {
+    pub session_id: i64,
+    pub total: usize,
+    pub identical: usize,
+    pub diverged: usize,
+    pub failed: usize,
+    pub entries: Vec<ReplayEntry>,
+}
+
+/// 最初に異なる文字の位置（文字単位）
+fn first_divergence(a: &str, b: &str) -> Option<usize> {
+    let mut a_chars = a.chars();
+    let mut b_chars = b.chars();
+    let mut index = 0;
+    loop {
+        match (a_chars.next(), b_chars.next()) {
+            (None, None) => return None,
+            (x, y) if x != y => return Some(index),
+            _ => index += 1,
+        }
+    }
+}
+
+/// 指定位置からのプレビュー
+fn preview_from(text: &str, start: usize) -> String {
+    text.chars().skip(start).take(PREVIEW_CHARS).collect()
+}
+
+// 記録済みの生成を同じシード・オプションで再実行し、出力の一致を検証
+#[command]
+pub async fn replay_session(app: AppHandle, session_id: i64) -> Result<ReplayReport, String> {
+    info!("replay_session 呼び出し: session_id={}", session_id);
+    let pool = db::pool(&app).await?;
+    let rows: Vec<(i64, String, String, String, String, String)> = sqlx::query_as(
+        "SELECT message_index, speaker, model, prompt, options, output FROM generation_log
+         WHERE session_id = ? ORDER BY message_index, id",
+    )
+    .bind(session_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("生成ログ取得失敗: {}", e))?;
+    if rows.is_empty() {
+        return Err("このセッションには再現用の生成ログがありません".into());
+    }
+
+    let keys = encryption::keys(&app);
+    let mut entries = Vec::with_capacity(rows.len());
+    for (message_index, speaker, model, prompt, options_json, original) in rows {
+        let prompt = keys.open(&prompt)?;
+        let original = keys.open(&original)?;
+        let options: GenerationOptions = serde_json::from_str(&options_json).unwrap_or_default();
+        let mut entry = ReplayEntry {
+            message_index,
+            speaker,
+            seed: options.seed,
+            identical: false,
+            diverged_at: None,
+            original_preview: None,
+            replayed_preview: None,
+            error: None,
+        };
+        if !is_allowed_model(&app, &model) {
+            entry.error = Some(ERR_UNSUPPORTED_MODEL.to_string());
+            entries.push(entry);
+            continue;
+        }
+        match call_ollama_generate_with(&app, &model, &prompt, &options).await {
+            Ok(replayed) => match first_divergence(&original, &replayed) {
+                None => entry.identical = true,
+                Some(at) => {
+                    entry.diverged_at = Some(at);
+                    entry.original_preview = Some(preview_from(&original, at));
+                    entry.replayed_preview = Some(preview_from(&replayed, at));
+                }
+            },
+            Err(e) => entry.error = Some(e.to_string()),
+        }
+        entries.push(entry);
+    }
+
+    let identical = entries.iter().filter(|e| e.identical).count();
+    let failed = entries.iter().filter(|e| e.error.is_some()).count();
+    let total = entries.len();
+    info!("replay_session 完了: 一致={}, 不一致={}, 失敗={}", identical, total - identical - failed, failed);
+    Ok(ReplayReport { session_id, total, identical, diverged: total - identical - failed, failed, entries })
+}
@@ -0,0 +1,81 @@
+// 生成リクエストの個別キャンセル
+// フロントエンドが付けたリクエストIDごとに CancellationToken を登録し、cancel_request で1件だけ中断できるようにする
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use tauri::{command, AppHandle, Manager};
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+
+use crate::{app_state::AppState, gen_error::GenError};
+
+/// 実行中リクエストの登録簿（AppState が持つ）
+#[derive(Default)]
+pub struct RequestRegistry {
+    /// リクエストID -> (登録番号, トークン)
+    tokens: Mutex<HashMap<String, (u64, CancellationToken)>>,
+    next_serial: AtomicU64,
+}
+
+impl RequestRegistry {
+    fn register(&self, request_id: &str) -> (u64, CancellationToken) {
+        let serial = self.next_serial.fetch_add(1, Ordering::Relaxed);
+        let token = CancellationToken::new();
+        let previous = self
+            .tokens
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(request_id.to_string(), (serial, token.clone()));
+        // 同じIDで再送された場合は古い方を中断する
+        if let Some((_, previous)) = previous {
+            previous.cancel();
+        }
+        (serial, token)
+    }
+
+    fn finish(&self, request_id: &str, serial: u64) {
+        let mut tokens = self.tokens.lock().unwrap_or_else(|e| e.into_inner());
+        // 同じIDで登録し直されていたら消さない
+        if tokens.get(request_id).is_some_and(|(s, _)| *s == serial) {
+            tokens.remove(request_id);
+        }
+    }
+}
+
+/// リクエストIDがあれば登録し、キャンセルされたら処理を打ち切る（ID なしならそのまま実行）
+/// キャンセル時は GenError::Cancelled を呼び出し側のエラー型に変換して返す
+pub async fn run_cancellable<T, E, F>(app: &AppHandle, request_id: Option<&str>, task: F) -> Result<T, E>
+where
+    E: From<GenError>,
+    F: Future<Output = Result<T, E>>,
+{
+    let Some(request_id) = request_id else {
+        return task.await;
+    };
+    let registry = &app.state::<AppState>().inner().requests;
+    let (serial, token) = registry.register(request_id);
+    let result = tokio::select! {
+        result = task => result,
+        _ = token.cancelled() => {
+            info!("リクエストをキャンセル: {}", request_id);
+            Err(GenError::Cancelled.into())
+        }
+    };
+    registry.finish(request_id, serial);
+    result
+}
+
+// 指定したリクエストだけを中断（実行中のものが見つかれば true）
+#[command]
+pub fn cancel_request(app: AppHandle, request_id: String) -> bool {
+    let token = app.state::<AppState>().requests.tokens.lock().unwrap_or_else(|e| e.into_inner()).remove(&request_id);
+    match token {
+        Some((_, token)) => {
+            token.cancel();
+            true
+        }
+        None => false,
+    }
+}
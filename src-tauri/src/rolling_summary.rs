@@ -0,0 +1,151 @@
+// コンテキスト超過時の自動要約（ローリング要約）
+// 発言プロンプトに会話履歴が収まらない場合、古い発言を要約に畳み込んで履歴の先頭に置く
+// 履歴をそのまま切り捨てると序盤の論点が黙って失われるため、フロントエンドに意識させずバックエンドで処理する
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use tauri::AppHandle;
+use tracing::{info, warn};
+
+use crate::{call_ollama_generate, db, generation::GenerationOptions, prompts, tokens};
+
+// 応答の生成用に残すトークン数（num_predict 未指定時）
+const RESPONSE_RESERVE_TOKENS: usize = 512;
+// 畳み込み後に直近の発言へ割り当てる割合（残りを要約に使い、数ターンは再要約せずに済むようにする）
+const RECENT_SHARE: f64 = 0.5;
+
+/// 畳み込み済みの要約（同じ会話の次の発言で差分だけ要約するために保持する）
+struct Rolling {
+    /// 要約に反映済みの行数
+    folded: usize,
+    /// 反映済みの行のハッシュ（履歴が編集・削除されていないかの確認用）
+    prefix_hash: String,
+    summary: String,
+}
+
+fn cache() -> &'static Mutex<HashMap<String, Rolling>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, Rolling>>> = OnceLock::new();
+    CACHE.get_or_init(Default::default)
+}
+
+/// 履歴の行（"発言者: 内容"）から発言者名を出現順に集める
+fn speakers(lines: &[&str]) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for line in lines {
+        if let Some((name, _)) = line.split_once(':') {
+            let name = name.trim();
+            if !name.is_empty() && !names.iter().any(|n| n == name) {
+                names.push(name.to_string());
+            }
+        }
+    }
+    names
+}
+
+/// 会話履歴がプロンプトに収まるよう必要なら古い発言を要約に畳み込み、プロンプトに渡す履歴を返す
+/// build は履歴からプロンプト全体を組み立てる関数（指示文などの固定部分のトークン数を測るのに使う）
+/// 要約に失敗した場合は元の履歴を返す（プロンプト側で直近の発言だけに切り詰められる）
+#[allow(clippy::too_many_arguments)] // 発言生成の各経路がそれぞれ持っている値をそのまま受ける
+pub async fn fit_history(
+    app: &AppHandle,
+    model: &str,
+    session_id: Option<i64>,
+    discussion_topic: &str,
+    conversation_history: &str,
+    options: &GenerationOptions,
+    style: &prompts::PromptStyle,
+    build: impl Fn(&str) -> String,
+) -> String {
+    let window = options.num_ctx.map(|n| n as usize).unwrap_or_else(|| tokens::context_window(model));
+    let reserve = options.num_predict.filter(|n| *n > 0).map(|n| n as usize).unwrap_or(RESPONSE_RESERVE_TOKENS);
+    let overhead = tokens::count(&build(""));
+    let budget = window.saturating_sub(overhead + reserve).min(prompts::HISTORY_TOKEN_BUDGET);
+    if budget == 0 || tokens::count(conversation_history) <= budget {
+        return conversation_history.to_string();
+    }
+
+    let lines: Vec<&str> = conversation_history.lines().filter(|l| !l.trim().is_empty()).collect();
+    // 直近の発言を予算の RECENT_SHARE まで残し、それより前を畳み込む
+    let recent_budget = (budget as f64 * RECENT_SHARE) as usize;
+    let mut used = 0;
+    let mut split = lines.len();
+    for (i, line) in lines.iter().enumerate().rev() {
+        let cost = tokens::count(line) + 1;
+        if used + cost > recent_budget && split < lines.len() {
+            break;
+        }
+        used += cost;
+        split = i;
+    }
+    if split == 0 {
+        return conversation_history.to_string();
+    }
+
+    let key = match session_id {
+        Some(id) => format!("session:{}", id),
+        None => format!("topic:{}", db::content_hash(discussion_topic)),
+    };
+    match fold(app, model, &key, discussion_topic, &lines, split, style).await {
+        Ok(summary) => {
+            info!("履歴がコンテキストを超えるため要約に畳み込み: {}行中{}行", lines.len(), split);
+            format!("【これまでの議論の要約】\n{}\n\n【直近の発言】\n{}", summary.trim(), lines[split..].join("\n"))
+        }
+        Err(e) => {
+            warn!("履歴の要約に失敗（直近の発言のみ使用）: {}", e);
+            conversation_history.to_string()
+        }
+    }
+}
+
+/// lines[..split] を要約に畳み込む（前回の要約があれば差分だけ反映する）
+async fn fold(
+    app: &AppHandle,
+    model: &str,
+    key: &str,
+    discussion_topic: &str,
+    lines: &[&str],
+    split: usize,
+    style: &prompts::PromptStyle,
+) -> Result<String, String> {
+    let previous = {
+        let cache = cache().lock().unwrap_or_else(|e| e.into_inner());
+        cache.get(key).and_then(|r| {
+            (r.folded <= split && r.prefix_hash == db::content_hash(&lines[..r.folded].join("\n")))
+                .then(|| (r.folded, r.summary.clone()))
+        })
+    };
+    let (mut folded, mut summary) = previous.unwrap_or((0, String::new()));
+    if folded == split {
+        return Ok(summary);
+    }
+
+    let participants = speakers(lines);
+    // 要約対象が一度に入りきらない場合に備え、予算ごとに区切って順に畳み込む
+    let chunk_budget = prompts::HISTORY_TOKEN_BUDGET;
+    while folded < split {
+        let mut end = folded;
+        let mut used = 0;
+        while end < split {
+            let cost = tokens::count(lines[end]) + 1;
+            if used + cost > chunk_budget && end > folded {
+                break;
+            }
+            used += cost;
+            end += 1;
+        }
+        let chunk = lines[folded..end].join("\n");
+        let prompt = if summary.is_empty() {
+            prompts::build_discussion_summary_prompt(discussion_topic, &chunk, &participants, style)
+        } else {
+            prompts::build_incremental_summary_prompt(discussion_topic, &summary, &chunk, &participants, style)
+        };
+        summary = call_ollama_generate(app, model, &prompt).await?;
+        folded = end;
+    }
+
+    cache().lock().unwrap_or_else(|e| e.into_inner()).insert(
+        key.to_string(),
+        Rolling { folded, prefix_hash: db::content_hash(&lines[..folded].join("\n")), summary: summary.clone() },
+    );
+    Ok(summary)
+}
@@ -0,0 +1,134 @@
+// ラウンドの持ち時間
+// 自動進行のラウンドごとの制限時間をバックエンドのタイマーで管理し、開始・残り時間の警告・終了を通知する
+// （フロントエンドで計時するとウィンドウがバックグラウンドのときにずれるため）
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::{async_runtime::JoinHandle, AppHandle, Emitter};
+use tokio::time::Instant;
+
+pub const EVENT_ROUND_STARTED: &str = "round://started";
+pub const EVENT_ROUND_TIME_WARNING: &str = "round://time-warning";
+pub const EVENT_ROUND_ENDED: &str = "round://ended";
+
+// roundTimeLimitSecs に指定できる範囲
+pub const MIN_TIME_LIMIT_SECS: u64 = 10;
+pub const MAX_TIME_LIMIT_SECS: u64 = 3600;
+// 残り時間の警告（持ち時間の1/5、5〜60秒の範囲）
+const WARNING_DIVISOR: u32 = 5;
+const MIN_WARNING: Duration = Duration::from_secs(5);
+const MAX_WARNING: Duration = Duration::from_secs(60);
+
+/// ラウンドの終わり方
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RoundEndReason {
+    /// 全員が発言した
+    Completed,
+    /// 持ち時間を使い切った（要約を自動実行する）
+    TimeUp,
+    /// stop_discussion で停止した
+    Stopped,
+}
+
+/// round://started / round://time-warning / round://ended のペイロード
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RoundEvent {
+    session_id: i64,
+    round: u32,
+    total_rounds: u32,
+    time_limit_secs: Option<u64>,
+    /// 残り秒数（持ち時間がなければ None）
+    remaining_secs: Option<u64>,
+    /// round://ended のときだけ
+    reason: Option<RoundEndReason>,
+}
+
+/// 進行中ラウンドの持ち時間（破棄すると警告のタイマーも止まる）
+pub struct RoundTimer {
+    pub round: u32,
+    deadline: Instant,
+    warning: JoinHandle<()>,
+}
+
+impl RoundTimer {
+    /// 残り時間 remaining で計時を始め、警告の時刻になったら round://time-warning を通知する
+    pub fn start(app: &AppHandle, session_id: i64, round: u32, total_rounds: u32, limit: Duration, remaining: Duration) -> Self {
+        let deadline = Instant::now() + remaining;
+        let warn_before = (limit / WARNING_DIVISOR).clamp(MIN_WARNING, MAX_WARNING);
+        let app = app.clone();
+        let warning = tauri::async_runtime::spawn(async move {
+            if remaining <= warn_before {
+                return;
+            }
+            tokio::time::sleep_until(deadline - warn_before).await;
+            let event = RoundEvent {
+                session_id,
+                round,
+                total_rounds,
+                time_limit_secs: Some(limit.as_secs()),
+                remaining_secs: Some(warn_before.as_secs()),
+                reason: None,
+            };
+            let _ = app.emit(EVENT_ROUND_TIME_WARNING, event);
+        });
+        Self { round, deadline, warning }
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.deadline.saturating_duration_since(Instant::now())
+    }
+
+    pub fn expired(&self) -> bool {
+        Instant::now() >= self.deadline
+    }
+
+    /// 持ち時間の終わりまで待つ（タイマーがなければ終わらない）
+    pub async fn wait(timer: Option<&RoundTimer>) {
+        match timer {
+            Some(timer) => tokio::time::sleep_until(timer.deadline).await,
+            None => std::future::pending().await,
+        }
+    }
+}
+
+impl Drop for RoundTimer {
+    fn drop(&mut self) {
+        self.warning.abort();
+    }
+}
+
+/// round://started を通知
+pub fn emit_started(app: &AppHandle, session_id: i64, round: u32, total_rounds: u32, limit: Option<Duration>) {
+    let event = RoundEvent {
+        session_id,
+        round,
+        total_rounds,
+        time_limit_secs: limit.map(|l| l.as_secs()),
+        remaining_secs: limit.map(|l| l.as_secs()),
+        reason: None,
+    };
+    let _ = app.emit(EVENT_ROUND_STARTED, event);
+}
+
+/// round://ended を通知
+pub fn emit_ended(
+    app: &AppHandle,
+    session_id: i64,
+    round: u32,
+    total_rounds: u32,
+    timer: Option<&RoundTimer>,
+    limit: Option<Duration>,
+    reason: RoundEndReason,
+) {
+    let event = RoundEvent {
+        session_id,
+        round,
+        total_rounds,
+        time_limit_secs: limit.map(|l| l.as_secs()),
+        remaining_secs: timer.map(|t| t.remaining().as_secs()),
+        reason: Some(reason),
+    };
+    let _ = app.emit(EVENT_ROUND_ENDED, event);
+}
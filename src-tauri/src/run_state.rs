@@ -0,0 +1,226 @@
+// 自動進行の実行状態
+// 残りラウンド・現在のフェーズ・次の話者を engine_runs に保存し、アプリ再起動後に続きから再開できるようにする
+use dewai_core::engine::TurnPosition;
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Emitter};
+use tracing::{info, warn};
+
+use crate::{db, discussion_engine, discussion_engine::RoundConfig, participant_memory};
+
+pub const EVENT_RUN_FINISHED: &str = "run://finished";
+
+/// 自動進行のフェーズ
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RunPhase {
+    /// AI参加者が発言中
+    Speaking,
+    /// 全ラウンド終了後の要約中
+    Summarizing,
+}
+
+impl RunPhase {
+    fn as_str(self) -> &'static str {
+        match self {
+            RunPhase::Speaking => "speaking",
+            RunPhase::Summarizing => "summarizing",
+        }
+    }
+
+    fn parse(s: &str) -> Self {
+        match s {
+            "summarizing" => RunPhase::Summarizing,
+            _ => RunPhase::Speaking,
+        }
+    }
+}
+
+/// 1セッション分の実行状態
+#[derive(Debug, Clone)]
+pub struct RunState {
+    pub session_id: i64,
+    /// 全ラウンド数・現在ラウンド・次に話す位置
+    pub position: TurnPosition,
+    pub phase: RunPhase,
+    pub config: RoundConfig,
+}
+
+impl RunState {
+    pub fn new(session_id: i64, total_rounds: u32, config: RoundConfig) -> Self {
+        Self { session_id, position: TurnPosition::new(total_rounds), phase: RunPhase::Speaking, config }
+    }
+}
+
+/// 再開待ちの実行（フロントエンドへの提示用）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingRun {
+    pub session_id: i64,
+    pub topic: String,
+    pub total_rounds: u32,
+    pub current_round: u32,
+    /// 次に話す参加者名（要約フェーズなら None）
+    pub next_speaker: Option<String>,
+    pub phase: RunPhase,
+    pub updated_at: String,
+}
+
+/// run://finished のペイロード
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RunFinishedEvent {
+    session_id: i64,
+    generated: usize,
+    /// stop_discussion で途中終了したか
+    stopped: bool,
+    error: Option<String>,
+}
+
+/// 実行状態を保存（進行のたびに上書き）
+pub async fn save(app: &AppHandle, state: &RunState) -> Result<(), String> {
+    let pool = db::pool(app).await?;
+    save_in(&pool, state).await
+}
+
+/// save のトランザクション内版（発言の追記と同じトランザクションで進み具合を保存する）
+pub async fn save_in<'e>(executor: impl sqlx::SqliteExecutor<'e>, state: &RunState) -> Result<(), String> {
+    let config = serde_json::to_string(&state.config).map_err(|e| e.to_string())?;
+    sqlx::query(
+        "INSERT INTO engine_runs (session_id, total_rounds, current_round, next_speaker, phase, config, updated_at)
+         VALUES (?, ?, ?, ?, ?, ?, ?)
+         ON CONFLICT(session_id) DO UPDATE SET
+           total_rounds = excluded.total_rounds, current_round = excluded.current_round,
+           next_speaker = excluded.next_speaker, phase = excluded.phase,
+           config = excluded.config, updated_at = excluded.updated_at",
+    )
+    .bind(state.session_id)
+    .bind(state.position.total_rounds as i64)
+    .bind(state.position.current_round as i64)
+    .bind(state.position.next_speaker as i64)
+    .bind(state.phase.as_str())
+    .bind(config)
+    .bind(db::now_string())
+    .execute(executor)
+    .await
+    .map_err(|e| format!("実行状態保存失敗: {}", e))?;
+    Ok(())
+}
+
+/// 完了した実行の状態を削除
+pub async fn clear(app: &AppHandle, session_id: i64) -> Result<(), String> {
+    let pool = db::pool(app).await?;
+    sqlx::query("DELETE FROM engine_runs WHERE session_id = ?")
+        .bind(session_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| format!("実行状態削除失敗: {}", e))?;
+    Ok(())
+}
+
+/// 保存済みの実行状態をすべて読み込む（更新日時の古い順）
+async fn load_all(app: &AppHandle) -> Result<Vec<(RunState, String)>, String> {
+    let pool = db::pool(app).await?;
+    let rows: Vec<(i64, i64, i64, i64, String, String, String)> = sqlx::query_as(
+        "SELECT session_id, total_rounds, current_round, next_speaker, phase, config, updated_at
+         FROM engine_runs ORDER BY updated_at",
+    )
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("実行状態取得失敗: {}", e))?;
+    Ok(rows
+        .into_iter()
+        .map(|(session_id, total_rounds, current_round, next_speaker, phase, config, updated_at)| {
+            let state = RunState {
+                session_id,
+                position: TurnPosition {
+                    total_rounds: total_rounds.max(0) as u32,
+                    current_round: current_round.max(1) as u32,
+                    next_speaker: next_speaker.max(0) as usize,
+                },
+                phase: RunPhase::parse(&phase),
+                config: serde_json::from_str(&config).unwrap_or_default(),
+            };
+            (state, updated_at)
+        })
+        .collect())
+}
+
+/// 自動進行をバックグラウンドで開始し、各完了時に run://finished を通知する
+/// （ローカルLLMの負荷を考慮し、1件ずつ順番に実行する）
+pub fn spawn_runs(app: &AppHandle, states: Vec<RunState>) {
+    let runner = app.clone();
+    tauri::async_runtime::spawn(async move {
+        for state in states {
+            let session_id = state.session_id;
+            let position = state.position;
+            info!("自動進行を開始: session_id={}, ラウンド{}/{}", session_id, position.current_round, position.total_rounds);
+            let (generated, stopped, error) = match discussion_engine::drive_run(&runner, state).await {
+                Ok(stats) => {
+                    participant_memory::schedule(&runner, session_id);
+                    (stats.generated, stats.stopped, None)
+                }
+                Err(e) => {
+                    warn!("自動進行失敗 (session_id={}): {}", session_id, e);
+                    (0, false, Some(e))
+                }
+            };
+            let _ = runner.emit(EVENT_RUN_FINISHED, RunFinishedEvent { session_id, generated, stopped, error });
+        }
+    });
+}
+
+// 中断された自動進行の一覧（起動時に再開を提案するため）
+#[command]
+pub async fn list_pending_runs(app: AppHandle) -> Result<Vec<PendingRun>, String> {
+    let mut pending = Vec::new();
+    for (state, updated_at) in load_all(&app).await? {
+        if discussion_engine::is_running(&app, state.session_id) {
+            continue;
+        }
+        let Ok(session) = db::load_session(&app, state.session_id).await else {
+            continue;
+        };
+        let next_speaker = match state.phase {
+            RunPhase::Speaking => discussion_engine::speaking_order(&session.participants.ai_data, &state)
+                .get(state.position.next_speaker)
+                .map(|p| p.name.clone()),
+            RunPhase::Summarizing => None,
+        };
+        pending.push(PendingRun {
+            session_id: state.session_id,
+            topic: session.topic,
+            total_rounds: state.position.total_rounds,
+            current_round: state.position.current_round,
+            next_speaker,
+            phase: state.phase,
+            updated_at,
+        });
+    }
+    Ok(pending)
+}
+
+// 中断された自動進行を続きから再開（未指定ならすべて）。再開を受け付けたセッションIDを返す
+#[command]
+pub async fn resume_pending_runs(app: AppHandle, session_ids: Option<Vec<i64>>) -> Result<Vec<i64>, String> {
+    info!("resume_pending_runs 呼び出し: {:?}", session_ids);
+    let states: Vec<RunState> = load_all(&app)
+        .await?
+        .into_iter()
+        .map(|(state, _)| state)
+        .filter(|s| session_ids.as_ref().is_none_or(|ids| ids.contains(&s.session_id)))
+        .filter(|s| !discussion_engine::is_running(&app, s.session_id))
+        .collect();
+    let resumed: Vec<i64> = states.iter().map(|s| s.session_id).collect();
+
+    spawn_runs(&app, states);
+    Ok(resumed)
+}
+
+// 中断された自動進行を破棄
+#[command]
+pub async fn discard_pending_run(app: AppHandle, session_id: i64) -> Result<(), String> {
+    if discussion_engine::is_running(&app, session_id) {
+        return Err("実行中のため破棄できません".into());
+    }
+    clear(&app, session_id).await
+}
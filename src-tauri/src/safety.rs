@@ -0,0 +1,52 @@
+// コンテンツ安全ポリシー
+// 設定のルール（カテゴリ・重大度・対応）をすべての生成呼び出しの入力と出力に適用し、適用結果を監査ログに残す
+// ポリシーの型と適用の処理は dewai-core にあり、ここでは実行中のルールを持って監査ログに記録する
+use std::sync::{Arc, OnceLock, RwLock};
+
+use serde_json::json;
+use tracing::info;
+
+pub use dewai_core::safety::{Direction, SafetyPolicy};
+use dewai_core::safety::SafetyRules;
+
+use crate::audit;
+
+fn slot() -> &'static RwLock<Arc<SafetyRules>> {
+    static RULES: OnceLock<RwLock<Arc<SafetyRules>>> = OnceLock::new();
+    RULES.get_or_init(|| RwLock::new(Arc::new(SafetyRules::default())))
+}
+
+/// ポリシーを反映（起動時と設定保存時）
+pub fn set_policy(policy: &SafetyPolicy) {
+    let compiled = SafetyRules::compile(policy);
+    info!("安全ポリシー反映: 有効ルール{}件", compiled.len());
+    *slot().write().unwrap_or_else(|e| e.into_inner()) = Arc::new(compiled);
+}
+
+/// テキストにポリシーを適用する
+/// block に一致すればエラー、redact は伏せ字にしたテキストを返す。一致はすべて監査ログに記録する
+pub fn enforce(direction: Direction, model: &str, text: &str) -> Result<String, String> {
+    let rules = slot().read().unwrap_or_else(|e| e.into_inner()).clone();
+    rules.enforce(text, |rule, matches| {
+        info!(
+            "安全ポリシー適用: {} ({}, 重大度{}, {}, {}件)",
+            rule.category,
+            rule.action.as_str(),
+            rule.severity,
+            direction.as_str(),
+            matches
+        );
+        // 一致した文言そのものは記録しない
+        audit::record(
+            "safety",
+            rule.action.as_str(),
+            json!({
+                "direction": direction.as_str(),
+                "category": rule.category,
+                "severity": rule.severity,
+                "matches": matches,
+                "model": model,
+            }),
+        );
+    })
+}
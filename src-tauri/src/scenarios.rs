@@ -0,0 +1,229 @@
+// 議論シナリオのテンプレート
+// テーマの雛形・アジェンダ・参加者・進行形式・ラウンド数・モデル設定を保存し、同じ演習をワンクリックで開始できるようにする
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle};
+use tracing::info;
+
+use crate::{
+    db,
+    db::{AiParticipant, ParticipantsData},
+    discussion_engine::{RoundConfig, TurnStrategy},
+    generation::GenerationOptions,
+    is_allowed_model,
+    run_state::{self, RunState},
+    ERR_UNSUPPORTED_MODEL,
+};
+
+// シナリオあたりの上限（プロンプト長・実行時間を考慮）
+const MAX_PANEL: usize = 10;
+const MAX_ROUNDS: u32 = 20;
+
+/// シナリオの内容（scenarios.definition に JSON で保存）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScenarioDefinition {
+    pub name: String,
+    /// テーマの雛形。`{キー}` は開始時の topic_overrides で置き換える
+    pub topic_pattern: String,
+    /// 議題の進行順（開始時にセッションの分析データとして保存）
+    #[serde(default)]
+    pub agenda: Vec<String>,
+    pub panel: Vec<AiParticipant>,
+    /// ユーザーが参加する形式か（参加しない場合は開始と同時に自動進行する）
+    #[serde(default)]
+    pub user_participates: bool,
+    #[serde(default)]
+    pub turn_strategy: TurnStrategy,
+    pub rounds: u32,
+    pub model: String,
+    #[serde(default)]
+    pub generation: GenerationOptions,
+}
+
+impl ScenarioDefinition {
+    fn validate(&self, app: &AppHandle) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("シナリオ名が指定されていません".into());
+        }
+        if self.topic_pattern.trim().is_empty() {
+            return Err("テーマが指定されていません".into());
+        }
+        if self.panel.is_empty() || self.panel.len() > MAX_PANEL {
+            return Err(format!("参加者は1〜{}人で指定してください", MAX_PANEL));
+        }
+        if self.panel.iter().any(|p| p.name.trim().is_empty()) {
+            return Err("名前が空の参加者がいます".into());
+        }
+        if self.rounds > MAX_ROUNDS {
+            return Err(format!("ラウンド数は{}以下で指定してください", MAX_ROUNDS));
+        }
+        if !is_allowed_model(app, &self.model) {
+            return Err(ERR_UNSUPPORTED_MODEL.to_string());
+        }
+        Ok(())
+    }
+}
+
+/// 保存済みシナリオ
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Scenario {
+    pub id: i64,
+    #[serde(flatten)]
+    pub definition: ScenarioDefinition,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// シナリオから開始したセッション
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartedSession {
+    pub session_id: i64,
+    pub topic: String,
+    /// バックエンドで自動進行を開始したか
+    pub auto_run: bool,
+}
+
+/// テーマの雛形の `{キー}` を置き換え、未解決のキーがあればエラー
+fn render_topic(pattern: &str, overrides: &HashMap<String, String>) -> Result<String, String> {
+    let mut topic = pattern.to_string();
+    for (key, value) in overrides {
+        topic = topic.replace(&format!("{{{}}}", key), value.trim());
+    }
+    let missing: Vec<&str> = topic
+        .split('{')
+        .skip(1)
+        .filter_map(|rest| rest.split_once('}').map(|(key, _)| key))
+        .filter(|key| !key.is_empty())
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!("テーマの置き換え値が不足しています: {}", missing.join(", ")));
+    }
+    Ok(topic.trim().to_string())
+}
+
+async fn load(app: &AppHandle, scenario_id: i64) -> Result<Scenario, String> {
+    let pool = db::pool(app).await?;
+    let row: Option<(String, String, String)> =
+        sqlx::query_as("SELECT definition, created_at, updated_at FROM scenarios WHERE id = ?")
+            .bind(scenario_id)
+            .fetch_optional(&pool)
+            .await
+            .map_err(|e| format!("シナリオ取得失敗: {}", e))?;
+    let (definition, created_at, updated_at) =
+        row.ok_or_else(|| format!("シナリオが見つかりません: id={}", scenario_id))?;
+    let definition = serde_json::from_str(&definition).map_err(|e| format!("シナリオの解析失敗: {}", e))?;
+    Ok(Scenario { id: scenario_id, definition, created_at, updated_at })
+}
+
+// シナリオを作成
+#[command]
+pub async fn create_scenario(app: AppHandle, definition: ScenarioDefinition) -> Result<Scenario, String> {
+    info!("create_scenario 呼び出し: name='{}'", definition.name);
+    definition.validate(&app)?;
+    let pool = db::pool(&app).await?;
+    let json = serde_json::to_string(&definition).map_err(|e| format!("シナリオのシリアライズ失敗: {}", e))?;
+    let now = db::now_string();
+    let id = sqlx::query("INSERT INTO scenarios (name, definition, created_at, updated_at) VALUES (?, ?, ?, ?)")
+        .bind(definition.name.trim())
+        .bind(json)
+        .bind(&now)
+        .bind(&now)
+        .execute(&pool)
+        .await
+        .map_err(|e| format!("シナリオ保存失敗: {}", e))?
+        .last_insert_rowid();
+    Ok(Scenario { id, definition, created_at: now.clone(), updated_at: now })
+}
+
+// シナリオ一覧（更新の新しい順）
+#[command]
+pub async fn list_scenarios(app: AppHandle) -> Result<Vec<Scenario>, String> {
+    let pool = db::pool(&app).await?;
+    let rows: Vec<(i64, String, String, String)> =
+        sqlx::query_as("SELECT id, definition, created_at, updated_at FROM scenarios ORDER BY updated_at DESC, id DESC")
+            .fetch_all(&pool)
+            .await
+            .map_err(|e| format!("シナリオ一覧取得失敗: {}", e))?;
+    Ok(rows
+        .into_iter()
+        .filter_map(|(id, definition, created_at, updated_at)| {
+            let definition = serde_json::from_str(&definition).ok()?;
+            Some(Scenario { id, definition, created_at, updated_at })
+        })
+        .collect())
+}
+
+// シナリオを更新（全項目を上書き）
+#[command]
+pub async fn update_scenario(
+    app: AppHandle,
+    scenario_id: i64,
+    definition: ScenarioDefinition,
+) -> Result<Scenario, String> {
+    definition.validate(&app)?;
+    let pool = db::pool(&app).await?;
+    let json = serde_json::to_string(&definition).map_err(|e| format!("シナリオのシリアライズ失敗: {}", e))?;
+    let updated = sqlx::query("UPDATE scenarios SET name = ?, definition = ?, updated_at = ? WHERE id = ?")
+        .bind(definition.name.trim())
+        .bind(json)
+        .bind(db::now_string())
+        .bind(scenario_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| format!("シナリオ更新失敗: {}", e))?;
+    if updated.rows_affected() == 0 {
+        return Err(format!("シナリオが見つかりません: id={}", scenario_id));
+    }
+    load(&app, scenario_id).await
+}
+
+// シナリオを削除
+#[command]
+pub async fn delete_scenario(app: AppHandle, scenario_id: i64) -> Result<(), String> {
+    let pool = db::pool(&app).await?;
+    sqlx::query("DELETE FROM scenarios WHERE id = ?")
+        .bind(scenario_id)
+        .execute(&pool)
+        .await
+        .map_err(|e| format!("シナリオ削除失敗: {}", e))?;
+    Ok(())
+}
+
+// シナリオからセッションを開始（ユーザー不参加の形式なら自動進行も開始）
+#[command]
+pub async fn start_session_from_scenario(
+    app: AppHandle,
+    scenario_id: i64,
+    topic_overrides: Option<HashMap<String, String>>,
+) -> Result<StartedSession, String> {
+    info!("start_session_from_scenario 呼び出し: scenario_id={}", scenario_id);
+    let scenario = load(&app, scenario_id).await?;
+    let def = scenario.definition;
+    let topic = render_topic(&def.topic_pattern, &topic_overrides.unwrap_or_default())?;
+
+    let participants = ParticipantsData { user_participates: def.user_participates, ai_data: def.panel.clone() };
+    let session_id = db::create_session(&app, &topic, &participants, &def.model).await?;
+    if !def.agenda.is_empty() {
+        let payload = serde_json::json!({ "agenda": def.agenda, "scenarioId": scenario_id });
+        db::save_analysis(&app, session_id, "agenda", &payload.to_string()).await?;
+    }
+
+    let auto_run = !def.user_participates && def.rounds > 0;
+    if auto_run {
+        let config = RoundConfig {
+            options: def.generation.clone(),
+            turn_strategy: def.turn_strategy,
+            summarize_at_end: true,
+            ..Default::default()
+        };
+        let state = RunState::new(session_id, def.rounds, config);
+        // 開始直後に終了しても再開できるよう、先に実行状態を保存しておく
+        run_state::save(&app, &state).await?;
+        run_state::spawn_runs(&app, vec![state]);
+    }
+    Ok(StartedSession { session_id, topic, auto_run })
+}
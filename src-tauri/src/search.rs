@@ -0,0 +1,222 @@
+// セッションの全文検索
+// テーマ（sessions_fts）と発言本文（messages_fts）を FTS5 の trigram 索引で検索し、セッション単位にまとめて返す
+// trigram は3文字未満の語を索引で引けないため、短い語を含む検索は LIKE で代替する
+use std::collections::HashMap;
+
+use serde::Serialize;
+use sqlx::SqlitePool;
+use tauri::{command, AppHandle};
+use tracing::info;
+
+use crate::db;
+
+// 強調表示の印（フロントエンドは本文をエスケープした上でこの印だけを <mark> として扱う）
+const MARK_START: &str = "<mark>";
+const MARK_END: &str = "</mark>";
+// 検索結果に含めるセッション数の上限
+const MAX_LIMIT: i64 = 100;
+// 1セッションあたりに返す抜粋の数
+const SNIPPETS_PER_SESSION: usize = 3;
+// 発言の一致を集計する件数の上限（ランキング用）
+const MAX_MESSAGE_HITS: i64 = 1000;
+// LIKE 検索時の抜粋で一致箇所の前後に残す文字数
+const SNIPPET_CONTEXT_CHARS: usize = 24;
+// trigram 索引で引ける最短の語長
+const MIN_FTS_TERM_CHARS: usize = 3;
+
+/// 発言の抜粋
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageSnippet {
+    /// セッション内の位置（messages.position）
+    pub position: i64,
+    pub speaker: String,
+    /// 一致箇所を <mark>〜</mark> で囲んだ抜粋
+    pub snippet: String,
+}
+
+/// 検索に一致したセッション
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSearchHit {
+    pub session_id: i64,
+    pub topic: String,
+    /// テーマが一致した場合の強調表示付きテーマ
+    pub topic_highlight: Option<String>,
+    /// 一致した発言の数
+    pub message_hits: usize,
+    pub snippets: Vec<MessageSnippet>,
+    pub updated_at: String,
+}
+
+/// 集計途中のセッション
+#[derive(Default)]
+struct Acc {
+    topic_highlight: Option<String>,
+    /// 最良の bm25（小さいほど良い。LIKE 検索では出現順）
+    best_score: f64,
+    message_hits: usize,
+    snippets: Vec<MessageSnippet>,
+}
+
+/// 検索語に分割（空白区切り、前後の引用符は除去）
+fn split_terms(query: &str) -> Vec<String> {
+    query
+        .split_whitespace()
+        .map(|t| t.trim_matches(|c| c == '"' || c == '\'').to_string())
+        .filter(|t| !t.is_empty())
+        .collect()
+}
+
+/// FTS5 の MATCH 式（各語をフレーズとして AND で結ぶ）
+fn match_expression(terms: &[String]) -> String {
+    terms.iter().map(|t| format!("\"{}\"", t.replace('"', "\"\""))).collect::<Vec<_>>().join(" AND ")
+}
+
+/// LIKE 用のパターン（% と _ をエスケープ）
+fn like_pattern(term: &str) -> String {
+    let escaped = term.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+/// 最初に一致した語の前後を切り出し、一致した語をすべて強調表示する（LIKE 検索用）
+fn highlight_excerpt(text: &str, terms: &[String]) -> String {
+    let lower = text.to_lowercase();
+    let first = terms.iter().filter_map(|t| lower.find(&t.to_lowercase())).min().unwrap_or(0);
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let center = chars.iter().position(|(i, _)| *i >= first).unwrap_or(0);
+    let start = center.saturating_sub(SNIPPET_CONTEXT_CHARS);
+    let end = (center + SNIPPET_CONTEXT_CHARS * 2).min(chars.len());
+    let mut excerpt: String = chars[start..end].iter().map(|(_, c)| c).collect();
+    for term in terms {
+        excerpt = excerpt.replace(term.as_str(), &format!("{}{}{}", MARK_START, term, MARK_END));
+    }
+    let prefix = if start > 0 { "…" } else { "" };
+    let suffix = if end < chars.len() { "…" } else { "" };
+    format!("{}{}{}", prefix, excerpt, suffix)
+}
+
+async fn search_fts(pool: &SqlitePool, terms: &[String], acc: &mut HashMap<i64, Acc>) -> Result<(), String> {
+    let expr = match_expression(terms);
+    let topics: Vec<(i64, String)> = sqlx::query_as(
+        "SELECT rowid, highlight(sessions_fts, 0, ?, ?) FROM sessions_fts WHERE sessions_fts MATCH ?",
+    )
+    .bind(MARK_START)
+    .bind(MARK_END)
+    .bind(&expr)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("テーマ検索失敗: {}", e))?;
+    for (session_id, highlighted) in topics {
+        acc.entry(session_id).or_insert_with(|| Acc { best_score: f64::MAX, ..Default::default() }).topic_highlight =
+            Some(highlighted);
+    }
+
+    let messages: Vec<(i64, i64, String, String, f64)> = sqlx::query_as(
+        "SELECT m.session_id, m.position, m.speaker, snippet(messages_fts, 0, ?, ?, '…', 24), bm25(messages_fts)
+         FROM messages_fts JOIN messages AS m ON m.id = messages_fts.rowid
+         WHERE messages_fts MATCH ? ORDER BY bm25(messages_fts) LIMIT ?",
+    )
+    .bind(MARK_START)
+    .bind(MARK_END)
+    .bind(&expr)
+    .bind(MAX_MESSAGE_HITS)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("発言検索失敗: {}", e))?;
+    for (session_id, position, speaker, snippet, score) in messages {
+        push_message_hit(acc, session_id, score, MessageSnippet { position, speaker, snippet });
+    }
+    Ok(())
+}
+
+async fn search_like(pool: &SqlitePool, terms: &[String], acc: &mut HashMap<i64, Acc>) -> Result<(), String> {
+    let conditions = |column: &str| vec![format!("{} LIKE ? ESCAPE '\\'", column); terms.len()].join(" AND ");
+
+    let sql = format!("SELECT id, topic FROM sessions WHERE {}", conditions("topic"));
+    let mut query = sqlx::query_as::<_, (i64, String)>(&sql);
+    for term in terms {
+        query = query.bind(like_pattern(term));
+    }
+    let topics = query.fetch_all(pool).await.map_err(|e| format!("テーマ検索失敗: {}", e))?;
+    for (session_id, topic) in topics {
+        acc.entry(session_id).or_insert_with(|| Acc { best_score: f64::MAX, ..Default::default() }).topic_highlight =
+            Some(highlight_excerpt(&topic, terms));
+    }
+
+    let sql = format!(
+        "SELECT session_id, position, speaker, content FROM messages WHERE {} ORDER BY session_id DESC, position LIMIT ?",
+        conditions("content")
+    );
+    let mut query = sqlx::query_as::<_, (i64, i64, String, String)>(&sql);
+    for term in terms {
+        query = query.bind(like_pattern(term));
+    }
+    let messages =
+        query.bind(MAX_MESSAGE_HITS).fetch_all(pool).await.map_err(|e| format!("発言検索失敗: {}", e))?;
+    for (rank, (session_id, position, speaker, content)) in messages.into_iter().enumerate() {
+        let snippet = highlight_excerpt(&content, terms);
+        push_message_hit(acc, session_id, rank as f64, MessageSnippet { position, speaker, snippet });
+    }
+    Ok(())
+}
+
+fn push_message_hit(acc: &mut HashMap<i64, Acc>, session_id: i64, score: f64, snippet: MessageSnippet) {
+    let entry = acc.entry(session_id).or_insert_with(|| Acc { best_score: f64::MAX, ..Default::default() });
+    entry.message_hits += 1;
+    entry.best_score = entry.best_score.min(score);
+    if entry.snippets.len() < SNIPPETS_PER_SESSION {
+        entry.snippets.push(snippet);
+    }
+}
+
+// テーマ・発言本文からセッションを検索（テーマ一致を優先し、次に発言の関連度順）
+#[command]
+pub async fn search_sessions(
+    app: AppHandle,
+    query: String,
+    limit: Option<i64>,
+) -> Result<Vec<SessionSearchHit>, String> {
+    let terms = split_terms(&query);
+    info!("search_sessions 呼び出し: 語数={}", terms.len());
+    if terms.is_empty() {
+        return Ok(Vec::new());
+    }
+    let pool = db::pool(&app).await?;
+    let mut acc: HashMap<i64, Acc> = HashMap::new();
+    if terms.iter().all(|t| t.chars().count() >= MIN_FTS_TERM_CHARS) {
+        search_fts(&pool, &terms, &mut acc).await?;
+    } else {
+        search_like(&pool, &terms, &mut acc).await?;
+    }
+
+    let mut ranked: Vec<(i64, Acc)> = acc.into_iter().collect();
+    ranked.sort_by(|(a_id, a), (b_id, b)| {
+        b.topic_highlight
+            .is_some()
+            .cmp(&a.topic_highlight.is_some())
+            .then(a.best_score.total_cmp(&b.best_score))
+            .then(b.message_hits.cmp(&a.message_hits))
+            .then(b_id.cmp(a_id))
+    });
+    ranked.truncate(limit.unwrap_or(20).clamp(1, MAX_LIMIT) as usize);
+
+    let mut hits = Vec::with_capacity(ranked.len());
+    for (session_id, acc) in ranked {
+        let row: Option<(String, String)> = sqlx::query_as("SELECT topic, updated_at FROM sessions WHERE id = ?")
+            .bind(session_id)
+            .fetch_optional(&pool)
+            .await
+            .map_err(|e| format!("セッション取得失敗: {}", e))?;
+        let Some((topic, updated_at)) = row else { continue };
+        hits.push(SessionSearchHit {
+            session_id,
+            topic,
+            topic_highlight: acc.topic_highlight,
+            message_hits: acc.message_hits,
+            snippets: acc.snippets,
+            updated_at,
+        });
+    }
+    Ok(hits)
+}
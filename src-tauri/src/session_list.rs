@@ -0,0 +1,189 @@
+// セッション一覧のページ取得
+// 一覧表示に必要なメタデータ（テーマ・参加者名・発言数・日時・タグ）だけを返し、発言本文（sessions.messages）は読み込まない
+// 発言数は messages テーブルの件数を使う
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle};
+use tracing::info;
+
+use crate::db;
+
+// 1ページの件数の既定値と上限
+const DEFAULT_PAGE_SIZE: usize = 20;
+const MAX_PAGE_SIZE: usize = 100;
+
+/// 並び順の基準
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SessionSortKey {
+    /// 最後に開いた日時（開いていなければ更新日時）
+    #[default]
+    LastOpened,
+    Updated,
+    Created,
+    Topic,
+}
+
+impl SessionSortKey {
+    fn column(self) -> &'static str {
+        match self {
+            SessionSortKey::LastOpened => "datetime(COALESCE(m.last_opened_at, s.updated_at))",
+            SessionSortKey::Updated => "datetime(s.updated_at)",
+            SessionSortKey::Created => "datetime(s.created_at)",
+            SessionSortKey::Topic => "s.topic",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+/// 絞り込み・並び替えの条件
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SessionFilter {
+    /// テーマに含まれる文字列
+    pub query: Option<String>,
+    /// すべて付いているセッションだけに絞り込むタグ
+    pub tags: Vec<String>,
+    /// 作成日時の範囲（'YYYY-MM-DD' または 'YYYY-MM-DD HH:MM:SS'。日付だけの上限はその日の終わりまで）
+    pub created_from: Option<String>,
+    pub created_to: Option<String>,
+    pub sort_by: SessionSortKey,
+    /// 未指定なら topic は昇順、それ以外は降順
+    pub order: Option<SortOrder>,
+}
+
+/// 一覧の1行
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionListItem {
+    pub id: i64,
+    pub topic: String,
+    pub participants: Vec<String>,
+    pub message_count: i64,
+    pub model: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub last_opened_at: Option<String>,
+    pub tags: Vec<String>,
+}
+
+/// get_sessions_page の戻り値
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionPage {
+    pub items: Vec<SessionListItem>,
+    /// 条件に合うセッションの総数
+    pub total: i64,
+    pub offset: usize,
+    pub limit: usize,
+    pub has_more: bool,
+}
+
+/// 一覧の行（ID, テーマ, 参加者JSON, モデル, 作成日時, 更新日時, 最終オープン日時, 発言数, タグJSON）
+type SessionListRow = (i64, String, String, String, String, String, Option<String>, i64, String);
+
+/// 条件から WHERE 句とバインドする値を組み立てる
+fn where_clause(filter: &SessionFilter) -> (String, Vec<String>) {
+    let mut conditions = Vec::new();
+    let mut binds = Vec::new();
+    if let Some(query) = filter.query.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+        conditions.push("instr(lower(s.topic), lower(?)) > 0".to_string());
+        binds.push(query.to_string());
+    }
+    let mut tags: Vec<String> = filter.tags.iter().map(|t| t.trim().to_string()).filter(|t| !t.is_empty()).collect();
+    tags.sort();
+    tags.dedup();
+    if !tags.is_empty() {
+        conditions.push(format!(
+            "s.id IN (SELECT session_id FROM tags WHERE tag IN ({}) GROUP BY session_id HAVING COUNT(DISTINCT tag) = {})",
+            vec!["?"; tags.len()].join(", "),
+            tags.len()
+        ));
+        binds.extend(tags);
+    }
+    if let Some(from) = filter.created_from.as_deref().map(str::trim).filter(|d| !d.is_empty()) {
+        conditions.push("datetime(s.created_at) >= datetime(?)".to_string());
+        binds.push(from.to_string());
+    }
+    if let Some(to) = filter.created_to.as_deref().map(str::trim).filter(|d| !d.is_empty()) {
+        conditions.push("datetime(s.created_at) <= datetime(?)".to_string());
+        binds.push(if to.len() <= 10 { format!("{} 23:59:59", to) } else { to.to_string() });
+    }
+    let clause = if conditions.is_empty() { String::new() } else { format!("WHERE {}", conditions.join(" AND ")) };
+    (clause, binds)
+}
+
+// セッション一覧を1ページ分返す（発言本文は含めない）
+#[command]
+pub async fn get_sessions_page(
+    app: AppHandle,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    filter: Option<SessionFilter>,
+) -> Result<SessionPage, String> {
+    let offset = offset.unwrap_or(0);
+    let limit = limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    let filter = filter.unwrap_or_default();
+    info!("get_sessions_page 呼び出し: offset={}, limit={}, filter={:?}", offset, limit, filter);
+    let pool = db::pool(&app).await?;
+    let (clause, binds) = where_clause(&filter);
+
+    let count_sql = format!("SELECT COUNT(*) FROM sessions s {}", clause);
+    let mut count_query = sqlx::query_as::<_, (i64,)>(&count_sql);
+    for value in &binds {
+        count_query = count_query.bind(value);
+    }
+    let (total,) = count_query.fetch_one(&pool).await.map_err(|e| format!("セッション一覧取得失敗: {}", e))?;
+
+    let ascending = match filter.order {
+        Some(order) => order == SortOrder::Asc,
+        None => filter.sort_by == SessionSortKey::Topic,
+    };
+    let list_sql = format!(
+        "SELECT s.id, s.topic, s.participants, s.model, s.created_at, s.updated_at, m.last_opened_at,
+                (SELECT COUNT(*) FROM messages WHERE session_id = s.id),
+                (SELECT json_group_array(tag) FROM tags WHERE session_id = s.id)
+         FROM sessions s
+         LEFT JOIN session_meta m ON m.session_id = s.id
+         {}
+         ORDER BY {} {}, s.id DESC
+         LIMIT ? OFFSET ?",
+        clause,
+        filter.sort_by.column(),
+        if ascending { "ASC" } else { "DESC" }
+    );
+    let mut list_query = sqlx::query_as::<_, SessionListRow>(&list_sql);
+    for value in &binds {
+        list_query = list_query.bind(value);
+    }
+    let rows = list_query
+        .bind(limit as i64)
+        .bind(offset as i64)
+        .fetch_all(&pool)
+        .await
+        .map_err(|e| format!("セッション一覧取得失敗: {}", e))?;
+
+    let items: Vec<SessionListItem> = rows
+        .into_iter()
+        .map(|(id, topic, participants, model, created_at, updated_at, last_opened_at, message_count, tags)| {
+            SessionListItem {
+                id,
+                topic,
+                participants: db::parse_participants(&participants).names(),
+                message_count,
+                model,
+                created_at,
+                updated_at,
+                last_opened_at,
+                tags: serde_json::from_str(&tags).unwrap_or_default(),
+            }
+        })
+        .collect();
+    let has_more = (offset + items.len()) < total as usize;
+    Ok(SessionPage { items, total, offset, limit, has_more })
+}
@@ -0,0 +1,199 @@
+// 保存済みの議論への質問応答
+// 最新の要約と、質問との文字の重なり（日本語は語の区切りがないため2文字単位）で選んだ発言を根拠としてモデルに答えさせ、
+// 回答が参照した発言番号を引用付きで返す
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{command, AppHandle};
+use tracing::{info, warn};
+
+use crate::{
+    call_ollama_generate_with, db,
+    db::StoredMessage,
+    generation::{GenerationOptions, OutputFormat},
+    is_allowed_model, llm_json, prompts,
+    prompts::SessionExcerpt,
+    session_settings, ERR_UNSUPPORTED_MODEL,
+};
+
+// 質問の最大文字数
+const MAX_QUESTION_CHARS: usize = 1000;
+// 根拠として渡す発言の数
+const MAX_EXCERPTS: usize = 8;
+// 質問と重ならない場合に渡す直近の発言の数
+const RECENT_FALLBACK: usize = 4;
+// 質問で名指しされた参加者の発言への加点
+const SPEAKER_BONUS: f32 = 0.5;
+// 引用として返す抜粋の最大文字数
+const MAX_QUOTE_CHARS: usize = 120;
+// 回答の温度（記録に沿って答えさせるため低め）
+const QA_TEMPERATURE: f32 = 0.2;
+
+/// 回答が参照した発言
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MessageReference {
+    /// 1始まりの発言番号（回答中の [#番号]）
+    pub number: usize,
+    /// セッション内の位置（0始まり）
+    pub index: usize,
+    pub speaker: String,
+    pub quote: String,
+    pub timestamp: String,
+}
+
+/// ask_session の戻り値
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionAnswer {
+    pub answer: String,
+    pub references: Vec<MessageReference>,
+    /// 要約を根拠に含めたか
+    pub used_summary: bool,
+}
+
+/// モデルの回答（{ "answer": ..., "references": [...] }）
+#[derive(Debug, Deserialize)]
+struct RawAnswer {
+    #[serde(default)]
+    answer: String,
+    #[serde(default)]
+    references: Vec<usize>,
+}
+
+/// 照合用の断片（英数字は単語、それ以外は2文字ずつ。1文字だけの塊はそのまま）
+pub fn fragments(text: &str) -> HashSet<String> {
+    let mut out = HashSet::new();
+    let lower = text.to_lowercase();
+    for chunk in lower.split(|c: char| c.is_whitespace() || c.is_ascii_punctuation() || "、。！？「」『』（）・".contains(c)) {
+        if chunk.is_empty() {
+            continue;
+        }
+        if chunk.chars().all(|c| c.is_ascii_alphanumeric()) {
+            out.insert(chunk.to_string());
+            continue;
+        }
+        let chars: Vec<char> = chunk.chars().collect();
+        if chars.len() == 1 {
+            out.insert(chunk.to_string());
+        }
+        for pair in chars.windows(2) {
+            out.insert(pair.iter().collect());
+        }
+    }
+    out
+}
+
+/// 質問に関係しそうな発言を選び、発言順に並べて返す（位置の一覧）
+fn relevant_messages(messages: &[StoredMessage], question: &str) -> Vec<usize> {
+    let wanted = fragments(question);
+    let mut scored: Vec<(usize, f32)> = messages
+        .iter()
+        .enumerate()
+        .filter_map(|(i, m)| {
+            let have = fragments(&m.message);
+            let overlap = wanted.iter().filter(|f| have.contains(*f)).count();
+            let mut score = if wanted.is_empty() { 0.0 } else { overlap as f32 / wanted.len() as f32 };
+            if !m.speaker.is_empty() && question.contains(&m.speaker) {
+                score += SPEAKER_BONUS;
+            }
+            (score > 0.0).then_some((i, score))
+        })
+        .collect();
+    // 同点なら新しい発言を優先
+    scored.sort_by(|(a_i, a), (b_i, b)| b.total_cmp(a).then(b_i.cmp(a_i)));
+    let mut picked: Vec<usize> = scored.into_iter().take(MAX_EXCERPTS).map(|(i, _)| i).collect();
+    if picked.is_empty() {
+        picked = (messages.len().saturating_sub(RECENT_FALLBACK)..messages.len()).collect();
+    }
+    picked.sort_unstable();
+    picked
+}
+
+fn quote(text: &str) -> String {
+    let text = text.trim();
+    if text.chars().count() <= MAX_QUOTE_CHARS {
+        return text.to_string();
+    }
+    let mut cut: String = text.chars().take(MAX_QUOTE_CHARS - 1).collect();
+    cut.push('…');
+    cut
+}
+
+fn output_format() -> OutputFormat {
+    OutputFormat::Schema(json!({
+        "type": "object",
+        "properties": {
+            "answer": { "type": "string" },
+            "references": { "type": "array", "items": { "type": "integer" } }
+        },
+        "required": ["answer", "references"]
+    }))
+}
+
+// 保存済みの議論について質問し、根拠の発言を引用付きで返す（model 未指定ならセッションのモデル）
+#[command]
+pub async fn ask_session(
+    app: AppHandle,
+    session_id: i64,
+    question: String,
+    model: Option<String>,
+) -> Result<SessionAnswer, String> {
+    let question = question.trim().to_string();
+    info!("ask_session 呼び出し: session_id={}, 質問 {}文字", session_id, question.chars().count());
+    if question.is_empty() {
+        return Err("質問を入力してください".into());
+    }
+    if question.chars().count() > MAX_QUESTION_CHARS {
+        return Err(format!("質問は{}文字以内で入力してください", MAX_QUESTION_CHARS));
+    }
+    let session = db::load_session(&app, session_id).await?;
+    if session.messages.is_empty() {
+        return Err("このセッションにはまだ発言がありません".into());
+    }
+    let model = model.unwrap_or_else(|| session.model.clone());
+    if !is_allowed_model(&app, &model) {
+        return Err(ERR_UNSUPPORTED_MODEL.to_string());
+    }
+
+    let summary = db::latest_summary(&app, session_id).await?.map(|s| s.summary);
+    let picked = relevant_messages(&session.messages, &question);
+    let excerpts: Vec<SessionExcerpt> = picked
+        .iter()
+        .map(|&i| SessionExcerpt { number: i + 1, speaker: &session.messages[i].speaker, message: &session.messages[i].message })
+        .collect();
+    let language = session_settings::load(&app, session_id).await?.language();
+    let prompt = prompts::build_session_qa_prompt(&session.topic, summary.as_deref(), &excerpts, &question, language);
+    let options =
+        GenerationOptions { temperature: Some(QA_TEMPERATURE), ..Default::default() }.with_format(output_format());
+
+    let raw = call_ollama_generate_with(&app, &model, &prompt, &options).await?;
+    let parsed = match llm_json::parse_llm_json::<RawAnswer>(&raw) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            // JSON でなければ本文をそのまま回答とみなす（参照なし）
+            warn!("質問応答の{}（本文をそのまま使用）", e);
+            RawAnswer { answer: raw.trim().to_string(), references: Vec::new() }
+        }
+    };
+    let mut numbers = parsed.references;
+    numbers.sort_unstable();
+    numbers.dedup();
+    // 抜粋として渡していない番号は根拠にならないので除く
+    let references = numbers
+        .into_iter()
+        .filter(|n| picked.contains(&n.wrapping_sub(1)))
+        .map(|number| {
+            let message = &session.messages[number - 1];
+            MessageReference {
+                number,
+                index: number - 1,
+                speaker: message.speaker.clone(),
+                quote: quote(&message.message),
+                timestamp: message.timestamp.clone(),
+            }
+        })
+        .collect();
+    Ok(SessionAnswer { answer: parsed.answer.trim().to_string(), references, used_summary: summary.is_some() })
+}
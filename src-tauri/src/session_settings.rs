@@ -0,0 +1,109 @@
+// セッション単位の設定（やさしい日本語モードなど）
+// session_settings テーブルに JSON として保存し、項目追加時もマイグレーション不要にする
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle};
+use tracing::info;
+
+use crate::{
+    db,
+    formats::{DiscussionFormat, FormatPhase},
+    prompts::{self, Language, PromptStyle, ReadingLevel},
+};
+
+/// セッションごとの設定値
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SessionSettings {
+    /// やさしい日本語モード（短文・専門用語回避）
+    pub plain_language: bool,
+    /// 想定読者の読解レベル（elementary / high-school / expert）
+    pub reading_level: Option<ReadingLevel>,
+    /// 常に反論役（悪魔の代弁者）を務めるAI参加者の名前（自動進行で使用）
+    pub devils_advocate: Option<String>,
+    /// 出力言語（ja / en。未指定ならアプリ設定の language）
+    pub language: Option<Language>,
+    /// 形式のある議論の形式と現在のフェーズ（start_formatted_discussion が記録する）
+    pub format: Option<DiscussionFormat>,
+    pub phase: Option<FormatPhase>,
+    /// 参加者ごとの同意度・苛立ちを数発言ごとに見積もり、発言プロンプトに反映する
+    pub persona_state: bool,
+}
+
+impl SessionSettings {
+    /// プロンプト生成用のスタイル指定へ変換
+    pub fn prompt_style(&self) -> PromptStyle {
+        PromptStyle {
+            plain_language: self.plain_language,
+            reading_level: self.reading_level,
+            language: self.language(),
+        }
+    }
+
+    /// このセッションの出力言語
+    pub fn language(&self) -> Language {
+        self.language.unwrap_or_else(prompts::default_language)
+    }
+}
+
+/// 設定を読み込む（未保存なら既定値）
+pub async fn load(app: &AppHandle, session_id: i64) -> Result<SessionSettings, String> {
+    let pool = db::pool(app).await?;
+    let row: Option<(String,)> = sqlx::query_as("SELECT settings FROM session_settings WHERE session_id = ?")
+        .bind(session_id)
+        .fetch_optional(&pool)
+        .await
+        .map_err(|e| format!("セッション設定取得失敗: {}", e))?;
+    Ok(row
+        .and_then(|(json,)| serde_json::from_str(&json).ok())
+        .unwrap_or_default())
+}
+
+/// セッションIDが指定されていればその設定から、なければ既定のスタイルを返す
+pub async fn prompt_style_for(app: &AppHandle, session_id: Option<i64>) -> Result<PromptStyle, String> {
+    match session_id {
+        Some(id) => Ok(load(app, id).await?.prompt_style()),
+        None => Ok(PromptStyle { language: prompts::default_language(), ..PromptStyle::default() }),
+    }
+}
+
+/// セッションIDが指定されていればその設定の、なければアプリ設定の出力言語を返す
+pub async fn language_for(app: &AppHandle, session_id: Option<i64>) -> Result<Language, String> {
+    match session_id {
+        Some(id) => Ok(load(app, id).await?.language()),
+        None => Ok(prompts::default_language()),
+    }
+}
+
+// セッション設定を取得
+#[command]
+pub async fn get_session_settings(app: AppHandle, session_id: i64) -> Result<SessionSettings, String> {
+    load(&app, session_id).await
+}
+
+// セッション設定を保存（全項目を上書き）
+#[command]
+pub async fn set_session_settings(
+    app: AppHandle,
+    session_id: i64,
+    settings: SessionSettings,
+) -> Result<(), String> {
+    info!("set_session_settings 呼び出し: session_id={}, settings={:?}", session_id, settings);
+    save(&app, session_id, &settings).await
+}
+
+/// 設定を保存（全項目を上書き）
+pub async fn save(app: &AppHandle, session_id: i64, settings: &SessionSettings) -> Result<(), String> {
+    let pool = db::pool(app).await?;
+    let json = serde_json::to_string(settings).map_err(|e| format!("設定のシリアライズ失敗: {}", e))?;
+    sqlx::query(
+        "INSERT INTO session_settings (session_id, settings, updated_at) VALUES (?, ?, ?)
+         ON CONFLICT(session_id) DO UPDATE SET settings = excluded.settings, updated_at = excluded.updated_at",
+    )
+    .bind(session_id)
+    .bind(json)
+    .bind(db::now_string())
+    .execute(&pool)
+    .await
+    .map_err(|e| format!("セッション設定保存失敗: {}", e))?;
+    Ok(())
+}
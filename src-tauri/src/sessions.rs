@@ -0,0 +1,207 @@
+// セッションの名前変更・複製・分岐
+// 複製と分岐は参加者・発言・セッション設定・タグを新しいセッションにコピーし、元のセッションは変更しない
+// 分岐は指定した発言までで打ち切ったコピーで、別の展開を試すのに使う。元のセッションとの関係は session_lineage に記録する
+use serde::Serialize;
+use serde_json::json;
+use tauri::{command, AppHandle};
+use tracing::info;
+
+use crate::{audit, db, db::StoredMessage, discussion_engine, encryption};
+
+// テーマの最大文字数
+const MAX_TOPIC_CHARS: usize = 200;
+
+/// コピーの種類（session_lineage.kind）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyKind {
+    Duplicate,
+    Fork,
+}
+
+impl CopyKind {
+    fn key(self) -> &'static str {
+        match self {
+            CopyKind::Duplicate => "duplicate",
+            CopyKind::Fork => "fork",
+        }
+    }
+
+    /// 新しいセッションのテーマに付ける印
+    fn topic_suffix(self) -> &'static str {
+        match self {
+            CopyKind::Duplicate => "（コピー）",
+            CopyKind::Fork => "（分岐）",
+        }
+    }
+}
+
+/// 複製・分岐元の情報（get_session_lineage の戻り値）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionLineage {
+    /// 元のセッション（削除済みなら None）
+    pub parent_id: Option<i64>,
+    pub kind: String,
+    /// 分岐した発言の位置（0始まり。複製なら None）
+    pub message_index: Option<i64>,
+    pub created_at: String,
+}
+
+fn validate_topic(topic: &str) -> Result<String, String> {
+    let topic = topic.trim();
+    if topic.is_empty() {
+        return Err("テーマを入力してください".into());
+    }
+    if topic.chars().count() > MAX_TOPIC_CHARS {
+        return Err(format!("テーマは{}文字以内で入力してください", MAX_TOPIC_CHARS));
+    }
+    Ok(topic.to_string())
+}
+
+// セッションのテーマを変更する
+#[command]
+pub async fn rename_session(app: AppHandle, id: i64, new_topic: String) -> Result<(), String> {
+    info!("rename_session 呼び出し: id={}", id);
+    let topic = validate_topic(&new_topic)?;
+    let pool = db::pool(&app).await?;
+    let result = sqlx::query("UPDATE sessions SET topic = ?, updated_at = ? WHERE id = ?")
+        .bind(&topic)
+        .bind(db::now_string())
+        .bind(id)
+        .execute(&pool)
+        .await
+        .map_err(|e| format!("テーマ変更失敗: {}", e))?;
+    if result.rows_affected() == 0 {
+        return Err(format!("セッションが見つかりません: id={}", id));
+    }
+    Ok(())
+}
+
+/// セッションを新しいセッションにコピーする（messages が None なら発言をすべてコピー）
+async fn copy_session(
+    app: &AppHandle,
+    source_id: i64,
+    kind: CopyKind,
+    messages: Option<&[StoredMessage]>,
+    message_index: Option<usize>,
+) -> Result<i64, String> {
+    let pool = db::pool(app).await?;
+    let row: Option<(String, String, String, String)> =
+        sqlx::query_as("SELECT topic, participants, messages, model FROM sessions WHERE id = ?")
+            .bind(source_id)
+            .fetch_optional(&pool)
+            .await
+            .map_err(|e| format!("セッション取得失敗: {}", e))?;
+    let (topic, participants, all_messages, model) =
+        row.ok_or_else(|| format!("セッションが見つかりません: id={}", source_id))?;
+    let messages = match messages {
+        Some(messages) => {
+            // load_session で復号済みの発言なので、保存前に暗号化し直す
+            let mut messages = messages.to_vec();
+            encryption::keys(app).seal_messages(&mut messages)?;
+            serde_json::to_string(&messages).map_err(|e| format!("メッセージのシリアライズ失敗: {}", e))?
+        }
+        None => all_messages,
+    };
+    let topic: String = format!("{}{}", topic, kind.topic_suffix()).chars().take(MAX_TOPIC_CHARS).collect();
+    let now = db::now_string();
+
+    let mut tx = pool.begin().await.map_err(|e| format!("トランザクション開始失敗: {}", e))?;
+    let session_id = sqlx::query(
+        "INSERT INTO sessions (topic, participants, messages, model, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&topic)
+    .bind(participants)
+    .bind(messages)
+    .bind(model)
+    .bind(&now)
+    .bind(&now)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("セッション登録失敗: {}", e))?
+    .last_insert_rowid();
+    sqlx::query(
+        "INSERT INTO session_settings (session_id, settings, updated_at)
+         SELECT ?, settings, ? FROM session_settings WHERE session_id = ?",
+    )
+    .bind(session_id)
+    .bind(&now)
+    .bind(source_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("セッション設定のコピー失敗: {}", e))?;
+    sqlx::query(
+        "INSERT INTO tags (session_id, tag, source, created_at) SELECT ?, tag, source, ? FROM tags WHERE session_id = ?",
+    )
+    .bind(session_id)
+    .bind(&now)
+    .bind(source_id)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("タグのコピー失敗: {}", e))?;
+    sqlx::query(
+        "INSERT INTO session_lineage (session_id, parent_id, kind, message_index, created_at) VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(session_id)
+    .bind(source_id)
+    .bind(kind.key())
+    .bind(message_index.map(|i| i as i64))
+    .bind(&now)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| format!("分岐元の記録失敗: {}", e))?;
+    tx.commit().await.map_err(|e| format!("コミット失敗: {}", e))?;
+
+    audit::record(
+        "session",
+        kind.key(),
+        json!({ "sessionId": session_id, "sourceSessionId": source_id, "messageIndex": message_index }),
+    );
+    // 要約・分析・埋め込みは新しいセッションとして作り直す
+    discussion_engine::on_message_persisted(app, session_id);
+    Ok(session_id)
+}
+
+// セッションを複製する（参加者・発言・設定・タグをコピーした新しいセッションのIDを返す）
+#[command]
+pub async fn duplicate_session(app: AppHandle, id: i64) -> Result<i64, String> {
+    info!("duplicate_session 呼び出し: id={}", id);
+    copy_session(&app, id, CopyKind::Duplicate, None, None).await
+}
+
+// 過去の発言の時点から議論を分岐する（at_message_index の発言までをコピーした新しいセッションのIDを返す）
+// 元のセッションは変更しない
+#[command]
+pub async fn fork_session(app: AppHandle, id: i64, at_message_index: usize) -> Result<i64, String> {
+    info!("fork_session 呼び出し: id={}, at_message_index={}", id, at_message_index);
+    let session = db::load_session(&app, id).await?;
+    if at_message_index >= session.messages.len() {
+        return Err(format!(
+            "発言の位置が範囲外です: {}（発言は{}件）",
+            at_message_index,
+            session.messages.len()
+        ));
+    }
+    let kept = &session.messages[..=at_message_index];
+    copy_session(&app, id, CopyKind::Fork, Some(kept), Some(at_message_index)).await
+}
+
+// 複製・分岐で作られたセッションの元の情報（元のセッションから作られたものでなければ None）
+#[command]
+pub async fn get_session_lineage(app: AppHandle, id: i64) -> Result<Option<SessionLineage>, String> {
+    info!("get_session_lineage 呼び出し: id={}", id);
+    let pool = db::pool(&app).await?;
+    let row: Option<(Option<i64>, String, Option<i64>, String)> = sqlx::query_as(
+        "SELECT parent_id, kind, message_index, created_at FROM session_lineage WHERE session_id = ?",
+    )
+    .bind(id)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| format!("分岐元の取得失敗: {}", e))?;
+    Ok(row.map(|(parent_id, kind, message_index, created_at)| SessionLineage {
+        parent_id,
+        kind,
+        message_index,
+        created_at,
+    }))
+}
@@ -0,0 +1,285 @@
+// 初回セットアップ
+// 画面の案内に沿って、前提条件の確認 → 推奨モデルの取得 → 動作確認の生成 を順に行うためのコマンド
+// 各ステップの進み具合は setup://progress で通知する（モデル取得の詳細は model://pull-progress）
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use serde::Serialize;
+use sysinfo::Disks;
+use tauri::{command, AppHandle, Emitter};
+use tracing::{info, warn};
+
+use crate::{
+    backend, bootstrap, call_ollama_generate_full, generation::GenerationOptions, health, is_allowed_model,
+    model_manager,
+    system_info::{self, memory, GIB, LARGE_MODEL},
+    ERR_UNSUPPORTED_MODEL,
+};
+
+pub const EVENT_SETUP_PROGRESS: &str = "setup://progress";
+
+// モデル取得に必要なディスクの空き（ダウンロードサイズに余裕を持たせた値）
+const LARGE_MODEL_DISK: u64 = 4 * GIB;
+const SMALL_MODEL_DISK: u64 = GIB;
+// 動作確認の生成（短く返させて所要時間を測る）
+const SMOKE_TEST_PROMPT: &str = "「こんにちは」とだけ日本語で返してください。";
+const SMOKE_TEST_MAX_TOKENS: i32 = 16;
+
+/// setup://progress のペイロード
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SetupProgressEvent {
+    /// "prerequisites" / "install" / "smokeTest"
+    step: &'static str,
+    /// 確認中の項目（前提条件の各項目やモデル名）
+    item: String,
+    /// "running" / "done" / "failed"
+    status: &'static str,
+    message: String,
+}
+
+fn emit(app: &AppHandle, step: &'static str, item: &str, status: &'static str, message: impl Into<String>) {
+    let payload = SetupProgressEvent { step, item: item.to_string(), status, message: message.into() };
+    let _ = app.emit(EVENT_SETUP_PROGRESS, payload);
+}
+
+/// 前提条件1項目の確認結果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrerequisiteCheck {
+    /// "ollamaInstalled" / "ollamaRunning" / "memory" / "disk" / "models"
+    pub name: String,
+    pub ok: bool,
+    pub detail: String,
+}
+
+/// check_prerequisites の戻り値
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Prerequisites {
+    pub ollama_installed: bool,
+    pub ollama_binary: Option<String>,
+    pub ollama_running: bool,
+    pub ollama_version: Option<String>,
+    /// ローカルにある許可済みのモデル
+    pub installed_models: Vec<String>,
+    /// この環境に勧めるモデル
+    pub recommended_model: String,
+    pub recommended_model_installed: bool,
+    pub total_memory_bytes: u64,
+    pub available_memory_bytes: u64,
+    /// モデルの保存先があるディスクの空き容量（分からなければ None）
+    pub free_disk_bytes: Option<u64>,
+    pub checks: Vec<PrerequisiteCheck>,
+    /// すべての確認が通ったか
+    pub ready: bool,
+}
+
+fn required_disk(model: &str) -> u64 {
+    if model == LARGE_MODEL {
+        LARGE_MODEL_DISK
+    } else {
+        SMALL_MODEL_DISK
+    }
+}
+
+/// Ollama のモデル保存先（OLLAMA_MODELS、なければホーム配下の .ollama/models）
+fn models_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os("OLLAMA_MODELS") {
+        return Some(PathBuf::from(dir));
+    }
+    std::env::var_os(if cfg!(windows) { "USERPROFILE" } else { "HOME" })
+        .map(|home| PathBuf::from(home).join(".ollama").join("models"))
+}
+
+/// path を含むディスクの空き容量（マウント位置が最も深く一致するもの）
+fn free_space_for(path: &Path) -> Option<u64> {
+    let disks = Disks::new_with_refreshed_list();
+    disks
+        .list()
+        .iter()
+        .filter(|disk| path.starts_with(disk.mount_point()))
+        .max_by_key(|disk| disk.mount_point().as_os_str().len())
+        .map(|disk| disk.available_space())
+}
+
+fn format_gib(bytes: u64) -> String {
+    format!("{:.1}GB", bytes as f64 / GIB as f64)
+}
+
+// セットアップの前提条件（Ollama の有無・起動状態・モデル・メモリ・ディスク）を確認する
+#[command]
+pub async fn check_prerequisites(app: AppHandle) -> Result<Prerequisites, String> {
+    const STEP: &str = "prerequisites";
+    let mut checks = Vec::new();
+    let mut record = |app: &AppHandle, name: &str, ok: bool, detail: String| {
+        emit(app, STEP, name, if ok { "done" } else { "failed" }, detail.clone());
+        checks.push(PrerequisiteCheck { name: name.to_string(), ok, detail });
+    };
+
+    emit(&app, STEP, "ollamaInstalled", "running", "Ollama を探しています");
+    let binary = bootstrap::find_binary();
+    let installed = binary.is_some();
+    record(
+        &app,
+        "ollamaInstalled",
+        installed,
+        match &binary {
+            Some(path) => path.display().to_string(),
+            None => format!("Ollama が見つかりません。{} からインストールしてください", bootstrap::DOWNLOAD_URL),
+        },
+    );
+
+    emit(&app, STEP, "ollamaRunning", "running", "Ollama への接続を確認しています");
+    let status = health::refresh(&app).await;
+    let running = !matches!(status.state, health::OllamaState::Down | health::OllamaState::Unknown);
+    record(
+        &app,
+        "ollamaRunning",
+        running,
+        match (&status.version, &status.error) {
+            (Some(version), _) => format!("Ollama {}", version),
+            (None, Some(error)) => error.clone(),
+            (None, None) => backend::current(&app).name().to_string(),
+        },
+    );
+
+    emit(&app, STEP, "memory", "running", "メモリを確認しています");
+    let (total_memory, available_memory) = memory();
+    let recommended = system_info::recommended_model(total_memory);
+    record(
+        &app,
+        "memory",
+        total_memory > 0,
+        format!("搭載 {}・空き {}（推奨モデル: {}）", format_gib(total_memory), format_gib(available_memory), recommended),
+    );
+
+    emit(&app, STEP, "models", "running", "モデルを確認しています");
+    let installed_models: Vec<String> = if running {
+        backend::current(&app)
+            .list_models()
+            .await
+            .unwrap_or_else(|e| {
+                warn!("{}", e);
+                Vec::new()
+            })
+            .into_iter()
+            .filter(|name| is_allowed_model(&app, name))
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let recommended_installed = installed_models.iter().any(|name| name == recommended);
+    record(
+        &app,
+        "models",
+        !installed_models.is_empty(),
+        if installed_models.is_empty() {
+            format!("使えるモデルがありません（{} の取得を勧めます）", recommended)
+        } else {
+            installed_models.join(", ")
+        },
+    );
+
+    emit(&app, STEP, "disk", "running", "ディスクの空き容量を確認しています");
+    let free_disk = models_dir().and_then(|dir| free_space_for(&dir));
+    let disk_needed = if recommended_installed { 0 } else { required_disk(recommended) };
+    record(
+        &app,
+        "disk",
+        free_disk.is_none_or(|free| free >= disk_needed),
+        match free_disk {
+            Some(free) => format!(
+                "空き {}（{} の取得に約 {} 必要）",
+                format_gib(free),
+                recommended,
+                format_gib(required_disk(recommended))
+            ),
+            None => "空き容量を取得できませんでした".into(),
+        },
+    );
+
+    let ready = checks.iter().all(|c| c.ok);
+    info!("前提条件の確認: ready={}", ready);
+    Ok(Prerequisites {
+        ollama_installed: installed,
+        ollama_binary: binary.map(|p| p.display().to_string()),
+        ollama_running: running,
+        ollama_version: status.version,
+        installed_models,
+        recommended_model: recommended.to_string(),
+        recommended_model_installed: recommended_installed,
+        total_memory_bytes: total_memory,
+        available_memory_bytes: available_memory,
+        free_disk_bytes: free_disk,
+        checks,
+        ready,
+    })
+}
+
+// 搭載メモリに合わせた推奨モデル（gemma3:1b / gemma3:4b）を取得する。取得したモデル名を返す
+#[command]
+pub async fn install_recommended_model(app: AppHandle) -> Result<String, String> {
+    const STEP: &str = "install";
+    let (total_memory, _) = memory();
+    let model = system_info::recommended_model(total_memory);
+    if let Some(free) = models_dir().and_then(|dir| free_space_for(&dir)) {
+        if free < required_disk(model) {
+            let message =
+                format!("ディスクの空きが足りません（空き {}、必要 {}）", format_gib(free), format_gib(required_disk(model)));
+            emit(&app, STEP, model, "failed", message.clone());
+            return Err(message);
+        }
+    }
+    emit(&app, STEP, model, "running", format!("{} を取得しています", model));
+    match model_manager::pull_model(app.clone(), model.to_string()).await {
+        Ok(()) => {
+            emit(&app, STEP, model, "done", format!("{} を取得しました", model));
+            health::refresh(&app).await;
+            Ok(model.to_string())
+        }
+        Err(e) => {
+            emit(&app, STEP, model, "failed", e.clone());
+            Err(e)
+        }
+    }
+}
+
+/// run_smoke_test の戻り値
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SmokeTestResult {
+    pub model: String,
+    pub ok: bool,
+    /// 依頼から応答までの時間（モデルの読み込みを含む）
+    pub latency_ms: u64,
+    pub response: Option<String>,
+    pub error: Option<String>,
+}
+
+// 短い生成を1回行い、応答までの時間を測る（model 未指定なら推奨モデル）
+#[command]
+pub async fn run_smoke_test(app: AppHandle, model: Option<String>) -> Result<SmokeTestResult, String> {
+    const STEP: &str = "smokeTest";
+    let model = model.unwrap_or_else(|| system_info::recommended_model(memory().0).to_string());
+    if !is_allowed_model(&app, &model) {
+        return Err(ERR_UNSUPPORTED_MODEL.to_string());
+    }
+    emit(&app, STEP, &model, "running", "テスト生成を実行しています");
+    let options = GenerationOptions { num_predict: Some(SMOKE_TEST_MAX_TOKENS), ..GenerationOptions::default() };
+    let started = Instant::now();
+    let result = call_ollama_generate_full(&app, &model, SMOKE_TEST_PROMPT, &options).await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+    let result = match result {
+        Ok(r) => {
+            SmokeTestResult { model, ok: true, latency_ms, response: Some(r.text.trim().to_string()), error: None }
+        }
+        Err(e) => SmokeTestResult { model, ok: false, latency_ms, response: None, error: Some(e.to_string()) },
+    };
+    match &result.error {
+        None => emit(&app, STEP, &result.model, "done", format!("{}ms で応答しました", latency_ms)),
+        Some(e) => emit(&app, STEP, &result.model, "failed", e.clone()),
+    }
+    info!("テスト生成: model={}, ok={}, {}ms", result.model, result.ok, latency_ms);
+    Ok(result)
+}
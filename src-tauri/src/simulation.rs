@@ -0,0 +1,313 @@
+// シミュレーション（同じ条件の議論の繰り返し実行）
+// 同じテーマ・参加者・ラウンド数の自動進行を指定回数バックグラウンドで実行し、1回ごとに新しいセッションとして保存する
+// 研究用に合成ディベートのコーパスを一晩で作るためのもので、run_simulation はすぐに戻り、
+// 進み具合は simulation://progress、終了は simulation://finished で通知する。並列数には上限があり、cancel_simulation で中止できる
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Instant;
+
+use serde::Serialize;
+use tauri::{command, AppHandle, Emitter};
+use tokio::sync::Semaphore;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+use crate::{
+    batch, db,
+    db::{AiParticipant, ParticipantsData},
+    discussion_engine::{self, RoundConfig, MAX_AUTO_ROUNDS},
+    is_allowed_model, ERR_UNSUPPORTED_MODEL,
+};
+
+pub const EVENT_SIMULATION_PROGRESS: &str = "simulation://progress";
+pub const EVENT_SIMULATION_FINISHED: &str = "simulation://finished";
+
+// 1回のシミュレーションで実行できる議論の数
+const MAX_REPETITIONS: u32 = 200;
+// 同時に進める議論の数の上限（ローカルLLMの負荷を考慮）
+const MAX_PARALLEL: usize = 4;
+
+/// 1回分の議論の状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RepetitionStatus {
+    Started,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+/// 実行中のシミュレーション
+struct Simulation {
+    topic: String,
+    repetitions: u32,
+    started_at: String,
+    cancel: CancellationToken,
+    progress: Mutex<SimulationProgress>,
+}
+
+/// 実行中のシミュレーションの集計
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SimulationProgress {
+    completed: u32,
+    failed: u32,
+    cancelled: u32,
+    /// 作成したセッション（実行した順）
+    session_ids: Vec<i64>,
+}
+
+/// list_simulations の要素
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulationStatus {
+    pub simulation_id: u64,
+    pub topic: String,
+    pub repetitions: u32,
+    pub completed: u32,
+    pub failed: u32,
+    pub cancelled: u32,
+    pub session_ids: Vec<i64>,
+    /// cancel_simulation を受け付けたか
+    pub cancelling: bool,
+    pub started_at: String,
+}
+
+/// simulation://progress のペイロード
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SimulationProgressEvent {
+    simulation_id: u64,
+    /// 何回目の議論か（0 始まり）
+    repetition: u32,
+    repetitions: u32,
+    status: RepetitionStatus,
+    session_id: Option<i64>,
+    error: Option<String>,
+    completed: u32,
+    failed: u32,
+}
+
+/// simulation://finished のペイロード
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SimulationFinishedEvent {
+    simulation_id: u64,
+    #[serde(flatten)]
+    progress: SimulationProgress,
+    duration_ms: u128,
+}
+
+/// 実行中のシミュレーション（ID -> 状態）
+fn simulations() -> &'static Mutex<HashMap<u64, Arc<Simulation>>> {
+    static SIMULATIONS: OnceLock<Mutex<HashMap<u64, Arc<Simulation>>>> = OnceLock::new();
+    SIMULATIONS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn next_id() -> u64 {
+    static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// 1回分の議論を実行し、作成したセッションと中止されたかを返す
+async fn run_repetition(
+    app: &AppHandle,
+    simulation: &Simulation,
+    participants: &ParticipantsData,
+    rounds: u32,
+    model: &str,
+    session_id: &mut Option<i64>,
+) -> Result<bool, String> {
+    let id = db::create_session(app, &simulation.topic, participants, model).await?;
+    *session_id = Some(id);
+    let config = RoundConfig { summarize_at_end: true, ..Default::default() };
+    let run = discussion_engine::run_rounds_with(app, id, rounds, &config);
+    tokio::pin!(run);
+    // 進行を先に開始させ、中止の指示は開始済みの進行に停止として伝える
+    let stats = tokio::select! {
+        biased;
+        stats = &mut run => stats?,
+        _ = simulation.cancel.cancelled() => {
+            discussion_engine::stop(app, id);
+            run.await?
+        }
+    };
+    Ok(stats.stopped)
+}
+
+/// 全回を実行する（run_simulation から spawn する）
+async fn run_all(
+    app: AppHandle,
+    simulation_id: u64,
+    simulation: Arc<Simulation>,
+    participants: ParticipantsData,
+    rounds: u32,
+    model: String,
+    parallel: usize,
+) {
+    let started = Instant::now();
+    let semaphore = Arc::new(Semaphore::new(parallel));
+    let participants = Arc::new(participants);
+    let model = Arc::new(model);
+    let mut handles = Vec::with_capacity(simulation.repetitions as usize);
+
+    for repetition in 0..simulation.repetitions {
+        let (app, simulation, semaphore) = (app.clone(), simulation.clone(), semaphore.clone());
+        let (participants, model) = (participants.clone(), model.clone());
+        handles.push(tauri::async_runtime::spawn(async move {
+            let Ok(_permit) = semaphore.acquire_owned().await else { return };
+            let emit = |status: RepetitionStatus, session_id: Option<i64>, error: Option<String>| {
+                let progress = simulation.progress.lock().unwrap_or_else(|e| e.into_inner());
+                let _ = app.emit(
+                    EVENT_SIMULATION_PROGRESS,
+                    SimulationProgressEvent {
+                        simulation_id,
+                        repetition,
+                        repetitions: simulation.repetitions,
+                        status,
+                        session_id,
+                        error,
+                        completed: progress.completed,
+                        failed: progress.failed,
+                    },
+                );
+            };
+            // 中止後に順番が来た回は実行しない
+            if simulation.cancel.is_cancelled() {
+                simulation.progress.lock().unwrap_or_else(|e| e.into_inner()).cancelled += 1;
+                emit(RepetitionStatus::Cancelled, None, None);
+                return;
+            }
+            emit(RepetitionStatus::Started, None, None);
+
+            let mut session_id = None;
+            let outcome = run_repetition(&app, &simulation, &participants, rounds, &model, &mut session_id).await;
+            let (status, error) = {
+                let mut progress = simulation.progress.lock().unwrap_or_else(|e| e.into_inner());
+                progress.session_ids.extend(session_id);
+                match outcome {
+                    Ok(false) => {
+                        progress.completed += 1;
+                        (RepetitionStatus::Completed, None)
+                    }
+                    Ok(true) => {
+                        progress.cancelled += 1;
+                        (RepetitionStatus::Cancelled, None)
+                    }
+                    Err(e) => {
+                        warn!("シミュレーションの議論失敗 (simulation_id={}, {}回目): {}", simulation_id, repetition + 1, e);
+                        progress.failed += 1;
+                        (RepetitionStatus::Failed, Some(e))
+                    }
+                }
+            };
+            emit(status, session_id, error);
+        }));
+    }
+    for handle in handles {
+        let _ = handle.await;
+    }
+
+    simulations().lock().unwrap_or_else(|e| e.into_inner()).remove(&simulation_id);
+    let progress = simulation.progress.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    info!(
+        "シミュレーション終了: simulation_id={}, 完了={}, 失敗={}, 中止={}",
+        simulation_id, progress.completed, progress.failed, progress.cancelled
+    );
+    let _ = app.emit(
+        EVENT_SIMULATION_FINISHED,
+        SimulationFinishedEvent { simulation_id, progress, duration_ms: started.elapsed().as_millis() },
+    );
+}
+
+// 同じテーマ・参加者の自動進行を repetitions 回バックグラウンドで実行し、シミュレーションIDをすぐに返す
+// 1回ごとに新しいセッションを作成して終了時に要約する。profiles が空ならテーマから参加者を1回だけ生成して全回で使う
+#[command]
+pub async fn run_simulation(
+    app: AppHandle,
+    topic: String,
+    profiles: Vec<AiParticipant>,
+    rounds: u32,
+    model: String,
+    repetitions: u32,
+    max_parallel: Option<usize>,
+) -> Result<u64, String> {
+    info!(
+        "run_simulation 呼び出し: topic='{}', 参加者={}人, rounds={}, model={}, repetitions={}, max_parallel={:?}",
+        topic,
+        profiles.len(),
+        rounds,
+        model,
+        repetitions,
+        max_parallel
+    );
+    let topic = topic.trim().to_string();
+    if topic.is_empty() {
+        return Err("テーマを入力してください".into());
+    }
+    if rounds == 0 || rounds > MAX_AUTO_ROUNDS {
+        return Err(format!("ラウンド数は1〜{}で指定してください", MAX_AUTO_ROUNDS));
+    }
+    if repetitions == 0 || repetitions > MAX_REPETITIONS {
+        return Err(format!("繰り返し回数は1〜{}で指定してください", MAX_REPETITIONS));
+    }
+    if !is_allowed_model(&app, &model) {
+        return Err(ERR_UNSUPPORTED_MODEL.to_string());
+    }
+    let profiles: Vec<AiParticipant> = profiles.into_iter().filter(|p| !p.name.trim().is_empty()).collect();
+    let profiles = if profiles.is_empty() { batch::generate_participants(&app, &topic, &model).await? } else { profiles };
+    let participants = ParticipantsData { user_participates: false, ai_data: profiles };
+
+    let simulation_id = next_id();
+    let simulation = Arc::new(Simulation {
+        topic,
+        repetitions,
+        started_at: db::now_string(),
+        cancel: CancellationToken::new(),
+        progress: Mutex::new(SimulationProgress::default()),
+    });
+    simulations().lock().unwrap_or_else(|e| e.into_inner()).insert(simulation_id, simulation.clone());
+    let parallel = max_parallel.unwrap_or(1).clamp(1, MAX_PARALLEL);
+    tauri::async_runtime::spawn(run_all(app, simulation_id, simulation, participants, rounds, model, parallel));
+    Ok(simulation_id)
+}
+
+// シミュレーションを中止する（進行中の議論は停止し、まだ始まっていない回は実行しない。見つかれば true）
+#[command]
+pub fn cancel_simulation(simulation_id: u64) -> bool {
+    info!("cancel_simulation 呼び出し: simulation_id={}", simulation_id);
+    match simulations().lock().unwrap_or_else(|e| e.into_inner()).get(&simulation_id) {
+        Some(simulation) => {
+            simulation.cancel.cancel();
+            true
+        }
+        None => false,
+    }
+}
+
+// 実行中のシミュレーションの一覧と進み具合
+#[command]
+pub fn list_simulations() -> Vec<SimulationStatus> {
+    info!("list_simulations 呼び出し");
+    let simulations = simulations().lock().unwrap_or_else(|e| e.into_inner());
+    let mut statuses: Vec<SimulationStatus> = simulations
+        .iter()
+        .map(|(id, simulation)| {
+            let progress = simulation.progress.lock().unwrap_or_else(|e| e.into_inner()).clone();
+            SimulationStatus {
+                simulation_id: *id,
+                topic: simulation.topic.clone(),
+                repetitions: simulation.repetitions,
+                completed: progress.completed,
+                failed: progress.failed,
+                cancelled: progress.cancelled,
+                session_ids: progress.session_ids,
+                cancelling: simulation.cancel.is_cancelled(),
+                started_at: simulation.started_at.clone(),
+            }
+        })
+        .collect();
+    statuses.sort_by_key(|s| s.simulation_id);
+    statuses
+}
@@ -0,0 +1,162 @@
+// 長い議論の分割要約（map-reduce）
+// 会話履歴が要約プロンプトのコンテキストに収まらない場合、トークン予算ごとに前から区切って部分ごとに要約し（map）、
+// 部分の要約をテンプレート summary_merge で1つに統合する（reduce）。統合も一度に収まらなければ段階的にまとめる
+// 収まる場合は従来どおり1回で要約する。進み具合は summary://progress で通知する
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+use tracing::info;
+
+use crate::{
+    call_ollama_generate_background, call_ollama_generate_full,
+    gen_error::GenError,
+    generation::{GenerationOptions, GenerationResult},
+    prompts::{self, PromptStyle},
+    tokens,
+};
+
+pub const EVENT_SUMMARY_PROGRESS: &str = "summary://progress";
+
+// 要約の出力用に残すトークン数（num_predict 未指定時）
+const SUMMARY_RESERVE_TOKENS: usize = 1024;
+// 予算が極端に小さい設定でも分割が進むようにする下限
+const MIN_CHUNK_TOKENS: usize = 256;
+
+/// 分割要約の段階
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SummaryStage {
+    /// 部分ごとの要約
+    Map,
+    /// 部分の要約の統合
+    Reduce,
+}
+
+/// summary://progress のペイロード
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SummaryProgressEvent {
+    session_id: Option<i64>,
+    stage: SummaryStage,
+    /// その段階で終わった生成の数
+    done: usize,
+    total: usize,
+}
+
+/// 要約1回分の入力
+pub struct SummaryRequest<'a> {
+    pub session_id: Option<i64>,
+    pub model: &'a str,
+    pub discussion_topic: &'a str,
+    pub participants: &'a [String],
+    pub style: &'a PromptStyle,
+    pub options: &'a GenerationOptions,
+    /// 裏方の生成として実行する時のキー（自動要約。None なら画面からの生成として実行）
+    pub background_key: Option<&'a str>,
+}
+
+impl SummaryRequest<'_> {
+    /// テンプレートの固定部分と出力分を除いた、履歴（または部分の要約）に使えるトークン数
+    fn budget(&self, overhead_prompt: &str) -> usize {
+        let window = self.options.num_ctx.map(|n| n as usize).unwrap_or_else(|| tokens::context_window(self.model));
+        let reserve =
+            self.options.num_predict.filter(|n| *n > 0).map(|n| n as usize).unwrap_or(SUMMARY_RESERVE_TOKENS);
+        window.saturating_sub(tokens::count(overhead_prompt) + reserve).max(MIN_CHUNK_TOKENS)
+    }
+
+    async fn generate(&self, app: &AppHandle, prompt: &str, part: Option<String>) -> Result<GenerationResult, GenError> {
+        match self.background_key {
+            None => call_ollama_generate_full(app, self.model, prompt, self.options).await,
+            Some(key) => {
+                // 部分ごとの生成が待機中の別の部分と合流しないよう、キーを分ける
+                let key = match part {
+                    Some(part) => format!("{}:{}", key, part),
+                    None => key.to_string(),
+                };
+                call_ollama_generate_background(app, &key, self.model, prompt, self.options)
+                    .await
+                    .map(|text| GenerationResult::text_only(self.model, text))
+            }
+        }
+    }
+
+    fn progress(&self, app: &AppHandle, stage: SummaryStage, done: usize, total: usize) {
+        let _ = app.emit(EVENT_SUMMARY_PROGRESS, SummaryProgressEvent { session_id: self.session_id, stage, done, total });
+    }
+}
+
+/// 項目（発言の行・部分の要約）をトークン予算ごとに前から区切る（1項目で予算を超える場合はその項目だけで1つにする）
+fn split_by_budget<S: AsRef<str>>(items: &[S], budget: usize) -> Vec<Vec<&str>> {
+    let mut chunks: Vec<Vec<&str>> = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    let mut used = 0;
+    for item in items {
+        let cost = tokens::count(item.as_ref()) + 1;
+        if used + cost > budget && !current.is_empty() {
+            chunks.push(std::mem::take(&mut current));
+            used = 0;
+        }
+        used += cost;
+        current.push(item.as_ref());
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+/// 会話履歴を要約する（収まらなければ分割して要約し、統合する）
+pub async fn summarize_history(
+    app: &AppHandle,
+    request: &SummaryRequest<'_>,
+    conversation_history: &str,
+) -> Result<GenerationResult, String> {
+    let summary_prompt = |history: &str| {
+        prompts::build_discussion_summary_prompt(request.discussion_topic, history, request.participants, request.style)
+    };
+    let budget = request.budget(&summary_prompt(""));
+    if tokens::count(conversation_history) <= budget {
+        return Ok(request.generate(app, &summary_prompt(conversation_history), None).await?);
+    }
+
+    // map: 履歴を予算ごとに区切って部分ごとに要約する
+    let lines: Vec<&str> = conversation_history.lines().filter(|l| !l.trim().is_empty()).collect();
+    let chunks = split_by_budget(&lines, budget);
+    info!("要約対象が長いため分割して要約: {}行を{}部分に分割", lines.len(), chunks.len());
+    let mut partials: Vec<String> = Vec::with_capacity(chunks.len());
+    request.progress(app, SummaryStage::Map, 0, chunks.len());
+    for (index, chunk) in chunks.iter().enumerate() {
+        let result = request.generate(app, &summary_prompt(&chunk.join("\n")), Some(format!("map{}", index))).await?;
+        partials.push(result.text.trim().to_string());
+        request.progress(app, SummaryStage::Map, index + 1, chunks.len());
+    }
+
+    // reduce: 部分の要約を統合する（一度に収まらなければ収まる単位ごとにまとめて繰り返す）
+    let merge_prompt = |parts: &[String]| {
+        prompts::build_summary_merge_prompt(request.discussion_topic, parts, request.participants, request.style)
+    };
+    let merge_budget = request.budget(&merge_prompt(&[]));
+    let mut round = 0;
+    loop {
+        let groups = split_by_budget(&partials, merge_budget);
+        // 1部分ずつにしか分けられない場合も、2つずつ統合して必ず数を減らす
+        let groups: Vec<Vec<String>> = if groups.len() == partials.len() && partials.len() > 1 {
+            partials.chunks(2).map(|g| g.to_vec()).collect()
+        } else {
+            groups.into_iter().map(|g| g.into_iter().map(str::to_string).collect()).collect()
+        };
+        if groups.len() == 1 {
+            request.progress(app, SummaryStage::Reduce, 0, 1);
+            let result = request.generate(app, &merge_prompt(&groups[0]), None).await?;
+            request.progress(app, SummaryStage::Reduce, 1, 1);
+            return Ok(result);
+        }
+        let mut merged = Vec::with_capacity(groups.len());
+        for (index, group) in groups.iter().enumerate() {
+            request.progress(app, SummaryStage::Reduce, index, groups.len());
+            let part = format!("reduce{}-{}", round, index);
+            merged.push(request.generate(app, &merge_prompt(group), Some(part)).await?.text.trim().to_string());
+        }
+        partials = merged;
+        round += 1;
+    }
+}
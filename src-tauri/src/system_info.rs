@@ -0,0 +1,153 @@
+// マシンの性能に合わせたモデルの推奨
+// 搭載メモリ・CPU・GPU の有無を調べ、gemma3:1b と gemma3:4b のどちらが快適に動くかを判断する
+// メモリが明らかに足りないマシンでは、既定のモデルとして 4b を選ばない（読み込みでスワップし、応答が極端に遅くなるため）
+use serde::Serialize;
+use sysinfo::System;
+use tauri::command;
+use tracing::warn;
+
+use crate::{bootstrap, health};
+
+pub const GIB: u64 = 1024 * 1024 * 1024;
+pub const LARGE_MODEL: &str = "gemma3:4b";
+pub const SMALL_MODEL: &str = "gemma3:1b";
+// この搭載メモリ以上なら 4b を勧める（未満で 4b を読み込む時は警告する）
+const LARGE_MODEL_MIN_MEMORY: u64 = 8 * GIB;
+// この搭載メモリ未満では 4b を既定として選ばない
+const LARGE_MODEL_HARD_MIN_MEMORY: u64 = 6 * GIB;
+
+/// 検出した GPU
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GpuInfo {
+    /// "nvidia" / "amd" / "apple" / "ollama"（Ollama が VRAM にモデルを載せている）
+    pub kind: String,
+    pub detail: String,
+}
+
+/// system_info の戻り値
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemInfo {
+    pub total_memory_bytes: u64,
+    pub available_memory_bytes: u64,
+    /// 論理コア数
+    pub cpu_cores: usize,
+    /// 物理コア数（取得できない環境では None）
+    pub physical_cores: Option<usize>,
+    pub cpu_brand: String,
+    pub has_gpu: bool,
+    pub gpu: Option<GpuInfo>,
+}
+
+/// 搭載メモリと空きメモリ（バイト）
+pub fn memory() -> (u64, u64) {
+    let mut system = System::new();
+    system.refresh_memory();
+    (system.total_memory(), system.available_memory())
+}
+
+/// GPU の有無を推定する（ドライバの痕跡と、Ollama が VRAM にモデルを載せているかで判断）
+fn detect_gpu() -> Option<GpuInfo> {
+    if cfg!(all(target_os = "macos", target_arch = "aarch64")) {
+        return Some(GpuInfo { kind: "apple".into(), detail: "Apple Silicon（Metal）".into() });
+    }
+    if std::path::Path::new("/proc/driver/nvidia/version").exists() {
+        return Some(GpuInfo { kind: "nvidia".into(), detail: "NVIDIA ドライバ".into() });
+    }
+    if let Some(path) = bootstrap::find_on_path(if cfg!(windows) { "nvidia-smi.exe" } else { "nvidia-smi" }) {
+        return Some(GpuInfo { kind: "nvidia".into(), detail: path.display().to_string() });
+    }
+    if std::path::Path::new("/dev/kfd").exists() {
+        return Some(GpuInfo { kind: "amd".into(), detail: "ROCm（/dev/kfd）".into() });
+    }
+    health::current()
+        .loaded_models
+        .iter()
+        .find(|m| m.size_vram > 0)
+        .map(|m| GpuInfo { kind: "ollama".into(), detail: format!("{} を VRAM に読み込み済み", m.name) })
+}
+
+/// メモリ量から勧めるモデル
+pub fn recommended_model(total_memory: u64) -> &'static str {
+    if total_memory >= LARGE_MODEL_MIN_MEMORY {
+        LARGE_MODEL
+    } else {
+        SMALL_MODEL
+    }
+}
+
+/// 指定がない時に使うモデル（メモリが明らかに足りなければ 4b を選ばない）
+pub fn default_model() -> &'static str {
+    let (total, _) = memory();
+    // 取得できない環境（0）では従来どおり 4b
+    if total > 0 && total < LARGE_MODEL_HARD_MIN_MEMORY {
+        SMALL_MODEL
+    } else {
+        LARGE_MODEL
+    }
+}
+
+/// model を読み込む前に出す警告（メモリが推奨量に満たない場合）
+pub fn memory_warning(model: &str, total_memory: u64) -> Option<String> {
+    (model.starts_with(LARGE_MODEL) && total_memory > 0 && total_memory < LARGE_MODEL_MIN_MEMORY).then(|| {
+        format!(
+            "搭載メモリが {:.1}GB のため、{} は動作が遅くなるか読み込めない可能性があります（{} を推奨）",
+            total_memory as f64 / GIB as f64,
+            model,
+            SMALL_MODEL
+        )
+    })
+}
+
+// マシンの性能（メモリ・CPU・GPU の有無）を取得
+#[command]
+pub fn system_info() -> SystemInfo {
+    let mut system = System::new();
+    system.refresh_memory();
+    system.refresh_cpu_all();
+    let gpu = detect_gpu();
+    SystemInfo {
+        total_memory_bytes: system.total_memory(),
+        available_memory_bytes: system.available_memory(),
+        cpu_cores: system.cpus().len(),
+        physical_cores: System::physical_core_count(),
+        cpu_brand: system.cpus().first().map(|cpu| cpu.brand().trim().to_string()).unwrap_or_default(),
+        has_gpu: gpu.is_some(),
+        gpu,
+    }
+}
+
+/// recommend_model の戻り値
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelRecommendation {
+    pub recommended: String,
+    pub reason: String,
+    /// model を指定した場合、そのモデルを読み込む前の警告
+    pub warning: Option<String>,
+    pub system: SystemInfo,
+}
+
+// マシンに合うモデルを勧める。model を渡すと、そのモデルを使う場合の警告も返す
+#[command]
+pub fn recommend_model(model: Option<String>) -> ModelRecommendation {
+    let system = system_info();
+    let recommended = recommended_model(system.total_memory_bytes);
+    let memory_gb = system.total_memory_bytes as f64 / GIB as f64;
+    let reason = if recommended == LARGE_MODEL {
+        format!("搭載メモリ {:.1}GB で {} を快適に動かせます", memory_gb, LARGE_MODEL)
+    } else {
+        format!(
+            "搭載メモリ {:.1}GB では {} 以上を推奨するため {} を勧めます",
+            memory_gb,
+            LARGE_MODEL_MIN_MEMORY / GIB,
+            SMALL_MODEL
+        )
+    };
+    let warning = model.as_deref().and_then(|m| memory_warning(m, system.total_memory_bytes));
+    if let Some(w) = &warning {
+        warn!("{}", w);
+    }
+    ModelRecommendation { recommended: recommended.to_string(), reason, warning, system }
+}
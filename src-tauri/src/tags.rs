@@ -0,0 +1,222 @@
+// セッションのタグ
+// ユーザーが付けたタグ（source='user'）と、要約からモデルに挙げさせたキーワード（source='auto'）を tags に保存する
+// セッション一覧の絞り込みはフロントエンドの getAllSessions が tags を参照して行う
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{command, AppHandle};
+use tracing::{info, warn};
+
+use crate::{
+    call_ollama_generate_background, call_ollama_generate_with, db,
+    generation::{GenerationOptions, OutputFormat},
+    is_allowed_model, llm_json, prompts, session_settings, ERR_UNSUPPORTED_MODEL,
+};
+
+const SOURCE_USER: &str = "user";
+const SOURCE_AUTO: &str = "auto";
+// タグの最大文字数と、1セッションあたりの上限
+const MAX_TAG_CHARS: usize = 32;
+const MAX_TAGS_PER_SESSION: i64 = 20;
+// 自動タグの数
+const MIN_AUTO_TAGS: usize = 3;
+const MAX_AUTO_TAGS: usize = 5;
+
+/// タグと、そのタグが付いたセッションの数
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagCount {
+    pub tag: String,
+    pub sessions: i64,
+}
+
+/// モデルの回答（{ "tags": [...] }）
+#[derive(Debug, Deserialize)]
+struct RawTags {
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+/// 前後の空白と先頭の # を除き、連続する空白を1つにする（空なら None）
+fn normalize(tag: &str) -> Option<String> {
+    let tag = tag.trim().trim_start_matches(['#', '＃']).split_whitespace().collect::<Vec<_>>().join(" ");
+    (!tag.is_empty()).then(|| tag.chars().take(MAX_TAG_CHARS).collect())
+}
+
+async fn session_tags(pool: &sqlx::SqlitePool, session_id: i64) -> Result<Vec<String>, String> {
+    let rows: Vec<(String,)> = sqlx::query_as("SELECT tag FROM tags WHERE session_id = ? ORDER BY created_at, tag")
+        .bind(session_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| format!("タグ取得失敗: {}", e))?;
+    Ok(rows.into_iter().map(|(tag,)| tag).collect())
+}
+
+// セッションにタグを付け、付いているタグの一覧を返す（同じタグは1つだけ）
+#[command]
+pub async fn add_tag(app: AppHandle, session_id: i64, tag: String) -> Result<Vec<String>, String> {
+    info!("add_tag 呼び出し: session_id={}, tag={}", session_id, tag);
+    let tag = normalize(&tag).ok_or_else(|| "タグを入力してください".to_string())?;
+    db::load_session(&app, session_id).await?;
+    let pool = db::pool(&app).await?;
+    let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM tags WHERE session_id = ? AND tag <> ?")
+        .bind(session_id)
+        .bind(&tag)
+        .fetch_one(&pool)
+        .await
+        .map_err(|e| format!("タグ取得失敗: {}", e))?;
+    if count >= MAX_TAGS_PER_SESSION {
+        return Err(format!("タグは1セッションあたり{}個までです", MAX_TAGS_PER_SESSION));
+    }
+    // 自動で付いたタグをユーザーが付け直した場合はユーザーのタグとして扱う
+    sqlx::query(
+        "INSERT INTO tags (session_id, tag, source, created_at) VALUES (?, ?, ?, ?)
+         ON CONFLICT(session_id, tag) DO UPDATE SET source = excluded.source",
+    )
+    .bind(session_id)
+    .bind(&tag)
+    .bind(SOURCE_USER)
+    .bind(db::now_string())
+    .execute(&pool)
+    .await
+    .map_err(|e| format!("タグ保存失敗: {}", e))?;
+    session_tags(&pool, session_id).await
+}
+
+// セッションからタグを外し、残ったタグの一覧を返す
+#[command]
+pub async fn remove_tag(app: AppHandle, session_id: i64, tag: String) -> Result<Vec<String>, String> {
+    info!("remove_tag 呼び出し: session_id={}, tag={}", session_id, tag);
+    let pool = db::pool(&app).await?;
+    if let Some(tag) = normalize(&tag) {
+        sqlx::query("DELETE FROM tags WHERE session_id = ? AND tag = ?")
+            .bind(session_id)
+            .bind(&tag)
+            .execute(&pool)
+            .await
+            .map_err(|e| format!("タグ削除失敗: {}", e))?;
+    }
+    session_tags(&pool, session_id).await
+}
+
+// タグの一覧（session_id 指定時はそのセッションのタグだけ）。使われているセッションが多い順
+#[command]
+pub async fn list_tags(app: AppHandle, session_id: Option<i64>) -> Result<Vec<TagCount>, String> {
+    info!("list_tags 呼び出し: session_id={:?}", session_id);
+    let pool = db::pool(&app).await?;
+    let rows: Vec<(String, i64)> = sqlx::query_as(
+        "SELECT tag, COUNT(*) AS sessions FROM tags
+         WHERE ? IS NULL OR tag IN (SELECT tag FROM tags WHERE session_id = ?)
+         GROUP BY tag ORDER BY sessions DESC, tag",
+    )
+    .bind(session_id)
+    .bind(session_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("タグ一覧取得失敗: {}", e))?;
+    Ok(rows.into_iter().map(|(tag, sessions)| TagCount { tag, sessions }).collect())
+}
+
+fn output_format() -> OutputFormat {
+    OutputFormat::Schema(json!({
+        "type": "object",
+        "properties": {
+            "tags": {
+                "type": "array",
+                "items": { "type": "string" },
+                "minItems": MIN_AUTO_TAGS,
+                "maxItems": MAX_AUTO_TAGS
+            }
+        },
+        "required": ["tags"]
+    }))
+}
+
+/// モデルの回答からタグを取り出す（重複・空を除いて最大5個）
+fn parse_tags(raw: &str) -> Result<Vec<String>, String> {
+    let parsed: RawTags = llm_json::parse_llm_json(raw)?;
+    let mut tags: Vec<String> = Vec::new();
+    for tag in parsed.tags.iter().filter_map(|t| normalize(t)) {
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags.truncate(MAX_AUTO_TAGS);
+    if tags.is_empty() {
+        return Err("タグを取り出せませんでした".into());
+    }
+    Ok(tags)
+}
+
+/// 自動タグを入れ替える（ユーザーが付けたタグは残す）
+async fn replace_auto_tags(app: &AppHandle, session_id: i64, tags: &[String]) -> Result<(), String> {
+    let pool = db::pool(app).await?;
+    let mut tx = pool.begin().await.map_err(|e| format!("トランザクション開始失敗: {}", e))?;
+    sqlx::query("DELETE FROM tags WHERE session_id = ? AND source = ?")
+        .bind(session_id)
+        .bind(SOURCE_AUTO)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("タグ削除失敗: {}", e))?;
+    let now = db::now_string();
+    for tag in tags {
+        sqlx::query("INSERT OR IGNORE INTO tags (session_id, tag, source, created_at) VALUES (?, ?, ?, ?)")
+            .bind(session_id)
+            .bind(tag)
+            .bind(SOURCE_AUTO)
+            .bind(&now)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| format!("タグ保存失敗: {}", e))?;
+    }
+    tx.commit().await.map_err(|e| format!("トランザクション確定失敗: {}", e))
+}
+
+// 最新の要約からキーワードを3〜5個挙げさせ、自動タグとして付ける（model 未指定ならセッションのモデル）
+// 以前の自動タグは置き換え、ユーザーが付けたタグは残す
+#[command]
+pub async fn auto_tag_session(app: AppHandle, session_id: i64, model: Option<String>) -> Result<Vec<String>, String> {
+    info!("auto_tag_session 呼び出し: session_id={}, model={:?}", session_id, model);
+    let session = db::load_session(&app, session_id).await?;
+    let model = model.unwrap_or_else(|| session.model.clone());
+    if !is_allowed_model(&app, &model) {
+        return Err(ERR_UNSUPPORTED_MODEL.to_string());
+    }
+    let summary = db::latest_summary(&app, session_id)
+        .await?
+        .ok_or_else(|| "要約がまだありません。先に要約を作成してください".to_string())?;
+    let language = session_settings::load(&app, session_id).await?.language();
+    let prompt = prompts::build_session_tags_prompt(&session.topic, &summary.summary, language);
+    let options = GenerationOptions::default().with_format(output_format());
+    let tags = parse_tags(&call_ollama_generate_with(&app, &model, &prompt, &options).await?)?;
+    replace_auto_tags(&app, session_id, &tags).await?;
+    let pool = db::pool(&app).await?;
+    session_tags(&pool, session_id).await
+}
+
+/// 最初の要約の後に呼ぶ。タグがまだないセッションに自動タグをバックグラウンドで付ける
+pub fn schedule_after_first_summary(app: &AppHandle, session_id: i64, model: &str, topic: &str, summary: &str) {
+    let app = app.clone();
+    let (model, topic, summary) = (model.to_string(), topic.to_string(), summary.to_string());
+    tauri::async_runtime::spawn(async move {
+        let result = async {
+            let pool = db::pool(&app).await?;
+            if !session_tags(&pool, session_id).await?.is_empty() {
+                return Ok(Vec::new());
+            }
+            let language = session_settings::load(&app, session_id).await?.language();
+            let prompt = prompts::build_session_tags_prompt(&topic, &summary, language);
+            let options = GenerationOptions::default().with_format(output_format());
+            let raw =
+                call_ollama_generate_background(&app, &format!("tags:{}", session_id), &model, &prompt, &options).await?;
+            let tags = parse_tags(&raw)?;
+            replace_auto_tags(&app, session_id, &tags).await?;
+            Ok::<_, String>(tags)
+        }
+        .await;
+        match result {
+            Ok(tags) if tags.is_empty() => {}
+            Ok(tags) => info!("自動タグ: session_id={}, {:?}", session_id, tags),
+            Err(e) => warn!("自動タグの付与に失敗 (session_id={}): {}", session_id, e),
+        }
+    });
+}
@@ -0,0 +1,85 @@
+// 生成のタイムアウト
+// 生成1件ごとの全体の期限（接続設定の generationDeadlineSecs。生成キューの待ち時間は含まない）と、
+// ストリーミングで応答が途切れた時の無通信タイムアウト（streamIdleTimeoutSecs）で、止まった Ollama が発言を塞ぎ続けないようにする
+// 期限の時点でまだ断片が届いているストリーミングは、長い生成とみなして deadlineGraceSecs だけ待つ
+// タイムアウトは GenError::Timeout で返し、ユーザーのキャンセル（GenError::Cancelled）と区別できる
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use tracing::warn;
+
+use crate::backend::OllamaConnection;
+use crate::circuit_breaker::CircuitBreaker;
+use crate::gen_error::{GenError, TimeoutStage};
+
+/// ストリーミングで最後に断片を受け取った時刻（期限の猶予の判定に使う）
+pub struct Activity {
+    started: Instant,
+    last_ms: AtomicU64,
+}
+
+impl Activity {
+    pub fn new() -> Self {
+        Self { started: Instant::now(), last_ms: AtomicU64::new(0) }
+    }
+
+    /// 断片を受け取った
+    pub fn touch(&self) {
+        self.last_ms.store(self.started.elapsed().as_millis() as u64, Ordering::Relaxed);
+    }
+
+    /// within 以内に断片を受け取っているか
+    fn is_recent(&self, within: Duration) -> bool {
+        let last = self.last_ms.load(Ordering::Relaxed);
+        last > 0 && self.started.elapsed().saturating_sub(Duration::from_millis(last)) <= within
+    }
+}
+
+/// 全体の期限付きで生成を待つ（activity があれば、期限の時点で断片が届いている間は猶予を与える）
+pub async fn with_deadline<T, F>(
+    connection: &OllamaConnection,
+    task: F,
+    activity: Option<&Activity>,
+) -> Result<T, GenError>
+where
+    F: Future<Output = Result<T, GenError>>,
+{
+    let deadline = Duration::from_secs(connection.generation_deadline_secs);
+    let grace = Duration::from_secs(connection.deadline_grace_secs);
+    let idle = Duration::from_secs(connection.stream_idle_timeout_secs);
+    tokio::pin!(task);
+    if let Ok(result) = tokio::time::timeout(deadline, &mut task).await {
+        return result;
+    }
+    let mut waited = deadline;
+    if !grace.is_zero() && activity.is_some_and(|a| a.is_recent(idle)) {
+        warn!("生成が期限（{}秒）を過ぎましたが応答が続いているため{}秒待ちます", deadline.as_secs(), grace.as_secs());
+        if let Ok(result) = tokio::time::timeout(grace, &mut task).await {
+            return result;
+        }
+        waited += grace;
+    }
+    warn!("生成が期限（{}秒）を過ぎたため打ち切り", waited.as_secs());
+    Err(GenError::Timeout { stage: TimeoutStage::Deadline, after: waited })
+}
+
+/// ストリーミングの次の断片を無通信タイムアウト付きで待つ（res.chunk() を渡す。途切れたら breaker に失敗を記録する）
+pub async fn next_chunk<T, F>(
+    breaker: &CircuitBreaker,
+    connection: &OllamaConnection,
+    chunk: F,
+) -> Result<Option<T>, GenError>
+where
+    F: Future<Output = reqwest::Result<Option<T>>>,
+{
+    let idle = Duration::from_secs(connection.stream_idle_timeout_secs);
+    match tokio::time::timeout(idle, chunk).await {
+        Ok(result) => result.map_err(|e| GenError::Failed(format!("ストリーム受信失敗: {}", e))),
+        Err(_) => {
+            let err = GenError::Timeout { stage: TimeoutStage::Idle, after: idle };
+            breaker.record_failure(&err.to_string());
+            Err(err)
+        }
+    }
+}
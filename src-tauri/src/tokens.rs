@@ -0,0 +1,27 @@
+// トークン数の見積もり（count_tokens コマンド）
+// 見積もり自体は dewai-core（CLI と共通）にある
+use serde::Serialize;
+use tauri::command;
+
+pub use dewai_core::tokens::{context_window, count, DEFAULT_NUM_CTX};
+
+/// count_tokens の戻り値
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenCount {
+    pub model: String,
+    /// 見積もりトークン数
+    pub tokens: usize,
+    /// num_ctx 未指定時に実際に使えるコンテキスト長（モデル本来の長さと Ollama 既定の小さい方）
+    pub context_window: usize,
+    /// context_window に収まるか
+    pub fits: bool,
+}
+
+// テキストのトークン数を見積もる（プロンプトがコンテキストに収まるかの確認用）
+#[command]
+pub async fn count_tokens(text: String, model: String) -> Result<TokenCount, String> {
+    let tokens = count(&text);
+    let context_window = context_window(&model);
+    Ok(TokenCount { model, tokens, context_window, fits: tokens <= context_window })
+}
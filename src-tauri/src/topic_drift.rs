@@ -0,0 +1,208 @@
+// テーマからの脱線の検出
+// 直近の発言をテーマと比べさせ（テンプレート topic_drift）、脱線の度合い（0〜1）と逸れていった話題を返す
+// 結果は session_analysis（kind='topic_drift'）に保存する。発言の保存後の処理（discussion_engine）が
+// 発言 analysisInterval 件ごとに裏方で判定し、アプリ設定のしきい値を超えたら analysis://topic-drift で通知する
+// （手動の発言・自動進行の発言のどちらも）。司会役も脱線の判定にこの結果を使う
+use std::collections::HashSet;
+use std::sync::{Mutex, OnceLock};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{command, AppHandle, Emitter};
+use tracing::{info, warn};
+
+use crate::{
+    call_ollama_generate_background, call_ollama_generate_with, config, db, encryption,
+    generation::{GenerationOptions, OutputFormat},
+    is_allowed_model, llm_json, prompts, session_settings, ERR_UNSUPPORTED_MODEL,
+};
+
+pub const EVENT_TOPIC_DRIFT: &str = "analysis://topic-drift";
+
+// session_analysis の kind
+const KIND: &str = "topic_drift";
+// テーマと比べる直近の発言数
+const RECENT_MESSAGES: usize = 8;
+const MAX_TANGENT_CHARS: usize = 60;
+
+/// 脱線の判定結果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TopicDrift {
+    /// テーマからの離れ具合（0 = テーマどおり、1 = 完全に別の話題）
+    pub score: f32,
+    /// 逸れていった話題（テーマどおりなら空）
+    pub tangent: String,
+    /// 判定時点の発言数
+    pub message_count: usize,
+    pub model: String,
+}
+
+/// analysis://topic-drift のペイロード
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TopicDriftEvent {
+    pub session_id: i64,
+    #[serde(flatten)]
+    pub drift: TopicDrift,
+    pub threshold: f32,
+}
+
+#[derive(Debug, Deserialize)]
+struct RawDrift {
+    drift: f32,
+    #[serde(default)]
+    tangent: String,
+}
+
+fn output_format() -> OutputFormat {
+    OutputFormat::Schema(json!({
+        "type": "object",
+        "properties": {
+            "drift": { "type": "number", "minimum": 0, "maximum": 1 },
+            "tangent": { "type": "string" }
+        },
+        "required": ["drift", "tangent"]
+    }))
+}
+
+/// モデルの回答から判定結果を取り出す（度合いは 0〜1 に収める）
+fn parse(raw: &str, message_count: usize, model: String) -> Result<TopicDrift, String> {
+    let parsed: RawDrift = llm_json::parse_llm_json(raw)?;
+    if !parsed.drift.is_finite() {
+        return Err("脱線の度合いを取り出せませんでした".into());
+    }
+    Ok(TopicDrift {
+        score: parsed.drift.clamp(0.0, 1.0),
+        tangent: parsed.tangent.trim().chars().take(MAX_TANGENT_CHARS).collect(),
+        message_count,
+        model,
+    })
+}
+
+/// しきい値を超えて通知すべきか
+fn exceeds(drift: &TopicDrift, threshold: f32) -> bool {
+    drift.score > threshold
+}
+
+/// 判定結果を通知するなら、そのペイロード（通知が無効か、しきい値以下なら None）
+pub(crate) fn alert(session_id: i64, drift: TopicDrift, settings: &config::AppSettings) -> Option<TopicDriftEvent> {
+    let threshold = settings.topic_drift_threshold;
+    (settings.topic_drift_alerts && exceeds(&drift, threshold)).then_some(TopicDriftEvent { session_id, drift, threshold })
+}
+
+/// 判定中のセッション（同じセッションを並行して判定しない）
+fn running() -> &'static Mutex<HashSet<i64>> {
+    static RUNNING: OnceLock<Mutex<HashSet<i64>>> = OnceLock::new();
+    RUNNING.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+/// 直近の発言をテーマと比べ、結果を保存する（background なら裏方の生成として実行）
+async fn detect(
+    app: &AppHandle,
+    session_id: i64,
+    model: Option<String>,
+    background: bool,
+) -> Result<TopicDrift, String> {
+    let session = db::load_session(app, session_id).await?;
+    let model = model.unwrap_or_else(|| session.model.clone());
+    if !is_allowed_model(app, &model) {
+        return Err(ERR_UNSUPPORTED_MODEL.to_string());
+    }
+    let total = session.messages.len();
+    if total == 0 {
+        return Err("発言がまだありません".into());
+    }
+    let recent = db::format_history(&session.messages[total.saturating_sub(RECENT_MESSAGES)..]);
+    let language = session_settings::load(app, session_id).await?.language();
+    let prompt = prompts::build_topic_drift_prompt(&session.topic, &recent, language);
+    let options = GenerationOptions::default().with_format(output_format());
+    let raw = if background {
+        call_ollama_generate_background(app, &format!("drift:{}", session_id), &model, &prompt, &options).await?
+    } else {
+        call_ollama_generate_with(app, &model, &prompt, &options).await?
+    };
+    let drift = parse(&raw, total, model)?;
+    let payload = serde_json::to_string(&drift).map_err(|e| format!("脱線の判定結果のシリアライズ失敗: {}", e))?;
+    db::save_analysis(app, session_id, KIND, &payload).await?;
+    Ok(drift)
+}
+
+/// 保存済みの最新の判定結果（まだなければ None）
+pub async fn latest(app: &AppHandle, session_id: i64) -> Result<Option<TopicDrift>, String> {
+    let pool = db::pool(app).await?;
+    let row: Option<(String,)> = sqlx::query_as(
+        "SELECT payload FROM session_analysis WHERE session_id = ? AND kind = ? ORDER BY id DESC LIMIT 1",
+    )
+    .bind(session_id)
+    .bind(KIND)
+    .fetch_optional(&pool)
+    .await
+    .map_err(|e| format!("脱線の判定結果取得失敗: {}", e))?;
+    let Some((payload,)) = row else { return Ok(None) };
+    Ok(serde_json::from_str(&encryption::keys(app).open(&payload)?).ok())
+}
+
+/// 裏方で脱線を判定し、しきい値を超えていれば通知する（発言の保存後の処理から呼ぶ）
+pub fn schedule(app: &AppHandle, session_id: i64) {
+    if !running().lock().unwrap_or_else(|e| e.into_inner()).insert(session_id) {
+        // 判定中に増えた発言は、次の判定の時期に反映される
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = check(&app, session_id).await {
+            warn!("脱線の判定失敗 (session_id={}): {}", session_id, e);
+        }
+        running().lock().unwrap_or_else(|e| e.into_inner()).remove(&session_id);
+    });
+}
+
+/// 脱線を判定し、しきい値を超えていれば通知する
+async fn check(app: &AppHandle, session_id: i64) -> Result<(), String> {
+    let settings = config::load(app).await?;
+    let drift = detect(app, session_id, None, true).await?;
+    if let Some(event) = alert(session_id, drift, &settings) {
+        info!("テーマからの脱線を検出: session_id={}, score={:.2}", session_id, event.drift.score);
+        app.emit(EVENT_TOPIC_DRIFT, event).map_err(|e| format!("イベント送信失敗: {}", e))?;
+    }
+    Ok(())
+}
+
+// 直近の発言がテーマからどれだけ逸れているかと、逸れていった話題を返す（model 未指定ならセッションのモデル）
+#[command]
+pub async fn detect_topic_drift(app: AppHandle, session_id: i64, model: Option<String>) -> Result<TopicDrift, String> {
+    info!("detect_topic_drift 呼び出し: session_id={}, model={:?}", session_id, model);
+    detect(&app, session_id, model, false).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drift_above_threshold_alerts() {
+        let drift = parse(r#"{"drift": 0.8, "tangent": " 昼食の話 "}"#, 12, "gemma3:4b".into()).unwrap();
+        assert_eq!(drift.tangent, "昼食の話");
+        assert_eq!(drift.message_count, 12);
+        assert!(exceeds(&drift, 0.6));
+        assert!(!exceeds(&drift, 0.8));
+    }
+
+    #[test]
+    fn alert_follows_the_app_settings() {
+        let drift = parse(r#"{"drift": 0.8, "tangent": "昼食の話"}"#, 12, "gemma3:4b".into()).unwrap();
+        let settings = config::AppSettings { topic_drift_threshold: 0.6, ..Default::default() };
+        let event = alert(4, drift.clone(), &settings).unwrap();
+        assert_eq!((event.session_id, event.threshold), (4, 0.6));
+        assert!(alert(4, drift.clone(), &config::AppSettings { topic_drift_alerts: false, ..settings.clone() }).is_none());
+        assert!(alert(4, drift, &config::AppSettings { topic_drift_threshold: 0.8, ..settings }).is_none());
+    }
+
+    #[test]
+    fn drift_score_is_clamped() {
+        let drift = parse(r#"{"drift": 1.7, "tangent": ""}"#, 3, "gemma3:4b".into()).unwrap();
+        assert_eq!(drift.score, 1.0);
+        assert!(parse(r#"{"tangent": "x"}"#, 3, "gemma3:4b".into()).is_err());
+    }
+}
@@ -0,0 +1,407 @@
+// トーナメントモード
+// ペルソナ同士が同じ論題で1対1のディベートを行い、審査プロンプトの採点で勝ち上がりを決める
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::{command, AppHandle};
+use tracing::{info, warn};
+
+use crate::{
+    call_ollama_generate, db,
+    db::{AiParticipant, ParticipantsData},
+    discussion_engine, is_allowed_model, llm_json, prompts, ERR_UNSUPPORTED_MODEL,
+};
+
+const STATUS_IN_PROGRESS: &str = "in_progress";
+const STATUS_FINISHED: &str = "finished";
+const MATCH_PENDING: &str = "pending";
+const MATCH_DONE: &str = "done";
+const MATCH_BYE: &str = "bye";
+const MATCH_FAILED: &str = "failed";
+
+// 1試合あたりのラウンド数の既定値と上限
+const DEFAULT_ROUNDS_PER_DEBATE: u32 = 2;
+const MAX_ROUNDS_PER_DEBATE: u32 = 6;
+
+/// トーナメント概要
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TournamentInfo {
+    pub id: i64,
+    pub name: String,
+    pub resolution: String,
+    pub model: String,
+    pub rounds_per_debate: i64,
+    pub current_round: i64,
+    pub status: String,
+}
+
+/// 1試合の情報
+#[derive(Debug, Clone, Serialize, sqlx::FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchInfo {
+    pub id: i64,
+    pub round: i64,
+    pub entrant_a: i64,
+    pub entrant_b: Option<i64>,
+    pub session_id: Option<i64>,
+    pub score_a: Option<f64>,
+    pub score_b: Option<f64>,
+    pub winner: Option<i64>,
+    pub judge_notes: Option<String>,
+    pub status: String,
+}
+
+/// 順位表の1行
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StandingRow {
+    pub entrant_id: i64,
+    pub name: String,
+    pub role: String,
+    pub wins: u32,
+    pub losses: u32,
+    /// 審査スコアの合計
+    pub points: f64,
+    pub eliminated: bool,
+}
+
+/// get_standings の戻り値
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TournamentStandings {
+    pub tournament: TournamentInfo,
+    pub leaderboard: Vec<StandingRow>,
+    pub matches: Vec<MatchInfo>,
+}
+
+/// ルーブリック採点（各 1〜10）
+#[derive(Debug, Clone, Copy, Deserialize)]
+struct RubricScore {
+    logic: f64,
+    evidence: f64,
+    rebuttal: f64,
+    clarity: f64,
+}
+
+impl RubricScore {
+    fn total(&self) -> f64 {
+        [self.logic, self.evidence, self.rebuttal, self.clarity]
+            .iter()
+            .map(|v| v.clamp(1.0, 10.0))
+            .sum()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Judgement {
+    affirmative: RubricScore,
+    negative: RubricScore,
+    #[serde(default)]
+    reason: String,
+}
+
+async fn load_info(pool: &SqlitePool, tournament_id: i64) -> Result<TournamentInfo, String> {
+    let row: Option<(i64, String, String, String, i64, i64, String)> = sqlx::query_as(
+        "SELECT id, name, resolution, model, rounds_per_debate, current_round, status FROM tournaments WHERE id = ?",
+    )
+    .bind(tournament_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("トーナメント取得失敗: {}", e))?;
+    let (id, name, resolution, model, rounds_per_debate, current_round, status) =
+        row.ok_or_else(|| format!("トーナメントが見つかりません: id={}", tournament_id))?;
+    Ok(TournamentInfo { id, name, resolution, model, rounds_per_debate, current_round, status })
+}
+
+async fn load_entrants(pool: &SqlitePool, tournament_id: i64) -> Result<Vec<(i64, AiParticipant)>, String> {
+    let rows: Vec<(i64, String, String, String)> = sqlx::query_as(
+        "SELECT id, name, role, description FROM tournament_entrants WHERE tournament_id = ? ORDER BY id",
+    )
+    .bind(tournament_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("参加者取得失敗: {}", e))?;
+    Ok(rows
+        .into_iter()
+        .map(|(id, name, role, description)| (id, AiParticipant { name, role, description }))
+        .collect())
+}
+
+async fn load_matches(pool: &SqlitePool, tournament_id: i64) -> Result<Vec<MatchInfo>, String> {
+    sqlx::query_as(
+        "SELECT id, round, entrant_a, entrant_b, session_id, score_a, score_b, winner, judge_notes, status
+         FROM tournament_matches WHERE tournament_id = ? ORDER BY round, id",
+    )
+    .bind(tournament_id)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("試合取得失敗: {}", e))
+}
+
+/// 勝ち残りを順に2人ずつ組み合わせる（奇数なら最後の1人は不戦勝）
+async fn create_pairings(pool: &SqlitePool, tournament_id: i64, round: i64, entrants: &[i64]) -> Result<(), String> {
+    for pair in entrants.chunks(2) {
+        let (b, status, winner) = match pair {
+            [_, b] => (Some(*b), MATCH_PENDING, None),
+            _ => (None, MATCH_BYE, Some(pair[0])),
+        };
+        sqlx::query(
+            "INSERT INTO tournament_matches (tournament_id, round, entrant_a, entrant_b, winner, status) VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(tournament_id)
+        .bind(round)
+        .bind(pair[0])
+        .bind(b)
+        .bind(winner)
+        .bind(status)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("組み合わせ作成失敗: {}", e))?;
+    }
+    Ok(())
+}
+
+/// 肯定側・否定側の立場を説明に付与
+fn with_side(p: &AiParticipant, affirmative: bool) -> AiParticipant {
+    let side = if affirmative {
+        "【肯定側】論題に賛成する立場で一貫して主張し、否定側に反論してください。"
+    } else {
+        "【否定側】論題に反対する立場で一貫して主張し、肯定側に反論してください。"
+    };
+    AiParticipant { name: p.name.clone(), role: p.role.clone(), description: format!("{} {}", side, p.description) }
+}
+
+/// 1試合を実行して結果を保存
+async fn run_match(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    info: &TournamentInfo,
+    m: &MatchInfo,
+    entrants: &[(i64, AiParticipant)],
+) -> Result<(), String> {
+    let find = |id: i64| entrants.iter().find(|(eid, _)| *eid == id).map(|(_, p)| p.clone());
+    let a = find(m.entrant_a).ok_or("参加者Aが見つかりません")?;
+    let b = m.entrant_b.and_then(find).ok_or("参加者Bが見つかりません")?;
+
+    let data = ParticipantsData { user_participates: false, ai_data: vec![with_side(&a, true), with_side(&b, false)] };
+    let topic = format!("【ディベート】{}", info.resolution);
+    let session_id = db::create_session(app, &topic, &data, &info.model).await?;
+    sqlx::query("UPDATE tournament_matches SET session_id = ? WHERE id = ?")
+        .bind(session_id)
+        .bind(m.id)
+        .execute(pool)
+        .await
+        .map_err(|e| format!("試合更新失敗: {}", e))?;
+
+    discussion_engine::run_rounds(app, session_id, info.rounds_per_debate.max(1) as u32).await?;
+
+    let session = db::load_session(app, session_id).await?;
+    let prompt = prompts::build_debate_judge_prompt(&info.resolution, &a.name, &b.name, &session.history_text(), prompts::default_language());
+    // 審査結果が壊れていた場合は1回だけ再試行
+    let mut judgement: Option<Judgement> = None;
+    for attempt in 1..=2 {
+        let raw = call_ollama_generate(app, &info.model, &prompt).await?;
+        match llm_json::parse_llm_json::<Judgement>(&raw) {
+            Ok(j) => {
+                judgement = Some(j);
+                break;
+            }
+            Err(e) => warn!("審査結果の解析失敗 (attempt={}): {}", attempt, e),
+        }
+    }
+    let judgement = judgement.ok_or("審査結果を取得できませんでした")?;
+
+    let (score_a, score_b) = (judgement.affirmative.total(), judgement.negative.total());
+    // 同点なら反論の質、それも同じなら組み合わせ上位（A）を勝者とする
+    let a_wins = score_a > score_b
+        || (score_a == score_b && judgement.affirmative.rebuttal >= judgement.negative.rebuttal);
+    let winner = if a_wins { m.entrant_a } else { m.entrant_b.unwrap_or(m.entrant_a) };
+
+    sqlx::query(
+        "UPDATE tournament_matches SET score_a = ?, score_b = ?, winner = ?, judge_notes = ?, status = ? WHERE id = ?",
+    )
+    .bind(score_a)
+    .bind(score_b)
+    .bind(winner)
+    .bind(&judgement.reason)
+    .bind(MATCH_DONE)
+    .bind(m.id)
+    .execute(pool)
+    .await
+    .map_err(|e| format!("試合結果保存失敗: {}", e))?;
+    info!("試合終了: {} {:.0} - {:.0} {}", a.name, score_a, score_b, b.name);
+    Ok(())
+}
+
+/// 順位表を組み立てる
+async fn standings(pool: &SqlitePool, tournament_id: i64) -> Result<TournamentStandings, String> {
+    let tournament = load_info(pool, tournament_id).await?;
+    let entrants = load_entrants(pool, tournament_id).await?;
+    let matches = load_matches(pool, tournament_id).await?;
+
+    let mut leaderboard: Vec<StandingRow> = entrants
+        .iter()
+        .map(|(id, p)| {
+            let mut row = StandingRow {
+                entrant_id: *id,
+                name: p.name.clone(),
+                role: p.role.clone(),
+                wins: 0,
+                losses: 0,
+                points: 0.0,
+                eliminated: false,
+            };
+            for m in matches.iter().filter(|m| m.status == MATCH_DONE) {
+                let score = if m.entrant_a == *id {
+                    m.score_a
+                } else if m.entrant_b == Some(*id) {
+                    m.score_b
+                } else {
+                    continue;
+                };
+                row.points += score.unwrap_or(0.0);
+                if m.winner == Some(*id) {
+                    row.wins += 1;
+                } else {
+                    row.losses += 1;
+                    row.eliminated = true;
+                }
+            }
+            row
+        })
+        .collect();
+    leaderboard.sort_by(|a, b| {
+        a.eliminated
+            .cmp(&b.eliminated)
+            .then(b.wins.cmp(&a.wins))
+            .then(b.points.total_cmp(&a.points))
+    });
+
+    Ok(TournamentStandings { tournament, leaderboard, matches })
+}
+
+// トーナメントを作成し、1回戦の組み合わせを決める
+#[command]
+pub async fn create_tournament(
+    app: AppHandle,
+    name: String,
+    resolution: String,
+    entrants: Vec<AiParticipant>,
+    model: String,
+    rounds_per_debate: Option<u32>,
+) -> Result<TournamentStandings, String> {
+    info!("create_tournament 呼び出し: name={}, entrants={}, model={}", name, entrants.len(), model);
+    if !is_allowed_model(&app, &model) {
+        return Err(ERR_UNSUPPORTED_MODEL.to_string());
+    }
+    if resolution.trim().is_empty() {
+        return Err("論題が空です".into());
+    }
+    if entrants.len() < 2 {
+        return Err("参加者は2名以上必要です".into());
+    }
+    let mut names: Vec<&str> = entrants.iter().map(|e| e.name.trim()).collect();
+    names.sort_unstable();
+    if names.iter().any(|n| n.is_empty()) || names.windows(2).any(|w| w[0] == w[1]) {
+        return Err("参加者名が空、または重複しています".into());
+    }
+    let rounds = rounds_per_debate.unwrap_or(DEFAULT_ROUNDS_PER_DEBATE).clamp(1, MAX_ROUNDS_PER_DEBATE);
+
+    let pool = db::pool(&app).await?;
+    let result = sqlx::query(
+        "INSERT INTO tournaments (name, resolution, model, rounds_per_debate, current_round, status, created_at)
+         VALUES (?, ?, ?, ?, 1, ?, ?)",
+    )
+    .bind(name.trim())
+    .bind(resolution.trim())
+    .bind(&model)
+    .bind(rounds as i64)
+    .bind(STATUS_IN_PROGRESS)
+    .bind(db::now_string())
+    .execute(&pool)
+    .await
+    .map_err(|e| format!("トーナメント作成失敗: {}", e))?;
+    let tournament_id = result.last_insert_rowid();
+
+    let mut ids = Vec::with_capacity(entrants.len());
+    for e in &entrants {
+        let r = sqlx::query("INSERT INTO tournament_entrants (tournament_id, name, role, description) VALUES (?, ?, ?, ?)")
+            .bind(tournament_id)
+            .bind(e.name.trim())
+            .bind(&e.role)
+            .bind(&e.description)
+            .execute(&pool)
+            .await
+            .map_err(|e| format!("参加者登録失敗: {}", e))?;
+        ids.push(r.last_insert_rowid());
+    }
+    create_pairings(&pool, tournament_id, 1, &ids).await?;
+    standings(&pool, tournament_id).await
+}
+
+// 現在のラウンドの未実施試合をすべて行い、勝者で次ラウンドを組む
+#[command]
+pub async fn advance_round(app: AppHandle, tournament_id: i64) -> Result<TournamentStandings, String> {
+    info!("advance_round 呼び出し: tournament_id={}", tournament_id);
+    let pool = db::pool(&app).await?;
+    let info = load_info(&pool, tournament_id).await?;
+    if info.status == STATUS_FINISHED {
+        return Err("このトーナメントは終了しています".into());
+    }
+    let entrants = load_entrants(&pool, tournament_id).await?;
+    let round_matches: Vec<MatchInfo> = load_matches(&pool, tournament_id)
+        .await?
+        .into_iter()
+        .filter(|m| m.round == info.current_round)
+        .collect();
+
+    for m in round_matches.iter().filter(|m| m.status == MATCH_PENDING || m.status == MATCH_FAILED) {
+        if let Err(e) = run_match(&app, &pool, &info, m, &entrants).await {
+            warn!("試合失敗 (match_id={}): {}", m.id, e);
+            sqlx::query("UPDATE tournament_matches SET status = ?, judge_notes = ? WHERE id = ?")
+                .bind(MATCH_FAILED)
+                .bind(&e)
+                .bind(m.id)
+                .execute(&pool)
+                .await
+                .map_err(|e| format!("試合更新失敗: {}", e))?;
+        }
+    }
+
+    // 失敗した試合が残っていれば次ラウンドへ進めない（再度 advance_round で再試行）
+    let round_matches: Vec<MatchInfo> = load_matches(&pool, tournament_id)
+        .await?
+        .into_iter()
+        .filter(|m| m.round == info.current_round)
+        .collect();
+    if round_matches.iter().any(|m| m.winner.is_none()) {
+        return standings(&pool, tournament_id).await;
+    }
+
+    let winners: Vec<i64> = round_matches.iter().filter_map(|m| m.winner).collect();
+    if winners.len() <= 1 {
+        sqlx::query("UPDATE tournaments SET status = ? WHERE id = ?")
+            .bind(STATUS_FINISHED)
+            .bind(tournament_id)
+            .execute(&pool)
+            .await
+            .map_err(|e| format!("トーナメント更新失敗: {}", e))?;
+    } else {
+        let next_round = info.current_round + 1;
+        create_pairings(&pool, tournament_id, next_round, &winners).await?;
+        sqlx::query("UPDATE tournaments SET current_round = ? WHERE id = ?")
+            .bind(next_round)
+            .bind(tournament_id)
+            .execute(&pool)
+            .await
+            .map_err(|e| format!("トーナメント更新失敗: {}", e))?;
+    }
+    standings(&pool, tournament_id).await
+}
+
+// 順位表と全試合結果を取得
+#[command]
+pub async fn get_standings(app: AppHandle, tournament_id: i64) -> Result<TournamentStandings, String> {
+    let pool = db::pool(&app).await?;
+    standings(&pool, tournament_id).await
+}
@@ -0,0 +1,210 @@
+// 議論記録のオンデマンド翻訳
+// 発言・要約ごとに translations テーブルへキャッシュし、2回目以降の言語切替を即時にする
+// 訳文は発言本文と同じく、暗号化が有効なら暗号化して保存する
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle};
+use tracing::{info, warn};
+
+use crate::{call_ollama_generate, db, encryption, is_allowed_model, llm_json, prompts, ERR_UNSUPPORTED_MODEL};
+
+// 1回のLLM呼び出しにまとめる上限（小さいモデルでも取りこぼさない程度）
+const MAX_BATCH_ITEMS: usize = 8;
+const MAX_BATCH_CHARS: usize = 1500;
+
+const KIND_MESSAGE: &str = "message";
+const KIND_SUMMARY: &str = "summary";
+
+/// 翻訳済み発言
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranslatedMessage {
+    pub index: usize,
+    pub speaker: String,
+    pub original: String,
+    /// 翻訳に失敗した発言は None（次回呼び出し時に再試行される）
+    pub translated: Option<String>,
+}
+
+/// セッション全体の翻訳結果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranslatedSession {
+    pub lang: String,
+    pub messages: Vec<TranslatedMessage>,
+    pub summary: Option<String>,
+    /// 今回新たに翻訳した件数（0ならすべてキャッシュ）
+    pub newly_translated: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct TranslatedItem {
+    index: usize,
+    text: String,
+}
+
+/// 翻訳待ちの項目
+struct Pending {
+    kind: &'static str,
+    key: i64,
+    text: String,
+    hash: String,
+}
+
+/// 言語コードをプロンプト用の言語名に変換（未知のコードはそのまま使う）
+fn language_name(lang: &str) -> &str {
+    match lang {
+        "ja" => "日本語",
+        "en" => "English",
+        "zh" => "中文（简体）",
+        "ko" => "한국어",
+        "fr" => "Français",
+        "de" => "Deutsch",
+        "es" => "Español",
+        other => other,
+    }
+}
+
+/// 文字数・件数の上限でチャンク分割
+fn chunk_pending(pending: Vec<Pending>) -> Vec<Vec<Pending>> {
+    let mut chunks: Vec<Vec<Pending>> = Vec::new();
+    let mut current: Vec<Pending> = Vec::new();
+    let mut current_chars = 0;
+    for item in pending {
+        let len = item.text.chars().count();
+        if !current.is_empty() && (current.len() >= MAX_BATCH_ITEMS || current_chars + len > MAX_BATCH_CHARS) {
+            chunks.push(std::mem::take(&mut current));
+            current_chars = 0;
+        }
+        current_chars += len;
+        current.push(item);
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+// セッションの発言と最新要約を指定言語に翻訳
+#[command]
+pub async fn translate_session(
+    app: AppHandle,
+    session_id: i64,
+    target_lang: String,
+) -> Result<TranslatedSession, String> {
+    let lang = target_lang.trim().to_string();
+    info!("translate_session 呼び出し: session_id={}, lang={}", session_id, lang);
+    if lang.is_empty() || lang.chars().count() > 32 {
+        return Err("翻訳先の言語指定が不正です".into());
+    }
+
+    let session = db::load_session(&app, session_id).await?;
+    if !is_allowed_model(&app, &session.model) {
+        return Err(ERR_UNSUPPORTED_MODEL.to_string());
+    }
+    let summary = db::latest_summary(&app, session_id).await?;
+
+    // キャッシュ読込: (種別, キー) -> (ハッシュ, 訳文)
+    let pool = db::pool(&app).await?;
+    let rows: Vec<(String, i64, String, String)> = sqlx::query_as(
+        "SELECT source_kind, source_key, source_hash, text FROM translations WHERE session_id = ? AND lang = ?",
+    )
+    .bind(session_id)
+    .bind(&lang)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("翻訳キャッシュ取得失敗: {}", e))?;
+    let mut cache: HashMap<(String, i64), (String, String)> = rows
+        .into_iter()
+        .map(|(kind, key, hash, text)| Ok(((kind, key), (hash, encryption::keys(&app).open(&text)?))))
+        .collect::<Result<_, String>>()?;
+
+    // 未翻訳・内容が変わった項目を抽出
+    let mut sources: Vec<Pending> = session
+        .messages
+        .iter()
+        .enumerate()
+        .map(|(i, m)| Pending { kind: KIND_MESSAGE, key: i as i64, text: m.message.clone(), hash: db::content_hash(&m.message) })
+        .collect();
+    if let Some(record) = &summary {
+        sources.push(Pending {
+            kind: KIND_SUMMARY,
+            key: record.id,
+            text: record.summary.clone(),
+            hash: db::content_hash(&record.summary),
+        });
+    }
+    let pending: Vec<Pending> = sources
+        .into_iter()
+        .filter(|p| !p.text.trim().is_empty())
+        .filter(|p| cache.get(&(p.kind.to_string(), p.key)).map(|(h, _)| h != &p.hash).unwrap_or(true))
+        .collect();
+    info!("翻訳対象: {}件（キャッシュ済み: {}件）", pending.len(), cache.len());
+
+    let mut newly_translated = 0;
+    for chunk in chunk_pending(pending) {
+        let items: Vec<(usize, String)> = chunk.iter().enumerate().map(|(i, p)| (i, p.text.clone())).collect();
+        let prompt = prompts::build_translation_prompt(language_name(&lang), &items);
+        let raw = match call_ollama_generate(&app, &session.model, &prompt).await {
+            Ok(raw) => raw,
+            Err(e) => {
+                warn!("翻訳バッチ失敗（スキップ）: {}", e);
+                continue;
+            }
+        };
+        let translated: Vec<TranslatedItem> = match llm_json::parse_llm_json(&raw) {
+            Ok(v) => v,
+            Err(e) => {
+                warn!("翻訳結果の解析失敗（スキップ）: {}", e);
+                continue;
+            }
+        };
+        for item in translated {
+            let Some(src) = chunk.get(item.index) else { continue };
+            if item.text.trim().is_empty() {
+                continue;
+            }
+            sqlx::query(
+                "INSERT INTO translations (session_id, source_kind, source_key, lang, source_hash, text, created_at)
+                 VALUES (?, ?, ?, ?, ?, ?, ?)
+                 ON CONFLICT(session_id, source_kind, source_key, lang)
+                 DO UPDATE SET source_hash = excluded.source_hash, text = excluded.text, created_at = excluded.created_at",
+            )
+            .bind(session_id)
+            .bind(src.kind)
+            .bind(src.key)
+            .bind(&lang)
+            .bind(&src.hash)
+            .bind(encryption::keys(&app).seal(&item.text)?)
+            .bind(db::now_string())
+            .execute(&pool)
+            .await
+            .map_err(|e| format!("翻訳キャッシュ保存失敗: {}", e))?;
+            cache.insert((src.kind.to_string(), src.key), (src.hash.clone(), item.text));
+            newly_translated += 1;
+        }
+    }
+
+    // ハッシュが一致するキャッシュのみ採用
+    let lookup = |kind: &str, key: i64, text: &str| -> Option<String> {
+        cache
+            .get(&(kind.to_string(), key))
+            .filter(|(h, _)| *h == db::content_hash(text))
+            .map(|(_, t)| t.clone())
+    };
+    let messages = session
+        .messages
+        .iter()
+        .enumerate()
+        .map(|(i, m)| TranslatedMessage {
+            index: i,
+            speaker: m.speaker.clone(),
+            original: m.message.clone(),
+            translated: lookup(KIND_MESSAGE, i as i64, &m.message),
+        })
+        .collect();
+    let summary = summary.and_then(|record| lookup(KIND_SUMMARY, record.id, &record.summary));
+
+    Ok(TranslatedSession { lang, messages, summary, newly_translated })
+}
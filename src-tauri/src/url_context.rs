@@ -0,0 +1,279 @@
+// Webページを議論の参考資料として取り込む
+// ページを取得して script・ナビゲーションなどを除いた本文テキストを取り出し、url_cache に保存して再取得を避ける
+// 返すテキストはトークン予算で切り詰め、セッション指定時は添付資料（種類 web）として発言プロンプトの参考資料に使う
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use regex::Regex;
+use reqwest::{header, Client};
+use serde::Serialize;
+use serde_json::json;
+use tauri::{command, AppHandle};
+use tracing::info;
+
+use crate::{attachments, attachments::DocumentKind, audit, db, encryption, encryption::KeyStore, permissions, tokens};
+
+// 取得するページの上限（超えたら打ち切ってエラー）
+const MAX_DOWNLOAD_BYTES: usize = 5 * 1024 * 1024;
+const FETCH_TIMEOUT: Duration = Duration::from_secs(20);
+// 保存する本文の上限
+const MAX_TEXT_CHARS: usize = 200_000;
+// キャッシュの有効期間
+const CACHE_TTL_HOURS: i64 = 24;
+// 返すテキストのトークン予算の既定値と上限
+const DEFAULT_MAX_TOKENS: usize = tokens::DEFAULT_NUM_CTX / 2;
+const MAX_TOKENS_LIMIT: usize = tokens::DEFAULT_NUM_CTX * 4;
+// 本文とみなす行の最短文字数（メニューの1語などを除く）
+const MIN_LINE_CHARS: usize = 2;
+const USER_AGENT: &str = "DewAI/0.1 (discussion reference fetcher)";
+
+/// fetch_url_context の戻り値
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UrlContext {
+    pub url: String,
+    pub title: String,
+    /// トークン予算に収めた本文
+    pub text: String,
+    pub tokens: usize,
+    /// 予算に収めるために切り詰めたか
+    pub truncated: bool,
+    /// キャッシュから返したか
+    pub cached: bool,
+    pub fetched_at: String,
+    /// セッション指定時に作成した添付資料のID
+    pub attachment_id: Option<i64>,
+}
+
+struct Patterns {
+    comment: Regex,
+    boilerplate: Vec<Regex>,
+    article: Regex,
+    main: Regex,
+    body: Regex,
+    title: Regex,
+    line_break: Regex,
+    block_end: Regex,
+    list_item: Regex,
+    tag: Regex,
+    numeric_entity: Regex,
+    spaces: Regex,
+    blank_lines: Regex,
+}
+
+fn patterns() -> &'static Patterns {
+    static PATTERNS: OnceLock<Patterns> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        let re = |p: &str| Regex::new(p).expect("本文抽出の正規表現");
+        Patterns {
+            comment: re(r"(?s)<!--.*?-->"),
+            // regex は後方参照を使えないため、要素ごとに開始・終了タグの組を用意する
+            boilerplate: ["script", "style", "noscript", "svg", "nav", "header", "footer", "aside", "form", "iframe", "template"]
+                .iter()
+                .map(|tag| re(&format!(r"(?is)<{0}\b[^>]*>.*?</{0}\s*>", tag)))
+                .collect(),
+            article: re(r"(?is)<article\b[^>]*>(.*)</article\s*>"),
+            main: re(r"(?is)<main\b[^>]*>(.*)</main\s*>"),
+            body: re(r"(?is)<body\b[^>]*>(.*)</body\s*>"),
+            title: re(r"(?is)<title\b[^>]*>(.*?)</title\s*>"),
+            line_break: re(r"(?i)<br\s*/?>"),
+            block_end: re(r"(?i)</(?:p|div|h[1-6]|li|tr|section|blockquote|pre|table|ul|ol|dl|dd|dt)\s*>"),
+            list_item: re(r"(?i)<li\b[^>]*>"),
+            tag: re(r"(?s)<[^>]*>"),
+            numeric_entity: re(r"&#(x[0-9a-fA-F]+|[0-9]+);"),
+            spaces: re(r"[ \t\u{3000}\u{a0}]+"),
+            blank_lines: re(r"\n{3,}"),
+        }
+    })
+}
+
+fn decode_entities(text: &str) -> String {
+    let decoded = patterns().numeric_entity.replace_all(text, |caps: &regex::Captures| {
+        let code = &caps[1];
+        let value = match code.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok(),
+            None => code.parse().ok(),
+        };
+        value.and_then(char::from_u32).map(String::from).unwrap_or_default()
+    });
+    decoded
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+/// HTML からタイトルと本文テキストを取り出す（article → main → body の順に本文の範囲を探す）
+fn extract_readable(html: &str) -> (String, String) {
+    let p = patterns();
+    let title = p.title.captures(html).map(|c| decode_entities(p.tag.replace_all(&c[1], "").trim())).unwrap_or_default();
+    let mut cleaned = p.comment.replace_all(html, "").into_owned();
+    for pattern in &p.boilerplate {
+        cleaned = pattern.replace_all(&cleaned, "").into_owned();
+    }
+    let region = [&p.article, &p.main, &p.body]
+        .iter()
+        .find_map(|re| re.captures(&cleaned).map(|c| c[1].to_string()))
+        .unwrap_or(cleaned);
+    let text = p.line_break.replace_all(&region, "\n");
+    let text = p.block_end.replace_all(&text, "\n\n");
+    let text = p.list_item.replace_all(&text, "\n- ");
+    let text = p.tag.replace_all(&text, "");
+    let text = decode_entities(&text);
+    let lines: Vec<String> = text
+        .lines()
+        .map(|l| p.spaces.replace_all(l.trim(), " ").into_owned())
+        .map(|l| if l.chars().count() < MIN_LINE_CHARS { String::new() } else { l })
+        .collect();
+    let body = p.blank_lines.replace_all(lines.join("\n").trim(), "\n\n").into_owned();
+    (title, body)
+}
+
+/// トークン予算に収まるところまで行単位で切り詰める（最初の行は予算を超えても文字数で切って残す）
+fn fit_tokens(text: &str, max_tokens: usize) -> (String, usize, bool) {
+    let total = tokens::count(text);
+    if total <= max_tokens {
+        return (text.to_string(), total, false);
+    }
+    let mut kept = String::new();
+    let mut used = 0;
+    for line in text.lines() {
+        let cost = tokens::count(line) + 1;
+        if used + cost > max_tokens {
+            if kept.is_empty() {
+                // 1行目だけで予算を超える場合は、予算の目安（1トークン ≒ 1文字）で切る
+                kept = line.chars().take(max_tokens).collect();
+                used = tokens::count(&kept);
+            }
+            break;
+        }
+        kept.push_str(line);
+        kept.push('\n');
+        used += cost;
+    }
+    (kept.trim_end().to_string(), used, true)
+}
+
+fn http_client() -> Result<Client, String> {
+    Client::builder()
+        .timeout(FETCH_TIMEOUT)
+        .user_agent(USER_AGENT)
+        .build()
+        .map_err(|e| format!("HTTPクライアント初期化失敗: {}", e))
+}
+
+/// ページを取得してタイトルと本文を返す（HTML とプレーンテキストのみ。上限を超えたら打ち切る）
+async fn download(url: &str) -> Result<(String, String), String> {
+    let mut res = http_client()?.get(url).send().await.map_err(|e| format!("ページの取得に失敗: {}", e))?;
+    if !res.status().is_success() {
+        return Err(format!("ページの取得に失敗: HTTP {}", res.status()));
+    }
+    let content_type =
+        res.headers().get(header::CONTENT_TYPE).and_then(|v| v.to_str().ok()).unwrap_or("text/html").to_lowercase();
+    let is_html = content_type.contains("html");
+    if !is_html && !content_type.starts_with("text/") {
+        return Err(format!("テキストではないページです: {}", content_type));
+    }
+    if res.content_length().is_some_and(|len| len as usize > MAX_DOWNLOAD_BYTES) {
+        return Err(format!("ページが大きすぎます（上限 {} bytes）", MAX_DOWNLOAD_BYTES));
+    }
+    let mut bytes = Vec::new();
+    while let Some(chunk) = res.chunk().await.map_err(|e| format!("ページの取得が中断されました: {}", e))? {
+        if bytes.len() + chunk.len() > MAX_DOWNLOAD_BYTES {
+            return Err(format!("ページが大きすぎます（上限 {} bytes）", MAX_DOWNLOAD_BYTES));
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    let raw = String::from_utf8_lossy(&bytes);
+    let (title, text) = if is_html { extract_readable(&raw) } else { (String::new(), raw.trim().to_string()) };
+    Ok((title, text.chars().take(MAX_TEXT_CHARS).collect()))
+}
+
+/// 有効期間内のキャッシュ（タイトル, 本文, 取得日時）
+async fn cached(
+    pool: &sqlx::SqlitePool,
+    keys: &KeyStore,
+    url: &str,
+) -> Result<Option<(String, String, String)>, String> {
+    let row: Option<(String, String, String)> = sqlx::query_as(
+        "SELECT title, content, fetched_at FROM url_cache
+         WHERE url = ? AND datetime(fetched_at) >= datetime('now', ?)",
+    )
+    .bind(url)
+    .bind(format!("-{} hours", CACHE_TTL_HOURS))
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("URLキャッシュ取得失敗: {}", e))?;
+    row.map(|(title, content, fetched_at)| Ok((keys.open(&title)?, keys.open(&content)?, fetched_at))).transpose()
+}
+
+fn normalize_url(url: &str) -> Result<String, String> {
+    let parsed = reqwest::Url::parse(url.trim()).map_err(|e| format!("URLが不正です: {}", e))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Err("http / https のURLを指定してください".into());
+    }
+    let mut parsed = parsed;
+    parsed.set_fragment(None);
+    Ok(parsed.to_string())
+}
+
+// Webページの本文を取り出して返す（ツール権限 url-fetch（network）が必要）
+// session_id 指定時はセッションの添付資料として保存し、発言プロンプトの参考資料に使う
+#[command]
+pub async fn fetch_url_context(
+    app: AppHandle,
+    url: String,
+    session_id: Option<i64>,
+    max_tokens: Option<usize>,
+    refresh: Option<bool>,
+) -> Result<UrlContext, String> {
+    let url = normalize_url(&url)?;
+    info!("fetch_url_context 呼び出し: url={}, session_id={:?}", url, session_id);
+    permissions::authorize(&app, permissions::TOOL_URL_FETCH).await?;
+    if let Some(id) = session_id {
+        db::load_session(&app, id).await?;
+    }
+    let pool = db::pool(&app).await?;
+    let keys = encryption::keys(&app);
+    let hit = if refresh.unwrap_or(false) { None } else { cached(&pool, keys, &url).await? };
+    let is_cached = hit.is_some();
+    let (title, text, fetched_at) = match hit {
+        Some(hit) => hit,
+        None => {
+            let (title, text) = download(&url).await?;
+            if text.trim().is_empty() {
+                return Err("ページから本文を取り出せませんでした".into());
+            }
+            let fetched_at = db::now_string();
+            sqlx::query(
+                "INSERT INTO url_cache (url, title, content, fetched_at) VALUES (?, ?, ?, ?)
+                 ON CONFLICT(url) DO UPDATE SET
+                   title = excluded.title, content = excluded.content, fetched_at = excluded.fetched_at",
+            )
+            .bind(&url)
+            .bind(keys.seal(&title)?)
+            .bind(keys.seal(&text)?)
+            .bind(&fetched_at)
+            .execute(&pool)
+            .await
+            .map_err(|e| format!("URLキャッシュ保存失敗: {}", e))?;
+            audit::record("fetch", "url", json!({ "url": url, "chars": text.chars().count() }));
+            info!("ページを取得: {} ({}文字)", url, text.chars().count());
+            (title, text, fetched_at)
+        }
+    };
+
+    let budget = max_tokens.unwrap_or(DEFAULT_MAX_TOKENS).clamp(1, MAX_TOKENS_LIMIT);
+    let (fitted, used, truncated) = fit_tokens(&text, budget);
+    let attachment_id = match session_id {
+        Some(id) => {
+            let name = if title.is_empty() { url.clone() } else { format!("{} ({})", title, url) };
+            Some(attachments::store_document(&app, id, &name, DocumentKind::Web, &fitted).await?.id)
+        }
+        None => None,
+    };
+    Ok(UrlContext { url, title, text: fitted, tokens: used, truncated, cached: is_cached, fetched_at, attachment_id })
+}
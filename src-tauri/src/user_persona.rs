@@ -0,0 +1,23 @@
+// ユーザー（人間の参加者）の自己紹介
+// 名前・役職・立場をアプリ設定の userPersona に保存し、発言プロンプトの <user_profile> に差し込む
+// AI参加者は会話履歴の「ユーザー」をこの名前で呼び、申告された専門・立場を踏まえて応答する
+use tauri::{command, AppHandle};
+use tracing::info;
+
+use crate::{config, prompts::UserPersona};
+
+// 登録済みの自己紹介（未登録なら空の項目）
+#[command]
+pub async fn get_user_persona(app: AppHandle) -> Result<UserPersona, String> {
+    info!("get_user_persona 呼び出し");
+    Ok(config::load(&app).await?.user_persona)
+}
+
+// 自己紹介を保存し、以後の発言プロンプトに反映する（全項目を空にすると登録を消す）
+#[command]
+pub async fn set_user_persona(app: AppHandle, persona: UserPersona) -> Result<UserPersona, String> {
+    info!("set_user_persona 呼び出し: {:?}", persona);
+    let mut settings = config::load(&app).await?;
+    settings.user_persona = persona;
+    Ok(config::save(&app, settings).await?.user_persona)
+}
@@ -0,0 +1,230 @@
+// 投票ラウンド
+// 議論の締めくくりに、AI参加者ごとに個別のプロンプトで選択肢への投票と理由を求め、集計結果をセッションに保存する
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::{command, AppHandle};
+use tracing::{info, warn};
+
+use crate::{
+    call_ollama_generate_with, db, encryption,
+    generation::{GenerationOptions, OutputFormat},
+    is_allowed_model, llm_json, prompts, session_settings, ERR_UNSUPPORTED_MODEL,
+};
+
+// 選択肢の数の範囲と、質問・選択肢の上限文字数
+const MIN_OPTIONS: usize = 2;
+const MAX_OPTIONS: usize = 10;
+const MAX_QUESTION_CHARS: usize = 500;
+const MAX_OPTION_CHARS: usize = 100;
+// 投票の温度（立場を大きくぶらさないよう低め）
+const VOTE_TEMPERATURE: f32 = 0.3;
+
+/// 参加者1人の投票
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Vote {
+    pub participant: String,
+    /// 選んだ選択肢（生成失敗・選択肢外の回答は棄権として None）
+    pub choice: Option<String>,
+    pub rationale: String,
+}
+
+/// 選択肢ごとの得票
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VoteTally {
+    pub option: String,
+    pub count: usize,
+}
+
+/// 投票結果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VoteResult {
+    pub id: i64,
+    pub session_id: i64,
+    pub question: String,
+    pub options: Vec<String>,
+    pub votes: Vec<Vote>,
+    /// 選択肢の順に並べた得票数
+    pub tally: Vec<VoteTally>,
+    /// 最多得票の選択肢（同数1位・有効票なしの場合は None）
+    pub winner: Option<String>,
+    /// 全員が同じ選択肢に投票した場合 true（棄権がある場合は false）
+    pub unanimous: bool,
+    pub model: String,
+    pub created_at: String,
+}
+
+/// モデルの回答（{ "choice": ..., "rationale": ... }）
+#[derive(Debug, Deserialize)]
+struct RawVote {
+    #[serde(default)]
+    choice: String,
+    #[serde(default)]
+    rationale: String,
+}
+
+/// 質問と選択肢の前後の空白・空の選択肢・重複を除き、数と長さを確認する
+fn validate(question: &str, options: Vec<String>) -> Result<(String, Vec<String>), String> {
+    let question = question.trim().to_string();
+    if question.is_empty() {
+        return Err("質問が指定されていません".into());
+    }
+    if question.chars().count() > MAX_QUESTION_CHARS {
+        return Err(format!("質問は{}文字以内で指定してください", MAX_QUESTION_CHARS));
+    }
+    let mut cleaned: Vec<String> = Vec::new();
+    for option in options.iter().map(|o| o.trim()).filter(|o| !o.is_empty()) {
+        if option.chars().count() > MAX_OPTION_CHARS {
+            return Err(format!("選択肢は{}文字以内で指定してください", MAX_OPTION_CHARS));
+        }
+        if !cleaned.iter().any(|o| o == option) {
+            cleaned.push(option.to_string());
+        }
+    }
+    if !(MIN_OPTIONS..=MAX_OPTIONS).contains(&cleaned.len()) {
+        return Err(format!("選択肢は{}〜{}個で指定してください", MIN_OPTIONS, MAX_OPTIONS));
+    }
+    Ok((question, cleaned))
+}
+
+/// JSON モードで渡す出力スキーマ（choice は選択肢のいずれか）
+fn output_format(options: &[String]) -> OutputFormat {
+    OutputFormat::Schema(json!({
+        "type": "object",
+        "properties": {
+            "choice": { "type": "string", "enum": options },
+            "rationale": { "type": "string" }
+        },
+        "required": ["choice", "rationale"]
+    }))
+}
+
+/// 回答の選択肢を正規化（完全一致、なければ前後の空白・括弧を除いて一致するもの）
+fn match_option(choice: &str, options: &[String]) -> Option<String> {
+    let trimmed = choice.trim().trim_matches(|c| matches!(c, '「' | '」' | '"' | '\''));
+    options.iter().find(|o| o.as_str() == choice || o.as_str() == trimmed).cloned()
+}
+
+/// 得票数・最多得票・全会一致を集計
+fn tally(options: &[String], votes: &[Vote]) -> (Vec<VoteTally>, Option<String>, bool) {
+    let tally: Vec<VoteTally> = options
+        .iter()
+        .map(|o| VoteTally {
+            option: o.clone(),
+            count: votes.iter().filter(|v| v.choice.as_deref() == Some(o.as_str())).count(),
+        })
+        .collect();
+    let top = tally.iter().map(|t| t.count).max().unwrap_or(0);
+    let leaders: Vec<&VoteTally> = tally.iter().filter(|t| t.count == top).collect();
+    let winner = (top > 0 && leaders.len() == 1).then(|| leaders[0].option.clone());
+    let unanimous = !votes.is_empty() && winner.is_some() && top == votes.len();
+    (tally, winner, unanimous)
+}
+
+// AI参加者ごとに投票と理由を求め、集計してセッションに保存する
+#[command]
+pub async fn run_vote(
+    app: AppHandle,
+    session_id: i64,
+    question: String,
+    options: Vec<String>,
+    model: Option<String>,
+) -> Result<VoteResult, String> {
+    let (question, options) = validate(&question, options)?;
+    let session = db::load_session(&app, session_id).await?;
+    let model = model.unwrap_or_else(|| session.model.clone());
+    info!("run_vote 呼び出し: session_id={}, 選択肢={}, model={}", session_id, options.len(), model);
+    if !is_allowed_model(&app, &model) {
+        return Err(ERR_UNSUPPORTED_MODEL.to_string());
+    }
+    if session.participants.ai_data.is_empty() {
+        return Err("投票できるAI参加者がいません".into());
+    }
+
+    let history = session.history_text();
+    let generation = GenerationOptions { temperature: Some(VOTE_TEMPERATURE), ..Default::default() }
+        .with_format(output_format(&options));
+    let language = session_settings::load(&app, session_id).await?.language();
+    let mut votes = Vec::with_capacity(session.participants.ai_data.len());
+    // 他の参加者の投票に引きずられないよう、1人ずつ独立したプロンプトで聞く
+    for participant in &session.participants.ai_data {
+        let prompt = prompts::build_vote_prompt(
+            &participant.name,
+            &participant.role,
+            &participant.description,
+            &session.topic,
+            &history,
+            &question,
+            &options,
+            language,
+        );
+        let vote = match call_ollama_generate_with(&app, &model, &prompt, &generation).await {
+            Ok(raw) => match llm_json::parse_llm_json::<RawVote>(&raw) {
+                Ok(raw_vote) => Vote {
+                    participant: participant.name.clone(),
+                    choice: match_option(&raw_vote.choice, &options),
+                    rationale: raw_vote.rationale.trim().to_string(),
+                },
+                Err(e) => {
+                    warn!("投票の解析失敗 ({}): {}", participant.name, e);
+                    Vote { participant: participant.name.clone(), choice: None, rationale: String::new() }
+                }
+            },
+            Err(e) => {
+                warn!("投票の生成失敗 ({}): {}", participant.name, e);
+                Vote { participant: participant.name.clone(), choice: None, rationale: String::new() }
+            }
+        };
+        votes.push(vote);
+    }
+    if votes.iter().all(|v| v.choice.is_none()) {
+        return Err("有効な投票がありませんでした".into());
+    }
+
+    let pool = db::pool(&app).await?;
+    let options_json = serde_json::to_string(&options).map_err(|e| format!("選択肢のシリアライズ失敗: {}", e))?;
+    let payload = serde_json::to_string(&votes).map_err(|e| format!("投票のシリアライズ失敗: {}", e))?;
+    let keys = encryption::keys(&app);
+    let created_at = db::now_string();
+    let id = sqlx::query(
+        "INSERT INTO votes (session_id, question, options, payload, model, created_at) VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(session_id)
+    .bind(keys.seal(&question)?)
+    .bind(keys.seal(&options_json)?)
+    .bind(keys.seal(&payload)?)
+    .bind(&model)
+    .bind(&created_at)
+    .execute(&pool)
+    .await
+    .map_err(|e| format!("投票結果保存失敗: {}", e))?
+    .last_insert_rowid();
+
+    let (tally, winner, unanimous) = tally(&options, &votes);
+    info!("投票完了: session_id={}, 最多得票={:?}", session_id, winner);
+    Ok(VoteResult { id, session_id, question, options, votes, tally, winner, unanimous, model, created_at })
+}
+
+// セッションの投票結果を古い順に取得
+#[command]
+pub async fn list_votes(app: AppHandle, session_id: i64) -> Result<Vec<VoteResult>, String> {
+    let pool = db::pool(&app).await?;
+    let rows: Vec<(i64, String, String, String, String, String)> = sqlx::query_as(
+        "SELECT id, question, options, payload, model, created_at FROM votes WHERE session_id = ? ORDER BY id",
+    )
+    .bind(session_id)
+    .fetch_all(&pool)
+    .await
+    .map_err(|e| format!("投票結果取得失敗: {}", e))?;
+    let keys = encryption::keys(&app);
+    rows.into_iter()
+        .map(|(id, question, options, payload, model, created_at)| {
+            let question = keys.open(&question)?;
+            let options: Vec<String> = serde_json::from_str(&keys.open(&options)?).unwrap_or_default();
+            let votes: Vec<Vote> = serde_json::from_str(&keys.open(&payload)?).unwrap_or_default();
+            let (tally, winner, unanimous) = tally(&options, &votes);
+            Ok(VoteResult { id, session_id, question, options, votes, tally, winner, unanimous, model, created_at })
+        })
+        .collect()
+}
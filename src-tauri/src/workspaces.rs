@@ -0,0 +1,388 @@
+// ワークスペースとデータベースの保存先
+// 仕事用・個人用など、ワークスペースごとに別の SQLite ファイルへ保存して議論を分ける
+// 一覧・選択中のワークスペース・保存先ディレクトリは DB の外（アプリ設定ディレクトリの workspaces.json）に置く
+// 既定のワークスペースは従来の dewai.db をそのまま使い、アプリ設定もワークスペースごとに別になる
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sqlx::error::BoxDynError;
+use sqlx::migrate::{Migration as SqlxMigration, MigrationSource, Migrator};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode};
+use sqlx::SqlitePool;
+use tauri::{command, AppHandle, Manager};
+use tauri_plugin_sql::{DbInstances, DbPool, MigrationKind};
+use tracing::{info, warn};
+
+use crate::{app_state::AppState, audit, config, db, discussion_engine, encryption, prompt_templates};
+
+const REGISTRY_FILE: &str = "workspaces.json";
+const DEFAULT_WORKSPACE_ID: &str = "default";
+const DEFAULT_WORKSPACE_NAME: &str = "既定";
+const DEFAULT_DB_FILE: &str = "dewai.db";
+// ワークスペース名の最大文字数
+const MAX_NAME_CHARS: usize = 40;
+
+/// workspaces.json の1件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WorkspaceEntry {
+    id: String,
+    name: String,
+    /// 保存先ディレクトリ内のファイル名
+    file: String,
+    created_at: String,
+}
+
+/// workspaces.json の内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Registry {
+    /// データベースの保存先（未指定ならアプリ設定ディレクトリ）
+    #[serde(default)]
+    db_dir: Option<String>,
+    active: String,
+    #[serde(default)]
+    workspaces: Vec<WorkspaceEntry>,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Registry { db_dir: None, active: DEFAULT_WORKSPACE_ID.to_string(), workspaces: Vec::new() }
+    }
+}
+
+impl Registry {
+    /// 既定のワークスペースを補い、選択中のものが消えていれば既定に戻す
+    fn normalized(mut self) -> Self {
+        if !self.workspaces.iter().any(|w| w.id == DEFAULT_WORKSPACE_ID) {
+            self.workspaces.insert(
+                0,
+                WorkspaceEntry {
+                    id: DEFAULT_WORKSPACE_ID.to_string(),
+                    name: DEFAULT_WORKSPACE_NAME.to_string(),
+                    file: DEFAULT_DB_FILE.to_string(),
+                    created_at: db::now_string(),
+                },
+            );
+        }
+        if !self.workspaces.iter().any(|w| w.id == self.active) {
+            self.active = DEFAULT_WORKSPACE_ID.to_string();
+        }
+        self
+    }
+
+    fn find(&self, id: &str) -> Option<&WorkspaceEntry> {
+        self.workspaces.iter().find(|w| w.id == id)
+    }
+}
+
+/// ワークスペース1件（list_workspaces などの戻り値）
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Workspace {
+    pub id: String,
+    pub name: String,
+    pub db_path: String,
+    pub active: bool,
+    pub created_at: String,
+}
+
+/// list_workspaces の戻り値
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceList {
+    pub active_id: String,
+    /// 選択中のワークスペースの接続URL（フロントエンドの Database.get に渡す）
+    pub db_url: String,
+    /// 保存先ディレクトリ（未指定なら None）
+    pub db_dir: Option<String>,
+    pub workspaces: Vec<Workspace>,
+}
+
+fn config_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path().app_config_dir().map_err(|e| format!("アプリ設定の場所を取得できません: {}", e))
+}
+
+async fn load_registry(app: &AppHandle) -> Result<Registry, String> {
+    let path = config_dir(app)?.join(REGISTRY_FILE);
+    let registry = match tokio::fs::read_to_string(&path).await {
+        Ok(text) => serde_json::from_str(&text).map_err(|e| format!("{} の読み込み失敗: {}", REGISTRY_FILE, e))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Registry::default(),
+        Err(e) => return Err(format!("{} の読み込み失敗: {}", REGISTRY_FILE, e)),
+    };
+    Ok(registry.normalized())
+}
+
+async fn save_registry(app: &AppHandle, registry: &Registry) -> Result<(), String> {
+    let dir = config_dir(app)?;
+    tokio::fs::create_dir_all(&dir).await.map_err(|e| format!("フォルダ作成失敗: {}", e))?;
+    let text = serde_json::to_string_pretty(registry).map_err(|e| e.to_string())?;
+    // 書きかけのファイルが残らないよう一時ファイルから置き換える
+    let tmp = dir.join(format!("{}.tmp", REGISTRY_FILE));
+    tokio::fs::write(&tmp, text).await.map_err(|e| format!("{} の保存失敗: {}", REGISTRY_FILE, e))?;
+    tokio::fs::rename(&tmp, dir.join(REGISTRY_FILE)).await.map_err(|e| format!("{} の保存失敗: {}", REGISTRY_FILE, e))
+}
+
+/// ワークスペースのデータベースファイルの場所
+fn db_path(app: &AppHandle, db_dir: Option<&str>, entry: &WorkspaceEntry) -> Result<PathBuf, String> {
+    let dir = match db_dir {
+        Some(dir) => PathBuf::from(dir),
+        None => config_dir(app)?,
+    };
+    Ok(dir.join(&entry.file))
+}
+
+/// 接続URL（既定の場所の既定ワークスペースは preload と同じ DB_URL）
+fn db_url(db_dir: Option<&str>, entry: &WorkspaceEntry, path: &Path) -> String {
+    if db_dir.is_none() && entry.id == DEFAULT_WORKSPACE_ID {
+        db::DB_URL.to_string()
+    } else {
+        format!("sqlite:{}", path.display())
+    }
+}
+
+type MigrationFuture = Pin<Box<dyn Future<Output = Result<Vec<SqlxMigration>, BoxDynError>> + Send>>;
+
+/// db::migrations() を sqlx のマイグレーションとして渡す（preload 以外のファイルに適用するため）
+/// 記録される内容は SQL プラグインが適用した場合と同じにする
+#[derive(Debug)]
+struct WorkspaceMigrations;
+
+impl MigrationSource<'static> for WorkspaceMigrations {
+    fn resolve(self) -> MigrationFuture {
+        Box::pin(async move {
+            Ok(db::migrations()
+                .into_iter()
+                .filter(|m| matches!(m.kind, MigrationKind::Up))
+                .map(|m| SqlxMigration::new(m.version, m.description.into(), m.kind.into(), m.sql.into(), false))
+                .collect())
+        })
+    }
+}
+
+/// データベースを開いてマイグレーションを適用し、SQL プラグインのプールに登録する（登録済みなら何もしない）
+async fn open(app: &AppHandle, url: &str, path: &Path) -> Result<(), String> {
+    let instances = app
+        .try_state::<DbInstances>()
+        .ok_or_else(|| "データベースが初期化されていません".to_string())?;
+    if instances.0.read().await.contains_key(url) {
+        return Ok(());
+    }
+    if let Some(dir) = path.parent() {
+        tokio::fs::create_dir_all(dir).await.map_err(|e| format!("フォルダ作成失敗: {}", e))?;
+    }
+    let options = SqliteConnectOptions::new()
+        .filename(path)
+        .create_if_missing(true)
+        .journal_mode(SqliteJournalMode::Wal);
+    let pool = SqlitePool::connect_with(options).await.map_err(|e| format!("データベースを開けません: {}", e))?;
+    let migrator = Migrator::new(WorkspaceMigrations).await.map_err(|e| format!("マイグレーション準備失敗: {}", e))?;
+    migrator.run(&pool).await.map_err(|e| format!("マイグレーション失敗: {}", e))?;
+    instances.0.write().await.insert(url.to_string(), DbPool::Sqlite(pool));
+    info!("データベースを開きました: {}", path.display());
+    Ok(())
+}
+
+/// 選択中のワークスペースを開いて以後の接続先にする
+async fn activate(app: &AppHandle, registry: &Registry) -> Result<String, String> {
+    let entry = registry.find(&registry.active).ok_or_else(|| "ワークスペースが見つかりません".to_string())?;
+    let path = db_path(app, registry.db_dir.as_deref(), entry)?;
+    let url = db_url(registry.db_dir.as_deref(), entry, &path);
+    open(app, &url, &path).await?;
+    // 以後の db::pool() が返すデータベースを切り替える
+    app.state::<AppState>().db_url.set(url.clone());
+    Ok(url)
+}
+
+/// 切り替え後のワークスペースの設定・テンプレート・暗号化の状態を反映する
+async fn reload_settings(app: &AppHandle) {
+    encryption::reload(app).await;
+    match config::load(app).await {
+        Ok(settings) => config::apply(app, &settings),
+        Err(e) => warn!("設定読込失敗: {}", e),
+    }
+    if let Err(e) = prompt_templates::load(app).await {
+        warn!("テンプレート読込失敗（組み込みを使用）: {}", e);
+    }
+}
+
+/// 起動時に呼ぶ。前回選択していたワークスペースを開く（失敗したら既定のデータベースのまま）
+pub async fn init(app: &AppHandle) {
+    let result = async {
+        let registry = load_registry(app).await?;
+        activate(app, &registry).await
+    }
+    .await;
+    match result {
+        Ok(url) => info!("ワークスペース: {}", url),
+        Err(e) => warn!("ワークスペースを開けません（既定のデータベースを使用）: {}", e),
+    }
+}
+
+fn to_list(app: &AppHandle, registry: &Registry) -> Result<WorkspaceList, String> {
+    let db_dir = registry.db_dir.as_deref();
+    let mut workspaces = Vec::new();
+    let mut active_url = db::DB_URL.to_string();
+    for entry in &registry.workspaces {
+        let path = db_path(app, db_dir, entry)?;
+        let active = entry.id == registry.active;
+        if active {
+            active_url = db_url(db_dir, entry, &path);
+        }
+        workspaces.push(Workspace {
+            id: entry.id.clone(),
+            name: entry.name.clone(),
+            db_path: path.display().to_string(),
+            active,
+            created_at: entry.created_at.clone(),
+        });
+    }
+    Ok(WorkspaceList {
+        active_id: registry.active.clone(),
+        db_url: active_url,
+        db_dir: registry.db_dir.clone(),
+        workspaces,
+    })
+}
+
+fn ensure_idle(app: &AppHandle) -> Result<(), String> {
+    if discussion_engine::any_running(app) {
+        return Err("自動進行中のセッションがあるため切り替えできません".into());
+    }
+    Ok(())
+}
+
+// ワークスペースの一覧と、選択中のワークスペースの接続URLを返す（選択中のものは開いておく）
+#[command]
+pub async fn list_workspaces(app: AppHandle) -> Result<WorkspaceList, String> {
+    info!("list_workspaces 呼び出し");
+    let registry = load_registry(&app).await?;
+    activate(&app, &registry).await?;
+    to_list(&app, &registry)
+}
+
+// ワークスペースを作成する（データベースファイルを作ってマイグレーションを適用する。切り替えはしない）
+#[command]
+pub async fn create_workspace(app: AppHandle, name: String) -> Result<Workspace, String> {
+    info!("create_workspace 呼び出し: name={}", name);
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("ワークスペース名を入力してください".into());
+    }
+    if name.chars().count() > MAX_NAME_CHARS {
+        return Err(format!("ワークスペース名は{}文字以内で入力してください", MAX_NAME_CHARS));
+    }
+    let mut registry = load_registry(&app).await?;
+    if registry.workspaces.iter().any(|w| w.name == name) {
+        return Err(format!("同じ名前のワークスペースがあります: {}", name));
+    }
+    // 名前は日本語も使えるため、ファイル名には連番を使う
+    let next = registry
+        .workspaces
+        .iter()
+        .filter_map(|w| w.id.strip_prefix("ws").and_then(|n| n.parse::<u32>().ok()))
+        .max()
+        .unwrap_or(0)
+        + 1;
+    let id = format!("ws{}", next);
+    let entry = WorkspaceEntry {
+        file: format!("dewai-{}.db", id),
+        id,
+        name: name.to_string(),
+        created_at: db::now_string(),
+    };
+    let path = db_path(&app, registry.db_dir.as_deref(), &entry)?;
+    if tokio::fs::try_exists(&path).await.unwrap_or(false) {
+        return Err(format!("同名のデータベースファイルが既にあります: {}", path.display()));
+    }
+    open(&app, &db_url(registry.db_dir.as_deref(), &entry, &path), &path).await?;
+    registry.workspaces.push(entry.clone());
+    save_registry(&app, &registry).await?;
+    audit::record("workspace", "create", json!({ "id": entry.id, "name": entry.name }));
+    Ok(Workspace {
+        id: entry.id,
+        name: entry.name,
+        db_path: path.display().to_string(),
+        active: false,
+        created_at: entry.created_at,
+    })
+}
+
+// ワークスペースを切り替える（以後の保存・読み込みはそのワークスペースのデータベースに対して行う）
+// 自動進行中のセッションがある間は切り替えできない
+#[command]
+pub async fn switch_workspace(app: AppHandle, id: String) -> Result<WorkspaceList, String> {
+    info!("switch_workspace 呼び出し: id={}", id);
+    ensure_idle(&app)?;
+    let mut registry = load_registry(&app).await?;
+    if registry.find(&id).is_none() {
+        return Err(format!("ワークスペースが見つかりません: {}", id));
+    }
+    registry.active = id;
+    activate(&app, &registry).await?;
+    save_registry(&app, &registry).await?;
+    reload_settings(&app).await;
+    audit::record("workspace", "switch", json!({ "id": registry.active }));
+    to_list(&app, &registry)
+}
+
+/// 開いているワークスペースのプール（開いていなければ None）
+async fn opened_pool(app: &AppHandle, url: &str) -> Option<SqlitePool> {
+    let instances = app.try_state::<DbInstances>()?;
+    let map = instances.0.read().await;
+    map.get(url).map(|DbPool::Sqlite(pool)| pool.clone())
+}
+
+/// ワークスペースのファイルを新しい場所にコピーする（開いているものは VACUUM INTO で書き出す）
+/// コピー元のファイルがなければ false
+async fn copy_database(app: &AppHandle, url: &str, from: &Path, to: &Path) -> Result<bool, String> {
+    if let Some(pool) = opened_pool(app, url).await {
+        sqlx::query("VACUUM INTO ?")
+            .bind(to.display().to_string())
+            .execute(&pool)
+            .await
+            .map_err(|e| format!("データベースのコピー失敗: {}", e))?;
+        return Ok(true);
+    }
+    if !tokio::fs::try_exists(from).await.unwrap_or(false) {
+        return Ok(false);
+    }
+    tokio::fs::copy(from, to).await.map_err(|e| format!("データベースのコピー失敗: {}", e))?;
+    Ok(true)
+}
+
+// データベースの保存先ディレクトリを変更する（None でアプリ設定ディレクトリに戻す）
+// 新しい場所に同名のファイルがないワークスペースは現在のファイルをコピーし、元のファイルは残す
+#[command]
+pub async fn set_db_directory(app: AppHandle, dir: Option<String>) -> Result<WorkspaceList, String> {
+    info!("set_db_directory 呼び出し: dir={:?}", dir);
+    ensure_idle(&app)?;
+    let dir = dir.map(|d| d.trim().to_string()).filter(|d| !d.is_empty());
+    if let Some(dir) = &dir {
+        if !Path::new(dir).is_absolute() {
+            return Err("保存先は絶対パスで指定してください".into());
+        }
+        tokio::fs::create_dir_all(dir).await.map_err(|e| format!("フォルダ作成失敗: {}", e))?;
+    }
+    let mut registry = load_registry(&app).await?;
+    for entry in &registry.workspaces {
+        let from = db_path(&app, registry.db_dir.as_deref(), entry)?;
+        let to = db_path(&app, dir.as_deref(), entry)?;
+        if from == to || tokio::fs::try_exists(&to).await.unwrap_or(false) {
+            continue;
+        }
+        let url = db_url(registry.db_dir.as_deref(), entry, &from);
+        if copy_database(&app, &url, &from, &to).await? {
+            info!("データベースをコピー: {} -> {}", from.display(), to.display());
+        }
+    }
+    registry.db_dir = dir;
+    activate(&app, &registry).await?;
+    save_registry(&app, &registry).await?;
+    reload_settings(&app).await;
+    audit::record("workspace", "set_db_directory", json!({ "dir": registry.db_dir }));
+    to_list(&app, &registry)
+}
@@ -53,6 +53,26 @@ export const showSessionDeleteSuccess = (sessionTopic: string) => {
   })
 }
 
+/** セッションのエクスポート 成功 */
+export const showSessionExportSuccess = (path: string) => {
+  showNotification({
+    type: "success",
+    title: "セッションをエクスポートしました",
+    description: path,
+    duration: 4000,
+  })
+}
+
+/** AIプロフィール保存 成功 */
+export const showProfileSaveSuccess = (name: string) => {
+  showNotification({
+    type: "success",
+    title: "プロフィールを保存しました",
+    description: `「${name}」をライブラリに追加しました。`,
+    duration: 3000,
+  })
+}
+
 /** セッション削除 エラー */
 export const showSessionDeleteError = (error: string) => {
   showNotification({
@@ -1,14 +1,861 @@
 import { useState, useEffect } from 'react';
 import { invoke } from '@tauri-apps/api/core';
-import { jsonrepair } from 'jsonrepair';
+import { listen } from '@tauri-apps/api/event';
+import type { DiscussionAnalysis, TalkMessage } from '../pages/play/PlayTypes';
+import { setDatabaseUrl } from '../utils/database';
 
 /**
- * 許可するOllamaモデルの接頭辞一覧。
- * Gemma3 1B/4BのみをUI上で選択可能にします。
+ * 既定で許可されるOllamaモデルの接頭辞一覧。
+ * 実際の許可モデルはバックエンドの設定（set_allowed_models）に従い、get_available_models が許可済みのみを返します。
  * @internal
  */
-const ALLOWED_PREFIXES = ['gemma3:1b', 'gemma3:4b'];
-const isAllowedModel = (m: string) => ALLOWED_PREFIXES.some(p => m?.startsWith(p));
+const DEFAULT_PREFIXES = ['gemma3:1b', 'gemma3:4b'];
+const isDefaultModel = (m: string) => DEFAULT_PREFIXES.some(p => m?.startsWith(p));
+
+/**
+ * 生成のメタデータ（ターンごとのトークン数・待ち時間の表示用）。
+ */
+export interface GenerationMeta {
+  model: string;
+  /** 入力トークン数（バックエンドが返さない場合は null） */
+  promptTokens?: number | null;
+  /** 出力トークン数 */
+  completionTokens?: number | null;
+  /** 生成にかかった時間（ミリ秒） */
+  durationMs?: number | null;
+  /** 出力の生成速度（トークン/秒） */
+  tokensPerSec?: number | null;
+  /** 失敗して再送した回数 */
+  retries?: number | null;
+  /** 生成キューの待ち時間と再送を含む実時間（ミリ秒） */
+  wallMs?: number | null;
+}
+
+/**
+ * 生成系コマンドの戻り値（本文 + メタデータ）。
+ */
+export interface GenerationResult extends GenerationMeta {
+  text: string;
+  /** 生成時のシード（指定・採番した場合のみ） */
+  seed?: number;
+}
+
+/**
+ * AI参加者の応答と、その生成に使ったシード・メタデータ。
+ */
+export interface AIResponse extends GenerationResult {
+  /** 生成時のシード（同じ条件での再現・不具合報告用） */
+  seed: number;
+}
+
+/** 並行生成を依頼する参加者（generate_responses_parallel の participants 要素）。 */
+export interface ParticipantSpec {
+  name: string;
+  role: string;
+  description: string;
+  /** 参加者ごとのシード（省略時はバックエンドで採番） */
+  seed?: number;
+}
+
+/** 並行生成の参加者1人分の結果（generate://participant-response のペイロード）。 */
+export interface ParticipantResponse {
+  requestId: string | null;
+  participantName: string;
+  /** participants 内の位置 */
+  index: number;
+  response: AIResponse | null;
+  error: string | null;
+}
+
+/** メモリに読み込まれているモデル（/api/ps） */
+export interface LoadedModel {
+  name: string;
+  size: number;
+  sizeVram: number;
+  expiresAt: string | null;
+}
+
+/** Ollama の状態（get_ollama_status / ollama://status-changed） */
+export interface OllamaStatus {
+  state: 'unknown' | 'down' | 'up' | 'modelLoaded';
+  version: string | null;
+  loadedModels: LoadedModel[];
+  /** 最後に応答があった時刻（UTC） */
+  lastSeen: string | null;
+  checkedAt: string | null;
+  error: string | null;
+}
+
+/** サーキットブレーカーの状態（get_circuit_breaker_status / backend://degraded） */
+export interface BreakerStatus {
+  state: 'closed' | 'open' | 'halfOpen';
+  /** 失敗が続いて生成の送信を止めている */
+  degraded: boolean;
+  consecutiveFailures: number;
+  /** 送信を再開するまでの秒数（停止中のみ） */
+  retryAfterSecs: number | null;
+  lastError: string | null;
+  trips: number;
+}
+
+/** ensure_ollama_running の結果 */
+export interface BootstrapStatus {
+  outcome: 'alreadyRunning' | 'started' | 'notInstalled' | 'skipped' | 'failed';
+  binaryPath: string | null;
+  message: string;
+}
+
+/** 初回セットアップの進捗（setup://progress） */
+export interface SetupProgress {
+  step: 'prerequisites' | 'install' | 'smokeTest';
+  item: string;
+  status: 'running' | 'done' | 'failed';
+  message: string;
+}
+
+/** check_prerequisites の結果 */
+export interface Prerequisites {
+  ollamaInstalled: boolean;
+  ollamaBinary: string | null;
+  ollamaRunning: boolean;
+  ollamaVersion: string | null;
+  installedModels: string[];
+  recommendedModel: string;
+  recommendedModelInstalled: boolean;
+  totalMemoryBytes: number;
+  availableMemoryBytes: number;
+  freeDiskBytes: number | null;
+  checks: Array<{ name: string; ok: boolean; detail: string }>;
+  ready: boolean;
+}
+
+/** run_smoke_test の結果 */
+export interface SmokeTestResult {
+  model: string;
+  ok: boolean;
+  latencyMs: number;
+  response: string | null;
+  error: string | null;
+}
+
+/** system_info の結果 */
+export interface SystemInfo {
+  totalMemoryBytes: number;
+  availableMemoryBytes: number;
+  cpuCores: number;
+  physicalCores: number | null;
+  cpuBrand: string;
+  hasGpu: boolean;
+  gpu: { kind: string; detail: string } | null;
+}
+
+/** recommend_model の結果 */
+export interface ModelRecommendation {
+  recommended: string;
+  reason: string;
+  /** 指定したモデルを読み込む前の警告（メモリ不足など） */
+  warning: string | null;
+  system: SystemInfo;
+}
+
+/** test_openai_connection の結果 */
+export interface ConnectionTest {
+  ok: boolean;
+  /** サーバーのモデルID */
+  models: string[];
+  error: string | null;
+}
+
+/** list_curated_gguf の要素（candle 用に取得できる GGUF） */
+export interface CuratedGguf {
+  id: string;
+  modelName: string;
+  repo: string;
+  file: string;
+  approxSize: number;
+  description: string;
+  installed: boolean;
+}
+
+/** gguf://download-progress のペイロード */
+export interface GgufDownloadProgress {
+  id: string;
+  file: string;
+  downloaded: number;
+  total: number | null;
+  percent: number | null;
+  resumed: boolean;
+}
+
+/** download_gguf の結果 */
+export interface DownloadedGguf {
+  id: string;
+  modelName: string;
+  modelPath: string;
+  tokenizerPath: string;
+}
+
+/** list_local_gguf の要素 */
+export interface LocalGguf {
+  id: string;
+  modelName: string | null;
+  modelPath: string;
+  size: number;
+  hasTokenizer: boolean;
+  /** 途中まで取得したファイルがある（downloadGguf で再開できる） */
+  partial: boolean;
+  /** candle の設定で使用中 */
+  active: boolean;
+}
+
+/** get_models_dir_usage の結果 */
+export interface ModelsDirUsage {
+  path: string;
+  usedBytes: number;
+  modelCount: number;
+}
+
+/** 編集できるプロンプトテンプレートの種類 */
+export type PromptTemplateKind =
+  | 'ai_response'
+  | 'devils_advocate'
+  | 'discussion_start'
+  | 'discussion_analysis'
+  | 'incremental_analysis'
+  | 'discussion_summary'
+  | 'incremental_summary'
+  | 'summary_merge'
+  | 'ai_profiles'
+  | 'next_speaker'
+  | 'session_qa'
+  | 'session_tags'
+  | 'participant_memory'
+  | 'persona_state'
+  | 'moderator_intervention'
+  | 'topic_drift'
+  | 'action_items'
+  | 'ai_profiles_repair';
+
+/** get_template などの戻り値 */
+export interface PromptTemplate {
+  kind: PromptTemplateKind;
+  body: string;
+  defaultBody: string;
+  /** 使えるプレースホルダー（{discussion_topic} の中の名前） */
+  placeholders: string[];
+  /** 省けないプレースホルダー */
+  required: string[];
+  customized: boolean;
+  updatedAt: string | null;
+}
+
+/** 次の話者の候補ごとの順位付け */
+export interface SpeakerCandidateScore {
+  name: string;
+  score: number;
+  notes: string[];
+}
+
+/** suggest_next_speaker の結果 */
+export interface SpeakerSuggestion {
+  speaker: string;
+  reason: string;
+  /** model: モデルが選んだ / heuristic: 手がかりの順位で選んだ */
+  source: 'model' | 'heuristic';
+  candidates: SpeakerCandidateScore[];
+}
+
+/** 形式のある議論（debate: ディベート / brainstorm: ブレインストーミング / six-hats: 六つの思考帽子） */
+export type DiscussionFormat = 'debate' | 'brainstorm' | 'six-hats';
+
+/** discussion://phase-changed のペイロード */
+export interface PhaseChangedEvent {
+  sessionId: number;
+  format: DiscussionFormat;
+  phase: string;
+  /** 1始まりのフェーズ番号 */
+  index: number;
+  total: number;
+}
+
+/** round://started / round://time-warning / round://ended のペイロード */
+export interface RoundEvent {
+  sessionId: number;
+  round: number;
+  totalRounds: number;
+  timeLimitSecs: number | null;
+  /** 残り秒数（持ち時間がなければ null） */
+  remainingSecs: number | null;
+  /** round://ended のときだけ */
+  reason: 'completed' | 'time-up' | 'stopped' | null;
+}
+
+/** ask_session の回答が根拠にした発言 */
+export interface MessageReference {
+  /** 1始まりの発言番号（回答中の [#番号]） */
+  number: number;
+  /** セッション内の位置（0始まり） */
+  index: number;
+  speaker: string;
+  quote: string;
+  timestamp: string;
+}
+
+/** ask_session の結果 */
+export interface SessionAnswer {
+  answer: string;
+  references: MessageReference[];
+  /** 要約を根拠に含めたか */
+  usedSummary: boolean;
+}
+
+/** semantic_search の結果1件 */
+export interface SemanticHit {
+  sessionId: number;
+  topic: string;
+  /** セッション内の位置（0始まり） */
+  position: number;
+  speaker: string;
+  snippet: string;
+  /** コサイン類似度（大きいほど近い） */
+  score: number;
+}
+
+/** find_related_sessions の結果1件 */
+export interface RelatedSession {
+  sessionId: number;
+  topic: string;
+  updatedAt: string;
+  /** 近さ（embedding はコサイン類似度、keywords はタグとテーマの重なり 0〜1） */
+  score: number;
+  by: 'embedding' | 'keywords';
+  sharedTags: string[];
+  /** 最新の要約（なければ null） */
+  summary: string | null;
+}
+
+/** セッションの添付資料（attach_document / list_attachments の戻り値） */
+export interface AttachmentInfo {
+  id: number;
+  sessionId: number;
+  fileName: string;
+  kind: 'text' | 'markdown' | 'pdf' | 'web';
+  chars: number;
+  chunks: number;
+  /** ベクトル化できたチャンクの数 */
+  embeddedChunks: number;
+  createdAt: string;
+}
+
+/** Webページの取り込み結果（fetch_url_context の戻り値） */
+export interface UrlContext {
+  url: string;
+  title: string;
+  /** トークン予算に収めた本文 */
+  text: string;
+  tokens: number;
+  truncated: boolean;
+  /** キャッシュ（24時間）から返したか */
+  cached: boolean;
+  fetchedAt: string;
+  /** sessionId 指定時に作成した添付資料のID */
+  attachmentId: number | null;
+}
+
+/** タグと、そのタグが付いたセッションの数（list_tags の戻り値） */
+export interface TagCount {
+  tag: string;
+  sessions: number;
+}
+
+/** get_sessions_page の絞り込み・並び替えの条件 */
+export interface SessionPageFilter {
+  /** テーマに含まれる文字列 */
+  query?: string;
+  /** すべて付いているセッションだけに絞り込むタグ */
+  tags?: string[];
+  /** 作成日時の範囲（'YYYY-MM-DD' または 'YYYY-MM-DD HH:MM:SS'） */
+  createdFrom?: string;
+  createdTo?: string;
+  sortBy?: 'lastOpened' | 'updated' | 'created' | 'topic';
+  order?: 'asc' | 'desc';
+}
+
+/** セッション一覧の1行（発言本文を含まない軽量なメタデータ） */
+export interface SessionListItem {
+  id: number;
+  topic: string;
+  participants: string[];
+  messageCount: number;
+  model: string;
+  createdAt: string;
+  updatedAt: string;
+  lastOpenedAt: string | null;
+  tags: string[];
+}
+
+/** get_sessions_page の戻り値 */
+export interface SessionPage {
+  items: SessionListItem[];
+  /** 条件に合うセッションの総数 */
+  total: number;
+  offset: number;
+  limit: number;
+  hasMore: boolean;
+}
+
+/** 複製・分岐で作られたセッションの元の情報（get_session_lineage の戻り値） */
+export interface SessionLineage {
+  /** 元のセッション（削除済みなら null） */
+  parentId: number | null;
+  kind: 'duplicate' | 'fork';
+  /** 分岐した発言の位置（0始まり。複製なら null） */
+  messageIndex: number | null;
+  createdAt: string;
+}
+
+/** messages テーブルの1行（edit_message / regenerate_message の戻り値） */
+export interface MessageRow {
+  /** 行ID（追記・編集・再生成・ほかの発言の削除の前後で変わらない） */
+  id: number;
+  sessionId: number;
+  /** セッション内の位置（0始まり） */
+  position: number;
+  speaker: string;
+  role: 'user' | 'ai';
+  content: string;
+  createdAt: string;
+  /** シード・editedAt / regeneratedAt など、本文以外の項目 */
+  metadata: Record<string, unknown>;
+}
+
+/** クラッシュ前の状態（recover_unsaved_session の戻り値） */
+export interface RecoveredSession {
+  sessionId: number;
+  topic: string;
+  participants: { userParticipates: boolean; aiData: { name: string; role: string; description: string }[] };
+  /** チェックポイント時点までの発言 */
+  messages: TalkMessage[];
+  model: string;
+  summary: string | null;
+  analysis: DiscussionAnalysis | null;
+  /** 自動進行が途中で止まっている（resumePendingRuns で再開できる） */
+  pendingRun: boolean;
+  checkpointAt: string;
+}
+
+/** データベースの整合性チェックの結果（check_db_integrity の戻り値） */
+export interface IntegrityReport {
+  ok: boolean;
+  /** PRAGMA integrity_check が返した問題（正常なら空） */
+  problems: string[];
+  /** 参照先が存在しない外部キー */
+  foreignKeyViolations: string[];
+  journalMode: string;
+  checkedAt: string;
+}
+
+/** VACUUM の結果（vacuum_db の戻り値。サイズはバイト） */
+export interface VacuumReport {
+  sizeBefore: number;
+  sizeAfter: number;
+  freelistPages: number;
+  durationMs: number;
+}
+
+/** ワークスペース（データベースファイル）1件 */
+export interface Workspace {
+  id: string;
+  name: string;
+  dbPath: string;
+  active: boolean;
+  createdAt: string;
+}
+
+/** list_workspaces / switch_workspace / set_db_directory の戻り値 */
+export interface WorkspaceList {
+  activeId: string;
+  /** 選択中のワークスペースの接続URL */
+  dbUrl: string;
+  /** データベースの保存先（未指定ならアプリ設定ディレクトリ） */
+  dbDir: string | null;
+  workspaces: Workspace[];
+}
+
+/** 発言本文の暗号化の状態 */
+export interface EncryptionStatus {
+  enabled: boolean;
+  /** パスフレーズで解除済み（本文を読み書きできる） */
+  unlocked: boolean;
+  /** パスフレーズを OS のキーチェーンに保存済み（起動時に自動で解除する） */
+  remembered: boolean;
+  /** 暗号化しても平文のまま保存される項目（テーマ・参加者名・添付ファイル名・URL・埋め込みベクトル）。設定画面で案内する */
+  plaintext: string[];
+}
+
+/** モデル別（model が null なら全体）の生成計測の集計 */
+export interface MetricsSummary {
+  model: string | null;
+  /** 計測値のあるAIの発言数 */
+  messages: number;
+  promptTokens: number;
+  completionTokens: number;
+  retries: number;
+  avgTokensPerSec: number | null;
+  avgDurationMs: number | null;
+  /** 待ち時間・再送を含む平均の実時間 */
+  avgWallMs: number | null;
+}
+
+/** get_session_metrics の戻り値 */
+export interface SessionMetrics {
+  sessionId: number;
+  aiMessages: number;
+  /** 計測値のないAIの発言数 */
+  unmeasured: number;
+  total: MetricsSummary;
+  byModel: MetricsSummary[];
+}
+
+/** ベンチマークのプロンプト集（quick: 3件, standard: 6件） */
+export type BenchmarkPromptSet = 'quick' | 'standard';
+
+/** ベンチマークの1件分の結果 */
+export interface BenchmarkCaseResult {
+  topic: string;
+  /** 生成キューの待ちを除いた応答時間 */
+  latencyMs: number | null;
+  tokensPerSec: number | null;
+  completionTokens: number | null;
+  /** 審査モデルによる質の評価（1〜10） */
+  score: number | null;
+  reason: string | null;
+  output: string | null;
+  error: string | null;
+}
+
+/** ベンチマークのモデル1つ分の結果 */
+export interface ModelBenchmark {
+  model: string;
+  /** 最初の読み込みにかかった時間（計測には含めない） */
+  loadMs: number | null;
+  completed: number;
+  failed: number;
+  avgLatencyMs: number | null;
+  maxLatencyMs: number | null;
+  avgTokensPerSec: number | null;
+  avgScore: number | null;
+  cases: BenchmarkCaseResult[];
+}
+
+/** benchmark_models の戻り値 */
+export interface BenchmarkReport {
+  backend: string;
+  promptSet: BenchmarkPromptSet;
+  judgeModel: string | null;
+  models: ModelBenchmark[];
+  fastest: string | null;
+  bestQuality: string | null;
+  totalMs: number;
+}
+
+/** 名前付きの接続先（app_settings.backendProfiles） */
+export interface BackendProfile {
+  name: string;
+  llmBackend: 'ollama' | 'mock' | 'record' | 'replay' | 'candle' | 'openai';
+  /** Ollama の接続設定（host / port / タイムアウトなど。省略した項目は既定値） */
+  ollama: Record<string, unknown>;
+  /** 切り替えた時に選ぶモデル（空なら選択中のモデルのまま） */
+  model: string;
+}
+
+/** list_backend_profiles の要素 */
+export interface BackendProfileStatus extends BackendProfile {
+  active: boolean;
+  /** 疎通確認の結果（確認しなかった・Ollama 以外なら null） */
+  reachable: boolean | null;
+  version: string | null;
+  models: string[];
+  error: string | null;
+}
+
+/** 生成キューの実行中・待機中の生成1件 */
+export interface QueuedJob {
+  jobId: number;
+  model: string;
+  priority: 'interactive' | 'background';
+  running: boolean;
+  /** 受付から枠を得るまで（待機中なら現在まで）の時間 */
+  waitedMs: number;
+}
+
+/** get_queue_status の戻り値 */
+export interface QueueStatus {
+  /** 同時実行の上限（接続設定の maxParallel） */
+  limit: number;
+  running: number;
+  waitingInteractive: number;
+  waitingBackground: number;
+  coalesced: number;
+  jobs: QueuedJob[];
+}
+
+/** 生成が枠を得て始まった時の通知（queue://job-started） */
+export interface JobStartedEvent {
+  jobId: number;
+  model: string;
+  priority: 'interactive' | 'background';
+  waitedMs: number;
+  running: number;
+  waiting: number;
+}
+
+/** get_latest_summary の戻り値（保存済みの要約） */
+export interface StoredSummary {
+  id: number;
+  /** full: 履歴全体から作った要約、incremental: 前回の要約に新しい発言を反映した要約 */
+  kind: 'full' | 'incremental';
+  summary: string;
+  /** 要約に反映済みの発言数（不明なら null） */
+  covered: number | null;
+  model: string;
+  createdAt: string;
+}
+
+/** 保存済みプロフィールの議論をまたいだ記憶（get_profile_memory の戻り値） */
+export interface ProfileMemory {
+  profileId: number;
+  memory: string;
+  /** 記憶に反映した議論の数 */
+  sessions: number;
+  updatedAt: string;
+}
+
+/** 議論中の参加者の状態（get_persona_states の要素と persona://updated の states） */
+export interface PersonaState {
+  participant: string;
+  /** 他の意見への同意の度合い（0〜1） */
+  agreement: number;
+  /** 苛立ちの強さ（0〜1） */
+  frustration: number;
+  note: string;
+  /** 状態に反映済みの発言数 */
+  covered: number;
+  updatedAt: string;
+}
+
+/** discussion://turn-failed のペイロード（自動進行で発言の生成に失敗した時） */
+export interface TurnFailedEvent {
+  sessionId: number;
+  round: number;
+  speaker: string;
+  /** 同じ手番で何回目の失敗か */
+  attempt: number;
+  error: string;
+  /** やり直さずにこの参加者を飛ばしたか */
+  skipped: boolean;
+}
+
+/** 司会者が介入した理由 */
+export type InterventionReason = 'circular' | 'dominating' | 'topicDrift';
+
+/** facilitator://intervened のペイロード（司会者の発言そのものは discussion://new-message で届く） */
+export interface FacilitatorIntervention {
+  sessionId: number;
+  /** 司会者の発言の位置 */
+  index: number;
+  reason: InterventionReason;
+  situation: string;
+  /** しきい値と比べた値（0〜1） */
+  score: number;
+}
+
+/** テーマからの脱線の判定結果（detect_topic_drift の戻り値） */
+export interface TopicDrift {
+  /** テーマからの離れ具合（0 = テーマどおり、1 = 完全に別の話題） */
+  score: number;
+  /** 逸れていった話題（テーマどおりなら空） */
+  tangent: string;
+  /** 判定時点の発言数 */
+  messageCount: number;
+  model: string;
+}
+
+/** 議論から抜き出したアクションアイテム（extract_action_items / get_action_items の要素） */
+export interface ActionItem {
+  description: string;
+  /** 担当者（名前が挙がっていなければ null） */
+  owner: string | null;
+  /** 期限・時期の手がかり（「来週まで」など。なければ null） */
+  dueHint: string | null;
+}
+
+/** analysis://topic-drift のペイロード（発言 analysisInterval 件ごとの判定で、脱線の度合いがしきい値を超えた時） */
+export interface TopicDriftAlert extends TopicDrift {
+  sessionId: number;
+  threshold: number;
+}
+
+/** シミュレーションの1回分の議論の状態 */
+export type SimulationRepetitionStatus = 'started' | 'completed' | 'failed' | 'cancelled';
+
+/** simulation://progress のペイロード */
+export interface SimulationProgress {
+  simulationId: number;
+  /** 何回目の議論か（0 始まり） */
+  repetition: number;
+  repetitions: number;
+  status: SimulationRepetitionStatus;
+  sessionId: number | null;
+  error: string | null;
+  completed: number;
+  failed: number;
+}
+
+/** simulation://finished のペイロード */
+export interface SimulationFinished {
+  simulationId: number;
+  completed: number;
+  failed: number;
+  cancelled: number;
+  /** 作成したセッション（実行した順） */
+  sessionIds: number[];
+  durationMs: number;
+}
+
+/** 実行中のシミュレーション（list_simulations の要素） */
+export interface SimulationStatus {
+  simulationId: number;
+  topic: string;
+  repetitions: number;
+  completed: number;
+  failed: number;
+  cancelled: number;
+  sessionIds: number[];
+  /** 中止を受け付けて、進行中の議論の停止を待っているか */
+  cancelling: boolean;
+  startedAt: string;
+}
+
+/** ユーザー（人間の参加者）の自己紹介。AI参加者が名前で呼びかけ、専門・立場を踏まえて応答します */
+export interface UserPersona {
+  name: string;
+  /** 役職・専門 */
+  role: string;
+  /** テーマへの立場・考え方 */
+  stance: string;
+}
+
+/** AI参加者プロフィール生成の顔ぶれの条件（generate_ai_profiles の constraints） */
+export interface ProfileConstraints {
+  /** 必ず含める役職（例: "弁護士"）。1つにつき1人 */
+  requiredRoles?: string[];
+  /** 立場の内訳（合計が人数より少なければ残りは自由） */
+  stances?: { pro?: number; con?: number; neutral?: number };
+  formality?: 'casual' | 'neutral' | 'formal';
+  /** 年齢層をばらけさせる */
+  diverseAges?: boolean;
+  /** 職業をばらけさせる */
+  diverseOccupations?: boolean;
+}
+
+/** 長い議論を分割して要約している時の進み具合（summary://progress） */
+export interface SummaryProgress {
+  /** 画面から要約した時は null */
+  sessionId: number | null;
+  /** map: 部分ごとの要約、reduce: 部分の要約の統合 */
+  stage: 'map' | 'reduce';
+  done: number;
+  total: number;
+}
+
+/** 生成結果からメッセージに保存するメタデータだけを取り出します。 */
+export const toGenerationMeta = ({
+  model,
+  promptTokens,
+  completionTokens,
+  durationMs,
+  tokensPerSec,
+  retries,
+  wallMs,
+}: GenerationResult): GenerationMeta => ({
+  model,
+  promptTokens,
+  completionTokens,
+  durationMs,
+  tokensPerSec,
+  retries,
+  wallMs,
+});
+
+/** 生成のタイムアウト（全体の期限・ストリーミングの無通信）で失敗したエラーかを判定します。キャンセルとは区別されます。 */
+export const isTimeoutError = (error: unknown): boolean => String(error).startsWith('生成がタイムアウトしました');
+
+/**
+ * 生成オプション（Ollama の options に対応。未指定の項目はモデル既定値）。
+ */
+export interface GenerationOptions {
+  temperature?: number;
+  topP?: number;
+  topK?: number;
+  numPredict?: number;
+  numCtx?: number;
+  repeatPenalty?: number;
+  /** 固定すると同じ条件で同じ出力を再現できます（テスト実行用） */
+  seed?: number;
+}
+
+/** 分析・要約向けの既定オプション（低温度で安定させる） */
+export const ANALYSIS_OPTIONS: GenerationOptions = { temperature: 0.3 };
+/** 参加者の発言向けの既定オプション（高めの温度で多様にする） */
+export const PERSONA_OPTIONS: GenerationOptions = { temperature: 0.8 };
+
+/**
+ * モデル取得（pull_model）の進捗。`model://pull-progress` イベントのペイロード。
+ */
+export interface PullProgress {
+  model: string;
+  /** Ollama の状態（"pulling manifest", "success" など） */
+  status: string;
+  completed?: number | null;
+  total?: number | null;
+  /** 進捗率（0〜100。サイズ不明の段階では null） */
+  percent?: number | null;
+}
+
+/**
+ * 分析結果の保存先。指定するとバックエンドが発言数ごとのスナップショットを残します。
+ */
+export interface AnalysisSnapshotTarget {
+  sessionId: number;
+  /** 分析時点の発言数 */
+  messageCount: number;
+}
+
+/**
+ * 保存済みの分析スナップショット（get_analysis_history の戻り値）。
+ */
+export interface AnalysisSnapshot {
+  id: number;
+  sessionId: number;
+  messageCount: number;
+  model: string;
+  analysis: DiscussionAnalysis;
+  createdAt: string;
+}
+
+/**
+ * 参加者1人の立場の推移の1点（get_stance_timeline の戻り値）。
+ */
+export interface StancePoint {
+  snapshotId: number;
+  /** 分析時点の発言数 */
+  messageCount: number;
+  label: string;
+  /** 0.0〜1.0 */
+  confidence: number;
+  stance: string;
+}
 
 /**
  * useAIModel フックが提供するAPIの型。
@@ -20,16 +867,154 @@ export interface UseAIModelApi {
   selectedModel: string;
   /** 利用可能モデル一覧（Ollamaから取得） */
   availableModels: string[];
+  /** Ollama の状態（バックグラウンドの死活監視が更新） */
+  ollamaStatus: OllamaStatus | null;
+  /** 生成の失敗が続いた時のサーキットブレーカーの状態 */
+  breakerStatus: BreakerStatus | null;
+  /** ブレーカーの停止を解除して生成の送信を再開します（Ollama を起動し直した時など）。 */
+  resetCircuitBreaker: () => Promise<BreakerStatus>;
+  /** Ollama が止まっていれば起動します（未インストールならその旨を返します）。 */
+  ensureOllamaRunning: () => Promise<BootstrapStatus>;
+  /** OpenAI 互換サーバー（LM Studio・llama.cpp server）への接続を保存前に確認します。 */
+  testOpenAiConnection: (baseUrl: string, apiKey?: string) => Promise<ConnectionTest>;
+  /** candle 用に取得できる GGUF モデルの一覧を返します。 */
+  listCuratedGguf: () => Promise<CuratedGguf[]>;
+  /** GGUF モデルを Hugging Face から取得します（中断しても次回は続きから）。 */
+  downloadGguf: (id: string, onProgress?: (p: GgufDownloadProgress) => void, requestId?: string) => Promise<DownloadedGguf>;
+  /** 取得済みの GGUF モデルを返します。 */
+  listLocalGguf: () => Promise<LocalGguf[]>;
+  /** 取得済みの GGUF モデルを削除します。 */
+  deleteLocalGguf: (id: string) => Promise<void>;
+  /** 取得済みの GGUF モデルを candle で使うモデルにします。 */
+  useLocalGguf: (id: string) => Promise<void>;
+  /** GGUF の保存先と使用量を返します。 */
+  getModelsDirUsage: () => Promise<ModelsDirUsage>;
+  /** 編集できるプロンプトテンプレートの一覧を返します。 */
+  listTemplates: () => Promise<PromptTemplate[]>;
+  /** プロンプトテンプレートを返します。 */
+  getTemplate: (kind: PromptTemplateKind) => Promise<PromptTemplate>;
+  /** プロンプトテンプレートを書き換えます（必須のプレースホルダーが欠けているとエラー）。 */
+  updateTemplate: (kind: PromptTemplateKind, body: string) => Promise<PromptTemplate>;
+  /** プロンプトテンプレートを組み込みに戻します。 */
+  resetTemplate: (kind: PromptTemplateKind) => Promise<PromptTemplate>;
+  /** ストリーミングで受け取った発言から話者名・コードフェンスなどを除き、最大文字数に収めます。 */
+  postprocessText: (text: string, speaker: string) => Promise<string>;
+  /** 次に発言させるAI参加者を理由付きで推薦します。 */
+  suggestNextSpeaker: (sessionId: number, model?: string) => Promise<SpeakerSuggestion>;
+  startFormattedDiscussion: (sessionId: number, format: DiscussionFormat, participants?: string[]) => Promise<void>;
+  askSession: (sessionId: number, question: string, model?: string) => Promise<SessionAnswer>;
+  semanticSearch: (query: string, sessionId?: number, limit?: number) => Promise<SemanticHit[]>;
+  indexEmbeddings: (sessionId?: number) => Promise<number>;
+  /** 保存済みプロフィールの議論をまたいだ記憶を返します（アプリ設定 participantMemory が有効な場合に蓄積）。 */
+  getProfileMemory: (profileId: number) => Promise<ProfileMemory | null>;
+  clearProfileMemory: (profileId: number) => Promise<boolean>;
+  /** セッション設定 personaState が有効な場合に見積もった、参加者ごとの同意度・苛立ちを返します。 */
+  getPersonaStates: (sessionId: number) => Promise<PersonaState[]>;
+  /** 直近の発言がテーマからどれだけ逸れているかを判定します（model 未指定ならセッションのモデル）。 */
+  detectTopicDrift: (sessionId: number, model?: string) => Promise<TopicDrift>;
+  /** 議論からアクションアイテムを抜き出して保存します（以前の抽出結果は置き換え）。 */
+  extractActionItems: (sessionId: number, model?: string) => Promise<ActionItem[]>;
+  getActionItems: (sessionId: number) => Promise<ActionItem[]>;
+  /** 保存済みのアクションアイテムを Markdown のチェックリストで返します。 */
+  exportActionItems: (sessionId: number) => Promise<string>;
+  /**
+   * 同じテーマ・参加者の自動進行を repetitions 回バックグラウンドで実行し、シミュレーションIDを返します。
+   * profiles が空ならテーマから参加者を生成します。進み具合は simulation://progress、終了は simulation://finished で届きます。
+   */
+  runSimulation: (
+    topic: string,
+    profiles: { name: string; role: string; description: string }[],
+    rounds: number,
+    model: string,
+    repetitions: number,
+    maxParallel?: number
+  ) => Promise<number>;
+  cancelSimulation: (simulationId: number) => Promise<boolean>;
+  listSimulations: () => Promise<SimulationStatus[]>;
+  getUserPersona: () => Promise<UserPersona>;
+  /** 自己紹介を保存します（全項目を空にすると登録を消します）。 */
+  setUserPersona: (persona: UserPersona) => Promise<UserPersona>;
+  /** 似たテーマの過去のセッションを、それぞれの最新の要約付きで近い順に返します。 */
+  findRelatedSessions: (sessionId: number, limit?: number) => Promise<RelatedSession[]>;
+  attachDocument: (sessionId: number, path: string) => Promise<AttachmentInfo>;
+  listAttachments: (sessionId: number) => Promise<AttachmentInfo[]>;
+  deleteAttachment: (attachmentId: number) => Promise<void>;
+  fetchUrlContext: (url: string, sessionId?: number, maxTokens?: number, refresh?: boolean) => Promise<UrlContext>;
+  addTag: (sessionId: number, tag: string) => Promise<string[]>;
+  removeTag: (sessionId: number, tag: string) => Promise<string[]>;
+  listTags: (sessionId?: number) => Promise<TagCount[]>;
+  autoTagSession: (sessionId: number, model?: string) => Promise<string[]>;
+  /** セッション一覧を1ページ分取得します（発言本文は含まない。続きは offset をずらして取得）。 */
+  getSessionsPage: (offset?: number, limit?: number, filter?: SessionPageFilter) => Promise<SessionPage>;
+  renameSession: (id: number, newTopic: string) => Promise<void>;
+  /** 参加者・発言・設定・タグをコピーした新しいセッションのIDを返します。 */
+  duplicateSession: (id: number) => Promise<number>;
+  /** atMessageIndex の発言までをコピーした新しいセッションを作り、そのIDを返します（元のセッションはそのまま）。 */
+  forkSession: (id: number, atMessageIndex: number) => Promise<number>;
+  getSessionLineage: (id: number) => Promise<SessionLineage | null>;
+  /** 取得後に別の操作で発言が変更されていた場合は保存せずにエラーになります。 */
+  editMessage: (messageId: number, newContent: string) => Promise<MessageRow>;
+  /** 残りの発言数を返します。取得後に別の操作で発言が変更されていた場合は削除しません。 */
+  deleteMessage: (messageId: number) => Promise<number>;
+  /** AIの発言を、それより前の発言だけを文脈にして生成し直します。 */
+  regenerateMessage: (messageId: number, model?: string) => Promise<MessageRow>;
+  /** 閉じられないまま終わった最新のセッションを返します（なければ null）。起動時に呼びます。 */
+  recoverUnsavedSession: () => Promise<RecoveredSession | null>;
+  /** セッションを正常に区切ったことを記録し、復元の対象から外します。 */
+  closeSession: (sessionId: number) => Promise<void>;
+  /** データベースの整合性をチェックします。 */
+  checkDbIntegrity: () => Promise<IntegrityReport>;
+  /** VACUUM で未使用領域を回収し、前後のサイズを返します。 */
+  vacuumDb: () => Promise<VacuumReport>;
+  /** ワークスペースの一覧と選択中のものを返します。 */
+  listWorkspaces: () => Promise<WorkspaceList>;
+  /** ワークスペースを作成します（切り替えはしません）。 */
+  createWorkspace: (name: string) => Promise<Workspace>;
+  /** ワークスペースを切り替え、以後の保存・読み込みをそのデータベースに向けます。 */
+  switchWorkspace: (id: string) => Promise<WorkspaceList>;
+  /** データベースの保存先ディレクトリを変更します（null で既定の場所）。 */
+  setDbDirectory: (dir: string | null) => Promise<WorkspaceList>;
+  /** 発言本文の暗号化の状態を返します。 */
+  getEncryptionStatus: () => Promise<EncryptionStatus>;
+  /**
+   * パスフレーズで暗号化を解除し、本文を読み書きできるようにします。
+   * remember が true ならパスフレーズを OS のキーチェーンに保存し、false なら保存済みのものを削除します
+   * （キーチェーンに失敗しても解除はされ、結果は remembered で分かります）。
+   */
+  unlockDb: (passphrase: string, remember?: boolean) => Promise<EncryptionStatus>;
+  /** 解除した鍵をメモリから消します。 */
+  lockDb: () => Promise<EncryptionStatus>;
+  /**
+   * パスフレーズを設定・変更します。未暗号化のデータベースでは既存の本文と、要約・分析・注釈・投票・添付資料など
+   * 議論から作った内容を暗号化して有効にし（EncryptionStatus.plaintext の項目は平文のまま残ります）、
+   * passphrase に null を渡すと本文を復号して無効にします（有効な場合は currentPassphrase が必要）。
+   * remember を省略すると、キーチェーンに保存済みの場合だけ新しいパスフレーズに差し替えます。
+   */
+  setDbPassphrase: (passphrase: string | null, currentPassphrase?: string, remember?: boolean) => Promise<EncryptionStatus>;
+  /** キーチェーンに保存したパスフレーズを削除します。 */
+  forgetDbPassphrase: () => Promise<EncryptionStatus>;
+  /** セッションのAIの発言の生成速度・待ち時間・再送回数をモデル別に集計します。 */
+  getSessionMetrics: (sessionId: number) => Promise<SessionMetrics>;
+  benchmarkModels: (models: string[], promptSet?: BenchmarkPromptSet, judgeModel?: string) => Promise<BenchmarkReport>;
+  getQueueStatus: () => Promise<QueueStatus>;
+  /** 接続プロファイルの一覧を返します（check: true で各接続先の応答・モデルも確認）。 */
+  listBackendProfiles: (check?: boolean) => Promise<BackendProfileStatus[]>;
+  saveBackendProfile: (profile: BackendProfile) => Promise<BackendProfile>;
+  deleteBackendProfile: (name: string) => Promise<boolean>;
+  /** 接続プロファイルに切り替え、プロファイルにモデルがあればそれを選びます。 */
+  setActiveProfile: (name: string) => Promise<BackendProfile>;
+  /** セッションの保存済みの最新の要約を返します（なければ null）。 */
+  getLatestSummary: (sessionId: number) => Promise<StoredSummary | null>;
   /** Ollamaのヘルスを確認し、接続状態を更新して返します。 */
   checkModelStatus: () => Promise<boolean>;
   /** モデル一覧をOllamaから読み込みます。 */
   loadAvailableModels: () => Promise<void>;
-  /** 使用するモデルを切り替えます（未許可は gemma3:4b にフォールバック）。 */
+  /** 使用するモデルを切り替えます（許可一覧にないモデルは gemma3:4b にフォールバック）。 */
   changeModel: (model: string) => void;
   /** 選択中モデルで任意プロンプトを生成します。 */
   generateText: (prompt: string) => Promise<string>;
   /** 指定モデルで任意プロンプトを生成します。 */
-  generateTextWithModel: (prompt: string, model?: string) => Promise<string>;
+  generateTextWithModel: (prompt: string, model?: string, options?: GenerationOptions) => Promise<string>;
   /** バックエンド疎通確認用のテキスト生成。 */
   testGenerateText: () => Promise<string>;
   /** 1人のAI参加者の応答を生成します。 */
@@ -38,33 +1023,111 @@ export interface UseAIModelApi {
     role: string,
     description: string,
     conversationHistory: string,
-    discussionTopic: string
-  ) => Promise<string>;
+    discussionTopic: string,
+    sessionId?: number | null,
+    seed?: number,
+    requestId?: string,
+    options?: GenerationOptions
+  ) => Promise<AIResponse>;
+  /** 添付画像（PNG / JPEG / WebP のパス、最大4枚）を見せて応答を生成します（gemma3:4b などのマルチモーダルモデルのみ）。 */
+  generateAIResponseWithImages: (
+    participantName: string,
+    role: string,
+    description: string,
+    conversationHistory: string,
+    discussionTopic: string,
+    imagePaths: string[],
+    sessionId?: number | null,
+    seed?: number,
+    requestId?: string,
+    options?: GenerationOptions
+  ) => Promise<AIResponse>;
+  /** 反論役（悪魔の代弁者）として、最新の分析の合意に異議を唱える応答を生成します。 */
+  generateDevilsAdvocateResponse: (
+    participantName: string,
+    role: string,
+    description: string,
+    conversationHistory: string,
+    discussionTopic: string,
+    sessionId?: number | null,
+    seed?: number,
+    requestId?: string,
+    options?: GenerationOptions
+  ) => Promise<AIResponse>;
+  /** 複数参加者の応答を並行して生成します（できた順に onResponse で通知）。 */
+  generateResponsesParallel: (
+    participants: ParticipantSpec[],
+    conversationHistory: string,
+    discussionTopic: string,
+    sessionId?: number | null,
+    requestId?: string,
+    onResponse?: (response: ParticipantResponse) => void,
+    options?: GenerationOptions
+  ) => Promise<ParticipantResponse[]>;
+  /** requestId を指定した生成を1件だけ中断します（実行中のものがあれば true）。 */
+  cancelRequest: (requestId: string) => Promise<boolean>;
   /** 議論全体の初回フル要約を生成します。 */
   summarizeDiscussion: (
     discussionTopic: string,
     conversationHistory: string,
-    participants: string[]
+    participants: string[],
+    options?: GenerationOptions
   ) => Promise<string>;
   /** 差分のみを反映するインクリメンタル要約を生成します。 */
   incrementalSummarizeDiscussion: (
     discussionTopic: string,
     previousSummary: string,
     newMessages: string,
-    participants: string[]
+    participants: string[],
+    options?: GenerationOptions
   ) => Promise<string>;
-  /** 議論の論点・立場などの分析（JSON文字列）を生成します。 */
+  /** 前回の分析結果と新しい発言だけから最新の分析を生成します（パース済み）。 */
+  incrementalAnalyzeDiscussion: (
+    discussionTopic: string,
+    previousAnalysis: DiscussionAnalysis,
+    newMessages: string,
+    participants: string[],
+    options?: GenerationOptions,
+    snapshot?: AnalysisSnapshotTarget
+  ) => Promise<DiscussionAnalysis>;
+  /** 議論の論点・立場などの分析を生成します（パース済み）。 */
   analyzeDiscussionPoints: (
     discussionTopic: string,
     conversationHistory: string,
-    participants: string[]
-  ) => Promise<string>;
+    participants: string[],
+    options?: GenerationOptions,
+    snapshot?: AnalysisSnapshotTarget
+  ) => Promise<DiscussionAnalysis>;
+  /** セッションの分析スナップショットを発言数の古い順に取得します。 */
+  getAnalysisHistory: (sessionId: number) => Promise<AnalysisSnapshot[]>;
+  getStanceTimeline: (sessionId: number, participant: string) => Promise<StancePoint[]>;
   /** テーマに適したAI参加者プロフィールの候補を生成します。 */
   generateAIProfiles: (
     discussionTopic: string,
     desiredCount?: number,
-    styleHint?: string
+    styleHint?: string,
+    constraints?: ProfileConstraints
   ) => Promise<Array<{ name: string; role: string; description: string }>>;
+  /** 参加者1人分だけプロフィールを作り直します（ほかの参加者と名前・役職が重ならないもの）。 */
+  regenerateSingleProfile: (
+    topic: string,
+    existingProfiles: Array<{ name: string; role: string; description: string }>,
+    slotIndex: number,
+    styleHint?: string
+  ) => Promise<{ name: string; role: string; description: string }>;
+  /** Ollama にモデルを取得（ダウンロード）し、完了後にモデル一覧を更新します。 */
+  pullModel: (name: string, onProgress?: (progress: PullProgress) => void) => Promise<void>;
+  /** マシンの性能に合うモデルを返します。model を渡すと、そのモデルを使う場合の警告も返します。 */
+  recommendModel: (model?: string) => Promise<ModelRecommendation>;
+  /** 初回セットアップの前提条件（Ollama・モデル・メモリ・ディスク）を確認します。 */
+  checkPrerequisites: (onProgress?: (progress: SetupProgress) => void) => Promise<Prerequisites>;
+  /** 搭載メモリに合わせた推奨モデルを取得し、取得したモデル名を返します。 */
+  installRecommendedModel: (
+    onProgress?: (progress: SetupProgress) => void,
+    onPullProgress?: (progress: PullProgress) => void
+  ) => Promise<string>;
+  /** 短いテスト生成を行い、応答までの時間を返します。 */
+  runSmokeTest: (model?: string, onProgress?: (progress: SetupProgress) => void) => Promise<SmokeTestResult>;
 }
 
 /**
@@ -76,6 +1139,8 @@ export interface UseAIModelApi {
  */
 export const useAIModel = (): UseAIModelApi => {
   const [isModelLoaded, setIsModelLoaded] = useState(false);
+  const [ollamaStatus, setOllamaStatus] = useState<OllamaStatus | null>(null);
+  const [breakerStatus, setBreakerStatus] = useState<BreakerStatus | null>(null);
   const [selectedModel, setSelectedModel] = useState<string>('gemma3:4b');
   const [availableModels, setAvailableModels] = useState<string[]>([]);
 
@@ -95,6 +1160,204 @@ export const useAIModel = (): UseAIModelApi => {
     }
   };
 
+  /**
+   * Ollama が起動していなければ起動します。起動できた場合は接続状態も更新します。
+   * @returns 起動の結果（未インストールなら outcome が notInstalled）
+   */
+  const ensureOllamaRunning = async (): Promise<BootstrapStatus> => {
+    const status = await invoke<BootstrapStatus>('ensure_ollama_running');
+    if (status.outcome === 'started' || status.outcome === 'alreadyRunning') setIsModelLoaded(true);
+    return status;
+  };
+
+  /**
+   * マシンの性能（メモリ・CPU・GPU）に合うモデルを返します。
+   * @param model 使おうとしているモデル（メモリ不足の警告を確認する場合）
+   */
+  const recommendModel = (model?: string) => invoke<ModelRecommendation>('recommend_model', { model });
+
+  /**
+   * OpenAI 互換サーバーの /v1/models が取得できるかを確認します（設定は保存しません）。
+   * @param baseUrl "/v1" までのURL（例: http://localhost:1234/v1）
+   * @param apiKey 必要な場合のみ
+   */
+  const testOpenAiConnection = (baseUrl: string, apiKey?: string) =>
+    invoke<ConnectionTest>('test_openai_connection', { baseUrl, apiKey });
+
+  const listCuratedGguf = () => invoke<CuratedGguf[]>('list_curated_gguf');
+
+  /**
+   * GGUF モデルと tokenizer.json を取得します。Hugging Face が公開する SHA256 と照合してから配置します。
+   * @param onProgress gguf://download-progress を受け取るコールバック
+   * @param requestId cancelRequest に渡すと中断できます（途中まで取得した分は残ります）
+   */
+  const downloadGguf = async (
+    id: string,
+    onProgress?: (p: GgufDownloadProgress) => void,
+    requestId?: string,
+  ): Promise<DownloadedGguf> => {
+    const unlisten = onProgress
+      ? await listen<GgufDownloadProgress>('gguf://download-progress', (e) => {
+          if (e.payload.id === id) onProgress(e.payload);
+        })
+      : undefined;
+    try {
+      return await invoke<DownloadedGguf>('download_gguf', { id, requestId });
+    } finally {
+      unlisten?.();
+    }
+  };
+
+  const listLocalGguf = () => invoke<LocalGguf[]>('list_local_gguf');
+
+  const deleteLocalGguf = (id: string) => invoke<void>('delete_local_gguf', { id });
+
+  const useLocalGguf = async (id: string) => {
+    await invoke('use_local_gguf', { id });
+  };
+
+  const getModelsDirUsage = () => invoke<ModelsDirUsage>('get_models_dir_usage');
+
+  const listTemplates = () => invoke<PromptTemplate[]>('list_templates');
+
+  const getTemplate = (kind: PromptTemplateKind) => invoke<PromptTemplate>('get_template', { kind });
+
+  const updateTemplate = (kind: PromptTemplateKind, body: string) =>
+    invoke<PromptTemplate>('update_template', { kind, body });
+
+  const resetTemplate = (kind: PromptTemplateKind) => invoke<PromptTemplate>('reset_template', { kind });
+
+  const postprocessText = (text: string, speaker: string) => invoke<string>('postprocess_text', { text, speaker });
+
+  const suggestNextSpeaker = (sessionId: number, model?: string) =>
+    invoke<SpeakerSuggestion>('suggest_next_speaker', { sessionId, model });
+
+  const startFormattedDiscussion = (sessionId: number, format: DiscussionFormat, participants?: string[]) =>
+    invoke<void>('start_formatted_discussion', { sessionId, format, participants });
+
+  const askSession = (sessionId: number, question: string, model?: string) =>
+    invoke<SessionAnswer>('ask_session', { sessionId, question, model });
+
+  const semanticSearch = (query: string, sessionId?: number, limit?: number) =>
+    invoke<SemanticHit[]>('semantic_search', { query, sessionId, limit });
+
+  const indexEmbeddings = (sessionId?: number) => invoke<number>('index_embeddings', { sessionId });
+
+  const findRelatedSessions = (sessionId: number, limit?: number) =>
+    invoke<RelatedSession[]>('find_related_sessions', { sessionId, limit });
+
+  const getProfileMemory = (profileId: number) => invoke<ProfileMemory | null>('get_profile_memory', { profileId });
+  const clearProfileMemory = (profileId: number) => invoke<boolean>('clear_profile_memory', { profileId });
+  const getPersonaStates = (sessionId: number) => invoke<PersonaState[]>('get_persona_states', { sessionId });
+  const detectTopicDrift = (sessionId: number, model?: string) =>
+    invoke<TopicDrift>('detect_topic_drift', { sessionId, model });
+  const extractActionItems = (sessionId: number, model?: string) =>
+    invoke<ActionItem[]>('extract_action_items', { sessionId, model });
+  const getActionItems = (sessionId: number) => invoke<ActionItem[]>('get_action_items', { sessionId });
+  const exportActionItems = (sessionId: number) => invoke<string>('export_action_items', { sessionId });
+
+  const runSimulation = (
+    topic: string,
+    profiles: { name: string; role: string; description: string }[],
+    rounds: number,
+    model: string,
+    repetitions: number,
+    maxParallel?: number
+  ) => invoke<number>('run_simulation', { topic, profiles, rounds, model, repetitions, maxParallel });
+  const cancelSimulation = (simulationId: number) => invoke<boolean>('cancel_simulation', { simulationId });
+  const listSimulations = () => invoke<SimulationStatus[]>('list_simulations');
+  const getUserPersona = () => invoke<UserPersona>('get_user_persona');
+  const setUserPersona = (persona: UserPersona) => invoke<UserPersona>('set_user_persona', { persona });
+
+  const attachDocument = (sessionId: number, path: string) =>
+    invoke<AttachmentInfo>('attach_document', { sessionId, path });
+
+  const listAttachments = (sessionId: number) => invoke<AttachmentInfo[]>('list_attachments', { sessionId });
+
+  const deleteAttachment = (attachmentId: number) => invoke<void>('delete_attachment', { attachmentId });
+
+  const fetchUrlContext = (url: string, sessionId?: number, maxTokens?: number, refresh?: boolean) =>
+    invoke<UrlContext>('fetch_url_context', { url, sessionId, maxTokens, refresh });
+
+  const addTag = (sessionId: number, tag: string) => invoke<string[]>('add_tag', { sessionId, tag });
+
+  const removeTag = (sessionId: number, tag: string) => invoke<string[]>('remove_tag', { sessionId, tag });
+
+  const listTags = (sessionId?: number) => invoke<TagCount[]>('list_tags', { sessionId });
+
+  const autoTagSession = (sessionId: number, model?: string) =>
+    invoke<string[]>('auto_tag_session', { sessionId, model });
+
+  const getSessionsPage = (offset?: number, limit?: number, filter?: SessionPageFilter) =>
+    invoke<SessionPage>('get_sessions_page', { offset, limit, filter });
+
+  const renameSession = (id: number, newTopic: string) => invoke<void>('rename_session', { id, newTopic });
+
+  const duplicateSession = (id: number) => invoke<number>('duplicate_session', { id });
+
+  const forkSession = (id: number, atMessageIndex: number) => invoke<number>('fork_session', { id, atMessageIndex });
+
+  const getSessionLineage = (id: number) => invoke<SessionLineage | null>('get_session_lineage', { id });
+
+  const editMessage = (messageId: number, newContent: string) =>
+    invoke<MessageRow>('edit_message', { messageId, newContent });
+
+  const deleteMessage = (messageId: number) => invoke<number>('delete_message', { messageId });
+
+  const regenerateMessage = (messageId: number, model?: string) =>
+    invoke<MessageRow>('regenerate_message', { messageId, model });
+
+  const recoverUnsavedSession = () => invoke<RecoveredSession | null>('recover_unsaved_session');
+
+  const closeSession = (sessionId: number) => invoke<void>('close_session', { sessionId });
+
+  const checkDbIntegrity = () => invoke<IntegrityReport>('check_db_integrity');
+
+  const vacuumDb = () => invoke<VacuumReport>('vacuum_db');
+
+  const listWorkspaces = () => invoke<WorkspaceList>('list_workspaces');
+
+  const createWorkspace = (name: string) => invoke<Workspace>('create_workspace', { name });
+
+  const switchWorkspace = async (id: string) => {
+    const list = await invoke<WorkspaceList>('switch_workspace', { id });
+    setDatabaseUrl(list.dbUrl);
+    return list;
+  };
+
+  const setDbDirectory = async (dir: string | null) => {
+    const list = await invoke<WorkspaceList>('set_db_directory', { dir });
+    setDatabaseUrl(list.dbUrl);
+    return list;
+  };
+
+  const getEncryptionStatus = () => invoke<EncryptionStatus>('get_encryption_status');
+
+  const unlockDb = (passphrase: string, remember?: boolean) =>
+    invoke<EncryptionStatus>('unlock_db', { passphrase, remember });
+
+  const lockDb = () => invoke<EncryptionStatus>('lock_db');
+
+  const setDbPassphrase = (passphrase: string | null, currentPassphrase?: string, remember?: boolean) =>
+    invoke<EncryptionStatus>('set_db_passphrase', { passphrase, currentPassphrase, remember });
+
+  const forgetDbPassphrase = () => invoke<EncryptionStatus>('forget_db_passphrase');
+
+  const getSessionMetrics = (sessionId: number) => invoke<SessionMetrics>('get_session_metrics', { sessionId });
+  const benchmarkModels = (models: string[], promptSet?: BenchmarkPromptSet, judgeModel?: string) =>
+    invoke<BenchmarkReport>('benchmark_models', { models, promptSet, judgeModel });
+  const getQueueStatus = () => invoke<QueueStatus>('get_queue_status');
+  const listBackendProfiles = (check?: boolean) => invoke<BackendProfileStatus[]>('list_backend_profiles', { check });
+  const saveBackendProfile = (profile: BackendProfile) => invoke<BackendProfile>('save_backend_profile', { profile });
+  const deleteBackendProfile = (name: string) => invoke<boolean>('delete_backend_profile', { name });
+  const setActiveProfile = async (name: string) => {
+    const profile = await invoke<BackendProfile>('set_active_profile', { name });
+    await loadAvailableModels();
+    if (profile.model) changeModel(profile.model);
+    return profile;
+  };
+  const getLatestSummary = (sessionId: number) => invoke<StoredSummary | null>('get_latest_summary', { sessionId });
+
   /**
    * Ollamaから利用可能なモデル一覧を取得します。
    */
@@ -102,6 +1365,9 @@ export const useAIModel = (): UseAIModelApi => {
     try {
       const models = await invoke<string[]>('get_available_models');
       setAvailableModels(models);
+      // 保存済みのモデルが許可一覧にあれば復元
+      const savedModel = localStorage.getItem('selectedModel');
+      if (savedModel && models.includes(savedModel)) setSelectedModel(savedModel);
     } catch (error) {
       console.error('モデル一覧取得エラー:', error);
       setAvailableModels(['gemma3:4b', 'gemma3:1b']);
@@ -113,15 +1379,45 @@ export const useAIModel = (): UseAIModelApi => {
     checkModelStatus();
     loadAvailableModels();
     const savedModel = localStorage.getItem('selectedModel');
-    if (savedModel && isAllowedModel(savedModel)) setSelectedModel(savedModel);
+    if (savedModel && isDefaultModel(savedModel)) setSelectedModel(savedModel);
+    // 未選択ならマシンの性能に合うモデルを既定にする（メモリが少ないマシンで 4b を選ばない）
+    else recommendModel().then((r) => setSelectedModel(r.recommended)).catch((error) => console.error('推奨モデル取得エラー:', error));
+  }, []);
+
+  // 死活監視の通知で接続状態を更新（ポーリング不要）
+  useEffect(() => {
+    const apply = (status: OllamaStatus) => {
+      setOllamaStatus(status);
+      if (status.state !== 'unknown') setIsModelLoaded(status.state !== 'down');
+    };
+    invoke<OllamaStatus>('get_ollama_status').then(apply).catch((error) => console.error('Ollama状態取得エラー:', error));
+    const unlisten = listen<OllamaStatus>('ollama://status-changed', (event) => apply(event.payload));
+    return () => {
+      unlisten.then((f) => f());
+    };
+  }, []);
+
+  // 生成の失敗が続いて送信を止めた・復旧した時の通知
+  useEffect(() => {
+    invoke<BreakerStatus>('get_circuit_breaker_status').then(setBreakerStatus).catch((error) => console.error('ブレーカー状態取得エラー:', error));
+    const unlisten = listen<BreakerStatus>('backend://degraded', (event) => setBreakerStatus(event.payload));
+    return () => {
+      unlisten.then((f) => f());
+    };
   }, []);
 
+  const resetCircuitBreaker = async () => {
+    const status = await invoke<BreakerStatus>('reset_circuit_breaker');
+    setBreakerStatus(status);
+    return status;
+  };
+
   /**
-   * 使用するモデルを切り替えます。許可外の場合は既定の gemma3:4b になります。
+   * 使用するモデルを切り替えます。許可一覧（または既定のGemma3）にない場合は gemma3:4b になります。
    * @param model モデル名（例: "gemma3:4b"）
    */
   const changeModel = (model: string) => {
-    const next = isAllowedModel(model) ? model : 'gemma3:4b';
+    const next = availableModels.includes(model) || isDefaultModel(model) ? model : 'gemma3:4b';
     setSelectedModel(next);
     localStorage.setItem('selectedModel', next);
   };
@@ -130,13 +1426,14 @@ export const useAIModel = (): UseAIModelApi => {
    * 明示したモデルでテキスト生成を行います。
    * @param prompt プロンプト
    * @param model 使用するモデル（未指定時は選択中のモデル）
+   * @param options 生成オプション（温度・シードなど）
    * @returns 生成テキスト
    */
-  const generateTextWithModel = async (prompt: string, model?: string): Promise<string> => {
+  const generateTextWithModel = async (prompt: string, model?: string, options?: GenerationOptions): Promise<string> => {
     const modelToUse = model || selectedModel;
     try {
-      const res = await invoke<string>('generate_text_with_model', { prompt, model: modelToUse });
-      return res;
+      const res = await invoke<GenerationResult>('generate_text_with_model', { prompt, model: modelToUse, options: options ?? null });
+      return res.text;
     } catch (error) {
       console.error('テキスト生成エラー:', error);
       throw error;
@@ -152,8 +1449,8 @@ export const useAIModel = (): UseAIModelApi => {
   /** Tauriバックエンドの疎通確認用メソッド。 */
   const testGenerateText = async (): Promise<string> => {
     try {
-      const res = await invoke<string>('test_generate_text');
-      return res;
+      const res = await invoke<GenerationResult>('test_generate_text');
+      return res.text;
     } catch (error) {
       console.error('テストエラー:', error);
       throw error;
@@ -167,22 +1464,34 @@ export const useAIModel = (): UseAIModelApi => {
    * @param description 口調・行動方針などの説明
    * @param conversationHistory 直近履歴（必要なら要約を含む）
    * @param discussionTopic 議論テーマ
+   * @param sessionId 保存済みセッションID（指定すると生成ログに記録され、再現検証に使えます）
+   * @param seed 生成シード（省略時はバックエンドで採番）
+   * @param requestId 中断用のリクエストID（cancelRequest に渡すと、この応答の生成だけを中断できます）
+   * @param options 生成オプション（既定: PERSONA_OPTIONS）
    */
   const generateAIResponse = async (
     participantName: string,
     role: string,
     description: string,
     conversationHistory: string,
-    discussionTopic: string
-  ): Promise<string> => {
+    discussionTopic: string,
+    sessionId?: number | null,
+    seed?: number,
+    requestId?: string,
+    options: GenerationOptions = PERSONA_OPTIONS
+  ): Promise<AIResponse> => {
     try {
-      const res = await invoke<string>('generate_ai_response', {
+      const res = await invoke<AIResponse>('generate_ai_response', {
         participantName,
         role,
         description,
         conversationHistory,
         discussionTopic,
         model: selectedModel,
+        sessionId: sessionId ?? null,
+        seed: seed ?? null,
+        requestId: requestId ?? null,
+        options,
       });
       return res;
     } catch (error) {
@@ -191,25 +1500,154 @@ export const useAIModel = (): UseAIModelApi => {
     }
   };
 
+  /**
+   * 画像付きで応答を生成します。ユーザーが会話に添付したグラフやスクリーンショットをモデルに渡します。
+   * 画像入力に対応したモデル（gemma3:4b など）と Ollama バックエンドが必要です。
+   */
+  const generateAIResponseWithImages = async (
+    participantName: string,
+    role: string,
+    description: string,
+    conversationHistory: string,
+    discussionTopic: string,
+    imagePaths: string[],
+    sessionId?: number | null,
+    seed?: number,
+    requestId?: string,
+    options: GenerationOptions = PERSONA_OPTIONS
+  ): Promise<AIResponse> => {
+    try {
+      return await invoke<AIResponse>('generate_ai_response_with_images', {
+        participantName,
+        role,
+        description,
+        conversationHistory,
+        discussionTopic,
+        model: selectedModel,
+        imagePaths,
+        sessionId: sessionId ?? null,
+        seed: seed ?? null,
+        requestId: requestId ?? null,
+        options,
+      });
+    } catch (error) {
+      console.error('画像付き応答生成エラー:', error);
+      throw error;
+    }
+  };
+
+  /**
+   * 反論役（悪魔の代弁者）の応答を生成します。
+   * sessionId を指定すると、そのセッションの最新の分析（共通認識・主要論点）を「現在の合意」として反論させます。
+   * 引数は generateAIResponse と同じです。
+   */
+  const generateDevilsAdvocateResponse = async (
+    participantName: string,
+    role: string,
+    description: string,
+    conversationHistory: string,
+    discussionTopic: string,
+    sessionId?: number | null,
+    seed?: number,
+    requestId?: string,
+    options: GenerationOptions = PERSONA_OPTIONS
+  ): Promise<AIResponse> => {
+    try {
+      return await invoke<AIResponse>('generate_devils_advocate_response', {
+        participantName,
+        role,
+        description,
+        conversationHistory,
+        discussionTopic,
+        model: selectedModel,
+        sessionId: sessionId ?? null,
+        seed: seed ?? null,
+        requestId: requestId ?? null,
+        options,
+      });
+    } catch (error) {
+      console.error('反論役の応答生成エラー:', error);
+      throw error;
+    }
+  };
+
+  /**
+   * 複数のAI参加者の応答を、同じ会話履歴に対して並行して生成します。
+   * 同時に送る数はバックエンドの生成キューの上限（接続設定の maxParallel）で抑えられます。
+   * @param participants 応答を生成する参加者
+   * @param conversationHistory 直近履歴
+   * @param discussionTopic 議論テーマ
+   * @param sessionId 保存済みセッションID（生成ログの記録用）
+   * @param requestId 中断用のリクエストID（cancelRequest で未完了の生成をまとめて中断できます）
+   * @param onResponse 参加者ごとの結果ができた順に呼ばれます
+   * @param options 生成オプション（既定: PERSONA_OPTIONS）
+   * @returns participants の順に並べた結果（失敗した参加者は error のみ）
+   */
+  const generateResponsesParallel = async (
+    participants: ParticipantSpec[],
+    conversationHistory: string,
+    discussionTopic: string,
+    sessionId?: number | null,
+    requestId?: string,
+    onResponse?: (response: ParticipantResponse) => void,
+    options: GenerationOptions = PERSONA_OPTIONS
+  ): Promise<ParticipantResponse[]> => {
+    const unlisten = await listen<ParticipantResponse>('generate://participant-response', (event) => {
+      if ((event.payload.requestId ?? undefined) === requestId) onResponse?.(event.payload);
+    });
+    try {
+      return await invoke<ParticipantResponse[]>('generate_responses_parallel', {
+        participants,
+        conversationHistory,
+        discussionTopic,
+        model: selectedModel,
+        sessionId: sessionId ?? null,
+        options,
+        requestId: requestId ?? null,
+      });
+    } catch (error) {
+      console.error('並行生成エラー:', error);
+      throw error;
+    } finally {
+      unlisten();
+    }
+  };
+
+  /**
+   * 実行中の生成を1件だけ中断します。中断された呼び出しは「生成はキャンセルされました」で reject されます。
+   * @param requestId generateAIResponse に渡したリクエストID
+   */
+  const cancelRequest = async (requestId: string): Promise<boolean> => {
+    try {
+      return await invoke<boolean>('cancel_request', { requestId });
+    } catch (error) {
+      console.error('キャンセルエラー:', error);
+      return false;
+    }
+  };
+
   /**
    * 議論全体の要約を生成します（初回フル）。
    * @param discussionTopic テーマ
    * @param conversationHistory 履歴テキスト（全量）
    * @param participants 参加者名の配列（"ユーザー" を含むことがあります）
+   * @param options 生成オプション（既定: ANALYSIS_OPTIONS）
    */
   const summarizeDiscussion = async (
     discussionTopic: string,
     conversationHistory: string,
-    participants: string[]
+    participants: string[],
+    options: GenerationOptions = ANALYSIS_OPTIONS
   ): Promise<string> => {
     try {
-      const res = await invoke<string>('summarize_discussion', {
+      const res = await invoke<GenerationResult>('summarize_discussion', {
         discussionTopic,
         conversationHistory,
         participants,
         model: selectedModel,
+        options,
       });
-      return res;
+      return res.text;
     } catch (error) {
       console.error('要約エラー:', error);
       throw error;
@@ -222,22 +1660,25 @@ export const useAIModel = (): UseAIModelApi => {
    * @param previousSummary 直前の要約
    * @param newMessages 追加分の発言テキスト
    * @param participants 参加者名の配列
+   * @param options 生成オプション（既定: ANALYSIS_OPTIONS）
    */
   const incrementalSummarizeDiscussion = async (
     discussionTopic: string,
     previousSummary: string,
     newMessages: string,
-    participants: string[]
+    participants: string[],
+    options: GenerationOptions = ANALYSIS_OPTIONS
   ): Promise<string> => {
     try {
-      const res = await invoke<string>('incremental_summarize_discussion', {
+      const res = await invoke<GenerationResult>('incremental_summarize_discussion', {
         discussionTopic,
         previousSummary,
         newMessages,
         participants,
         model: selectedModel,
+        options,
       });
-      return res;
+      return res.text;
     } catch (error) {
       console.error('インクリメンタル要約エラー:', error);
       throw error;
@@ -245,22 +1686,64 @@ export const useAIModel = (): UseAIModelApi => {
   };
 
   /**
-   * 論点や立場など、議論の分析情報（JSON文字列）を生成します。
+   * 前回の分析結果に新しい発言だけを統合して分析を更新します（全履歴を再送しない）。
+   * @param discussionTopic テーマ
+   * @param previousAnalysis 前回の分析結果
+   * @param newMessages 前回の分析以降の発言（"名前: 内容" の行形式）
+   * @param participants 参加者名の配列
+   * @param options 生成オプション（既定: ANALYSIS_OPTIONS）
+   * @param snapshot 指定時は発言数ごとのスナップショットとして履歴に保存
+   */
+  const incrementalAnalyzeDiscussion = async (
+    discussionTopic: string,
+    previousAnalysis: DiscussionAnalysis,
+    newMessages: string,
+    participants: string[],
+    options: GenerationOptions = ANALYSIS_OPTIONS,
+    snapshot?: AnalysisSnapshotTarget
+  ): Promise<DiscussionAnalysis> => {
+    try {
+      return await invoke<DiscussionAnalysis>('incremental_analyze_discussion', {
+        discussionTopic,
+        previousAnalysisJson: JSON.stringify(previousAnalysis),
+        newMessages,
+        participants,
+        model: selectedModel,
+        sessionId: snapshot?.sessionId,
+        messageCount: snapshot?.messageCount,
+        options,
+      });
+    } catch (error) {
+      console.error('インクリメンタル分析エラー:', error);
+      throw error;
+    }
+  };
+
+  /**
+   * 論点や立場など、議論の分析情報を生成します。
+   * JSONのパース・修復はバックエンドで行い、型付きの分析結果を返します。
    * @param discussionTopic テーマ
    * @param conversationHistory 履歴テキスト（要約+直近など）
    * @param participants 参加者名の配列
+   * @param options 生成オプション（既定: ANALYSIS_OPTIONS）
+   * @param snapshot 指定時は発言数ごとのスナップショットとして履歴に保存
    */
   const analyzeDiscussionPoints = async (
     discussionTopic: string,
     conversationHistory: string,
-    participants: string[]
-  ): Promise<string> => {
+    participants: string[],
+    options: GenerationOptions = ANALYSIS_OPTIONS,
+    snapshot?: AnalysisSnapshotTarget
+  ): Promise<DiscussionAnalysis> => {
     try {
-      const res = await invoke<string>('analyze_discussion_points', {
+      const res = await invoke<DiscussionAnalysis>('analyze_discussion_points', {
         discussionTopic,
         conversationHistory,
         participants,
         model: selectedModel,
+        sessionId: snapshot?.sessionId,
+        messageCount: snapshot?.messageCount,
+        options,
       });
       return res;
     } catch (error) {
@@ -269,58 +1752,129 @@ export const useAIModel = (): UseAIModelApi => {
     }
   };
 
+  /**
+   * 分析の推移（立場・対立点の変化）を表示するため、保存済みスナップショットを取得します。
+   * @param sessionId セッションID
+   */
+  const getAnalysisHistory = async (sessionId: number): Promise<AnalysisSnapshot[]> => {
+    try {
+      return await invoke<AnalysisSnapshot[]>('get_analysis_history', { sessionId });
+    } catch (error) {
+      console.error('分析履歴取得エラー:', error);
+      throw error;
+    }
+  };
+
+  /**
+   * 参加者1人の立場ラベルと確信度の推移を取得します（グラフ表示用）。
+   * @param sessionId セッションID
+   * @param participant 参加者名
+   */
+  const getStanceTimeline = (sessionId: number, participant: string) =>
+    invoke<StancePoint[]>('get_stance_timeline', { sessionId, participant });
+
   /**
    * 指定テーマに適したAI参加者プロフィール案を生成します。
    * @param discussionTopic テーマ
    * @param desiredCount 生成数（既定: 4）
    * @param styleHint 文体・役割のヒント
+   * @param constraints 必須の役職・立場の内訳などの条件（人数に対して満たせない条件はエラー）
    * @returns name/role/description を持つプロフィール配列
    */
   const generateAIProfiles = async (
     discussionTopic: string,
     desiredCount = 4,
-    styleHint = ''
+    styleHint = '',
+    constraints?: ProfileConstraints
   ): Promise<Array<{ name: string; role: string; description: string }>> => {
     try {
-      const raw = await invoke<string>('generate_ai_profiles', {
+      // JSON の読み取りと検証（空の項目・名前の重複・説明の長さ・必須の役職）はバックエンドで行う
+      return await invoke<Array<{ name: string; role: string; description: string }>>('generate_ai_profiles', {
         discussionTopic,
         desiredCount,
         styleHint,
+        constraints,
         model: selectedModel,
       });
-      let cleaned = raw.trim();
-      if (cleaned.startsWith('```json')) cleaned = cleaned.replace(/^```json\s*/, '').replace(/\s*```$/, '');
-      else if (cleaned.startsWith('```')) cleaned = cleaned.replace(/^```\s*/, '').replace(/\s*```$/, '');
-
-      // まずは素直に JSON.parse、失敗したら jsonrepair で修復して再パース
-      try {
-        const parsed = JSON.parse(cleaned);
-        if (!Array.isArray(parsed)) throw new Error('JSONが配列ではありません');
-        return parsed
-          .filter((p: any) => p && typeof p.name === 'string')
-          .map((p: any) => ({
-            name: String(p.name).trim(),
-            role: String(p.role || '').trim(),
-            description: String(p.description || '').trim(),
-          }));
-      } catch (parseErr) {
-        const repaired = jsonrepair(cleaned);
-        const parsed2 = JSON.parse(repaired);
-        if (!Array.isArray(parsed2)) throw new Error('JSONが配列ではありません');
-        return parsed2
-          .filter((p: any) => p && typeof p.name === 'string')
-          .map((p: any) => ({
-            name: String(p.name).trim(),
-            role: String(p.role || '').trim(),
-            description: String(p.description || '').trim(),
-          }));
-      }
     } catch (error) {
       console.error('AIプロフィール生成エラー:', error);
       throw error;
     }
   };
 
+  /**
+   * 参加者1人分だけプロフィールを作り直します。
+   * @param topic テーマ
+   * @param existingProfiles 今の参加者（作り直す人を含む）
+   * @param slotIndex existingProfiles の中の作り直す位置
+   * @param styleHint 文体・役割のヒント
+   */
+  const regenerateSingleProfile = (
+    topic: string,
+    existingProfiles: Array<{ name: string; role: string; description: string }>,
+    slotIndex: number,
+    styleHint = ''
+  ) =>
+    invoke<{ name: string; role: string; description: string }>('regenerate_single_profile', {
+      topic,
+      existingProfiles,
+      slotIndex,
+      styleHint,
+      model: selectedModel,
+    });
+
+  /**
+   * Ollama にモデルを取得します（ターミナルでの `ollama pull` 相当）。
+   * @param name モデル名（例: "gemma3:1b"）
+   * @param onProgress 進捗の通知先
+   */
+  const pullModel = async (name: string, onProgress?: (progress: PullProgress) => void): Promise<void> => {
+    const unlisten = await listen<PullProgress>('model://pull-progress', (event) => {
+      if (event.payload.model === name) onProgress?.(event.payload);
+    });
+    try {
+      await invoke('pull_model', { name });
+      await loadAvailableModels();
+    } catch (error) {
+      console.error('モデル取得エラー:', error);
+      throw error;
+    } finally {
+      unlisten();
+    }
+  };
+
+  /**
+   * setup://progress を購読しながらセットアップのコマンドを実行します。
+   */
+  const withSetupProgress = async <T,>(run: () => Promise<T>, onProgress?: (progress: SetupProgress) => void): Promise<T> => {
+    const unlisten = await listen<SetupProgress>('setup://progress', (event) => onProgress?.(event.payload));
+    try {
+      return await run();
+    } finally {
+      unlisten();
+    }
+  };
+
+  const checkPrerequisites = (onProgress?: (progress: SetupProgress) => void) =>
+    withSetupProgress(() => invoke<Prerequisites>('check_prerequisites'), onProgress);
+
+  const installRecommendedModel = async (
+    onProgress?: (progress: SetupProgress) => void,
+    onPullProgress?: (progress: PullProgress) => void
+  ): Promise<string> => {
+    const unlisten = await listen<PullProgress>('model://pull-progress', (event) => onPullProgress?.(event.payload));
+    try {
+      const model = await withSetupProgress(() => invoke<string>('install_recommended_model'), onProgress);
+      await loadAvailableModels();
+      return model;
+    } finally {
+      unlisten();
+    }
+  };
+
+  const runSmokeTest = (model?: string, onProgress?: (progress: SetupProgress) => void) =>
+    withSetupProgress(() => invoke<SmokeTestResult>('run_smoke_test', { model }), onProgress);
+
   return {
     /** モデル接続状態 */
     isModelLoaded,
@@ -328,6 +1882,77 @@ export const useAIModel = (): UseAIModelApi => {
     selectedModel,
     /** 利用可能モデル一覧 */
     availableModels,
+    ollamaStatus,
+    breakerStatus,
+    resetCircuitBreaker,
+    ensureOllamaRunning,
+    testOpenAiConnection,
+    listCuratedGguf,
+    downloadGguf,
+    listLocalGguf,
+    deleteLocalGguf,
+    useLocalGguf,
+    getModelsDirUsage,
+    listTemplates,
+    getTemplate,
+    updateTemplate,
+    resetTemplate,
+    postprocessText,
+    suggestNextSpeaker,
+    startFormattedDiscussion,
+    askSession,
+    semanticSearch,
+    indexEmbeddings,
+    findRelatedSessions,
+    getProfileMemory,
+    clearProfileMemory,
+    getPersonaStates,
+    detectTopicDrift,
+    extractActionItems,
+    getActionItems,
+    exportActionItems,
+    runSimulation,
+    cancelSimulation,
+    listSimulations,
+    getUserPersona,
+    setUserPersona,
+    attachDocument,
+    listAttachments,
+    deleteAttachment,
+    fetchUrlContext,
+    addTag,
+    removeTag,
+    listTags,
+    autoTagSession,
+    getSessionsPage,
+    renameSession,
+    duplicateSession,
+    forkSession,
+    getSessionLineage,
+    editMessage,
+    deleteMessage,
+    regenerateMessage,
+    recoverUnsavedSession,
+    closeSession,
+    checkDbIntegrity,
+    vacuumDb,
+    listWorkspaces,
+    createWorkspace,
+    switchWorkspace,
+    setDbDirectory,
+    getEncryptionStatus,
+    unlockDb,
+    lockDb,
+    setDbPassphrase,
+    forgetDbPassphrase,
+    getSessionMetrics,
+    benchmarkModels,
+    getQueueStatus,
+    listBackendProfiles,
+    saveBackendProfile,
+    deleteBackendProfile,
+    setActiveProfile,
+    getLatestSummary,
     checkModelStatus,
     loadAvailableModels,
     changeModel,
@@ -335,9 +1960,22 @@ export const useAIModel = (): UseAIModelApi => {
     generateTextWithModel,
     testGenerateText,
     generateAIResponse,
+    generateAIResponseWithImages,
+    generateDevilsAdvocateResponse,
+    generateResponsesParallel,
+    cancelRequest,
     summarizeDiscussion,
     incrementalSummarizeDiscussion,
+    incrementalAnalyzeDiscussion,
     analyzeDiscussionPoints,
+    getAnalysisHistory,
+    getStanceTimeline,
     generateAIProfiles,
+    regenerateSingleProfile,
+    pullModel,
+    recommendModel,
+    checkPrerequisites,
+    installRecommendedModel,
+    runSmokeTest,
   };
 };
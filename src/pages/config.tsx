@@ -30,8 +30,20 @@ import {
   CheckboxHiddenInput,
   Badge,
 } from "@chakra-ui/react";
+import { invoke } from '@tauri-apps/api/core';
 import { useAIModel } from '../hooks/useAIModel';
-import { showGenericError } from '../components/ui/notifications';
+import { showGenericError, showProfileSaveSuccess } from '../components/ui/notifications';
+
+/** ライブラリに保存済みのAIプロフィール（list_profiles の戻り値） */
+interface SavedProfile {
+  id: number;
+  name: string;
+  role: string;
+  description: string;
+  tags: string[];
+  createdAt: string;
+  updatedAt: string;
+}
 
 /**
  * 設定フォームを表示するコンポーネント。
@@ -43,7 +55,7 @@ import { showGenericError } from '../components/ui/notifications';
  * - 入力検証後に設定を localStorage へ保存し、Playへ遷移
  */
 function Config() {
-  const { selectedModel, changeModel, isModelLoaded, generateAIProfiles } = useAIModel();
+  const { selectedModel, changeModel, isModelLoaded, regenerateSingleProfile } = useAIModel();
   const [botCount, setBotCount] = React.useState(1);
   const [showFields, setShowFields] = React.useState(false);
   const [participate, setParticipate] = React.useState(true);
@@ -60,6 +72,53 @@ function Config() {
 
   const [bots, setBots] = React.useState<BotProfile[]>([]);
   const [autoLoading, setAutoLoading] = React.useState<boolean[]>([]);
+  const [savedProfiles, setSavedProfiles] = React.useState<SavedProfile[]>([]);
+
+  /** 保存済みプロフィールを読み込む（失敗時は空として扱う） */
+  const loadProfiles = async () => {
+    try {
+      setSavedProfiles(await invoke<SavedProfile[]>('list_profiles'));
+    } catch (e) {
+      console.warn('プロフィール一覧取得（空として扱う）:', e);
+      setSavedProfiles([]);
+    }
+  };
+
+  React.useEffect(() => {
+    loadProfiles();
+  }, []);
+
+  /** カードの内容をライブラリに保存 */
+  const saveCardProfile = async (index: number) => {
+    const bot = bots[index];
+    if (!bot?.name.trim()) {
+      alert('名前を入力してから保存してください');
+      return;
+    }
+    try {
+      await invoke('save_profile', { profile: { name: bot.name, role: bot.role, description: bot.description } });
+      showProfileSaveSuccess(bot.name);
+      await loadProfiles();
+    } catch (e) {
+      console.error('プロフィール保存エラー:', e);
+      showGenericError('プロフィールの保存に失敗しました', `${e}`);
+    }
+  };
+
+  /** 保存済みプロフィールを空いているカードに入れる（空きがなければ追加） */
+  const attachProfile = (profile: SavedProfile) => {
+    setBots(prev => {
+      const next = [...prev];
+      const entry = { name: profile.name, role: profile.role, description: profile.description };
+      const empty = next.findIndex(b => !b.name && !b.role && !b.description);
+      if (empty >= 0) {
+        next[empty] = entry;
+      } else if (next.length < 10) {
+        next.push(entry);
+      }
+      return next;
+    });
+  };
 
   React.useEffect(() => {
     // bots長に合わせてロード配列を整える
@@ -93,17 +152,16 @@ function Config() {
       setAutoLoading(prev => prev.map((v, i) => (i === index ? true : v)));
       // 役職をヒントに入れて精度を上げる
       const hintBase = bots[index]?.role ? `この参加者の役割は「${bots[index].role}」。` : '';
-      const list = await generateAIProfiles(discussionTopic.trim(), 1, `${hintBase}1名分のみ生成。名前は重複不可。`);
-      if (list && list[0]) {
-        // 関数型更新で競合回避
-        setBots(prev => {
-          const next = [...prev];
-          if (index >= 0 && index < next.length) {
-            next[index] = list[0];
-          }
-          return next;
-        });
-      }
+      // ほかの参加者と名前・役職が重ならないかはバックエンドで確かめる
+      const profile = await regenerateSingleProfile(discussionTopic.trim(), bots, index, hintBase);
+      // 関数型更新で競合回避
+      setBots(prev => {
+        const next = [...prev];
+        if (index >= 0 && index < next.length) {
+          next[index] = profile;
+        }
+        return next;
+      });
     } catch (e) {
       console.error('自動補完エラー:', e);
       showGenericError('自動補完に失敗しました', `${e}`);
@@ -216,19 +274,46 @@ function Config() {
 
       {showFields && (
         <VStack gap={6} width="100%" maxW="2xl">
+          {savedProfiles.length > 0 && (
+            <FieldRoot>
+              <FieldLabel>保存済みのプロフィールから追加</FieldLabel>
+              <HStack gap={2} wrap="wrap">
+                {savedProfiles.map((profile) => (
+                  <Button
+                    key={profile.id}
+                    size="xs"
+                    variant="outline"
+                    onClick={() => attachProfile(profile)}
+                    title={profile.description}
+                  >
+                    {profile.name}{profile.role ? `（${profile.role}）` : ''}
+                  </Button>
+                ))}
+              </HStack>
+            </FieldRoot>
+          )}
           {bots.map((bot, index) => (
             <CardRoot key={index} width="100%" variant="outline">
               <CardHeader>
                 <HStack justify="space-between" align="center" width="100%">
                   <Heading size="md">AI {index + 1}</Heading>
-                  <Button 
-                    size="xs" 
-                    variant="subtle"
-                    onClick={() => autoFillCard(index)}
-                    disabled={autoLoading[index]}
-                  >
-                    {autoLoading[index] ? '生成中...' : '自動補完'}
-                  </Button>
+                  <HStack gap={2}>
+                    <Button 
+                      size="xs" 
+                      variant="outline"
+                      onClick={() => saveCardProfile(index)}
+                    >
+                      保存
+                    </Button>
+                    <Button 
+                      size="xs" 
+                      variant="subtle"
+                      onClick={() => autoFillCard(index)}
+                      disabled={autoLoading[index]}
+                    >
+                      {autoLoading[index] ? '生成中...' : '自動補完'}
+                    </Button>
+                  </HStack>
                 </HStack>
               </CardHeader>
               <CardBody>
@@ -50,7 +50,10 @@ import {
   Badge,
   Stack,
 } from '@chakra-ui/react';
-import { useAIModel } from '../hooks/useAIModel';
+import { useAIModel, toGenerationMeta } from '../hooks/useAIModel';
+import type { TurnFailedEvent } from '../hooks/useAIModel';
+import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import { useNavigate } from 'react-router-dom';
 import { 
   showAIResponseError, 
@@ -63,8 +66,7 @@ import {
   showSessionResumeHint,
 } from '../components/ui/notifications';
 import { ChatMessage } from '../components/ui/chat-message';
-import { saveSession, updateSession, getSessionById, saveSessionAnalysis, updateSessionLastOpened, updateSessionParticipants } from '../utils/database';
-import { jsonrepair } from 'jsonrepair';
+import { saveSession, updateSession, appendSessionMessages, getSessionById, saveSessionAnalysis, updateSessionLastOpened, updateSessionParticipants } from '../utils/database';
 import { ParticipantEditorDrawer } from '../components/ParticipantEditorDrawer';
 // 追加: 共通型と共通分析パネル
 import { AnalysisPanel } from './play/AnalysisPanel';
@@ -74,9 +76,6 @@ const USER_SPEAKER = 'ユーザー' as const;
 
 const CONFIG = {
   KEEP_RECENT_TURNS: 4, // 直近保持ターン数
-  MIN_INITIAL_FULL_SUMMARIZE: 12, // 初回フル要約の最小発言数
-  MIN_INCREMENTAL_SUMMARIZE: 4, // 差分要約の最小発言数
-  ANALYSIS_TURN_INTERVAL: 3, // 分析実行間隔（ターン数）
   SCROLL_END_DEBOUNCE_MS: 150, // スクロール終了検知のデバウンス時間(ms)
   MAX_INPUT_LENGTH: 10000, // 入力欄の最大文字数
 } as const
@@ -118,7 +117,7 @@ const calculateNextTurn = (
 const PlayPage: React.FC = () => {
   const navigate = useNavigate();// React Routerのナビゲーションフック
   // AIモデルフックから必要な関数を取得
-  const { generateAIResponse, summarizeDiscussion, analyzeDiscussionPoints, isModelLoaded, selectedModel, changeModel, checkModelStatus, incrementalSummarizeDiscussion } = useAIModel();
+  const { generateAIResponse, analyzeDiscussionPoints, incrementalAnalyzeDiscussion, isModelLoaded, selectedModel, changeModel, checkModelStatus, getLatestSummary } = useAIModel();
   
   // 状態定義
   /** 現在の画面設定（議論テーマ/参加者/ユーザー参加可否） */
@@ -155,7 +154,6 @@ const [turnIndex, setTurnIndex] = useState(0);
   /** 長大履歴の要約文字列（プロンプト圧縮用） */
   const [historySummary, setHistorySummary] = useState<string>('');
   /** 要約した時点のメッセージ数（差分要約トリガーの基準） */
-  const [lastSummarizedIndex, setLastSummarizedIndex] = useState<number>(0);
   /** 要約実行中フラグ */
   const [summarizing, setSummarizing] = useState(false);
   /** 分析実行中フラグ */
@@ -164,6 +162,8 @@ const [turnIndex, setTurnIndex] = useState(0);
   const [analysis, setAnalysis] = useState<DiscussionAnalysis | null>(null);
   /** 最後に分析を実行した時点のメッセージ数（開閉時の不要リクエスト抑止に使用） */
   const [lastAnalyzedCount, setLastAnalyzedCount] = useState<number>(0);
+  /** analysis が反映しているメッセージ数（成功時のみ更新。インクリメンタル分析の差分の起点） */
+  const analysisCoveredRef = useRef<number>(0);
 
   // UI
   /** 分析パネルの開閉状態 */
@@ -251,54 +251,48 @@ const [turnIndex, setTurnIndex] = useState(0);
   }, [scrollToBottom]);
 
   
-  // 3ターン毎の自動分析（ユーザー/AI問わずカウント後に発火）
-
+  // 要約・分析はバックエンドが発言保存の件数から自動実行する（summary://* / analysis://* イベントで受け取る）
 useEffect(() => {
-  if (!config) return;
-  const total = messages.length;
-  if (total === 0) return;
-
-  const parts = [ ...(config.participate ? [USER_SPEAKER] : []), ...config.aiData.map(a => a.name) ];
-
-  const run = async () => {
-    setSummarizing(true);
-    try {
-      if (!historySummary) {
-        if (total >= CONFIG.MIN_INITIAL_FULL_SUMMARIZE) {
-          const history = messages.map(m => `${m.speaker}: ${m.message}`).join('\n');
-          const full = await summarizeDiscussion(config.discussionTopic, history, parts);
-          setHistorySummary(full);
-          setLastSummarizedIndex(total);
-          if (sessionId && sessionId > 0) {
-            await saveSessionAnalysis(sessionId, 'summary', JSON.stringify({ summary: full }));
-          }
-        }
+  const unlisteners = [
+    listen<{ sessionId: number }>('summary://started', (e) => {
+      if (e.payload.sessionId === sessionIdRef.current) setSummarizing(true);
+    }),
+    listen<{ sessionId: number; summary: string; covered: number }>('summary://updated', (e) => {
+      if (e.payload.sessionId !== sessionIdRef.current) return;
+      setHistorySummary(e.payload.summary);
+      setSummarizing(false);
+    }),
+    listen<{ sessionId: number; error?: string }>('summary://failed', (e) => {
+      if (e.payload.sessionId !== sessionIdRef.current) return;
+      setSummarizing(false);
+      showAnalysisError('議論要約', String(e.payload.error ?? ''));
+    }),
+    // 分析もバックエンドのワーカーが発言数・無発言時間から自動実行する
+    listen<{ sessionId: number; analysis: DiscussionAnalysis; messageCount: number }>('analysis://updated', (e) => {
+      if (e.payload.sessionId !== sessionIdRef.current) return;
+      setAnalysis(e.payload.analysis);
+      analysisCoveredRef.current = e.payload.messageCount;
+      setLastAnalyzedCount(prev => Math.max(prev, e.payload.messageCount));
+    }),
+    listen<{ sessionId: number; error: string }>('analysis://failed', (e) => {
+      if (e.payload.sessionId !== sessionIdRef.current) return;
+      console.warn('[analysis] 自動分析失敗:', e.payload.error);
+    }),
+    // 自動進行の生成失敗（バックエンドが間を空けてやり直し、続けて失敗した参加者は飛ばす）
+    listen<TurnFailedEvent>('discussion://turn-failed', (e) => {
+      if (e.payload.sessionId !== sessionIdRef.current) return;
+      if (e.payload.skipped) {
+        showAIResponseError(e.payload.speaker, e.payload.error);
       } else {
-        const delta = total - lastSummarizedIndex;
-        if (delta >= CONFIG.MIN_INCREMENTAL_SUMMARIZE) {
-          const newSlice = messages.slice(lastSummarizedIndex).map(m => `${m.speaker}: ${m.message}`).join('\n');
-          if (newSlice) {
-            const updated = await incrementalSummarizeDiscussion(config.discussionTopic, historySummary, newSlice, parts);
-            setHistorySummary(updated);
-            setLastSummarizedIndex(total);
-            if (sessionId && sessionId > 0) {
-              await saveSessionAnalysis(sessionId, 'summary', JSON.stringify({ summary: updated, delta }));
-            }
-          }
-        }
+        console.warn(`[discussion] ${e.payload.speaker} の生成に失敗（${e.payload.attempt}回目）。再試行します:`, e.payload.error);
       }
-    } catch (e) {
-      console.error('[summary] failed:', e);
-      showAnalysisError('議論要約', String(e));
-    } finally {
-      setSummarizing(false);
-    }
+    }),
+  ];
+  return () => {
+    unlisteners.forEach(p => p.then(unlisten => unlisten()));
   };
+}, []);
 
-  run();
-}, [config, messages, historySummary, lastSummarizedIndex, summarizeDiscussion, incrementalSummarizeDiscussion, saveSessionAnalysis, sessionId]);
-
-  useEffect(() => {analyzeIfNeeded();}, [messages,config,lastAnalyzedCount]);
   
 
 
@@ -347,6 +341,10 @@ useEffect(() => {
               const saved: TalkMessage[] = JSON.parse(session.messages).map((m: any) => ({ ...m, timestamp: new Date(m.timestamp) }));
               setMessages(saved);
 
+              // 保存済みの要約を復元（再生成しない）
+              const stored = await getLatestSummary(parsed.sessionId).catch(() => null);
+              if (stored) setHistorySummary(stored.summary);
+
               // モデル状態確認→議論状態
               const ok = await checkModelStatus();
               if (ok) {
@@ -505,25 +503,9 @@ useEffect(() => {
    */
   
 
-  /**
-   * 必要に応じて ANALYSIS_TURN_INTERVAL ターン毎の自動分析を実行。
-   * 条件に満たない場合は何もしない。
-   */
-  const analyzeIfNeeded = async () => {
-    // 条件チェック
-    if (!config || messages.length === 0) return;
-    const turnCount = messages.length;
-    // ターン数が CONFIG.ANALYSIS_TURN_INTERVAL の倍数でない場合はスキップ
-    if (turnCount % CONFIG.ANALYSIS_TURN_INTERVAL !== 0) return;
-    if (messages.length < CONFIG.ANALYSIS_TURN_INTERVAL) return;
-    // 直近と同一内容ならスキップ
-    if (messages.length <= lastAnalyzedCount) return;
-    await runAnalysis();
-  };
-
   /**
    * 議論の分析を実行し、解析結果をUIとストレージに反映。
-   * JSONの破損に耐えるために `jsonrepair` で修復を試みる。
+   * JSONのパース・修復はバックエンド（analyze_discussion_points）で行う。
    */
   const runAnalysis = async () => {
     if (!config || messages.length === 0 || isSavingSession) {
@@ -537,44 +519,23 @@ useEffect(() => {
     }
     try {
       setAnalyzing(true);
-      const history = messages.map(m => `${m.speaker}: ${m.message}`).join('\n');
       const parts = [ ...(config.participate ? [USER_SPEAKER] : []), ...config.aiData.map(a => a.name) ];
-      const result = await analyzeDiscussionPoints(config.discussionTopic, history, parts);
-
-      try {
-        let cleaned = result.trim();
-        if (cleaned.startsWith('```json')) cleaned = cleaned.replace(/^```json\s*/, '').replace(/\s*```$/, '');
-        else if (cleaned.startsWith('```')) cleaned = cleaned.replace(/^```\s*/, '').replace(/\s*```$/, '');
-
-        let parsed: any;
-        try {
-          parsed = JSON.parse(cleaned);
-        } catch (e1) {
-          // 壊れたJSONを修復して再パース
-          const repaired = jsonrepair(cleaned);
-          parsed = JSON.parse(repaired);
-        }
-
-        // 必要なフィールドの検証と整形
-        const valid: DiscussionAnalysis = {
-          mainPoints: Array.isArray(parsed.mainPoints) ? parsed.mainPoints.filter((p: any) => p && typeof p.point === 'string' && typeof p.description === 'string') : [],
-          participantStances: Array.isArray(parsed.participantStances) ? parsed.participantStances.filter((s: any) => s && typeof s.participant === 'string') : [],
-          conflicts: Array.isArray(parsed.conflicts) ? parsed.conflicts.filter((c: any) => c && typeof c.issue === 'string') : [],
-          commonGround: Array.isArray(parsed.commonGround) ? parsed.commonGround.filter((x: any) => typeof x === 'string') : [],
-          unexploredAreas: Array.isArray(parsed.unexploredAreas) ? parsed.unexploredAreas.filter((x: any) => typeof x === 'string') : [],
-        };
-
-        setAnalysis(valid);
-        // この時点のメッセージ数を記録（次回開閉時の不要実行を抑止）
-        setLastAnalyzedCount(messages.length);
-        showAnalysisSuccess();
-        if (sessionId && sessionId > 0) {
-          try { await saveSessionAnalysis(sessionId, 'analysis', JSON.stringify(valid)); } catch (e) { console.warn('[save] 分析保存失敗:', e); }
-        }
-      } catch (pe) {
-        console.error('[analysis] JSON解析失敗:', pe);
-        console.log('raw:', result);
-        showAnalysisError('議論分析', `JSON解析に失敗しました: ${pe}`);
+      const snapshot = sessionId && sessionId > 0 ? { sessionId, messageCount: messages.length } : undefined;
+      // 前回の分析がある場合は、それ以降の発言だけを送って更新する
+      const covered = analysisCoveredRef.current;
+      const incremental = analysis !== null && covered > 0 && covered < messages.length;
+      const lines = (from: number) => messages.slice(from).map(m => `${m.speaker}: ${m.message}`).join('\n');
+      const valid = incremental
+        ? await incrementalAnalyzeDiscussion(config.discussionTopic, analysis, lines(covered), parts, undefined, snapshot)
+        : await analyzeDiscussionPoints(config.discussionTopic, lines(0), parts, undefined, snapshot);
+
+      setAnalysis(valid);
+      analysisCoveredRef.current = messages.length;
+      // この時点のメッセージ数を記録（次回開閉時の不要実行を抑止）
+      setLastAnalyzedCount(messages.length);
+      showAnalysisSuccess();
+      if (sessionId && sessionId > 0) {
+        try { await saveSessionAnalysis(sessionId, 'analysis', JSON.stringify(valid)); } catch (e) { console.warn('[save] 分析保存失敗:', e); }
       }
     } catch (e) {
       console.error('[analysis] 実行失敗:', e);
@@ -590,6 +551,8 @@ useEffect(() => {
    * 既存セッションは更新、新規は作成してIDを確定。
    */
 const saveQueueRef = useRef<Promise<void>>(Promise.resolve());
+// 最後に保存した時点の発言（末尾に足されただけなら追記で済ませる）
+const savedRef = useRef<{ sessionId: number; messages: TalkMessage[] } | null>(null);
 
 const enqueueSave = (snapshot: TalkMessage[]) => {
   if (!config) return;
@@ -599,8 +562,19 @@ const enqueueSave = (snapshot: TalkMessage[]) => {
     try {
       const participantsData = { userParticipates: config.participate, aiData: config.aiData };
       const currentId = sessionIdRef.current;
-      if (currentId && currentId > 0) {
+      const saved = savedRef.current;
+      const appendFrom =
+        saved && saved.sessionId === currentId && snapshot.length > saved.messages.length &&
+        saved.messages.every((m, i) => snapshot[i] === m)
+          ? saved.messages.length
+          : null;
+      if (currentId && currentId > 0 && appendFrom !== null) {
+        // 保存件数に応じた自動要約は、追記した発言ごとにバックエンド側で判定
+        await appendSessionMessages(currentId, snapshot.slice(appendFrom));
+      } else if (currentId && currentId > 0) {
         await updateSession(currentId, JSON.stringify(snapshot));
+        // 保存件数に応じた自動要約はバックエンド側で判定
+        invoke('notify_messages_persisted', { sessionId: currentId }).catch(e => console.warn('[summary] 通知失敗:', e));
       } else {
         const newId = await saveSession(
           config.discussionTopic,
@@ -611,7 +585,10 @@ const enqueueSave = (snapshot: TalkMessage[]) => {
         setSessionId(newId);
         sessionIdRef.current = newId;
         setIsResumed(true);
+        // 新規セッションも保存直後からバックエンドの自動保存（チェックポイント）の対象にする
+        invoke('notify_messages_persisted', { sessionId: newId }).catch(e => console.warn('[autosave] 通知失敗:', e));
       }
+      savedRef.current = { sessionId: sessionIdRef.current ?? 0, messages: snapshot };
     } catch (e) {
       console.error('[save] 失敗:', e);
     } finally {
@@ -657,10 +634,10 @@ const autoSaveSession = async (messagesToSave?: TalkMessage[]) => {
       const recentLines = base.slice(-CONFIG.KEEP_RECENT_TURNS).map((m: TalkMessage) => `${m.speaker}: ${m.message}`).join('\n');//末尾からKEEP_RECENT_TURNS件のメッセージを取得
       const history = historySummary ? `${historySummary}\n${recentLines}` : recentLines;
 
-      const response = await generateAIResponse(bot.name, bot.role, bot.description, history, config.discussionTopic);
-      const aiText = typeof response === 'string' ? response : String(response ?? '');
+      const response = await generateAIResponse(bot.name, bot.role, bot.description, history, config.discussionTopic, sessionIdRef.current);
+      const aiText = typeof response?.text === 'string' ? response.text : String(response?.text ?? '');
 
-      const aiMsg: TalkMessage = { speaker: bot.name, message: aiText, isUser: false, timestamp: new Date() };
+      const aiMsg: TalkMessage = { speaker: bot.name, message: aiText, isUser: false, timestamp: new Date(), seed: response?.seed, generation: response ? toGenerationMeta(response) : undefined };
 
       // 関数型更新で追記（上書き防止）
       setMessages(prev => [...prev, aiMsg]);
@@ -1,4 +1,5 @@
 // 共通型定義（Playページ周辺）
+import type { GenerationMeta } from '../../hooks/useAIModel';
 
 /**
  * AI参加者のプロフィール
@@ -24,6 +25,14 @@ export interface TalkMessage {
   isUser: boolean;
   /** 発話時刻 */
   timestamp: Date;
+  /** 生成時のシード（AI発言のみ。再現・不具合報告用） */
+  seed?: number;
+  /** トークン数・生成時間（AI発言のみ） */
+  generation?: GenerationMeta;
+  /** edit_message で本文を書き換えた日時 */
+  editedAt?: string;
+  /** regenerate_message で生成し直した日時 */
+  regeneratedAt?: string;
 }
 
 /**
@@ -48,6 +57,10 @@ export interface DiscussionAnalysis {
   participantStances: {
     /** 参加者名（"ユーザー" は人間） */
     participant: string;
+    /** 立場の短いラベル（例: "賛成", "反対"。古い分析にはない） */
+    label?: string;
+    /** 立場の確信度（0.0〜1.0） */
+    confidence?: number;
     /** その参加者の立場・主張の要約 */
     stance: string;
     /** 立場を支える主要な論拠 */
@@ -3,13 +3,15 @@
  * セッション一覧ページ（Sessions）。保存済みの議論セッションの閲覧・再開・参加者編集・削除を行えます。
  */
 
-import { Box, VStack, HStack, Text, Button, CardRoot, CardBody, Spinner, Heading } from '@chakra-ui/react';
-import { useState, useEffect } from 'react';
+import { Box, VStack, HStack, Text, Button, CardRoot, CardBody, Spinner, Heading, Input } from '@chakra-ui/react';
+import { useState, useEffect, Fragment, ReactNode } from 'react';
+import { invoke } from '@tauri-apps/api/core';
 import { useNavigate } from 'react-router-dom';
 import { 
   showSessionDeleteSuccess,
   showSessionDeleteError,
   showGenericError,
+  showSessionExportSuccess,
 } from '../components/ui/notifications';
 import { ConfirmDialog } from '../components/ui/confirm-dialog';
 import { getAllSessions, deleteSession as deleteDatabaseSession, SavedSession, updateSessionParticipants, updateSessionLastOpened } from '../utils/database';
@@ -31,6 +33,34 @@ interface BotProfile {
   description: string;
 }
 
+/** 発言の抜粋（search_sessions の戻り値） */
+interface MessageSnippet {
+  position: number;
+  speaker: string;
+  /** 一致箇所を <mark>〜</mark> で囲んだ抜粋 */
+  snippet: string;
+}
+
+/** 検索に一致したセッション（search_sessions の戻り値） */
+interface SessionSearchHit {
+  sessionId: number;
+  topic: string;
+  topicHighlight?: string | null;
+  messageHits: number;
+  snippets: MessageSnippet[];
+  updatedAt: string;
+}
+
+/**
+ * <mark>〜</mark> の印だけを強調表示として描画する（それ以外はテキストとして扱う）。
+ */
+const renderHighlight = (text: string): ReactNode =>
+  text.split(/(<mark>.*?<\/mark>)/g).map((part, i) =>
+    part.startsWith('<mark>') && part.endsWith('</mark>')
+      ? <mark key={i}>{part.slice(6, -7)}</mark>
+      : <Fragment key={i}>{part}</Fragment>
+  );
+
 /**
  * 保存済みセッションの一覧と操作を提供するページコンポーネント。
  *
@@ -50,10 +80,39 @@ export default function SessionsPage() {
   const [editingBots, setEditingBots] = useState<BotProfile[]>([]);
   const [editUserParticipates, setEditUserParticipates] = useState(false);
 
+  // 全文検索（空なら全件表示）
+  const [searchQuery, setSearchQuery] = useState('');
+  const [searchHits, setSearchHits] = useState<SessionSearchHit[] | null>(null);
+
   useEffect(() => {
     loadSessions();
   }, []);
 
+  useEffect(() => {
+    const query = searchQuery.trim();
+    if (!query) {
+      setSearchHits(null);
+      return;
+    }
+    const timer = setTimeout(async () => {
+      try {
+        setSearchHits(await invoke<SessionSearchHit[]>('search_sessions', { query, limit: 50 }));
+      } catch (error) {
+        console.error('セッション検索エラー:', error);
+        setSearchHits([]);
+      }
+    }, 250);
+    return () => clearTimeout(timer);
+  }, [searchQuery, sessions]);
+
+  // 検索中は一致順に並べ替えた一覧を表示
+  const hitById = new Map((searchHits ?? []).map((hit) => [hit.sessionId, hit]));
+  const visibleSessions = searchHits === null
+    ? sessions
+    : searchHits
+        .map((hit) => sessions.find((session) => session.id === hit.sessionId))
+        .filter((session): session is SavedSession => session !== undefined);
+
   /** セッション一覧をロード */
   const loadSessions = async () => {
     try {
@@ -69,6 +128,28 @@ export default function SessionsPage() {
     }
   };
 
+  /** セッションを Markdown / HTML に書き出す（保存先はダイアログで選択） */
+  const exportSession = async (session: SavedSession, format: 'markdown' | 'html' | 'json') => {
+    try {
+      const path = await invoke<string | null>('export_session', { sessionId: session.id, format });
+      if (path) showSessionExportSuccess(path);
+    } catch (error) {
+      console.error('エクスポートエラー:', error);
+      showGenericError('エクスポートに失敗しました', String(error));
+    }
+  };
+
+  /** JSON エクスポートを新しいセッションとして取り込む（ファイルはダイアログで選択） */
+  const importSession = async () => {
+    try {
+      const sessionId = await invoke<number | null>('import_session', {});
+      if (sessionId !== null) await loadSessions();
+    } catch (error) {
+      console.error('インポートエラー:', error);
+      showGenericError('インポートに失敗しました', String(error));
+    }
+  };
+
   /** 指定セッションを復元してPlayへ遷移 */
   const continueSession = async (session: SavedSession) => {
     try {
@@ -181,6 +262,17 @@ export default function SessionsPage() {
         <Text color="fg.muted">保存された議論から続きを選択してください</Text>
       </VStack>
 
+      <HStack width="100%" maxW="2xl" mx="auto">
+        <Input
+          placeholder="テーマや発言を検索"
+          value={searchQuery}
+          onChange={(e) => setSearchQuery(e.target.value)}
+        />
+        <Button variant="outline" onClick={importSession}>
+          インポート
+        </Button>
+      </HStack>
+
       {/* セッション一覧 */}
       <Box width="100%" maxW="2xl" mx="auto" flex={1}>
         {loading ? (
@@ -200,9 +292,13 @@ export default function SessionsPage() {
               {!isModelLoaded ? 'Ollama未接続' : '新しい議論を始める'}
             </Button>
           </VStack>
+        ) : visibleSessions.length === 0 ? (
+          <VStack justify="center" align="center" height="200px">
+            <Text color="fg.muted">一致するセッションがありません</Text>
+          </VStack>
         ) : (
           <VStack gap={4} width="100%">
-            {sessions.map((session) => (
+            {visibleSessions.map((session) => (
               <CardRoot key={session.id} width="100%">
                 <CardBody>
                   <VStack align="stretch" gap={3}>
@@ -212,7 +308,9 @@ export default function SessionsPage() {
                           fontWeight="bold"
                           color={{ base: "gray.800", _dark: "gray.100" }}
                         >
-                          {session.topic}
+                          {hitById.get(session.id)?.topicHighlight
+                            ? renderHighlight(hitById.get(session.id)!.topicHighlight!)
+                            : session.topic}
                         </Text>
                         <HStack gap={4} color="gray.500" fontSize="sm">
                           <Text>{getMessageCount(session.messages)}メッセージ</Text>
@@ -222,9 +320,14 @@ export default function SessionsPage() {
                         <Text color="gray.600" fontSize="sm">
                           参加者: {getParticipantInfo(session.participants)}
                         </Text>
+                        {hitById.get(session.id)?.snippets.map((snippet) => (
+                          <Text key={snippet.position} color="gray.600" fontSize="sm">
+                            {snippet.speaker}: {renderHighlight(snippet.snippet)}
+                          </Text>
+                        ))}
                       </VStack>
                       <HStack gap={2}>
-                        {/* 左から: 続きから / 編集 / 削除 の順 */}
+                        {/* 左から: 続きから / 編集 / 書き出し / 削除 の順 */}
                         <Button 
                           size="sm" 
                           colorPalette="green"
@@ -241,6 +344,27 @@ export default function SessionsPage() {
                         >
                           編集
                         </Button>
+                        <Button 
+                          size="sm" 
+                          variant="outline"
+                          onClick={() => exportSession(session, 'markdown')}
+                        >
+                          MD
+                        </Button>
+                        <Button 
+                          size="sm" 
+                          variant="outline"
+                          onClick={() => exportSession(session, 'html')}
+                        >
+                          HTML
+                        </Button>
+                        <Button 
+                          size="sm" 
+                          variant="outline"
+                          onClick={() => exportSession(session, 'json')}
+                        >
+                          JSON
+                        </Button>
                         <ConfirmDialog
                           trigger={
                             <Button 
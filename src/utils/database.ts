@@ -9,6 +9,7 @@
  * - スキーマ初期化とインデックス管理
  */
 import Database from '@tauri-apps/plugin-sql'
+import { invoke } from '@tauri-apps/api/core';
 
 /**
  * 保存されたセッションのデータ構造。
@@ -50,6 +51,8 @@ export interface SessionAnalysisRow {
 
 /** データベース接続インスタンス */
 let db: Database | null = null;
+/** 接続URL（選択中のワークスペース。初回の ensureSchema でバックエンドから取得） */
+let dbUrl: string | null = null;
 /** メタテーブル初期化フラグ */
 let metaInitialized = false;
 /** 全体スキーマ初期化フラグ */
@@ -60,10 +63,22 @@ let schemaInitialized = false;
  * @returns Database インスタンス
  */
 function getDb(): Database {
-  if (!db) db = Database.get('sqlite:dewai.db');
+  if (!db) db = Database.get(dbUrl ?? 'sqlite:dewai.db');
   return db;
 }
 
+/**
+ * 接続先のデータベースを切り替えます（ワークスペースの切り替え後に呼び出してください）。
+ * @param url switch_workspace / list_workspaces が返す dbUrl
+ */
+export function setDatabaseUrl(url: string): void {
+  if (url === dbUrl) return;
+  dbUrl = url;
+  db = null;
+  metaInitialized = false;
+  schemaInitialized = false;
+}
+
 /**
  * データベーススキーマを初期化します。
  * - PRAGMA設定（外部キー制約、WALモード）
@@ -74,6 +89,10 @@ function getDb(): Database {
  */
 async function ensureSchema() {
   if (schemaInitialized) return;
+  if (!dbUrl) {
+    const workspaces = await invoke<{ dbUrl: string }>('list_workspaces');
+    setDatabaseUrl(workspaces.dbUrl);
+  }
   const conn = getDb();
 
   // PRAGMA 設定
@@ -146,6 +165,25 @@ export async function updateSessionLastOpened(sessionId: number): Promise<void>
   );
 }
 
+/**
+ * 保存する messages JSON の本文を、設定に従って伏せ字にしてから暗号化します
+ * （どちらも無効ならそのまま返ります）。
+ * @internal
+ */
+async function sealMessages(messages: string): Promise<string> {
+  const redacted = await invoke<string>('redact_messages_json', { messages });
+  return invoke<string>('seal_messages_json', { messages: redacted });
+}
+
+/**
+ * 読み込んだ messages JSON の暗号化された本文を復号します。
+ * ロック中はエラーになります。
+ * @internal
+ */
+function openMessages(messages: string): Promise<string> {
+  return invoke<string>('open_messages_json', { messages });
+}
+
 /**
  * 新しい議論セッションを保存します。
  * 
@@ -166,7 +204,7 @@ export async function saveSession(
   const now = new Date().toISOString().slice(0, 19).replace('T', ' ');
   const result = await conn.execute(
     'INSERT INTO sessions (topic, participants, messages, model, created_at, updated_at) VALUES ($1, $2, $3, $4, $5, $6)',
-    [topic, participants, messages, model, now, now]
+    [topic, participants, await sealMessages(messages), model, now, now]
   );
   const newId = result.lastInsertId ?? 0;
 
@@ -189,10 +227,26 @@ export async function updateSession(sessionId: number, messages: string): Promis
   const now = new Date().toISOString().slice(0, 19).replace('T', ' ');
   await conn.execute(
     'UPDATE sessions SET messages = $1, updated_at = $2 WHERE id = $3',
-    [messages, now, sessionId]
+    [await sealMessages(messages), now, sessionId]
   );
 }
 
+/**
+ * 既存セッションの末尾に発言を追記します（messages JSON 全体は書き直しません）。
+ * 本文はバックエンドで設定に従って伏せ字・暗号化され、発言ごとに保存後の処理（要約の判定など）も行われます。
+ *
+ * @param sessionId 追記先のセッションID
+ * @param messages 追記する発言（保存済みの発言より後のもの）
+ * @returns 追記後の発言数
+ */
+export async function appendSessionMessages(sessionId: number, messages: unknown[]): Promise<number> {
+  let count = 0;
+  for (const message of messages) {
+    count = await invoke<number>('append_message', { sessionId, message });
+  }
+  return count;
+}
+
 /**
  * 既存セッションの参加者情報を更新します。
  * 
@@ -209,23 +263,83 @@ export async function updateSessionParticipants(sessionId: number, participants:
   );
 }
 
+/** セッション一覧の並び順の基準 */
+export type SessionSortKey = 'lastOpened' | 'updated' | 'created' | 'topic';
+
+/**
+ * セッション一覧の絞り込み・並び替えの条件。
+ * タグは add_tag / auto_tag_session で付けたもの（tags テーブル）を参照します。
+ */
+export interface SessionListOptions {
+  /** すべて付いているセッションだけに絞り込むタグ */
+  tags?: string[];
+  /** 作成日時の下限（'YYYY-MM-DD' または 'YYYY-MM-DD HH:MM:SS'） */
+  createdFrom?: string;
+  /** 作成日時の上限（日付だけならその日の終わりまで） */
+  createdTo?: string;
+  /** 並び順の基準（既定は lastOpened） */
+  sortBy?: SessionSortKey;
+  /** 昇順・降順（既定は topic のみ asc、それ以外は desc） */
+  order?: 'asc' | 'desc';
+}
+
+/** 並び順の基準ごとの ORDER BY 式 */
+const SESSION_SORT_COLUMNS: Record<SessionSortKey, string> = {
+  lastOpened: 'datetime(COALESCE(m.last_opened_at, s.updated_at))',
+  updated: 'datetime(s.updated_at)',
+  created: 'datetime(s.created_at)',
+  topic: 's.topic',
+};
+
 /**
  * 全セッションを取得します。
- * 最近開いた順 → 更新日時降順でソートされます。
+ * 既定では最近開いた順 → 更新日時降順でソートされます。
+ * options でタグ・作成日時による絞り込みと並び順を指定できます。
  * 
+ * @param options 絞り込み・並び替えの条件
  * @returns セッション配列（空の場合は空配列）
  */
-export async function getAllSessions(): Promise<SavedSession[]> {
+export async function getAllSessions(options: SessionListOptions = {}): Promise<SavedSession[]> {
   try {
     await ensureSchema();
     const conn = getDb();
+    const conditions: string[] = [];
+    const params: unknown[] = [];
+    const tags = [...new Set((options.tags ?? []).map((t) => t.trim()).filter((t) => t.length > 0))];
+    if (tags.length > 0) {
+      const placeholders = tags.map((_, i) => `$${params.length + i + 1}`).join(', ');
+      params.push(...tags);
+      conditions.push(
+        `s.id IN (SELECT session_id FROM tags WHERE tag IN (${placeholders}) GROUP BY session_id HAVING COUNT(DISTINCT tag) = ${tags.length})`
+      );
+    }
+    if (options.createdFrom) {
+      params.push(options.createdFrom);
+      conditions.push(`datetime(s.created_at) >= datetime($${params.length})`);
+    }
+    if (options.createdTo) {
+      // 日付だけの指定はその日の終わりまで含める
+      params.push(options.createdTo.length <= 10 ? `${options.createdTo} 23:59:59` : options.createdTo);
+      conditions.push(`datetime(s.created_at) <= datetime($${params.length})`);
+    }
+    const sortBy = options.sortBy ?? 'lastOpened';
+    const order = (options.order ?? (sortBy === 'topic' ? 'asc' : 'desc')) === 'asc' ? 'ASC' : 'DESC';
+    const where = conditions.length > 0 ? `WHERE ${conditions.join(' AND ')}` : '';
     const rows = await conn.select<SavedSession[]>(
-      'SELECT s.id, s.topic, s.participants, s.messages, s.model, s.created_at, s.updated_at \
-       FROM sessions s \
-       LEFT JOIN session_meta m ON m.session_id = s.id \
-       ORDER BY datetime(COALESCE(m.last_opened_at, s.updated_at)) DESC'
+      `SELECT s.id, s.topic, s.participants, s.messages, s.model, s.created_at, s.updated_at
+       FROM sessions s
+       LEFT JOIN session_meta m ON m.session_id = s.id
+       ${where}
+       ORDER BY ${SESSION_SORT_COLUMNS[sortBy]} ${order}, s.id DESC`,
+      params
+    );
+    // 一覧ではロック中でもテーマなどを表示できるよう、復号できない本文はそのまま返す
+    return Promise.all(
+      (rows ?? []).map(async (row) => ({
+        ...row,
+        messages: await openMessages(row.messages).catch(() => row.messages),
+      }))
     );
-    return rows ?? [];
   } catch (e) {
     // DBが未初期化/空などのケースでは空配列でスルー
     console.warn('[db] getAllSessions: 空/未初期化として扱います:', e);
@@ -246,7 +360,9 @@ export async function getSessionById(sessionId: number): Promise<SavedSession |
     'SELECT id, topic, participants, messages, model, created_at, updated_at FROM sessions WHERE id = $1',
     [sessionId]
   );
-  return rows?.[0] ?? null;
+  const row = rows?.[0];
+  if (!row) return null;
+  return { ...row, messages: await openMessages(row.messages) };
 }
 
 /**
@@ -287,13 +403,8 @@ export async function saveSessionAnalysis(
   payload: string
 ): Promise<number> {
   await ensureSchema();
-  const conn = getDb();
-  const now = new Date().toISOString().slice(0, 19).replace('T', ' ');
-  const result = await conn.execute(
-    'INSERT INTO session_analysis (session_id, kind, payload, created_at) VALUES ($1, $2, $3, $4)',
-    [sessionId, kind, payload, now]
-  );
-  return result.lastInsertId ?? 0;
+  // 暗号化が有効なら payload を暗号化し、要約は session_summaries にも記録するためバックエンドで保存する
+  return invoke<number>('save_session_analysis', { sessionId, kind, payload });
 }
 
 /**
@@ -311,18 +422,6 @@ export async function getSessionAnalysis(
   limit: number = 10
 ): Promise<SessionAnalysisRow[]> {
   await ensureSchema();
-  const conn = getDb();
-  if (kind) {
-    const rows = await conn.select<SessionAnalysisRow[]>(
-      'SELECT id, session_id, kind, payload, created_at FROM session_analysis WHERE session_id = $1 AND kind = $2 ORDER BY datetime(created_at) DESC LIMIT $3',
-      [sessionId, kind, limit]
-    );
-    return rows ?? [];
-  } else {
-    const rows = await conn.select<SessionAnalysisRow[]>(
-      'SELECT id, session_id, kind, payload, created_at FROM session_analysis WHERE session_id = $1 ORDER BY datetime(created_at) DESC LIMIT $2',
-      [sessionId, limit]
-    );
-    return rows ?? [];
-  }
+  // 暗号化された payload はバックエンドで復号する（ロック中はエラー）
+  return invoke<SessionAnalysisRow[]>('get_session_analysis', { sessionId, kind: kind ?? null, limit });
 }